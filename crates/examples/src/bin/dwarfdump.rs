@@ -2,7 +2,7 @@
 #![allow(unknown_lints)]
 
 use fallible_iterator::FallibleIterator;
-use gimli::{Section, UnitHeader, UnitOffset, UnitSectionOffset, UnitType, UnwindSection};
+use gimli::{Section, UnitHeader, UnitSectionOffset};
 use object::{Object, ObjectSection};
 use regex::bytes::Regex;
 use std::borrow::Cow;
@@ -70,6 +70,24 @@ impl From<object::read::Error> for Error {
     }
 }
 
+impl From<gimli::DumpError> for Error {
+    fn from(err: gimli::DumpError) -> Self {
+        match err {
+            gimli::DumpError::Read(err) => Error::GimliError(err),
+            gimli::DumpError::Fmt => Error::IoError,
+        }
+    }
+}
+
+/// Adapts an `io::Write` for use with the `gimli::dump_*` functions.
+struct FmtWriter<'a, W: Write>(&'a mut W);
+
+impl<'a, W: Write> fmt::Write for FmtWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
 
 fn parallel_output<W, II, F>(w: &mut W, max_workers: usize, iter: II, f: F) -> Result<()>
@@ -571,10 +589,6 @@ fn dump_eh_frame<R: Reader, W: Write>(
         object::Architecture::X86_64 => gimli::X86_64::register_name,
         _ => register_name_none,
     };
-    let register_name = &|register| match arch_register_name(register) {
-        Some(name) => Cow::Borrowed(name),
-        None => Cow::Owned(format!("{}", register.0)),
-    };
 
     let mut bases = gimli::BaseAddresses::default();
     if let Some(section) = file.section_by_name(".eh_frame_hdr") {
@@ -598,304 +612,10 @@ fn dump_eh_frame<R: Reader, W: Write>(
         "Exception handling frame information for section .eh_frame"
     )?;
 
-    let mut cies = HashMap::new();
-
-    let mut entries = eh_frame.entries(&bases);
-    loop {
-        match entries.next()? {
-            None => return Ok(()),
-            Some(gimli::CieOrFde::Cie(cie)) => {
-                writeln!(w)?;
-                writeln!(w, "{:#010x}: CIE", cie.offset())?;
-                writeln!(w, "        length: {:#010x}", cie.entry_len())?;
-                // TODO: CIE_id
-                writeln!(w, "       version: {:#04x}", cie.version())?;
-                // TODO: augmentation
-                writeln!(w, "    code_align: {}", cie.code_alignment_factor())?;
-                writeln!(w, "    data_align: {}", cie.data_alignment_factor())?;
-                writeln!(
-                    w,
-                    "   ra_register: {}",
-                    register_name(cie.return_address_register())
-                )?;
-                if let Some(encoding) = cie.lsda_encoding() {
-                    writeln!(
-                        w,
-                        " lsda_encoding: {}/{}",
-                        encoding.application(),
-                        encoding.format()
-                    )?;
-                }
-                if let Some((encoding, personality)) = cie.personality_with_encoding() {
-                    write!(
-                        w,
-                        "   personality: {}/{} ",
-                        encoding.application(),
-                        encoding.format()
-                    )?;
-                    dump_pointer(w, personality)?;
-                    writeln!(w)?;
-                }
-                if let Some(encoding) = cie.fde_address_encoding() {
-                    writeln!(
-                        w,
-                        "  fde_encoding: {}/{}",
-                        encoding.application(),
-                        encoding.format()
-                    )?;
-                }
-                let instructions = cie.instructions(&eh_frame, &bases);
-                dump_cfi_instructions(w, instructions, true, register_name)?;
-                writeln!(w)?;
-            }
-            Some(gimli::CieOrFde::Fde(partial)) => {
-                writeln!(w)?;
-                writeln!(w, "{:#010x}: FDE", partial.offset())?;
-                writeln!(w, "        length: {:#010x}", partial.entry_len())?;
-                writeln!(w, "   CIE_pointer: {:#010x}", partial.cie_offset().0)?;
-
-                let fde = match partial.parse(|_, bases, o| {
-                    cies.entry(o)
-                        .or_insert_with(|| eh_frame.cie_from_offset(bases, o))
-                        .clone()
-                }) {
-                    Ok(fde) => fde,
-                    Err(e) => {
-                        writeln!(w, "Failed to parse FDE: {}", e)?;
-                        continue;
-                    }
-                };
-
-                // TODO: symbolicate the start address like the canonical dwarfdump does.
-                writeln!(w, "    start_addr: {:#018x}", fde.initial_address())?;
-                writeln!(
-                    w,
-                    "    range_size: {:#018x} (end_addr = {:#018x})",
-                    fde.len(),
-                    fde.initial_address() + fde.len()
-                )?;
-                if let Some(lsda) = fde.lsda() {
-                    write!(w, "          lsda: ")?;
-                    dump_pointer(w, lsda)?;
-                    writeln!(w)?;
-                }
-                let instructions = fde.instructions(&eh_frame, &bases);
-                dump_cfi_instructions(w, instructions, false, register_name)?;
-                writeln!(w)?;
-            }
-        }
-    }
-}
-
-fn dump_pointer<W: Write>(w: &mut W, p: gimli::Pointer) -> Result<()> {
-    match p {
-        gimli::Pointer::Direct(p) => {
-            write!(w, "{:#018x}", p)?;
-        }
-        gimli::Pointer::Indirect(p) => {
-            write!(w, "({:#018x})", p)?;
-        }
-    }
+    gimli::dump_cfi(&mut FmtWriter(w), &eh_frame, &bases, arch_register_name)?;
     Ok(())
 }
 
-#[allow(clippy::unneeded_field_pattern)]
-fn dump_cfi_instructions<R: Reader, W: Write>(
-    w: &mut W,
-    mut insns: gimli::CallFrameInstructionIter<R>,
-    is_initial: bool,
-    register_name: &dyn Fn(gimli::Register) -> Cow<'static, str>,
-) -> Result<()> {
-    use gimli::CallFrameInstruction::*;
-
-    // TODO: we need to actually evaluate these instructions as we iterate them
-    // so we can print the initialized state for CIEs, and each unwind row's
-    // registers for FDEs.
-    //
-    // TODO: We should print DWARF expressions for the CFI instructions that
-    // embed DWARF expressions within themselves.
-
-    if !is_initial {
-        writeln!(w, "  Instructions:")?;
-    }
-
-    loop {
-        match insns.next() {
-            Err(e) => {
-                writeln!(w, "Failed to decode CFI instruction: {}", e)?;
-                return Ok(());
-            }
-            Ok(None) => {
-                if is_initial {
-                    writeln!(w, "  Instructions: Init State:")?;
-                }
-                return Ok(());
-            }
-            Ok(Some(op)) => match op {
-                SetLoc { address } => {
-                    writeln!(w, "                DW_CFA_set_loc ({:#x})", address)?;
-                }
-                AdvanceLoc { delta } => {
-                    writeln!(w, "                DW_CFA_advance_loc ({})", delta)?;
-                }
-                DefCfa { register, offset } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_def_cfa ({}, {})",
-                        register_name(register),
-                        offset
-                    )?;
-                }
-                DefCfaSf {
-                    register,
-                    factored_offset,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_def_cfa_sf ({}, {})",
-                        register_name(register),
-                        factored_offset
-                    )?;
-                }
-                DefCfaRegister { register } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_def_cfa_register ({})",
-                        register_name(register)
-                    )?;
-                }
-                DefCfaOffset { offset } => {
-                    writeln!(w, "                DW_CFA_def_cfa_offset ({})", offset)?;
-                }
-                DefCfaOffsetSf { factored_offset } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_def_cfa_offset_sf ({})",
-                        factored_offset
-                    )?;
-                }
-                DefCfaExpression { expression: _ } => {
-                    writeln!(w, "                DW_CFA_def_cfa_expression (...)")?;
-                }
-                Undefined { register } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_undefined ({})",
-                        register_name(register)
-                    )?;
-                }
-                SameValue { register } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_same_value ({})",
-                        register_name(register)
-                    )?;
-                }
-                Offset {
-                    register,
-                    factored_offset,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_offset ({}, {})",
-                        register_name(register),
-                        factored_offset
-                    )?;
-                }
-                OffsetExtendedSf {
-                    register,
-                    factored_offset,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_offset_extended_sf ({}, {})",
-                        register_name(register),
-                        factored_offset
-                    )?;
-                }
-                ValOffset {
-                    register,
-                    factored_offset,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_val_offset ({}, {})",
-                        register_name(register),
-                        factored_offset
-                    )?;
-                }
-                ValOffsetSf {
-                    register,
-                    factored_offset,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_val_offset_sf ({}, {})",
-                        register_name(register),
-                        factored_offset
-                    )?;
-                }
-                Register {
-                    dest_register,
-                    src_register,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_register ({}, {})",
-                        register_name(dest_register),
-                        register_name(src_register)
-                    )?;
-                }
-                Expression {
-                    register,
-                    expression: _,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_expression ({}, ...)",
-                        register_name(register)
-                    )?;
-                }
-                ValExpression {
-                    register,
-                    expression: _,
-                } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_val_expression ({}, ...)",
-                        register_name(register)
-                    )?;
-                }
-                Restore { register } => {
-                    writeln!(
-                        w,
-                        "                DW_CFA_restore ({})",
-                        register_name(register)
-                    )?;
-                }
-                RememberState => {
-                    writeln!(w, "                DW_CFA_remember_state")?;
-                }
-                RestoreState => {
-                    writeln!(w, "                DW_CFA_restore_state")?;
-                }
-                ArgsSize { size } => {
-                    writeln!(w, "                DW_CFA_GNU_args_size ({})", size)?;
-                }
-                NegateRaState => {
-                    writeln!(w, "                DW_CFA_AARCH64_negate_ra_state")?;
-                }
-                Nop => {
-                    writeln!(w, "                DW_CFA_nop")?;
-                }
-                _ => {
-                    writeln!(w, "                {:?}", op)?;
-                }
-            },
-        }
-    }
-}
-
 fn dump_dwp<R: Reader, W: Write + Send>(
     w: &mut W,
     dwp: &gimli::DwarfPackage<R>,
@@ -1046,43 +766,7 @@ fn dump_unit<R: Reader, W: Write>(
     dwo_parent_units: Option<&HashMap<gimli::DwoId, gimli::Unit<R>>>,
     flags: &Flags,
 ) -> Result<()> {
-    write!(w, "\nUNIT<")?;
-    match header.offset() {
-        UnitSectionOffset::DebugInfoOffset(o) => {
-            write!(w, ".debug_info+0x{:08x}", o.0)?;
-        }
-        UnitSectionOffset::DebugTypesOffset(o) => {
-            write!(w, ".debug_types+0x{:08x}", o.0)?;
-        }
-    }
-    writeln!(w, ">: length = 0x{:x}, format = {:?}, version = {}, address_size = {}, abbrev_offset = 0x{:x}",
-        header.unit_length(),
-        header.format(),
-        header.version(),
-        header.address_size(),
-        header.debug_abbrev_offset().0,
-    )?;
-
-    match header.type_() {
-        UnitType::Compilation | UnitType::Partial => (),
-        UnitType::Type {
-            type_signature,
-            type_offset,
-        }
-        | UnitType::SplitType {
-            type_signature,
-            type_offset,
-        } => {
-            write!(w, "  signature        = ")?;
-            dump_type_signature(w, type_signature)?;
-            writeln!(w)?;
-            writeln!(w, "  type_offset      = 0x{:x}", type_offset.0,)?;
-        }
-        UnitType::Skeleton(dwo_id) | UnitType::SplitCompilation(dwo_id) => {
-            write!(w, "  dwo_id           = ")?;
-            writeln!(w, "0x{:016x}", dwo_id.0)?;
-        }
-    }
+    gimli::dump_unit_header(&mut FmtWriter(w), &header)?;
 
     let mut unit = match dwarf.unit(header) {
         Ok(unit) => unit,
@@ -1180,10 +864,10 @@ fn dump_entries<R: Reader, W: Write>(
             if flags.raw {
                 writeln!(w, "{:?}", attr.raw_value())?;
             } else {
-                match dump_attr_value(w, &attr, unit) {
-                    Ok(_) => (),
+                match unit.dump_attr_value(&mut FmtWriter(w), &attr) {
+                    Ok(_) => writeln!(w)?,
                     Err(err) => {
-                        writeln_error(w, unit.dwarf, err, "Failed to dump attribute value")?
+                        writeln_error(w, unit.dwarf, err.into(), "Failed to dump attribute value")?
                     }
                 };
             }
@@ -1192,689 +876,6 @@ fn dump_entries<R: Reader, W: Write>(
     Ok(())
 }
 
-fn dump_attr_value<R: Reader, W: Write>(
-    w: &mut W,
-    attr: &gimli::Attribute<R>,
-    unit: gimli::UnitRef<R>,
-) -> Result<()> {
-    let value = attr.value();
-    match value {
-        gimli::AttributeValue::Addr(address) => {
-            writeln!(w, "0x{:08x}", address)?;
-        }
-        gimli::AttributeValue::Block(data) => {
-            for byte in data.to_slice()?.iter() {
-                write!(w, "{:02x}", byte)?;
-            }
-            writeln!(w)?;
-        }
-        gimli::AttributeValue::Data1(_)
-        | gimli::AttributeValue::Data2(_)
-        | gimli::AttributeValue::Data4(_)
-        | gimli::AttributeValue::Data8(_) => {
-            if let (Some(udata), Some(sdata)) = (attr.udata_value(), attr.sdata_value()) {
-                if sdata >= 0 {
-                    writeln!(w, "{}", udata)?;
-                } else {
-                    writeln!(w, "{} ({})", udata, sdata)?;
-                }
-            } else {
-                writeln!(w, "{:?}", value)?;
-            }
-        }
-        gimli::AttributeValue::Sdata(data) => {
-            match attr.name() {
-                gimli::DW_AT_data_member_location => {
-                    writeln!(w, "{}", data)?;
-                }
-                _ => {
-                    if data >= 0 {
-                        writeln!(w, "0x{:08x}", data)?;
-                    } else {
-                        writeln!(w, "0x{:08x} ({})", data, data)?;
-                    }
-                }
-            };
-        }
-        gimli::AttributeValue::Udata(data) => {
-            match attr.name() {
-                gimli::DW_AT_high_pc => {
-                    writeln!(w, "<offset-from-lowpc>{}", data)?;
-                }
-                gimli::DW_AT_data_member_location => {
-                    if let Some(sdata) = attr.sdata_value() {
-                        // This is a DW_FORM_data* value.
-                        // libdwarf-dwarfdump displays this as signed too.
-                        if sdata >= 0 {
-                            writeln!(w, "{}", data)?;
-                        } else {
-                            writeln!(w, "{} ({})", data, sdata)?;
-                        }
-                    } else {
-                        writeln!(w, "{}", data)?;
-                    }
-                }
-                gimli::DW_AT_lower_bound | gimli::DW_AT_upper_bound => {
-                    writeln!(w, "{}", data)?;
-                }
-                _ => {
-                    writeln!(w, "0x{:08x}", data)?;
-                }
-            };
-        }
-        gimli::AttributeValue::Exprloc(ref data) => {
-            if let gimli::AttributeValue::Exprloc(_) = attr.raw_value() {
-                write!(w, "len 0x{:04x}: ", data.0.len())?;
-                for byte in data.0.to_slice()?.iter() {
-                    write!(w, "{:02x}", byte)?;
-                }
-                write!(w, ": ")?;
-            }
-            dump_exprloc(w, unit.encoding(), data)?;
-            writeln!(w)?;
-        }
-        gimli::AttributeValue::Flag(true) => {
-            writeln!(w, "yes")?;
-        }
-        gimli::AttributeValue::Flag(false) => {
-            writeln!(w, "no")?;
-        }
-        gimli::AttributeValue::SecOffset(offset) => {
-            writeln!(w, "0x{:08x}", offset)?;
-        }
-        gimli::AttributeValue::DebugAddrBase(base) => {
-            writeln!(w, "<.debug_addr+0x{:08x}>", base.0)?;
-        }
-        gimli::AttributeValue::DebugAddrIndex(index) => {
-            write!(w, "(indirect address, index {:#x}): ", index.0)?;
-            let address = unit.address(index)?;
-            writeln!(w, "0x{:08x}", address)?;
-        }
-        gimli::AttributeValue::UnitRef(offset) => {
-            write!(w, "0x{:08x}", offset.0)?;
-            match offset.to_unit_section_offset(&unit) {
-                UnitSectionOffset::DebugInfoOffset(goff) => {
-                    write!(w, "<.debug_info+0x{:08x}>", goff.0)?;
-                }
-                UnitSectionOffset::DebugTypesOffset(goff) => {
-                    write!(w, "<.debug_types+0x{:08x}>", goff.0)?;
-                }
-            }
-            writeln!(w)?;
-        }
-        gimli::AttributeValue::DebugInfoRef(offset) => {
-            writeln!(w, "<.debug_info+0x{:08x}>", offset.0)?;
-        }
-        gimli::AttributeValue::DebugInfoRefSup(offset) => {
-            writeln!(w, "<.debug_info(sup)+0x{:08x}>", offset.0)?;
-        }
-        gimli::AttributeValue::DebugLineRef(offset) => {
-            writeln!(w, "<.debug_line+0x{:08x}>", offset.0)?;
-        }
-        gimli::AttributeValue::LocationListsRef(offset) => {
-            dump_loc_list(w, offset, unit)?;
-        }
-        gimli::AttributeValue::DebugLocListsBase(base) => {
-            writeln!(w, "<.debug_loclists+0x{:08x}>", base.0)?;
-        }
-        gimli::AttributeValue::DebugLocListsIndex(index) => {
-            write!(w, "(indirect location list, index {:#x}): ", index.0)?;
-            let offset = unit.locations_offset(index)?;
-            dump_loc_list(w, offset, unit)?;
-        }
-        gimli::AttributeValue::DebugMacinfoRef(offset) => {
-            writeln!(w, "<.debug_macinfo+0x{:08x}>", offset.0)?;
-        }
-        gimli::AttributeValue::DebugMacroRef(offset) => {
-            writeln!(w, "<.debug_macro+0x{:08x}>", offset.0)?;
-        }
-        gimli::AttributeValue::RangeListsRef(offset) => {
-            let offset = unit.ranges_offset_from_raw(offset);
-            dump_range_list(w, offset, unit)?;
-        }
-        gimli::AttributeValue::DebugRngListsBase(base) => {
-            writeln!(w, "<.debug_rnglists+0x{:08x}>", base.0)?;
-        }
-        gimli::AttributeValue::DebugRngListsIndex(index) => {
-            write!(w, "(indirect range list, index {:#x}): ", index.0)?;
-            let offset = unit.ranges_offset(index)?;
-            dump_range_list(w, offset, unit)?;
-        }
-        gimli::AttributeValue::DebugTypesRef(signature) => {
-            dump_type_signature(w, signature)?;
-            writeln!(w, " <type signature>")?;
-        }
-        gimli::AttributeValue::DebugStrRef(offset) => {
-            if let Ok(s) = unit.string(offset) {
-                writeln!(w, "{}", s.to_string_lossy()?)?;
-            } else {
-                writeln!(w, "<.debug_str+0x{:08x}>", offset.0)?;
-            }
-        }
-        gimli::AttributeValue::DebugStrRefSup(offset) => {
-            if let Ok(s) = unit.sup_string(offset) {
-                writeln!(w, "{}", s.to_string_lossy()?)?;
-            } else {
-                writeln!(w, "<.debug_str(sup)+0x{:08x}>", offset.0)?;
-            }
-        }
-        gimli::AttributeValue::DebugStrOffsetsBase(base) => {
-            writeln!(w, "<.debug_str_offsets+0x{:08x}>", base.0)?;
-        }
-        gimli::AttributeValue::DebugStrOffsetsIndex(index) => {
-            write!(w, "(indirect string, index {:#x}): ", index.0)?;
-            let offset = unit.string_offset(index)?;
-            if let Ok(s) = unit.string(offset) {
-                writeln!(w, "{}", s.to_string_lossy()?)?;
-            } else {
-                writeln!(w, "<.debug_str+0x{:08x}>", offset.0)?;
-            }
-        }
-        gimli::AttributeValue::DebugLineStrRef(offset) => {
-            if let Ok(s) = unit.line_string(offset) {
-                writeln!(w, "{}", s.to_string_lossy()?)?;
-            } else {
-                writeln!(w, "<.debug_line_str=0x{:08x}>", offset.0)?;
-            }
-        }
-        gimli::AttributeValue::String(s) => {
-            writeln!(w, "{}", s.to_string_lossy()?)?;
-        }
-        gimli::AttributeValue::Encoding(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::DecimalSign(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Endianity(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Accessibility(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Visibility(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Virtuality(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Language(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::AddressClass(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::IdentifierCase(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::CallingConvention(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Inline(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::Ordering(value) => {
-            writeln!(w, "{}", value)?;
-        }
-        gimli::AttributeValue::FileIndex(value) => {
-            write!(w, "0x{:08x}", value)?;
-            dump_file_index(w, value, unit)?;
-            writeln!(w)?;
-        }
-        gimli::AttributeValue::DwoId(value) => {
-            writeln!(w, "0x{:016x}", value.0)?;
-        }
-    }
-
-    Ok(())
-}
-
-fn dump_type_signature<W: Write>(w: &mut W, signature: gimli::DebugTypeSignature) -> Result<()> {
-    write!(w, "0x{:016x}", signature.0)?;
-    Ok(())
-}
-
-fn dump_file_index<R: Reader, W: Write>(
-    w: &mut W,
-    file_index: u64,
-    unit: gimli::UnitRef<R>,
-) -> Result<()> {
-    if file_index == 0 && unit.header.version() <= 4 {
-        return Ok(());
-    }
-    let header = match unit.line_program {
-        Some(ref program) => program.header(),
-        None => return Ok(()),
-    };
-    let file = match header.file(file_index) {
-        Some(file) => file,
-        None => {
-            writeln!(w, "Unable to get header for file {}", file_index)?;
-            return Ok(());
-        }
-    };
-    write!(w, " ")?;
-    if let Some(directory) = file.directory(header) {
-        let directory = unit.attr_string(directory)?;
-        let directory = directory.to_string_lossy()?;
-        if file.directory_index() != 0 && !directory.starts_with('/') {
-            if let Some(ref comp_dir) = unit.comp_dir {
-                write!(w, "{}/", comp_dir.to_string_lossy()?,)?;
-            }
-        }
-        write!(w, "{}/", directory)?;
-    }
-    write!(
-        w,
-        "{}",
-        unit.attr_string(file.path_name())?.to_string_lossy()?
-    )?;
-    Ok(())
-}
-
-fn dump_exprloc<R: Reader, W: Write>(
-    w: &mut W,
-    encoding: gimli::Encoding,
-    data: &gimli::Expression<R>,
-) -> Result<()> {
-    let mut pc = data.0.clone();
-    let mut space = false;
-    while pc.len() != 0 {
-        let pc_clone = pc.clone();
-        match gimli::Operation::parse(&mut pc, encoding) {
-            Ok(op) => {
-                if space {
-                    write!(w, " ")?;
-                } else {
-                    space = true;
-                }
-                dump_op(w, encoding, pc_clone, op)?;
-            }
-            Err(gimli::Error::InvalidExpression(op)) => {
-                writeln!(w, "WARNING: unsupported operation 0x{:02x}", op.0)?;
-                return Ok(());
-            }
-            Err(gimli::Error::UnsupportedRegister(register)) => {
-                writeln!(w, "WARNING: unsupported register {}", register)?;
-                return Ok(());
-            }
-            Err(gimli::Error::UnexpectedEof(_)) => {
-                writeln!(w, "WARNING: truncated or malformed expression")?;
-                return Ok(());
-            }
-            Err(e) => {
-                writeln!(w, "WARNING: unexpected operation parse error: {}", e)?;
-                return Ok(());
-            }
-        }
-    }
-    Ok(())
-}
-
-fn dump_op<R: Reader, W: Write>(
-    w: &mut W,
-    encoding: gimli::Encoding,
-    mut pc: R,
-    op: gimli::Operation<R>,
-) -> Result<()> {
-    let dwop = gimli::DwOp(pc.read_u8()?);
-    write!(w, "{}", dwop)?;
-    match op {
-        gimli::Operation::Deref {
-            base_type, size, ..
-        } => {
-            if dwop == gimli::DW_OP_deref_size || dwop == gimli::DW_OP_xderef_size {
-                write!(w, " {}", size)?;
-            }
-            if base_type != UnitOffset(0) {
-                write!(w, " type 0x{:08x}", base_type.0)?;
-            }
-        }
-        gimli::Operation::Pick { index } => {
-            if dwop == gimli::DW_OP_pick {
-                write!(w, " {}", index)?;
-            }
-        }
-        gimli::Operation::PlusConstant { value } => {
-            write!(w, " {}", value as i64)?;
-        }
-        gimli::Operation::Bra { target } => {
-            write!(w, " {}", target)?;
-        }
-        gimli::Operation::Skip { target } => {
-            write!(w, " {}", target)?;
-        }
-        gimli::Operation::SignedConstant { value } => match dwop {
-            gimli::DW_OP_const1s
-            | gimli::DW_OP_const2s
-            | gimli::DW_OP_const4s
-            | gimli::DW_OP_const8s
-            | gimli::DW_OP_consts => {
-                write!(w, " {}", value)?;
-            }
-            _ => {}
-        },
-        gimli::Operation::UnsignedConstant { value } => match dwop {
-            gimli::DW_OP_const1u
-            | gimli::DW_OP_const2u
-            | gimli::DW_OP_const4u
-            | gimli::DW_OP_const8u
-            | gimli::DW_OP_constu => {
-                write!(w, " {}", value)?;
-            }
-            _ => {
-                // These have the value encoded in the operation, eg DW_OP_lit0.
-            }
-        },
-        gimli::Operation::Register { register } => {
-            if dwop == gimli::DW_OP_regx {
-                write!(w, " {}", register.0)?;
-            }
-        }
-        gimli::Operation::RegisterOffset {
-            register,
-            offset,
-            base_type,
-        } => {
-            if dwop >= gimli::DW_OP_breg0 && dwop <= gimli::DW_OP_breg31 {
-                write!(w, "{:+}", offset)?;
-            } else {
-                write!(w, " {}", register.0)?;
-                if offset != 0 {
-                    write!(w, "{:+}", offset)?;
-                }
-                if base_type != UnitOffset(0) {
-                    write!(w, " type 0x{:08x}", base_type.0)?;
-                }
-            }
-        }
-        gimli::Operation::FrameOffset { offset } => {
-            write!(w, " {}", offset)?;
-        }
-        gimli::Operation::Call { offset } => match offset {
-            gimli::DieReference::UnitRef(gimli::UnitOffset(offset)) => {
-                write!(w, " 0x{:08x}", offset)?;
-            }
-            gimli::DieReference::DebugInfoRef(gimli::DebugInfoOffset(offset)) => {
-                write!(w, " 0x{:08x}", offset)?;
-            }
-        },
-        gimli::Operation::Piece {
-            size_in_bits,
-            bit_offset: None,
-        } => {
-            write!(w, " {}", size_in_bits / 8)?;
-        }
-        gimli::Operation::Piece {
-            size_in_bits,
-            bit_offset: Some(bit_offset),
-        } => {
-            write!(w, " 0x{:08x} offset 0x{:08x}", size_in_bits, bit_offset)?;
-        }
-        gimli::Operation::ImplicitValue { data } => {
-            let data = data.to_slice()?;
-            write!(w, " 0x{:08x} contents 0x", data.len())?;
-            for byte in data.iter() {
-                write!(w, "{:02x}", byte)?;
-            }
-        }
-        gimli::Operation::ImplicitPointer { value, byte_offset } => {
-            write!(w, " 0x{:08x} {}", value.0, byte_offset)?;
-        }
-        gimli::Operation::EntryValue { expression } => {
-            write!(w, "(")?;
-            dump_exprloc(w, encoding, &gimli::Expression(expression))?;
-            write!(w, ")")?;
-        }
-        gimli::Operation::ParameterRef { offset } => {
-            write!(w, " 0x{:08x}", offset.0)?;
-        }
-        gimli::Operation::Address { address } => {
-            write!(w, " 0x{:08x}", address)?;
-        }
-        gimli::Operation::AddressIndex { index } => {
-            write!(w, " 0x{:08x}", index.0)?;
-        }
-        gimli::Operation::ConstantIndex { index } => {
-            write!(w, " 0x{:08x}", index.0)?;
-        }
-        gimli::Operation::TypedLiteral { base_type, value } => {
-            write!(w, " type 0x{:08x} contents 0x", base_type.0)?;
-            for byte in value.to_slice()?.iter() {
-                write!(w, "{:02x}", byte)?;
-            }
-        }
-        gimli::Operation::Convert { base_type } => {
-            write!(w, " type 0x{:08x}", base_type.0)?;
-        }
-        gimli::Operation::Reinterpret { base_type } => {
-            write!(w, " type 0x{:08x}", base_type.0)?;
-        }
-        gimli::Operation::WasmLocal { index }
-        | gimli::Operation::WasmGlobal { index }
-        | gimli::Operation::WasmStack { index } => {
-            let wasmop = pc.read_u8()?;
-            write!(w, " 0x{:x} 0x{:x}", wasmop, index)?;
-        }
-        gimli::Operation::Drop
-        | gimli::Operation::Swap
-        | gimli::Operation::Rot
-        | gimli::Operation::Abs
-        | gimli::Operation::And
-        | gimli::Operation::Div
-        | gimli::Operation::Minus
-        | gimli::Operation::Mod
-        | gimli::Operation::Mul
-        | gimli::Operation::Neg
-        | gimli::Operation::Not
-        | gimli::Operation::Or
-        | gimli::Operation::Plus
-        | gimli::Operation::Shl
-        | gimli::Operation::Shr
-        | gimli::Operation::Shra
-        | gimli::Operation::Xor
-        | gimli::Operation::Eq
-        | gimli::Operation::Ge
-        | gimli::Operation::Gt
-        | gimli::Operation::Le
-        | gimli::Operation::Lt
-        | gimli::Operation::Ne
-        | gimli::Operation::Nop
-        | gimli::Operation::PushObjectAddress
-        | gimli::Operation::TLS
-        | gimli::Operation::CallFrameCFA
-        | gimli::Operation::StackValue => {}
-    };
-    Ok(())
-}
-
-fn dump_range<W: Write>(w: &mut W, range: Option<gimli::Range>) -> Result<()> {
-    if let Some(range) = range {
-        write!(w, " [0x{:08x}, 0x{:08x}]", range.begin, range.end)?;
-    } else {
-        write!(w, " [ignored]")?;
-    }
-    Ok(())
-}
-
-fn dump_loc_list<R: Reader, W: Write>(
-    w: &mut W,
-    offset: gimli::LocationListsOffset<R::Offset>,
-    unit: gimli::UnitRef<R>,
-) -> Result<()> {
-    let mut locations = unit.locations(offset)?;
-    writeln!(
-        w,
-        "<loclist at {}+0x{:08x}>",
-        if unit.encoding().version < 5 {
-            ".debug_loc"
-        } else {
-            ".debug_loclists"
-        },
-        offset.0,
-    )?;
-    let mut i = 0;
-    while let Some(raw) = locations.next_raw()? {
-        write!(w, "\t\t\t[{:2}]", i)?;
-        i += 1;
-        let range = locations
-            .convert_raw(raw.clone())?
-            .map(|location| location.range);
-        match raw {
-            gimli::RawLocListEntry::BaseAddress { addr } => {
-                writeln!(w, "<base-address 0x{:08x}>", addr)?;
-            }
-            gimli::RawLocListEntry::BaseAddressx { addr } => {
-                let addr_val = unit.address(addr)?;
-                writeln!(w, "<base-addressx [{}]0x{:08x}>", addr.0, addr_val)?;
-            }
-            gimli::RawLocListEntry::StartxEndx {
-                begin,
-                end,
-                ref data,
-            } => {
-                let begin_val = unit.address(begin)?;
-                let end_val = unit.address(end)?;
-                write!(
-                    w,
-                    "<startx-endx [{}]0x{:08x}, [{}]0x{:08x}>",
-                    begin.0, begin_val, end.0, end_val,
-                )?;
-                dump_range(w, range)?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-            gimli::RawLocListEntry::StartxLength {
-                begin,
-                length,
-                ref data,
-            } => {
-                let begin_val = unit.address(begin)?;
-                write!(
-                    w,
-                    "<startx-length [{}]0x{:08x}, 0x{:08x}>",
-                    begin.0, begin_val, length,
-                )?;
-                dump_range(w, range)?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-            gimli::RawLocListEntry::AddressOrOffsetPair {
-                begin,
-                end,
-                ref data,
-            }
-            | gimli::RawLocListEntry::OffsetPair {
-                begin,
-                end,
-                ref data,
-            } => {
-                write!(w, "<offset-pair 0x{:08x}, 0x{:08x}>", begin, end)?;
-                dump_range(w, range)?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-            gimli::RawLocListEntry::DefaultLocation { ref data } => {
-                write!(w, "<default location>")?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-            gimli::RawLocListEntry::StartEnd {
-                begin,
-                end,
-                ref data,
-            } => {
-                write!(w, "<start-end 0x{:08x}, 0x{:08x}>", begin, end)?;
-                dump_range(w, range)?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-            gimli::RawLocListEntry::StartLength {
-                begin,
-                length,
-                ref data,
-            } => {
-                write!(w, "<start-length 0x{:08x}, 0x{:08x}>", begin, length)?;
-                dump_range(w, range)?;
-                dump_exprloc(w, unit.encoding(), data)?;
-                writeln!(w)?;
-            }
-        };
-    }
-    Ok(())
-}
-
-fn dump_range_list<R: Reader, W: Write>(
-    w: &mut W,
-    offset: gimli::RangeListsOffset<R::Offset>,
-    unit: gimli::UnitRef<R>,
-) -> Result<()> {
-    let mut ranges = unit.ranges(offset)?;
-    writeln!(
-        w,
-        "<rnglist at {}+0x{:08x}>",
-        if unit.encoding().version < 5 {
-            ".debug_ranges"
-        } else {
-            ".debug_rnglists"
-        },
-        offset.0,
-    )?;
-    let mut i = 0;
-    while let Some(raw) = ranges.next_raw()? {
-        write!(w, "\t\t\t[{:2}] ", i)?;
-        i += 1;
-        let range = ranges.convert_raw(raw.clone())?;
-        match raw {
-            gimli::RawRngListEntry::BaseAddress { addr } => {
-                writeln!(w, "<new base address 0x{:08x}>", addr)?;
-            }
-            gimli::RawRngListEntry::BaseAddressx { addr } => {
-                let addr_val = unit.address(addr)?;
-                writeln!(w, "<new base addressx [{}]0x{:08x}>", addr.0, addr_val)?;
-            }
-            gimli::RawRngListEntry::StartxEndx { begin, end } => {
-                let begin_val = unit.address(begin)?;
-                let end_val = unit.address(end)?;
-                write!(
-                    w,
-                    "<startx-endx [{}]0x{:08x}, [{}]0x{:08x}>",
-                    begin.0, begin_val, end.0, end_val,
-                )?;
-                dump_range(w, range)?;
-                writeln!(w)?;
-            }
-            gimli::RawRngListEntry::StartxLength { begin, length } => {
-                let begin_val = unit.address(begin)?;
-                write!(
-                    w,
-                    "<startx-length [{}]0x{:08x}, 0x{:08x}>",
-                    begin.0, begin_val, length,
-                )?;
-                dump_range(w, range)?;
-                writeln!(w)?;
-            }
-            gimli::RawRngListEntry::AddressOrOffsetPair { begin, end }
-            | gimli::RawRngListEntry::OffsetPair { begin, end } => {
-                write!(w, "<offset-pair 0x{:08x}, 0x{:08x}>", begin, end)?;
-                dump_range(w, range)?;
-                writeln!(w)?;
-            }
-            gimli::RawRngListEntry::StartEnd { begin, end } => {
-                write!(w, "<start-end 0x{:08x}, 0x{:08x}>", begin, end)?;
-                dump_range(w, range)?;
-                writeln!(w)?;
-            }
-            gimli::RawRngListEntry::StartLength { begin, length } => {
-                write!(w, "<start-length 0x{:08x}, 0x{:08x}>", begin, length)?;
-                dump_range(w, range)?;
-                writeln!(w)?;
-            }
-        };
-    }
-    Ok(())
-}
-
 fn dump_line<R: Reader, W: Write>(w: &mut W, dwarf: &gimli::Dwarf<R>) -> Result<()> {
     let mut iter = dwarf.units();
     while let Some(header) = iter.next()? {
@@ -1896,7 +897,10 @@ fn dump_line<R: Reader, W: Write>(w: &mut W, dwarf: &gimli::Dwarf<R>) -> Result<
             }
         };
         let unit_ref = unit.unit_ref(dwarf);
-        match dump_line_program(w, unit_ref) {
+        match unit_ref
+            .dump_line_program(&mut FmtWriter(w))
+            .map_err(Error::from)
+        {
             Ok(_) => (),
             Err(Error::IoError) => return Err(Error::IoError),
             Err(err) => writeln_error(w, dwarf, err, "Failed to dump line program")?,
@@ -1905,189 +909,6 @@ fn dump_line<R: Reader, W: Write>(w: &mut W, dwarf: &gimli::Dwarf<R>) -> Result<
     Ok(())
 }
 
-fn dump_line_program<R: Reader, W: Write>(w: &mut W, unit: gimli::UnitRef<R>) -> Result<()> {
-    if let Some(program) = unit.line_program.clone() {
-        {
-            let header = program.header();
-            writeln!(w)?;
-            writeln!(
-                w,
-                "Offset:                             0x{:x}",
-                header.offset().0
-            )?;
-            writeln!(
-                w,
-                "Length:                             {}",
-                header.unit_length()
-            )?;
-            writeln!(
-                w,
-                "DWARF version:                      {}",
-                header.version()
-            )?;
-            writeln!(
-                w,
-                "Address size:                       {}",
-                header.address_size()
-            )?;
-            writeln!(
-                w,
-                "Prologue length:                    {}",
-                header.header_length()
-            )?;
-            writeln!(
-                w,
-                "Minimum instruction length:         {}",
-                header.minimum_instruction_length()
-            )?;
-            writeln!(
-                w,
-                "Maximum operations per instruction: {}",
-                header.maximum_operations_per_instruction()
-            )?;
-            writeln!(
-                w,
-                "Default is_stmt:                    {}",
-                header.default_is_stmt()
-            )?;
-            writeln!(
-                w,
-                "Line base:                          {}",
-                header.line_base()
-            )?;
-            writeln!(
-                w,
-                "Line range:                         {}",
-                header.line_range()
-            )?;
-            writeln!(
-                w,
-                "Opcode base:                        {}",
-                header.opcode_base()
-            )?;
-
-            writeln!(w)?;
-            writeln!(w, "Opcodes:")?;
-            for (i, length) in header
-                .standard_opcode_lengths()
-                .to_slice()?
-                .iter()
-                .enumerate()
-            {
-                writeln!(w, "  Opcode {} has {} args", i + 1, length)?;
-            }
-
-            let base = if header.version() >= 5 { 0 } else { 1 };
-            writeln!(w)?;
-            writeln!(w, "The Directory Table:")?;
-            for (i, dir) in header.include_directories().iter().enumerate() {
-                writeln!(
-                    w,
-                    "  {} {}",
-                    base + i,
-                    unit.attr_string(dir.clone())?.to_string_lossy()?
-                )?;
-            }
-
-            writeln!(w)?;
-            writeln!(w, "The File Name Table")?;
-            write!(w, "  Entry\tDir\tTime\tSize")?;
-            if header.file_has_md5() {
-                write!(w, "\tMD5\t\t\t\t")?;
-            }
-            writeln!(w, "\tName")?;
-            for (i, file) in header.file_names().iter().enumerate() {
-                write!(
-                    w,
-                    "  {}\t{}\t{}\t{}",
-                    base + i,
-                    file.directory_index(),
-                    file.timestamp(),
-                    file.size(),
-                )?;
-                if header.file_has_md5() {
-                    let md5 = file.md5();
-                    write!(w, "\t")?;
-                    for byte in md5 {
-                        write!(w, "{:02X}", byte)?;
-                    }
-                }
-                writeln!(
-                    w,
-                    "\t{}",
-                    unit.attr_string(file.path_name())?.to_string_lossy()?
-                )?;
-            }
-
-            writeln!(w)?;
-            writeln!(w, "Line Number Instructions:")?;
-            let mut instructions = header.instructions();
-            while let Some(instruction) = instructions.next_instruction(header)? {
-                writeln!(w, "  {}", instruction)?;
-            }
-
-            writeln!(w)?;
-            writeln!(w, "Line Number Rows:")?;
-            writeln!(w, "<pc>        [lno,col]")?;
-        }
-        let mut rows = program.rows();
-        let mut file_index = std::u64::MAX;
-        while let Some((header, row)) = rows.next_row()? {
-            let line = match row.line() {
-                Some(line) => line.get(),
-                None => 0,
-            };
-            let column = match row.column() {
-                gimli::ColumnType::Column(column) => column.get(),
-                gimli::ColumnType::LeftEdge => 0,
-            };
-            write!(w, "0x{:08x}  [{:4},{:2}]", row.address(), line, column)?;
-            if row.is_stmt() {
-                write!(w, " NS")?;
-            }
-            if row.basic_block() {
-                write!(w, " BB")?;
-            }
-            if row.end_sequence() {
-                write!(w, " ET")?;
-            }
-            if row.prologue_end() {
-                write!(w, " PE")?;
-            }
-            if row.epilogue_begin() {
-                write!(w, " EB")?;
-            }
-            if row.isa() != 0 {
-                write!(w, " IS={}", row.isa())?;
-            }
-            if row.discriminator() != 0 {
-                write!(w, " DI={}", row.discriminator())?;
-            }
-            if file_index != row.file_index() {
-                file_index = row.file_index();
-                if let Some(file) = row.file(header) {
-                    if let Some(directory) = file.directory(header) {
-                        write!(
-                            w,
-                            " uri: \"{}/{}\"",
-                            unit.attr_string(directory)?.to_string_lossy()?,
-                            unit.attr_string(file.path_name())?.to_string_lossy()?
-                        )?;
-                    } else {
-                        write!(
-                            w,
-                            " uri: \"{}\"",
-                            unit.attr_string(file.path_name())?.to_string_lossy()?
-                        )?;
-                    }
-                }
-            }
-            writeln!(w)?;
-        }
-    }
-    Ok(())
-}
-
 fn dump_pubnames<R: Reader, W: Write>(
     w: &mut W,
     debug_pubnames: &gimli::DebugPubNames<R>,
@@ -581,6 +581,9 @@ registers!(MIPS, {
     F29 = (61, "$f29"),
     F30 = (62, "$f30"),
     F31 = (63, "$f31"),
+
+    HI = (64, "$hi"),
+    LO = (65, "$lo"),
 },
 aliases {
     ZERO = (0, "$zero"),
@@ -930,9 +933,185 @@ registers!(X86_64, {
     K7 = (125, "k7"),
 });
 
+/// SPARC architecture specific definitions.
+///
+/// See the SPARC Compliance Definition 2.4.1.
+///
+/// SPARC uses register windows. The `DW_CFA_GNU_window_save` instruction is
+/// parsed when the vendor is `Vendor::Sparc`, and indicates that the in and
+/// local registers of the caller have been saved at the CFA. The return
+/// address register is `%o7` (or `%i7` after a window save), which contains
+/// the address of the call instruction rather than the return address.
+#[derive(Debug, Clone, Copy)]
+pub struct Sparc;
+
+registers!(Sparc, {
+    G0 = (0, "%g0"),
+    G1 = (1, "%g1"),
+    G2 = (2, "%g2"),
+    G3 = (3, "%g3"),
+    G4 = (4, "%g4"),
+    G5 = (5, "%g5"),
+    G6 = (6, "%g6"),
+    G7 = (7, "%g7"),
+
+    O0 = (8, "%o0"),
+    O1 = (9, "%o1"),
+    O2 = (10, "%o2"),
+    O3 = (11, "%o3"),
+    O4 = (12, "%o4"),
+    O5 = (13, "%o5"),
+    O6 = (14, "%o6"),
+    O7 = (15, "%o7"),
+
+    L0 = (16, "%l0"),
+    L1 = (17, "%l1"),
+    L2 = (18, "%l2"),
+    L3 = (19, "%l3"),
+    L4 = (20, "%l4"),
+    L5 = (21, "%l5"),
+    L6 = (22, "%l6"),
+    L7 = (23, "%l7"),
+
+    I0 = (24, "%i0"),
+    I1 = (25, "%i1"),
+    I2 = (26, "%i2"),
+    I3 = (27, "%i3"),
+    I4 = (28, "%i4"),
+    I5 = (29, "%i5"),
+    I6 = (30, "%i6"),
+    I7 = (31, "%i7"),
+
+    F0 = (32, "%f0"),
+    F1 = (33, "%f1"),
+    F2 = (34, "%f2"),
+    F3 = (35, "%f3"),
+    F4 = (36, "%f4"),
+    F5 = (37, "%f5"),
+    F6 = (38, "%f6"),
+    F7 = (39, "%f7"),
+    F8 = (40, "%f8"),
+    F9 = (41, "%f9"),
+    F10 = (42, "%f10"),
+    F11 = (43, "%f11"),
+    F12 = (44, "%f12"),
+    F13 = (45, "%f13"),
+    F14 = (46, "%f14"),
+    F15 = (47, "%f15"),
+    F16 = (48, "%f16"),
+    F17 = (49, "%f17"),
+    F18 = (50, "%f18"),
+    F19 = (51, "%f19"),
+    F20 = (52, "%f20"),
+    F21 = (53, "%f21"),
+    F22 = (54, "%f22"),
+    F23 = (55, "%f23"),
+    F24 = (56, "%f24"),
+    F25 = (57, "%f25"),
+    F26 = (58, "%f26"),
+    F27 = (59, "%f27"),
+    F28 = (60, "%f28"),
+    F29 = (61, "%f29"),
+    F30 = (62, "%f30"),
+    F31 = (63, "%f31"),
+},
+aliases {
+    SP = (14, "%sp"),
+    FP = (30, "%fp"),
+});
+
+/// s390x architecture specific definitions.
+///
+/// See [ELF Application Binary Interface s390x Supplement](https://github.com/IBM/s390x-abi).
+///
+/// Note that the floating-point registers are not numbered in order.
+///
+/// On s390x, the CFA is the stack pointer of the caller plus 160, so the
+/// caller's stack pointer `R15` is not equal to the CFA.
+#[derive(Debug, Clone, Copy)]
+pub struct S390x;
+
+registers!(S390x, {
+    R0 = (0, "%r0"),
+    R1 = (1, "%r1"),
+    R2 = (2, "%r2"),
+    R3 = (3, "%r3"),
+    R4 = (4, "%r4"),
+    R5 = (5, "%r5"),
+    R6 = (6, "%r6"),
+    R7 = (7, "%r7"),
+    R8 = (8, "%r8"),
+    R9 = (9, "%r9"),
+    R10 = (10, "%r10"),
+    R11 = (11, "%r11"),
+    R12 = (12, "%r12"),
+    R13 = (13, "%r13"),
+    R14 = (14, "%r14"),
+    R15 = (15, "%r15"),
+
+    F0 = (16, "%f0"),
+    F2 = (17, "%f2"),
+    F4 = (18, "%f4"),
+    F6 = (19, "%f6"),
+    F1 = (20, "%f1"),
+    F3 = (21, "%f3"),
+    F5 = (22, "%f5"),
+    F7 = (23, "%f7"),
+    F8 = (24, "%f8"),
+    F10 = (25, "%f10"),
+    F12 = (26, "%f12"),
+    F14 = (27, "%f14"),
+    F9 = (28, "%f9"),
+    F11 = (29, "%f11"),
+    F13 = (30, "%f13"),
+    F15 = (31, "%f15"),
+
+    C0 = (32, "%c0"),
+    C1 = (33, "%c1"),
+    C2 = (34, "%c2"),
+    C3 = (35, "%c3"),
+    C4 = (36, "%c4"),
+    C5 = (37, "%c5"),
+    C6 = (38, "%c6"),
+    C7 = (39, "%c7"),
+    C8 = (40, "%c8"),
+    C9 = (41, "%c9"),
+    C10 = (42, "%c10"),
+    C11 = (43, "%c11"),
+    C12 = (44, "%c12"),
+    C13 = (45, "%c13"),
+    C14 = (46, "%c14"),
+    C15 = (47, "%c15"),
+
+    A0 = (48, "%a0"),
+    A1 = (49, "%a1"),
+    A2 = (50, "%a2"),
+    A3 = (51, "%a3"),
+    A4 = (52, "%a4"),
+    A5 = (53, "%a5"),
+    A6 = (54, "%a6"),
+    A7 = (55, "%a7"),
+    A8 = (56, "%a8"),
+    A9 = (57, "%a9"),
+    A10 = (58, "%a10"),
+    A11 = (59, "%a11"),
+    A12 = (60, "%a12"),
+    A13 = (61, "%a13"),
+    A14 = (62, "%a14"),
+    A15 = (63, "%a15"),
+
+    PSWM = (64, "pswm"),
+    PSWA = (65, "pswa"),
+},
+aliases {
+    SP = (15, "%sp"),
+});
+
 /// PowerPC 64bit
 ///
 /// See [64-bit ELF ABI Specification for OpenPOWER Architecture](https://openpowerfoundation.org/specifications/64bitelfabi/).
+///
+/// The 32-bit PowerPC ELF ABI uses the same DWARF register numbers.
 #[derive(Debug, Clone, Copy)]
 pub struct PowerPc64;
 
@@ -1072,6 +1251,36 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_sparc_registers() {
+        use super::*;
+        use std::collections::HashSet;
+
+        let mut names = HashSet::new();
+        for n in 0..=63 {
+            let name = Sparc::register_name(Register(n))
+                .unwrap_or_else(|| panic!("Register {} should have a name.", n));
+            assert!(names.insert(name));
+        }
+        assert_eq!(Sparc::name_to_register("%fp"), Some(Sparc::I6));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_s390x_registers() {
+        use super::*;
+        use std::collections::HashSet;
+
+        let mut names = HashSet::new();
+        for n in 0..=65 {
+            let name = S390x::register_name(Register(n))
+                .unwrap_or_else(|| panic!("Register {} should have a name.", n));
+            assert!(names.insert(name));
+        }
+        assert_eq!(S390x::name_to_register("%f2"), Some(Register(17)));
+    }
+
     #[test]
     #[cfg(feature = "std")]
     fn test_power64_registers() {
@@ -0,0 +1,501 @@
+//! A C API for the unwinding subset of `gimli`.
+//!
+//! This provides lookup of unwind information in the `.eh_frame` and
+//! `.eh_frame_hdr` sections, and evaluation of the resulting unwind table
+//! rows, so that it can be used from C or C++ in place of a minimal `libunwind`.
+//!
+//! All functions are `extern "C"` and use only FFI-safe types. The section data
+//! passed to [`gimli_unwind_info_new`] must remain valid and unmodified until
+//! the returned handle is freed with [`gimli_unwind_info_free`].
+//!
+//! A handle must not be used concurrently from multiple threads, because it
+//! contains the unwinding context used for evaluating CFI.
+//!
+//! The corresponding C declarations are:
+//!
+//! ```c
+//! typedef struct GimliUnwindInfo GimliUnwindInfo;
+//!
+//! typedef struct {
+//!     const uint8_t *data;
+//!     size_t len;
+//!     uint64_t address;
+//! } GimliSection;
+//!
+//! typedef enum {
+//!     GIMLI_STATUS_OK = 0,
+//!     GIMLI_STATUS_NOT_FOUND = 1,
+//!     GIMLI_STATUS_ERROR = 2,
+//!     GIMLI_STATUS_INVALID_ARGUMENT = 3,
+//!     GIMLI_STATUS_UNSUPPORTED = 4,
+//! } GimliStatus;
+//!
+//! typedef struct {
+//!     uint64_t start_address;
+//!     uint64_t end_address;
+//!     uint16_t return_address_register;
+//!     uint16_t cfa_register;
+//!     int64_t cfa_offset;
+//!     bool cfa_is_expression;
+//! } GimliUnwindRow;
+//!
+//! typedef bool (*GimliReadMemory)(void *data, uint64_t address, uint64_t *value);
+//!
+//! /* `address_size` must be between 1 and 8. Returns NULL on error. */
+//! GimliUnwindInfo *gimli_unwind_info_new(const GimliSection *eh_frame,
+//!                                        const GimliSection *eh_frame_hdr,
+//!                                        uint64_t text_address,
+//!                                        uint8_t address_size,
+//!                                        bool big_endian);
+//! void gimli_unwind_info_free(GimliUnwindInfo *info);
+//! GimliStatus gimli_unwind_row_for_address(GimliUnwindInfo *info, uint64_t address,
+//!                                          GimliUnwindRow *row);
+//! /* `registers` and `caller_registers` must not overlap. */
+//! GimliStatus gimli_unwind_step(GimliUnwindInfo *info, uint64_t address,
+//!                               const uint64_t *registers, uint64_t *caller_registers,
+//!                               size_t register_count, uint64_t *cfa,
+//!                               GimliReadMemory read_memory, void *data);
+//! ```
+
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use core::ptr;
+use core::slice;
+
+use crate::common::Register;
+use crate::endianity::RunTimeEndian;
+use crate::read::{
+    BaseAddresses, CfaRule, EhFrame, EhFrameHdr, EndianSlice, Error, ParsedEhFrameHdr,
+    RegisterRule, UnwindContext, UnwindSection, UnwindTableRow,
+};
+
+type Slice = EndianSlice<'static, RunTimeEndian>;
+
+/// The section data and load address of a section.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GimliSection {
+    /// A pointer to the contents of the section.
+    pub data: *const u8,
+    /// The length in bytes of the contents of the section.
+    pub len: usize,
+    /// The address of the section when loaded in memory.
+    pub address: u64,
+}
+
+/// The status returned by the C API functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GimliStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// There is no unwind information for the given address.
+    NotFound = 1,
+    /// An error occurred while parsing the unwind information.
+    Error = 2,
+    /// An argument was invalid, such as a null pointer.
+    InvalidArgument = 3,
+    /// The unwind information requires a feature that is not supported, such as
+    /// evaluation of DWARF expressions.
+    Unsupported = 4,
+}
+
+impl From<Error> for GimliStatus {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::NoUnwindInfoForAddress => GimliStatus::NotFound,
+            _ => GimliStatus::Error,
+        }
+    }
+}
+
+/// The unwind table row for an address.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GimliUnwindRow {
+    /// The first address that the row applies to.
+    pub start_address: u64,
+    /// The first address after the end of the range that the row applies to.
+    pub end_address: u64,
+    /// The register containing the return address.
+    pub return_address_register: u16,
+    /// The register used to compute the canonical frame address (CFA).
+    ///
+    /// Not valid if `cfa_is_expression` is true.
+    pub cfa_register: u16,
+    /// The offset added to `cfa_register` to compute the CFA.
+    ///
+    /// Not valid if `cfa_is_expression` is true.
+    pub cfa_offset: i64,
+    /// True if the CFA is computed using a DWARF expression.
+    pub cfa_is_expression: bool,
+}
+
+/// A callback function to read a pointer sized value from memory.
+///
+/// Returns false if the memory could not be read.
+pub type GimliReadMemory =
+    Option<unsafe extern "C" fn(data: *mut c_void, address: u64, value: *mut u64) -> bool>;
+
+/// A handle for the unwind information of a loaded object.
+///
+/// Created by [`gimli_unwind_info_new`].
+#[derive(Debug)]
+pub struct GimliUnwindInfo {
+    eh_frame: EhFrame<Slice>,
+    eh_frame_hdr: Option<ParsedEhFrameHdr<Slice>>,
+    bases: BaseAddresses,
+    ctx: UnwindContext<usize>,
+}
+
+impl GimliUnwindInfo {
+    fn row_for_address(
+        &mut self,
+        address: u64,
+    ) -> Result<(Register, &UnwindTableRow<usize>), Error> {
+        let fde = match self.eh_frame_hdr.as_ref().and_then(|hdr| hdr.table()) {
+            Some(table) => table.fde_for_address(
+                &self.eh_frame,
+                &self.bases,
+                address,
+                EhFrame::cie_from_offset,
+            )?,
+            None => {
+                self.eh_frame
+                    .fde_for_address(&self.bases, address, EhFrame::cie_from_offset)?
+            }
+        };
+        let row =
+            fde.unwind_info_for_address(&self.eh_frame, &self.bases, &mut self.ctx, address)?;
+        Ok((fde.cie().return_address_register(), row))
+    }
+}
+
+unsafe fn section_slice(section: &GimliSection, endian: RunTimeEndian) -> Option<Slice> {
+    if section.data.is_null() {
+        if section.len != 0 {
+            return None;
+        }
+        return Some(EndianSlice::new(&[], endian));
+    }
+    Some(EndianSlice::new(
+        slice::from_raw_parts(section.data, section.len),
+        endian,
+    ))
+}
+
+/// Create a handle for the unwind information in the given sections.
+///
+/// `eh_frame_hdr` may be null, in which case lookups perform a linear search of
+/// `eh_frame`. `text_address` is the address of the `.text` section, which is
+/// needed for some pointer encodings. `address_size` is the size in bytes of a
+/// target address, and must be between 1 and 8.
+///
+/// Returns null if an argument is invalid, such as an unsupported
+/// `address_size`, or if the `.eh_frame_hdr` section cannot be parsed.
+///
+/// # Safety
+///
+/// `eh_frame` must be a valid pointer, and `eh_frame_hdr` must be null or a
+/// valid pointer. The section data must remain valid until the handle is freed.
+#[no_mangle]
+pub unsafe extern "C" fn gimli_unwind_info_new(
+    eh_frame: *const GimliSection,
+    eh_frame_hdr: *const GimliSection,
+    text_address: u64,
+    address_size: u8,
+    big_endian: bool,
+) -> *mut GimliUnwindInfo {
+    let endian = if big_endian {
+        RunTimeEndian::Big
+    } else {
+        RunTimeEndian::Little
+    };
+    if eh_frame.is_null() || !(1..=8).contains(&address_size) {
+        return ptr::null_mut();
+    }
+    let eh_frame = &*eh_frame;
+    let mut bases = BaseAddresses::default()
+        .set_eh_frame(eh_frame.address)
+        .set_text(text_address);
+    let mut eh_frame = match section_slice(eh_frame, endian) {
+        Some(data) => EhFrame::from(data),
+        None => return ptr::null_mut(),
+    };
+    eh_frame.set_address_size(address_size);
+
+    let eh_frame_hdr = if eh_frame_hdr.is_null() {
+        None
+    } else {
+        let eh_frame_hdr = &*eh_frame_hdr;
+        bases = bases.set_eh_frame_hdr(eh_frame_hdr.address);
+        let data = match section_slice(eh_frame_hdr, endian) {
+            Some(data) => data,
+            None => return ptr::null_mut(),
+        };
+        match EhFrameHdr::from(data).parse(&bases, address_size) {
+            Ok(hdr) => Some(hdr),
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+
+    Box::into_raw(Box::new(GimliUnwindInfo {
+        eh_frame,
+        eh_frame_hdr,
+        bases,
+        ctx: UnwindContext::new(),
+    }))
+}
+
+/// Free a handle created by [`gimli_unwind_info_new`].
+///
+/// # Safety
+///
+/// `info` must be null or a handle returned by `gimli_unwind_info_new` that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn gimli_unwind_info_free(info: *mut GimliUnwindInfo) {
+    if !info.is_null() {
+        drop(Box::from_raw(info));
+    }
+}
+
+/// Find the unwind table row for the given address.
+///
+/// # Safety
+///
+/// `info` must be a valid handle, and `row` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gimli_unwind_row_for_address(
+    info: *mut GimliUnwindInfo,
+    address: u64,
+    row: *mut GimliUnwindRow,
+) -> GimliStatus {
+    if info.is_null() || row.is_null() {
+        return GimliStatus::InvalidArgument;
+    }
+    let (ra, unwind_row) = match (*info).row_for_address(address) {
+        Ok(x) => x,
+        Err(e) => return e.into(),
+    };
+    let mut result = GimliUnwindRow {
+        start_address: unwind_row.start_address(),
+        end_address: unwind_row.end_address(),
+        return_address_register: ra.0,
+        ..Default::default()
+    };
+    match *unwind_row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => {
+            result.cfa_register = register.0;
+            result.cfa_offset = offset;
+        }
+        CfaRule::Expression(_) => result.cfa_is_expression = true,
+    }
+    *row = result;
+    GimliStatus::Ok
+}
+
+/// Compute the register values for the caller of the frame at the given address.
+///
+/// `registers` contains the values of the registers in the current frame,
+/// indexed by DWARF register number. `caller_registers` receives the values of
+/// the registers in the caller's frame; registers with undefined rules are set
+/// to zero, and registers without rules keep their current value. The return
+/// address is stored in the entry for the CIE's return address register.
+///
+/// `cfa` receives the canonical frame address, which for most architectures
+/// is the value of the stack pointer in the caller's frame.
+///
+/// `read_memory` is called with `data` to read saved register values.
+///
+/// Returns `GimliStatus::Unsupported` if a rule requires evaluating a DWARF
+/// expression, and `GimliStatus::InvalidArgument` if a rule refers to a
+/// register that is not less than `register_count`.
+///
+/// # Safety
+///
+/// `info` must be a valid handle, `registers` and `caller_registers` must be
+/// valid for `register_count` elements, and `cfa` must be a valid pointer.
+/// `registers` and `caller_registers` must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn gimli_unwind_step(
+    info: *mut GimliUnwindInfo,
+    address: u64,
+    registers: *const u64,
+    caller_registers: *mut u64,
+    register_count: usize,
+    cfa: *mut u64,
+    read_memory: GimliReadMemory,
+    data: *mut c_void,
+) -> GimliStatus {
+    if info.is_null() || registers.is_null() || caller_registers.is_null() || cfa.is_null() {
+        return GimliStatus::InvalidArgument;
+    }
+    let registers = slice::from_raw_parts(registers, register_count);
+    let caller_registers = slice::from_raw_parts_mut(caller_registers, register_count);
+    let (_, row) = match (*info).row_for_address(address) {
+        Ok(x) => x,
+        Err(e) => return e.into(),
+    };
+
+    let cfa_value = match *row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => {
+            match registers.get(usize::from(register.0)) {
+                Some(value) => value.wrapping_add(offset as u64),
+                None => return GimliStatus::InvalidArgument,
+            }
+        }
+        CfaRule::Expression(_) => return GimliStatus::Unsupported,
+    };
+
+    let read = |address: u64| -> Option<u64> {
+        let read_memory = read_memory?;
+        let mut value = 0;
+        if read_memory(data, address, &mut value) {
+            Some(value)
+        } else {
+            None
+        }
+    };
+
+    caller_registers.copy_from_slice(registers);
+    for &(register, ref rule) in row.registers() {
+        let value = match *rule {
+            RegisterRule::Undefined => 0,
+            RegisterRule::SameValue => continue,
+            RegisterRule::Offset(offset) => match read(cfa_value.wrapping_add(offset as u64)) {
+                Some(value) => value,
+                None => return GimliStatus::Error,
+            },
+            RegisterRule::ValOffset(offset) => cfa_value.wrapping_add(offset as u64),
+            RegisterRule::Register(other) => match registers.get(usize::from(other.0)) {
+                Some(value) => *value,
+                None => return GimliStatus::InvalidArgument,
+            },
+            RegisterRule::Constant(value) => value,
+            _ => return GimliStatus::Unsupported,
+        };
+        match caller_registers.get_mut(usize::from(register.0)) {
+            Some(slot) => *slot = value,
+            None => return GimliStatus::InvalidArgument,
+        }
+    }
+    *cfa = cfa_value;
+    GimliStatus::Ok
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::write::{
+        self, Address, CallFrameInstruction, CommonInformationEntry, EndianVec,
+        FrameDescriptionEntry, FrameTable,
+    };
+    use crate::{LittleEndian, X86_64};
+    use alloc::vec::Vec;
+
+    fn eh_frame() -> Vec<u8> {
+        let mut table = FrameTable::default();
+        let mut cie = CommonInformationEntry::new(
+            Encoding {
+                format: Format::Dwarf32,
+                version: 1,
+                address_size: 8,
+            },
+            1,
+            -8,
+            X86_64::RA,
+        );
+        cie.add_instruction(CallFrameInstruction::Cfa(X86_64::RSP, 8));
+        cie.add_instruction(CallFrameInstruction::Offset(X86_64::RA, -8));
+        let cie_id = table.add_cie(cie);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x1000), 0x20);
+        fde.add_instruction(1, CallFrameInstruction::CfaOffset(16));
+        fde.add_instruction(1, CallFrameInstruction::Offset(X86_64::RBP, -16));
+        table.add_fde(cie_id, fde);
+        let mut eh_frame = write::EhFrame::from(EndianVec::new(LittleEndian));
+        table.write_eh_frame(&mut eh_frame).unwrap();
+        eh_frame.slice().to_vec()
+    }
+
+    unsafe extern "C" fn read_memory(data: *mut c_void, address: u64, value: *mut u64) -> bool {
+        let stack = &*(data as *const [u64; 4]);
+        match address.checked_sub(0x8000) {
+            Some(offset) if offset % 8 == 0 && offset < 32 => {
+                *value = stack[offset as usize / 8];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_unwind() {
+        let data = eh_frame();
+        let section = GimliSection {
+            data: data.as_ptr(),
+            len: data.len(),
+            address: 0x2000,
+        };
+        unsafe {
+            for address_size in [0, 9] {
+                let info = gimli_unwind_info_new(&section, ptr::null(), 0, address_size, false);
+                assert!(info.is_null());
+            }
+            let info = gimli_unwind_info_new(&section, ptr::null(), 0, 3, false);
+            assert!(!info.is_null());
+            gimli_unwind_info_free(info);
+
+            let info = gimli_unwind_info_new(&section, ptr::null(), 0, 8, false);
+            assert!(!info.is_null());
+
+            let mut row = GimliUnwindRow::default();
+            assert_eq!(
+                gimli_unwind_row_for_address(info, 0x3000, &mut row),
+                GimliStatus::NotFound
+            );
+            assert_eq!(
+                gimli_unwind_row_for_address(info, 0x1008, &mut row),
+                GimliStatus::Ok
+            );
+            assert_eq!(
+                row,
+                GimliUnwindRow {
+                    start_address: 0x1001,
+                    end_address: 0x1020,
+                    return_address_register: X86_64::RA.0,
+                    cfa_register: X86_64::RSP.0,
+                    cfa_offset: 16,
+                    cfa_is_expression: false,
+                }
+            );
+
+            let mut registers = [0u64; 17];
+            registers[X86_64::RSP.0 as usize] = 0x8000;
+            registers[X86_64::RBP.0 as usize] = 1;
+            let mut stack = [0x1234u64, 0x5678, 0, 0];
+            let mut caller_registers = [0u64; 17];
+            let mut cfa = 0;
+            assert_eq!(
+                gimli_unwind_step(
+                    info,
+                    0x1008,
+                    registers.as_ptr(),
+                    caller_registers.as_mut_ptr(),
+                    registers.len(),
+                    &mut cfa,
+                    Some(read_memory),
+                    &mut stack as *mut _ as *mut c_void,
+                ),
+                GimliStatus::Ok
+            );
+            assert_eq!(cfa, 0x8010);
+            assert_eq!(caller_registers[X86_64::RA.0 as usize], 0x5678);
+            assert_eq!(caller_registers[X86_64::RBP.0 as usize], 0x1234);
+
+            gimli_unwind_info_free(info);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 /// Whether the format of a compilation unit is 32- or 64-bit.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Format {
     /// 64-bit DWARF
     Dwarf64 = 8,
@@ -29,18 +30,22 @@ impl Format {
 
 /// Which vendor extensions to support.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Vendor {
     /// A default set of extensions, including some common GNU extensions.
     Default,
     /// AAarch64 extensions.
     AArch64,
+    /// SPARC extensions.
+    Sparc,
 }
 
 /// Encoding parameters that are commonly used for multiple DWARF sections.
 ///
 /// This is intended to be small enough to pass by value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // `address_size` and `format` are used more often than `version`, so keep
 // them first.
 #[repr(C)]
@@ -59,6 +64,7 @@ pub struct Encoding {
 
 /// Encoding parameters for a line number program.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineEncoding {
     /// The size in bytes of the smallest target machine instruction.
     pub minimum_instruction_length: u8,
@@ -95,100 +101,133 @@ impl Default for LineEncoding {
 /// The meaning of this value is ABI dependent. This is generally encoded as
 /// a ULEB128, but supported architectures need 16 bits at most.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Register(pub u16);
 
 /// An offset into the `.debug_abbrev` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugAbbrevOffset<T = usize>(pub T);
 
 /// An offset to a set of entries in the `.debug_addr` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugAddrBase<T = usize>(pub T);
 
 /// An index into a set of addresses in the `.debug_addr` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugAddrIndex<T = usize>(pub T);
 
 /// An offset into the `.debug_aranges` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugArangesOffset<T = usize>(pub T);
 
 /// An offset into the `.debug_info` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugInfoOffset<T = usize>(pub T);
 
 /// An offset into the `.debug_line` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugLineOffset<T = usize>(pub T);
 
 /// An offset into the `.debug_line_str` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugLineStrOffset<T = usize>(pub T);
 
 /// An offset into either the `.debug_loc` section or the `.debug_loclists` section,
 /// depending on the version of the unit the offset was contained in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocationListsOffset<T = usize>(pub T);
 
 /// An offset to a set of location list offsets in the `.debug_loclists` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugLocListsBase<T = usize>(pub T);
 
 /// An index into a set of location list offsets in the `.debug_loclists` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugLocListsIndex<T = usize>(pub T);
 
 /// An offset into the `.debug_macinfo` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugMacinfoOffset<T = usize>(pub T);
 
 /// An offset into the `.debug_macro` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugMacroOffset<T = usize>(pub T);
 
+/// An offset into the `.debug_pubnames` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugPubNamesOffset<T = usize>(pub T);
+
+/// An offset into the `.debug_pubtypes` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugPubTypesOffset<T = usize>(pub T);
+
 /// An offset into either the `.debug_ranges` section or the `.debug_rnglists` section,
 /// depending on the version of the unit the offset was contained in.
 ///
 /// If this is from a DWARF 4 DWO file, then it must additionally be offset by the
 /// value of `DW_AT_GNU_ranges_base`. You can use `Dwarf::ranges_offset_from_raw` to do this.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RawRangeListsOffset<T = usize>(pub T);
 
 /// An offset into either the `.debug_ranges` section or the `.debug_rnglists` section,
 /// depending on the version of the unit the offset was contained in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RangeListsOffset<T = usize>(pub T);
 
 /// An offset to a set of range list offsets in the `.debug_rnglists` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugRngListsBase<T = usize>(pub T);
 
 /// An index into a set of range list offsets in the `.debug_rnglists` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugRngListsIndex<T = usize>(pub T);
 
 /// An offset into the `.debug_str` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugStrOffset<T = usize>(pub T);
 
 /// An offset to a set of entries in the `.debug_str_offsets` section.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugStrOffsetsBase<T = usize>(pub T);
 
 /// An index into a set of entries in the `.debug_str_offsets` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugStrOffsetsIndex<T = usize>(pub T);
 
 /// An offset into the `.debug_types` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugTypesOffset<T = usize>(pub T);
 
 /// A type signature as used in the `.debug_types` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugTypeSignature(pub u64);
 
 /// An offset into the `.debug_frame` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugFrameOffset<T = usize>(pub T);
 
 impl<T> From<T> for DebugFrameOffset<T> {
@@ -200,6 +239,7 @@ impl<T> From<T> for DebugFrameOffset<T> {
 
 /// An offset into the `.eh_frame` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EhFrameOffset<T = usize>(pub T);
 
 impl<T> From<T> for EhFrameOffset<T> {
@@ -211,6 +251,7 @@ impl<T> From<T> for EhFrameOffset<T> {
 
 /// An offset into the `.debug_info` or `.debug_types` sections.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnitSectionOffset<T = usize> {
     /// An offset into the `.debug_info` section.
     DebugInfoOffset(DebugInfoOffset<T>),
@@ -252,6 +293,7 @@ where
 
 /// An identifier for a DWARF section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SectionId {
     /// The `.debug_abbrev` section.
     DebugAbbrev,
@@ -281,6 +323,12 @@ pub enum SectionId {
     DebugMacinfo,
     /// The `.debug_macro` section.
     DebugMacro,
+    /// The `.debug_names` section.
+    DebugNames,
+    /// The `.debug_gnu_pubnames` section.
+    DebugGnuPubNames,
+    /// The `.debug_gnu_pubtypes` section.
+    DebugGnuPubTypes,
     /// The `.debug_pubnames` section.
     DebugPubNames,
     /// The `.debug_pubtypes` section.
@@ -297,6 +345,24 @@ pub enum SectionId {
     DebugTuIndex,
     /// The `.debug_types` section.
     DebugTypes,
+    /// The `.gdb_index` section.
+    GdbIndex,
+    /// The `.apple_names` section.
+    AppleNames,
+    /// The `.apple_namespac` section.
+    AppleNamespaces,
+    /// The `.apple_objc` section.
+    AppleObjc,
+    /// The `.apple_types` section.
+    AppleTypes,
+    /// The `.debug_sup` section.
+    DebugSup,
+    /// The `.gnu_debuglink` section.
+    GnuDebuglink,
+    /// The `.gnu_debugaltlink` section.
+    GnuDebugaltlink,
+    /// The `.note.gnu.build-id` section.
+    NoteGnuBuildId,
 }
 
 impl SectionId {
@@ -317,6 +383,9 @@ impl SectionId {
             SectionId::DebugLocLists => ".debug_loclists",
             SectionId::DebugMacinfo => ".debug_macinfo",
             SectionId::DebugMacro => ".debug_macro",
+            SectionId::DebugGnuPubNames => ".debug_gnu_pubnames",
+            SectionId::DebugGnuPubTypes => ".debug_gnu_pubtypes",
+            SectionId::DebugNames => ".debug_names",
             SectionId::DebugPubNames => ".debug_pubnames",
             SectionId::DebugPubTypes => ".debug_pubtypes",
             SectionId::DebugRanges => ".debug_ranges",
@@ -325,6 +394,15 @@ impl SectionId {
             SectionId::DebugStrOffsets => ".debug_str_offsets",
             SectionId::DebugTuIndex => ".debug_tu_index",
             SectionId::DebugTypes => ".debug_types",
+            SectionId::GdbIndex => ".gdb_index",
+            SectionId::AppleNames => ".apple_names",
+            SectionId::AppleNamespaces => ".apple_namespac",
+            SectionId::AppleObjc => ".apple_objc",
+            SectionId::AppleTypes => ".apple_types",
+            SectionId::DebugSup => ".debug_sup",
+            SectionId::GnuDebuglink => ".gnu_debuglink",
+            SectionId::GnuDebugaltlink => ".gnu_debugaltlink",
+            SectionId::NoteGnuBuildId => ".note.gnu.build-id",
         }
     }
 
@@ -366,16 +444,154 @@ impl SectionId {
             _ => return None,
         })
     }
+
+    /// Returns the Mach-O section name for this kind.
+    ///
+    /// Mach-O section names are limited to 16 bytes, so some names are truncated.
+    pub fn macho_name(self) -> Option<&'static str> {
+        Some(match self {
+            SectionId::DebugAbbrev => "__debug_abbrev",
+            SectionId::DebugAddr => "__debug_addr",
+            SectionId::DebugAranges => "__debug_aranges",
+            SectionId::DebugCuIndex => "__debug_cu_index",
+            SectionId::DebugFrame => "__debug_frame",
+            SectionId::EhFrame => "__eh_frame",
+            SectionId::DebugInfo => "__debug_info",
+            SectionId::DebugLine => "__debug_line",
+            SectionId::DebugLineStr => "__debug_line_str",
+            SectionId::DebugLoc => "__debug_loc",
+            SectionId::DebugLocLists => "__debug_loclists",
+            SectionId::DebugMacinfo => "__debug_macinfo",
+            SectionId::DebugMacro => "__debug_macro",
+            SectionId::DebugNames => "__debug_names",
+            SectionId::DebugPubNames => "__debug_pubnames",
+            SectionId::DebugPubTypes => "__debug_pubtypes",
+            SectionId::DebugRanges => "__debug_ranges",
+            SectionId::DebugRngLists => "__debug_rnglists",
+            SectionId::DebugStr => "__debug_str",
+            SectionId::DebugStrOffsets => "__debug_str_offs",
+            SectionId::DebugTuIndex => "__debug_tu_index",
+            SectionId::DebugTypes => "__debug_types",
+            SectionId::AppleNames => "__apple_names",
+            SectionId::AppleNamespaces => "__apple_namespac",
+            SectionId::AppleObjc => "__apple_objc",
+            SectionId::AppleTypes => "__apple_types",
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the compressed ELF section for this kind.
+    ///
+    /// These sections are named `.zdebug_*` and contain a `ZLIB` header
+    /// followed by zlib compressed data. Decompression is the responsibility
+    /// of the loader.
+    pub fn zdebug_name(self) -> Option<&'static str> {
+        Some(match self {
+            SectionId::DebugAbbrev => ".zdebug_abbrev",
+            SectionId::DebugAddr => ".zdebug_addr",
+            SectionId::DebugAranges => ".zdebug_aranges",
+            SectionId::DebugCuIndex => ".zdebug_cu_index",
+            SectionId::DebugFrame => ".zdebug_frame",
+            SectionId::DebugInfo => ".zdebug_info",
+            SectionId::DebugLine => ".zdebug_line",
+            SectionId::DebugLineStr => ".zdebug_line_str",
+            SectionId::DebugLoc => ".zdebug_loc",
+            SectionId::DebugLocLists => ".zdebug_loclists",
+            SectionId::DebugMacinfo => ".zdebug_macinfo",
+            SectionId::DebugMacro => ".zdebug_macro",
+            SectionId::DebugNames => ".zdebug_names",
+            SectionId::DebugPubNames => ".zdebug_pubnames",
+            SectionId::DebugPubTypes => ".zdebug_pubtypes",
+            SectionId::DebugRanges => ".zdebug_ranges",
+            SectionId::DebugRngLists => ".zdebug_rnglists",
+            SectionId::DebugStr => ".zdebug_str",
+            SectionId::DebugStrOffsets => ".zdebug_str_offsets",
+            SectionId::DebugTuIndex => ".zdebug_tu_index",
+            SectionId::DebugTypes => ".zdebug_types",
+            _ => return None,
+        })
+    }
+
+    /// Returns the WebAssembly custom section name for this kind.
+    ///
+    /// WebAssembly stores DWARF sections in custom sections using the ELF names.
+    /// Unwind information and ELF link sections are not used for WebAssembly.
+    pub fn wasm_name(self) -> Option<&'static str> {
+        match self {
+            SectionId::EhFrame
+            | SectionId::EhFrameHdr
+            | SectionId::GnuDebuglink
+            | SectionId::GnuDebugaltlink
+            | SectionId::NoteGnuBuildId => None,
+            _ => Some(self.name()),
+        }
+    }
+
+    /// Returns all section kinds.
+    pub fn all() -> &'static [SectionId] {
+        &[
+            SectionId::DebugAbbrev,
+            SectionId::DebugAddr,
+            SectionId::DebugAranges,
+            SectionId::DebugCuIndex,
+            SectionId::DebugFrame,
+            SectionId::EhFrame,
+            SectionId::EhFrameHdr,
+            SectionId::DebugInfo,
+            SectionId::DebugLine,
+            SectionId::DebugLineStr,
+            SectionId::DebugLoc,
+            SectionId::DebugLocLists,
+            SectionId::DebugMacinfo,
+            SectionId::DebugMacro,
+            SectionId::DebugGnuPubNames,
+            SectionId::DebugGnuPubTypes,
+            SectionId::DebugNames,
+            SectionId::DebugPubNames,
+            SectionId::DebugPubTypes,
+            SectionId::DebugRanges,
+            SectionId::DebugRngLists,
+            SectionId::DebugStr,
+            SectionId::DebugStrOffsets,
+            SectionId::DebugTuIndex,
+            SectionId::DebugTypes,
+            SectionId::GdbIndex,
+            SectionId::AppleNames,
+            SectionId::AppleNamespaces,
+            SectionId::AppleObjc,
+            SectionId::AppleTypes,
+            SectionId::DebugSup,
+            SectionId::GnuDebuglink,
+            SectionId::GnuDebugaltlink,
+            SectionId::NoteGnuBuildId,
+        ]
+    }
+
+    /// Find the section kind for the given section name.
+    ///
+    /// This recognizes ELF names (including `.dwo` and `.zdebug_*` variants),
+    /// Mach-O names, and XCOFF names.
+    pub fn from_name(name: &str) -> Option<SectionId> {
+        SectionId::all().iter().copied().find(|id| {
+            id.name() == name
+                || id.dwo_name() == Some(name)
+                || id.zdebug_name() == Some(name)
+                || id.macho_name() == Some(name)
+                || id.xcoff_name() == Some(name)
+        })
+    }
 }
 
 /// An optionally-provided implementation-defined compilation unit ID to enable
 /// split DWARF and linking a split compilation unit back together.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DwoId(pub u64);
 
 /// The "type" of file with DWARF debugging information. This determines, among other things,
 /// which files DWARF sections should be loaded from.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DwarfFileType {
     /// A normal executable or object file.
     Main,
@@ -389,3 +605,175 @@ impl Default for DwarfFileType {
         DwarfFileType::Main
     }
 }
+
+/// The convention used for code addresses in the debugging information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AddressConvention {
+    /// Addresses are virtual addresses in the address space of the program.
+    Absolute,
+    /// Addresses are offsets relative to the start of the code section.
+    ///
+    /// This is the convention used for WebAssembly. Since a function body
+    /// can never start at offset 0 of the code section, an address of 0
+    /// indicates code that was removed by the linker. `Dwarf::tombstone_filter`
+    /// treats this address as a tombstone, so entries that begin at it are
+    /// skipped in range lists, location lists, and line programs.
+    CodeSectionRelative,
+}
+
+impl Default for AddressConvention {
+    fn default() -> Self {
+        AddressConvention::Absolute
+    }
+}
+
+/// A filter for recognizing the addresses of code that was removed by the linker.
+///
+/// When a linker discards a section, it may resolve relocations against that
+/// section to a tombstone value such as 0, -1, or -2, or leave them relative
+/// to the start of an unrelated section. Entries that begin at such an address
+/// do not describe any code in the program.
+///
+/// The standard tombstone values (-1 for DWARF 5, and -2 for `.debug_ranges`
+/// and `.debug_loc`) are always recognized. This filter specifies additional
+/// addresses to treat as tombstones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TombstoneFilter {
+    /// Treat entries that begin at address 0 as tombstones.
+    pub zero: bool,
+    /// Treat entries that begin at the maximum address (-1) or one less than
+    /// the maximum address (-2) as tombstones, regardless of the DWARF version.
+    pub max: bool,
+    /// Treat entries that begin below this address as tombstones.
+    pub min_address: u64,
+}
+
+impl TombstoneFilter {
+    /// Return true if an entry that begins at `address` should be treated as a
+    /// tombstone.
+    ///
+    /// `address_size` is the size in bytes of addresses in the unit.
+    pub fn is_tombstone(&self, address: u64, address_size: u8) -> bool {
+        let max = !0 >> (64 - address_size * 8);
+        (self.zero && address == 0)
+            || (self.max && (address == max || address == max - 1))
+            || address < self.min_address
+    }
+}
+
+/// The kind of a symbol in a `.gdb_index` CU vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GdbIndexSymbolKind {
+    /// The kind is not specified.
+    None,
+    /// A type, such as a struct or typedef.
+    Type,
+    /// A variable or enumerator.
+    Variable,
+    /// A function.
+    Function,
+    /// Any other kind of symbol.
+    Other,
+    /// A reserved value.
+    Reserved(u8),
+}
+
+impl GdbIndexSymbolKind {
+    #[cfg(feature = "read")]
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            0 => GdbIndexSymbolKind::None,
+            1 => GdbIndexSymbolKind::Type,
+            2 => GdbIndexSymbolKind::Variable,
+            3 => GdbIndexSymbolKind::Function,
+            4 => GdbIndexSymbolKind::Other,
+            _ => GdbIndexSymbolKind::Reserved(value),
+        }
+    }
+
+    #[cfg(feature = "write")]
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            GdbIndexSymbolKind::None => 0,
+            GdbIndexSymbolKind::Type => 1,
+            GdbIndexSymbolKind::Variable => 2,
+            GdbIndexSymbolKind::Function => 3,
+            GdbIndexSymbolKind::Other => 4,
+            GdbIndexSymbolKind::Reserved(value) => value,
+        }
+    }
+}
+
+/// The hash function used for the symbol table in a `.gdb_index` section.
+///
+/// This is the hash function used by GDB for index versions 5 and later,
+/// which ignores ASCII case.
+#[cfg(any(feature = "read", feature = "write"))]
+pub(crate) fn gdb_index_hash(name: &[u8]) -> u32 {
+    name.iter().fold(0u32, |hash, c| {
+        hash.wrapping_mul(67)
+            .wrapping_add(u32::from(c.to_ascii_lowercase()))
+            .wrapping_sub(113)
+    })
+}
+
+/// The hash function used for `.debug_names` name tables.
+///
+/// This is Bernstein's DJB hash of the case folded name. Only ASCII
+/// characters are folded.
+#[cfg(feature = "read")]
+pub(crate) fn debug_names_hash(name: &[u8]) -> u32 {
+    name.iter().fold(5381u32, |hash, c| {
+        hash.wrapping_mul(33)
+            .wrapping_add(u32::from(c.to_ascii_lowercase()))
+    })
+}
+
+/// The hash function used for Apple accelerator tables.
+///
+/// This is Bernstein's DJB hash.
+#[cfg(any(feature = "read", feature = "write"))]
+pub(crate) fn apple_hash(name: &[u8]) -> u32 {
+    name.iter().fold(5381u32, |hash, c| {
+        hash.wrapping_mul(33).wrapping_add(u32::from(*c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_id_from_name() {
+        for id in SectionId::all() {
+            assert_eq!(SectionId::from_name(id.name()), Some(*id));
+            for name in [
+                id.dwo_name(),
+                id.zdebug_name(),
+                id.macho_name(),
+                id.xcoff_name(),
+                id.wasm_name(),
+            ]
+            .iter()
+            .flatten()
+            {
+                assert_eq!(SectionId::from_name(name), Some(*id));
+                if let Some(macho_name) = id.macho_name() {
+                    assert!(macho_name.len() <= 16);
+                }
+            }
+        }
+        assert_eq!(
+            SectionId::from_name("__debug_str_offs"),
+            Some(SectionId::DebugStrOffsets)
+        );
+        assert_eq!(
+            SectionId::from_name(".zdebug_info"),
+            Some(SectionId::DebugInfo)
+        );
+        assert_eq!(SectionId::from_name(".text"), None);
+    }
+}
@@ -25,7 +25,7 @@
 #![allow(non_upper_case_globals)]
 #![allow(missing_docs)]
 
-use core::{fmt, ops};
+use core::{fmt, ops, str};
 
 // The `dw!` macro turns this:
 //
@@ -48,6 +48,10 @@ use core::{fmt, ops};
 //         pub fn static_string(&self) -> Option<&'static str> {
 //             ...
 //         }
+//
+//         pub fn name(&self) -> Option<&'static str> {
+//             ...
+//         }
 //     }
 //
 //     impl fmt::Display for DwFoo {
@@ -55,6 +59,14 @@ use core::{fmt, ops};
 //             ...
 //         }
 //     }
+//
+//     impl str::FromStr for DwFoo {
+//         type Err = ParseConstantError;
+//
+//         fn from_str(s: &str) -> Result<Self, Self::Err> {
+//             ...
+//         }
+//     }
 macro_rules! dw {
     ($(#[$meta:meta])* $struct_name:ident($struct_type:ty)
         { $($name:ident = $val:expr),+ $(,)? }
@@ -62,6 +74,7 @@ macro_rules! dw {
     ) => {
         $(#[$meta])*
         #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $struct_name(pub $struct_type);
 
         $(
@@ -80,27 +93,108 @@ macro_rules! dw {
                     _ => return None,
                 })
             }
+
+            /// Return all of the names of this constant.
+            ///
+            /// The first name is the one returned by `static_string`. It is
+            /// followed by the names of any aliases with the same value, such
+            /// as vendor extensions that conflict with other vendors.
+            pub fn names(&self) -> impl Iterator<Item = &'static str> {
+                let value = *self;
+                let aliases: &'static [($struct_name, &'static str)] = &[
+                    $($(($alias_name, stringify!($alias_name)),)+)*
+                ];
+                self.static_string().into_iter().chain(
+                    aliases
+                        .iter()
+                        .filter(move |alias| alias.0 == value)
+                        .map(|alias| alias.1),
+                )
+            }
         }
 
         impl fmt::Display for $struct_name {
+            /// Format the name of this constant.
+            ///
+            /// Values that are not known are formatted using the common prefix
+            /// of the known names, such as `DW_AT_unknown_0x2fff`.
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
                 if let Some(s) = self.static_string() {
                     f.pad(s)
                 } else {
+                    let prefix = unknown_prefix(&[$(stringify!($name)),+]);
                     #[cfg(feature = "read")]
                     {
-                        f.pad(&format!("Unknown {}: {}", stringify!($struct_name), self.0))
+                        f.pad(&format!("{}unknown_{:#x}", prefix, self.0))
                     }
                     #[cfg(not(feature = "read"))]
                     {
-                        write!(f, "Unknown {}: {}", stringify!($struct_name), self.0)
+                        write!(f, "{}unknown_{:#x}", prefix, self.0)
                     }
                 }
             }
         }
+
+        impl str::FromStr for $struct_name {
+            type Err = ParseConstantError;
+
+            /// Parse the name of a constant, such as `DW_AT_name`.
+            ///
+            /// This accepts the names of all constants of this type, including
+            /// aliases, and the fallback names produced by `Display` for values
+            /// that are not known, such as `DW_AT_unknown_0x2fff`.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $(
+                        stringify!($name) => return Ok($name),
+                    )+
+                    $($(
+                        stringify!($alias_name) => return Ok($alias_name),
+                    )+)*
+                    _ => {}
+                }
+                let prefix = unknown_prefix(&[$(stringify!($name)),+]);
+                s.strip_prefix(prefix)
+                    .and_then(|s| s.strip_prefix("unknown_0x"))
+                    .and_then(|s| <$struct_type>::from_str_radix(s, 16).ok())
+                    .map($struct_name)
+                    .ok_or(ParseConstantError)
+            }
+        }
     };
 }
 
+/// An error returned when parsing the name of a constant fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseConstantError;
+
+impl fmt::Display for ParseConstantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "Unknown DWARF constant name")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseConstantError {}
+
+/// Return the common prefix of the names of a constant type, up to and
+/// including the last underscore, such as `DW_AT_`.
+fn unknown_prefix(names: &[&'static str]) -> &'static str {
+    let mut prefix = names[0];
+    for name in &names[1..] {
+        let len = prefix
+            .bytes()
+            .zip(name.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        prefix = &prefix[..len];
+    }
+    match prefix.rfind('_') {
+        Some(index) => &prefix[..=index],
+        None => prefix,
+    }
+}
+
 dw!(
 /// The section type field in a `.dwp` unit index.
 ///
@@ -307,6 +401,7 @@ DwTag(u16) {
     DW_TAG_GNU_call_site = 0x4109,
     DW_TAG_GNU_call_site_parameter = 0x410a,
 
+// Apple extensions.
     DW_TAG_APPLE_property = 0x4200,
 
 // SUN extensions.
@@ -323,6 +418,10 @@ DwTag(u16) {
     DW_TAG_SUN_dtor = 0x420b,
     DW_TAG_SUN_f90_interface = 0x420c,
     DW_TAG_SUN_fortran_vax_structure = 0x420d,
+    DW_TAG_SUN_hi = 0x42ff,
+
+// LLVM project extensions.
+    DW_TAG_LLVM_ptrauth_type = 0x4300,
 
 // ALTIUM extensions.
     DW_TAG_ALTIUM_circ_type = 0x5101,
@@ -330,6 +429,9 @@ DwTag(u16) {
     DW_TAG_ALTIUM_rev_carry_type = 0x5103,
     DW_TAG_ALTIUM_rom = 0x5111,
 
+// LLVM project extensions.
+    DW_TAG_LLVM_annotation = 0x6000,
+
 // Extensions for UPC.
     DW_TAG_upc_shared_type = 0x8765,
     DW_TAG_upc_strict_type = 0x8766,
@@ -507,8 +609,24 @@ DwAt(u16) {
 // fortran and may conflict with other extensions.
     DW_AT_MIPS_assumed_size = 0x2011,
 
-// TODO: HP/CPQ extensions.
-// These conflict with the MIPS extensions.
+// HP extensions.
+// Values that conflict with the MIPS extensions are aliases.
+    DW_AT_HP_raw_data_ptr = 0x2012,
+    DW_AT_HP_pass_by_reference = 0x2013,
+    DW_AT_HP_opt_level = 0x2014,
+    DW_AT_HP_prof_version_id = 0x2015,
+    DW_AT_HP_opt_flags = 0x2016,
+    DW_AT_HP_cold_region_low_pc = 0x2017,
+    DW_AT_HP_cold_region_high_pc = 0x2018,
+    DW_AT_HP_all_variables_modifiable = 0x2019,
+    DW_AT_HP_linkage_name = 0x201a,
+    DW_AT_HP_prof_flags = 0x201b,
+    DW_AT_HP_unit_name = 0x201f,
+    DW_AT_HP_unit_size = 0x2020,
+    DW_AT_HP_widened_byte_size = 0x2021,
+    DW_AT_HP_definition_points = 0x2022,
+    DW_AT_HP_default_location = 0x2023,
+    DW_AT_HP_is_result_param = 0x2029,
 
     DW_AT_INTEL_other_endian = 0x2026,
 
@@ -551,9 +669,6 @@ DwAt(u16) {
     DW_AT_GNU_locviews = 0x2137,
     DW_AT_GNU_entry_view = 0x2138,
 
-// Conflict with Sun.
-// DW_AT_VMS_rtnbeg_pd_address = 0x2201,
-
 // Sun extensions.
     DW_AT_SUN_template = 0x2201,
     DW_AT_SUN_alignment = 0x2202,
@@ -640,6 +755,15 @@ DwAt(u16) {
     DW_AT_LLVM_include_path = 0x3e00,
     DW_AT_LLVM_config_macros = 0x3e01,
     DW_AT_LLVM_isysroot = 0x3e02,
+    DW_AT_LLVM_tag_offset = 0x3e03,
+    DW_AT_LLVM_ptrauth_key = 0x3e04,
+    DW_AT_LLVM_ptrauth_address_discriminated = 0x3e05,
+    DW_AT_LLVM_ptrauth_extra_discriminator = 0x3e06,
+    DW_AT_LLVM_apinotes = 0x3e07,
+    DW_AT_LLVM_ptrauth_isa_pointer = 0x3e08,
+    DW_AT_LLVM_ptrauth_authenticates_null_values = 0x3e09,
+    DW_AT_LLVM_ptrauth_authentication_mode = 0x3e0a,
+    DW_AT_LLVM_num_extra_inhabitants = 0x3e0b,
 
 // Apple extensions.
     DW_AT_APPLE_optimized = 0x3fe1,
@@ -654,7 +778,32 @@ DwAt(u16) {
     DW_AT_APPLE_property_setter = 0x3fea,
     DW_AT_APPLE_property_attribute = 0x3feb,
     DW_AT_APPLE_objc_complete_type = 0x3fec,
-    DW_AT_APPLE_property = 0x3fed
+    DW_AT_APPLE_property = 0x3fed,
+    DW_AT_APPLE_objc_direct = 0x3fee,
+    DW_AT_APPLE_sdk = 0x3fef,
+    DW_AT_APPLE_origin = 0x3ff0,
+},
+aliases {
+// HP extensions that conflict with the MIPS extensions.
+    DW_AT_HP_block_index = 0x2000,
+    DW_AT_HP_unmodifiable = 0x2001,
+    DW_AT_HP_prologue = 0x2005,
+    DW_AT_HP_epilogue = 0x2008,
+    DW_AT_HP_actuals_stmt_list = 0x2010,
+    DW_AT_HP_proc_per_section = 0x2011,
+
+// Compaq extensions that conflict with the MIPS extensions.
+    DW_AT_CPQ_discontig_ranges = 0x2001,
+    DW_AT_CPQ_semantic_events = 0x2002,
+    DW_AT_CPQ_split_lifetimes_var = 0x2003,
+    DW_AT_CPQ_split_lifetimes_rtn = 0x2004,
+    DW_AT_CPQ_prologue_length = 0x2005,
+
+// VMS extensions that conflict with the Sun extensions.
+    DW_AT_VMS_rtnbeg_pd_address = 0x2201,
+
+// Renamed LLVM extensions.
+    DW_AT_LLVM_sysroot = 0x3e02,
 });
 
 dw!(
@@ -718,7 +867,10 @@ DwForm(u16) {
 
 // Alternate debug sections proposal (output of "dwz" tool).
     DW_FORM_GNU_ref_alt = 0x1f20,
-    DW_FORM_GNU_strp_alt = 0x1f21
+    DW_FORM_GNU_strp_alt = 0x1f21,
+
+// LLVM project extensions.
+    DW_FORM_LLVM_addrx_offset = 0x2001,
 });
 
 dw!(
@@ -912,11 +1064,18 @@ impl DwLang {
             | DW_LANG_Dylan
             | DW_LANG_C_plus_plus_14
             | DW_LANG_RenderScript
-            | DW_LANG_BLISS => Some(0),
+            | DW_LANG_BLISS
+            | DW_LANG_Kotlin
+            | DW_LANG_Zig
+            | DW_LANG_Crystal
+            | DW_LANG_C_plus_plus_17
+            | DW_LANG_C_plus_plus_20
+            | DW_LANG_C17 => Some(0),
             DW_LANG_Ada83 | DW_LANG_Cobol74 | DW_LANG_Cobol85 | DW_LANG_Fortran77
             | DW_LANG_Fortran90 | DW_LANG_Pascal83 | DW_LANG_Modula2 | DW_LANG_Ada95
             | DW_LANG_Fortran95 | DW_LANG_PLI | DW_LANG_Modula3 | DW_LANG_Julia
-            | DW_LANG_Fortran03 | DW_LANG_Fortran08 => Some(1),
+            | DW_LANG_Fortran03 | DW_LANG_Fortran08 | DW_LANG_Fortran18 | DW_LANG_Ada2005
+            | DW_LANG_Ada2012 => Some(1),
             _ => None,
         }
     }
@@ -999,6 +1158,20 @@ DwIdx(u16) {
     DW_IDX_hi_user = 0x3fff,
 });
 
+dw!(
+/// Atom types for the Apple accelerator tables.
+///
+/// These are used in the `.apple_names`, `.apple_types`, `.apple_namespac`,
+/// and `.apple_objc` sections.
+DwAtom(u16) {
+    DW_ATOM_null = 0,
+    DW_ATOM_die_offset = 1,
+    DW_ATOM_cu_offset = 2,
+    DW_ATOM_die_tag = 3,
+    DW_ATOM_type_flags = 4,
+    DW_ATOM_qual_name_hash = 5,
+});
+
 dw!(
 /// The encodings of the constants used in the `DW_AT_defaulted` attribute.
 ///
@@ -1056,6 +1229,18 @@ DwLnct(u16) {
     DW_LNCT_hi_user = 0x3fff,
 });
 
+dw!(
+/// The encodings for `.debug_macinfo` entry types in DWARF version 4 and earlier.
+///
+/// See Section 7.22 of the DWARF 4 standard, Figure 39.
+DwMacinfo(u8) {
+    DW_MACINFO_define = 0x01,
+    DW_MACINFO_undef = 0x02,
+    DW_MACINFO_start_file = 0x03,
+    DW_MACINFO_end_file = 0x04,
+    DW_MACINFO_vendor_ext = 0xff,
+});
+
 dw!(
 /// The encodings for macro information entry types.
 ///
@@ -1402,6 +1587,91 @@ impl DwEhPe {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_dw_names() {
+        assert_eq!(DW_AT_name.names().collect::<Vec<_>>(), ["DW_AT_name"]);
+        assert_eq!(
+            DW_AT_LLVM_apinotes.static_string(),
+            Some("DW_AT_LLVM_apinotes")
+        );
+        assert_eq!(
+            DW_AT_HP_opt_level.static_string(),
+            Some("DW_AT_HP_opt_level")
+        );
+        assert_eq!(
+            DW_TAG_LLVM_annotation.static_string(),
+            Some("DW_TAG_LLVM_annotation")
+        );
+        assert_eq!(
+            DW_FORM_LLVM_addrx_offset.static_string(),
+            Some("DW_FORM_LLVM_addrx_offset")
+        );
+        // Conflicting vendor values use the primary name first.
+        assert_eq!(
+            DW_AT_HP_unmodifiable.static_string(),
+            Some("DW_AT_MIPS_fde")
+        );
+        assert_eq!(
+            DW_AT_HP_unmodifiable.names().collect::<Vec<_>>(),
+            [
+                "DW_AT_MIPS_fde",
+                "DW_AT_HP_unmodifiable",
+                "DW_AT_CPQ_discontig_ranges"
+            ]
+        );
+        assert_eq!(
+            DW_AT_LLVM_sysroot.names().collect::<Vec<_>>(),
+            ["DW_AT_LLVM_isysroot", "DW_AT_LLVM_sysroot"]
+        );
+        assert_eq!(DwAt(0x3ffe).names().next(), None);
+        assert_eq!(format!("{}", DwAt(0x3ffe)), "DW_AT_unknown_0x3ffe");
+    }
+
+    #[test]
+    fn test_dw_display() {
+        assert_eq!(format!("{}", DW_TAG_member), "DW_TAG_member");
+        assert_eq!(format!("{:>14}", DW_AT_name), "    DW_AT_name");
+        assert_eq!(format!("{}", DW_AT_HP_unmodifiable), "DW_AT_MIPS_fde");
+        assert_eq!(format!("{}", DwAt(0x2fff)), "DW_AT_unknown_0x2fff");
+        assert_eq!(format!("{}", DwTag(0x5000)), "DW_TAG_unknown_0x5000");
+        assert_eq!(format!("{}", DwForm(0x100)), "DW_FORM_unknown_0x100");
+        assert_eq!(format!("{}", DwOp(0xe5)), "DW_OP_unknown_0xe5");
+        assert_eq!(format!("{}", DwCfa(0x3e)), "DW_CFA_unknown_0x3e");
+        assert_eq!(format!("{}", DwSectV2(0x20)), "DW_SECT_V2_unknown_0x20");
+        assert_eq!(format!("{}", DwAddr(1)), "DW_ADDR_unknown_0x1");
+        assert_eq!(format!("{}", DwEhPe(0x05)), "DW_EH_PE_unknown_0x5");
+    }
+
+    #[test]
+    fn test_dw_from_str() {
+        assert_eq!("DW_TAG_member".parse(), Ok(DW_TAG_member));
+        assert_eq!("DW_AT_name".parse(), Ok(DW_AT_name));
+        assert_eq!("DW_OP_lit0".parse(), Ok(DW_OP_lit0));
+        assert_eq!("DW_SECT_V2_TYPES".parse(), Ok(DW_SECT_V2_TYPES));
+        // Aliases.
+        assert_eq!("DW_AT_HP_unmodifiable".parse(), Ok(DW_AT_HP_unmodifiable));
+        assert_eq!("DW_AT_MIPS_fde".parse(), Ok(DW_AT_HP_unmodifiable));
+        // Unknown values round trip through `Display`.
+        assert_eq!("DW_AT_unknown_0x2fff".parse(), Ok(DwAt(0x2fff)));
+        for value in [DwAt(0x3ffe), DW_AT_name] {
+            assert_eq!(format!("{}", value).parse(), Ok(value));
+        }
+
+        assert_eq!("DW_AT_member".parse::<DwAt>(), Err(ParseConstantError));
+        assert_eq!("DW_TAG_name".parse::<DwTag>(), Err(ParseConstantError));
+        assert_eq!("dw_at_name".parse::<DwAt>(), Err(ParseConstantError));
+        assert_eq!("DW_AT_unknown_0x".parse::<DwAt>(), Err(ParseConstantError));
+        assert_eq!(
+            "DW_AT_unknown_0x10000".parse::<DwAt>(),
+            Err(ParseConstantError)
+        );
+        assert_eq!(
+            "DW_TAG_unknown_0x2".parse::<DwAt>(),
+            Err(ParseConstantError)
+        );
+    }
 
     #[test]
     fn test_dw_eh_pe_format() {
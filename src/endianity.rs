@@ -169,10 +169,29 @@ pub trait Endianity: Debug + Default + Clone + Copy + PartialEq + Eq {
         };
         buf[..8].copy_from_slice(&bytes);
     }
+
+    /// Writes an unsigned n-bytes integer `n` to `buf`, where n is the
+    /// length of `buf`.
+    ///
+    /// Any bits of `n` that do not fit in `buf` are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `buf.len() > 8`.
+    #[inline]
+    fn write_uint(self, buf: &mut [u8], n: u64) {
+        let len = buf.len();
+        if self.is_big_endian() {
+            buf.copy_from_slice(&n.to_be_bytes()[8 - len..]);
+        } else {
+            buf.copy_from_slice(&n.to_le_bytes()[..len]);
+        }
+    }
 }
 
 /// Byte order that is selectable at runtime.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RunTimeEndian {
     /// Little endian byte order.
     Little,
@@ -203,6 +222,7 @@ impl Endianity for RunTimeEndian {
 
 /// Little endian byte order.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LittleEndian;
 
 impl Default for LittleEndian {
@@ -221,6 +241,7 @@ impl Endianity for LittleEndian {
 
 /// Big endian byte order.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BigEndian;
 
 impl Default for BigEndian {
@@ -18,6 +18,20 @@
 //!
 //! * `write`: Enabled by default. Enables the `write` module. Always uses
 //! the `std` library.
+//!
+//! * `capi`: Enables the `capi` module, which provides a C API for unwinding
+//! using the `.eh_frame` and `.eh_frame_hdr` sections.
+//!
+//! * `json`: Enables functions for writing DIE trees and line tables as JSON.
+//!
+//! * `serde`: Implements `Serialize` and `Deserialize` for plain data types,
+//! such as constants, offsets, attribute values, line rows, and unwind table rows.
+//!
+//! * `rayon`: Enables `write::Dwarf::write_parallel`, which uses `rayon` to
+//! write units in parallel.
+//!
+//! * `testing`: Enables the `testing` module, which provides builders for
+//! constructing DWARF sections in tests.
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 // Selectively enable rust 2018 warnings
@@ -75,5 +89,11 @@ pub use crate::read::*;
 #[cfg(feature = "write")]
 pub mod write;
 
+#[cfg(all(feature = "read", feature = "write", any(test, feature = "testing")))]
+pub mod testing;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
 #[cfg(test)]
 mod test_util;
@@ -21,6 +21,7 @@ use crate::read::{
 #[derive(Debug, Default, Clone, Copy)]
 pub struct DebugAbbrev<R> {
     debug_abbrev_section: R,
+    unknown_form_size: Option<fn(constants::DwForm) -> Option<u8>>,
 }
 
 impl<'input, Endian> DebugAbbrev<EndianSlice<'input, Endian>>
@@ -57,7 +58,27 @@ impl<R: Reader> DebugAbbrev<R> {
     ) -> Result<Abbreviations> {
         let input = &mut self.debug_abbrev_section.clone();
         input.skip(debug_abbrev_offset.0)?;
-        Abbreviations::parse(input)
+        Abbreviations::parse(input, self.unknown_form_size)
+    }
+}
+
+impl<R> DebugAbbrev<R> {
+    /// Set a function that returns the size in bytes of attribute values that
+    /// use a form not known to gimli.
+    ///
+    /// By default, an entry that uses an unknown form cannot be parsed. If this
+    /// function returns a size for a form, then attributes using that form are
+    /// parsed as `AttributeValue::Unknown` instead, and the remaining attributes
+    /// and entries of the unit can still be parsed. The returned size is ignored
+    /// for forms that gimli knows how to parse.
+    ///
+    /// This only applies to forms specified in abbreviations that are parsed
+    /// after this is set.
+    pub fn set_unknown_form_size(
+        &mut self,
+        unknown_form_size: fn(constants::DwForm) -> Option<u8>,
+    ) {
+        self.unknown_form_size = Some(unknown_form_size);
     }
 }
 
@@ -71,7 +92,10 @@ impl<T> DebugAbbrev<T> {
     where
         F: FnMut(&'a T) -> R,
     {
-        borrow(&self.debug_abbrev_section).into()
+        DebugAbbrev {
+            debug_abbrev_section: borrow(&self.debug_abbrev_section),
+            unknown_form_size: self.unknown_form_size,
+        }
     }
 }
 
@@ -89,6 +113,7 @@ impl<R> From<R> for DebugAbbrev<R> {
     fn from(debug_abbrev_section: R) -> Self {
         DebugAbbrev {
             debug_abbrev_section,
+            unknown_form_size: None,
         }
     }
 }
@@ -249,6 +274,11 @@ impl Abbreviations {
         }
     }
 
+    /// Iterate over the abbreviations in the set.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Abbreviation> {
+        self.vec.iter().chain(self.map.values())
+    }
+
     /// Get the abbreviation associated with the given code.
     #[inline]
     pub fn get(&self, code: u64) -> Option<&Abbreviation> {
@@ -263,10 +293,13 @@ impl Abbreviations {
     }
 
     /// Parse a series of abbreviations, terminated by a null abbreviation.
-    fn parse<R: Reader>(input: &mut R) -> Result<Abbreviations> {
+    fn parse<R: Reader>(
+        input: &mut R,
+        unknown_form_size: Option<fn(constants::DwForm) -> Option<u8>>,
+    ) -> Result<Abbreviations> {
         let mut abbrevs = Abbreviations::empty();
 
-        while let Some(abbrev) = Abbreviation::parse(input)? {
+        while let Some(abbrev) = Abbreviation::parse(input, unknown_form_size)? {
             if abbrevs.insert(abbrev).is_err() {
                 return Err(Error::DuplicateAbbreviationCode);
             }
@@ -354,10 +387,13 @@ impl Abbreviation {
 
     /// Parse a series of attribute specifications, terminated by a null attribute
     /// specification.
-    fn parse_attributes<R: Reader>(input: &mut R) -> Result<Attributes> {
+    fn parse_attributes<R: Reader>(
+        input: &mut R,
+        unknown_form_size: Option<fn(constants::DwForm) -> Option<u8>>,
+    ) -> Result<Attributes> {
         let mut attrs = Attributes::new();
 
-        while let Some(attr) = AttributeSpecification::parse(input)? {
+        while let Some(attr) = AttributeSpecification::parse(input, unknown_form_size)? {
             attrs.push(attr);
         }
 
@@ -366,7 +402,10 @@ impl Abbreviation {
 
     /// Parse an abbreviation. Return `None` for the null abbreviation, `Some`
     /// for an actual abbreviation.
-    fn parse<R: Reader>(input: &mut R) -> Result<Option<Abbreviation>> {
+    fn parse<R: Reader>(
+        input: &mut R,
+        unknown_form_size: Option<fn(constants::DwForm) -> Option<u8>>,
+    ) -> Result<Option<Abbreviation>> {
         let code = input.read_uleb128()?;
         if code == 0 {
             return Ok(None);
@@ -374,7 +413,7 @@ impl Abbreviation {
 
         let tag = Self::parse_tag(input)?;
         let has_children = Self::parse_has_children(input)?;
-        let attributes = Self::parse_attributes(input)?;
+        let attributes = Self::parse_attributes(input, unknown_form_size)?;
         let abbrev = Abbreviation::new(code, tag, has_children, attributes);
         Ok(Some(abbrev))
     }
@@ -473,6 +512,7 @@ impl From<Vec<AttributeSpecification>> for Attributes {
 pub struct AttributeSpecification {
     name: constants::DwAt,
     form: constants::DwForm,
+    unknown_form_size: Option<u8>,
     implicit_const_value: i64,
 }
 
@@ -492,10 +532,21 @@ impl AttributeSpecification {
         AttributeSpecification {
             name,
             form,
+            unknown_form_size: None,
             implicit_const_value: implicit_const_value.unwrap_or(0),
         }
     }
 
+    /// Set the size in bytes of values for this attribute if its form is not
+    /// known to gimli.
+    ///
+    /// See `DebugAbbrev::set_unknown_form_size`.
+    #[inline]
+    pub fn with_unknown_form_size(mut self, size: u8) -> AttributeSpecification {
+        self.unknown_form_size = Some(size);
+        self
+    }
+
     /// Get the attribute's name.
     #[inline]
     pub fn name(&self) -> constants::DwAt {
@@ -518,6 +569,13 @@ impl AttributeSpecification {
         }
     }
 
+    /// Get the size in bytes of values for this attribute if its form is not
+    /// known to gimli.
+    #[inline]
+    pub fn unknown_form_size(&self) -> Option<u8> {
+        self.unknown_form_size
+    }
+
     /// Return the size of the attribute, in bytes.
     ///
     /// Note that because some attributes are variably sized, the size cannot
@@ -538,7 +596,10 @@ impl AttributeSpecification {
 
     /// Parse an attribute specification. Returns `None` for the null attribute
     /// specification, `Some` for an actual attribute specification.
-    fn parse<R: Reader>(input: &mut R) -> Result<Option<AttributeSpecification>> {
+    fn parse<R: Reader>(
+        input: &mut R,
+        unknown_form_size: Option<fn(constants::DwForm) -> Option<u8>>,
+    ) -> Result<Option<AttributeSpecification>> {
         let name = input.read_uleb128_u16()?;
         if name == 0 {
             // Parse the null attribute specification.
@@ -557,7 +618,10 @@ impl AttributeSpecification {
         } else {
             None
         };
-        let spec = AttributeSpecification::new(name, form, implicit_const_value);
+        let mut spec = AttributeSpecification::new(name, form, implicit_const_value);
+        if let Some(size) = unknown_form_size.and_then(|f| f(form)) {
+            spec = spec.with_unknown_form_size(size);
+        }
         Ok(Some(spec))
     }
 }
@@ -827,6 +891,36 @@ pub(crate) mod tests {
         assert_abbrev(&abbrevs, wrap_code);
     }
 
+    #[test]
+    fn test_debug_abbrev_unknown_form_size() {
+        let unknown_form = constants::DwForm(0x1f80);
+        #[rustfmt::skip]
+        let buf = Section::new()
+            .abbrev(1, constants::DW_TAG_compile_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_name, unknown_form)
+                .abbrev_attr(constants::DW_AT_language, constants::DW_FORM_udata)
+                .abbrev_attr_null()
+            .abbrev_null()
+            .get_contents()
+            .unwrap();
+
+        let mut debug_abbrev = DebugAbbrev::new(&buf, LittleEndian);
+        let abbrevs = debug_abbrev.abbreviations(DebugAbbrevOffset(0)).unwrap();
+        let attrs = abbrevs.get(1).unwrap().attributes();
+        assert_eq!(attrs[0].unknown_form_size(), None);
+        assert_eq!(attrs[1].unknown_form_size(), None);
+
+        debug_abbrev.set_unknown_form_size(|form| match form {
+            constants::DW_FORM_udata => Some(1),
+            constants::DwForm(0x1f80) => Some(3),
+            _ => None,
+        });
+        let abbrevs = debug_abbrev.abbreviations(DebugAbbrevOffset(0)).unwrap();
+        let attrs = abbrevs.get(1).unwrap().attributes();
+        assert_eq!(attrs[0].form(), unknown_form);
+        assert_eq!(attrs[0].unknown_form_size(), Some(3));
+    }
+
     #[test]
     fn test_parse_abbreviations_ok() {
         let expected_rest = [1, 2, 3, 4];
@@ -876,7 +970,7 @@ pub(crate) mod tests {
             .into(),
         );
 
-        let abbrevs = Abbreviations::parse(rest).expect("Should parse abbreviations");
+        let abbrevs = Abbreviations::parse(rest, None).expect("Should parse abbreviations");
         assert_eq!(abbrevs.get(1), Some(&abbrev1));
         assert_eq!(abbrevs.get(2), Some(&abbrev2));
         assert_eq!(*rest, EndianSlice::new(&expected_rest, LittleEndian));
@@ -900,7 +994,7 @@ pub(crate) mod tests {
             .unwrap();
         let buf = &mut EndianSlice::new(&buf, LittleEndian);
 
-        match Abbreviations::parse(buf) {
+        match Abbreviations::parse(buf, None) {
             Err(Error::DuplicateAbbreviationCode) => {}
             otherwise => panic!("Unexpected result: {:?}", otherwise),
         };
@@ -963,7 +1057,7 @@ pub(crate) mod tests {
             .into(),
         ));
 
-        let abbrev = Abbreviation::parse(rest).expect("Should parse abbreviation");
+        let abbrev = Abbreviation::parse(rest, None).expect("Should parse abbreviation");
         assert_eq!(abbrev, expect);
         assert_eq!(*rest, EndianSlice::new(&expected_rest, LittleEndian));
     }
@@ -992,7 +1086,7 @@ pub(crate) mod tests {
             .into(),
         ));
 
-        let abbrev = Abbreviation::parse(rest).expect("Should parse abbreviation");
+        let abbrev = Abbreviation::parse(rest, None).expect("Should parse abbreviation");
         assert_eq!(abbrev, expect);
         assert_eq!(*rest, EndianSlice::new(&expected_rest, LittleEndian));
     }
@@ -1006,7 +1100,7 @@ pub(crate) mod tests {
             .unwrap();
         let buf = &mut EndianSlice::new(&buf, LittleEndian);
 
-        match Abbreviation::parse(buf) {
+        match Abbreviation::parse(buf, None) {
             Err(Error::UnexpectedEof(_)) => {}
             otherwise => panic!("Unexpected result: {:?}", otherwise),
         }
@@ -1022,7 +1116,7 @@ pub(crate) mod tests {
             .unwrap();
         let rest = &mut EndianSlice::new(&buf, LittleEndian);
 
-        let abbrev = Abbreviation::parse(rest).expect("Should parse null abbreviation");
+        let abbrev = Abbreviation::parse(rest, None).expect("Should parse null abbreviation");
         assert!(abbrev.is_none());
         assert_eq!(*rest, EndianSlice::new(&expected_rest, LittleEndian));
     }
@@ -1050,8 +1144,8 @@ pub(crate) mod tests {
     fn test_parse_null_attribute_specification_ok() {
         let buf = [0x00, 0x00, 0x01];
         let rest = &mut EndianSlice::new(&buf, LittleEndian);
-        let attr =
-            AttributeSpecification::parse(rest).expect("Should parse null attribute specification");
+        let attr = AttributeSpecification::parse(rest, None)
+            .expect("Should parse null attribute specification");
         assert!(attr.is_none());
         assert_eq!(*rest, EndianSlice::new(&buf[2..], LittleEndian));
     }
@@ -1060,7 +1154,7 @@ pub(crate) mod tests {
     fn test_parse_attribute_specifications_name_zero() {
         let buf = [0x00, 0x01, 0x00, 0x00];
         let buf = &mut EndianSlice::new(&buf, LittleEndian);
-        match AttributeSpecification::parse(buf) {
+        match AttributeSpecification::parse(buf, None) {
             Err(Error::ExpectedZero) => {}
             otherwise => panic!("Unexpected result: {:?}", otherwise),
         };
@@ -1070,7 +1164,7 @@ pub(crate) mod tests {
     fn test_parse_attribute_specifications_form_zero() {
         let buf = [0x01, 0x00, 0x00, 0x00];
         let buf = &mut EndianSlice::new(&buf, LittleEndian);
-        match AttributeSpecification::parse(buf) {
+        match AttributeSpecification::parse(buf, None) {
             Err(Error::AttributeFormZero) => {}
             otherwise => panic!("Unexpected result: {:?}", otherwise),
         };
@@ -0,0 +1,364 @@
+use alloc::collections::btree_set::BTreeSet;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::common::DebugInfoOffset;
+use crate::constants;
+use crate::read::aranges::{find_range, sort_ranges, RangeEntry};
+use crate::read::{
+    DebugAranges, Dwarf, Error, Range, Reader, ReaderOffset, Result, Unit, UnitOffset,
+};
+
+/// The unit and function that contain an address.
+///
+/// Returned by `AddressMap::find`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressMapEntry<T = usize> {
+    /// The offset of the header of the unit that contains the address.
+    pub unit_offset: DebugInfoOffset<T>,
+    /// The offset within the unit of the subprogram entry that contains the
+    /// address, if any.
+    pub function: Option<UnitOffset<T>>,
+}
+
+/// A map from addresses to the units and functions that contain them.
+///
+/// The map combines the unit ranges in `.debug_aranges`, the ranges of the
+/// units in `.debug_info`, and the ranges of the subprogram entries within the
+/// units. It is built once using `Dwarf::address_map` or `AddressMapBuilder`,
+/// and can then be used to look up many addresses without parsing the sections
+/// again.
+#[derive(Debug, Clone, Default)]
+pub struct AddressMap<T = usize> {
+    units: Vec<RangeEntry<AddressMapEntry<T>>>,
+    functions: Vec<RangeEntry<AddressMapEntry<T>>>,
+}
+
+impl<T: ReaderOffset> AddressMap<T> {
+    /// Find the unit and function containing the given address.
+    ///
+    /// If multiple ranges contain the address, then the range with the
+    /// highest beginning address is used.
+    ///
+    /// Returns `None` if no unit contains the address.
+    pub fn find(&self, address: u64) -> Option<AddressMapEntry<T>> {
+        let function = find_range(&self.functions, address).map(|entry| entry.value);
+        let unit_offset = match find_range(&self.units, address) {
+            Some(unit) => unit.value.unit_offset,
+            None => function?.unit_offset,
+        };
+        let function = function
+            .filter(|function| function.unit_offset == unit_offset)
+            .and_then(|function| function.function);
+        Some(AddressMapEntry {
+            unit_offset,
+            function,
+        })
+    }
+
+    /// Find the offset of the unit containing the given address.
+    pub fn find_unit(&self, address: u64) -> Option<DebugInfoOffset<T>> {
+        self.find(address).map(|entry| entry.unit_offset)
+    }
+
+    /// Return the number of unit ranges in the map.
+    pub fn unit_ranges_len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Return the number of function ranges in the map.
+    pub fn function_ranges_len(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Return true if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty() && self.functions.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the map.
+    pub fn allocated_bytes(&self) -> usize {
+        (self.units.capacity() + self.functions.capacity())
+            * mem::size_of::<RangeEntry<AddressMapEntry<T>>>()
+    }
+}
+
+/// A builder for an `AddressMap`.
+///
+/// `Dwarf::address_map` is sufficient for most uses. This builder can be used
+/// to add ranges from other sources, such as when `.debug_aranges` is
+/// incomplete and the ranges must be found by scanning the entries of
+/// particular units.
+#[derive(Debug, Clone, Default)]
+pub struct AddressMapBuilder<T = usize> {
+    map: AddressMap<T>,
+}
+
+impl<T: ReaderOffset> AddressMapBuilder<T> {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        AddressMapBuilder {
+            map: AddressMap {
+                units: Vec::new(),
+                functions: Vec::new(),
+            },
+        }
+    }
+
+    /// Add an address range for a unit.
+    ///
+    /// Empty ranges are ignored.
+    pub fn add_unit_range(&mut self, range: Range, unit_offset: DebugInfoOffset<T>) {
+        if range.begin < range.end {
+            self.map.units.push(RangeEntry::new(
+                range,
+                AddressMapEntry {
+                    unit_offset,
+                    function: None,
+                },
+            ));
+        }
+    }
+
+    /// Add an address range for a subprogram entry within a unit.
+    ///
+    /// Empty ranges are ignored.
+    pub fn add_function_range(
+        &mut self,
+        range: Range,
+        unit_offset: DebugInfoOffset<T>,
+        function: UnitOffset<T>,
+    ) {
+        if range.begin < range.end {
+            self.map.functions.push(RangeEntry::new(
+                range,
+                AddressMapEntry {
+                    unit_offset,
+                    function: Some(function),
+                },
+            ));
+        }
+    }
+
+    /// Add the unit ranges in a `.debug_aranges` section.
+    ///
+    /// Returns the offsets of the units that have ranges in the section.
+    pub fn add_aranges<R>(
+        &mut self,
+        debug_aranges: &DebugAranges<R>,
+    ) -> Result<BTreeSet<DebugInfoOffset<T>>>
+    where
+        R: Reader<Offset = T>,
+    {
+        let mut units = BTreeSet::new();
+        let mut headers = debug_aranges.headers();
+        while let Some(header) = headers.next()? {
+            let unit_offset = header.debug_info_offset();
+            let mut aranges = header.entries();
+            while let Some(arange) = aranges.next()? {
+                self.add_unit_range(arange.range(), unit_offset);
+                units.insert(unit_offset);
+            }
+        }
+        Ok(units)
+    }
+
+    /// Add the ranges of the subprogram entries in a unit.
+    ///
+    /// If `unit_ranges` is true, then the ranges of the unit's root entry are
+    /// also added. If the root entry has no ranges, then the ranges of the
+    /// subprograms are used for the unit instead. This can be used for units
+    /// that are missing from `.debug_aranges`.
+    pub fn add_unit<R>(&mut self, dwarf: &Dwarf<R>, unit: &Unit<R>, unit_ranges: bool) -> Result<()>
+    where
+        R: Reader<Offset = T>,
+    {
+        let unit_offset = unit
+            .header
+            .offset()
+            .as_debug_info_offset()
+            .ok_or(Error::UnsupportedOffset)?;
+        let mut root_has_ranges = false;
+        let mut first = true;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            let is_root = mem::replace(&mut first, false);
+            if is_root && !unit_ranges {
+                continue;
+            }
+            if !is_root && entry.tag() != constants::DW_TAG_subprogram {
+                continue;
+            }
+            let mut ranges = dwarf.die_ranges(unit, entry)?;
+            while let Some(range) = ranges.next()? {
+                if is_root {
+                    root_has_ranges = true;
+                    self.add_unit_range(range, unit_offset);
+                } else {
+                    if unit_ranges && !root_has_ranges {
+                        self.add_unit_range(range, unit_offset);
+                    }
+                    self.add_function_range(range, unit_offset, entry.offset());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the address map.
+    pub fn build(mut self) -> AddressMap<T> {
+        sort_ranges(&mut self.map.units);
+        sort_ranges(&mut self.map.functions);
+        self.map
+    }
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Build a map from addresses to the units and functions that contain them.
+    ///
+    /// This uses `.debug_aranges` for the unit ranges if it is present.
+    /// The ranges of units that are not in `.debug_aranges` are found from
+    /// their entries in `.debug_info`. The ranges of subprogram entries are
+    /// always found from `.debug_info`.
+    pub fn address_map(&self) -> Result<AddressMap<R::Offset>> {
+        let mut builder = AddressMapBuilder::new();
+        let aranges_units = builder.add_aranges(&self.debug_aranges)?;
+        let mut units = self.units();
+        while let Some(header) = units.next()? {
+            let unit_offset = header
+                .offset()
+                .as_debug_info_offset()
+                .ok_or(Error::UnsupportedOffset)?;
+            let unit = self.unit(header)?;
+            builder.add_unit(self, &unit, !aranges_units.contains(&unit_offset))?;
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::{DebugAbbrev, DebugInfo};
+    use crate::write::{self, Address, AttributeValue, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_address_map() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = write::UnitTable::default();
+        let set_range = |unit: &mut write::Unit, id, begin, end| {
+            let entry = unit.get_mut(id);
+            entry.set(
+                constants::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(begin)),
+            );
+            entry.set(constants::DW_AT_high_pc, AttributeValue::Udata(end - begin));
+        };
+
+        let unit_id1 = units.add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id1);
+        let root1 = unit.root();
+        set_range(unit, root1, 0x1000, 0x2000);
+        let namespace = unit.add(root1, constants::DW_TAG_namespace);
+        let subprogram1 = unit.add(namespace, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram1, 0x1000, 0x1100);
+        let inlined = unit.add(subprogram1, constants::DW_TAG_inlined_subroutine);
+        set_range(unit, inlined, 0x1020, 0x1030);
+        let subprogram2 = unit.add(root1, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram2, 0x1100, 0x1200);
+
+        // A unit without ranges on the root entry, and not in `.debug_aranges`.
+        let unit_id2 = units.add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id2);
+        let root2 = unit.root();
+        let subprogram3 = unit.add(root2, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram3, 0x3000, 0x3100);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let offsets = units
+            .write(
+                &mut sections,
+                &write::DebugLineStrOffsets::none(),
+                &write::DebugStrOffsets::none(),
+            )
+            .unwrap();
+        let unit1 = offsets.unit(unit_id1);
+        let unit2 = offsets.unit(unit_id2);
+        let entry =
+            |unit_id, entry| UnitOffset(offsets.entry(unit_id, entry).0 - offsets.unit(unit_id).0);
+        let subprogram1 = entry(unit_id1, subprogram1);
+        let subprogram2 = entry(unit_id1, subprogram2);
+        let subprogram3 = entry(unit_id2, subprogram3);
+
+        // `.debug_aranges` for the first unit only, with an extra range that
+        // isn't in `.debug_info`.
+        let mut debug_aranges = Vec::new();
+        debug_aranges.extend_from_slice(&60u32.to_le_bytes());
+        debug_aranges.extend_from_slice(&2u16.to_le_bytes());
+        debug_aranges.extend_from_slice(&(unit1.0 as u32).to_le_bytes());
+        debug_aranges.extend_from_slice(&[8, 0, 0, 0, 0, 0]);
+        for value in [0x1000u64, 0x1000, 0x5000, 0x100, 0, 0] {
+            debug_aranges.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            debug_aranges: DebugAranges::new(&debug_aranges, LittleEndian),
+            ..Default::default()
+        };
+        let map = dwarf.address_map().unwrap();
+        assert_eq!(map.unit_ranges_len(), 3);
+        assert_eq!(map.function_ranges_len(), 3);
+        assert!(map.allocated_bytes() > 0);
+        let found = |unit_offset, function| {
+            Some(AddressMapEntry {
+                unit_offset,
+                function,
+            })
+        };
+        for (address, expect) in [
+            (0xfff, None),
+            (0x1000, found(unit1, Some(subprogram1))),
+            (0x1020, found(unit1, Some(subprogram1))),
+            (0x1100, found(unit1, Some(subprogram2))),
+            (0x1200, found(unit1, None)),
+            (0x2000, None),
+            (0x3000, found(unit2, Some(subprogram3))),
+            (0x3100, None),
+            (0x5000, found(unit1, None)),
+            (0x5100, None),
+        ] {
+            assert_eq!(map.find(address), expect, "{:#x}", address);
+        }
+
+        // Compensate for a missing range by adding it explicitly.
+        let mut builder = AddressMapBuilder::new();
+        builder.add_aranges(&dwarf.debug_aranges).unwrap();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next().unwrap() {
+            let unit = dwarf.unit(header).unwrap();
+            builder.add_unit(&dwarf, &unit, false).unwrap();
+        }
+        let map = builder.clone().build();
+        assert_eq!(map.find(0x3000), found(unit2, Some(subprogram3)));
+        builder.add_unit_range(
+            Range {
+                begin: 0x4000,
+                end: 0x4010,
+            },
+            unit2,
+        );
+        let map = builder.build();
+        assert_eq!(map.find_unit(0x4000), Some(unit2));
+        assert_eq!(map.find_unit(0x4010), None);
+        assert!(AddressMap::<usize>::default().is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+use alloc::vec::Vec;
+
+use crate::common::{apple_hash, DebugInfoOffset, DebugStrOffset, SectionId};
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::{DebugStr, EndianSlice, Error, Reader, ReaderOffset, Result, Section};
+
+/// The magic number at the start of an Apple accelerator table ("HASH").
+pub(crate) const APPLE_HASH_MAGIC: u32 = 0x4841_5348;
+
+/// The hash function used by an Apple accelerator table (Bernstein's DJB hash).
+pub(crate) const APPLE_HASH_FUNCTION_DJB: u16 = 0;
+
+macro_rules! apple_section {
+    ($name:ident, $id:ident, $section_name:expr, $docs:expr) => {
+        #[doc=$docs]
+        #[derive(Debug, Default, Clone, Copy)]
+        pub struct $name<R> {
+            section: R,
+        }
+
+        impl<'input, Endian> $name<EndianSlice<'input, Endian>>
+        where
+            Endian: Endianity,
+        {
+            #[doc = concat!(
+                "Construct a new `", stringify!($name), "` instance from the data in the `",
+                $section_name, "`\nsection."
+            )]
+            pub fn new(section: &'input [u8], endian: Endian) -> Self {
+                Self::from(EndianSlice::new(section, endian))
+            }
+        }
+
+        impl<T> $name<T> {
+            #[doc = concat!(
+                "Create a `", stringify!($name), "` section that references the data in `self`."
+            )]
+            ///
+            /// This is useful when `R` implements `Reader` but `T` does not.
+            pub fn borrow<'a, F, R>(&'a self, mut borrow: F) -> $name<R>
+            where
+                F: FnMut(&'a T) -> R,
+            {
+                borrow(&self.section).into()
+            }
+        }
+
+        impl<R> Section<R> for $name<R> {
+            fn id() -> SectionId {
+                SectionId::$id
+            }
+
+            fn reader(&self) -> &R {
+                &self.section
+            }
+        }
+
+        impl<R> From<R> for $name<R> {
+            fn from(section: R) -> Self {
+                $name { section }
+            }
+        }
+
+        impl<R: Reader> $name<R> {
+            /// Parse the accelerator table header.
+            pub fn table(&self) -> Result<AppleAccelTable<R>> {
+                AppleAccelTable::parse(self.section.clone())
+            }
+        }
+    };
+}
+
+apple_section!(
+    AppleNames,
+    AppleNames,
+    ".apple_names",
+    "The data in the `.apple_names` section.\n\n\
+     This is an accelerator table mapping the names of functions and variables \
+     to their debugging information entries."
+);
+
+apple_section!(
+    AppleTypes,
+    AppleTypes,
+    ".apple_types",
+    "The data in the `.apple_types` section.\n\n\
+     This is an accelerator table mapping the names of types to their \
+     debugging information entries."
+);
+
+apple_section!(
+    AppleNamespaces,
+    AppleNamespaces,
+    ".apple_namespac",
+    "The data in the `.apple_namespac` section.\n\n\
+     This is an accelerator table mapping the names of namespaces to their \
+     debugging information entries."
+);
+
+apple_section!(
+    AppleObjc,
+    AppleObjc,
+    ".apple_objc",
+    "The data in the `.apple_objc` section.\n\n\
+     This is an accelerator table mapping the names of Objective-C classes \
+     to the debugging information entries of their methods."
+);
+
+/// The description of a value stored for each entry in an Apple accelerator table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppleAtom {
+    /// The type of the value.
+    pub atom_type: constants::DwAtom,
+    /// The form used to encode the value.
+    pub form: constants::DwForm,
+}
+
+/// The parsed header of an Apple accelerator table.
+///
+/// These tables are generated by Apple toolchains and are found in the
+/// `.apple_names`, `.apple_types`, `.apple_namespac`, and `.apple_objc`
+/// sections of dSYM bundles.
+#[derive(Debug, Clone)]
+pub struct AppleAccelTable<R: Reader> {
+    section: R,
+    bucket_count: u32,
+    hash_count: u32,
+    die_offset_base: u32,
+    atoms: Vec<AppleAtom>,
+    buckets: R,
+    hashes: R,
+    offsets: R,
+}
+
+impl<R: Reader> AppleAccelTable<R> {
+    fn parse(section: R) -> Result<AppleAccelTable<R>> {
+        let mut input = section.clone();
+        if input.read_u32()? != APPLE_HASH_MAGIC {
+            return Err(Error::InvalidAppleAccelTableHeader);
+        }
+        let version = input.read_u16()?;
+        if version != 1 {
+            return Err(Error::UnknownVersion(version.into()));
+        }
+        if input.read_u16()? != APPLE_HASH_FUNCTION_DJB {
+            return Err(Error::InvalidAppleAccelTableHeader);
+        }
+        let bucket_count = input.read_u32()?;
+        let hash_count = input.read_u32()?;
+        let header_data_length = input.read_u32()?;
+
+        let mut header_data = input.split(R::Offset::from_u32(header_data_length))?;
+        let die_offset_base = header_data.read_u32()?;
+        let atom_count = header_data.read_u32()?;
+        let mut atoms = Vec::new();
+        for _ in 0..atom_count {
+            let atom_type = constants::DwAtom(header_data.read_u16()?);
+            let form = constants::DwForm(header_data.read_u16()?);
+            atoms.push(AppleAtom { atom_type, form });
+        }
+
+        let buckets = input.split(R::Offset::from_u64(u64::from(bucket_count) * 4)?)?;
+        let hashes = input.split(R::Offset::from_u64(u64::from(hash_count) * 4)?)?;
+        let offsets = input.split(R::Offset::from_u64(u64::from(hash_count) * 4)?)?;
+
+        Ok(AppleAccelTable {
+            section,
+            bucket_count,
+            hash_count,
+            die_offset_base,
+            atoms,
+            buckets,
+            hashes,
+            offsets,
+        })
+    }
+
+    /// Return the number of buckets in the hash table.
+    pub fn bucket_count(&self) -> u32 {
+        self.bucket_count
+    }
+
+    /// Return the number of hashes in the hash table.
+    pub fn hash_count(&self) -> u32 {
+        self.hash_count
+    }
+
+    /// Return the base offset that is added to DIE offsets encoded using
+    /// `DW_FORM_ref*` forms.
+    pub fn die_offset_base(&self) -> u32 {
+        self.die_offset_base
+    }
+
+    /// Return the descriptions of the values stored for each entry.
+    pub fn atoms(&self) -> &[AppleAtom] {
+        &self.atoms
+    }
+
+    fn hash(&self, index: u32) -> Result<u32> {
+        let mut input = self.hashes.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 4)?)?;
+        input.read_u32()
+    }
+
+    fn hash_data(&self, index: u32) -> Result<R> {
+        let mut input = self.offsets.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 4)?)?;
+        let offset = input.read_u32()?;
+        let mut input = self.section.clone();
+        input.skip(R::Offset::from_u32(offset))?;
+        Ok(input)
+    }
+
+    /// Find the entries for the given name.
+    ///
+    /// `debug_str` is used to compare the names of the entries that have
+    /// the same hash as `name`.
+    ///
+    /// Returns an empty iterator if the name is not found.
+    pub fn lookup(
+        &self,
+        name: &[u8],
+        debug_str: &DebugStr<R>,
+    ) -> Result<AppleAccelEntryIter<'_, R>> {
+        let mut empty = self.section.clone();
+        empty.empty();
+        if self.bucket_count == 0 {
+            return Ok(AppleAccelEntryIter::new(self, empty, 0));
+        }
+        let hash = apple_hash(name);
+        let bucket = hash % self.bucket_count;
+        let mut buckets = self.buckets.clone();
+        buckets.skip(R::Offset::from_u64(u64::from(bucket) * 4)?)?;
+        let mut index = buckets.read_u32()?;
+        if index == u32::MAX {
+            return Ok(AppleAccelEntryIter::new(self, empty, 0));
+        }
+        while index < self.hash_count {
+            let entry_hash = self.hash(index)?;
+            if entry_hash % self.bucket_count != bucket {
+                break;
+            }
+            if entry_hash == hash {
+                let mut names = AppleAccelNameIter {
+                    table: self,
+                    input: self.hash_data(index)?,
+                    index: self.hash_count,
+                };
+                while let Some(entry_name) = names.next_in_chain()? {
+                    if debug_str.get_str(entry_name.name)?.to_slice()? == name {
+                        return Ok(entry_name.entries);
+                    }
+                }
+            }
+            index += 1;
+        }
+        Ok(AppleAccelEntryIter::new(self, empty, 0))
+    }
+
+    /// Iterate over all names in the table.
+    pub fn names(&self) -> AppleAccelNameIter<'_, R> {
+        let mut input = self.section.clone();
+        input.empty();
+        AppleAccelNameIter {
+            table: self,
+            input,
+            index: 0,
+        }
+    }
+
+    fn read_atom_value(&self, input: &mut R, atom: AppleAtom) -> Result<u64> {
+        let value = match atom.form {
+            constants::DW_FORM_data1 | constants::DW_FORM_flag => u64::from(input.read_u8()?),
+            constants::DW_FORM_data2 => u64::from(input.read_u16()?),
+            constants::DW_FORM_data4 | constants::DW_FORM_sec_offset => {
+                u64::from(input.read_u32()?)
+            }
+            constants::DW_FORM_data8 => input.read_u64()?,
+            constants::DW_FORM_udata => input.read_uleb128()?,
+            constants::DW_FORM_sdata => input.read_sleb128()? as u64,
+            constants::DW_FORM_ref1 => {
+                u64::from(input.read_u8()?) + u64::from(self.die_offset_base)
+            }
+            constants::DW_FORM_ref2 => {
+                u64::from(input.read_u16()?) + u64::from(self.die_offset_base)
+            }
+            constants::DW_FORM_ref4 => {
+                u64::from(input.read_u32()?) + u64::from(self.die_offset_base)
+            }
+            constants::DW_FORM_ref8 => input.read_u64()? + u64::from(self.die_offset_base),
+            constants::DW_FORM_ref_udata => input.read_uleb128()? + u64::from(self.die_offset_base),
+            _ => return Err(Error::UnknownForm),
+        };
+        Ok(value)
+    }
+}
+
+/// A name in an Apple accelerator table.
+#[derive(Debug, Clone)]
+pub struct AppleAccelName<'table, R: Reader> {
+    /// The offset of the name in the `.debug_str` section.
+    pub name: DebugStrOffset<R::Offset>,
+    /// The entries for the name.
+    pub entries: AppleAccelEntryIter<'table, R>,
+}
+
+/// An iterator over the names in an Apple accelerator table.
+#[derive(Debug, Clone)]
+pub struct AppleAccelNameIter<'table, R: Reader> {
+    table: &'table AppleAccelTable<R>,
+    input: R,
+    index: u32,
+}
+
+impl<'table, R: Reader> AppleAccelNameIter<'table, R> {
+    /// Advance the iterator to the next name.
+    pub fn next(&mut self) -> Result<Option<AppleAccelName<'table, R>>> {
+        loop {
+            match self.next_in_chain() {
+                Ok(Some(name)) => return Ok(Some(name)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.index = self.table.hash_count;
+                    self.input.empty();
+                    return Err(e);
+                }
+            }
+            if self.index >= self.table.hash_count {
+                return Ok(None);
+            }
+            self.input = self.table.hash_data(self.index)?;
+            self.index += 1;
+        }
+    }
+
+    /// Return the next name in the current chain of names with the same hash.
+    fn next_in_chain(&mut self) -> Result<Option<AppleAccelName<'table, R>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        let name = self.input.read_u32()?;
+        if name == 0 {
+            self.input.empty();
+            return Ok(None);
+        }
+        let count = self.input.read_u32()?;
+        let entries = AppleAccelEntryIter::new(self.table, self.input.clone(), count);
+        // Skip over the entries to find the next name.
+        for _ in 0..count {
+            for atom in &self.table.atoms {
+                self.table.read_atom_value(&mut self.input, *atom)?;
+            }
+        }
+        Ok(Some(AppleAccelName {
+            name: DebugStrOffset(R::Offset::from_u32(name)),
+            entries,
+        }))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'table, R: Reader> fallible_iterator::FallibleIterator for AppleAccelNameIter<'table, R> {
+    type Item = AppleAccelName<'table, R>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        AppleAccelNameIter::next(self)
+    }
+}
+
+result_iterator!(['table, R: Reader] AppleAccelNameIter<'table, R>, AppleAccelName<'table, R>);
+
+/// An entry in an Apple accelerator table.
+///
+/// Fields are `None` if the table does not contain the corresponding atom.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AppleAccelEntry<T = usize> {
+    /// The offset of the DIE in the `.debug_info` section (`DW_ATOM_die_offset`).
+    pub die_offset: Option<DebugInfoOffset<T>>,
+    /// The offset of the unit containing the DIE (`DW_ATOM_cu_offset`).
+    pub cu_offset: Option<DebugInfoOffset<T>>,
+    /// The tag of the DIE (`DW_ATOM_die_tag`).
+    pub tag: Option<constants::DwTag>,
+    /// Flags for the type (`DW_ATOM_type_flags`).
+    pub type_flags: Option<u32>,
+    /// The hash of the fully qualified name of the type (`DW_ATOM_qual_name_hash`).
+    pub qual_name_hash: Option<u32>,
+}
+
+/// An iterator over the entries for a name in an Apple accelerator table.
+#[derive(Debug, Clone)]
+pub struct AppleAccelEntryIter<'table, R: Reader> {
+    table: &'table AppleAccelTable<R>,
+    input: R,
+    remaining: u32,
+}
+
+impl<'table, R: Reader> AppleAccelEntryIter<'table, R> {
+    fn new(table: &'table AppleAccelTable<R>, input: R, remaining: u32) -> Self {
+        AppleAccelEntryIter {
+            table,
+            input,
+            remaining,
+        }
+    }
+
+    /// Return the number of entries remaining.
+    pub fn len(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Return true if there are no entries remaining.
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Advance the iterator to the next entry.
+    pub fn next(&mut self) -> Result<Option<AppleAccelEntry<R::Offset>>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        match self.parse() {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => {
+                self.remaining = 0;
+                Err(e)
+            }
+        }
+    }
+
+    fn parse(&mut self) -> Result<AppleAccelEntry<R::Offset>> {
+        let mut entry = AppleAccelEntry {
+            die_offset: None,
+            cu_offset: None,
+            tag: None,
+            type_flags: None,
+            qual_name_hash: None,
+        };
+        for atom in &self.table.atoms {
+            let value = self.table.read_atom_value(&mut self.input, *atom)?;
+            match atom.atom_type {
+                constants::DW_ATOM_die_offset => {
+                    entry.die_offset = Some(DebugInfoOffset(R::Offset::from_u64(value)?));
+                }
+                constants::DW_ATOM_cu_offset => {
+                    entry.cu_offset = Some(DebugInfoOffset(R::Offset::from_u64(value)?));
+                }
+                constants::DW_ATOM_die_tag => entry.tag = Some(constants::DwTag(value as u16)),
+                constants::DW_ATOM_type_flags => entry.type_flags = Some(value as u32),
+                constants::DW_ATOM_qual_name_hash => entry.qual_name_hash = Some(value as u32),
+                _ => {}
+            }
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'table, R: Reader> fallible_iterator::FallibleIterator for AppleAccelEntryIter<'table, R> {
+    type Item = AppleAccelEntry<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        AppleAccelEntryIter::next(self)
+    }
+}
+
+result_iterator!(['table, R: Reader] AppleAccelEntryIter<'table, R>, AppleAccelEntry<R::Offset>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endianity::LittleEndian;
+    use test_assembler::{Endian, Label, LabelMaker, Section};
+
+    #[test]
+    fn test_apple_hash() {
+        assert_eq!(apple_hash(b""), 5381);
+        assert_eq!(apple_hash(b"main"), 0x7c9a_7f6a);
+    }
+
+    #[test]
+    fn test_apple_types() {
+        // .debug_str contents.
+        let debug_str = b"\0int\0main\0foo\0";
+        let int = 1;
+        let main = 5;
+        let foo = 10;
+        let bucket_count = 2;
+        let mut names = [(&b"int"[..], int), (&b"main"[..], main)]
+            .iter()
+            .map(|&(name, offset)| (apple_hash(name), offset))
+            .collect::<Vec<_>>();
+        names.push((apple_hash(b"foo"), foo));
+        names.sort_by_key(|&(hash, _)| (hash % bucket_count, hash));
+
+        let data = [Label::new(), Label::new(), Label::new()];
+        let section = Section::with_endian(Endian::Little);
+        section.start().set_const(0);
+        let mut section = section
+            .D32(APPLE_HASH_MAGIC)
+            .D16(1)
+            .D16(APPLE_HASH_FUNCTION_DJB)
+            .D32(bucket_count)
+            .D32(names.len() as u32)
+            .D32(8 + 3 * 4)
+            // Header data.
+            .D32(0)
+            .D32(3)
+            .D16(constants::DW_ATOM_die_offset.0)
+            .D16(constants::DW_FORM_data4.0)
+            .D16(constants::DW_ATOM_die_tag.0)
+            .D16(constants::DW_FORM_data2.0)
+            .D16(constants::DW_ATOM_type_flags.0)
+            .D16(constants::DW_FORM_data1.0);
+        // Buckets.
+        for bucket in 0..bucket_count {
+            section = match names
+                .iter()
+                .position(|&(hash, _)| hash % bucket_count == bucket)
+            {
+                Some(index) => section.D32(index as u32),
+                None => section.D32(u32::MAX),
+            };
+        }
+        for &(hash, _) in &names {
+            section = section.D32(hash);
+        }
+        for label in &data {
+            section = section.D32(label);
+        }
+        for (&(_, name), label) in names.iter().zip(data.iter()) {
+            section = section.mark(label).D32(name);
+            if name == foo {
+                section = section
+                    .D32(2)
+                    .D32(0x30)
+                    .D16(constants::DW_TAG_structure_type.0)
+                    .D8(0)
+                    .D32(0x40)
+                    .D16(constants::DW_TAG_structure_type.0)
+                    .D8(2);
+            } else {
+                section = section
+                    .D32(1)
+                    .D32(name * 0x10)
+                    .D16(constants::DW_TAG_base_type.0)
+                    .D8(0);
+            }
+            section = section.D32(0);
+        }
+        let buf = section.get_contents().unwrap();
+
+        let debug_str = DebugStr::new(debug_str, LittleEndian);
+        let table = AppleTypes::new(&buf, LittleEndian).table().unwrap();
+        assert_eq!(table.bucket_count(), 2);
+        assert_eq!(table.hash_count(), 3);
+        assert_eq!(table.atoms().len(), 3);
+
+        let mut entries = table.lookup(b"foo", &debug_str).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries.next().unwrap(),
+            Some(AppleAccelEntry {
+                die_offset: Some(DebugInfoOffset(0x30)),
+                tag: Some(constants::DW_TAG_structure_type),
+                type_flags: Some(0),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            entries.next().unwrap().unwrap().die_offset,
+            Some(DebugInfoOffset(0x40))
+        );
+        assert_eq!(entries.next().unwrap(), None);
+
+        let mut entries = table.lookup(b"int", &debug_str).unwrap();
+        let entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.die_offset, Some(DebugInfoOffset(0x10)));
+        assert_eq!(entry.tag, Some(constants::DW_TAG_base_type));
+
+        assert!(table.lookup(b"bar", &debug_str).unwrap().is_empty());
+
+        let mut count = 0;
+        let mut names = table.names();
+        while let Some(name) = names.next().unwrap() {
+            let s = debug_str.get_str(name.name).unwrap();
+            let mut entries = table.lookup(s.slice(), &debug_str).unwrap();
+            assert_eq!(entries.len(), name.entries.len());
+            assert_eq!(
+                entries.next().unwrap(),
+                name.entries.clone().next().unwrap()
+            );
+            count += 1;
+        }
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_apple_invalid_magic() {
+        let buf = [0; 20];
+        match AppleNames::new(&buf, LittleEndian).table() {
+            Err(Error::InvalidAppleAccelTableHeader) => {}
+            otherwise => panic!("Unexpected result: {:?}", otherwise),
+        }
+    }
+}
@@ -1,6 +1,13 @@
-use crate::common::{DebugArangesOffset, DebugInfoOffset, Encoding, SectionId};
+#[cfg(feature = "read")]
+use alloc::vec::Vec;
+#[cfg(feature = "read")]
+use core::mem;
+
+use crate::common::{DebugArangesOffset, DebugInfoOffset, Encoding, SectionId, TombstoneFilter};
 use crate::endianity::Endianity;
-use crate::read::{EndianSlice, Error, Range, Reader, ReaderOffset, Result, Section};
+use crate::read::{
+    truncated_section_error, EndianSlice, Error, Range, Reader, ReaderOffset, Result, Section,
+};
 
 /// The `DebugAranges` struct represents the DWARF address range information
 /// found in the `.debug_aranges` section.
@@ -52,6 +59,43 @@ impl<R: Reader> DebugAranges<R> {
         input.skip(offset.0)?;
         ArangeHeader::parse(&mut input, offset)
     }
+
+    /// Parse all of the entries in the section into a table that is sorted
+    /// by address.
+    ///
+    /// The table can then be used to find the unit containing an address
+    /// without parsing the section again.
+    ///
+    /// Use `Dwarf::arange_table` to also skip aranges that begin at a
+    /// tombstone address.
+    #[cfg(feature = "read")]
+    pub fn table(&self) -> Result<ArangeTable<R::Offset>> {
+        self.table_with_tombstones(TombstoneFilter::default())
+    }
+
+    /// Parse all of the entries in the section into a table that is sorted
+    /// by address, skipping aranges that begin at a tombstone address.
+    #[cfg(feature = "read")]
+    pub(crate) fn table_with_tombstones(
+        &self,
+        tombstones: TombstoneFilter,
+    ) -> Result<ArangeTable<R::Offset>> {
+        let mut entries = Vec::new();
+        let mut headers = self.headers();
+        while let Some(header) = headers.next()? {
+            let debug_info_offset = header.debug_info_offset();
+            let mut aranges = header.entries();
+            aranges.set_tombstone_filter(tombstones);
+            while let Some(arange) = aranges.next()? {
+                let range = arange.range();
+                if range.begin < range.end {
+                    entries.push(RangeEntry::new(range, debug_info_offset));
+                }
+            }
+        }
+        sort_ranges(&mut entries);
+        Ok(ArangeTable { entries })
+    }
 }
 
 impl<T> DebugAranges<T> {
@@ -99,6 +143,7 @@ impl<R: Reader> ArangeHeaderIter<R> {
         }
 
         let len = self.input.len();
+        let input = self.input.clone();
         match ArangeHeader::parse(&mut self.input, self.offset) {
             Ok(header) => {
                 self.offset.0 += len - self.input.len();
@@ -106,7 +151,7 @@ impl<R: Reader> ArangeHeaderIter<R> {
             }
             Err(e) => {
                 self.input.empty();
-                Err(e)
+                Err(truncated_section_error(&input, e))
             }
         }
     }
@@ -122,6 +167,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for ArangeHeaderIter<R> {
     }
 }
 
+result_iterator!([R: Reader] ArangeHeaderIter<R>, ArangeHeader<R>);
+
 /// A header for a set of entries in the `.debug_arange` section.
 ///
 /// These entries all belong to a single unit.
@@ -228,12 +275,16 @@ where
     }
 
     /// Return the arange entries in this set.
+    ///
+    /// Use `Dwarf::arange_entries` to also skip aranges that begin at a
+    /// tombstone address.
     #[inline]
     pub fn entries(&self) -> ArangeEntryIter<R> {
         ArangeEntryIter {
             input: self.entries.clone(),
             encoding: self.encoding,
             segment_size: self.segment_size,
+            tombstones: TombstoneFilter::default(),
         }
     }
 }
@@ -247,9 +298,17 @@ pub struct ArangeEntryIter<R: Reader> {
     input: R,
     encoding: Encoding,
     segment_size: u8,
+    tombstones: TombstoneFilter,
 }
 
 impl<R: Reader> ArangeEntryIter<R> {
+    /// Set the filter for recognizing tombstone addresses.
+    ///
+    /// Aranges that begin at a tombstone address are skipped.
+    pub fn set_tombstone_filter(&mut self, tombstones: TombstoneFilter) {
+        self.tombstones = tombstones;
+    }
+
     /// Advance the iterator and return the next arange.
     ///
     /// Returns the newly parsed arange as `Ok(Some(arange))`. Returns `Ok(None)`
@@ -257,19 +316,28 @@ impl<R: Reader> ArangeEntryIter<R> {
     /// yielded. If an error occurs while parsing the next arange, then this error
     /// is returned as `Err(e)`, and all subsequent calls return `Ok(None)`.
     pub fn next(&mut self) -> Result<Option<ArangeEntry>> {
-        if self.input.is_empty() {
-            return Ok(None);
-        }
-
-        match ArangeEntry::parse(&mut self.input, self.encoding, self.segment_size) {
-            Ok(Some(entry)) => Ok(Some(entry)),
-            Ok(None) => {
-                self.input.empty();
-                Ok(None)
+        loop {
+            if self.input.is_empty() {
+                return Ok(None);
             }
-            Err(e) => {
-                self.input.empty();
-                Err(e)
+
+            match ArangeEntry::parse(&mut self.input, self.encoding, self.segment_size) {
+                Ok(Some(entry))
+                    if self
+                        .tombstones
+                        .is_tombstone(entry.address, self.encoding.address_size) =>
+                {
+                    continue
+                }
+                Ok(Some(entry)) => return Ok(Some(entry)),
+                Ok(None) => {
+                    self.input.empty();
+                    return Ok(None);
+                }
+                Err(e) => {
+                    self.input.empty();
+                    return Err(e);
+                }
             }
         }
     }
@@ -285,6 +353,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for ArangeEntryIter<R> {
     }
 }
 
+result_iterator!([R: Reader] ArangeEntryIter<R>, ArangeEntry);
+
 /// A single parsed arange.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ArangeEntry {
@@ -361,6 +431,90 @@ impl ArangeEntry {
     }
 }
 
+/// A table of the address ranges in a `.debug_aranges` section, sorted by address.
+///
+/// This is created by `DebugAranges::table`.
+#[cfg(feature = "read")]
+#[derive(Debug, Default, Clone)]
+pub struct ArangeTable<Offset = usize> {
+    entries: Vec<RangeEntry<DebugInfoOffset<Offset>>>,
+}
+
+/// An address range and an associated value.
+///
+/// This is used for tables of ranges that are sorted by `sort_ranges` and
+/// searched by `find_range`.
+#[cfg(feature = "read")]
+#[derive(Debug, Clone)]
+pub(crate) struct RangeEntry<V> {
+    pub(crate) range: Range,
+    /// The maximum end address of this entry and all preceding entries.
+    max_end: u64,
+    pub(crate) value: V,
+}
+
+#[cfg(feature = "read")]
+impl<V> RangeEntry<V> {
+    pub(crate) fn new(range: Range, value: V) -> Self {
+        RangeEntry {
+            range,
+            max_end: range.end,
+            value,
+        }
+    }
+}
+
+/// Sort the entries by beginning address, and set their `max_end`.
+#[cfg(feature = "read")]
+pub(crate) fn sort_ranges<V>(entries: &mut [RangeEntry<V>]) {
+    entries.sort_by_key(|entry| entry.range.begin);
+    let mut max_end = 0;
+    for entry in entries {
+        max_end = max_end.max(entry.range.end);
+        entry.max_end = max_end;
+    }
+}
+
+/// Find the entry with the greatest beginning address that contains `address`.
+///
+/// The entries must have been sorted by `sort_ranges`.
+#[cfg(feature = "read")]
+pub(crate) fn find_range<V>(entries: &[RangeEntry<V>], address: u64) -> Option<&RangeEntry<V>> {
+    // The number of entries that begin at or before the address.
+    let count = entries.partition_point(|entry| entry.range.begin <= address);
+    entries[..count]
+        .iter()
+        .rev()
+        .take_while(|entry| entry.max_end > address)
+        .find(|entry| entry.range.end > address)
+}
+
+#[cfg(feature = "read")]
+impl<Offset: ReaderOffset> ArangeTable<Offset> {
+    /// Find the offset of the unit containing the given address.
+    ///
+    /// If multiple ranges contain the address, then the unit for the range
+    /// with the highest beginning address is returned.
+    pub fn find(&self, address: u64) -> Option<DebugInfoOffset<Offset>> {
+        find_range(&self.entries, address).map(|entry| entry.value)
+    }
+
+    /// Return the number of address ranges in the table.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if the table contains no address ranges.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the table.
+    pub fn allocated_bytes(&self) -> usize {
+        self.entries.capacity() * mem::size_of::<RangeEntry<DebugInfoOffset<Offset>>>()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -647,4 +801,84 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_entries_tombstone_filter() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 2,
+            address_size: 4,
+        };
+        #[rustfmt::skip]
+        let buf = [
+            // An arange at address 0.
+            0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            // An arange below the minimum address.
+            0x00, 0x01, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            // A valid arange.
+            0x00, 0x00, 0x00, 0x01, 0x20, 0x00, 0x00, 0x00,
+            // Terminator.
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut entries = ArangeEntryIter {
+            input: EndianSlice::new(&buf, LittleEndian),
+            encoding,
+            segment_size: 0,
+            tombstones: TombstoneFilter::default(),
+        };
+        entries.set_tombstone_filter(TombstoneFilter {
+            zero: true,
+            max: false,
+            min_address: 0x1000,
+        });
+        assert_eq!(
+            entries.next(),
+            Ok(Some(ArangeEntry {
+                segment: None,
+                address: 0x0100_0000,
+                length: 0x20,
+            }))
+        );
+        assert_eq!(entries.next(), Ok(None));
+    }
+
+    #[test]
+    #[cfg(feature = "read")]
+    fn test_table_find() {
+        fn set(buf: &mut Vec<u8>, debug_info_offset: u32, aranges: &[(u32, u32)]) {
+            // unit_length + version + offset + address_size + segment_size + padding
+            let length = 2 + 4 + 1 + 1 + 4 + (aranges.len() + 1) * 8;
+            buf.extend_from_slice(&(length as u32).to_le_bytes());
+            buf.extend_from_slice(&2u16.to_le_bytes());
+            buf.extend_from_slice(&debug_info_offset.to_le_bytes());
+            buf.extend_from_slice(&[4, 0, 0, 0, 0, 0]);
+            for &(address, length) in aranges.iter().chain(&[(0, 0)]) {
+                buf.extend_from_slice(&address.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+            }
+        }
+
+        let mut buf = Vec::new();
+        set(&mut buf, 0x10, &[(0x3000, 0x100), (0x1000, 0x100)]);
+        // The second unit has a range that contains a range of the third unit.
+        set(&mut buf, 0x20, &[(0x2000, 0x800), (0x4000, 0)]);
+        set(&mut buf, 0x30, &[(0x2100, 0x10)]);
+        let debug_aranges = DebugAranges::new(&buf, LittleEndian);
+        let table = debug_aranges.table().unwrap();
+        assert_eq!(table.len(), 4);
+        assert!(
+            table.allocated_bytes() >= 4 * mem::size_of::<RangeEntry<DebugInfoOffset<usize>>>()
+        );
+
+        assert_eq!(table.find(0), None);
+        assert_eq!(table.find(0x1000), Some(DebugInfoOffset(0x10)));
+        assert_eq!(table.find(0x10ff), Some(DebugInfoOffset(0x10)));
+        assert_eq!(table.find(0x1100), None);
+        assert_eq!(table.find(0x2000), Some(DebugInfoOffset(0x20)));
+        assert_eq!(table.find(0x2108), Some(DebugInfoOffset(0x30)));
+        assert_eq!(table.find(0x2200), Some(DebugInfoOffset(0x20)));
+        assert_eq!(table.find(0x2800), None);
+        assert_eq!(table.find(0x3080), Some(DebugInfoOffset(0x10)));
+        assert_eq!(table.find(0x4000), None);
+    }
 }
@@ -0,0 +1,419 @@
+use alloc::vec::Vec;
+
+use crate::constants;
+use crate::read::{
+    AttributeValue, DebuggingInformationEntry, DieReference, Error, Expression, Reader, Result,
+    UnitOffset, UnitRef,
+};
+
+/// A call site within a subprogram.
+///
+/// This is parsed from either a DWARF 5 `DW_TAG_call_site` entry, or a
+/// `DW_TAG_GNU_call_site` entry (the GNU extension for earlier versions).
+///
+/// Returned by `UnitRef::call_sites`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSite<R: Reader> {
+    /// The offset of the call site entry.
+    pub offset: UnitOffset<R::Offset>,
+    /// The return address of the call.
+    ///
+    /// This is from `DW_AT_call_return_pc`, or from `DW_AT_low_pc` for the
+    /// GNU extension.
+    pub return_pc: Option<u64>,
+    /// The address of the call instruction, from `DW_AT_call_pc`.
+    pub pc: Option<u64>,
+    /// The subprogram entry for the called function, if it is known.
+    ///
+    /// This is from `DW_AT_call_origin`, or from `DW_AT_abstract_origin` for
+    /// the GNU extension.
+    pub origin: Option<DieReference<R::Offset>>,
+    /// An expression which computes the address of the called function, if
+    /// the call is indirect.
+    ///
+    /// This is from `DW_AT_call_target` or `DW_AT_GNU_call_site_target`.
+    pub target: Option<Expression<R>>,
+    /// Whether this call is a tail call.
+    ///
+    /// This is from `DW_AT_call_tail_call` or `DW_AT_GNU_tail_call`.
+    pub tail_call: bool,
+    /// The parameters of the call.
+    pub parameters: Vec<CallSiteParameter<R>>,
+}
+
+/// A parameter of a call site.
+///
+/// This is parsed from either a DWARF 5 `DW_TAG_call_site_parameter` entry,
+/// or a `DW_TAG_GNU_call_site_parameter` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSiteParameter<R: Reader> {
+    /// The offset of the call site parameter entry.
+    pub offset: UnitOffset<R::Offset>,
+    /// The formal parameter entry of the called function, if it is known.
+    ///
+    /// This is from `DW_AT_call_parameter`, or from `DW_AT_abstract_origin`
+    /// for the GNU extension.
+    pub parameter: Option<DieReference<R::Offset>>,
+    /// The location where the parameter is passed, from `DW_AT_location`.
+    ///
+    /// This is usually a register location description.
+    pub location: Option<Expression<R>>,
+    /// An expression which computes the value of the parameter at the time
+    /// of the call.
+    ///
+    /// This is from `DW_AT_call_value` or `DW_AT_GNU_call_site_value`. It is
+    /// evaluated in the context of the caller, so it can be used to recover
+    /// the value of the parameter after the callee has clobbered its location.
+    pub value: Option<Expression<R>>,
+    /// An expression which computes the value that the parameter points to
+    /// at the time of the call, if the parameter is passed by reference.
+    ///
+    /// This is from `DW_AT_call_data_value` or `DW_AT_GNU_call_site_data_value`.
+    pub data_value: Option<Expression<R>>,
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Return the call sites within the subprogram entry at the given offset.
+    ///
+    /// This includes call sites within nested lexical blocks and inlined
+    /// subroutines, but not those within nested subprograms. The call sites
+    /// are returned in the order that they occur in the entry tree.
+    pub fn call_sites(&self, offset: UnitOffset<R::Offset>) -> Result<Vec<CallSite<R>>> {
+        let mut call_sites = Vec::new();
+        let mut entries = self.entries_at_offset(offset)?;
+        entries.next_entry()?;
+        if entries.current().is_none() {
+            return Err(Error::UnexpectedNull);
+        }
+        // The call site that is being built, and its depth.
+        let mut call_site: Option<(isize, CallSite<R>)> = None;
+        // The depth of an entry whose children are skipped.
+        let mut skip_depth = None;
+        let mut depth = 0;
+        while let Some((delta_depth, entry)) = entries.next_dfs()? {
+            depth += delta_depth;
+            if depth <= 0 {
+                break;
+            }
+            if let Some((call_site_depth, _)) = call_site {
+                if depth <= call_site_depth {
+                    call_sites.extend(call_site.take().map(|(_, call_site)| call_site));
+                }
+            }
+            if let Some(parent_depth) = skip_depth {
+                if depth > parent_depth {
+                    if let Some((call_site_depth, ref mut call_site)) = call_site {
+                        if depth == call_site_depth + 1 {
+                            match entry.tag() {
+                                constants::DW_TAG_call_site_parameter
+                                | constants::DW_TAG_GNU_call_site_parameter => {
+                                    call_site.parameters.push(call_site_parameter(entry)?);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    continue;
+                }
+                skip_depth = None;
+            }
+            match entry.tag() {
+                constants::DW_TAG_call_site | constants::DW_TAG_GNU_call_site => {
+                    call_site = Some((depth, self.call_site(entry)?));
+                    skip_depth = Some(depth);
+                }
+                constants::DW_TAG_subprogram => skip_depth = Some(depth),
+                _ => {}
+            }
+        }
+        call_sites.extend(call_site.map(|(_, call_site)| call_site));
+        Ok(call_sites)
+    }
+
+    /// Return true if the subprogram entry at the given offset describes
+    /// all of its calls.
+    ///
+    /// This is from `DW_AT_call_all_calls`, or from `DW_AT_GNU_all_call_sites`
+    /// for the GNU extension. If this is false, then the call sites returned
+    /// by `call_sites` may be incomplete.
+    pub fn all_call_sites(&self, offset: UnitOffset<R::Offset>) -> Result<bool> {
+        let entry = self.entry(offset)?;
+        let mut attrs = entry.attrs();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                constants::DW_AT_call_all_calls | constants::DW_AT_GNU_all_call_sites => {
+                    return Ok(!matches!(attr.value(), AttributeValue::Flag(false)));
+                }
+                _ => {}
+            }
+        }
+        Ok(false)
+    }
+
+    fn call_site(&self, entry: &DebuggingInformationEntry<'_, '_, R>) -> Result<CallSite<R>> {
+        let mut call_site = CallSite {
+            offset: entry.offset(),
+            return_pc: None,
+            pc: None,
+            origin: None,
+            target: None,
+            tail_call: false,
+            parameters: Vec::new(),
+        };
+        let mut attrs = entry.attrs();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                constants::DW_AT_call_return_pc | constants::DW_AT_low_pc => {
+                    call_site.return_pc = self.attr_address(attr.value())?;
+                }
+                constants::DW_AT_call_pc => {
+                    call_site.pc = self.attr_address(attr.value())?;
+                }
+                constants::DW_AT_call_origin | constants::DW_AT_abstract_origin => {
+                    call_site.origin = die_reference(attr.value());
+                }
+                constants::DW_AT_call_target | constants::DW_AT_GNU_call_site_target => {
+                    call_site.target = attr.exprloc_value();
+                }
+                constants::DW_AT_call_tail_call | constants::DW_AT_GNU_tail_call => {
+                    call_site.tail_call = !matches!(attr.value(), AttributeValue::Flag(false));
+                }
+                _ => {}
+            }
+        }
+        Ok(call_site)
+    }
+}
+
+fn call_site_parameter<R: Reader>(
+    entry: &DebuggingInformationEntry<'_, '_, R>,
+) -> Result<CallSiteParameter<R>> {
+    let mut parameter = CallSiteParameter {
+        offset: entry.offset(),
+        parameter: None,
+        location: None,
+        value: None,
+        data_value: None,
+    };
+    let mut attrs = entry.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            constants::DW_AT_call_parameter | constants::DW_AT_abstract_origin => {
+                parameter.parameter = die_reference(attr.value());
+            }
+            constants::DW_AT_location => {
+                parameter.location = attr.exprloc_value();
+            }
+            constants::DW_AT_call_value | constants::DW_AT_GNU_call_site_value => {
+                parameter.value = attr.exprloc_value();
+            }
+            constants::DW_AT_call_data_value | constants::DW_AT_GNU_call_site_data_value => {
+                parameter.data_value = attr.exprloc_value();
+            }
+            _ => {}
+        }
+    }
+    Ok(parameter)
+}
+
+fn die_reference<R: Reader>(value: AttributeValue<R>) -> Option<DieReference<R::Offset>> {
+    match value {
+        AttributeValue::UnitRef(offset) => Some(DieReference::UnitRef(offset)),
+        AttributeValue::DebugInfoRef(offset) => Some(DieReference::DebugInfoRef(offset)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::{DebugAbbrev, DebugInfo, Dwarf, EndianSlice};
+    use crate::write::{self, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_call_sites() {
+        for (
+            version,
+            call_site_tag,
+            parameter_tag,
+            return_pc,
+            origin,
+            value,
+            tail_call,
+            all_calls,
+        ) in [
+            (
+                5,
+                constants::DW_TAG_call_site,
+                constants::DW_TAG_call_site_parameter,
+                constants::DW_AT_call_return_pc,
+                constants::DW_AT_call_origin,
+                constants::DW_AT_call_value,
+                constants::DW_AT_call_tail_call,
+                constants::DW_AT_call_all_calls,
+            ),
+            (
+                4,
+                constants::DW_TAG_GNU_call_site,
+                constants::DW_TAG_GNU_call_site_parameter,
+                constants::DW_AT_low_pc,
+                constants::DW_AT_abstract_origin,
+                constants::DW_AT_GNU_call_site_value,
+                constants::DW_AT_GNU_tail_call,
+                constants::DW_AT_GNU_all_call_sites,
+            ),
+        ] {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version,
+                address_size: 8,
+            };
+            let mut dwarf = write::Dwarf::new();
+            let unit_id = dwarf
+                .units
+                .add(write::Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+
+            let callee = unit.add(root, constants::DW_TAG_subprogram);
+            let formal_parameter = unit.add(callee, constants::DW_TAG_formal_parameter);
+
+            let caller = unit.add(root, constants::DW_TAG_subprogram);
+            unit.get_mut(caller)
+                .set(all_calls, write::AttributeValue::Flag(true));
+            let block = unit.add(caller, constants::DW_TAG_lexical_block);
+            let call_site1 = unit.add(block, call_site_tag);
+            let entry = unit.get_mut(call_site1);
+            entry.set(
+                return_pc,
+                write::AttributeValue::Address(write::Address::Constant(0x1010)),
+            );
+            entry.set(origin, write::AttributeValue::UnitRef(callee));
+            let parameter = unit.add(call_site1, parameter_tag);
+            let mut location = write::Expression::new();
+            location.op_reg(crate::Register(5));
+            let mut call_value = write::Expression::new();
+            call_value.op_constu(42);
+            let entry = unit.get_mut(parameter);
+            entry.set(
+                constants::DW_AT_location,
+                write::AttributeValue::Exprloc(location),
+            );
+            entry.set(value, write::AttributeValue::Exprloc(call_value));
+            if version >= 5 {
+                entry.set(
+                    constants::DW_AT_call_parameter,
+                    write::AttributeValue::UnitRef(formal_parameter),
+                );
+            }
+
+            // Call sites in nested subprograms are not included.
+            let nested = unit.add(caller, constants::DW_TAG_subprogram);
+            unit.add(nested, call_site_tag);
+
+            let call_site2 = unit.add(caller, call_site_tag);
+            let mut target = write::Expression::new();
+            target.op_breg(crate::Register(0), 8);
+            let entry = unit.get_mut(call_site2);
+            entry.set(
+                return_pc,
+                write::AttributeValue::Address(write::Address::Constant(0x1020)),
+            );
+            entry.set(
+                if version >= 5 {
+                    constants::DW_AT_call_target
+                } else {
+                    constants::DW_AT_GNU_call_site_target
+                },
+                write::AttributeValue::Exprloc(target),
+            );
+            entry.set(tail_call, write::AttributeValue::Flag(true));
+
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            let offsets = dwarf
+                .units
+                .write(
+                    &mut sections,
+                    &write::DebugLineStrOffsets::none(),
+                    &write::DebugStrOffsets::none(),
+                )
+                .unwrap();
+            let unit_offset =
+                |entry| UnitOffset(offsets.entry(unit_id, entry).0 - offsets.unit(unit_id).0);
+
+            let read_dwarf = Dwarf {
+                debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+                debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+                ..Default::default()
+            };
+            let header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(header).unwrap();
+            let read_unit = read_unit.unit_ref(&read_dwarf);
+
+            let call_sites = read_unit.call_sites(unit_offset(caller)).unwrap();
+            assert_eq!(call_sites.len(), 2);
+
+            let call_site = &call_sites[0];
+            assert_eq!(call_site.offset, unit_offset(call_site1));
+            assert_eq!(call_site.return_pc, Some(0x1010));
+            assert_eq!(call_site.pc, None);
+            assert_eq!(
+                call_site.origin,
+                Some(DieReference::UnitRef(unit_offset(callee)))
+            );
+            assert_eq!(call_site.target, None);
+            assert!(!call_site.tail_call);
+            assert_eq!(call_site.parameters.len(), 1);
+            let read_parameter = &call_site.parameters[0];
+            assert_eq!(read_parameter.offset, unit_offset(parameter));
+            if version >= 5 {
+                assert_eq!(
+                    read_parameter.parameter,
+                    Some(DieReference::UnitRef(unit_offset(formal_parameter)))
+                );
+            } else {
+                assert_eq!(read_parameter.parameter, None);
+            }
+            assert_eq!(
+                read_parameter.location,
+                Some(Expression(EndianSlice::new(
+                    &[constants::DW_OP_reg5.0],
+                    LittleEndian
+                )))
+            );
+            assert_eq!(
+                read_parameter.value,
+                Some(Expression(EndianSlice::new(
+                    &[constants::DW_OP_constu.0, 42],
+                    LittleEndian
+                )))
+            );
+            assert_eq!(read_parameter.data_value, None);
+
+            let call_site = &call_sites[1];
+            assert_eq!(call_site.offset, unit_offset(call_site2));
+            assert_eq!(call_site.return_pc, Some(0x1020));
+            assert_eq!(call_site.origin, None);
+            assert_eq!(
+                call_site.target,
+                Some(Expression(EndianSlice::new(
+                    &[constants::DW_OP_breg0.0, 8],
+                    LittleEndian
+                )))
+            );
+            assert!(call_site.tail_call);
+            assert!(call_site.parameters.is_empty());
+
+            assert!(read_unit
+                .call_sites(unit_offset(callee))
+                .unwrap()
+                .is_empty());
+
+            assert!(read_unit.all_call_sites(unit_offset(caller)).unwrap());
+            assert!(!read_unit.all_call_sites(unit_offset(callee)).unwrap());
+        }
+    }
+}
@@ -1,5 +1,7 @@
 #[cfg(feature = "read")]
 use alloc::boxed::Box;
+#[cfg(feature = "read")]
+use alloc::vec::Vec;
 
 use core::cmp::Ordering;
 use core::fmt::{self, Debug};
@@ -14,7 +16,8 @@ use crate::common::{
 use crate::constants::{self, DwEhPe};
 use crate::endianity::Endianity;
 use crate::read::{
-    EndianSlice, Error, Expression, Reader, ReaderOffset, Result, Section, StoreOnHeap,
+    truncated_section_error, EndianSlice, Error, Expression, Reader, ReaderOffset, Result, Section,
+    StoreOnHeap,
 };
 
 /// `DebugFrame` contains the `.debug_frame` section's frame unwinding
@@ -300,6 +303,8 @@ impl<'a, 'bases, R: Reader> fallible_iterator::FallibleIterator for EhHdrTableIt
     }
 }
 
+result_iterator!(['a, 'bases, R: Reader] EhHdrTableIter<'a, 'bases, R>, (Pointer, Pointer));
+
 /// The CFI binary search table that is an optional part of the `.eh_frame_hdr` section.
 #[derive(Debug, Clone)]
 pub struct EhHdrTable<'a, R: Reader> {
@@ -791,6 +796,378 @@ pub trait UnwindSection<R: Reader>: Clone + Debug + _UnwindSectionPrivate<R> {
         let fde = self.fde_for_address(bases, address, get_cie)?;
         fde.unwind_info_for_address(self, bases, ctx, address)
     }
+
+    /// Find the CFA and return address unwind information for the given
+    /// address.
+    ///
+    /// This is the same as `unwind_info_for_address`, except that the returned
+    /// row only contains a rule for the CIE's return address register. See
+    /// [`UnwindTable::new_cfa_only`](./struct.UnwindTable.html#method.new_cfa_only).
+    #[inline]
+    fn cfa_unwind_info_for_address<'ctx, F, A: UnwindContextStorage<R::Offset>>(
+        &self,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        address: u64,
+        get_cie: F,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>>
+    where
+        F: FnMut(&Self, &BaseAddresses, Self::Offset) -> Result<CommonInformationEntry<R>>,
+    {
+        let fde = self.fde_for_address(bases, address, get_cie)?;
+        fde.cfa_unwind_info_for_address(self, bases, ctx, address)
+    }
+
+    /// Build a table of the FDEs in this section, sorted by address.
+    ///
+    /// This parses every FDE once. The table can then be used to find the FDE
+    /// for an address using a binary search, which is useful when there is no
+    /// `.eh_frame_hdr` section, or for `.debug_frame`.
+    ///
+    /// You must provide a function to get the associated CIE of each FDE. See
+    /// `PartialFrameDescriptionEntry::parse` for more information.
+    #[cfg(feature = "read")]
+    fn fde_table<F>(&self, bases: &BaseAddresses, mut get_cie: F) -> Result<FdeTable<Self::Offset>>
+    where
+        F: FnMut(&Self, &BaseAddresses, Self::Offset) -> Result<CommonInformationEntry<R>>,
+    {
+        let mut entries = Vec::new();
+        let mut iter = self.entries(bases);
+        while let Some(entry) = iter.next()? {
+            if let CieOrFde::Fde(partial) = entry {
+                let fde = partial.parse(&mut get_cie)?;
+                if fde.len() != 0 {
+                    entries.push(FdeTableEntry {
+                        initial_address: fde.initial_address(),
+                        len: fde.len(),
+                        offset: partial.offset().into(),
+                    });
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.initial_address);
+        Ok(FdeTable::from_sorted(entries))
+    }
+}
+
+/// An entry in an [`FdeTable`].
+#[cfg(feature = "read")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FdeTableEntry<Offset> {
+    /// The first address covered by the FDE.
+    pub initial_address: u64,
+    /// The number of bytes of instructions covered by the FDE.
+    pub len: u64,
+    /// The offset of the FDE within the section.
+    pub offset: Offset,
+}
+
+#[cfg(feature = "read")]
+impl<Offset> FdeTableEntry<Offset> {
+    /// Return `true` if the given address is within the range of this entry.
+    #[inline]
+    pub fn contains(&self, address: u64) -> bool {
+        address
+            .checked_sub(self.initial_address)
+            .map_or(false, |delta| delta < self.len)
+    }
+}
+
+/// A table of the FDEs in an unwind section, sorted by address.
+///
+/// This is created by `UnwindSection::fde_table`.
+#[cfg(feature = "read")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FdeTable<Offset> {
+    entries: Vec<FdeTableEntry<Offset>>,
+    /// The maximum end address of each entry and all preceding entries.
+    max_end: Vec<u64>,
+}
+
+#[cfg(feature = "read")]
+impl<Offset: Copy> FdeTable<Offset> {
+    /// Create a table from entries that are sorted by initial address.
+    fn from_sorted(entries: Vec<FdeTableEntry<Offset>>) -> Self {
+        let mut end = 0;
+        let max_end = entries
+            .iter()
+            .map(|entry| {
+                end = end.max(entry.initial_address.saturating_add(entry.len));
+                end
+            })
+            .collect();
+        FdeTable { entries, max_end }
+    }
+
+    /// Return the entries in the table, sorted by initial address.
+    pub fn entries(&self) -> &[FdeTableEntry<Offset>] {
+        &self.entries
+    }
+
+    /// Return the number of FDEs in the table.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if the table contains no FDEs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the table.
+    pub fn allocated_bytes(&self) -> usize {
+        self.entries.capacity() * mem::size_of::<FdeTableEntry<Offset>>()
+            + self.max_end.capacity() * mem::size_of::<u64>()
+    }
+
+    /// Find the entry for the FDE that contains the given address.
+    ///
+    /// If multiple FDEs contain the address, such as when FDEs are nested,
+    /// then the one with the greatest initial address is returned.
+    pub fn find(&self, address: u64) -> Option<&FdeTableEntry<Offset>> {
+        // The number of entries that begin at or before the address.
+        let count = self
+            .entries
+            .partition_point(|entry| entry.initial_address <= address);
+        self.entries[..count]
+            .iter()
+            .zip(&self.max_end[..count])
+            .rev()
+            .take_while(|(_, max_end)| **max_end > address)
+            .map(|(entry, _)| entry)
+            .find(|entry| entry.contains(address))
+    }
+
+    /// Find the `FrameDescriptionEntry` for the given address.
+    ///
+    /// If not found, `Err(gimli::Error::NoUnwindInfoForAddress)` is returned.
+    /// `section` must be the section that this table was created from.
+    pub fn fde_for_address<R, S, F>(
+        &self,
+        section: &S,
+        bases: &BaseAddresses,
+        address: u64,
+        get_cie: F,
+    ) -> Result<FrameDescriptionEntry<R>>
+    where
+        R: Reader,
+        S: UnwindSection<R, Offset = Offset>,
+        F: FnMut(&S, &BaseAddresses, S::Offset) -> Result<CommonInformationEntry<R>>,
+    {
+        let entry = self.find(address).ok_or(Error::NoUnwindInfoForAddress)?;
+        section.fde_from_offset(bases, entry.offset, get_cie)
+    }
+
+    /// Find the frame unwind information for the given address.
+    ///
+    /// If not found, `Err(gimli::Error::NoUnwindInfoForAddress)` is returned.
+    /// `section` must be the section that this table was created from.
+    pub fn unwind_info_for_address<'ctx, R, S, F, A>(
+        &self,
+        section: &S,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        address: u64,
+        get_cie: F,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>>
+    where
+        R: Reader,
+        S: UnwindSection<R, Offset = Offset>,
+        F: FnMut(&S, &BaseAddresses, S::Offset) -> Result<CommonInformationEntry<R>>,
+        A: UnwindContextStorage<R::Offset>,
+    {
+        let fde = self.fde_for_address(section, bases, address, get_cie)?;
+        fde.unwind_info_for_address(section, bases, ctx, address)
+    }
+}
+
+/// The offset of an FDE in either the `.debug_frame` or `.eh_frame` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallFrameOffset<T = usize> {
+    /// An offset into the `.debug_frame` section.
+    DebugFrame(DebugFrameOffset<T>),
+    /// An offset into the `.eh_frame` section.
+    EhFrame(EhFrameOffset<T>),
+}
+
+/// The call frame information for an object file, which may be contained in
+/// a `.debug_frame` section, an `.eh_frame` section, or both.
+///
+/// This provides a single interface for finding unwind information, without
+/// the caller needing to know which section describes a given address. The
+/// differences between the sections, such as how CIE pointers and
+/// augmentations are encoded, are handled by the sections themselves.
+///
+/// If both sections describe an address, then `.eh_frame` is preferred,
+/// since it is the section that is used for unwinding at runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CallFrameInfo<R: Reader> {
+    debug_frame: Option<DebugFrame<R>>,
+    eh_frame: Option<EhFrame<R>>,
+}
+
+impl<R: Reader> CallFrameInfo<R> {
+    /// Construct a new `CallFrameInfo` from the sections that are present.
+    ///
+    /// The address size and vendor of each section should already have been
+    /// set.
+    pub fn new(debug_frame: Option<DebugFrame<R>>, eh_frame: Option<EhFrame<R>>) -> Self {
+        CallFrameInfo {
+            debug_frame,
+            eh_frame,
+        }
+    }
+
+    /// Return the `.debug_frame` section, if present.
+    pub fn debug_frame(&self) -> Option<&DebugFrame<R>> {
+        self.debug_frame.as_ref()
+    }
+
+    /// Return the `.eh_frame` section, if present.
+    pub fn eh_frame(&self) -> Option<&EhFrame<R>> {
+        self.eh_frame.as_ref()
+    }
+
+    /// Parse the `FrameDescriptionEntry` at the given offset.
+    ///
+    /// Returns `Err(gimli::Error::OffsetOutOfBounds)` if the offset is for a
+    /// section that is not present.
+    pub fn fde_from_offset(
+        &self,
+        bases: &BaseAddresses,
+        offset: CallFrameOffset<R::Offset>,
+    ) -> Result<FrameDescriptionEntry<R>> {
+        match offset {
+            CallFrameOffset::DebugFrame(offset) => self
+                .debug_frame
+                .as_ref()
+                .ok_or(Error::OffsetOutOfBounds)?
+                .fde_from_offset(bases, offset, DebugFrame::cie_from_offset),
+            CallFrameOffset::EhFrame(offset) => self
+                .eh_frame
+                .as_ref()
+                .ok_or(Error::OffsetOutOfBounds)?
+                .fde_from_offset(bases, offset, EhFrame::cie_from_offset),
+        }
+    }
+
+    /// Find the `FrameDescriptionEntry` for the given address.
+    ///
+    /// If not found, `Err(gimli::Error::NoUnwindInfoForAddress)` is returned.
+    ///
+    /// Note: this iterates over all FDEs. Use `fde_table` to build a table
+    /// that can be searched more efficiently.
+    pub fn fde_for_address(
+        &self,
+        bases: &BaseAddresses,
+        address: u64,
+    ) -> Result<FrameDescriptionEntry<R>> {
+        self.find_fde(bases, address).map(|(_, fde)| fde)
+    }
+
+    /// Find the frame unwind information for the given address.
+    ///
+    /// If not found, `Err(gimli::Error::NoUnwindInfoForAddress)` is returned.
+    ///
+    /// Note: this iterates over all FDEs. Use `fde_table` to build a table
+    /// that can be searched more efficiently.
+    pub fn unwind_info_for_address<'ctx, A: UnwindContextStorage<R::Offset>>(
+        &self,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        address: u64,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>> {
+        let (offset, fde) = self.find_fde(bases, address)?;
+        self.unwind_info_for_fde(bases, ctx, offset, &fde, address)
+    }
+
+    /// Find the frame unwind information for the given address, using the
+    /// FDE at the given offset.
+    ///
+    /// The offset is typically obtained from an entry of the table returned
+    /// by `fde_table`.
+    pub fn unwind_info_for_fde_offset<'ctx, A: UnwindContextStorage<R::Offset>>(
+        &self,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        offset: CallFrameOffset<R::Offset>,
+        address: u64,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>> {
+        let fde = self.fde_from_offset(bases, offset)?;
+        self.unwind_info_for_fde(bases, ctx, offset, &fde, address)
+    }
+
+    /// Build a table of the FDEs in both sections, sorted by address.
+    ///
+    /// If both sections contain an FDE with the same initial address, then
+    /// only the `.eh_frame` FDE is included.
+    #[cfg(feature = "read")]
+    pub fn fde_table(&self, bases: &BaseAddresses) -> Result<FdeTable<CallFrameOffset<R::Offset>>> {
+        let mut entries = Vec::new();
+        if let Some(eh_frame) = &self.eh_frame {
+            let table = eh_frame.fde_table(bases, EhFrame::cie_from_offset)?;
+            entries.extend(table.entries.into_iter().map(|entry| FdeTableEntry {
+                initial_address: entry.initial_address,
+                len: entry.len,
+                offset: CallFrameOffset::EhFrame(entry.offset),
+            }));
+        }
+        if let Some(debug_frame) = &self.debug_frame {
+            let table = debug_frame.fde_table(bases, DebugFrame::cie_from_offset)?;
+            entries.extend(table.entries.into_iter().map(|entry| FdeTableEntry {
+                initial_address: entry.initial_address,
+                len: entry.len,
+                offset: CallFrameOffset::DebugFrame(entry.offset),
+            }));
+        }
+        // This is a stable sort, so `.eh_frame` entries remain first.
+        entries.sort_by_key(|entry| entry.initial_address);
+        entries.dedup_by_key(|entry| entry.initial_address);
+        Ok(FdeTable::from_sorted(entries))
+    }
+
+    fn find_fde(
+        &self,
+        bases: &BaseAddresses,
+        address: u64,
+    ) -> Result<(CallFrameOffset<R::Offset>, FrameDescriptionEntry<R>)> {
+        if let Some(eh_frame) = &self.eh_frame {
+            match eh_frame.fde_for_address(bases, address, EhFrame::cie_from_offset) {
+                Ok(fde) => {
+                    let offset = CallFrameOffset::EhFrame(EhFrameOffset(fde.offset()));
+                    return Ok((offset, fde));
+                }
+                Err(Error::NoUnwindInfoForAddress) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(debug_frame) = &self.debug_frame {
+            let fde = debug_frame.fde_for_address(bases, address, DebugFrame::cie_from_offset)?;
+            let offset = CallFrameOffset::DebugFrame(DebugFrameOffset(fde.offset()));
+            return Ok((offset, fde));
+        }
+        Err(Error::NoUnwindInfoForAddress)
+    }
+
+    fn unwind_info_for_fde<'ctx, A: UnwindContextStorage<R::Offset>>(
+        &self,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        offset: CallFrameOffset<R::Offset>,
+        fde: &FrameDescriptionEntry<R>,
+        address: u64,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>> {
+        match offset {
+            CallFrameOffset::DebugFrame(_) => {
+                let debug_frame = self.debug_frame.as_ref().ok_or(Error::OffsetOutOfBounds)?;
+                fde.unwind_info_for_address(debug_frame, bases, ctx, address)
+            }
+            CallFrameOffset::EhFrame(_) => {
+                let eh_frame = self.eh_frame.as_ref().ok_or(Error::OffsetOutOfBounds)?;
+                fde.unwind_info_for_address(eh_frame, bases, ctx, address)
+            }
+        }
+    }
 }
 
 impl<R: Reader> _UnwindSectionPrivate<R> for DebugFrame<R> {
@@ -1032,10 +1409,11 @@ where
             return Ok(None);
         }
 
+        let input = self.input.clone();
         match parse_cfi_entry(self.bases, &self.section, &mut self.input) {
             Err(e) => {
                 self.input.empty();
-                Err(e)
+                Err(truncated_section_error(&input, e))
             }
             Ok(None) => {
                 self.input.empty();
@@ -1060,6 +1438,12 @@ where
     }
 }
 
+result_iterator!(
+    ['bases, Section, R] CfiEntriesIter<'bases, Section, R>,
+    CieOrFde<'bases, Section, R>,
+    where R: Reader, Section: UnwindSection<R>
+);
+
 /// Either a `CommonInformationEntry` (CIE) or a `FrameDescriptionEntry` (FDE).
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CieOrFde<'bases, Section, R>
@@ -1431,6 +1815,13 @@ impl<R: Reader> CommonInformationEntry<R> {
         self.address_size
     }
 
+    /// The size of segment selectors (in bytes) in this CIE.
+    ///
+    /// This is 0 if the target does not use segmented addresses.
+    pub fn segment_size(&self) -> u8 {
+        self.segment_size
+    }
+
     /// Iterate over this CIE's initial instructions.
     ///
     /// Can be [used with
@@ -1768,6 +2159,45 @@ impl<R: Reader> FrameDescriptionEntry<R> {
         }
         Err(Error::NoUnwindInfoForAddress)
     }
+
+    /// Return the table of unwind information for this FDE, which only tracks
+    /// the CFA and return address rules.
+    ///
+    /// See [`UnwindTable::new_cfa_only`](./struct.UnwindTable.html#method.new_cfa_only).
+    #[inline]
+    pub fn cfa_rows<'a, 'ctx, Section: UnwindSection<R>, A: UnwindContextStorage<R::Offset>>(
+        &self,
+        section: &'a Section,
+        bases: &'a BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+    ) -> Result<UnwindTable<'a, 'ctx, R, A>> {
+        UnwindTable::new_cfa_only(section, bases, ctx, self)
+    }
+
+    /// Find the CFA and return address unwind information for the given
+    /// address.
+    ///
+    /// This is the same as `unwind_info_for_address`, except that the returned
+    /// row only contains a rule for the CIE's return address register.
+    pub fn cfa_unwind_info_for_address<
+        'ctx,
+        Section: UnwindSection<R>,
+        A: UnwindContextStorage<R::Offset>,
+    >(
+        &self,
+        section: &Section,
+        bases: &BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        address: u64,
+    ) -> Result<&'ctx UnwindTableRow<R::Offset, A>> {
+        let mut table = self.cfa_rows(section, bases, ctx)?;
+        while let Some(row) = table.next_row()? {
+            if row.contains(address) {
+                return Ok(table.ctx.row());
+            }
+        }
+        Err(Error::NoUnwindInfoForAddress)
+    }
 }
 
 /// # Signal Safe Methods
@@ -1822,6 +2252,18 @@ impl<R: Reader> FrameDescriptionEntry<R> {
         }
     }
 
+    /// The segment selector of the first address for which this entry has
+    /// unwind information for.
+    ///
+    /// Returns `None` if the segment size of the CIE is 0.
+    pub fn initial_segment(&self) -> Option<u64> {
+        if self.cie.segment_size > 0 {
+            Some(self.initial_segment)
+        } else {
+            None
+        }
+    }
+
     /// The first address for which this entry has unwind information for.
     pub fn initial_address(&self) -> u64 {
         self.initial_address
@@ -1936,6 +2378,102 @@ impl<T: ReaderOffset> UnwindContextStorage<T> for StoreOnHeap {
     type Stack = Box<[UnwindTableRow<T, Self>; MAX_UNWIND_STACK_DEPTH]>;
 }
 
+/// Architecture specific register rules that are not expressed in the CIE.
+///
+/// Compilers generally only emit CFI for registers that a function modifies,
+/// and ABIs define the remaining callee-saved registers as being preserved
+/// across calls. Setting the ABI of an `UnwindContext` using
+/// [`UnwindContext::set_abi`] applies these rules to every unwind table row.
+///
+/// This is implemented for the architectures in this crate that have such
+/// rules. Other ABIs can be supported by implementing this trait.
+///
+/// ```
+/// use gimli::{UnwindContext, X86_64};
+///
+/// let mut ctx = UnwindContext::<usize>::new();
+/// ctx.set_abi(&X86_64);
+/// ```
+pub trait UnwindAbi {
+    /// Return the registers that keep their value across a call, unless a
+    /// different rule is defined by the CFI.
+    ///
+    /// These registers have the `RegisterRule::SameValue` rule before the CIE's
+    /// initial instructions are evaluated.
+    fn same_value_registers(&self) -> &'static [Register];
+}
+
+/// The callee-saved registers in the System V i386 ABI.
+impl UnwindAbi for crate::X86 {
+    fn same_value_registers(&self) -> &'static [Register] {
+        &[Self::EBX, Self::EBP, Self::ESI, Self::EDI]
+    }
+}
+
+/// The callee-saved registers in the System V x86-64 ABI.
+impl UnwindAbi for crate::X86_64 {
+    fn same_value_registers(&self) -> &'static [Register] {
+        &[
+            Self::RBX,
+            Self::RBP,
+            Self::R12,
+            Self::R13,
+            Self::R14,
+            Self::R15,
+        ]
+    }
+}
+
+/// The callee-saved registers in the AArch64 procedure call standard.
+///
+/// This includes the platform register X18, which is not modified by code
+/// that respects the platform ABI.
+impl UnwindAbi for crate::AArch64 {
+    fn same_value_registers(&self) -> &'static [Register] {
+        &[
+            Self::X18,
+            Self::X19,
+            Self::X20,
+            Self::X21,
+            Self::X22,
+            Self::X23,
+            Self::X24,
+            Self::X25,
+            Self::X26,
+            Self::X27,
+            Self::X28,
+            Self::X29,
+        ]
+    }
+}
+
+/// The callee-saved registers in the s390x ELF ABI.
+///
+/// This does not include the stack pointer `R15`, since its value in the caller
+/// is 160 less than the CFA, and so it must be described by the CFI.
+impl UnwindAbi for crate::S390x {
+    fn same_value_registers(&self) -> &'static [Register] {
+        &[
+            Self::R6,
+            Self::R7,
+            Self::R8,
+            Self::R9,
+            Self::R10,
+            Self::R11,
+            Self::R12,
+            Self::R13,
+            Self::F8,
+            Self::F9,
+            Self::F10,
+            Self::F11,
+            Self::F12,
+            Self::F13,
+            Self::F14,
+            Self::F15,
+        ]
+    }
+}
+
 /// Common context needed when evaluating the call frame unwinding information.
 ///
 /// By default, this structure is small and allocates its internal storage
@@ -1987,6 +2525,10 @@ pub struct UnwindContext<T: ReaderOffset, A: UnwindContextStorage<T> = StoreOnHe
     initial_rule: Option<(Register, RegisterRule<T>)>,
 
     is_initialized: bool,
+
+    // Registers which have the `SameValue` rule before the CIE's initial
+    // instructions are evaluated. See `UnwindAbi`.
+    same_value_registers: &'static [Register],
 }
 
 impl<T: ReaderOffset, S: UnwindContextStorage<T>> Debug for UnwindContext<T, S> {
@@ -1995,6 +2537,7 @@ impl<T: ReaderOffset, S: UnwindContextStorage<T>> Debug for UnwindContext<T, S>
             .field("stack", &self.stack)
             .field("initial_rule", &self.initial_rule)
             .field("is_initialized", &self.is_initialized)
+            .field("same_value_registers", &self.same_value_registers)
             .finish()
     }
 }
@@ -2024,17 +2567,32 @@ impl<T: ReaderOffset, A: UnwindContextStorage<T>> UnwindContext<T, A> {
             stack: Default::default(),
             initial_rule: None,
             is_initialized: false,
+            same_value_registers: &[],
         };
         ctx.reset();
         ctx
     }
 
+    /// Set the ABI that is used to determine the initial register rules.
+    ///
+    /// These rules apply before the CIE's initial instructions are evaluated,
+    /// and so they are overridden by any rules that the CIE or FDE define.
+    /// They are also the rules that `DW_CFA_restore` restores to if the CIE
+    /// doesn't define a rule for a register.
+    ///
+    /// By default, the initial rule for every register is
+    /// `RegisterRule::Undefined`.
+    pub fn set_abi<Abi: UnwindAbi>(&mut self, abi: &Abi) {
+        self.same_value_registers = abi.same_value_registers();
+    }
+
     /// Run the CIE's initial instructions and initialize this `UnwindContext`.
     fn initialize<Section, R>(
         &mut self,
         section: &Section,
         bases: &BaseAddresses,
         cie: &CommonInformationEntry<R>,
+        cfa_only: bool,
     ) -> Result<()>
     where
         R: Reader<Offset = T>,
@@ -2042,8 +2600,15 @@ impl<T: ReaderOffset, A: UnwindContextStorage<T>> UnwindContext<T, A> {
     {
         // Always reset because previous initialization failure may leave dirty state.
         self.reset();
+        for &register in self.same_value_registers {
+            // Only the return address rule is tracked for a CFA-only table.
+            if cfa_only && register != cie.return_address_register() {
+                continue;
+            }
+            self.set_register_rule(register, RegisterRule::SameValue)?;
+        }
 
-        let mut table = UnwindTable::new_for_cie(section, bases, self, cie);
+        let mut table = UnwindTable::new_for_cie(section, bases, self, cie, cfa_only);
         while table.next_row()?.is_some() {}
 
         self.save_initial_rules()?;
@@ -2203,6 +2768,10 @@ pub struct UnwindTable<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset> =
     last_end_address: u64,
     returned_last_row: bool,
     current_row_valid: bool,
+    // If set, then only rules for this register (the return address register)
+    // are recorded, and rules for all other registers are ignored.
+    only_register: Option<Register>,
+    address_size: u8,
     instructions: CallFrameInstructionIter<'a, R>,
     ctx: &'ctx mut UnwindContext<R::Offset, A>,
 }
@@ -2220,8 +2789,28 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
         ctx: &'ctx mut UnwindContext<R::Offset, A>,
         fde: &FrameDescriptionEntry<R>,
     ) -> Result<Self> {
-        ctx.initialize(section, bases, fde.cie())?;
-        Ok(Self::new_for_fde(section, bases, ctx, fde))
+        ctx.initialize(section, bases, fde.cie(), false)?;
+        Ok(Self::new_for_fde(section, bases, ctx, fde, false))
+    }
+
+    /// Construct a new `UnwindTable` for the given `FrameDescriptionEntry`'s
+    /// CFI unwinding program, which only tracks the CFA and return address
+    /// rules.
+    ///
+    /// Instructions that define rules for any register other than the CIE's
+    /// return address register are skipped, so the rows of this table will
+    /// never contain rules for those registers. This is sufficient for a
+    /// stack walker that only needs to recover the caller's PC and stack
+    /// pointer, and avoids the cost of maintaining and copying the rules for
+    /// callee-saved registers.
+    pub fn new_cfa_only<Section: UnwindSection<R>>(
+        section: &'a Section,
+        bases: &'a BaseAddresses,
+        ctx: &'ctx mut UnwindContext<R::Offset, A>,
+        fde: &FrameDescriptionEntry<R>,
+    ) -> Result<Self> {
+        ctx.initialize(section, bases, fde.cie(), true)?;
+        Ok(Self::new_for_fde(section, bases, ctx, fde, true))
     }
 
     fn new_for_fde<Section: UnwindSection<R>>(
@@ -2229,6 +2818,7 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
         bases: &'a BaseAddresses,
         ctx: &'ctx mut UnwindContext<R::Offset, A>,
         fde: &FrameDescriptionEntry<R>,
+        cfa_only: bool,
     ) -> Self {
         assert!(ctx.stack.len() >= 1);
         UnwindTable {
@@ -2238,6 +2828,12 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
             last_end_address: fde.initial_address().wrapping_add(fde.len()),
             returned_last_row: false,
             current_row_valid: false,
+            only_register: if cfa_only {
+                Some(fde.cie().return_address_register())
+            } else {
+                None
+            },
+            address_size: fde.cie().address_size(),
             instructions: fde.instructions(section, bases),
             ctx,
         }
@@ -2248,6 +2844,7 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
         bases: &'a BaseAddresses,
         ctx: &'ctx mut UnwindContext<R::Offset, A>,
         cie: &CommonInformationEntry<R>,
+        cfa_only: bool,
     ) -> Self {
         assert!(ctx.stack.len() >= 1);
         UnwindTable {
@@ -2257,6 +2854,12 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
             last_end_address: 0,
             returned_last_row: false,
             current_row_valid: false,
+            only_register: if cfa_only {
+                Some(cie.return_address_register())
+            } else {
+                None
+            },
+            address_size: cie.address_size(),
             instructions: cie.instructions(section, bases),
             ctx,
         }
@@ -2308,6 +2911,17 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
         }
     }
 
+    fn set_register_rule(
+        &mut self,
+        register: Register,
+        rule: RegisterRule<R::Offset>,
+    ) -> Result<()> {
+        match self.only_register {
+            Some(only) if only != register => Ok(()),
+            _ => self.ctx.set_register_rule(register, rule),
+        }
+    }
+
     /// Evaluate one call frame instruction. Return `Ok(true)` if the row is
     /// complete, `Ok(false)` otherwise.
     fn evaluate(&mut self, instruction: CallFrameInstruction<R::Offset>) -> Result<bool> {
@@ -2389,65 +3003,58 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
 
             // Instructions that define register rules.
             Undefined { register } => {
-                self.ctx
-                    .set_register_rule(register, RegisterRule::Undefined)?;
+                self.set_register_rule(register, RegisterRule::Undefined)?;
             }
             SameValue { register } => {
-                self.ctx
-                    .set_register_rule(register, RegisterRule::SameValue)?;
+                self.set_register_rule(register, RegisterRule::SameValue)?;
             }
             Offset {
                 register,
                 factored_offset,
             } => {
                 let offset = Wrapping(factored_offset as i64) * self.data_alignment_factor;
-                self.ctx
-                    .set_register_rule(register, RegisterRule::Offset(offset.0))?;
+                self.set_register_rule(register, RegisterRule::Offset(offset.0))?;
             }
             OffsetExtendedSf {
                 register,
                 factored_offset,
             } => {
                 let offset = Wrapping(factored_offset) * self.data_alignment_factor;
-                self.ctx
-                    .set_register_rule(register, RegisterRule::Offset(offset.0))?;
+                self.set_register_rule(register, RegisterRule::Offset(offset.0))?;
             }
             ValOffset {
                 register,
                 factored_offset,
             } => {
                 let offset = Wrapping(factored_offset as i64) * self.data_alignment_factor;
-                self.ctx
-                    .set_register_rule(register, RegisterRule::ValOffset(offset.0))?;
+                self.set_register_rule(register, RegisterRule::ValOffset(offset.0))?;
             }
             ValOffsetSf {
                 register,
                 factored_offset,
             } => {
                 let offset = Wrapping(factored_offset) * self.data_alignment_factor;
-                self.ctx
-                    .set_register_rule(register, RegisterRule::ValOffset(offset.0))?;
+                self.set_register_rule(register, RegisterRule::ValOffset(offset.0))?;
             }
             Register {
                 dest_register,
                 src_register,
             } => {
-                self.ctx
-                    .set_register_rule(dest_register, RegisterRule::Register(src_register))?;
+                self.set_register_rule(dest_register, RegisterRule::Register(src_register))?;
             }
             Expression {
                 register,
                 expression,
             } => {
                 let expression = RegisterRule::Expression(expression);
-                self.ctx.set_register_rule(register, expression)?;
+                self.set_register_rule(register, expression)?;
             }
             ValExpression {
                 register,
                 expression,
             } => {
                 let expression = RegisterRule::ValExpression(expression);
-                self.ctx.set_register_rule(register, expression)?;
+                self.set_register_rule(register, expression)?;
             }
             Restore { register } => {
                 let initial_rule = if let Some(rule) = self.ctx.get_initial_rule(register) {
@@ -2458,7 +3065,7 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
                     return Err(Error::CfiInstructionInInvalidContext);
                 };
 
-                self.ctx.set_register_rule(register, initial_rule)?;
+                self.set_register_rule(register, initial_rule)?;
             }
 
             // Row push and pop instructions.
@@ -2486,10 +3093,24 @@ impl<'a, 'ctx, R: Reader, A: UnwindContextStorage<R::Offset>> UnwindTable<'a, 'c
                     RegisterRule::Constant(value) => value,
                     _ => return Err(Error::CfiInstructionInInvalidContext),
                 };
+                // The return address signing state is always needed to recover
+                // the return address, so this bypasses `only_register`.
                 self.ctx
                     .set_register_rule(register, RegisterRule::Constant(value ^ 1))?;
             }
 
+            // SPARC extension.
+            WindowSave => {
+                let word_size = i64::from(self.address_size);
+                for register in 16..32 {
+                    let offset = (register - 16) * word_size;
+                    self.set_register_rule(
+                        Register(register as u16),
+                        RegisterRule::Offset(offset),
+                    )?;
+                }
+            }
+
             // No operation.
             Nop => {}
         };
@@ -2597,6 +3218,10 @@ impl<T: ReaderOffset, S: UnwindContextStorage<T>> RegisterRuleMap<T, S> {
     fn iter(&self) -> RegisterRuleIter<'_, T> {
         RegisterRuleIter(self.rules.iter())
     }
+
+    fn len(&self) -> usize {
+        self.rules.len()
+    }
 }
 
 impl<'a, R, S: UnwindContextStorage<R>> FromIterator<&'a (Register, RegisterRule<R>)>
@@ -2645,6 +3270,60 @@ where
 
 impl<T, S: UnwindContextStorage<T>> Eq for RegisterRuleMap<T, S> where T: ReaderOffset + Eq {}
 
+#[cfg(feature = "serde")]
+impl<T, S> serde::Serialize for RegisterRuleMap<T, S>
+where
+    T: ReaderOffset + serde::Serialize,
+    S: UnwindContextStorage<T>,
+{
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> core::result::Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(&*self.rules)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, S> serde::Deserialize<'de> for RegisterRuleMap<T, S>
+where
+    T: ReaderOffset + serde::Deserialize<'de>,
+    S: UnwindContextStorage<T>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        struct Visitor<T, S>(core::marker::PhantomData<(T, S)>);
+
+        impl<'de, T, S> serde::de::Visitor<'de> for Visitor<T, S>
+        where
+            T: ReaderOffset + serde::Deserialize<'de>,
+            S: UnwindContextStorage<T>,
+        {
+            type Value = RegisterRuleMap<T, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence of register rules")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> core::result::Result<Self::Value, A::Error> {
+                let mut rules = RegisterRuleMap::default();
+                while let Some((register, rule)) = seq.next_element()? {
+                    rules
+                        .set(register, rule)
+                        .map_err(serde::de::Error::custom)?;
+                }
+                Ok(rules)
+            }
+        }
+
+        deserializer.deserialize_seq(Visitor(core::marker::PhantomData))
+    }
+}
+
 /// An unordered iterator for register rules.
 #[derive(Debug, Clone)]
 pub struct RegisterRuleIter<'iter, T>(::core::slice::Iter<'iter, (Register, RegisterRule<T>)>)
@@ -2657,11 +3336,25 @@ impl<'iter, T: ReaderOffset> Iterator for RegisterRuleIter<'iter, T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
+impl<'iter, T: ReaderOffset> ExactSizeIterator for RegisterRuleIter<'iter, T> {}
+
 /// A row in the virtual unwind table that describes how to find the values of
 /// the registers in the *previous* frame for a range of PC addresses.
 #[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>"
+    ))
+)]
 pub struct UnwindTableRow<T: ReaderOffset, S: UnwindContextStorage<T> = StoreOnHeap> {
     start_address: u64,
     end_address: u64,
@@ -2815,10 +3508,19 @@ impl<T: ReaderOffset, S: UnwindContextStorage<T>> UnwindTableRow<T, S> {
     pub fn registers(&self) -> RegisterRuleIter<'_, T> {
         self.registers.iter()
     }
+
+    /// Return the number of registers that have a defined rule in this row.
+    ///
+    /// This is the number of items returned by `registers`, and does not
+    /// include the CFA rule.
+    pub fn rule_count(&self) -> usize {
+        self.registers.len()
+    }
 }
 
 /// The canonical frame address (CFA) recovery rules.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CfaRule<T: ReaderOffset> {
     /// The CFA is given offset from the given register's value.
     RegisterAndOffset {
@@ -2859,6 +3561,7 @@ impl<T: ReaderOffset> CfaRule<T> {
 /// has been saved and the rule to find the value for the register in the
 /// previous frame."
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum RegisterRule<T: ReaderOffset> {
     /// > A register that has this rule has no recoverable value in the previous
@@ -2922,8 +3625,8 @@ pub enum CallFrameInstruction<T: ReaderOffset> {
         address: u64,
     },
 
-    /// The `AdvanceLoc` instruction is used for all of `DW_CFA_advance_loc` and
-    /// `DW_CFA_advance_loc{1,2,4}`.
+    /// The `AdvanceLoc` instruction is used for all of `DW_CFA_advance_loc`,
+    /// `DW_CFA_advance_loc{1,2,4}` and `DW_CFA_MIPS_advance_loc8`.
     ///
     /// > 2. DW_CFA_advance_loc
     /// >
@@ -3194,6 +3897,16 @@ pub enum CallFrameInstruction<T: ReaderOffset> {
     /// > Information Entry (CIE) and Frame Descriptor Entry (FDE) program sequence.
     NegateRaState,
 
+    /// > DW_CFA_GNU_window_save
+    /// >
+    /// > SPARC Extension
+    /// >
+    /// > The DW_CFA_GNU_window_save instruction takes no operands. It indicates
+    /// > that a new register window has been saved. The required action is to
+    /// > set the rule for the in and local registers (numbers 16 through 31)
+    /// > to be saved at consecutive words starting at the CFA.
+    WindowSave,
+
     // 6.4.2.5 Padding Instruction
     /// > 1. DW_CFA_nop
     /// >
@@ -3270,6 +3983,13 @@ impl<T: ReaderOffset> CallFrameInstruction<T> {
                 Ok(CallFrameInstruction::AdvanceLoc { delta })
             }
 
+            constants::DW_CFA_MIPS_advance_loc8 => {
+                use core::convert::TryFrom;
+                let delta = input.read_u64()?;
+                let delta = u32::try_from(delta).map_err(|_| Error::UnsupportedOffset)?;
+                Ok(CallFrameInstruction::AdvanceLoc { delta })
+            }
+
             constants::DW_CFA_offset_extended => {
                 let register = input.read_uleb128().and_then(Register::from_u64)?;
                 let offset = input.read_uleb128()?;
@@ -3406,6 +4126,10 @@ impl<T: ReaderOffset> CallFrameInstruction<T> {
                 Ok(CallFrameInstruction::NegateRaState)
             }
 
+            constants::DW_CFA_GNU_window_save if vendor == Vendor::Sparc => {
+                Ok(CallFrameInstruction::WindowSave)
+            }
+
             otherwise => Err(Error::UnknownCallFrameInstruction(otherwise)),
         }
     }
@@ -3455,11 +4179,14 @@ impl<'a, R: Reader> fallible_iterator::FallibleIterator for CallFrameInstruction
     }
 }
 
+result_iterator!(['a, R: Reader] CallFrameInstructionIter<'a, R>, CallFrameInstruction<R::Offset>);
+
 /// The location of a DWARF expression within an unwind section.
 ///
 /// This is stored as an offset and length within the section instead of as a
 /// `Reader` to avoid lifetime issues when reusing [`UnwindContext`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnwindExpression<T: ReaderOffset> {
     /// The offset of the expression within the section.
     pub offset: T,
@@ -3499,6 +4226,7 @@ fn parse_pointer_encoding<R: Reader>(input: &mut R) -> Result<constants::DwEhPe>
 
 /// A decoded pointer.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pointer {
     /// This value is the decoded pointer value.
     Direct(u64),
@@ -4257,8 +4985,13 @@ mod tests {
             Ok(cie.clone())
         };
 
-        assert_eq!(parse_fde(debug_frame, rest, get_cie), Ok(fde));
+        let parsed = parse_fde(debug_frame, rest, get_cie);
+        assert_eq!(parsed, Ok(fde));
         assert_eq!(*rest, EndianSlice::new(&expected_rest, LittleEndian));
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.cie().segment_size(), 4);
+        assert_eq!(parsed.initial_segment(), Some(0xbadb_ad11));
+        assert_eq!(parsed.initial_address(), 0xfeed_beef);
     }
 
     #[test]
@@ -4767,6 +5500,35 @@ mod tests {
         assert_eq!(*input, EndianSlice::new(&expected_rest, LittleEndian));
     }
 
+    #[test]
+    fn test_parse_cfi_instruction_mips_advance_loc8() {
+        let expected_rest = [1, 2, 3, 4];
+        let expected_delta = 1 << 20;
+        let section = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_MIPS_advance_loc8.0)
+            .L64(expected_delta)
+            .append_bytes(&expected_rest);
+        let contents = section.get_contents().unwrap();
+        let input = &mut EndianSlice::new(&contents, LittleEndian);
+        assert_eq!(
+            parse_cfi_instruction(input, 8),
+            Ok(CallFrameInstruction::AdvanceLoc {
+                delta: expected_delta as u32,
+            })
+        );
+        assert_eq!(*input, EndianSlice::new(&expected_rest, LittleEndian));
+
+        let section = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_MIPS_advance_loc8.0)
+            .L64(1 << 32);
+        let contents = section.get_contents().unwrap();
+        let input = &mut EndianSlice::new(&contents, LittleEndian);
+        assert_eq!(
+            parse_cfi_instruction(input, 8),
+            Err(Error::UnsupportedOffset)
+        );
+    }
+
     #[test]
     fn test_parse_cfi_instruction_offset_extended() {
         let expected_rest = [1, 2, 3, 4];
@@ -5194,6 +5956,36 @@ mod tests {
         assert_eq!(*input, EndianSlice::new(&expected_rest, LittleEndian));
     }
 
+    #[test]
+    fn test_parse_cfi_instruction_window_save() {
+        let expected_rest = [1, 2, 3, 4];
+        let section = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_GNU_window_save.0)
+            .append_bytes(&expected_rest);
+        let contents = section.get_contents().unwrap();
+        let parameters = &PointerEncodingParameters {
+            bases: &SectionBaseAddresses::default(),
+            func_base: None,
+            address_size: 8,
+            section: &EndianSlice::default(),
+        };
+
+        let input = &mut EndianSlice::new(&contents, LittleEndian);
+        assert_eq!(
+            CallFrameInstruction::parse(input, None, parameters, Vendor::Sparc),
+            Ok(CallFrameInstruction::WindowSave)
+        );
+        assert_eq!(*input, EndianSlice::new(&expected_rest, LittleEndian));
+
+        let input = &mut EndianSlice::new(&contents, LittleEndian);
+        assert_eq!(
+            CallFrameInstruction::parse(input, None, parameters, Vendor::Default),
+            Err(Error::UnknownCallFrameInstruction(
+                constants::DW_CFA_GNU_window_save
+            ))
+        );
+    }
+
     #[test]
     fn test_parse_cfi_instruction_unknown_instruction() {
         let expected_rest = [1, 2, 3, 4];
@@ -5307,8 +6099,10 @@ mod tests {
             let section = &DebugFrame::from(EndianSlice::default());
             let bases = &BaseAddresses::default();
             let mut table = match fde {
-                Some(fde) => UnwindTable::new_for_fde(section, bases, &mut initial_ctx, &fde),
-                None => UnwindTable::new_for_cie(section, bases, &mut initial_ctx, &cie),
+                Some(fde) => {
+                    UnwindTable::new_for_fde(section, bases, &mut initial_ctx, &fde, false)
+                }
+                None => UnwindTable::new_for_cie(section, bases, &mut initial_ctx, &cie, false),
             };
             for (expected_result, instruction) in instructions.as_ref() {
                 assert_eq!(*expected_result, table.evaluate(instruction.clone()));
@@ -5755,6 +6549,23 @@ mod tests {
         assert_eval(ctx, expected, cie, None, instructions);
     }
 
+    #[test]
+    fn test_eval_window_save() {
+        let cie = make_test_cie();
+        let ctx = UnwindContext::new();
+        let mut expected = ctx.clone();
+        for register in 16..32 {
+            expected
+                .set_register_rule(
+                    Register(register),
+                    RegisterRule::Offset(i64::from(register - 16) * cie.address_size as i64),
+                )
+                .unwrap();
+        }
+        let instructions = [(Ok(false), CallFrameInstruction::WindowSave)];
+        assert_eval(ctx, expected, cie, None, instructions);
+    }
+
     #[test]
     fn test_eval_negate_ra_state() {
         let cie = make_test_cie();
@@ -6362,6 +7173,196 @@ mod tests {
         assert_eq!(result.unwrap_err(), Error::NoUnwindInfoForAddress);
     }
 
+    #[test]
+    fn test_cfa_unwind_info_for_address() {
+        let instrs = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_def_cfa.0)
+            .uleb(7)
+            .uleb(8)
+            .D8(constants::DW_CFA_offset.0 | 16)
+            .uleb(1)
+            .D8(constants::DW_CFA_offset.0 | 6)
+            .uleb(2)
+            .D8(constants::DW_CFA_advance_loc1.0)
+            .D8(4)
+            .D8(constants::DW_CFA_def_cfa_offset.0)
+            .uleb(16)
+            .D8(constants::DW_CFA_offset.0 | 3)
+            .uleb(3)
+            .D8(constants::DW_CFA_remember_state.0)
+            .D8(constants::DW_CFA_same_value.0)
+            .uleb(16);
+        let instrs = instrs.get_contents().unwrap();
+
+        let mut cie = CommonInformationEntry {
+            offset: 0,
+            length: 0,
+            format: Format::Dwarf32,
+            version: 4,
+            augmentation: None,
+            address_size: 8,
+            segment_size: 0,
+            code_alignment_factor: 1,
+            data_alignment_factor: -8,
+            return_address_register: Register(16),
+            initial_instructions: EndianSlice::new(&[], LittleEndian),
+        };
+
+        let mut fde = FrameDescriptionEntry {
+            offset: 0,
+            length: 0,
+            format: Format::Dwarf32,
+            cie: cie.clone(),
+            initial_segment: 0,
+            initial_address: 0x1000,
+            address_range: 0x20,
+            augmentation: None,
+            instructions: EndianSlice::new(&instrs, LittleEndian),
+        };
+
+        let cie_location = Label::new();
+        let kind = debug_frame_le();
+        let section = Section::with_endian(kind.endian())
+            .mark(&cie_location)
+            .cie(kind, None, &mut cie)
+            .fde(kind, &cie_location, &mut fde);
+        section.start().set_const(0);
+        let contents = section.get_contents().unwrap();
+        let debug_frame = kind.section(&contents);
+
+        let bases = Default::default();
+        let mut ctx = Box::new(UnwindContext::new());
+
+        let row = debug_frame
+            .cfa_unwind_info_for_address(&bases, &mut ctx, 0x1000, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(
+            *row.cfa(),
+            CfaRule::RegisterAndOffset {
+                register: Register(7),
+                offset: 8,
+            }
+        );
+        assert_eq!(
+            row.registers().cloned().collect::<Vec<_>>(),
+            [(Register(16), RegisterRule::Offset(-8))]
+        );
+
+        let row = debug_frame
+            .cfa_unwind_info_for_address(&bases, &mut ctx, 0x1010, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(
+            *row.cfa(),
+            CfaRule::RegisterAndOffset {
+                register: Register(7),
+                offset: 16,
+            }
+        );
+        assert_eq!(row.register(Register(16)), RegisterRule::SameValue);
+        assert_eq!(row.register(Register(6)), RegisterRule::Undefined);
+        assert_eq!(row.register(Register(3)), RegisterRule::Undefined);
+
+        // The full evaluation still sees the other registers.
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x1010, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(Register(6)), RegisterRule::Offset(-16));
+        assert_eq!(row.register(Register(3)), RegisterRule::Offset(-24));
+    }
+
+    #[test]
+    fn test_unwind_context_abi() {
+        let initial_instructions = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_def_cfa.0)
+            .uleb(7)
+            .uleb(8)
+            .D8(constants::DW_CFA_offset.0 | 16)
+            .uleb(1);
+        let initial_instructions = initial_instructions.get_contents().unwrap();
+
+        let instrs = Section::with_endian(Endian::Little)
+            .D8(constants::DW_CFA_advance_loc1.0)
+            .D8(4)
+            .D8(constants::DW_CFA_offset.0 | 6)
+            .uleb(2)
+            .D8(constants::DW_CFA_advance_loc1.0)
+            .D8(4)
+            .D8(constants::DW_CFA_restore.0 | 6);
+        let instrs = instrs.get_contents().unwrap();
+
+        let mut cie = CommonInformationEntry {
+            offset: 0,
+            length: 0,
+            format: Format::Dwarf32,
+            version: 4,
+            augmentation: None,
+            address_size: 8,
+            segment_size: 0,
+            code_alignment_factor: 1,
+            data_alignment_factor: -8,
+            return_address_register: crate::X86_64::RA,
+            initial_instructions: EndianSlice::new(&initial_instructions, LittleEndian),
+        };
+
+        let mut fde = FrameDescriptionEntry {
+            offset: 0,
+            length: 0,
+            format: Format::Dwarf32,
+            cie: cie.clone(),
+            initial_segment: 0,
+            initial_address: 0x1000,
+            address_range: 0x20,
+            augmentation: None,
+            instructions: EndianSlice::new(&instrs, LittleEndian),
+        };
+
+        let cie_location = Label::new();
+        let kind = debug_frame_le();
+        let section = Section::with_endian(kind.endian())
+            .mark(&cie_location)
+            .cie(kind, None, &mut cie)
+            .fde(kind, &cie_location, &mut fde);
+        section.start().set_const(0);
+        let contents = section.get_contents().unwrap();
+        let debug_frame = kind.section(&contents);
+
+        let bases = Default::default();
+        let mut ctx = Box::new(UnwindContext::new());
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x1000, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(crate::X86_64::RBX), RegisterRule::Undefined);
+
+        ctx.set_abi(&crate::X86_64);
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x1000, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(crate::X86_64::RBX), RegisterRule::SameValue);
+        assert_eq!(row.register(crate::X86_64::RBP), RegisterRule::SameValue);
+        assert_eq!(row.register(crate::X86_64::RAX), RegisterRule::Undefined);
+        assert_eq!(row.register(crate::X86_64::RA), RegisterRule::Offset(-8));
+
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x1004, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(crate::X86_64::RBP), RegisterRule::Offset(-16));
+
+        // `DW_CFA_restore` restores the ABI's rule.
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x1008, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(crate::X86_64::RBP), RegisterRule::SameValue);
+        assert_eq!(row.register(crate::X86_64::RBX), RegisterRule::SameValue);
+
+        // The ABI's rules are also filtered for a CFA-only table.
+        let row = debug_frame
+            .cfa_unwind_info_for_address(&bases, &mut ctx, 0x1000, DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.register(crate::X86_64::RBX), RegisterRule::Undefined);
+        assert_eq!(row.register(crate::X86_64::RBP), RegisterRule::Undefined);
+        assert_eq!(row.register(crate::X86_64::RA), RegisterRule::Offset(-8));
+    }
+
     #[test]
     fn test_eh_frame_hdr_unknown_version() {
         let bases = BaseAddresses::default();
@@ -6668,6 +7669,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fde_table() {
+        let mut cie = make_test_cie();
+        cie.format = Format::Dwarf32;
+
+        let start_of_cie = Label::new();
+        let end_of_cie = Label::new();
+
+        let kind = debug_frame_le();
+        let section = Section::with_endian(kind.endian())
+            .mark(&start_of_cie)
+            .cie(kind, None, &mut cie)
+            .mark(&end_of_cie);
+
+        let make_fde = |initial_address, address_range| FrameDescriptionEntry {
+            offset: 0,
+            length: 0,
+            format: Format::Dwarf32,
+            cie: cie.clone(),
+            initial_segment: 0,
+            initial_address,
+            address_range,
+            augmentation: None,
+            instructions: EndianSlice::new(&[], LittleEndian),
+        };
+        // The FDEs are deliberately not in address order.
+        let mut fde1 = make_fde(20, 8);
+        let mut fde2 = make_fde(9, 4);
+        let mut fde3 = make_fde(15, 0);
+
+        let start_of_fde1 = Label::new();
+        let start_of_fde2 = Label::new();
+        let start_of_fde3 = Label::new();
+
+        let section = section
+            .mark(&start_of_fde1)
+            .fde(kind, &start_of_cie, &mut fde1)
+            .mark(&start_of_fde2)
+            .fde(kind, &start_of_cie, &mut fde2)
+            .mark(&start_of_fde3)
+            .fde(kind, &start_of_cie, &mut fde3);
+
+        section.start().set_const(0);
+        let section = section.get_contents().unwrap();
+        let debug_frame = kind.section(&section);
+        let bases = BaseAddresses::default();
+
+        let f = |_: &_, _: &_, o: DebugFrameOffset| {
+            assert_eq!(o, DebugFrameOffset(start_of_cie.value().unwrap() as usize));
+            Ok(cie.clone())
+        };
+        let table = debug_frame.fde_table(&bases, f).unwrap();
+        // The empty FDE is omitted.
+        assert_eq!(table.len(), 2);
+        assert!(table.allocated_bytes() >= 2 * mem::size_of::<FdeTableEntry<DebugFrameOffset>>());
+        assert_eq!(
+            table.entries(),
+            &[
+                FdeTableEntry {
+                    initial_address: 9,
+                    len: 4,
+                    offset: DebugFrameOffset(start_of_fde2.value().unwrap() as usize),
+                },
+                FdeTableEntry {
+                    initial_address: 20,
+                    len: 8,
+                    offset: DebugFrameOffset(start_of_fde1.value().unwrap() as usize),
+                },
+            ]
+        );
+
+        assert_eq!(table.find(8), None);
+        assert_eq!(table.find(9), Some(&table.entries()[0]));
+        assert_eq!(table.find(12), Some(&table.entries()[0]));
+        assert_eq!(table.find(13), None);
+        assert_eq!(table.find(27), Some(&table.entries()[1]));
+        assert_eq!(table.find(28), None);
+
+        assert_eq!(table.fde_for_address(&debug_frame, &bases, 10, f), Ok(fde2));
+        assert_eq!(table.fde_for_address(&debug_frame, &bases, 21, f), Ok(fde1));
+        assert_eq!(
+            table.fde_for_address(&debug_frame, &bases, 15, f),
+            Err(Error::NoUnwindInfoForAddress)
+        );
+    }
+
+    #[test]
+    fn test_fde_table_nested() {
+        let entry = |initial_address, len, offset| FdeTableEntry {
+            initial_address,
+            len,
+            offset,
+        };
+        let table = FdeTable::from_sorted(vec![
+            entry(10, 20, 0),
+            entry(12, 4, 1),
+            entry(18, 4, 2),
+            entry(40, 4, 3),
+        ]);
+        let find = |address| table.find(address).map(|entry| entry.offset);
+        assert_eq!(find(9), None);
+        assert_eq!(find(10), Some(0));
+        assert_eq!(find(13), Some(1));
+        assert_eq!(find(16), Some(0));
+        assert_eq!(find(18), Some(2));
+        assert_eq!(find(22), Some(0));
+        assert_eq!(find(30), None);
+        assert_eq!(find(43), Some(3));
+        assert_eq!(find(44), None);
+    }
+
     #[test]
     fn test_eh_frame_stops_at_zero_length() {
         let section = Section::with_endian(Endian::Little).L32(0);
@@ -7341,6 +8453,10 @@ mod tests {
         assert!(found0);
         assert!(found1);
         assert!(found2);
+
+        assert_eq!(row.rule_count(), 3);
+        assert_eq!(row.registers().len(), 3);
+        assert_eq!(UnwindTableRow::<usize>::default().rule_count(), 0);
     }
 
     #[test]
@@ -7918,4 +9034,65 @@ mod tests {
             unwind_one(&mut ctx, &data2);
         }
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_unwind_table_row_serde() {
+        use serde_test::{assert_tokens, Token};
+
+        let mut registers = RegisterRuleMap::default();
+        registers
+            .set(Register(16), RegisterRule::Offset(-8))
+            .unwrap();
+        let row = UnwindTableRow::<usize> {
+            start_address: 1,
+            end_address: 2,
+            saved_args_size: 0,
+            cfa: CfaRule::RegisterAndOffset {
+                register: Register(7),
+                offset: 8,
+            },
+            registers,
+        };
+        assert_tokens(
+            &row,
+            &[
+                Token::Struct {
+                    name: "UnwindTableRow",
+                    len: 5,
+                },
+                Token::Str("start_address"),
+                Token::U64(1),
+                Token::Str("end_address"),
+                Token::U64(2),
+                Token::Str("saved_args_size"),
+                Token::U64(0),
+                Token::Str("cfa"),
+                Token::StructVariant {
+                    name: "CfaRule",
+                    variant: "RegisterAndOffset",
+                    len: 2,
+                },
+                Token::Str("register"),
+                Token::NewtypeStruct { name: "Register" },
+                Token::U16(7),
+                Token::Str("offset"),
+                Token::I64(8),
+                Token::StructVariantEnd,
+                Token::Str("registers"),
+                Token::Seq { len: Some(1) },
+                Token::Tuple { len: 2 },
+                Token::NewtypeStruct { name: "Register" },
+                Token::U16(16),
+                Token::NewtypeVariant {
+                    name: "RegisterRule",
+                    variant: "Offset",
+                },
+                Token::I64(-8),
+                Token::TupleEnd,
+                Token::SeqEnd,
+                Token::StructEnd,
+            ],
+        );
+    }
 }
@@ -0,0 +1,759 @@
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::common::DebugInfoOffset;
+use crate::constants;
+use crate::read::{
+    AttributeValue, DieReference, Dwarf, Error, Expression, Operation, Range, Reader, ReaderOffset,
+    Result, UnitRef,
+};
+
+/// An attribute value that has been resolved so that it can be compared with
+/// the values from a different DWARF input.
+///
+/// The values don't depend on the attribute form, or on the layout of the
+/// sections. For example, strings are resolved regardless of whether they are
+/// inline or in a string section, and references are given as the path of
+/// the entry that they refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemanticValue {
+    /// An address. Address indices are resolved.
+    Address(u64),
+    /// An unsigned constant, including the values of constants such as
+    /// `DW_AT_language`.
+    Unsigned(u64),
+    /// A signed constant.
+    Signed(i64),
+    /// A flag.
+    Flag(bool),
+    /// A block of bytes.
+    Block(Vec<u8>),
+    /// A DWARF expression.
+    Expression(Vec<SemanticOperation>),
+    /// A string.
+    String(Vec<u8>),
+    /// A reference to another entry, given as its path. See `Difference::path`.
+    ///
+    /// This is `None` if the reference is not to an entry in the `.debug_info`
+    /// section.
+    Reference(Option<Vec<usize>>),
+    /// A source file, given as its full path.
+    ///
+    /// This is `None` if the file index is not in the line number program.
+    File(Option<String>),
+    /// A list of address ranges.
+    Ranges(Vec<Range>),
+    /// A location list, given as the address range and expression of each entry.
+    Locations(Vec<(Range, Vec<SemanticOperation>)>),
+    /// An offset into another section, such as the value of `DW_AT_stmt_list`.
+    ///
+    /// The contents of the other section are not compared.
+    Offset,
+}
+
+/// An operation in a `SemanticValue::Expression` or `SemanticValue::Locations`.
+///
+/// Operations that refer to entries, indices or other operations are resolved,
+/// so that they don't depend on the section layout or the operation encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemanticOperation {
+    /// `DW_OP_addr` or `DW_OP_addrx`. Address indices are resolved.
+    Address(u64),
+    /// An unsigned constant, such as `DW_OP_constu` or `DW_OP_constx`.
+    /// Address indices are resolved.
+    Unsigned(u64),
+    /// `DW_OP_call2`, `DW_OP_call4` or `DW_OP_call_ref`, given as the path of
+    /// the entry that is called.
+    ///
+    /// This is `None` if the entry is not in the `.debug_info` section.
+    Call(Option<Vec<usize>>),
+    /// `DW_OP_implicit_pointer`, given as the path of the entry and the byte offset.
+    ///
+    /// The path is `None` if the entry is not in the `.debug_info` section.
+    ImplicitPointer(Option<Vec<usize>>, i64),
+    /// `DW_OP_skip`, given as the index of the target operation.
+    ///
+    /// This is `None` if the target is not the start of an operation or the
+    /// end of the expression.
+    Skip(Option<usize>),
+    /// `DW_OP_bra`, given as the index of the target operation.
+    ///
+    /// This is `None` if the target is not the start of an operation or the
+    /// end of the expression.
+    Bra(Option<usize>),
+    /// Any other operation, given as its bytes.
+    Other(Vec<u8>),
+}
+
+/// A difference between two DWARF inputs that was found by `Dwarf::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The path of the entry that differs.
+    ///
+    /// The first element is the index of the unit in the `.debug_info` section,
+    /// and each following element is the index of a child of the previous entry.
+    /// The path for the root entry of a unit has a single element. The path is
+    /// empty for `DifferenceKind::UnitCount`.
+    pub path: Vec<usize>,
+    /// The kind of difference.
+    pub kind: DifferenceKind,
+}
+
+/// The kind of a `Difference`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// The inputs have a different number of units.
+    ///
+    /// The units that are in both inputs are still compared.
+    UnitCount {
+        /// The number of units in the left input.
+        left: usize,
+        /// The number of units in the right input.
+        right: usize,
+    },
+    /// The entries have different tags.
+    ///
+    /// The attributes and children of the entries are not compared.
+    Tag {
+        /// The tag of the left entry.
+        left: constants::DwTag,
+        /// The tag of the right entry.
+        right: constants::DwTag,
+    },
+    /// The entries have a different number of children.
+    ///
+    /// The children that are in both entries are still compared.
+    ChildCount {
+        /// The number of children of the left entry.
+        left: usize,
+        /// The number of children of the right entry.
+        right: usize,
+    },
+    /// The attribute is only present in the left entry.
+    LeftOnly {
+        /// The name of the attribute.
+        name: constants::DwAt,
+        /// The value of the attribute.
+        value: SemanticValue,
+    },
+    /// The attribute is only present in the right entry.
+    RightOnly {
+        /// The name of the attribute.
+        name: constants::DwAt,
+        /// The value of the attribute.
+        value: SemanticValue,
+    },
+    /// The attribute has different values.
+    Value {
+        /// The name of the attribute.
+        name: constants::DwAt,
+        /// The value of the attribute in the left entry.
+        left: SemanticValue,
+        /// The value of the attribute in the right entry.
+        right: SemanticValue,
+    },
+}
+
+#[derive(Debug)]
+struct SemanticEntry {
+    tag: constants::DwTag,
+    attrs: Vec<(constants::DwAt, SemanticValue)>,
+    children: Vec<SemanticEntry>,
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Compare the units in the `.debug_info` section with those of another
+    /// DWARF input, and return the differences.
+    ///
+    /// Units are matched by their order in the section, and the entries in
+    /// each unit are matched by their position in the tree. Attributes are
+    /// matched by name, and their values are compared using `SemanticValue`,
+    /// so differences in abbreviation codes, attribute forms, attribute order,
+    /// and section layout are ignored. `DW_AT_sibling` and the `DW_AT_*_base`
+    /// attributes are also ignored.
+    ///
+    /// This can be used to check that a `write::Dwarf` is equivalent to the
+    /// input that it was converted from, by writing it and then reading the
+    /// written sections.
+    pub fn diff<R2: Reader>(&self, other: &Dwarf<R2>) -> Result<Vec<Difference>> {
+        let left = semantic_units(self)?;
+        let right = semantic_units(other)?;
+        let mut differences = Vec::new();
+        if left.len() != right.len() {
+            differences.push(Difference {
+                path: Vec::new(),
+                kind: DifferenceKind::UnitCount {
+                    left: left.len(),
+                    right: right.len(),
+                },
+            });
+        }
+        let mut path = Vec::new();
+        for (index, (left, right)) in left.iter().zip(right.iter()).enumerate() {
+            path.push(index);
+            diff_entries(&mut path, left, right, &mut differences);
+            path.pop();
+        }
+        Ok(differences)
+    }
+}
+
+fn semantic_units<R: Reader>(dwarf: &Dwarf<R>) -> Result<Vec<SemanticEntry>> {
+    let mut units = Vec::new();
+    let mut iter = dwarf.parsed_units();
+    while let Some(unit) = iter.next()? {
+        units.push(unit);
+    }
+
+    // Find the paths of all entries first, so that references can be resolved.
+    let mut paths = BTreeMap::new();
+    for (index, unit) in units.iter().enumerate() {
+        entry_paths(unit.unit_ref(dwarf), index, &mut paths)?;
+    }
+
+    let mut entries = Vec::with_capacity(units.len());
+    for unit in &units {
+        entries.push(semantic_entries(unit.unit_ref(dwarf), &paths)?);
+    }
+    Ok(entries)
+}
+
+/// Record the path of each entry in the unit, using an explicit path
+/// instead of recursion.
+fn entry_paths<R: Reader>(
+    unit: UnitRef<'_, R>,
+    index: usize,
+    paths: &mut BTreeMap<DebugInfoOffset<R::Offset>, Vec<usize>>,
+) -> Result<()> {
+    let mut path = Vec::new();
+    let mut depth = 0;
+    let mut entries = unit.entries();
+    while let Some((delta_depth, entry)) = entries.next_dfs()? {
+        if path.is_empty() {
+            path.push(index);
+        } else {
+            depth += delta_depth;
+            if depth <= 0 {
+                break;
+            }
+            if delta_depth > 0 {
+                path.push(0);
+            } else {
+                for _ in delta_depth..0 {
+                    path.pop();
+                }
+                if let Some(last) = path.last_mut() {
+                    *last += 1;
+                }
+            }
+        }
+        if let Some(offset) = entry.offset().to_debug_info_offset(&unit.header) {
+            paths.insert(offset, path.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Build the semantic entries for the unit, using an explicit stack of the
+/// entries that are still being built instead of recursion.
+fn semantic_entries<R: Reader>(
+    unit: UnitRef<'_, R>,
+    paths: &BTreeMap<DebugInfoOffset<R::Offset>, Vec<usize>>,
+) -> Result<SemanticEntry> {
+    let mut entries = unit.entries();
+    let mut stack = Vec::new();
+    let mut depth = 0;
+    while let Some((delta_depth, entry)) = entries.next_dfs()? {
+        if !stack.is_empty() {
+            depth += delta_depth;
+            if depth <= 0 {
+                break;
+            }
+            // Finish the previous entry and any ancestors that have ended.
+            for _ in delta_depth..=0 {
+                finish_semantic_entry(&mut stack);
+            }
+        }
+
+        let mut attrs = Vec::new();
+        let mut iter = entry.attrs();
+        while let Some(attr) = iter.next()? {
+            if attr.name() == constants::DW_AT_sibling {
+                continue;
+            }
+            if let Some(value) = semantic_value(unit, attr.value(), paths)? {
+                attrs.push((attr.name(), value));
+            }
+        }
+        stack.push(SemanticEntry {
+            tag: entry.tag(),
+            attrs,
+            children: Vec::new(),
+        });
+    }
+    while stack.len() > 1 {
+        finish_semantic_entry(&mut stack);
+    }
+    stack.pop().ok_or(Error::UnexpectedNull)
+}
+
+/// Move the last entry in `stack` to the children of its parent.
+fn finish_semantic_entry(stack: &mut Vec<SemanticEntry>) {
+    if let Some(entry) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(entry);
+        }
+    }
+}
+
+fn semantic_value<R: Reader>(
+    unit: UnitRef<'_, R>,
+    value: AttributeValue<R>,
+    paths: &BTreeMap<DebugInfoOffset<R::Offset>, Vec<usize>>,
+) -> Result<Option<SemanticValue>> {
+    let bytes = |r: &R| -> Result<Vec<u8>> { Ok(r.to_slice()?.into_owned()) };
+    Ok(Some(match value {
+        AttributeValue::Addr(val) => SemanticValue::Address(val),
+        AttributeValue::DebugAddrIndex(index) => SemanticValue::Address(unit.address(index)?),
+        AttributeValue::Block(ref val) | AttributeValue::Unknown(ref val) => {
+            SemanticValue::Block(bytes(val)?)
+        }
+        AttributeValue::Data1(val) => SemanticValue::Unsigned(val.into()),
+        AttributeValue::Data2(val) => SemanticValue::Unsigned(val.into()),
+        AttributeValue::Data4(val) => SemanticValue::Unsigned(val.into()),
+        AttributeValue::Data8(val) | AttributeValue::Udata(val) => SemanticValue::Unsigned(val),
+        AttributeValue::Data16(val) => SemanticValue::Block(val.to_vec()),
+        AttributeValue::Sdata(val) => SemanticValue::Signed(val),
+        AttributeValue::Exprloc(ref val) => {
+            SemanticValue::Expression(semantic_expression(unit, val.clone(), paths)?)
+        }
+        AttributeValue::Flag(val) => SemanticValue::Flag(val),
+        AttributeValue::UnitRef(offset) => SemanticValue::Reference(
+            offset
+                .to_debug_info_offset(&unit.header)
+                .and_then(|offset| paths.get(&offset).cloned()),
+        ),
+        AttributeValue::DebugInfoRef(offset) => {
+            SemanticValue::Reference(paths.get(&offset).cloned())
+        }
+        AttributeValue::DebugInfoRefSup(_) => SemanticValue::Reference(None),
+        AttributeValue::DebugTypesRef(signature) => SemanticValue::Unsigned(signature.0),
+        AttributeValue::String(_)
+        | AttributeValue::DebugStrRef(_)
+        | AttributeValue::DebugStrRefSup(_)
+        | AttributeValue::DebugStrOffsetsIndex(_)
+        | AttributeValue::DebugLineStrRef(_) => {
+            SemanticValue::String(bytes(&unit.attr_string(value)?)?)
+        }
+        AttributeValue::RangeListsRef(_) | AttributeValue::DebugRngListsIndex(_) => {
+            let mut ranges = Vec::new();
+            if let Some(mut iter) = unit.attr_ranges(value)? {
+                while let Some(range) = iter.next()? {
+                    ranges.push(range);
+                }
+            }
+            SemanticValue::Ranges(ranges)
+        }
+        AttributeValue::LocationListsRef(_) | AttributeValue::DebugLocListsIndex(_) => {
+            let mut locations = Vec::new();
+            if let Some(mut iter) = unit.attr_locations(value)? {
+                while let Some(location) = iter.next()? {
+                    let expression = semantic_expression(unit, location.data, paths)?;
+                    locations.push((location.range, expression));
+                }
+            }
+            SemanticValue::Locations(locations)
+        }
+        AttributeValue::FileIndex(index) => {
+            let mut path = None;
+            if let Some(program) = &unit.line_program {
+                let header = program.header();
+                if let Some(file) = header.file(index) {
+                    path = Some(file.full_path(unit, header)?);
+                }
+            }
+            SemanticValue::File(path)
+        }
+        AttributeValue::Encoding(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::DecimalSign(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Endianity(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Accessibility(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Visibility(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Virtuality(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Language(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::AddressClass(val) => SemanticValue::Unsigned(val.0),
+        AttributeValue::IdentifierCase(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::CallingConvention(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Inline(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::Ordering(val) => SemanticValue::Unsigned(val.0.into()),
+        AttributeValue::DwoId(val) => SemanticValue::Unsigned(val.0),
+        AttributeValue::SecOffset(_)
+        | AttributeValue::DebugLineRef(_)
+        | AttributeValue::DebugMacinfoRef(_)
+        | AttributeValue::DebugMacroRef(_) => SemanticValue::Offset,
+        // The bases depend on the section layout and the forms that are used.
+        AttributeValue::DebugAddrBase(_)
+        | AttributeValue::DebugLocListsBase(_)
+        | AttributeValue::DebugRngListsBase(_)
+        | AttributeValue::DebugStrOffsetsBase(_) => return Ok(None),
+    }))
+}
+
+fn semantic_expression<R: Reader>(
+    unit: UnitRef<'_, R>,
+    expression: Expression<R>,
+    paths: &BTreeMap<DebugInfoOffset<R::Offset>, Vec<usize>>,
+) -> Result<Vec<SemanticOperation>> {
+    let reference = |offset: DieReference<R::Offset>| match offset {
+        DieReference::UnitRef(offset) => offset
+            .to_debug_info_offset(&unit.header)
+            .and_then(|offset| paths.get(&offset).cloned()),
+        DieReference::DebugInfoRef(offset) => paths.get(&offset).cloned(),
+    };
+    let mut operations = Vec::new();
+    // The offset of each operation, and the targets of branches.
+    let mut offsets = Vec::new();
+    let mut branches = Vec::new();
+    let mut input = expression.0;
+    let len = input.len().into_u64();
+    while !input.is_empty() {
+        let offset = len - input.len().into_u64();
+        let mut rest = input.clone();
+        let operation = Operation::parse(&mut rest, unit.encoding())?;
+        let size = input.len() - rest.len();
+        let next = offset + size.into_u64();
+        offsets.push(offset);
+        operations.push(match operation {
+            Operation::Address { address } => SemanticOperation::Address(address),
+            Operation::AddressIndex { index } => SemanticOperation::Address(unit.address(index)?),
+            Operation::UnsignedConstant { value } => SemanticOperation::Unsigned(value),
+            Operation::ConstantIndex { index } => SemanticOperation::Unsigned(unit.address(index)?),
+            Operation::Call { offset } => SemanticOperation::Call(reference(offset)),
+            Operation::ImplicitPointer { value, byte_offset } => {
+                SemanticOperation::ImplicitPointer(paths.get(&value).cloned(), byte_offset)
+            }
+            Operation::Skip { target } | Operation::Bra { target } => {
+                let target = (next as i64).wrapping_add(target.into()) as u64;
+                branches.push((operations.len(), target));
+                if let Operation::Skip { .. } = operation {
+                    SemanticOperation::Skip(None)
+                } else {
+                    SemanticOperation::Bra(None)
+                }
+            }
+            _ => SemanticOperation::Other(input.split(size)?.to_slice()?.into_owned()),
+        });
+        input = rest;
+    }
+    offsets.push(len);
+    for (index, target) in branches {
+        let target = offsets.binary_search(&target).ok();
+        match operations[index] {
+            SemanticOperation::Skip(ref mut t) | SemanticOperation::Bra(ref mut t) => *t = target,
+            _ => {}
+        }
+    }
+    Ok(operations)
+}
+
+/// Compare two entries and their children, using an explicit stack instead
+/// of recursion.
+fn diff_entries(
+    path: &mut Vec<usize>,
+    left: &SemanticEntry,
+    right: &SemanticEntry,
+    differences: &mut Vec<Difference>,
+) {
+    // The length of the path of the parent, the index of the child (if any),
+    // and the entries to compare.
+    let len = path.len();
+    let mut stack = vec![(len, None, left, right)];
+    while let Some((parent_len, index, left, right)) = stack.pop() {
+        path.truncate(parent_len);
+        path.extend(index);
+        diff_entry(path, left, right, differences);
+        if left.tag == right.tag {
+            let children = left.children.iter().zip(right.children.iter()).enumerate();
+            for (index, (left, right)) in children.rev() {
+                stack.push((path.len(), Some(index), left, right));
+            }
+        }
+    }
+    path.truncate(len);
+}
+
+/// Compare the tag, attributes and number of children of two entries.
+fn diff_entry(
+    path: &[usize],
+    left: &SemanticEntry,
+    right: &SemanticEntry,
+    differences: &mut Vec<Difference>,
+) {
+    let mut push = |path: &[usize], kind| {
+        differences.push(Difference {
+            path: path.to_vec(),
+            kind,
+        })
+    };
+    if left.tag != right.tag {
+        push(
+            path,
+            DifferenceKind::Tag {
+                left: left.tag,
+                right: right.tag,
+            },
+        );
+        return;
+    }
+
+    for (name, left_value) in &left.attrs {
+        match right
+            .attrs
+            .iter()
+            .find(|(right_name, _)| right_name == name)
+        {
+            Some((_, right_value)) => {
+                if left_value != right_value {
+                    push(
+                        path,
+                        DifferenceKind::Value {
+                            name: *name,
+                            left: left_value.clone(),
+                            right: right_value.clone(),
+                        },
+                    );
+                }
+            }
+            None => push(
+                path,
+                DifferenceKind::LeftOnly {
+                    name: *name,
+                    value: left_value.clone(),
+                },
+            ),
+        }
+    }
+    for (name, right_value) in &right.attrs {
+        if !left.attrs.iter().any(|(left_name, _)| left_name == name) {
+            push(
+                path,
+                DifferenceKind::RightOnly {
+                    name: *name,
+                    value: right_value.clone(),
+                },
+            );
+        }
+    }
+
+    if left.children.len() != right.children.len() {
+        push(
+            path,
+            DifferenceKind::ChildCount {
+                left: left.children.len(),
+                right: right.children.len(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format, LineEncoding};
+    use crate::read::EndianSlice;
+    use crate::write::{self, Address, AttributeValue as WriteValue, EndianVec, LineString};
+    use crate::LittleEndian;
+
+    fn build(version: u16, modify: bool) -> write::Sections<EndianVec<LittleEndian>> {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let mut program = write::LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"/src".to_vec()),
+            LineString::String(b"main.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let file = program.add_file(LineString::String(b"a.h".to_vec()), dir, None);
+        let unit_id = dwarf.units.add(write::Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        if version >= 5 {
+            unit.form_selector = Some(|_, value, _| match value {
+                WriteValue::StringRef(_) => Some(constants::DW_FORM_strx1),
+                WriteValue::Address(_) => Some(constants::DW_FORM_addrx),
+                _ => None,
+            });
+        }
+        let root = unit.root();
+        let producer = dwarf.strings.add("producer");
+        let root_entry = unit.get_mut(root);
+        root_entry.set(constants::DW_AT_producer, WriteValue::StringRef(producer));
+        root_entry.set(
+            constants::DW_AT_name,
+            WriteValue::String(b"main.c".to_vec()),
+        );
+        root_entry.set(
+            constants::DW_AT_comp_dir,
+            WriteValue::String(b"/src".to_vec()),
+        );
+        root_entry.set(
+            constants::DW_AT_language,
+            WriteValue::Language(constants::DW_LANG_C99),
+        );
+
+        let mut types = Vec::new();
+        for name in ["int", "long"] {
+            let id = unit.add(root, constants::DW_TAG_base_type);
+            let entry = unit.get_mut(id);
+            entry.set(constants::DW_AT_name, WriteValue::String(name.into()));
+            entry.set(constants::DW_AT_byte_size, WriteValue::Udata(4));
+            types.push(id);
+        }
+
+        let variable = unit.add(root, constants::DW_TAG_variable);
+        let entry = unit.get_mut(variable);
+        let name = if modify { "y" } else { "x" };
+        entry.set(constants::DW_AT_name, WriteValue::String(name.into()));
+        let ty = if modify { types[1] } else { types[0] };
+        entry.set(constants::DW_AT_type, WriteValue::UnitRef(ty));
+        entry.set(
+            constants::DW_AT_decl_file,
+            WriteValue::FileIndex(Some(file)),
+        );
+        // The same expression, using indexed operations for version 5.
+        let mut expression = if version >= 5 {
+            write::Expression::raw(vec![
+                constants::DW_OP_skip.0,
+                2,
+                0,
+                constants::DW_OP_addrx.0,
+                0,
+                constants::DW_OP_constx.0,
+                0,
+            ])
+        } else {
+            let mut expression = write::Expression::new();
+            let skip = expression.op_skip();
+            expression.op_addr(Address::Constant(0x1000));
+            expression.set_target(skip, 2);
+            expression.op_constu(0x1000);
+            expression
+        };
+        expression.op_call(ty);
+        expression.op_implicit_pointer(write::Reference::Entry(unit_id, types[0]), 4);
+        entry.set(constants::DW_AT_location, WriteValue::Exprloc(expression));
+
+        let ranges = unit.ranges.add(write::RangeList(vec![
+            write::Range::StartLength {
+                begin: Address::Constant(0x1000),
+                length: 0x10,
+            },
+            write::Range::StartLength {
+                begin: Address::Constant(0x2000),
+                length: 0x20,
+            },
+        ]));
+        let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(subprogram);
+        entry.set(constants::DW_AT_name, WriteValue::StringRef(producer));
+        entry.set(constants::DW_AT_ranges, WriteValue::RangeListRef(ranges));
+        entry.set(
+            constants::DW_AT_entry_pc,
+            WriteValue::Address(Address::Constant(0x1000)),
+        );
+        if modify {
+            entry.set(constants::DW_AT_external, WriteValue::Flag(true));
+            unit.add(root, constants::DW_TAG_variable);
+        }
+
+        let mut sections = write::Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        sections
+    }
+
+    #[test]
+    fn test_diff() {
+        let v4 = build(4, false);
+        let v5 = build(5, false);
+        let modified = build(4, true);
+        // Make sure that the inputs are really encoded differently.
+        assert_ne!(v4.debug_info.slice(), v5.debug_info.slice());
+        assert!(!v5.debug_str_offsets.slice().is_empty());
+        assert!(!v5.debug_addr.slice().is_empty());
+
+        let (v4, v5, modified) = (v4.read_dwarf(), v5.read_dwarf(), modified.read_dwarf());
+        assert_eq!(v4.diff(&v4).unwrap(), Vec::new());
+        assert_eq!(v4.diff(&v5).unwrap(), Vec::new());
+        assert_eq!(v5.diff(&v4).unwrap(), Vec::new());
+
+        assert_eq!(
+            v4.diff(&modified).unwrap(),
+            vec![
+                Difference {
+                    path: vec![0],
+                    kind: DifferenceKind::ChildCount { left: 4, right: 5 },
+                },
+                Difference {
+                    path: vec![0, 2],
+                    kind: DifferenceKind::Value {
+                        name: constants::DW_AT_name,
+                        left: SemanticValue::String(b"x".to_vec()),
+                        right: SemanticValue::String(b"y".to_vec()),
+                    },
+                },
+                Difference {
+                    path: vec![0, 2],
+                    kind: DifferenceKind::Value {
+                        name: constants::DW_AT_type,
+                        left: SemanticValue::Reference(Some(vec![0, 0])),
+                        right: SemanticValue::Reference(Some(vec![0, 1])),
+                    },
+                },
+                Difference {
+                    path: vec![0, 2],
+                    kind: DifferenceKind::Value {
+                        name: constants::DW_AT_location,
+                        left: SemanticValue::Expression(vec![
+                            SemanticOperation::Skip(Some(2)),
+                            SemanticOperation::Address(0x1000),
+                            SemanticOperation::Unsigned(0x1000),
+                            SemanticOperation::Call(Some(vec![0, 0])),
+                            SemanticOperation::ImplicitPointer(Some(vec![0, 0]), 4),
+                        ]),
+                        right: SemanticValue::Expression(vec![
+                            SemanticOperation::Skip(Some(2)),
+                            SemanticOperation::Address(0x1000),
+                            SemanticOperation::Unsigned(0x1000),
+                            SemanticOperation::Call(Some(vec![0, 1])),
+                            SemanticOperation::ImplicitPointer(Some(vec![0, 0]), 4),
+                        ]),
+                    },
+                },
+                Difference {
+                    path: vec![0, 3],
+                    kind: DifferenceKind::RightOnly {
+                        name: constants::DW_AT_external,
+                        value: SemanticValue::Flag(true),
+                    },
+                },
+            ]
+        );
+
+        assert_eq!(
+            Dwarf::<EndianSlice<'_, LittleEndian>>::default()
+                .diff(&v4)
+                .unwrap(),
+            vec![Difference {
+                path: Vec::new(),
+                kind: DifferenceKind::UnitCount { left: 0, right: 1 },
+            }]
+        );
+    }
+}
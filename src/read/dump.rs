@@ -0,0 +1,1566 @@
+use alloc::collections::btree_map::BTreeMap;
+use core::fmt::{self, Write};
+
+use crate::common::{Encoding, LocationListsOffset, RangeListsOffset, Register, UnitSectionOffset};
+use crate::constants;
+use crate::read::{
+    Attribute, AttributeValue, BaseAddresses, CallFrameInstruction, CallFrameInstructionIter,
+    CieOrFde, ColumnType, CommonInformationEntry, Dwarf, Error, Expression, FrameDescriptionEntry,
+    Operation, Pointer, Range, RawLocListEntry, RawRngListEntry, Reader, ReaderOffset, Result,
+    UnitHeader, UnitRef, UnitType, UnwindOffset, UnwindSection,
+};
+
+/// An error that occurred while dumping debugging information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpError {
+    /// An error occurred while parsing the debugging information.
+    Read(Error),
+    /// An error occurred while writing to the output.
+    Fmt,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> core::result::Result<(), fmt::Error> {
+        match *self {
+            DumpError::Read(e) => write!(f, "{}", e),
+            DumpError::Fmt => write!(f, "Failed to write output"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DumpError {}
+
+impl From<Error> for DumpError {
+    fn from(e: Error) -> Self {
+        DumpError::Read(e)
+    }
+}
+
+impl From<fmt::Error> for DumpError {
+    fn from(_: fmt::Error) -> Self {
+        DumpError::Fmt
+    }
+}
+
+/// The result of a dump operation.
+pub type DumpResult<T> = core::result::Result<T, DumpError>;
+
+impl<R: Reader> Dwarf<R> {
+    /// Write a textual dump of all units in the `.debug_info` and `.debug_types`
+    /// sections.
+    ///
+    /// The output format is similar to that of `dwarfdump`: each unit header is
+    /// followed by its tree of entries, with one line per attribute.
+    ///
+    /// ```rust,no_run
+    /// # fn example<R: gimli::Reader>(dwarf: &gimli::Dwarf<R>) -> gimli::DumpResult<()> {
+    /// let mut output = String::new();
+    /// dwarf.dump_info(&mut output)?;
+    /// print!("{}", output);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn dump_info<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        writeln!(w, ".debug_info")?;
+        let mut units = self.units();
+        while let Some(header) = units.next()? {
+            self.dump_unit(w, header)?;
+        }
+        let mut type_units = self.type_units();
+        if let Some(header) = type_units.next()? {
+            writeln!(w, "\n.debug_types")?;
+            self.dump_unit(w, header)?;
+            while let Some(header) = type_units.next()? {
+                self.dump_unit(w, header)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a textual dump of the line number program for each unit in the
+    /// `.debug_info` section.
+    pub fn dump_line<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        let mut units = self.units();
+        while let Some(header) = units.next()? {
+            if let UnitSectionOffset::DebugInfoOffset(offset) = header.offset() {
+                writeln!(
+                    w,
+                    "\n.debug_line: line number info for unit at .debug_info offset 0x{:08x}",
+                    offset.0.into_u64()
+                )?;
+            }
+            let unit = self.unit(header)?;
+            unit.unit_ref(self).dump_line_program(w)?;
+        }
+        Ok(())
+    }
+
+    /// Write a textual dump of a unit header and its entries.
+    pub fn dump_unit<W: Write>(&self, w: &mut W, header: UnitHeader<R>) -> DumpResult<()> {
+        dump_unit_header(w, &header)?;
+        let unit = self.unit(header)?;
+        unit.unit_ref(self).dump_entries(w)
+    }
+}
+
+/// Write a textual dump of a unit header.
+///
+/// This is the header line written by `Dwarf::dump_unit`, followed by the
+/// type signature or DWO id if the unit has one.
+pub fn dump_unit_header<R: Reader, W: Write>(w: &mut W, header: &UnitHeader<R>) -> DumpResult<()> {
+    match header.offset() {
+        UnitSectionOffset::DebugInfoOffset(o) => {
+            write!(w, "\nUNIT<.debug_info+0x{:08x}>", o.0.into_u64())?;
+        }
+        UnitSectionOffset::DebugTypesOffset(o) => {
+            write!(w, "\nUNIT<.debug_types+0x{:08x}>", o.0.into_u64())?;
+        }
+    }
+    writeln!(
+        w,
+        ": length = 0x{:x}, format = {:?}, version = {}, address_size = {}, abbrev_offset = 0x{:x}",
+        header.unit_length().into_u64(),
+        header.format(),
+        header.version(),
+        header.address_size(),
+        header.debug_abbrev_offset().0.into_u64(),
+    )?;
+    match header.type_() {
+        UnitType::Compilation | UnitType::Partial => {}
+        UnitType::Type {
+            type_signature,
+            type_offset,
+        }
+        | UnitType::SplitType {
+            type_signature,
+            type_offset,
+        } => {
+            writeln!(w, "  signature        = 0x{:016x}", type_signature.0)?;
+            writeln!(w, "  type_offset      = 0x{:x}", type_offset.0.into_u64())?;
+        }
+        UnitType::Skeleton(dwo_id) | UnitType::SplitCompilation(dwo_id) => {
+            writeln!(w, "  dwo_id           = 0x{:016x}", dwo_id.0)?;
+        }
+    }
+    Ok(())
+}
+
+fn spaces<W: Write>(w: &mut W, count: usize) -> fmt::Result {
+    for _ in 0..count {
+        w.write_char(' ')?;
+    }
+    Ok(())
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Write a textual dump of the entries in this unit.
+    ///
+    /// Each entry is written as its depth, unit offset and tag, followed by one
+    /// line for each attribute containing the attribute name and its decoded value.
+    pub fn dump_entries<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        let mut entries = self.entries_raw(None)?;
+        while !entries.is_empty() {
+            let offset = entries.next_offset();
+            let depth = entries.next_depth();
+            let abbrev = entries.read_abbreviation()?;
+
+            let indent = if depth >= 0 {
+                depth as usize * 2 + 2
+            } else {
+                2
+            };
+            write!(
+                w,
+                "<{}{}><0x{:08x}>",
+                if depth < 10 { " " } else { "" },
+                depth,
+                offset.0.into_u64()
+            )?;
+            spaces(w, indent)?;
+            writeln!(
+                w,
+                "{}",
+                abbrev.map(|x| x.tag()).unwrap_or(constants::DW_TAG_null)
+            )?;
+
+            for spec in abbrev.map(|x| x.attributes()).unwrap_or(&[]) {
+                let attr = entries.read_attribute(*spec)?;
+                spaces(w, indent + 18)?;
+                match attr.name().static_string() {
+                    Some(name) => write!(w, "{:27} ", name)?,
+                    None => write!(w, "{:27} ", attr.name())?,
+                }
+                self.dump_attr_value(w, &attr)?;
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a textual dump of the value of an attribute.
+    ///
+    /// Constants and references are decoded using the attribute name and
+    /// form, strings are resolved, and location and range lists are expanded
+    /// with one line for each entry. No trailing newline is written.
+    pub fn dump_attr_value<W: Write>(&self, w: &mut W, attr: &Attribute<R>) -> DumpResult<()> {
+        let value = attr.value();
+        match value {
+            AttributeValue::Addr(address) => write!(w, "0x{:08x}", address)?,
+            AttributeValue::Block(data) | AttributeValue::Unknown(data) => {
+                for byte in data.to_slice()?.iter() {
+                    write!(w, "{:02x}", byte)?;
+                }
+            }
+            AttributeValue::Data16(data) => {
+                for byte in data.iter() {
+                    write!(w, "{:02x}", byte)?;
+                }
+            }
+            AttributeValue::Data1(_)
+            | AttributeValue::Data2(_)
+            | AttributeValue::Data4(_)
+            | AttributeValue::Data8(_) => match (attr.udata_value(), attr.sdata_value()) {
+                (Some(udata), Some(sdata)) if sdata < 0 => write!(w, "{} ({})", udata, sdata)?,
+                (Some(udata), Some(_)) => write!(w, "{}", udata)?,
+                _ => write!(w, "{:?}", value)?,
+            },
+            AttributeValue::Sdata(data) => match attr.name() {
+                constants::DW_AT_data_member_location => write!(w, "{}", data)?,
+                _ if data < 0 => write!(w, "0x{:08x} ({})", data, data)?,
+                _ => write!(w, "0x{:08x}", data)?,
+            },
+            AttributeValue::Udata(data) => match attr.name() {
+                constants::DW_AT_high_pc => write!(w, "<offset-from-lowpc>{}", data)?,
+                constants::DW_AT_data_member_location => match attr.sdata_value() {
+                    // This is a `DW_FORM_data*` value, which `dwarfdump` displays
+                    // as signed too.
+                    Some(sdata) if sdata < 0 => write!(w, "{} ({})", data, sdata)?,
+                    _ => write!(w, "{}", data)?,
+                },
+                constants::DW_AT_lower_bound | constants::DW_AT_upper_bound => {
+                    write!(w, "{}", data)?
+                }
+                _ => write!(w, "0x{:08x}", data)?,
+            },
+            AttributeValue::Exprloc(ref data) => {
+                if let AttributeValue::Exprloc(_) = attr.raw_value() {
+                    write!(w, "len 0x{:04x}: ", data.0.len().into_u64())?;
+                    for byte in data.0.to_slice()?.iter() {
+                        write!(w, "{:02x}", byte)?;
+                    }
+                    write!(w, ": ")?;
+                }
+                dump_expression(w, self.encoding(), data)?;
+            }
+            AttributeValue::Flag(true) => write!(w, "yes")?,
+            AttributeValue::Flag(false) => write!(w, "no")?,
+            AttributeValue::SecOffset(offset) => write!(w, "0x{:08x}", offset.into_u64())?,
+            AttributeValue::DebugAddrBase(base) => {
+                write!(w, "<.debug_addr+0x{:08x}>", base.0.into_u64())?
+            }
+            AttributeValue::DebugAddrIndex(index) => {
+                write!(w, "(indirect address, index {:#x}): ", index.0.into_u64())?;
+                write!(w, "0x{:08x}", self.address(index)?)?;
+            }
+            AttributeValue::UnitRef(offset) => {
+                write!(w, "0x{:08x}", offset.0.into_u64())?;
+                match offset.to_unit_section_offset(self) {
+                    UnitSectionOffset::DebugInfoOffset(goff) => {
+                        write!(w, "<.debug_info+0x{:08x}>", goff.0.into_u64())?
+                    }
+                    UnitSectionOffset::DebugTypesOffset(goff) => {
+                        write!(w, "<.debug_types+0x{:08x}>", goff.0.into_u64())?
+                    }
+                }
+            }
+            AttributeValue::DebugInfoRef(offset) => {
+                write!(w, "<.debug_info+0x{:08x}>", offset.0.into_u64())?
+            }
+            AttributeValue::DebugInfoRefSup(offset) => {
+                write!(w, "<.debug_info(sup)+0x{:08x}>", offset.0.into_u64())?
+            }
+            AttributeValue::DebugLineRef(offset) => {
+                write!(w, "<.debug_line+0x{:08x}>", offset.0.into_u64())?
+            }
+            AttributeValue::LocationListsRef(offset) => self.dump_loc_list(w, offset)?,
+            AttributeValue::DebugLocListsBase(base) => {
+                write!(w, "<.debug_loclists+0x{:08x}>", base.0.into_u64())?
+            }
+            AttributeValue::DebugLocListsIndex(index) => {
+                write!(
+                    w,
+                    "(indirect location list, index {:#x}): ",
+                    index.0.into_u64()
+                )?;
+                let offset = self.locations_offset(index)?;
+                self.dump_loc_list(w, offset)?;
+            }
+            AttributeValue::DebugMacinfoRef(offset) => {
+                write!(w, "<.debug_macinfo+0x{:08x}>", offset.0.into_u64())?
+            }
+            AttributeValue::DebugMacroRef(offset) => {
+                write!(w, "<.debug_macro+0x{:08x}>", offset.0.into_u64())?
+            }
+            AttributeValue::RangeListsRef(offset) => {
+                let offset = self.ranges_offset_from_raw(offset);
+                self.dump_range_list(w, offset)?;
+            }
+            AttributeValue::DebugRngListsBase(base) => {
+                write!(w, "<.debug_rnglists+0x{:08x}>", base.0.into_u64())?
+            }
+            AttributeValue::DebugRngListsIndex(index) => {
+                write!(
+                    w,
+                    "(indirect range list, index {:#x}): ",
+                    index.0.into_u64()
+                )?;
+                let offset = self.ranges_offset(index)?;
+                self.dump_range_list(w, offset)?;
+            }
+            AttributeValue::DebugTypesRef(signature) => {
+                write!(w, "0x{:016x} <type signature>", signature.0)?
+            }
+            AttributeValue::DebugStrRef(offset) => match self.string(offset) {
+                Ok(s) => write!(w, "{}", s.to_string_lossy()?)?,
+                Err(_) => write!(w, "<.debug_str+0x{:08x}>", offset.0.into_u64())?,
+            },
+            AttributeValue::DebugStrRefSup(offset) => match self.sup_string(offset) {
+                Ok(s) => write!(w, "{}", s.to_string_lossy()?)?,
+                Err(_) => write!(w, "<.debug_str(sup)+0x{:08x}>", offset.0.into_u64())?,
+            },
+            AttributeValue::DebugStrOffsetsBase(base) => {
+                write!(w, "<.debug_str_offsets+0x{:08x}>", base.0.into_u64())?
+            }
+            AttributeValue::DebugStrOffsetsIndex(index) => {
+                write!(w, "(indirect string, index {:#x}): ", index.0.into_u64())?;
+                let offset = self.string_offset(index)?;
+                match self.string(offset) {
+                    Ok(s) => write!(w, "{}", s.to_string_lossy()?)?,
+                    Err(_) => write!(w, "<.debug_str+0x{:08x}>", offset.0.into_u64())?,
+                }
+            }
+            AttributeValue::DebugLineStrRef(offset) => match self.line_string(offset) {
+                Ok(s) => write!(w, "{}", s.to_string_lossy()?)?,
+                Err(_) => write!(w, "<.debug_line_str+0x{:08x}>", offset.0.into_u64())?,
+            },
+            AttributeValue::String(s) => write!(w, "{}", s.to_string_lossy()?)?,
+            AttributeValue::Encoding(value) => write!(w, "{}", value)?,
+            AttributeValue::DecimalSign(value) => write!(w, "{}", value)?,
+            AttributeValue::Endianity(value) => write!(w, "{}", value)?,
+            AttributeValue::Accessibility(value) => write!(w, "{}", value)?,
+            AttributeValue::Visibility(value) => write!(w, "{}", value)?,
+            AttributeValue::Virtuality(value) => write!(w, "{}", value)?,
+            AttributeValue::Language(value) => write!(w, "{}", value)?,
+            AttributeValue::AddressClass(value) => write!(w, "{}", value)?,
+            AttributeValue::IdentifierCase(value) => write!(w, "{}", value)?,
+            AttributeValue::CallingConvention(value) => write!(w, "{}", value)?,
+            AttributeValue::Inline(value) => write!(w, "{}", value)?,
+            AttributeValue::Ordering(value) => write!(w, "{}", value)?,
+            AttributeValue::FileIndex(value) => {
+                write!(w, "0x{:08x}", value)?;
+                self.dump_file_index(w, value)?;
+            }
+            AttributeValue::DwoId(value) => write!(w, "0x{:016x}", value.0)?,
+        }
+        Ok(())
+    }
+
+    fn dump_file_index<W: Write>(&self, w: &mut W, file_index: u64) -> DumpResult<()> {
+        if file_index == 0 && self.header.version() <= 4 {
+            return Ok(());
+        }
+        let header = match self.line_program {
+            Some(ref program) => program.header(),
+            None => return Ok(()),
+        };
+        let file = match header.file(file_index) {
+            Some(file) => file,
+            None => {
+                write!(w, " Unable to get header for file {}", file_index)?;
+                return Ok(());
+            }
+        };
+        write!(w, " ")?;
+        if let Some(directory) = file.directory(header) {
+            let directory = self.attr_string(directory)?;
+            let directory = directory.to_string_lossy()?;
+            if file.directory_index() != 0 && !directory.starts_with('/') {
+                if let Some(ref comp_dir) = self.comp_dir {
+                    write!(w, "{}/", comp_dir.to_string_lossy()?)?;
+                }
+            }
+            write!(w, "{}/", directory)?;
+        }
+        write!(
+            w,
+            "{}",
+            self.attr_string(file.path_name())?.to_string_lossy()?
+        )?;
+        Ok(())
+    }
+
+    fn dump_loc_list<W: Write>(
+        &self,
+        w: &mut W,
+        offset: LocationListsOffset<R::Offset>,
+    ) -> DumpResult<()> {
+        let mut locations = self.locations(offset)?;
+        write!(
+            w,
+            "<loclist at {}+0x{:08x}>",
+            if self.encoding().version < 5 {
+                ".debug_loc"
+            } else {
+                ".debug_loclists"
+            },
+            offset.0.into_u64(),
+        )?;
+        let mut i = 0;
+        while let Some(raw) = locations.next_raw()? {
+            write!(w, "\n\t\t\t[{:2}]", i)?;
+            i += 1;
+            let range = locations
+                .convert_raw(raw.clone())?
+                .map(|location| location.range);
+            let data = match raw {
+                RawLocListEntry::BaseAddress { addr } => {
+                    write!(w, "<base-address 0x{:08x}>", addr)?;
+                    continue;
+                }
+                RawLocListEntry::BaseAddressx { addr } => {
+                    let addr_val = self.address(addr)?;
+                    write!(
+                        w,
+                        "<base-addressx [{}]0x{:08x}>",
+                        addr.0.into_u64(),
+                        addr_val
+                    )?;
+                    continue;
+                }
+                RawLocListEntry::DefaultLocation { data } => {
+                    write!(w, "<default location>")?;
+                    dump_expression(w, self.encoding(), &data)?;
+                    continue;
+                }
+                RawLocListEntry::StartxEndx { begin, end, data } => {
+                    let begin_val = self.address(begin)?;
+                    let end_val = self.address(end)?;
+                    write!(
+                        w,
+                        "<startx-endx [{}]0x{:08x}, [{}]0x{:08x}>",
+                        begin.0.into_u64(),
+                        begin_val,
+                        end.0.into_u64(),
+                        end_val,
+                    )?;
+                    data
+                }
+                RawLocListEntry::StartxLength {
+                    begin,
+                    length,
+                    data,
+                } => {
+                    let begin_val = self.address(begin)?;
+                    write!(
+                        w,
+                        "<startx-length [{}]0x{:08x}, 0x{:08x}>",
+                        begin.0.into_u64(),
+                        begin_val,
+                        length,
+                    )?;
+                    data
+                }
+                RawLocListEntry::AddressOrOffsetPair { begin, end, data }
+                | RawLocListEntry::OffsetPair { begin, end, data } => {
+                    write!(w, "<offset-pair 0x{:08x}, 0x{:08x}>", begin, end)?;
+                    data
+                }
+                RawLocListEntry::StartEnd { begin, end, data } => {
+                    write!(w, "<start-end 0x{:08x}, 0x{:08x}>", begin, end)?;
+                    data
+                }
+                RawLocListEntry::StartLength {
+                    begin,
+                    length,
+                    data,
+                } => {
+                    write!(w, "<start-length 0x{:08x}, 0x{:08x}>", begin, length)?;
+                    data
+                }
+            };
+            dump_range(w, range)?;
+            dump_expression(w, self.encoding(), &data)?;
+        }
+        Ok(())
+    }
+
+    fn dump_range_list<W: Write>(
+        &self,
+        w: &mut W,
+        offset: RangeListsOffset<R::Offset>,
+    ) -> DumpResult<()> {
+        let mut ranges = self.ranges(offset)?;
+        write!(
+            w,
+            "<rnglist at {}+0x{:08x}>",
+            if self.encoding().version < 5 {
+                ".debug_ranges"
+            } else {
+                ".debug_rnglists"
+            },
+            offset.0.into_u64(),
+        )?;
+        let mut i = 0;
+        while let Some(raw) = ranges.next_raw()? {
+            write!(w, "\n\t\t\t[{:2}] ", i)?;
+            i += 1;
+            let range = ranges.convert_raw(raw.clone())?;
+            match raw {
+                RawRngListEntry::BaseAddress { addr } => {
+                    write!(w, "<new base address 0x{:08x}>", addr)?;
+                    continue;
+                }
+                RawRngListEntry::BaseAddressx { addr } => {
+                    let addr_val = self.address(addr)?;
+                    write!(
+                        w,
+                        "<new base addressx [{}]0x{:08x}>",
+                        addr.0.into_u64(),
+                        addr_val
+                    )?;
+                    continue;
+                }
+                RawRngListEntry::StartxEndx { begin, end } => {
+                    let begin_val = self.address(begin)?;
+                    let end_val = self.address(end)?;
+                    write!(
+                        w,
+                        "<startx-endx [{}]0x{:08x}, [{}]0x{:08x}>",
+                        begin.0.into_u64(),
+                        begin_val,
+                        end.0.into_u64(),
+                        end_val,
+                    )?;
+                }
+                RawRngListEntry::StartxLength { begin, length } => {
+                    let begin_val = self.address(begin)?;
+                    write!(
+                        w,
+                        "<startx-length [{}]0x{:08x}, 0x{:08x}>",
+                        begin.0.into_u64(),
+                        begin_val,
+                        length,
+                    )?;
+                }
+                RawRngListEntry::AddressOrOffsetPair { begin, end }
+                | RawRngListEntry::OffsetPair { begin, end } => {
+                    write!(w, "<offset-pair 0x{:08x}, 0x{:08x}>", begin, end)?;
+                }
+                RawRngListEntry::StartEnd { begin, end } => {
+                    write!(w, "<start-end 0x{:08x}, 0x{:08x}>", begin, end)?;
+                }
+                RawRngListEntry::StartLength { begin, length } => {
+                    write!(w, "<start-length 0x{:08x}, 0x{:08x}>", begin, length)?;
+                }
+            }
+            dump_range(w, range)?;
+        }
+        Ok(())
+    }
+
+    /// Write a textual dump of the line number program for this unit.
+    ///
+    /// This includes the program header, the directory and file tables,
+    /// the raw line number instructions, and the resulting rows.
+    /// Nothing is written if the unit does not have a line number program.
+    pub fn dump_line_program<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        let program = match self.line_program {
+            Some(ref program) => program.clone(),
+            None => return Ok(()),
+        };
+        {
+            let header = program.header();
+            writeln!(w)?;
+            writeln!(
+                w,
+                "Offset:                             0x{:x}",
+                header.offset().0.into_u64()
+            )?;
+            writeln!(
+                w,
+                "Length:                             {}",
+                header.unit_length().into_u64()
+            )?;
+            writeln!(
+                w,
+                "DWARF version:                      {}",
+                header.version()
+            )?;
+            writeln!(
+                w,
+                "Address size:                       {}",
+                header.address_size()
+            )?;
+            writeln!(
+                w,
+                "Prologue length:                    {}",
+                header.header_length().into_u64()
+            )?;
+            writeln!(
+                w,
+                "Minimum instruction length:         {}",
+                header.minimum_instruction_length()
+            )?;
+            writeln!(
+                w,
+                "Maximum operations per instruction: {}",
+                header.maximum_operations_per_instruction()
+            )?;
+            writeln!(
+                w,
+                "Default is_stmt:                    {}",
+                header.default_is_stmt()
+            )?;
+            writeln!(
+                w,
+                "Line base:                          {}",
+                header.line_base()
+            )?;
+            writeln!(
+                w,
+                "Line range:                         {}",
+                header.line_range()
+            )?;
+            writeln!(
+                w,
+                "Opcode base:                        {}",
+                header.opcode_base()
+            )?;
+
+            writeln!(w)?;
+            writeln!(w, "Opcodes:")?;
+            for (i, length) in header
+                .standard_opcode_lengths()
+                .to_slice()?
+                .iter()
+                .enumerate()
+            {
+                writeln!(w, "  Opcode {} has {} args", i + 1, length)?;
+            }
+
+            let base = if header.version() >= 5 { 0 } else { 1 };
+            writeln!(w)?;
+            writeln!(w, "The Directory Table:")?;
+            for (i, dir) in header.include_directories().iter().enumerate() {
+                writeln!(
+                    w,
+                    "  {} {}",
+                    base + i,
+                    self.attr_string(dir.clone())?.to_string_lossy()?
+                )?;
+            }
+
+            writeln!(w)?;
+            writeln!(w, "The File Name Table")?;
+            write!(w, "  Entry\tDir\tTime\tSize")?;
+            if header.file_has_md5() {
+                write!(w, "\tMD5\t\t\t\t")?;
+            }
+            writeln!(w, "\tName")?;
+            for (i, file) in header.file_names().iter().enumerate() {
+                write!(
+                    w,
+                    "  {}\t{}\t{}\t{}",
+                    base + i,
+                    file.directory_index(),
+                    file.timestamp(),
+                    file.size(),
+                )?;
+                if header.file_has_md5() {
+                    write!(w, "\t")?;
+                    for byte in file.md5() {
+                        write!(w, "{:02X}", byte)?;
+                    }
+                }
+                writeln!(
+                    w,
+                    "\t{}",
+                    self.attr_string(file.path_name())?.to_string_lossy()?
+                )?;
+            }
+
+            writeln!(w)?;
+            writeln!(w, "Line Number Instructions:")?;
+            let mut instructions = header.instructions();
+            while let Some(instruction) = instructions.next_instruction(header)? {
+                writeln!(w, "  {}", instruction)?;
+            }
+        }
+
+        writeln!(w)?;
+        writeln!(w, "Line Number Rows:")?;
+        writeln!(w, "<pc>        [lno,col]")?;
+        let mut rows = program.rows();
+        let mut file_index = None;
+        while let Some((header, row)) = rows.next_row()? {
+            let line = row.line().map(|line| line.get()).unwrap_or(0);
+            let column = match row.column() {
+                ColumnType::Column(column) => column.get(),
+                ColumnType::LeftEdge => 0,
+            };
+            write!(w, "0x{:08x}  [{:4},{:2}]", row.address(), line, column)?;
+            if row.is_stmt() {
+                write!(w, " NS")?;
+            }
+            if row.basic_block() {
+                write!(w, " BB")?;
+            }
+            if row.end_sequence() {
+                write!(w, " ET")?;
+            }
+            if row.prologue_end() {
+                write!(w, " PE")?;
+            }
+            if row.epilogue_begin() {
+                write!(w, " EB")?;
+            }
+            if row.isa() != 0 {
+                write!(w, " IS={}", row.isa())?;
+            }
+            if row.discriminator() != 0 {
+                write!(w, " DI={}", row.discriminator())?;
+            }
+            if file_index != Some(row.file_index()) {
+                file_index = Some(row.file_index());
+                if let Some(file) = row.file(header) {
+                    write!(w, " uri: \"")?;
+                    if let Some(directory) = file.directory(header) {
+                        write!(w, "{}/", self.attr_string(directory)?.to_string_lossy()?)?;
+                    }
+                    write!(
+                        w,
+                        "{}\"",
+                        self.attr_string(file.path_name())?.to_string_lossy()?
+                    )?;
+                }
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+fn dump_range<W: Write>(w: &mut W, range: Option<Range>) -> DumpResult<()> {
+    match range {
+        Some(range) => write!(w, " [0x{:08x}, 0x{:08x}]", range.begin, range.end)?,
+        None => write!(w, " [ignored]")?,
+    }
+    Ok(())
+}
+
+/// Write a textual dump of the operations in a DWARF expression.
+///
+/// Operations are separated by a space. If an operation cannot be parsed,
+/// a warning is written and the rest of the expression is skipped.
+pub fn dump_expression<R: Reader, W: Write>(
+    w: &mut W,
+    encoding: Encoding,
+    expression: &Expression<R>,
+) -> DumpResult<()> {
+    let mut pc = expression.0.clone();
+    let mut space = false;
+    while !pc.is_empty() {
+        let pc_clone = pc.clone();
+        match Operation::parse(&mut pc, encoding) {
+            Ok(Operation::Unknown { opcode, .. }) => {
+                write!(w, "WARNING: unsupported operation 0x{:02x}", opcode.0)?;
+                return Ok(());
+            }
+            Ok(op) => {
+                if space {
+                    write!(w, " ")?;
+                } else {
+                    space = true;
+                }
+                dump_operation(w, encoding, pc_clone, op)?;
+            }
+            Err(Error::InvalidExpression(op)) => {
+                write!(w, "WARNING: unsupported operation 0x{:02x}", op.0)?;
+                return Ok(());
+            }
+            Err(Error::UnsupportedRegister(register)) => {
+                write!(w, "WARNING: unsupported register {}", register)?;
+                return Ok(());
+            }
+            Err(Error::UnexpectedEof(_)) => {
+                write!(w, "WARNING: truncated or malformed expression")?;
+                return Ok(());
+            }
+            Err(e) => {
+                write!(w, "WARNING: unexpected operation parse error: {}", e)?;
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn dump_operation<R: Reader, W: Write>(
+    w: &mut W,
+    encoding: Encoding,
+    mut pc: R,
+    op: Operation<R>,
+) -> DumpResult<()> {
+    let dwop = constants::DwOp(pc.read_u8()?);
+    write!(w, "{}", dwop)?;
+    match op {
+        Operation::Deref {
+            base_type, size, ..
+        } => {
+            if dwop == constants::DW_OP_deref_size || dwop == constants::DW_OP_xderef_size {
+                write!(w, " {}", size)?;
+            }
+            if base_type.0 != R::Offset::from_u8(0) {
+                write!(w, " type 0x{:08x}", base_type.0.into_u64())?;
+            }
+        }
+        Operation::Pick { index } => {
+            if dwop == constants::DW_OP_pick {
+                write!(w, " {}", index)?;
+            }
+        }
+        Operation::PlusConstant { value } => write!(w, " {}", value as i64)?,
+        Operation::Bra { target } | Operation::Skip { target } => write!(w, " {}", target)?,
+        Operation::SignedConstant { value } => match dwop {
+            constants::DW_OP_const1s
+            | constants::DW_OP_const2s
+            | constants::DW_OP_const4s
+            | constants::DW_OP_const8s
+            | constants::DW_OP_consts => write!(w, " {}", value)?,
+            _ => {}
+        },
+        Operation::UnsignedConstant { value } => match dwop {
+            constants::DW_OP_const1u
+            | constants::DW_OP_const2u
+            | constants::DW_OP_const4u
+            | constants::DW_OP_const8u
+            | constants::DW_OP_constu => write!(w, " {}", value)?,
+            // These have the value encoded in the operation, eg `DW_OP_lit0`.
+            _ => {}
+        },
+        Operation::Register { register } => {
+            if dwop == constants::DW_OP_regx {
+                write!(w, " {}", register.0)?;
+            }
+        }
+        Operation::RegisterOffset {
+            register,
+            offset,
+            base_type,
+        } => {
+            if dwop >= constants::DW_OP_breg0 && dwop <= constants::DW_OP_breg31 {
+                write!(w, "{:+}", offset)?;
+            } else {
+                write!(w, " {}", register.0)?;
+                if offset != 0 {
+                    write!(w, "{:+}", offset)?;
+                }
+                if base_type.0 != R::Offset::from_u8(0) {
+                    write!(w, " type 0x{:08x}", base_type.0.into_u64())?;
+                }
+            }
+        }
+        Operation::FrameOffset { offset } => write!(w, " {}", offset)?,
+        Operation::Call { offset } => match offset {
+            crate::read::DieReference::UnitRef(offset) => {
+                write!(w, " 0x{:08x}", offset.0.into_u64())?
+            }
+            crate::read::DieReference::DebugInfoRef(offset) => {
+                write!(w, " 0x{:08x}", offset.0.into_u64())?
+            }
+        },
+        Operation::Piece {
+            size_in_bits,
+            bit_offset,
+        } => match bit_offset {
+            Some(bit_offset) => write!(w, " 0x{:08x} offset 0x{:08x}", size_in_bits, bit_offset)?,
+            None => write!(w, " {}", size_in_bits / 8)?,
+        },
+        Operation::ImplicitValue { data } => {
+            let data = data.to_slice()?;
+            write!(w, " 0x{:08x} contents 0x", data.len())?;
+            for byte in data.iter() {
+                write!(w, "{:02x}", byte)?;
+            }
+        }
+        Operation::ImplicitPointer { value, byte_offset } => {
+            write!(w, " 0x{:08x} {}", value.0.into_u64(), byte_offset)?
+        }
+        Operation::EntryValue { expression } => {
+            write!(w, "(")?;
+            dump_expression(w, encoding, &Expression(expression))?;
+            write!(w, ")")?;
+        }
+        Operation::ParameterRef { offset } => write!(w, " 0x{:08x}", offset.0.into_u64())?,
+        Operation::Address { address } => write!(w, " 0x{:08x}", address)?,
+        Operation::AddressIndex { index } | Operation::ConstantIndex { index } => {
+            write!(w, " 0x{:08x}", index.0.into_u64())?
+        }
+        Operation::TypedLiteral { base_type, value } => {
+            write!(w, " type 0x{:08x} contents 0x", base_type.0.into_u64())?;
+            for byte in value.to_slice()?.iter() {
+                write!(w, "{:02x}", byte)?;
+            }
+        }
+        Operation::Convert { base_type } | Operation::Reinterpret { base_type } => {
+            write!(w, " type 0x{:08x}", base_type.0.into_u64())?
+        }
+        Operation::WasmLocal { index }
+        | Operation::WasmGlobal { index }
+        | Operation::WasmStack { index } => {
+            let wasmop = pc.read_u8()?;
+            write!(w, " 0x{:x} 0x{:x}", wasmop, index)?;
+        }
+        Operation::Unknown { data, .. } => {
+            write!(w, " contents 0x")?;
+            for byte in data.to_slice()?.iter() {
+                write!(w, "{:02x}", byte)?;
+            }
+        }
+        Operation::Drop
+        | Operation::Swap
+        | Operation::Rot
+        | Operation::Abs
+        | Operation::And
+        | Operation::Div
+        | Operation::Minus
+        | Operation::Mod
+        | Operation::Mul
+        | Operation::Neg
+        | Operation::Not
+        | Operation::Or
+        | Operation::Plus
+        | Operation::Shl
+        | Operation::Shr
+        | Operation::Shra
+        | Operation::Xor
+        | Operation::Eq
+        | Operation::Ge
+        | Operation::Gt
+        | Operation::Le
+        | Operation::Lt
+        | Operation::Ne
+        | Operation::Nop
+        | Operation::PushObjectAddress
+        | Operation::TLS
+        | Operation::CallFrameCFA
+        | Operation::StackValue => {}
+    }
+    Ok(())
+}
+
+/// Write a textual dump of the CIEs and FDEs in an unwind section.
+///
+/// `register_name` is used to obtain the name of registers, such as
+/// `X86_64::register_name`. Registers without a name are written as numbers.
+/// CFI instructions are written using `SymbolicCallFrameInstruction`.
+///
+/// Errors while parsing an FDE or its instructions are written to the output,
+/// and dumping continues with the next entry.
+///
+/// ```rust,no_run
+/// # fn example<R: gimli::Reader>(eh_frame: &gimli::EhFrame<R>) -> gimli::DumpResult<()> {
+/// let bases = gimli::BaseAddresses::default();
+/// let mut output = String::new();
+/// gimli::dump_cfi(&mut output, eh_frame, &bases, gimli::X86_64::register_name)?;
+/// print!("{}", output);
+/// # Ok(())
+/// # }
+/// ```
+pub fn dump_cfi<R, S, W>(
+    w: &mut W,
+    section: &S,
+    bases: &BaseAddresses,
+    register_name: fn(Register) -> Option<&'static str>,
+) -> DumpResult<()>
+where
+    R: Reader,
+    S: UnwindSection<R>,
+    W: Write,
+{
+    let mut cies = BTreeMap::new();
+    let mut entries = section.entries(bases);
+    while let Some(entry) = entries.next()? {
+        match entry {
+            CieOrFde::Cie(cie) => {
+                writeln!(w)?;
+                writeln!(w, "{:#010x}: CIE", cie.offset().into_u64())?;
+                writeln!(w, "        length: {:#010x}", cie.entry_len().into_u64())?;
+                writeln!(w, "       version: {:#04x}", cie.version())?;
+                writeln!(w, "    code_align: {}", cie.code_alignment_factor())?;
+                writeln!(w, "    data_align: {}", cie.data_alignment_factor())?;
+                write!(w, "   ra_register: ")?;
+                dump_register(w, cie.return_address_register(), register_name)?;
+                writeln!(w)?;
+                if let Some(encoding) = cie.lsda_encoding() {
+                    writeln!(
+                        w,
+                        " lsda_encoding: {}/{}",
+                        encoding.application(),
+                        encoding.format()
+                    )?;
+                }
+                if let Some((encoding, personality)) = cie.personality_with_encoding() {
+                    write!(
+                        w,
+                        "   personality: {}/{} ",
+                        encoding.application(),
+                        encoding.format()
+                    )?;
+                    dump_pointer(w, personality)?;
+                    writeln!(w)?;
+                }
+                if let Some(encoding) = cie.fde_address_encoding() {
+                    writeln!(
+                        w,
+                        "  fde_encoding: {}/{}",
+                        encoding.application(),
+                        encoding.format()
+                    )?;
+                }
+                writeln!(w, "  Instructions: Init State:")?;
+                dump_cfi_instructions(w, cie.symbolic_instructions(section, bases, register_name))?;
+            }
+            CieOrFde::Fde(partial) => {
+                writeln!(w)?;
+                writeln!(w, "{:#010x}: FDE", partial.offset().into_u64())?;
+                writeln!(
+                    w,
+                    "        length: {:#010x}",
+                    partial.entry_len().into_u64()
+                )?;
+                let cie_offset = UnwindOffset::into(partial.cie_offset());
+                writeln!(w, "   CIE_pointer: {:#010x}", cie_offset.into_u64())?;
+                let fde = match partial.parse(|section, bases, offset| {
+                    cies.entry(UnwindOffset::into(offset))
+                        .or_insert_with(|| section.cie_from_offset(bases, offset))
+                        .clone()
+                }) {
+                    Ok(fde) => fde,
+                    Err(e) => {
+                        writeln!(w, "Failed to parse FDE: {}", e)?;
+                        continue;
+                    }
+                };
+                writeln!(w, "    start_addr: {:#018x}", fde.initial_address())?;
+                writeln!(
+                    w,
+                    "    range_size: {:#018x} (end_addr = {:#018x})",
+                    fde.len(),
+                    fde.initial_address().wrapping_add(fde.len())
+                )?;
+                if let Some(lsda) = fde.lsda() {
+                    write!(w, "          lsda: ")?;
+                    dump_pointer(w, lsda)?;
+                    writeln!(w)?;
+                }
+                writeln!(w, "  Instructions:")?;
+                dump_cfi_instructions(w, fde.symbolic_instructions(section, bases, register_name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn dump_pointer<W: Write>(w: &mut W, p: Pointer) -> DumpResult<()> {
+    match p {
+        Pointer::Direct(p) => write!(w, "{:#018x}", p)?,
+        Pointer::Indirect(p) => write!(w, "({:#018x})", p)?,
+    }
+    Ok(())
+}
+
+fn dump_register<W: Write>(
+    w: &mut W,
+    register: Register,
+    register_name: fn(Register) -> Option<&'static str>,
+) -> DumpResult<()> {
+    match register_name(register) {
+        Some(name) => write!(w, "{}", name)?,
+        None => write!(w, "{}", register.0)?,
+    }
+    Ok(())
+}
+
+fn dump_cfi_instructions<R: Reader, W: Write>(
+    w: &mut W,
+    mut instructions: SymbolicCallFrameInstructionIter<'_, R>,
+) -> DumpResult<()> {
+    loop {
+        match instructions.next() {
+            Ok(Some(instruction)) => writeln!(w, "                {}", instruction)?,
+            Ok(None) => break,
+            Err(e) => {
+                writeln!(w, "Failed to decode CFI instruction: {}", e)?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A call frame instruction that is displayed using register names and
+/// unfactored offsets.
+///
+/// The output is similar to that of `readelf --debug-dump=frames`, such as
+/// `DW_CFA_offset: r16 (rip) at cfa-8`.
+#[derive(Debug, Clone)]
+pub struct SymbolicCallFrameInstruction<T: ReaderOffset> {
+    instruction: CallFrameInstruction<T>,
+    code_alignment_factor: u64,
+    data_alignment_factor: i64,
+    register_name: fn(Register) -> Option<&'static str>,
+}
+
+impl<T: ReaderOffset> SymbolicCallFrameInstruction<T> {
+    /// Return the decoded instruction.
+    pub fn instruction(&self) -> &CallFrameInstruction<T> {
+        &self.instruction
+    }
+
+    fn register(&self, f: &mut fmt::Formatter<'_>, register: Register) -> fmt::Result {
+        match (self.register_name)(register) {
+            Some(name) => write!(f, "r{} ({})", register.0, name),
+            None => write!(f, "r{}", register.0),
+        }
+    }
+
+    fn offset(&self, factored_offset: i64) -> i64 {
+        factored_offset.wrapping_mul(self.data_alignment_factor)
+    }
+}
+
+impl<T: ReaderOffset> fmt::Display for SymbolicCallFrameInstruction<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CallFrameInstruction::*;
+
+        match self.instruction {
+            SetLoc { address } => write!(f, "DW_CFA_set_loc: {:#x}", address),
+            AdvanceLoc { delta } => write!(
+                f,
+                "DW_CFA_advance_loc: {}",
+                u64::from(delta).wrapping_mul(self.code_alignment_factor)
+            ),
+            DefCfa { register, offset } => {
+                write!(f, "DW_CFA_def_cfa: ")?;
+                self.register(f, register)?;
+                write!(f, " ofs {}", offset)
+            }
+            DefCfaSf {
+                register,
+                factored_offset,
+            } => {
+                write!(f, "DW_CFA_def_cfa_sf: ")?;
+                self.register(f, register)?;
+                write!(f, " ofs {}", self.offset(factored_offset))
+            }
+            DefCfaRegister { register } => {
+                write!(f, "DW_CFA_def_cfa_register: ")?;
+                self.register(f, register)
+            }
+            DefCfaOffset { offset } => write!(f, "DW_CFA_def_cfa_offset: {}", offset),
+            DefCfaOffsetSf { factored_offset } => write!(
+                f,
+                "DW_CFA_def_cfa_offset_sf: {}",
+                self.offset(factored_offset)
+            ),
+            DefCfaExpression { .. } => write!(f, "DW_CFA_def_cfa_expression (...)"),
+            Undefined { register } => {
+                write!(f, "DW_CFA_undefined: ")?;
+                self.register(f, register)
+            }
+            SameValue { register } => {
+                write!(f, "DW_CFA_same_value: ")?;
+                self.register(f, register)
+            }
+            Offset {
+                register,
+                factored_offset,
+            } => {
+                write!(f, "DW_CFA_offset: ")?;
+                self.register(f, register)?;
+                write!(f, " at cfa{:+}", self.offset(factored_offset as i64))
+            }
+            OffsetExtendedSf {
+                register,
+                factored_offset,
+            } => {
+                write!(f, "DW_CFA_offset_extended_sf: ")?;
+                self.register(f, register)?;
+                write!(f, " at cfa{:+}", self.offset(factored_offset))
+            }
+            ValOffset {
+                register,
+                factored_offset,
+            } => {
+                write!(f, "DW_CFA_val_offset: ")?;
+                self.register(f, register)?;
+                write!(f, " is cfa{:+}", self.offset(factored_offset as i64))
+            }
+            ValOffsetSf {
+                register,
+                factored_offset,
+            } => {
+                write!(f, "DW_CFA_val_offset_sf: ")?;
+                self.register(f, register)?;
+                write!(f, " is cfa{:+}", self.offset(factored_offset))
+            }
+            Register {
+                dest_register,
+                src_register,
+            } => {
+                write!(f, "DW_CFA_register: ")?;
+                self.register(f, dest_register)?;
+                write!(f, " in ")?;
+                self.register(f, src_register)
+            }
+            Expression { register, .. } => {
+                write!(f, "DW_CFA_expression: ")?;
+                self.register(f, register)?;
+                write!(f, " (...)")
+            }
+            ValExpression { register, .. } => {
+                write!(f, "DW_CFA_val_expression: ")?;
+                self.register(f, register)?;
+                write!(f, " (...)")
+            }
+            Restore { register } => {
+                write!(f, "DW_CFA_restore: ")?;
+                self.register(f, register)
+            }
+            RememberState => write!(f, "DW_CFA_remember_state"),
+            RestoreState => write!(f, "DW_CFA_restore_state"),
+            ArgsSize { size } => write!(f, "DW_CFA_GNU_args_size: {}", size),
+            NegateRaState => write!(f, "DW_CFA_AARCH64_negate_ra_state"),
+            WindowSave => write!(f, "DW_CFA_GNU_window_save"),
+            Nop => write!(f, "DW_CFA_nop"),
+        }
+    }
+}
+
+/// An iterator over call frame instructions that can be displayed symbolically.
+///
+/// Can be [used with
+/// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+#[derive(Clone, Debug)]
+pub struct SymbolicCallFrameInstructionIter<'a, R: Reader> {
+    instructions: CallFrameInstructionIter<'a, R>,
+    code_alignment_factor: u64,
+    data_alignment_factor: i64,
+    register_name: fn(Register) -> Option<&'static str>,
+}
+
+impl<'a, R: Reader> SymbolicCallFrameInstructionIter<'a, R> {
+    fn new(
+        instructions: CallFrameInstructionIter<'a, R>,
+        cie: &CommonInformationEntry<R>,
+        register_name: fn(Register) -> Option<&'static str>,
+    ) -> Self {
+        SymbolicCallFrameInstructionIter {
+            instructions,
+            code_alignment_factor: cie.code_alignment_factor(),
+            data_alignment_factor: cie.data_alignment_factor(),
+            register_name,
+        }
+    }
+
+    /// Parse the next call frame instruction.
+    pub fn next(&mut self) -> Result<Option<SymbolicCallFrameInstruction<R::Offset>>> {
+        Ok(self
+            .instructions
+            .next()?
+            .map(|instruction| SymbolicCallFrameInstruction {
+                instruction,
+                code_alignment_factor: self.code_alignment_factor,
+                data_alignment_factor: self.data_alignment_factor,
+                register_name: self.register_name,
+            }))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'a, R: Reader> fallible_iterator::FallibleIterator
+    for SymbolicCallFrameInstructionIter<'a, R>
+{
+    type Item = SymbolicCallFrameInstruction<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        SymbolicCallFrameInstructionIter::next(self)
+    }
+}
+
+result_iterator!(
+    ['a, R: Reader] SymbolicCallFrameInstructionIter<'a, R>,
+    SymbolicCallFrameInstruction<R::Offset>
+);
+
+impl<R: Reader> CommonInformationEntry<R> {
+    /// Iterate over this CIE's initial instructions, for display using
+    /// register names such as `X86_64::register_name`.
+    pub fn symbolic_instructions<'a, Section>(
+        &self,
+        section: &'a Section,
+        bases: &'a BaseAddresses,
+        register_name: fn(Register) -> Option<&'static str>,
+    ) -> SymbolicCallFrameInstructionIter<'a, R>
+    where
+        Section: UnwindSection<R>,
+    {
+        SymbolicCallFrameInstructionIter::new(
+            self.instructions(section, bases),
+            self,
+            register_name,
+        )
+    }
+}
+
+impl<R: Reader> FrameDescriptionEntry<R> {
+    /// Iterate over this FDE's instructions, for display using register names
+    /// such as `X86_64::register_name`.
+    pub fn symbolic_instructions<'a, Section>(
+        &self,
+        section: &'a Section,
+        bases: &'a BaseAddresses,
+        register_name: fn(Register) -> Option<&'static str>,
+    ) -> SymbolicCallFrameInstructionIter<'a, R>
+    where
+        Section: UnwindSection<R>,
+    {
+        SymbolicCallFrameInstructionIter::new(
+            self.instructions(section, bases),
+            self.cie(),
+            register_name,
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::Format;
+    use crate::read::{
+        DebugAbbrev, DebugFrame, DebugInfo, DebugLine, DebugLoc, DebugLocLists, DebugStr,
+        LocationLists,
+    };
+    use crate::write::{self, EndianVec, LineProgram, LineString, Sections};
+    use crate::{LittleEndian, X86_64};
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_dump_info_and_line() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let mut program = LineProgram::new(
+            encoding,
+            crate::LineEncoding::default(),
+            LineString::String(b"/dir".to_vec()),
+            LineString::String(b"file.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let file = program.add_file(LineString::String(b"file.c".to_vec()), dir, None);
+        program.begin_sequence(Some(write::Address::Constant(0x1000)));
+        program.row().file = file;
+        program.row().line = 3;
+        program.generate_row();
+        program.end_sequence(0x10);
+        let unit_id = dwarf.units.add(write::Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let name = dwarf.strings.add("file.c");
+        unit.get_mut(root).set(
+            constants::DW_AT_name,
+            write::AttributeValue::StringRef(name),
+        );
+        let child = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(child).set(
+            constants::DW_AT_location,
+            write::AttributeValue::Exprloc(write::Expression::raw(vec![
+                constants::DW_OP_fbreg.0,
+                0x7c,
+            ])),
+        );
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_reg0);
+        let loc_list =
+            unit.locations
+                .add(write::LocationList(vec![write::Location::StartLength {
+                    begin: write::Address::Constant(0x1000),
+                    length: 0x10,
+                    data: expression,
+                }]));
+        let child = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(child).set(
+            constants::DW_AT_location,
+            write::AttributeValue::LocationListRef(loc_list),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            debug_line: DebugLine::new(sections.debug_line.slice(), LittleEndian),
+            debug_str: DebugStr::new(sections.debug_str.slice(), LittleEndian),
+            locations: LocationLists::new(
+                DebugLoc::new(sections.debug_loc.slice(), LittleEndian),
+                DebugLocLists::new(sections.debug_loclists.slice(), LittleEndian),
+            ),
+            ..Default::default()
+        };
+
+        let mut output = String::new();
+        read_dwarf.dump_info(&mut output).unwrap();
+        assert!(output.contains("UNIT<.debug_info+0x00000000>: length = 0x"));
+        assert!(output.contains("< 0><0x0000000b>  DW_TAG_compile_unit\n"));
+        assert!(output.contains("DW_AT_name                  file.c\n"));
+        assert!(output.contains("< 1>"));
+        assert!(output.contains("DW_TAG_variable\n"));
+        assert!(output.contains("DW_AT_location              len 0x0002: 917c: DW_OP_fbreg -4\n"));
+        assert!(output.contains("DW_AT_location              <loclist at .debug_loc+0x00000000>\n"));
+        assert!(output.contains(
+            "\t\t\t[ 0]<offset-pair 0x00001000, 0x00001010> [0x00001000, 0x00001010]DW_OP_reg0\n"
+        ));
+
+        let mut output = String::new();
+        read_dwarf.dump_line(&mut output).unwrap();
+        assert!(output.contains("DWARF version:                      4\n"));
+        assert!(output.contains("0x00001000  [   3, 0] NS uri: \"file.c\"\n"));
+        assert!(output.contains("0x00001010  [   3, 0] NS ET\n"));
+    }
+
+    #[test]
+    fn test_dump_cfi() {
+        let mut table = write::FrameTable::default();
+        let mut cie = write::CommonInformationEntry::new(
+            Encoding {
+                format: Format::Dwarf32,
+                version: 1,
+                address_size: 8,
+            },
+            1,
+            -8,
+            X86_64::RA,
+        );
+        cie.add_instruction(write::CallFrameInstruction::Cfa(X86_64::RSP, 8));
+        let cie_id = table.add_cie(cie);
+        let mut fde = write::FrameDescriptionEntry::new(write::Address::Constant(0x1000), 0x20);
+        fde.add_instruction(1, write::CallFrameInstruction::CfaOffset(16));
+        table.add_fde(cie_id, fde);
+        let mut debug_frame = write::DebugFrame::from(EndianVec::new(LittleEndian));
+        table.write_debug_frame(&mut debug_frame).unwrap();
+        let mut data = debug_frame.slice().to_vec();
+        // An FDE with an invalid CIE pointer.
+        data.extend_from_slice(&[20, 0, 0, 0, 0, 0x10, 0, 0]);
+        data.extend_from_slice(&0x2000u64.to_le_bytes());
+        data.extend_from_slice(&0x10u64.to_le_bytes());
+        // An FDE with an unknown instruction.
+        data.extend_from_slice(&[24, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&0x3000u64.to_le_bytes());
+        data.extend_from_slice(&0x10u64.to_le_bytes());
+        data.extend_from_slice(&[0x3f, 0, 0, 0]);
+
+        let mut debug_frame = DebugFrame::new(&data, LittleEndian);
+        debug_frame.set_address_size(8);
+        let bases = BaseAddresses::default();
+        let mut output = String::new();
+        dump_cfi(&mut output, &debug_frame, &bases, X86_64::register_name).unwrap();
+        assert!(output.contains("Failed to parse FDE: "));
+        assert!(output.contains("    start_addr: 0x0000000000003000\n"));
+        assert!(output.contains("Failed to decode CFI instruction: "));
+        assert!(output.contains("0x00000000: CIE\n"));
+        assert!(output.contains("   ra_register: RA\n"));
+        assert!(output.contains("DW_CFA_def_cfa: r7 (rsp) ofs 8\n"));
+        assert!(output.contains(": FDE\n"));
+        assert!(output.contains("    start_addr: 0x0000000000001000\n"));
+        assert!(output.contains("DW_CFA_advance_loc: 1\n"));
+        assert!(output.contains("DW_CFA_def_cfa_offset: 16\n"));
+    }
+
+    #[test]
+    fn test_symbolic_cfi_instructions() {
+        let mut table = write::FrameTable::default();
+        let mut cie = write::CommonInformationEntry::new(
+            Encoding {
+                format: Format::Dwarf32,
+                version: 1,
+                address_size: 8,
+            },
+            4,
+            -8,
+            X86_64::RA,
+        );
+        cie.add_instruction(write::CallFrameInstruction::Cfa(X86_64::RSP, 8));
+        cie.add_instruction(write::CallFrameInstruction::Offset(X86_64::RA, -8));
+        let cie_id = table.add_cie(cie);
+        let mut fde = write::FrameDescriptionEntry::new(write::Address::Constant(0x1000), 0x20);
+        fde.add_instruction(4, write::CallFrameInstruction::CfaOffset(16));
+        fde.add_instruction(8, write::CallFrameInstruction::Offset(Register(100), -16));
+        table.add_fde(cie_id, fde);
+        let mut debug_frame = write::DebugFrame::from(EndianVec::new(LittleEndian));
+        table.write_debug_frame(&mut debug_frame).unwrap();
+
+        let mut debug_frame = DebugFrame::new(debug_frame.slice(), LittleEndian);
+        debug_frame.set_address_size(8);
+        let bases = BaseAddresses::default();
+        let mut entries = debug_frame.entries(&bases);
+        let cie = match entries.next().unwrap().unwrap() {
+            CieOrFde::Cie(cie) => cie,
+            _ => panic!("expected CIE"),
+        };
+        let mut instructions =
+            cie.symbolic_instructions(&debug_frame, &bases, X86_64::register_name);
+        let instruction = instructions.next().unwrap().unwrap();
+        assert_eq!(
+            *instruction.instruction(),
+            CallFrameInstruction::DefCfa {
+                register: X86_64::RSP,
+                offset: 8,
+            }
+        );
+        assert_eq!(instruction.to_string(), "DW_CFA_def_cfa: r7 (rsp) ofs 8");
+        let instruction = instructions.next().unwrap().unwrap();
+        assert_eq!(instruction.to_string(), "DW_CFA_offset: r16 (RA) at cfa-8");
+
+        let fde = match entries.next().unwrap().unwrap() {
+            CieOrFde::Fde(partial) => partial.parse(DebugFrame::cie_from_offset).unwrap(),
+            _ => panic!("expected FDE"),
+        };
+        let mut output = Vec::new();
+        let mut instructions =
+            fde.symbolic_instructions(&debug_frame, &bases, X86_64::register_name);
+        while let Some(instruction) = instructions.next().unwrap() {
+            if *instruction.instruction() != CallFrameInstruction::Nop {
+                output.push(instruction.to_string());
+            }
+        }
+        assert_eq!(
+            output,
+            [
+                "DW_CFA_advance_loc: 4",
+                "DW_CFA_def_cfa_offset: 16",
+                "DW_CFA_advance_loc: 4",
+                "DW_CFA_offset: r100 at cfa-16",
+            ]
+        );
+    }
+}
@@ -1,21 +1,25 @@
 use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use crate::common::{
-    DebugAddrBase, DebugAddrIndex, DebugInfoOffset, DebugLineStrOffset, DebugLocListsBase,
-    DebugLocListsIndex, DebugRngListsBase, DebugRngListsIndex, DebugStrOffset, DebugStrOffsetsBase,
-    DebugStrOffsetsIndex, DebugTypeSignature, DebugTypesOffset, DwarfFileType, DwoId, Encoding,
-    LocationListsOffset, RangeListsOffset, RawRangeListsOffset, SectionId, UnitSectionOffset,
+    AddressConvention, DebugAddrBase, DebugAddrIndex, DebugInfoOffset, DebugLineStrOffset,
+    DebugLocListsBase, DebugLocListsIndex, DebugRngListsBase, DebugRngListsIndex, DebugStrOffset,
+    DebugStrOffsetsBase, DebugStrOffsetsIndex, DebugTypeSignature, DebugTypesOffset, DwarfFileType,
+    DwoId, Encoding, LocationListsOffset, RangeListsOffset, RawRangeListsOffset, SectionId,
+    TombstoneFilter, UnitSectionOffset,
 };
 use crate::constants;
 use crate::read::{
-    Abbreviations, AbbreviationsCache, AbbreviationsCacheStrategy, AttributeValue, DebugAbbrev,
-    DebugAddr, DebugAranges, DebugCuIndex, DebugInfo, DebugInfoUnitHeadersIter, DebugLine,
-    DebugLineStr, DebugLoc, DebugLocLists, DebugRanges, DebugRngLists, DebugStr, DebugStrOffsets,
-    DebugTuIndex, DebugTypes, DebugTypesUnitHeadersIter, DebuggingInformationEntry, EntriesCursor,
-    EntriesRaw, EntriesTree, Error, IncompleteLineProgram, LocListIter, LocationLists, Range,
-    RangeLists, RawLocListIter, RawRngListIter, Reader, ReaderOffset, ReaderOffsetId, Result,
-    RngListIter, Section, UnitHeader, UnitIndex, UnitIndexSectionIterator, UnitOffset, UnitType,
+    Abbreviations, AbbreviationsCache, AbbreviationsCacheStrategy, ArangeEntryIter, ArangeHeader,
+    ArangeTable, AttributeValue, DebugAbbrev, DebugAddr, DebugAranges, DebugCuIndex, DebugInfo,
+    DebugInfoUnitHeadersIter, DebugLine, DebugLineStr, DebugLoc, DebugLocLists, DebugMacinfo,
+    DebugMacro, DebugRanges, DebugRngLists, DebugStr, DebugStrIndex, DebugStrOffsets, DebugTuIndex,
+    DebugTypes, DebugTypesUnitHeadersIter, DebuggingInformationEntry, DieReference, EntriesCursor,
+    EntriesRaw, EntriesTree, Error, IncompleteLineProgram, LineProgramCache, LocListIter,
+    LocationLists, Range, RangeLists, RawLocListIter, RawRngListIter, Reader, ReaderOffset,
+    ReaderOffsetId, Result, RngListIter, Section, UnitHeader, UnitIndex, UnitIndexSectionIterator,
+    UnitOffset, UnitType,
 };
 
 /// All of the commonly used DWARF sections.
@@ -59,6 +63,10 @@ pub struct DwarfSections<T> {
     pub debug_line: DebugLine<T>,
     /// The `.debug_line_str` section.
     pub debug_line_str: DebugLineStr<T>,
+    /// The `.debug_macinfo` section.
+    pub debug_macinfo: DebugMacinfo<T>,
+    /// The `.debug_macro` section.
+    pub debug_macro: DebugMacro<T>,
     /// The `.debug_str` section.
     pub debug_str: DebugStr<T>,
     /// The `.debug_str_offsets` section.
@@ -92,6 +100,8 @@ impl<T> DwarfSections<T> {
             debug_info: Section::load(&mut section)?,
             debug_line: Section::load(&mut section)?,
             debug_line_str: Section::load(&mut section)?,
+            debug_macinfo: Section::load(&mut section)?,
+            debug_macro: Section::load(&mut section)?,
             debug_str: Section::load(&mut section)?,
             debug_str_offsets: Section::load(&mut section)?,
             debug_types: Section::load(&mut section)?,
@@ -114,6 +124,8 @@ impl<T> DwarfSections<T> {
             debug_info: self.debug_info.borrow(&mut borrow),
             debug_line: self.debug_line.borrow(&mut borrow),
             debug_line_str: self.debug_line_str.borrow(&mut borrow),
+            debug_macinfo: self.debug_macinfo.borrow(&mut borrow),
+            debug_macro: self.debug_macro.borrow(&mut borrow),
             debug_str: self.debug_str.borrow(&mut borrow),
             debug_str_offsets: self.debug_str_offsets.borrow(&mut borrow),
             debug_types: self.debug_types.borrow(&mut borrow),
@@ -155,6 +167,13 @@ impl<T> DwarfSections<T> {
     }
 }
 
+/// A function that demangles a linkage name.
+///
+/// The function is given the mangled name and the language of the unit that
+/// contains it, if known. It returns the demangled name, or `None` if the name
+/// could not be demangled.
+pub type Demangler = fn(&[u8], Option<constants::DwLang>) -> Option<String>;
+
 /// All of the commonly used DWARF sections, and other common information.
 #[derive(Debug, Default)]
 pub struct Dwarf<R> {
@@ -176,6 +195,12 @@ pub struct Dwarf<R> {
     /// The `.debug_line_str` section.
     pub debug_line_str: DebugLineStr<R>,
 
+    /// The `.debug_macinfo` section.
+    pub debug_macinfo: DebugMacinfo<R>,
+
+    /// The `.debug_macro` section.
+    pub debug_macro: DebugMacro<R>,
+
     /// The `.debug_str` section.
     pub debug_str: DebugStr<R>,
 
@@ -194,11 +219,37 @@ pub struct Dwarf<R> {
     /// The type of this file.
     pub file_type: DwarfFileType,
 
+    /// The convention used for code addresses in this file.
+    ///
+    /// This should be set to `AddressConvention::CodeSectionRelative` for
+    /// WebAssembly modules.
+    ///
+    /// This is applied via `Dwarf::tombstone_filter`.
+    pub address_convention: AddressConvention,
+
+    /// The filter for recognizing the addresses of code that was removed by
+    /// the linker.
+    ///
+    /// This is applied to range lists, location lists, line programs, and
+    /// the aranges returned by `Dwarf::arange_entries` and `Dwarf::arange_table`.
+    pub tombstones: TombstoneFilter,
+
+    /// The function used to demangle linkage names.
+    ///
+    /// This is used by `Dwarf::entry_name`.
+    pub demangler: Option<Demangler>,
+
     /// The DWARF sections for a supplementary object file.
     pub sup: Option<Arc<Dwarf<R>>>,
 
     /// A cache of previously parsed abbreviations for units in this file.
     pub abbreviations_cache: AbbreviationsCache,
+
+    /// An index of the strings in the `.debug_str` section.
+    ///
+    /// This is used by `Dwarf::find_string` if present.
+    /// See `Dwarf::populate_debug_str_index`.
+    pub debug_str_index: Option<Arc<DebugStrIndex>>,
 }
 
 impl<T> Dwarf<T> {
@@ -241,14 +292,20 @@ impl<T> Dwarf<T> {
             debug_info: sections.debug_info,
             debug_line: sections.debug_line,
             debug_line_str: sections.debug_line_str,
+            debug_macinfo: sections.debug_macinfo,
+            debug_macro: sections.debug_macro,
             debug_str: sections.debug_str,
             debug_str_offsets: sections.debug_str_offsets,
             debug_types: sections.debug_types,
             locations: LocationLists::new(sections.debug_loc, sections.debug_loclists),
             ranges: RangeLists::new(sections.debug_ranges, sections.debug_rnglists),
             file_type: DwarfFileType::Main,
+            address_convention: AddressConvention::Absolute,
+            tombstones: TombstoneFilter::default(),
+            demangler: None,
             sup: None,
             abbreviations_cache: AbbreviationsCache::new(),
+            debug_str_index: None,
         }
     }
 
@@ -291,14 +348,20 @@ impl<T> Dwarf<T> {
             debug_info: self.debug_info.borrow(&mut borrow),
             debug_line: self.debug_line.borrow(&mut borrow),
             debug_line_str: self.debug_line_str.borrow(&mut borrow),
+            debug_macinfo: self.debug_macinfo.borrow(&mut borrow),
+            debug_macro: self.debug_macro.borrow(&mut borrow),
             debug_str: self.debug_str.borrow(&mut borrow),
             debug_str_offsets: self.debug_str_offsets.borrow(&mut borrow),
             debug_types: self.debug_types.borrow(&mut borrow),
             locations: self.locations.borrow(&mut borrow),
             ranges: self.ranges.borrow(&mut borrow),
             file_type: self.file_type,
+            address_convention: self.address_convention,
+            tombstones: self.tombstones,
+            demangler: self.demangler,
             sup: self.sup().map(|sup| Arc::new(sup.borrow(borrow))),
             abbreviations_cache: AbbreviationsCache::new(),
+            debug_str_index: self.debug_str_index.clone(),
         }
     }
 
@@ -326,6 +389,15 @@ impl<R: Reader> Dwarf<R> {
             .populate(strategy, &self.debug_abbrev, self.debug_info.units());
     }
 
+    /// Build an index of the strings in `self.debug_str`, and store it in
+    /// `self.debug_str_index`.
+    ///
+    /// This is useful if `Dwarf::find_string` will be called many times.
+    pub fn populate_debug_str_index(&mut self) -> Result<()> {
+        self.debug_str_index = Some(Arc::new(self.debug_str.index()?));
+        Ok(())
+    }
+
     /// Iterate the unit headers in the `.debug_info` section.
     ///
     /// Can be [used with
@@ -341,6 +413,36 @@ impl<R: Reader> Dwarf<R> {
         Unit::new(self, header)
     }
 
+    /// Construct a new `Unit` from the given unit header, using `cache` to
+    /// avoid reparsing line number programs that are shared with other units.
+    ///
+    /// `cache` must have been created for `self.debug_line`.
+    pub fn unit_with_line_program_cache(
+        &self,
+        header: UnitHeader<R>,
+        cache: &mut LineProgramCache<R>,
+    ) -> Result<Unit<R>> {
+        let abbreviations = self.abbreviations(&header)?;
+        Unit::new_impl(self, header, abbreviations, Some(cache))
+    }
+
+    /// Iterate the units in the `.debug_info` section.
+    ///
+    /// This is equivalent to calling `Dwarf::unit` for each header returned by
+    /// `Dwarf::units`, so each `Unit` has its abbreviations parsed and the
+    /// attributes of its root entry (such as the name, `DW_AT_low_pc`, and the
+    /// `DW_AT_*_base` attributes) already extracted.
+    ///
+    /// Can be [used with
+    /// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+    #[inline]
+    pub fn parsed_units(&self) -> ParsedUnitsIter<'_, R> {
+        ParsedUnitsIter {
+            dwarf: self,
+            headers: self.units(),
+        }
+    }
+
     /// Iterate the type-unit headers in the `.debug_types` section.
     ///
     /// Can be [used with
@@ -374,6 +476,19 @@ impl<R: Reader> Dwarf<R> {
         self.debug_str.get_str(offset)
     }
 
+    /// Find the offset of a string in `.debug_str`.
+    ///
+    /// The string may also be found as the tail of a longer string.
+    ///
+    /// This uses `self.debug_str_index` if it is present, otherwise it scans
+    /// the section.
+    pub fn find_string(&self, bytes: &[u8]) -> Result<Option<DebugStrOffset<R::Offset>>> {
+        match self.debug_str_index {
+            Some(ref index) => Ok(index.find(bytes)),
+            None => self.debug_str.find(bytes),
+        }
+    }
+
     /// Return the string at the given offset in `.debug_line_str`.
     #[inline]
     pub fn line_string(&self, offset: DebugLineStrOffset<R::Offset>) -> Result<R> {
@@ -442,6 +557,256 @@ impl<R: Reader> Dwarf<R> {
         }
     }
 
+    /// Return the `DW_AT_location` expression of the entry referenced by a
+    /// `DW_OP_call2`, `DW_OP_call4`, or `DW_OP_call_ref` operation.
+    ///
+    /// `unit` is the unit containing the expression that is being evaluated.
+    /// The result can be used to resume an `Evaluation` that returned
+    /// `EvaluationResult::RequiresAtLocation`.
+    ///
+    /// Returns an empty expression if the entry does not have a `DW_AT_location`
+    /// attribute, and an error if the attribute is not a single expression.
+    ///
+    /// A `DW_OP_call_ref` reference to an entry in another unit requires a
+    /// linear search of the unit headers, and parsing the abbreviations of the
+    /// unit that is found. If many such references will be evaluated, use
+    /// `UnitCache::find` to find the unit and offset of the entry instead, and
+    /// pass a `DieReference::UnitRef` for that unit.
+    pub fn call_location(&self, unit: &Unit<R>, reference: DieReference<R::Offset>) -> Result<R> {
+        let location = match reference {
+            DieReference::UnitRef(offset) => {
+                unit.entry(offset)?.attr_value(constants::DW_AT_location)?
+            }
+            DieReference::DebugInfoRef(offset) => match offset.to_unit_offset(&unit.header) {
+                Some(offset) => unit.entry(offset)?.attr_value(constants::DW_AT_location)?,
+                None => {
+                    let mut units = self.units();
+                    loop {
+                        let header = units.next()?.ok_or(Error::NoEntryAtGivenOffset)?;
+                        if let Some(offset) = offset.to_unit_offset(&header) {
+                            let abbreviations = self.abbreviations(&header)?;
+                            break header
+                                .entry(&abbreviations, offset)?
+                                .attr_value(constants::DW_AT_location)?;
+                        }
+                    }
+                }
+            },
+        };
+        match location {
+            Some(AttributeValue::Exprloc(expression)) => Ok(expression.0),
+            Some(_) => Err(Error::UnsupportedAttributeForm),
+            None => {
+                let mut empty = self.debug_info.reader().clone();
+                empty.empty();
+                Ok(empty)
+            }
+        }
+    }
+
+    /// Return a readable name for a `DebuggingInformationEntry`.
+    ///
+    /// If `demangler` is set, and it is able to demangle the entry's
+    /// `DW_AT_linkage_name` or `DW_AT_MIPS_linkage_name` attribute, then the
+    /// demangled name is returned. Otherwise the entry's `DW_AT_name` attribute
+    /// is returned, with any invalid UTF-8 replaced.
+    ///
+    /// Returns `None` if the entry has no name.
+    ///
+    /// This does not follow `DW_AT_abstract_origin` or `DW_AT_specification`.
+    /// Use `Dwarf::entry_name_with_origin` for that.
+    pub fn entry_name(
+        &self,
+        unit: &Unit<R>,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<String>> {
+        if let Some(demangle) = self.demangler {
+            let linkage_name = match entry.attr_value(constants::DW_AT_linkage_name)? {
+                Some(attr) => Some(attr),
+                None => entry.attr_value(constants::DW_AT_MIPS_linkage_name)?,
+            };
+            if let Some(attr) = linkage_name {
+                let linkage_name = self.attr_string(unit, attr)?;
+                if let Some(name) = demangle(&linkage_name.to_slice()?, unit.language) {
+                    return Ok(Some(name));
+                }
+            }
+        }
+        match entry.attr_value(constants::DW_AT_name)? {
+            Some(attr) => {
+                let name = self.attr_string(unit, attr)?;
+                Ok(Some(name.to_string_lossy()?.into_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Return a readable name for a `DebuggingInformationEntry`, following
+    /// references to other entries if needed.
+    ///
+    /// This is the same as `Dwarf::entry_name`, except that if the entry has
+    /// no name, then its `DW_AT_abstract_origin` or `DW_AT_specification`
+    /// attribute is followed using `Dwarf::resolve_reference`, and the name of
+    /// the referenced entry is returned. This allows names to be found for
+    /// entries that reference partial units or a supplementary object file,
+    /// such as those produced by `dwz`.
+    ///
+    /// Following a reference to another unit requires a linear search of the
+    /// unit headers, and parsing of that unit. Returns an error if a reference
+    /// is invalid, or if it references the supplementary object file and
+    /// `self.sup` is not set.
+    pub fn entry_name_with_origin(
+        &self,
+        unit: &Unit<R>,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<String>> {
+        // Limit the number of references that are followed, in case of cycles.
+        const MAX_DEPTH: usize = 16;
+
+        if let Some(name) = self.entry_name(unit, entry)? {
+            return Ok(Some(name));
+        }
+        let mut dwarf = self;
+        let mut resolved = match Self::entry_origin(dwarf, unit, entry)? {
+            Some(resolved) => resolved,
+            None => return Ok(None),
+        };
+        let mut owned_unit = None;
+        for _ in 0..MAX_DEPTH {
+            dwarf = resolved.dwarf(dwarf)?;
+            if let Some(next_unit) = resolved.unit.take() {
+                owned_unit = Some(next_unit);
+            }
+            let unit = owned_unit.as_ref().unwrap_or(unit);
+            let entry = unit.entry(resolved.offset)?;
+            if let Some(name) = dwarf.entry_name(unit, &entry)? {
+                return Ok(Some(name));
+            }
+            resolved = match Self::entry_origin(dwarf, unit, &entry)? {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+        }
+        Ok(None)
+    }
+
+    fn entry_origin(
+        dwarf: &Dwarf<R>,
+        unit: &Unit<R>,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<ResolvedEntry<R>>> {
+        let origin = match entry.attr_value(constants::DW_AT_abstract_origin)? {
+            Some(attr) => Some(attr),
+            None => entry.attr_value(constants::DW_AT_specification)?,
+        };
+        match origin {
+            Some(attr) => dwarf.resolve_reference(unit, attr),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the unit in `.debug_info` that contains the given offset.
+    ///
+    /// This requires a linear search of the unit headers.
+    ///
+    /// Returns an error if no unit contains the offset.
+    pub fn unit_containing(&self, offset: DebugInfoOffset<R::Offset>) -> Result<Unit<R>> {
+        let mut units = self.units();
+        while let Some(header) = units.next()? {
+            if offset.to_unit_offset(&header).is_some() {
+                return self.unit(header);
+            }
+        }
+        Err(Error::NoEntryAtGivenOffset)
+    }
+
+    /// Find the entry that is referenced by an attribute value.
+    ///
+    /// `unit` is the unit containing the attribute.
+    ///
+    /// The attribute value may be:
+    ///
+    /// - a `DW_FORM_ref*` reference to an entry in the same unit
+    /// - a `DW_FORM_ref_addr` reference to an entry in any unit in
+    /// `.debug_info`, such as a partial unit
+    /// - a `DW_FORM_ref_sup*` or `DW_FORM_GNU_ref_alt` reference to an entry
+    /// in the supplementary object file
+    ///
+    /// Returns `None` for other attribute values. Returns an error if the
+    /// reference is invalid, or if it references the supplementary object file
+    /// and `self.sup` is not set.
+    pub fn resolve_reference(
+        &self,
+        unit: &Unit<R>,
+        attr: AttributeValue<R>,
+    ) -> Result<Option<ResolvedEntry<R>>> {
+        let resolved = match attr {
+            AttributeValue::UnitRef(offset) => ResolvedEntry {
+                sup: false,
+                unit: None,
+                offset,
+            },
+            AttributeValue::DebugInfoRef(offset) => match offset.to_unit_offset(&unit.header) {
+                Some(offset) => ResolvedEntry {
+                    sup: false,
+                    unit: None,
+                    offset,
+                },
+                None => {
+                    let unit = self.unit_containing(offset)?;
+                    let offset = offset
+                        .to_unit_offset(&unit.header)
+                        .ok_or(Error::NoEntryAtGivenOffset)?;
+                    ResolvedEntry {
+                        sup: false,
+                        unit: Some(unit),
+                        offset,
+                    }
+                }
+            },
+            AttributeValue::DebugInfoRefSup(offset) => {
+                let sup = self.sup().ok_or(Error::MissingSupplementaryFile)?;
+                let unit = sup.unit_containing(offset)?;
+                let offset = offset
+                    .to_unit_offset(&unit.header)
+                    .ok_or(Error::NoEntryAtGivenOffset)?;
+                ResolvedEntry {
+                    sup: true,
+                    unit: Some(unit),
+                    offset,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(resolved))
+    }
+
+    /// Find the units that are imported by a unit.
+    ///
+    /// This returns the target of the `DW_AT_import` attribute of each
+    /// `DW_TAG_imported_unit` child of the root entry of `unit`. The targets are
+    /// usually the root entries of partial units in this file or in the
+    /// supplementary object file, such as those created by `dwz`.
+    ///
+    /// The returned units may themselves import other units.
+    pub fn imported_units(&self, unit: &Unit<R>) -> Result<Vec<ResolvedEntry<R>>> {
+        let mut imported = Vec::new();
+        let mut tree = unit.entries_tree(None)?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != constants::DW_TAG_imported_unit {
+                continue;
+            }
+            if let Some(attr) = entry.attr_value(constants::DW_AT_import)? {
+                if let Some(resolved) = self.resolve_reference(unit, attr)? {
+                    imported.push(resolved);
+                }
+            }
+        }
+        Ok(imported)
+    }
+
     /// Return the range list offset for the given raw offset.
     ///
     /// This handles adding `DW_AT_GNU_ranges_base` if required.
@@ -467,19 +832,56 @@ impl<R: Reader> Dwarf<R> {
             .get_offset(unit.encoding(), unit.rnglists_base, index)
     }
 
+    /// Return the filter for recognizing the addresses of code that was
+    /// removed by the linker.
+    ///
+    /// This is `self.tombstones`, with `zero` also set if the file uses
+    /// `AddressConvention::CodeSectionRelative`.
+    pub fn tombstone_filter(&self) -> TombstoneFilter {
+        let mut tombstones = self.tombstones;
+        if self.address_convention == AddressConvention::CodeSectionRelative {
+            tombstones.zero = true;
+        }
+        tombstones
+    }
+
+    /// Iterate over the aranges in the given set of entries from the
+    /// `.debug_aranges` section.
+    ///
+    /// Aranges that begin at a tombstone address are skipped, as determined
+    /// by `Dwarf::tombstone_filter`.
+    pub fn arange_entries(&self, header: &ArangeHeader<R>) -> ArangeEntryIter<R> {
+        let mut entries = header.entries();
+        entries.set_tombstone_filter(self.tombstone_filter());
+        entries
+    }
+
+    /// Parse all of the entries in the `.debug_aranges` section into a table
+    /// that is sorted by address.
+    ///
+    /// This is the same as `DebugAranges::table`, except that aranges that
+    /// begin at a tombstone address are skipped, as determined by
+    /// `Dwarf::tombstone_filter`.
+    pub fn arange_table(&self) -> Result<ArangeTable<R::Offset>> {
+        self.debug_aranges
+            .table_with_tombstones(self.tombstone_filter())
+    }
+
     /// Iterate over the `RangeListEntry`s starting at the given offset.
     pub fn ranges(
         &self,
         unit: &Unit<R>,
         offset: RangeListsOffset<R::Offset>,
     ) -> Result<RngListIter<R>> {
-        self.ranges.ranges(
+        let mut ranges = self.ranges.ranges(
             offset,
             unit.encoding(),
             unit.low_pc,
             &self.debug_addr,
             unit.addr_base,
-        )
+        )?;
+        ranges.set_tombstone_filter(self.tombstone_filter());
+        Ok(ranges)
     }
 
     /// Iterate over the `RawRngListEntry`ies starting at the given offset.
@@ -571,11 +973,17 @@ impl<R: Reader> Dwarf<R> {
                 _ => {}
             }
         }
-        let range = low_pc.and_then(|begin| {
-            let end = size.map(|size| begin + size).or(high_pc);
-            // TODO: perhaps return an error if `end` is `None`
-            end.map(|end| Range { begin, end })
-        });
+        let range = low_pc
+            .and_then(|begin| {
+                let end = size.map(|size| begin + size).or(high_pc);
+                // TODO: perhaps return an error if `end` is `None`
+                end.map(|end| Range { begin, end })
+            })
+            .filter(|range| {
+                !self
+                    .tombstone_filter()
+                    .is_tombstone(range.begin, unit.encoding().address_size)
+            });
         Ok(RangeIter(RangeIterInner::Single(range)))
     }
 
@@ -606,22 +1014,24 @@ impl<R: Reader> Dwarf<R> {
         unit: &Unit<R>,
         offset: LocationListsOffset<R::Offset>,
     ) -> Result<LocListIter<R>> {
-        match self.file_type {
+        let mut locations = match self.file_type {
             DwarfFileType::Main => self.locations.locations(
                 offset,
                 unit.encoding(),
                 unit.low_pc,
                 &self.debug_addr,
                 unit.addr_base,
-            ),
+            )?,
             DwarfFileType::Dwo => self.locations.locations_dwo(
                 offset,
                 unit.encoding(),
                 unit.low_pc,
                 &self.debug_addr,
                 unit.addr_base,
-            ),
-        }
+            )?,
+        };
+        locations.set_tombstone_filter(self.tombstone_filter());
+        Ok(locations)
     }
 
     /// Iterate over the raw `LocationListEntry`s starting at the given offset.
@@ -708,18 +1118,20 @@ impl<R: Reader> Dwarf<R> {
     pub fn format_error(&self, err: Error) -> String {
         #[allow(clippy::single_match)]
         match err {
-            Error::UnexpectedEof(id) => match self.lookup_offset_id(id) {
-                Some((sup, section, offset)) => {
-                    return format!(
-                        "{} at {}{}+0x{:x}",
-                        err,
-                        section.name(),
-                        if sup { "(sup)" } else { "" },
-                        offset.into_u64(),
-                    );
+            Error::UnexpectedEof(id) | Error::TruncatedSection(id) => {
+                match self.lookup_offset_id(id) {
+                    Some((sup, section, offset)) => {
+                        return format!(
+                            "{} at {}{}+0x{:x}",
+                            err,
+                            section.name(),
+                            if sup { "(sup)" } else { "" },
+                            offset.into_u64(),
+                        );
+                    }
+                    None => {}
                 }
-                None => {}
-            },
+            }
             _ => {}
         }
         err.description().into()
@@ -1046,7 +1458,7 @@ impl<R: Reader> DwarfPackage<R> {
                     types_size = section.size;
                 }
                 SectionId::DebugMacro | SectionId::DebugMacinfo => {
-                    // These are valid but we can't parse these yet.
+                    // These are valid but we don't load these from the package yet.
                 }
                 _ => return Err(Error::UnknownIndexSection),
             }
@@ -1074,6 +1486,8 @@ impl<R: Reader> DwarfPackage<R> {
 
         let debug_aranges = self.empty.clone().into();
         let debug_line_str = self.empty.clone().into();
+        let debug_macinfo = self.empty.clone().into();
+        let debug_macro = self.empty.clone().into();
 
         Ok(Dwarf {
             debug_abbrev,
@@ -1082,14 +1496,20 @@ impl<R: Reader> DwarfPackage<R> {
             debug_info,
             debug_line,
             debug_line_str,
+            debug_macinfo,
+            debug_macro,
             debug_str,
             debug_str_offsets,
             debug_types,
             locations: LocationLists::new(debug_loc, debug_loclists),
             ranges: RangeLists::new(debug_ranges, debug_rnglists),
             file_type: DwarfFileType::Dwo,
+            address_convention: parent.address_convention,
+            tombstones: parent.tombstones,
+            demangler: parent.demangler,
             sup: parent.sup.clone(),
             abbreviations_cache: AbbreviationsCache::new(),
+            debug_str_index: None,
         })
     }
 }
@@ -1114,6 +1534,9 @@ where
     /// The `DW_AT_comp_dir` attribute of the unit.
     pub comp_dir: Option<R>,
 
+    /// The `DW_AT_language` attribute of the unit.
+    pub language: Option<constants::DwLang>,
+
     /// The `DW_AT_low_pc` attribute of the unit. Defaults to 0.
     pub low_pc: u64,
 
@@ -1154,11 +1577,21 @@ impl<R: Reader> Unit<R> {
         dwarf: &Dwarf<R>,
         header: UnitHeader<R>,
         abbreviations: Arc<Abbreviations>,
+    ) -> Result<Self> {
+        Self::new_impl(dwarf, header, abbreviations, None)
+    }
+
+    fn new_impl(
+        dwarf: &Dwarf<R>,
+        header: UnitHeader<R>,
+        abbreviations: Arc<Abbreviations>,
+        line_program_cache: Option<&mut LineProgramCache<R>>,
     ) -> Result<Self> {
         let mut unit = Unit {
             abbreviations,
             name: None,
             comp_dir: None,
+            language: None,
             low_pc: 0,
             str_offsets_base: DebugStrOffsetsBase::default_for_encoding_and_file(
                 header.encoding(),
@@ -1199,6 +1632,11 @@ impl<R: Reader> Unit<R> {
                     constants::DW_AT_comp_dir => {
                         comp_dir = Some(attr.value());
                     }
+                    constants::DW_AT_language => {
+                        if let AttributeValue::Language(language) = attr.value() {
+                            unit.language = Some(language);
+                        }
+                    }
                     constants::DW_AT_low_pc => {
                         low_pc_attr = Some(attr.value());
                     }
@@ -1248,12 +1686,19 @@ impl<R: Reader> Unit<R> {
             None => None,
         };
         unit.line_program = match line_program_offset {
-            Some(offset) => Some(dwarf.debug_line.program(
-                offset,
-                unit.header.address_size(),
-                unit.comp_dir.clone(),
-                unit.name.clone(),
-            )?),
+            Some(offset) => {
+                let address_size = unit.header.address_size();
+                let comp_dir = unit.comp_dir.clone();
+                let comp_name = unit.name.clone();
+                let mut program = match line_program_cache {
+                    Some(cache) => cache.program(offset, address_size, comp_dir, comp_name)?,
+                    None => dwarf
+                        .debug_line
+                        .program(offset, address_size, comp_dir, comp_name)?,
+                };
+                program.set_tombstone_filter(dwarf.tombstone_filter());
+                Some(program)
+            }
             None => None,
         };
         if let Some(low_pc_attr) = low_pc_attr {
@@ -1430,6 +1875,14 @@ impl<'a, R: Reader> UnitRef<'a, R> {
         self.dwarf.attr_address(self.unit, attr)
     }
 
+    /// Return the `DW_AT_location` expression of the entry referenced by a
+    /// `DW_OP_call*` operation.
+    ///
+    /// See [`Dwarf::call_location`] for more information.
+    pub fn call_location(&self, reference: DieReference<R::Offset>) -> Result<R> {
+        self.dwarf.call_location(self.unit, reference)
+    }
+
     /// Return the range list offset for the given raw offset.
     ///
     /// This handles adding `DW_AT_GNU_ranges_base` if required.
@@ -1482,6 +1935,45 @@ impl<'a, R: Reader> UnitRef<'a, R> {
         self.dwarf.die_ranges(self.unit, entry)
     }
 
+    /// Return a readable name for a `DebuggingInformationEntry`.
+    ///
+    /// See `Dwarf::entry_name` for details.
+    #[inline]
+    pub fn entry_name(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<String>> {
+        self.dwarf.entry_name(self.unit, entry)
+    }
+
+    /// Return a readable name for a `DebuggingInformationEntry`, following
+    /// references to other entries if needed.
+    ///
+    /// See `Dwarf::entry_name_with_origin` for details.
+    #[inline]
+    pub fn entry_name_with_origin(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<String>> {
+        self.dwarf.entry_name_with_origin(self.unit, entry)
+    }
+
+    /// Find the entry that is referenced by an attribute value.
+    ///
+    /// See `Dwarf::resolve_reference` for details.
+    #[inline]
+    pub fn resolve_reference(&self, attr: AttributeValue<R>) -> Result<Option<ResolvedEntry<R>>> {
+        self.dwarf.resolve_reference(self.unit, attr)
+    }
+
+    /// Find the units that are imported by this unit.
+    ///
+    /// See `Dwarf::imported_units` for details.
+    #[inline]
+    pub fn imported_units(&self) -> Result<Vec<ResolvedEntry<R>>> {
+        self.dwarf.imported_units(self.unit)
+    }
+
     /// Return an iterator for the address ranges of the `Unit`.
     ///
     /// This uses `DW_AT_low_pc`, `DW_AT_high_pc` and `DW_AT_ranges` of the
@@ -1580,6 +2072,95 @@ impl<T: ReaderOffset> UnitOffset<T> {
     }
 }
 
+/// The location of an entry that is referenced by an attribute value.
+///
+/// Returned by `Dwarf::resolve_reference` and `Dwarf::imported_units`.
+#[derive(Debug)]
+pub struct ResolvedEntry<R: Reader> {
+    /// True if the entry is in the supplementary object file.
+    pub sup: bool,
+    /// The unit containing the entry.
+    ///
+    /// This is `None` if the entry is in the same unit as the reference.
+    pub unit: Option<Unit<R>>,
+    /// The offset of the entry within its unit.
+    pub offset: UnitOffset<R::Offset>,
+}
+
+impl<R: Reader> ResolvedEntry<R> {
+    /// Return the `Dwarf` containing the entry.
+    ///
+    /// `dwarf` is the `Dwarf` containing the reference.
+    pub fn dwarf<'a>(&self, dwarf: &'a Dwarf<R>) -> Result<&'a Dwarf<R>> {
+        if self.sup {
+            dwarf.sup().ok_or(Error::MissingSupplementaryFile)
+        } else {
+            Ok(dwarf)
+        }
+    }
+
+    /// Return the unit containing the entry.
+    ///
+    /// `unit` is the unit containing the reference.
+    pub fn unit<'a>(&'a self, unit: &'a Unit<R>) -> &'a Unit<R> {
+        self.unit.as_ref().unwrap_or(unit)
+    }
+
+    /// Return a reference to the unit containing the entry and its associated `Dwarf`.
+    ///
+    /// `unit` is the unit containing the reference.
+    pub fn unit_ref<'a>(&'a self, unit: UnitRef<'a, R>) -> Result<UnitRef<'a, R>> {
+        Ok(UnitRef::new(self.dwarf(unit.dwarf)?, self.unit(unit.unit)))
+    }
+
+    /// Read the referenced entry.
+    ///
+    /// `unit` is the unit containing the reference.
+    pub fn entry<'a>(&'a self, unit: &'a Unit<R>) -> Result<DebuggingInformationEntry<'a, 'a, R>> {
+        self.unit(unit).entry(self.offset)
+    }
+}
+
+/// An iterator over the units in the `.debug_info` section.
+///
+/// Returned by `Dwarf::parsed_units`.
+#[derive(Debug, Clone)]
+pub struct ParsedUnitsIter<'a, R: Reader> {
+    dwarf: &'a Dwarf<R>,
+    headers: DebugInfoUnitHeadersIter<R>,
+}
+
+impl<'a, R: Reader> ParsedUnitsIter<'a, R> {
+    /// Advance the iterator to the next unit.
+    ///
+    /// Returns `Ok(None)` when iteration is complete.
+    ///
+    /// If an error occurs while parsing a unit header, then this error is
+    /// returned, and all subsequent calls return `Ok(None)`. If an error occurs
+    /// while parsing the abbreviations or root entry of a unit, then this
+    /// error is returned, and the next call will continue with the following
+    /// unit.
+    pub fn next(&mut self) -> Result<Option<Unit<R>>> {
+        match self.headers.next()? {
+            Some(header) => self.dwarf.unit(header).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'a, R: Reader> fallible_iterator::FallibleIterator for ParsedUnitsIter<'a, R> {
+    type Item = Unit<R>;
+    type Error = Error;
+
+    #[inline]
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        ParsedUnitsIter::next(self)
+    }
+}
+
+result_iterator!(['a, R: Reader] ParsedUnitsIter<'a, R>, Unit<R>);
+
 /// An iterator for the address ranges of a `DebuggingInformationEntry`.
 ///
 /// Returned by `Dwarf::die_ranges` and `Dwarf::unit_ranges`.
@@ -1619,6 +2200,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for RangeIter<R> {
     }
 }
 
+result_iterator!([R: Reader] RangeIter<R>, Range);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1634,6 +2217,24 @@ mod tests {
         }
     }
 
+    /// Ensure that the parsed state can be shared between threads.
+    #[test]
+    fn test_send_sync() {
+        /// This only needs to compile.
+        fn assert_send_sync<T: Send + Sync>() {}
+        type R = EndianSlice<'static, LittleEndian>;
+        assert_send_sync::<Dwarf<R>>();
+        assert_send_sync::<DwarfPackage<R>>();
+        assert_send_sync::<crate::read::DwarfFiles<R>>();
+        assert_send_sync::<Unit<R>>();
+        assert_send_sync::<crate::read::UnitCache<R>>();
+        assert_send_sync::<crate::read::Abbreviations>();
+        assert_send_sync::<crate::read::AbbreviationsCache>();
+        assert_send_sync::<crate::read::AddressMap>();
+        assert_send_sync::<crate::read::DebugStrIndex>();
+        assert_send_sync::<crate::read::ScopeIndex>();
+    }
+
     /// Ensure that `Unit<R>` is covariant wrt R.
     #[test]
     fn test_dwarf_unit_variance() {
@@ -1650,6 +2251,24 @@ mod tests {
         assert_is_send::<Unit<EndianSlice<'_, LittleEndian>>>();
     }
 
+    #[test]
+    fn test_find_string() {
+        let mut dwarf = Dwarf {
+            debug_str: DebugStr::new(b"main\0unsigned int\0", LittleEndian),
+            ..Default::default()
+        };
+        assert_eq!(dwarf.find_string(b"int"), Ok(Some(DebugStrOffset(14))));
+        assert_eq!(dwarf.find_string(b"long"), Ok(None));
+
+        dwarf.populate_debug_str_index().unwrap();
+        assert_eq!(dwarf.find_string(b"int"), Ok(Some(DebugStrOffset(14))));
+        assert_eq!(dwarf.find_string(b"long"), Ok(None));
+        assert_eq!(
+            dwarf.debug_str_index.as_ref().map(|index| index.len()),
+            Some(2)
+        );
+    }
+
     #[test]
     fn test_format_error() {
         let dwarf_sections = DwarfSections::load(|_| -> Result<_> { Ok(vec![1, 2]) }).unwrap();
@@ -1678,4 +2297,577 @@ mod tests {
         }
         assert_eq!(dwarf.format_error(Error::Io), Error::Io.description());
     }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_address_convention() {
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        for low_pc in [0, 0x10].iter() {
+            let child = unit.add(root, constants::DW_TAG_subprogram);
+            let entry = unit.get_mut(child);
+            entry.set(
+                constants::DW_AT_low_pc,
+                write::AttributeValue::Address(write::Address::Constant(*low_pc)),
+            );
+            entry.set(constants::DW_AT_high_pc, write::AttributeValue::Udata(8));
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let ranges = |dwarf: &Dwarf<_>| {
+            let header = dwarf.units().next().unwrap().unwrap();
+            let unit = dwarf.unit(header).unwrap();
+            let mut ranges = vec![];
+            let mut entries = unit.entries();
+            while let Some((_, entry)) = entries.next_dfs().unwrap() {
+                if entry.tag() == constants::DW_TAG_subprogram {
+                    let mut iter = dwarf.die_ranges(&unit, entry).unwrap();
+                    while let Some(range) = iter.next().unwrap() {
+                        ranges.push(range);
+                    }
+                }
+            }
+            ranges
+        };
+        assert_eq!(
+            ranges(&dwarf),
+            vec![
+                Range { begin: 0, end: 8 },
+                Range {
+                    begin: 0x10,
+                    end: 0x18
+                }
+            ]
+        );
+        dwarf.address_convention = AddressConvention::CodeSectionRelative;
+        assert_eq!(
+            ranges(&dwarf),
+            vec![Range {
+                begin: 0x10,
+                end: 0x18
+            }]
+        );
+    }
+
+    #[test]
+    fn test_arange_tombstones() {
+        #[rustfmt::skip]
+        let buf = [
+            // unit_length, version, debug_info_offset, address_size, segment_size, padding
+            0x2c, 0x00, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00, 0x00, 0x00, 0x04, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            // An arange at address 0.
+            0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+            // An arange at the maximum address.
+            0xff, 0xff, 0xff, 0xff, 0x08, 0x00, 0x00, 0x00,
+            // A valid arange.
+            0x10, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+            // Terminator.
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut dwarf = Dwarf {
+            debug_aranges: DebugAranges::new(&buf, LittleEndian),
+            ..Default::default()
+        };
+        let addresses = |dwarf: &Dwarf<_>| {
+            let header = dwarf.debug_aranges.headers().next().unwrap().unwrap();
+            let mut entries = dwarf.arange_entries(&header);
+            let mut addresses = Vec::new();
+            while let Some(entry) = entries.next().unwrap() {
+                addresses.push(entry.address());
+            }
+            addresses
+        };
+        assert_eq!(addresses(&dwarf), [0, 0xffff_ffff, 0x10]);
+        assert_eq!(
+            dwarf.arange_table().unwrap().find(0),
+            Some(DebugInfoOffset(0x10))
+        );
+
+        dwarf.address_convention = AddressConvention::CodeSectionRelative;
+        dwarf.tombstones.max = true;
+        assert_eq!(addresses(&dwarf), [0x10]);
+        let table = dwarf.arange_table().unwrap();
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.find(0), None);
+        assert_eq!(table.find(0x10), Some(DebugInfoOffset(0x10)));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_address_convention_lines_locations() {
+        use crate::read::LocationListEntry;
+        use crate::write::{
+            self, EndianVec, LineProgram, LineString, Location, LocationList, Sections,
+        };
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 5,
+            address_size: 4,
+        };
+        let mut program = LineProgram::new(
+            encoding,
+            crate::LineEncoding::default(),
+            LineString::String(b"/dir".to_vec()),
+            LineString::String(b"a.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let file = program.add_file(LineString::String(b"a.c".to_vec()), dir, None);
+        for address in [0, 0x10].iter() {
+            program.begin_sequence(Some(write::Address::Constant(*address)));
+            program.row().file = file;
+            program.generate_row();
+            program.end_sequence(8);
+        }
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf.units.add(write::Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_reg0);
+        let loc_list = unit.locations.add(LocationList(
+            [0, 0x10]
+                .iter()
+                .map(|begin| Location::StartLength {
+                    begin: write::Address::Constant(*begin),
+                    length: 8,
+                    data: expression.clone(),
+                })
+                .collect(),
+        ));
+        let root = unit.root();
+        let var = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(var).set(
+            constants::DW_AT_location,
+            write::AttributeValue::LocationListRef(loc_list),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut dwarf = sections.read_dwarf();
+        let addresses = |dwarf: &Dwarf<_>| {
+            let header = dwarf.units().next().unwrap().unwrap();
+            let unit = dwarf.unit(header).unwrap();
+            let mut rows = unit.line_program.clone().unwrap().rows();
+            let mut lines = vec![];
+            while let Some((_, row)) = rows.next_row().unwrap() {
+                if !row.end_sequence() {
+                    lines.push(row.address());
+                }
+            }
+            let mut entries = unit.entries();
+            let mut locations = vec![];
+            while let Some((_, entry)) = entries.next_dfs().unwrap() {
+                if let Some(attr) = entry.attr_value(constants::DW_AT_location).unwrap() {
+                    let mut iter = dwarf.attr_locations(&unit, attr).unwrap().unwrap();
+                    while let Some(LocationListEntry { range, .. }) = iter.next().unwrap() {
+                        locations.push(range.begin);
+                    }
+                }
+            }
+            (lines, locations)
+        };
+        assert_eq!(addresses(&dwarf), (vec![0, 0x10], vec![0, 0x10]));
+        dwarf.address_convention = AddressConvention::CodeSectionRelative;
+        assert_eq!(addresses(&dwarf), (vec![0x10], vec![0x10]));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_call_location() {
+        use crate::read::{EvaluationResult, Location, Value};
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id1 = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit_id2 = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+
+        let unit2 = dwarf.units.get_mut(unit_id2);
+        let proc2 = unit2.add(unit2.root(), constants::DW_TAG_dwarf_procedure);
+        let mut expression = write::Expression::new();
+        expression.op_plus_uconst(4);
+        unit2.get_mut(proc2).set(
+            constants::DW_AT_location,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        let unit1 = dwarf.units.get_mut(unit_id1);
+        let proc1 = unit1.add(unit1.root(), constants::DW_TAG_dwarf_procedure);
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_lit2);
+        expression.op(constants::DW_OP_mul);
+        unit1.get_mut(proc1).set(
+            constants::DW_AT_location,
+            write::AttributeValue::Exprloc(expression),
+        );
+        let proc_empty = unit1.add(unit1.root(), constants::DW_TAG_dwarf_procedure);
+        let variable = unit1.add(unit1.root(), constants::DW_TAG_variable);
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_lit3);
+        expression.op_call(proc1);
+        expression.op_call(proc_empty);
+        expression.op_call_ref(write::Reference::Entry(unit_id2, proc2));
+        expression.op(constants::DW_OP_stack_value);
+        unit1.get_mut(variable).set(
+            constants::DW_AT_location,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let unit = dwarf.parsed_units().next().unwrap().unwrap();
+        let mut entries = unit.entries();
+        let expression = loop {
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            if entry.tag() == constants::DW_TAG_variable {
+                break entry.attr(constants::DW_AT_location).unwrap().unwrap();
+            }
+        };
+        let expression = expression.exprloc_value().unwrap();
+        let mut evaluation = expression.evaluation(encoding);
+        let mut calls = 0;
+        let mut result = evaluation.evaluate().unwrap();
+        while let EvaluationResult::RequiresAtLocation(reference) = result {
+            let location = dwarf.call_location(&unit, reference).unwrap();
+            result = evaluation.resume_with_at_location(location).unwrap();
+            calls += 1;
+        }
+        assert_eq!(result, EvaluationResult::Complete);
+        assert_eq!(calls, 3);
+        let pieces = evaluation.result();
+        assert_eq!(
+            pieces[0].location,
+            Location::Value {
+                value: Value::Generic(10)
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_parsed_units() {
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        for (name, low_pc) in [("a.c", 0x1000), ("b.c", 0x2000)] {
+            let unit_id = dwarf
+                .units
+                .add(write::Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            unit.form_selector = Some(|name, _, _| match name {
+                constants::DW_AT_ranges => Some(constants::DW_FORM_rnglistx),
+                _ => None,
+            });
+            let root = unit.root();
+            let root = unit.get_mut(root);
+            root.set(
+                constants::DW_AT_name,
+                write::AttributeValue::String(name.into()),
+            );
+            root.set(
+                constants::DW_AT_comp_dir,
+                write::AttributeValue::String(b"/src".to_vec()),
+            );
+            root.set(
+                constants::DW_AT_low_pc,
+                write::AttributeValue::Address(write::Address::Constant(low_pc)),
+            );
+            let range_list_id =
+                unit.ranges
+                    .add(write::RangeList(vec![write::Range::StartLength {
+                        begin: write::Address::Constant(low_pc),
+                        length: 0x10,
+                    }]));
+            let child = unit.add(unit.root(), constants::DW_TAG_subprogram);
+            unit.get_mut(child).set(
+                constants::DW_AT_ranges,
+                write::AttributeValue::RangeListRef(range_list_id),
+            );
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ranges: RangeLists::new(
+                DebugRanges::new(sections.debug_ranges.slice(), LittleEndian),
+                DebugRngLists::new(sections.debug_rnglists.slice(), LittleEndian),
+            ),
+            ..Default::default()
+        };
+        let mut units = dwarf.parsed_units();
+        let mut headers = dwarf.units();
+        for (name, low_pc) in [("a.c", 0x1000), ("b.c", 0x2000)] {
+            let unit = units.next().unwrap().unwrap();
+            assert_eq!(unit.header, headers.next().unwrap().unwrap());
+            assert_eq!(unit.name.unwrap().slice(), name.as_bytes());
+            assert_eq!(unit.comp_dir.unwrap().slice(), b"/src");
+            assert_eq!(unit.low_pc, low_pc);
+            // The range list of the child can only be found using the
+            // `DW_AT_rnglists_base` of the unit.
+            assert_ne!(unit.rnglists_base, DebugRngListsBase(0));
+            let mut entries = unit.entries();
+            entries.next_dfs().unwrap();
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            let mut ranges = dwarf.die_ranges(&unit, entry).unwrap();
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(Range {
+                    begin: low_pc,
+                    end: low_pc + 0x10,
+                })
+            );
+        }
+        assert!(units.next().unwrap().is_none());
+
+        assert_eq!(dwarf.parsed_units().into_result_iter().count(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_entry_name() {
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_language,
+            write::AttributeValue::Language(constants::DW_LANG_Rust),
+        );
+        let child = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(child);
+        entry.set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"f".to_vec()),
+        );
+        entry.set(
+            constants::DW_AT_linkage_name,
+            write::AttributeValue::String(b"_RNvC1a1f".to_vec()),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let name = |dwarf: &Dwarf<_>| {
+            let header = dwarf.units().next().unwrap().unwrap();
+            let unit = dwarf.unit(header).unwrap();
+            assert_eq!(unit.language, Some(constants::DW_LANG_Rust));
+            let mut entries = unit.entries();
+            entries.next_dfs().unwrap();
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            dwarf.entry_name(&unit, entry).unwrap()
+        };
+        assert_eq!(name(&dwarf), Some("f".into()));
+
+        dwarf.demangler = Some(|name, language| {
+            assert_eq!(language, Some(constants::DW_LANG_Rust));
+            if name == b"_RNvC1a1f" {
+                Some("a::f".into())
+            } else {
+                None
+            }
+        });
+        assert_eq!(name(&dwarf), Some("a::f".into()));
+
+        dwarf.demangler = Some(|_, _| None);
+        assert_eq!(name(&dwarf), Some("f".into()));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_imported_units() {
+        use crate::write::{self, EndianVec, LineProgram, Reference, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+
+        // The supplementary object file.
+        let mut sup = write::Dwarf::new();
+        let sup_unit_id = sup
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = sup.units.get_mut(sup_unit_id);
+        let sup_root = unit.root();
+        let sup_subprogram = unit.add(sup_root, constants::DW_TAG_subprogram);
+        unit.get_mut(sup_subprogram).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"g".to_vec()),
+        );
+        let mut sup_sections = Sections::new(EndianVec::new(LittleEndian));
+        let sup_offsets = sup
+            .units
+            .write(
+                &mut sup_sections,
+                &write::DebugLineStrOffsets::none(),
+                &write::DebugStrOffsets::none(),
+            )
+            .unwrap();
+
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let partial_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(partial_id);
+        let partial_root = unit.root();
+        let partial_subprogram = unit.add(partial_root, constants::DW_TAG_subprogram);
+        unit.get_mut(partial_subprogram).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"f".to_vec()),
+        );
+
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let import = unit.add(root, constants::DW_TAG_imported_unit);
+        unit.get_mut(import).set(
+            constants::DW_AT_import,
+            write::AttributeValue::DebugInfoRef(Reference::Entry(partial_id, partial_root)),
+        );
+        let import_sup = unit.add(root, constants::DW_TAG_imported_unit);
+        unit.get_mut(import_sup).set(
+            constants::DW_AT_import,
+            write::AttributeValue::DebugInfoRefSup(sup_offsets.entry(sup_unit_id, sup_root)),
+        );
+        let inlined = unit.add(root, constants::DW_TAG_inlined_subroutine);
+        unit.get_mut(inlined).set(
+            constants::DW_AT_abstract_origin,
+            write::AttributeValue::DebugInfoRef(Reference::Entry(partial_id, partial_subprogram)),
+        );
+        let specification = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(specification).set(
+            constants::DW_AT_specification,
+            write::AttributeValue::DebugInfoRefSup(sup_offsets.entry(sup_unit_id, sup_subprogram)),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+        let mut entries = unit.entries();
+        entries.next_dfs().unwrap();
+        entries.next_dfs().unwrap();
+        entries.next_dfs().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), constants::DW_TAG_inlined_subroutine);
+        let inlined = entry.offset();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), constants::DW_TAG_subprogram);
+        let specification = entry.offset();
+
+        let name = |dwarf: &Dwarf<_>, offset| {
+            let entry = unit.entry(offset).unwrap();
+            dwarf.entry_name_with_origin(&unit, &entry)
+        };
+        // `entry_name` does not follow references.
+        let entry = unit.entry(specification).unwrap();
+        assert_eq!(dwarf.entry_name(&unit, &entry), Ok(None));
+        assert_eq!(name(&dwarf, inlined), Ok(Some("f".into())));
+        assert_eq!(
+            name(&dwarf, specification),
+            Err(Error::MissingSupplementaryFile)
+        );
+        assert_eq!(
+            dwarf.imported_units(&unit).map(|units| units.len()),
+            Err(Error::MissingSupplementaryFile)
+        );
+
+        dwarf.set_sup(Dwarf {
+            debug_abbrev: DebugAbbrev::new(sup_sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sup_sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        });
+        assert_eq!(name(&dwarf, inlined), Ok(Some("f".into())));
+        assert_eq!(name(&dwarf, specification), Ok(Some("g".into())));
+
+        let imported = unit.unit_ref(&dwarf).imported_units().unwrap();
+        assert_eq!(imported.len(), 2);
+        assert!(!imported[0].sup);
+        let mut headers = dwarf.units();
+        headers.next().unwrap();
+        let partial_header = headers.next().unwrap().unwrap();
+        let partial = imported[0].unit_ref(unit.unit_ref(&dwarf)).unwrap();
+        assert_eq!(partial.header.offset(), partial_header.offset());
+        assert_eq!(
+            imported[0].entry(&unit).unwrap().tag(),
+            constants::DW_TAG_compile_unit
+        );
+        assert_eq!(
+            imported[0]
+                .entry(&unit)
+                .unwrap()
+                .attr_value(constants::DW_AT_name),
+            Ok(None)
+        );
+        assert!(imported[1].sup);
+        assert!(core::ptr::eq(
+            imported[1].unit_ref(unit.unit_ref(&dwarf)).unwrap().dwarf,
+            dwarf.sup().unwrap()
+        ));
+        assert_eq!(
+            imported[1].unit.as_ref().unwrap().header.offset(),
+            UnitSectionOffset::DebugInfoOffset(sup_offsets.unit(sup_unit_id))
+        );
+    }
 }
@@ -0,0 +1,438 @@
+use crate::common::{DebugTypeSignature, DwoId};
+use crate::read::{
+    DebugInfoUnitHeadersIter, Dwarf, DwarfPackage, Reader, Result, Unit, UnitHeader, UnitRef,
+    UnitType,
+};
+
+/// The DWARF sections for an executable, together with its `.dwp` package
+/// and supplementary object file.
+///
+/// With split DWARF, the skeleton units in the executable refer to split units
+/// in the `.dwp` package, and the split units use the `.debug_addr` section
+/// of the executable. With a supplementary object file, entries and strings
+/// may be stored in the supplementary object file instead of the executable.
+/// This type finds units in the file that contains them, and returns them with
+/// the `Dwarf` that must be used to read their attributes.
+///
+/// The supplementary object file is stored in `self.dwarf.sup`, and is also
+/// used for the units in the `.dwp` package.
+#[derive(Debug)]
+pub struct DwarfFiles<R: Reader> {
+    /// The DWARF sections for the executable.
+    pub dwarf: Dwarf<R>,
+
+    /// The `.dwp` package containing the split units, if any.
+    pub dwp: Option<DwarfPackage<R>>,
+}
+
+impl<R: Reader> DwarfFiles<R> {
+    /// Create a new `DwarfFiles` for the DWARF sections of an executable.
+    ///
+    /// Use `set_dwp` and `set_sup` to add the other files.
+    pub fn new(dwarf: Dwarf<R>) -> Self {
+        DwarfFiles { dwarf, dwp: None }
+    }
+
+    /// Store the `.dwp` package containing the split units.
+    pub fn set_dwp(&mut self, dwp: DwarfPackage<R>) {
+        self.dwp = Some(dwp);
+    }
+
+    /// Store the DWARF sections for the supplementary object file.
+    pub fn set_sup(&mut self, sup: Dwarf<R>) {
+        self.dwarf.set_sup(sup);
+    }
+
+    /// Return a reference to the DWARF sections for the supplementary object file.
+    pub fn sup(&self) -> Option<&Dwarf<R>> {
+        self.dwarf.sup()
+    }
+
+    /// Iterate the unit headers in the `.debug_info` section of the executable.
+    ///
+    /// Can be [used with
+    /// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+    #[inline]
+    pub fn units(&self) -> DebugInfoUnitHeadersIter<R> {
+        self.dwarf.units()
+    }
+
+    /// Construct a new `Unit` from the given unit header in the executable.
+    ///
+    /// If the unit is a skeleton unit and its split unit is in the `.dwp`
+    /// package, then the split unit is returned instead. Otherwise the unit
+    /// in the executable is returned.
+    pub fn unit(&self, header: UnitHeader<R>) -> Result<ResolvedUnit<R>> {
+        let unit = self.dwarf.unit(header)?;
+        match self.split_unit(&unit)? {
+            Some(split) => Ok(split),
+            None => Ok(ResolvedUnit { dwo: None, unit }),
+        }
+    }
+
+    /// Find the split unit in the `.dwp` package for a skeleton unit.
+    ///
+    /// The attributes that are subject to relocation are copied from
+    /// `skeleton` to the split unit.
+    ///
+    /// Returns `None` if `skeleton` does not have a DWO identifier, if there
+    /// is no `.dwp` package, or if the package does not contain the unit.
+    pub fn split_unit(&self, skeleton: &Unit<R>) -> Result<Option<ResolvedUnit<R>>> {
+        let dwo_id = match skeleton.dwo_id {
+            Some(dwo_id) => dwo_id,
+            None => return Ok(None),
+        };
+        let dwo = match self.find_cu(dwo_id)? {
+            Some(dwo) => dwo,
+            None => return Ok(None),
+        };
+        let header = match dwo.units().next()? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let mut unit = dwo.unit(header)?;
+        unit.copy_relocated_attributes(skeleton);
+        Ok(Some(ResolvedUnit {
+            dwo: Some(dwo),
+            unit,
+        }))
+    }
+
+    /// Find the type unit with the given type signature.
+    ///
+    /// This searches the `.dwp` package first, using its type unit index.
+    /// If the unit is not found there, then this searches the `.debug_types`
+    /// and `.debug_info` sections of the executable, which requires a linear
+    /// search of the unit headers.
+    ///
+    /// Returns `None` if the type unit is not found.
+    pub fn type_unit(&self, signature: DebugTypeSignature) -> Result<Option<ResolvedUnit<R>>> {
+        if let Some(dwp) = &self.dwp {
+            if let Some(dwo) = dwp.find_tu(signature, &self.dwarf)? {
+                if let Some(header) = find_type_unit(&dwo, signature)? {
+                    let unit = dwo.unit(header)?;
+                    return Ok(Some(ResolvedUnit {
+                        dwo: Some(dwo),
+                        unit,
+                    }));
+                }
+            }
+        }
+        if let Some(header) = find_type_unit(&self.dwarf, signature)? {
+            let unit = self.dwarf.unit(header)?;
+            return Ok(Some(ResolvedUnit { dwo: None, unit }));
+        }
+        Ok(None)
+    }
+
+    /// Find the compilation unit with the given DWO identifier in the `.dwp`
+    /// package and return its section contributions.
+    ///
+    /// Returns `None` if there is no `.dwp` package, or if the package does
+    /// not contain the unit.
+    pub fn find_cu(&self, id: DwoId) -> Result<Option<Dwarf<R>>> {
+        match &self.dwp {
+            Some(dwp) => dwp.find_cu(id, &self.dwarf),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Find the header of the type unit with the given signature in the
+/// `.debug_types` or `.debug_info` sections of `dwarf`.
+fn find_type_unit<R: Reader>(
+    dwarf: &Dwarf<R>,
+    signature: DebugTypeSignature,
+) -> Result<Option<UnitHeader<R>>> {
+    let matches = |header: &UnitHeader<R>| match header.type_() {
+        UnitType::Type { type_signature, .. } | UnitType::SplitType { type_signature, .. } => {
+            type_signature == signature
+        }
+        _ => false,
+    };
+    let mut headers = dwarf.type_units();
+    while let Some(header) = headers.next()? {
+        if matches(&header) {
+            return Ok(Some(header));
+        }
+    }
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        if matches(&header) {
+            return Ok(Some(header));
+        }
+    }
+    Ok(None)
+}
+
+/// A unit that was found by `DwarfFiles`.
+///
+/// Returned by `DwarfFiles::unit`, `DwarfFiles::split_unit`, and
+/// `DwarfFiles::type_unit`.
+#[derive(Debug)]
+pub struct ResolvedUnit<R: Reader> {
+    /// The section contributions from the `.dwp` package for the unit.
+    ///
+    /// This is `None` if the unit is in the executable.
+    pub dwo: Option<Dwarf<R>>,
+
+    /// The unit.
+    pub unit: Unit<R>,
+}
+
+impl<R: Reader> ResolvedUnit<R> {
+    /// Return the `Dwarf` that contains the unit.
+    ///
+    /// This is the `Dwarf` that must be used for reading strings, addresses,
+    /// and other attribute values of the unit.
+    pub fn dwarf<'a>(&'a self, files: &'a DwarfFiles<R>) -> &'a Dwarf<R> {
+        self.dwo.as_ref().unwrap_or(&files.dwarf)
+    }
+
+    /// Return a reference to the unit and the `Dwarf` that contains it.
+    pub fn unit_ref<'a>(&'a self, files: &'a DwarfFiles<R>) -> UnitRef<'a, R> {
+        UnitRef::new(self.dwarf(files), &self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{DebugAddrIndex, Format, SectionId};
+    use crate::constants;
+    use crate::endianity::LittleEndian;
+    use crate::read::abbrev::tests::AbbrevSectionMethods;
+    use crate::read::{DebugAbbrev, DebugAddr, DebugInfo, EndianSlice};
+    use crate::test_util::GimliSectionMethods;
+    use test_assembler::{Endian, Label, LabelMaker, Section};
+
+    #[test]
+    fn test_dwarf_files() {
+        const DWO_ID: u64 = 0x1234_5678_9abc_def0;
+        const DWO_SIGNATURE: u64 = 0xfedc_ba98_7654_3210;
+        const MAIN_SIGNATURE: u64 = 0x1111_2222_3333_4444;
+        let format = Format::Dwarf32;
+
+        // The executable.
+        #[rustfmt::skip]
+        let debug_abbrev = Section::with_endian(Endian::Little)
+            .abbrev(1, constants::DW_TAG_skeleton_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_addr_base, constants::DW_FORM_sec_offset)
+                .abbrev_attr_null()
+            .abbrev(2, constants::DW_TAG_type_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_name, constants::DW_FORM_string)
+                .abbrev_attr_null()
+            .abbrev(3, constants::DW_TAG_compile_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_name, constants::DW_FORM_string)
+                .abbrev_attr_null()
+            .abbrev_null();
+
+        let addr_length = Label::new();
+        let addr_start = Label::new();
+        let addr_base = Label::new();
+        let addr_end = Label::new();
+        let addr_zero = Label::new();
+        let debug_addr = Section::with_endian(Endian::Little)
+            .mark(&addr_zero)
+            .initial_length(format, &addr_length, &addr_start)
+            .D16(5)
+            .D8(8)
+            .D8(0)
+            .mark(&addr_base)
+            .D64(0x1000)
+            .mark(&addr_end);
+        addr_length.set_const((&addr_end - &addr_start) as u64);
+
+        let lengths = [Label::new(), Label::new(), Label::new()];
+        let starts = [Label::new(), Label::new(), Label::new()];
+        let ends = [Label::new(), Label::new(), Label::new()];
+        #[rustfmt::skip]
+        let debug_info = Section::with_endian(Endian::Little)
+            .initial_length(format, &lengths[0], &starts[0])
+                .D16(5).D8(constants::DW_UT_skeleton.0).D8(8).D32(0).D64(DWO_ID)
+                .uleb(1).D32((&addr_base - &addr_zero) as u32)
+                .mark(&ends[0])
+            .initial_length(format, &lengths[1], &starts[1])
+                .D16(5).D8(constants::DW_UT_type.0).D8(8).D32(0).D64(MAIN_SIGNATURE).D32(24)
+                .uleb(2).append_bytes(b"main_type\0")
+                .mark(&ends[1])
+            .initial_length(format, &lengths[2], &starts[2])
+                .D16(5).D8(constants::DW_UT_compile.0).D8(8).D32(0)
+                .uleb(3).append_bytes(b"main.c\0")
+                .mark(&ends[2]);
+        for i in 0..3 {
+            lengths[i].set_const((&ends[i] - &starts[i]) as u64);
+        }
+
+        // The `.dwp` package.
+        #[rustfmt::skip]
+        let dwp_abbrev = Section::with_endian(Endian::Little)
+            .abbrev(1, constants::DW_TAG_compile_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_name, constants::DW_FORM_strx1)
+                .abbrev_attr(constants::DW_AT_low_pc, constants::DW_FORM_addrx1)
+                .abbrev_attr_null()
+            .abbrev(2, constants::DW_TAG_type_unit, constants::DW_CHILDREN_no)
+                .abbrev_attr(constants::DW_AT_name, constants::DW_FORM_strx1)
+                .abbrev_attr_null()
+            .abbrev_null();
+        let dwp_abbrev = dwp_abbrev.get_contents().unwrap();
+
+        let zero = Label::new();
+        let lengths = [Label::new(), Label::new()];
+        let starts = [Label::new(), Label::new()];
+        let units = [Label::new(), Label::new()];
+        let ends = [Label::new(), Label::new()];
+        #[rustfmt::skip]
+        let dwp_info = Section::with_endian(Endian::Little)
+            .mark(&zero)
+            .mark(&units[0])
+            .initial_length(format, &lengths[0], &starts[0])
+                .D16(5).D8(constants::DW_UT_split_compile.0).D8(8).D32(0).D64(DWO_ID)
+                .uleb(1).D8(0).D8(0)
+                .mark(&ends[0])
+            .mark(&units[1])
+            .initial_length(format, &lengths[1], &starts[1])
+                .D16(5).D8(constants::DW_UT_split_type.0).D8(8).D32(0).D64(DWO_SIGNATURE).D32(24)
+                .uleb(2).D8(0)
+                .mark(&ends[1]);
+        for i in 0..2 {
+            lengths[i].set_const((&ends[i] - &starts[i]) as u64);
+        }
+        let info_ranges = [
+            ((&units[0] - &zero) as u32, (&ends[0] - &units[0]) as u32),
+            ((&units[1] - &zero) as u32, (&ends[1] - &units[1]) as u32),
+        ];
+
+        let zero = Label::new();
+        let lengths = [Label::new(), Label::new()];
+        let starts = [Label::new(), Label::new()];
+        let contributions = [Label::new(), Label::new()];
+        let ends = [Label::new(), Label::new()];
+        #[rustfmt::skip]
+        let dwp_str_offsets = Section::with_endian(Endian::Little)
+            .mark(&zero)
+            .mark(&contributions[0])
+            .initial_length(format, &lengths[0], &starts[0])
+                .D16(5).D16(0).D32(0)
+                .mark(&ends[0])
+            .mark(&contributions[1])
+            .initial_length(format, &lengths[1], &starts[1])
+                .D16(5).D16(0).D32(8)
+                .mark(&ends[1]);
+        for i in 0..2 {
+            lengths[i].set_const((&ends[i] - &starts[i]) as u64);
+        }
+        let str_offsets_ranges = [
+            (
+                (&contributions[0] - &zero) as u32,
+                (&ends[0] - &contributions[0]) as u32,
+            ),
+            (
+                (&contributions[1] - &zero) as u32,
+                (&ends[1] - &contributions[1]) as u32,
+            ),
+        ];
+        let dwp_str = b"split.c\0split_type\0";
+
+        let index = |id: u64, info: (u32, u32), str_offsets: (u32, u32)| {
+            #[rustfmt::skip]
+            let section = Section::with_endian(Endian::Little)
+                // Header.
+                .D16(5).D16(0).D32(3).D32(1).D32(2)
+                // Slots.
+                .D64(id).D64(0).D32(1).D32(0)
+                // Sections.
+                .D32(constants::DW_SECT_INFO.0)
+                .D32(constants::DW_SECT_ABBREV.0)
+                .D32(constants::DW_SECT_STR_OFFSETS.0)
+                // Offsets.
+                .D32(info.0).D32(0).D32(str_offsets.0)
+                // Sizes.
+                .D32(info.1).D32(dwp_abbrev.len() as u32).D32(str_offsets.1);
+            section.get_contents().unwrap()
+        };
+        let cu_index = index(DWO_ID, info_ranges[0], str_offsets_ranges[0]);
+        let tu_index = index(DWO_SIGNATURE, info_ranges[1], str_offsets_ranges[1]);
+
+        let debug_abbrev = debug_abbrev.get_contents().unwrap();
+        let debug_addr = debug_addr.get_contents().unwrap();
+        let debug_info = debug_info.get_contents().unwrap();
+        let dwp_info = dwp_info.get_contents().unwrap();
+        let dwp_str_offsets = dwp_str_offsets.get_contents().unwrap();
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(&debug_abbrev, LittleEndian),
+            debug_addr: DebugAddr::from(EndianSlice::new(&debug_addr, LittleEndian)),
+            debug_info: DebugInfo::new(&debug_info, LittleEndian),
+            ..Default::default()
+        };
+        let dwp = DwarfPackage::load(
+            |id| -> Result<_> {
+                let data: &[u8] = match id {
+                    SectionId::DebugCuIndex => &cu_index,
+                    SectionId::DebugTuIndex => &tu_index,
+                    SectionId::DebugAbbrev => &dwp_abbrev,
+                    SectionId::DebugInfo => &dwp_info,
+                    SectionId::DebugStr => dwp_str,
+                    SectionId::DebugStrOffsets => &dwp_str_offsets,
+                    _ => &[],
+                };
+                Ok(EndianSlice::new(data, LittleEndian))
+            },
+            EndianSlice::new(&[], LittleEndian),
+        )
+        .unwrap();
+        let mut files = DwarfFiles::new(dwarf);
+        files.set_dwp(dwp);
+
+        // The skeleton unit is replaced by the split unit, which reads strings
+        // from the package and addresses from the executable.
+        let mut headers = files.units();
+        let unit = files.unit(headers.next().unwrap().unwrap()).unwrap();
+        assert!(unit.dwo.is_some());
+        assert_eq!(unit.unit.dwo_id, Some(DwoId(DWO_ID)));
+        assert_eq!(unit.unit.name.unwrap().slice(), b"split.c");
+        let unit_ref = unit.unit_ref(&files);
+        let mut entries = unit_ref.entries();
+        let (_, root) = entries.next_dfs().unwrap().unwrap();
+        let low_pc = root.attr_value(constants::DW_AT_low_pc).unwrap().unwrap();
+        assert_eq!(unit_ref.attr_address(low_pc), Ok(Some(0x1000)));
+        assert_eq!(unit_ref.address(DebugAddrIndex(0)), Ok(0x1000));
+
+        // Other units are read from the executable.
+        let unit = files.unit(headers.next().unwrap().unwrap()).unwrap();
+        assert!(unit.dwo.is_none());
+        let unit = files.unit(headers.next().unwrap().unwrap()).unwrap();
+        assert!(unit.dwo.is_none());
+        assert_eq!(unit.unit.name.unwrap().slice(), b"main.c");
+        assert!(headers.next().unwrap().is_none());
+
+        let unit = files
+            .type_unit(DebugTypeSignature(DWO_SIGNATURE))
+            .unwrap()
+            .unwrap();
+        assert!(unit.dwo.is_some());
+        assert_eq!(unit.unit.name.unwrap().slice(), b"split_type");
+        let unit = files
+            .type_unit(DebugTypeSignature(MAIN_SIGNATURE))
+            .unwrap()
+            .unwrap();
+        assert!(unit.dwo.is_none());
+        assert_eq!(unit.unit.name.unwrap().slice(), b"main_type");
+        assert!(files
+            .type_unit(DebugTypeSignature(0x5555))
+            .unwrap()
+            .is_none());
+
+        // Without the package, the skeleton unit is returned.
+        files.dwp = None;
+        let header = files.units().next().unwrap().unwrap();
+        let unit = files.unit(header).unwrap();
+        assert!(unit.dwo.is_none());
+        assert_eq!(unit.unit.dwo_id, Some(DwoId(DWO_ID)));
+        assert!(files
+            .type_unit(DebugTypeSignature(DWO_SIGNATURE))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -15,14 +15,24 @@ use crate::read::{Error, Reader, ReaderOffsetId, Result};
 ///
 /// This implements the `Reader` trait, which is used for all reading of DWARF sections.
 #[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EndianSlice<'input, Endian>
 where
     Endian: Endianity,
 {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_slice"))]
     slice: &'input [u8],
     endian: Endian,
 }
 
+#[cfg(feature = "serde")]
+fn serialize_slice<S: serde::Serializer>(
+    slice: &&[u8],
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(slice)
+}
+
 impl<'input, Endian> EndianSlice<'input, Endian>
 where
     Endian: Endianity,
@@ -357,4 +367,15 @@ mod tests {
         let eb = EndianSlice::new(slice, NativeEndian);
         eb.split_at(30);
     }
+
+    #[test]
+    fn test_endian_slice_read_address() {
+        let slice = &[1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut eb = EndianSlice::new(slice, crate::LittleEndian);
+        assert_eq!(eb.read_address(3), Ok(0x0003_0201));
+        assert_eq!(eb.read_address(6), Ok(0x0908_0706_0504));
+        let mut eb = EndianSlice::new(slice, crate::BigEndian);
+        assert_eq!(eb.read_address(3), Ok(0x0001_0203));
+        assert_eq!(eb.read_address(9), Err(Error::UnsupportedAddressSize(9)));
+    }
 }
@@ -0,0 +1,275 @@
+use crate::common::Register;
+use crate::constants;
+use crate::read::{
+    DebuggingInformationEntry, Dwarf, Error, EvaluationResult, Expression, Location, Reader,
+    ReaderOffset, Result, Unit, Value,
+};
+
+/// The maximum number of operations to evaluate for a frame base expression.
+const MAX_ITERATIONS: u32 = 10_000;
+
+/// The maximum depth of nested `DW_OP_call*` operations in a frame base expression.
+const MAX_CALL_DEPTH: u32 = 16;
+
+impl<R: Reader> Dwarf<R> {
+    /// Evaluate the `DW_AT_frame_base` attribute of a subprogram entry.
+    ///
+    /// `pc` is the current address within the subprogram, which is used to
+    /// select the expression if the attribute is a location list. `cfa` is the
+    /// canonical frame address, which is typically found using the unwind
+    /// information for `pc` (see `UnwindSection::unwind_info_for_address`).
+    /// `register` returns the value of a register in the subprogram's frame.
+    ///
+    /// The result can be used to resume an `Evaluation` of a variable's location
+    /// that returned `EvaluationResult::RequiresFrameBase`.
+    ///
+    /// Returns `None` if the entry does not have a `DW_AT_frame_base` attribute,
+    /// if the location list does not cover `pc`, or if `register` returns `None`
+    /// for a register that the expression uses. Returns
+    /// `Error::UnsupportedFrameBase` if the expression requires other data,
+    /// such as the contents of memory.
+    ///
+    /// The evaluation is limited in the number of operations and the depth of
+    /// `DW_OP_call*` operations, so that malformed expressions that loop fail
+    /// with `Error::TooManyIterations` or `Error::CallDepthExceeded`.
+    pub fn frame_base<F>(
+        &self,
+        unit: &Unit<R>,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+        pc: u64,
+        cfa: u64,
+        mut register: F,
+    ) -> Result<Option<u64>>
+    where
+        F: FnMut(Register) -> Option<u64>,
+    {
+        let attr = match entry.attr_value(constants::DW_AT_frame_base)? {
+            Some(attr) => attr,
+            None => return Ok(None),
+        };
+        let expression = match attr.exprloc_value() {
+            Some(expression) => expression,
+            None => match self.attr_locations(unit, attr)? {
+                Some(mut locations) => loop {
+                    match locations.next()? {
+                        Some(location) => {
+                            if location.range.begin <= pc && pc < location.range.end {
+                                break location.data;
+                            }
+                        }
+                        None => return Ok(None),
+                    }
+                },
+                None => return Err(Error::UnsupportedAttributeForm),
+            },
+        };
+        self.evaluate_frame_base(unit, expression, cfa, &mut register)
+    }
+
+    fn evaluate_frame_base(
+        &self,
+        unit: &Unit<R>,
+        expression: Expression<R>,
+        cfa: u64,
+        register: &mut dyn FnMut(Register) -> Option<u64>,
+    ) -> Result<Option<u64>> {
+        let encoding = unit.encoding();
+        let mut evaluation = expression.evaluation(encoding);
+        evaluation.set_max_iterations(MAX_ITERATIONS);
+        evaluation.set_max_call_depth(MAX_CALL_DEPTH);
+        let mut result = evaluation.evaluate()?;
+        loop {
+            result = match result {
+                EvaluationResult::Complete => break,
+                EvaluationResult::RequiresCallFrameCfa => {
+                    evaluation.resume_with_call_frame_cfa(cfa)?
+                }
+                EvaluationResult::RequiresRegister {
+                    register: reg,
+                    base_type,
+                } => {
+                    if base_type.0 != R::Offset::from_u8(0) {
+                        return Err(Error::UnsupportedFrameBase);
+                    }
+                    match register(reg) {
+                        Some(value) => evaluation.resume_with_register(Value::Generic(value))?,
+                        None => return Ok(None),
+                    }
+                }
+                EvaluationResult::RequiresRelocatedAddress(address) => {
+                    evaluation.resume_with_relocated_address(address)?
+                }
+                EvaluationResult::RequiresIndexedAddress { index, .. } => {
+                    let address = self.address(unit, index)?;
+                    evaluation.resume_with_indexed_address(address)?
+                }
+                EvaluationResult::RequiresAtLocation(reference) => {
+                    let location = self.call_location(unit, reference)?;
+                    evaluation.resume_with_at_location(location)?
+                }
+                _ => return Err(Error::UnsupportedFrameBase),
+            };
+        }
+
+        let pieces = evaluation.as_result();
+        if pieces.len() != 1 {
+            return Err(Error::UnsupportedFrameBase);
+        }
+        match pieces[0].location {
+            Location::Register { register: reg } => Ok(register(reg)),
+            Location::Address { address } => Ok(Some(address)),
+            Location::Value { value } => {
+                let addr_mask = if encoding.address_size == 8 {
+                    !0u64
+                } else {
+                    (1 << (8 * u64::from(encoding.address_size))) - 1
+                };
+                Ok(Some(value.to_u64(addr_mask)?))
+            }
+            _ => Err(Error::UnsupportedFrameBase),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::{DebugAbbrev, DebugInfo, DebugLocLists, LocationLists};
+    use crate::write::{self, Address, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_frame_base() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+
+        // DW_OP_call_frame_cfa
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_call_frame_cfa);
+        let cfa = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(cfa).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        // DW_OP_reg6
+        let mut expression = write::Expression::new();
+        expression.op_reg(Register(6));
+        let reg = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(reg).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        // A location list using DW_OP_breg7 and DW_OP_breg6.
+        let mut expression1 = write::Expression::new();
+        expression1.op_breg(Register(7), 8);
+        let mut expression2 = write::Expression::new();
+        expression2.op_breg(Register(6), 16);
+        let list = unit.locations.add(write::LocationList(vec![
+            write::Location::StartEnd {
+                begin: Address::Constant(0x1000),
+                end: Address::Constant(0x1004),
+                data: expression1,
+            },
+            write::Location::StartEnd {
+                begin: Address::Constant(0x1004),
+                end: Address::Constant(0x1100),
+                data: expression2,
+            },
+        ]));
+        let loclist = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(loclist).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::LocationListRef(list),
+        );
+
+        // No frame base.
+        unit.add(root, constants::DW_TAG_subprogram);
+
+        // DW_OP_lit0 DW_OP_deref
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_lit0);
+        expression.op_deref();
+        let memory = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(memory).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        // DW_OP_nop DW_OP_skip -4
+        let mut expression = write::Expression::new();
+        expression.op(constants::DW_OP_nop);
+        let skip = expression.op_skip();
+        expression.set_target(skip, 0);
+        let skip = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(skip).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        // DW_OP_call4 to a procedure that calls itself.
+        let procedure = unit.add(root, constants::DW_TAG_dwarf_procedure);
+        let mut expression = write::Expression::new();
+        expression.op_call(procedure);
+        unit.get_mut(procedure).set(
+            constants::DW_AT_location,
+            write::AttributeValue::Exprloc(expression.clone()),
+        );
+        let call = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(call).set(
+            constants::DW_AT_frame_base,
+            write::AttributeValue::Exprloc(expression),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            locations: LocationLists::new(
+                Default::default(),
+                DebugLocLists::new(sections.debug_loclists.slice(), LittleEndian),
+            ),
+            ..Default::default()
+        };
+        let unit = dwarf.parsed_units().next().unwrap().unwrap();
+        let mut offsets = Vec::new();
+        let mut entries = unit.entries();
+        entries.next_dfs().unwrap();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            offsets.push(entry.offset());
+        }
+        let registers = |register: Register| match register.0 {
+            6 => Some(0x8000),
+            7 => Some(0x9000),
+            _ => None,
+        };
+        let frame_base = |index: usize, pc| {
+            let entry = unit.entry(offsets[index]).unwrap();
+            dwarf.frame_base(&unit, &entry, pc, 0x7000, registers)
+        };
+        assert_eq!(frame_base(0, 0x1000), Ok(Some(0x7000)));
+        assert_eq!(frame_base(1, 0x1000), Ok(Some(0x8000)));
+        assert_eq!(frame_base(2, 0x1000), Ok(Some(0x9008)));
+        assert_eq!(frame_base(2, 0x10ff), Ok(Some(0x8010)));
+        assert_eq!(frame_base(2, 0x1100), Ok(None));
+        assert_eq!(frame_base(3, 0x1000), Ok(None));
+        assert_eq!(frame_base(4, 0x1000), Err(Error::UnsupportedFrameBase));
+        assert_eq!(frame_base(5, 0x1000), Err(Error::TooManyIterations));
+        assert_eq!(frame_base(7, 0x1000), Err(Error::CallDepthExceeded));
+    }
+}
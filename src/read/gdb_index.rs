@@ -0,0 +1,818 @@
+use core::convert::TryFrom;
+
+use crate::common::{
+    gdb_index_hash, DebugInfoOffset, DebugTypeSignature, DebugTypesOffset, GdbIndexSymbolKind,
+    SectionId,
+};
+use crate::constants;
+use crate::endianity::LittleEndian;
+use crate::read::{EndianSlice, Error, Range, Reader, ReaderOffset, Result, Section, UnitOffset};
+
+/// The data in the `.gdb_index` section.
+///
+/// This section is an accelerator table generated by GDB or `gdb-add-index`.
+/// It contains the list of units, an address map, and a symbol hash table.
+///
+/// The section is always little endian, regardless of the endianity of the
+/// object file. When constructing this from a `Reader` directly, the reader
+/// must be little endian.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GdbIndex<R> {
+    section: R,
+}
+
+impl<'input> GdbIndex<EndianSlice<'input, LittleEndian>> {
+    /// Construct a new `GdbIndex` instance from the data in the `.gdb_index`
+    /// section.
+    ///
+    /// ```
+    /// use gimli::GdbIndex;
+    ///
+    /// # let buf = [];
+    /// # let read_gdb_index_section = || &buf;
+    /// let gdb_index = GdbIndex::new(read_gdb_index_section());
+    /// ```
+    pub fn new(section: &'input [u8]) -> Self {
+        Self::from(EndianSlice::new(section, LittleEndian))
+    }
+}
+
+impl<T> GdbIndex<T> {
+    /// Create a `GdbIndex` section that references the data in `self`.
+    ///
+    /// This is useful when `R` implements `Reader` but `T` does not.
+    pub fn borrow<'a, F, R>(&'a self, mut borrow: F) -> GdbIndex<R>
+    where
+        F: FnMut(&'a T) -> R,
+    {
+        borrow(&self.section).into()
+    }
+}
+
+impl<R> Section<R> for GdbIndex<R> {
+    fn id() -> SectionId {
+        SectionId::GdbIndex
+    }
+
+    fn reader(&self) -> &R {
+        &self.section
+    }
+}
+
+impl<R> From<R> for GdbIndex<R> {
+    fn from(section: R) -> Self {
+        GdbIndex { section }
+    }
+}
+
+impl<R: Reader> GdbIndex<R> {
+    /// Parse the index header.
+    ///
+    /// Versions 7 to 9 are supported.
+    pub fn index(self) -> Result<GdbIndexTable<R>> {
+        GdbIndexTable::parse(self.section)
+    }
+}
+
+/// The parsed contents of a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexTable<R: Reader> {
+    version: u32,
+    cu_list: R,
+    tu_list: R,
+    address_area: R,
+    symbol_table: R,
+    shortcut_table: R,
+    constant_pool: R,
+}
+
+impl<R: Reader> GdbIndexTable<R> {
+    fn parse(input: R) -> Result<GdbIndexTable<R>> {
+        let mut header = input.clone();
+        let version = header.read_u32()?;
+        if version < 7 || version > 9 {
+            return Err(Error::UnknownVersion(version.into()));
+        }
+        let header_count = if version >= 9 { 6 } else { 5 };
+        let mut offsets = [0u32; 7];
+        for offset in &mut offsets[..header_count] {
+            *offset = header.read_u32()?;
+        }
+        offsets[header_count] =
+            u32::try_from(input.len().into_u64()).map_err(|_| Error::InvalidGdbIndexHeader)?;
+
+        let area = |index: usize| -> Result<R> {
+            let start = offsets[index];
+            let end = offsets[index + 1];
+            if end < start {
+                return Err(Error::InvalidGdbIndexHeader);
+            }
+            let mut area = input.clone();
+            area.skip(R::Offset::from_u32(start))
+                .map_err(|_| Error::InvalidGdbIndexHeader)?;
+            area.truncate(R::Offset::from_u32(end - start))
+                .map_err(|_| Error::InvalidGdbIndexHeader)?;
+            Ok(area)
+        };
+        let cu_list = area(0)?;
+        let tu_list = area(1)?;
+        let address_area = area(2)?;
+        let symbol_table = area(3)?;
+        let (shortcut_table, constant_pool) = if version >= 9 {
+            (area(4)?, area(5)?)
+        } else {
+            let mut empty = input.clone();
+            empty.empty();
+            (empty, area(4)?)
+        };
+
+        let slot_count = symbol_table.len().into_u64() / 8;
+        if slot_count & slot_count.wrapping_sub(1) != 0 {
+            return Err(Error::InvalidGdbIndexSymbolTable);
+        }
+
+        Ok(GdbIndexTable {
+            version,
+            cu_list,
+            tu_list,
+            address_area,
+            symbol_table,
+            shortcut_table,
+            constant_pool,
+        })
+    }
+
+    /// Return the version of the index.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Return the number of compilation units in the index.
+    pub fn compilation_unit_count(&self) -> u32 {
+        (self.cu_list.len().into_u64() / 16) as u32
+    }
+
+    /// Return the compilation unit at the given index.
+    pub fn compilation_unit(&self, index: u32) -> Result<GdbIndexCompilationUnit<R::Offset>> {
+        let mut input = self.cu_list.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 16)?)?;
+        GdbIndexCompilationUnit::parse(&mut input)
+    }
+
+    /// Iterate over the compilation units in the index.
+    pub fn compilation_units(&self) -> GdbIndexCompilationUnitIter<R> {
+        GdbIndexCompilationUnitIter {
+            input: self.cu_list.clone(),
+        }
+    }
+
+    /// Return the number of type units in the index.
+    pub fn type_unit_count(&self) -> u32 {
+        (self.tu_list.len().into_u64() / 24) as u32
+    }
+
+    /// Return the type unit at the given index.
+    pub fn type_unit(&self, index: u32) -> Result<GdbIndexTypeUnit<R::Offset>> {
+        let mut input = self.tu_list.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 24)?)?;
+        GdbIndexTypeUnit::parse(&mut input)
+    }
+
+    /// Iterate over the type units in the index.
+    pub fn type_units(&self) -> GdbIndexTypeUnitIter<R> {
+        GdbIndexTypeUnitIter {
+            input: self.tu_list.clone(),
+        }
+    }
+
+    /// Return the unit referenced by a unit index in a symbol's CU vector.
+    ///
+    /// Unit indices that are less than the number of compilation units refer
+    /// to compilation units. Larger indices refer to type units.
+    pub fn unit(&self, index: u32) -> Result<GdbIndexUnit<R::Offset>> {
+        let cu_count = self.compilation_unit_count();
+        if index < cu_count {
+            self.compilation_unit(index).map(GdbIndexUnit::Compilation)
+        } else {
+            self.type_unit(index - cu_count).map(GdbIndexUnit::Type)
+        }
+    }
+
+    /// Iterate over the entries in the address area.
+    ///
+    /// Each entry maps an address range to a compilation unit index.
+    pub fn address_ranges(&self) -> GdbIndexAddressIter<R> {
+        GdbIndexAddressIter {
+            input: self.address_area.clone(),
+        }
+    }
+
+    /// Find the index of the compilation unit that contains the given address.
+    pub fn find_address(&self, address: u64) -> Result<Option<u32>> {
+        let mut ranges = self.address_ranges();
+        while let Some(entry) = ranges.next()? {
+            if entry.range.begin <= address && address < entry.range.end {
+                return Ok(Some(entry.cu_index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the number of slots in the symbol hash table.
+    pub fn symbol_slot_count(&self) -> u32 {
+        (self.symbol_table.len().into_u64() / 8) as u32
+    }
+
+    /// Iterate over the symbols in the symbol hash table.
+    ///
+    /// Empty slots are skipped.
+    pub fn symbols(&self) -> GdbIndexSymbolIter<'_, R> {
+        GdbIndexSymbolIter {
+            index: self,
+            input: self.symbol_table.clone(),
+        }
+    }
+
+    /// Find the symbol with the given name in the symbol hash table.
+    ///
+    /// Returns the CU vector for the symbol, which lists the units that
+    /// define the symbol.
+    pub fn find(&self, name: &[u8]) -> Result<Option<GdbIndexCuVector<R>>> {
+        let slot_count = self.symbol_slot_count();
+        if slot_count == 0 {
+            return Ok(None);
+        }
+        let mask = slot_count - 1;
+        let hash = gdb_index_hash(name);
+        let mut slot = hash & mask;
+        let step = (hash.wrapping_mul(17) & mask) | 1;
+        for _ in 0..slot_count {
+            let mut input = self.symbol_table.clone();
+            input.skip(R::Offset::from_u64(u64::from(slot) * 8)?)?;
+            let name_offset = input.read_u32()?;
+            let cu_vector_offset = input.read_u32()?;
+            if name_offset == 0 && cu_vector_offset == 0 {
+                return Ok(None);
+            }
+            if self.string(name_offset)?.to_slice()? == name {
+                return self.cu_vector(cu_vector_offset).map(Some);
+            }
+            slot = slot.wrapping_add(step) & mask;
+        }
+        Ok(None)
+    }
+
+    /// Return the language of the main function.
+    ///
+    /// This is only present in version 9 and later.
+    pub fn main_language(&self) -> Result<Option<constants::DwLang>> {
+        if self.shortcut_table.is_empty() {
+            return Ok(None);
+        }
+        let mut input = self.shortcut_table.clone();
+        let language = input.read_u32()?;
+        Ok(Some(constants::DwLang(language as u16)))
+    }
+
+    /// Return the name of the main function.
+    ///
+    /// This is only present in version 9 and later.
+    pub fn main_name(&self) -> Result<Option<R>> {
+        if self.shortcut_table.is_empty() {
+            return Ok(None);
+        }
+        let mut input = self.shortcut_table.clone();
+        input.skip(R::Offset::from_u8(4))?;
+        let name_offset = input.read_u32()?;
+        if name_offset == 0 {
+            return Ok(None);
+        }
+        self.string(name_offset).map(Some)
+    }
+
+    fn string(&self, offset: u32) -> Result<R> {
+        let mut input = self.constant_pool.clone();
+        input.skip(R::Offset::from_u32(offset))?;
+        input.read_null_terminated_slice()
+    }
+
+    fn cu_vector(&self, offset: u32) -> Result<GdbIndexCuVector<R>> {
+        let mut input = self.constant_pool.clone();
+        input.skip(R::Offset::from_u32(offset))?;
+        let count = input.read_u32()?;
+        let entries = input.split(R::Offset::from_u64(u64::from(count) * 4)?)?;
+        Ok(GdbIndexCuVector { entries })
+    }
+}
+
+/// A compilation unit in the CU list of a `.gdb_index` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdbIndexCompilationUnit<T = usize> {
+    /// The offset of the compilation unit in the `.debug_info` section.
+    pub offset: DebugInfoOffset<T>,
+    /// The length of the compilation unit, including its header.
+    pub length: u64,
+}
+
+impl<T: ReaderOffset> GdbIndexCompilationUnit<T> {
+    fn parse<R: Reader<Offset = T>>(input: &mut R) -> Result<Self> {
+        let offset = DebugInfoOffset(T::from_u64(input.read_u64()?)?);
+        let length = input.read_u64()?;
+        Ok(GdbIndexCompilationUnit { offset, length })
+    }
+}
+
+/// A type unit in the TU list of a `.gdb_index` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdbIndexTypeUnit<T = usize> {
+    /// The offset of the type unit in the `.debug_types` section.
+    pub offset: DebugTypesOffset<T>,
+    /// The offset of the type within the type unit.
+    pub type_offset: UnitOffset<T>,
+    /// The signature of the type unit.
+    pub signature: DebugTypeSignature,
+}
+
+impl<T: ReaderOffset> GdbIndexTypeUnit<T> {
+    fn parse<R: Reader<Offset = T>>(input: &mut R) -> Result<Self> {
+        let offset = DebugTypesOffset(T::from_u64(input.read_u64()?)?);
+        let type_offset = UnitOffset(T::from_u64(input.read_u64()?)?);
+        let signature = DebugTypeSignature(input.read_u64()?);
+        Ok(GdbIndexTypeUnit {
+            offset,
+            type_offset,
+            signature,
+        })
+    }
+}
+
+/// A unit referenced by a `.gdb_index` CU vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbIndexUnit<T = usize> {
+    /// A compilation unit.
+    Compilation(GdbIndexCompilationUnit<T>),
+    /// A type unit.
+    Type(GdbIndexTypeUnit<T>),
+}
+
+/// An iterator over the compilation units in a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexCompilationUnitIter<R: Reader> {
+    input: R,
+}
+
+impl<R: Reader> GdbIndexCompilationUnitIter<R> {
+    /// Advance the iterator to the next compilation unit.
+    pub fn next(&mut self) -> Result<Option<GdbIndexCompilationUnit<R::Offset>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        match GdbIndexCompilationUnit::parse(&mut self.input) {
+            Ok(unit) => Ok(Some(unit)),
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for GdbIndexCompilationUnitIter<R> {
+    type Item = GdbIndexCompilationUnit<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        GdbIndexCompilationUnitIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] GdbIndexCompilationUnitIter<R>, GdbIndexCompilationUnit<R::Offset>);
+
+/// An iterator over the type units in a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexTypeUnitIter<R: Reader> {
+    input: R,
+}
+
+impl<R: Reader> GdbIndexTypeUnitIter<R> {
+    /// Advance the iterator to the next type unit.
+    pub fn next(&mut self) -> Result<Option<GdbIndexTypeUnit<R::Offset>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        match GdbIndexTypeUnit::parse(&mut self.input) {
+            Ok(unit) => Ok(Some(unit)),
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for GdbIndexTypeUnitIter<R> {
+    type Item = GdbIndexTypeUnit<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        GdbIndexTypeUnitIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] GdbIndexTypeUnitIter<R>, GdbIndexTypeUnit<R::Offset>);
+
+/// An entry in the address area of a `.gdb_index` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdbIndexAddressRange {
+    /// The address range.
+    pub range: Range,
+    /// The index of the compilation unit containing the range.
+    pub cu_index: u32,
+}
+
+/// An iterator over the address area of a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexAddressIter<R: Reader> {
+    input: R,
+}
+
+impl<R: Reader> GdbIndexAddressIter<R> {
+    /// Advance the iterator to the next address range.
+    pub fn next(&mut self) -> Result<Option<GdbIndexAddressRange>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        let parse = |input: &mut R| -> Result<GdbIndexAddressRange> {
+            let begin = input.read_u64()?;
+            let end = input.read_u64()?;
+            let cu_index = input.read_u32()?;
+            Ok(GdbIndexAddressRange {
+                range: Range { begin, end },
+                cu_index,
+            })
+        };
+        match parse(&mut self.input) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for GdbIndexAddressIter<R> {
+    type Item = GdbIndexAddressRange;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        GdbIndexAddressIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] GdbIndexAddressIter<R>, GdbIndexAddressRange);
+
+/// A symbol in the symbol hash table of a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexSymbol<R: Reader> {
+    /// The name of the symbol.
+    pub name: R,
+    /// The units that define the symbol.
+    pub cu_vector: GdbIndexCuVector<R>,
+}
+
+/// An iterator over the symbols in a `.gdb_index` section.
+#[derive(Debug, Clone)]
+pub struct GdbIndexSymbolIter<'index, R: Reader> {
+    index: &'index GdbIndexTable<R>,
+    input: R,
+}
+
+impl<'index, R: Reader> GdbIndexSymbolIter<'index, R> {
+    /// Advance the iterator to the next symbol.
+    pub fn next(&mut self) -> Result<Option<GdbIndexSymbol<R>>> {
+        while !self.input.is_empty() {
+            let mut parse = || -> Result<Option<GdbIndexSymbol<R>>> {
+                let name_offset = self.input.read_u32()?;
+                let cu_vector_offset = self.input.read_u32()?;
+                if name_offset == 0 && cu_vector_offset == 0 {
+                    return Ok(None);
+                }
+                Ok(Some(GdbIndexSymbol {
+                    name: self.index.string(name_offset)?,
+                    cu_vector: self.index.cu_vector(cu_vector_offset)?,
+                }))
+            };
+            match parse() {
+                Ok(Some(symbol)) => return Ok(Some(symbol)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.input.empty();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'index, R: Reader> fallible_iterator::FallibleIterator for GdbIndexSymbolIter<'index, R> {
+    type Item = GdbIndexSymbol<R>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        GdbIndexSymbolIter::next(self)
+    }
+}
+
+result_iterator!(['index, R: Reader] GdbIndexSymbolIter<'index, R>, GdbIndexSymbol<R>);
+
+/// An entry in a `.gdb_index` CU vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdbIndexCuVectorEntry {
+    /// The index of the unit that defines the symbol.
+    ///
+    /// See `GdbIndexTable::unit`.
+    pub unit_index: u32,
+    /// The kind of the symbol.
+    pub kind: GdbIndexSymbolKind,
+    /// Whether the symbol is static, as opposed to global.
+    pub is_static: bool,
+}
+
+impl GdbIndexCuVectorEntry {
+    fn from_u32(value: u32) -> Self {
+        GdbIndexCuVectorEntry {
+            unit_index: value & 0x00ff_ffff,
+            kind: GdbIndexSymbolKind::from_u8(((value >> 28) & 7) as u8),
+            is_static: value & 0x8000_0000 != 0,
+        }
+    }
+}
+
+/// The list of units that define a symbol in a `.gdb_index` section.
+///
+/// This is an iterator over the entries in the list.
+#[derive(Debug, Clone)]
+pub struct GdbIndexCuVector<R: Reader> {
+    entries: R,
+}
+
+impl<R: Reader> GdbIndexCuVector<R> {
+    /// Return the number of entries remaining in the list.
+    pub fn len(&self) -> u32 {
+        (self.entries.len().into_u64() / 4) as u32
+    }
+
+    /// Return true if there are no entries remaining in the list.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Advance the iterator to the next entry.
+    pub fn next(&mut self) -> Result<Option<GdbIndexCuVectorEntry>> {
+        if self.entries.is_empty() {
+            return Ok(None);
+        }
+        match self.entries.read_u32() {
+            Ok(value) => Ok(Some(GdbIndexCuVectorEntry::from_u32(value))),
+            Err(e) => {
+                self.entries.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for GdbIndexCuVector<R> {
+    type Item = GdbIndexCuVectorEntry;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        GdbIndexCuVector::next(self)
+    }
+}
+
+result_iterator!([R: Reader] GdbIndexCuVector<R>, GdbIndexCuVectorEntry);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endianity::LittleEndian;
+    use alloc::vec::Vec;
+    use test_assembler::{Endian, Label, LabelMaker, Section};
+
+    fn symbol_table(names: &[(&str, u32)], slot_count: u32) -> Vec<Option<(usize, u32)>> {
+        let mut slots = vec![None; slot_count as usize];
+        for (i, &(name, cu_vector)) in names.iter().enumerate() {
+            let hash = gdb_index_hash(name.as_bytes());
+            let mask = slot_count - 1;
+            let mut slot = hash & mask;
+            let step = (hash.wrapping_mul(17) & mask) | 1;
+            while slots[slot as usize].is_some() {
+                slot = (slot + step) & mask;
+            }
+            slots[slot as usize] = Some((i, cu_vector));
+        }
+        slots
+    }
+
+    #[test]
+    fn test_gdb_index_hash() {
+        assert_eq!(gdb_index_hash(b""), 0);
+        assert_eq!(gdb_index_hash(b"a"), 97u32.wrapping_sub(113));
+        assert_eq!(gdb_index_hash(b"main"), gdb_index_hash(b"MAIN"));
+    }
+
+    #[test]
+    fn test_gdb_index() {
+        for &version in &[7, 8, 9] {
+            let names = ["main", "foo", "int"];
+            let slots = symbol_table(&[("main", 0), ("foo", 1), ("int", 2)], 8);
+
+            let cu_list = Label::new();
+            let tu_list = Label::new();
+            let address_area = Label::new();
+            let symbol_table = Label::new();
+            let shortcut_table = Label::new();
+            let constant_pool = Label::new();
+            // Offsets within the constant pool.
+            let cu_vector_offsets = [0, 8, 20];
+            let name_offsets = [28, 33, 37];
+
+            let section = Section::with_endian(Endian::Little);
+            section.start().set_const(0);
+            let mut section = section
+                .D32(version)
+                .D32(&cu_list)
+                .D32(&tu_list)
+                .D32(&address_area)
+                .D32(&symbol_table);
+            if version >= 9 {
+                section = section.D32(&shortcut_table);
+            }
+            section = section
+                .D32(&constant_pool)
+                // CU list.
+                .mark(&cu_list)
+                .D64(0)
+                .D64(0x30)
+                .D64(0x30)
+                .D64(0x40)
+                // TU list.
+                .mark(&tu_list)
+                .D64(0x10)
+                .D64(0x1d)
+                .D64(0x1234_5678)
+                // Address area.
+                .mark(&address_area)
+                .D64(0x1000)
+                .D64(0x1100)
+                .D32(0)
+                .D64(0x2000)
+                .D64(0x2200)
+                .D32(1)
+                // Symbol table.
+                .mark(&symbol_table);
+            for slot in &slots {
+                section = match *slot {
+                    Some((name, cu_vector)) => section
+                        .D32(name_offsets[name])
+                        .D32(cu_vector_offsets[cu_vector as usize]),
+                    None => section.D32(0).D32(0),
+                };
+            }
+            if version >= 9 {
+                section = section
+                    .mark(&shortcut_table)
+                    .D32(u32::from(constants::DW_LANG_C99.0))
+                    .D32(name_offsets[0]);
+            }
+            section = section
+                .mark(&constant_pool)
+                // CU vectors.
+                .D32(1)
+                .D32(0x3000_0000)
+                .D32(2)
+                .D32(0xb000_0000)
+                .D32(0xb000_0001)
+                .D32(1)
+                .D32(0x1000_0002);
+            for name in &names {
+                section = section.append_bytes(name.as_bytes()).D8(0);
+            }
+            let buf = section.get_contents().unwrap();
+
+            let index = GdbIndex::new(&buf).index().unwrap();
+            assert_eq!(index.version(), version);
+
+            assert_eq!(index.compilation_unit_count(), 2);
+            let mut units = index.compilation_units();
+            assert_eq!(
+                units.next().unwrap(),
+                Some(GdbIndexCompilationUnit {
+                    offset: DebugInfoOffset(0),
+                    length: 0x30,
+                })
+            );
+            assert_eq!(
+                units.next().unwrap(),
+                Some(GdbIndexCompilationUnit {
+                    offset: DebugInfoOffset(0x30),
+                    length: 0x40,
+                })
+            );
+            assert_eq!(units.next().unwrap(), None);
+
+            assert_eq!(index.type_unit_count(), 1);
+            let type_unit = GdbIndexTypeUnit {
+                offset: DebugTypesOffset(0x10),
+                type_offset: UnitOffset(0x1d),
+                signature: DebugTypeSignature(0x1234_5678),
+            };
+            assert_eq!(index.type_units().next().unwrap(), Some(type_unit));
+            assert_eq!(index.unit(2).unwrap(), GdbIndexUnit::Type(type_unit));
+
+            let mut ranges = index.address_ranges();
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(GdbIndexAddressRange {
+                    range: Range {
+                        begin: 0x1000,
+                        end: 0x1100
+                    },
+                    cu_index: 0,
+                })
+            );
+            assert_eq!(index.find_address(0x2100).unwrap(), Some(1));
+            assert_eq!(index.find_address(0x3000).unwrap(), None);
+
+            let mut foo = index.find(b"foo").unwrap().unwrap();
+            assert_eq!(foo.len(), 2);
+            assert_eq!(
+                foo.next().unwrap(),
+                Some(GdbIndexCuVectorEntry {
+                    unit_index: 0,
+                    kind: GdbIndexSymbolKind::Function,
+                    is_static: true,
+                })
+            );
+            assert_eq!(foo.next().unwrap().unwrap().unit_index, 1);
+            assert_eq!(foo.next().unwrap(), None);
+            let mut int = index.find(b"int").unwrap().unwrap();
+            let entry = int.next().unwrap().unwrap();
+            assert_eq!(entry.kind, GdbIndexSymbolKind::Type);
+            assert_eq!(
+                index.unit(entry.unit_index).unwrap(),
+                GdbIndexUnit::Type(type_unit)
+            );
+            assert!(index.find(b"bar").unwrap().is_none());
+
+            let mut count = 0;
+            let mut symbols = index.symbols();
+            while let Some(symbol) = symbols.next().unwrap() {
+                assert!(names.contains(&symbol.name.to_string().unwrap()));
+                count += 1;
+            }
+            assert_eq!(count, 3);
+
+            if version >= 9 {
+                assert_eq!(index.main_language().unwrap(), Some(constants::DW_LANG_C99));
+                assert_eq!(
+                    index.main_name().unwrap(),
+                    Some(EndianSlice::new(b"main", LittleEndian))
+                );
+            } else {
+                assert_eq!(index.main_language().unwrap(), None);
+                assert_eq!(index.main_name().unwrap(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gdb_index_version() {
+        let buf = [
+            6, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0,
+        ];
+        match GdbIndex::new(&buf).index() {
+            Err(Error::UnknownVersion(6)) => {}
+            otherwise => panic!("Unexpected result: {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn test_gdb_index_invalid_header() {
+        let buf = [
+            8, 0, 0, 0, 24, 0, 0, 0, 16, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0, 24, 0, 0, 0,
+        ];
+        match GdbIndex::new(&buf).index() {
+            Err(Error::InvalidGdbIndexHeader) => {}
+            otherwise => panic!("Unexpected result: {:?}", otherwise),
+        }
+    }
+}
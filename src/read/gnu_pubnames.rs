@@ -0,0 +1,234 @@
+use crate::common::{DebugInfoOffset, GdbIndexSymbolKind, SectionId};
+use crate::endianity::Endianity;
+use crate::read::lookup::{DebugLookup, LookupEntryIter, PubStuffEntry, PubStuffParser};
+use crate::read::{EndianSlice, Reader, Result, Section, UnitOffset};
+
+/// The descriptor of an entry in a `.debug_gnu_pubnames` or
+/// `.debug_gnu_pubtypes` section.
+///
+/// This uses the same encoding as the symbol attributes in a `.gdb_index`
+/// section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GnuPubDescriptor {
+    /// The kind of the symbol.
+    pub kind: GdbIndexSymbolKind,
+    /// Whether the symbol is static, as opposed to global.
+    pub is_static: bool,
+}
+
+impl GnuPubDescriptor {
+    fn from_u8(value: u8) -> Self {
+        GnuPubDescriptor {
+            kind: GdbIndexSymbolKind::from_u8((value >> 4) & 7),
+            is_static: value & 0x80 != 0,
+        }
+    }
+}
+
+/// A single parsed entry from a `.debug_gnu_pubnames` or `.debug_gnu_pubtypes`
+/// section.
+#[derive(Debug, Clone)]
+pub struct GnuPubEntry<R: Reader> {
+    unit_header_offset: DebugInfoOffset<R::Offset>,
+    die_offset: UnitOffset<R::Offset>,
+    descriptor: GnuPubDescriptor,
+    name: R,
+}
+
+impl<R: Reader> GnuPubEntry<R> {
+    /// Returns the name this entry refers to.
+    pub fn name(&self) -> &R {
+        &self.name
+    }
+
+    /// Returns the offset into the .debug_info section for the header of the compilation unit
+    /// which contains this name.
+    pub fn unit_header_offset(&self) -> DebugInfoOffset<R::Offset> {
+        self.unit_header_offset
+    }
+
+    /// Returns the offset into the compilation unit for the debugging information entry which
+    /// has this name.
+    pub fn die_offset(&self) -> UnitOffset<R::Offset> {
+        self.die_offset
+    }
+
+    /// Returns the kind and visibility of the symbol with this name.
+    pub fn descriptor(&self) -> GnuPubDescriptor {
+        self.descriptor
+    }
+}
+
+impl<R: Reader> PubStuffEntry<R> for GnuPubEntry<R> {
+    const HAS_DESCRIPTOR: bool = true;
+
+    fn new(
+        die_offset: UnitOffset<R::Offset>,
+        name: R,
+        unit_header_offset: DebugInfoOffset<R::Offset>,
+    ) -> Self {
+        GnuPubEntry {
+            unit_header_offset,
+            die_offset,
+            descriptor: GnuPubDescriptor::from_u8(0),
+            name,
+        }
+    }
+
+    fn set_descriptor(&mut self, descriptor: u8) {
+        self.descriptor = GnuPubDescriptor::from_u8(descriptor);
+    }
+}
+
+macro_rules! gnu_pub_section {
+    ($name:ident, $id:ident, $section_name:expr, $docs:expr) => {
+        #[doc=$docs]
+        #[derive(Debug, Clone)]
+        pub struct $name<R: Reader>(DebugLookup<R, PubStuffParser<R, GnuPubEntry<R>>>);
+
+        impl<'input, Endian> $name<EndianSlice<'input, Endian>>
+        where
+            Endian: Endianity,
+        {
+            #[doc = concat!("Construct a new `", stringify!($name), "` instance from the data in the")]
+            #[doc = concat!("`", $section_name, "` section.")]
+            pub fn new(section: &'input [u8], endian: Endian) -> Self {
+                Self::from(EndianSlice::new(section, endian))
+            }
+        }
+
+        impl<R: Reader> $name<R> {
+            #[doc = concat!("Iterate the entries in the `", $section_name, "` section.")]
+            pub fn items(&self) -> GnuPubEntryIter<R> {
+                GnuPubEntryIter(self.0.items())
+            }
+        }
+
+        impl<R: Reader> Section<R> for $name<R> {
+            fn id() -> SectionId {
+                SectionId::$id
+            }
+
+            fn reader(&self) -> &R {
+                self.0.reader()
+            }
+        }
+
+        impl<R: Reader> From<R> for $name<R> {
+            fn from(section: R) -> Self {
+                $name(DebugLookup::from(section))
+            }
+        }
+    };
+}
+
+gnu_pub_section!(
+    DebugGnuPubNames,
+    DebugGnuPubNames,
+    ".debug_gnu_pubnames",
+    "The data in the `.debug_gnu_pubnames` section.\n\n\
+     This is the GNU variant of `.debug_pubnames`, which adds a descriptor to each \
+     entry. It is emitted by `-ggnu-pubnames` for use when generating a `.gdb_index`."
+);
+
+gnu_pub_section!(
+    DebugGnuPubTypes,
+    DebugGnuPubTypes,
+    ".debug_gnu_pubtypes",
+    "The data in the `.debug_gnu_pubtypes` section.\n\n\
+     This is the GNU variant of `.debug_pubtypes`, which adds a descriptor to each \
+     entry. It is emitted by `-ggnu-pubnames` for use when generating a `.gdb_index`."
+);
+
+/// An iterator over the entries from a `.debug_gnu_pubnames` or
+/// `.debug_gnu_pubtypes` section.
+///
+/// Can be [used with
+/// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+#[derive(Debug, Clone)]
+pub struct GnuPubEntryIter<R: Reader>(LookupEntryIter<R, PubStuffParser<R, GnuPubEntry<R>>>);
+
+impl<R: Reader> GnuPubEntryIter<R> {
+    /// Advance the iterator and return the next entry.
+    ///
+    /// Returns the newly parsed entry as `Ok(Some(entry))`. Returns
+    /// `Ok(None)` when iteration is complete and all entries have already been
+    /// parsed and yielded. If an error occurs while parsing the next entry,
+    /// then this error is returned as `Err(e)`, and all subsequent calls return
+    /// `Ok(None)`.
+    pub fn next(&mut self) -> Result<Option<GnuPubEntry<R>>> {
+        self.0.next()
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for GnuPubEntryIter<R> {
+    type Item = GnuPubEntry<R>;
+    type Error = crate::read::Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        self.0.next()
+    }
+}
+
+result_iterator!([R: Reader] GnuPubEntryIter<R>, GnuPubEntry<R>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endianity::LittleEndian;
+    use test_assembler::{Endian, Label, LabelMaker, Section};
+
+    #[test]
+    fn test_gnu_pubnames() {
+        let length = Label::new();
+        let start = Label::new();
+        let end = Label::new();
+        let section = Section::with_endian(Endian::Little)
+            .D32(&length)
+            .mark(&start)
+            .D16(2)
+            .D32(0x40)
+            .D32(0x100)
+            // A global function.
+            .D32(0x20)
+            .D8(0x30)
+            .append_bytes(b"main\0")
+            // A static variable.
+            .D32(0x28)
+            .D8(0xa0)
+            .append_bytes(b"counter\0")
+            .D32(0)
+            .mark(&end);
+        length.set_const((&end - &start) as u64);
+        let buf = section.get_contents().unwrap();
+
+        let debug_gnu_pubnames = DebugGnuPubNames::new(&buf, LittleEndian);
+        let mut items = debug_gnu_pubnames.items();
+
+        let entry = items.next().unwrap().unwrap();
+        assert_eq!(entry.name().slice(), b"main");
+        assert_eq!(entry.unit_header_offset(), DebugInfoOffset(0x40));
+        assert_eq!(entry.die_offset(), UnitOffset(0x20));
+        assert_eq!(
+            entry.descriptor(),
+            GnuPubDescriptor {
+                kind: GdbIndexSymbolKind::Function,
+                is_static: false,
+            }
+        );
+
+        let entry = items.next().unwrap().unwrap();
+        assert_eq!(entry.name().slice(), b"counter");
+        assert_eq!(entry.die_offset(), UnitOffset(0x28));
+        assert_eq!(
+            entry.descriptor(),
+            GnuPubDescriptor {
+                kind: GdbIndexSymbolKind::Variable,
+                is_static: true,
+            }
+        );
+
+        assert!(items.next().unwrap().is_none());
+    }
+}
@@ -0,0 +1,391 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::common::UnitSectionOffset;
+use crate::read::{
+    AttributeValue, ColumnType, DebuggingInformationEntry, DumpResult, Dwarf, Reader, ReaderOffset,
+    UnitHeader, UnitRef,
+};
+
+/// Write `s` as a JSON string literal.
+fn write_json_string<W: Write>(w: &mut W, s: &str) -> DumpResult<()> {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')?;
+    Ok(())
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Write a JSON representation of all units in the `.debug_info` section.
+    ///
+    /// The output is an object with a `units` array. See `UnitRef::write_json`
+    /// for the representation of each unit.
+    ///
+    /// ```rust,no_run
+    /// # fn example<R: gimli::Reader>(dwarf: &gimli::Dwarf<R>) -> gimli::DumpResult<()> {
+    /// let mut json = String::new();
+    /// dwarf.write_json(&mut json)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_json<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        write!(w, "{{\"units\":[")?;
+        let mut units = self.units();
+        let mut first = true;
+        while let Some(header) = units.next()? {
+            if !first {
+                w.write_char(',')?;
+            }
+            first = false;
+            let unit = self.unit(header)?;
+            unit.unit_ref(self).write_json(w)?;
+        }
+        write!(w, "]}}")?;
+        Ok(())
+    }
+}
+
+fn write_json_header<R: Reader, W: Write>(w: &mut W, header: &UnitHeader<R>) -> DumpResult<()> {
+    let (section, offset) = match header.offset() {
+        UnitSectionOffset::DebugInfoOffset(o) => (".debug_info", o.0),
+        UnitSectionOffset::DebugTypesOffset(o) => (".debug_types", o.0),
+    };
+    write!(
+        w,
+        "\"section\":\"{}\",\"offset\":{},\"version\":{},\"address_size\":{},\"format\":\"{:?}\"",
+        section,
+        offset.into_u64(),
+        header.version(),
+        header.address_size(),
+        header.format(),
+    )?;
+    Ok(())
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Write a JSON representation of this unit.
+    ///
+    /// The unit is written as an object containing the unit header fields,
+    /// an `entries` array containing the tree of entries, and a `lines` array
+    /// containing the rows of the line number program.
+    ///
+    /// Each entry is an object with `offset`, `tag`, `attributes` and `children`
+    /// fields. Each attribute is an object with `name` and `value` fields.
+    /// Strings are resolved, flags are written as booleans, and constants are
+    /// written as numbers. Other values are written as strings using the same
+    /// format as `UnitRef::dump_entries`.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        w.write_char('{')?;
+        write_json_header(w, &self.header)?;
+        write!(w, ",\"entries\":[")?;
+        self.write_json_entries(w)?;
+        write!(w, "],\"lines\":")?;
+        self.write_json_lines(w)?;
+        w.write_char('}')?;
+        Ok(())
+    }
+
+    /// Write the tree of entries, using an explicit depth instead of recursion.
+    fn write_json_entries<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        let mut entries = self.entries();
+        let mut depth = 0;
+        let mut first = true;
+        while let Some((delta_depth, entry)) = entries.next_dfs()? {
+            if !first {
+                if delta_depth <= 0 {
+                    // Stop at the end of the tree for the root entry.
+                    if depth + delta_depth <= 0 {
+                        break;
+                    }
+                    // Close the previous entry and any ancestors that have ended.
+                    for _ in delta_depth..=0 {
+                        write!(w, "]}}")?;
+                    }
+                    w.write_char(',')?;
+                }
+                depth += delta_depth;
+            }
+            first = false;
+            self.write_json_entry(w, entry)?;
+        }
+        if !first {
+            for _ in 0..=depth {
+                write!(w, "]}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an entry, leaving its `children` array open.
+    fn write_json_entry<W: Write>(
+        &self,
+        w: &mut W,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> DumpResult<()> {
+        write!(w, "{{\"offset\":{},\"tag\":", entry.offset().0.into_u64())?;
+        match entry.tag().static_string() {
+            Some(tag) => write_json_string(w, tag)?,
+            None => write!(w, "{}", entry.tag().0)?,
+        }
+        write!(w, ",\"attributes\":[")?;
+        let mut attrs = entry.attrs();
+        let mut first = true;
+        while let Some(attr) = attrs.next()? {
+            if !first {
+                w.write_char(',')?;
+            }
+            first = false;
+            write!(w, "{{\"name\":")?;
+            match attr.name().static_string() {
+                Some(name) => write_json_string(w, name)?,
+                None => write!(w, "{}", attr.name().0)?,
+            }
+            write!(w, ",\"value\":")?;
+            self.write_json_value(w, &attr)?;
+            w.write_char('}')?;
+        }
+        write!(w, "],\"children\":[")?;
+        Ok(())
+    }
+
+    fn write_json_value<W: Write>(
+        &self,
+        w: &mut W,
+        attr: &crate::read::Attribute<R>,
+    ) -> DumpResult<()> {
+        let value = attr.value();
+        match value {
+            AttributeValue::Flag(flag) => write!(w, "{}", flag)?,
+            AttributeValue::Data1(_)
+            | AttributeValue::Data2(_)
+            | AttributeValue::Data4(_)
+            | AttributeValue::Data8(_)
+            | AttributeValue::Udata(_) => match attr.udata_value() {
+                Some(value) => write!(w, "{}", value)?,
+                None => self.write_json_text(w, attr)?,
+            },
+            AttributeValue::Sdata(value) => write!(w, "{}", value)?,
+            _ => match self.attr_string(value.clone()) {
+                Ok(s) => write_json_string(w, &s.to_string_lossy()?)?,
+                Err(_) => self.write_json_text(w, attr)?,
+            },
+        }
+        Ok(())
+    }
+
+    fn write_json_text<W: Write>(
+        &self,
+        w: &mut W,
+        attr: &crate::read::Attribute<R>,
+    ) -> DumpResult<()> {
+        let mut text = String::new();
+        self.dump_attr_value(&mut text, attr)?;
+        write_json_string(w, &text)
+    }
+
+    fn write_json_lines<W: Write>(&self, w: &mut W) -> DumpResult<()> {
+        w.write_char('[')?;
+        if let Some(program) = self.line_program.clone() {
+            let mut rows = program.rows();
+            let mut first = true;
+            while let Some((header, row)) = rows.next_row()? {
+                if !first {
+                    w.write_char(',')?;
+                }
+                first = false;
+                let line = row.line().map(|line| line.get()).unwrap_or(0);
+                let column = match row.column() {
+                    ColumnType::Column(column) => column.get(),
+                    ColumnType::LeftEdge => 0,
+                };
+                write!(w, "{{\"address\":{},\"file\":", row.address())?;
+                match row.file(header) {
+                    Some(file) => {
+                        let mut path = String::new();
+                        if let Some(directory) = file.directory(header) {
+                            path.push_str(&self.attr_string(directory)?.to_string_lossy()?);
+                            path.push('/');
+                        }
+                        path.push_str(&self.attr_string(file.path_name())?.to_string_lossy()?);
+                        write_json_string(w, &path)?;
+                    }
+                    None => write!(w, "null")?,
+                }
+                write!(
+                    w,
+                    ",\"line\":{},\"column\":{},\"is_stmt\":{},\"end_sequence\":{}}}",
+                    line,
+                    column,
+                    row.is_stmt(),
+                    row.end_sequence()
+                )?;
+            }
+        }
+        w.write_char(']')?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format, LineEncoding};
+    use crate::constants;
+    use crate::read::{DebugAbbrev, DebugInfo, DebugLine, DebugStr};
+    use crate::write::{self, EndianVec, LineProgram, LineString, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_json_string() {
+        let mut s = String::new();
+        write_json_string(&mut s, "a\"b\\c\nd\u{1}").unwrap();
+        assert_eq!(s, "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn test_write_json() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let mut program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"/dir".to_vec()),
+            LineString::String(b"file.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let file = program.add_file(LineString::String(b"file.c".to_vec()), dir, None);
+        program.begin_sequence(Some(write::Address::Constant(0x1000)));
+        program.row().file = file;
+        program.row().line = 3;
+        program.generate_row();
+        program.end_sequence(0x10);
+        let unit_id = dwarf.units.add(write::Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let name = dwarf.strings.add("file.c");
+        unit.get_mut(root).set(
+            constants::DW_AT_name,
+            write::AttributeValue::StringRef(name),
+        );
+        let child = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(child)
+            .set(constants::DW_AT_external, write::AttributeValue::Flag(true));
+        unit.get_mut(child)
+            .set(constants::DW_AT_decl_line, write::AttributeValue::Udata(7));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            debug_line: DebugLine::new(sections.debug_line.slice(), LittleEndian),
+            debug_str: DebugStr::new(sections.debug_str.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let mut json = String::new();
+        read_dwarf.write_json(&mut json).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                "{\"units\":[{\"section\":\".debug_info\",\"offset\":0,\"version\":4,",
+                "\"address_size\":8,\"format\":\"Dwarf32\",\"entries\":[",
+                "{\"offset\":11,\"tag\":\"DW_TAG_compile_unit\",\"attributes\":[",
+                "{\"name\":\"DW_AT_name\",\"value\":\"file.c\"},",
+                "{\"name\":\"DW_AT_stmt_list\",\"value\":\"<.debug_line+0x00000000>\"}",
+                "],\"children\":[",
+                "{\"offset\":20,\"tag\":\"DW_TAG_variable\",\"attributes\":[",
+                "{\"name\":\"DW_AT_external\",\"value\":true},",
+                "{\"name\":\"DW_AT_decl_line\",\"value\":7}",
+                "],\"children\":[]}]}],\"lines\":[",
+                "{\"address\":4096,\"file\":\"file.c\",\"line\":3,\"column\":0,",
+                "\"is_stmt\":true,\"end_sequence\":false},",
+                "{\"address\":4112,\"file\":\"file.c\",\"line\":3,\"column\":0,",
+                "\"is_stmt\":true,\"end_sequence\":true}]}]}",
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_json_nesting() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+        let block = unit.add(subprogram, constants::DW_TAG_lexical_block);
+        unit.add(block, constants::DW_TAG_variable);
+        unit.add(root, constants::DW_TAG_base_type);
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let mut json = String::new();
+        read_dwarf.write_json(&mut json).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                "{\"units\":[{\"section\":\".debug_info\",\"offset\":0,\"version\":4,",
+                "\"address_size\":8,\"format\":\"Dwarf32\",\"entries\":[",
+                "{\"offset\":11,\"tag\":\"DW_TAG_compile_unit\",\"attributes\":[],\"children\":[",
+                "{\"offset\":12,\"tag\":\"DW_TAG_base_type\",\"attributes\":[],\"children\":[]},",
+                "{\"offset\":13,\"tag\":\"DW_TAG_subprogram\",\"attributes\":[],\"children\":[",
+                "{\"offset\":14,\"tag\":\"DW_TAG_lexical_block\",\"attributes\":[],\"children\":[",
+                "{\"offset\":15,\"tag\":\"DW_TAG_variable\",\"attributes\":[],\"children\":[]}",
+                "]}]}]}],\"lines\":[]}]}",
+            )
+        );
+
+        // Deep nesting must not overflow the stack.
+        #[rustfmt::skip]
+        let debug_abbrev = [
+            1, 0x11, 1, 0, 0, // DW_TAG_compile_unit, children
+            2, 0x0b, 1, 0, 0, // DW_TAG_lexical_block, children
+            0,
+        ];
+        let depth = 100_000;
+        let mut debug_info = vec![0; 11];
+        debug_info[4] = 4; // version
+        debug_info[10] = 8; // address_size
+        debug_info.push(1);
+        debug_info.resize(debug_info.len() + depth, 2);
+        debug_info.resize(debug_info.len() + depth + 1, 0);
+        let length = (debug_info.len() - 4) as u32;
+        debug_info[..4].copy_from_slice(&length.to_le_bytes());
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(&debug_abbrev, LittleEndian),
+            debug_info: DebugInfo::new(&debug_info, LittleEndian),
+            ..Default::default()
+        };
+        let mut json = String::new();
+        read_dwarf.write_json(&mut json).unwrap();
+        assert_eq!(json.matches("\"children\":[").count(), depth + 1);
+        assert!(json.ends_with(&format!("{}],\"lines\":[]}}]}}", "]}".repeat(depth + 1))));
+    }
+}
@@ -1,15 +1,21 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt;
+use core::mem;
 use core::num::{NonZeroU64, Wrapping};
 use core::result;
 
 use crate::common::{
     DebugLineOffset, DebugLineStrOffset, DebugStrOffset, DebugStrOffsetsIndex, Encoding, Format,
-    LineEncoding, SectionId,
+    LineEncoding, SectionId, TombstoneFilter,
 };
 use crate::constants;
 use crate::endianity::Endianity;
-use crate::read::{AttributeValue, EndianSlice, Error, Reader, ReaderOffset, Result, Section};
+use crate::read::{
+    AttributeValue, EndianSlice, Error, Reader, ReaderOffset, Result, Section, UnitRef,
+};
 
 /// The `DebugLine` struct contains the source location to instruction mapping
 /// found in the `.debug_line` section.
@@ -95,6 +101,90 @@ impl<T> DebugLine<T> {
     }
 }
 
+/// A cache of previously parsed line number programs in a `.debug_line`
+/// section, keyed by their offset in the section.
+///
+/// Parsing the header of a line number program requires allocating its
+/// directory and file name tables. Units that share a line number program,
+/// such as type units and their compilation unit, can use this cache to avoid
+/// parsing the same header more than once. The tables are shared between the
+/// cached program and the programs that are returned, so returning a cached
+/// program does not copy them.
+///
+/// Use `Dwarf::unit_with_line_program_cache` to construct units that use the
+/// cache.
+#[derive(Debug)]
+pub struct LineProgramCache<R: Reader> {
+    debug_line: DebugLine<R>,
+    programs: BTreeMap<(u64, u8), Result<IncompleteLineProgram<R>>>,
+}
+
+impl<R: Reader> LineProgramCache<R> {
+    /// Create an empty cache for the line number programs in `debug_line`.
+    pub fn new(debug_line: DebugLine<R>) -> Self {
+        LineProgramCache {
+            debug_line,
+            programs: BTreeMap::new(),
+        }
+    }
+
+    /// Return the line number program at the given offset, parsing it and
+    /// storing it in the cache if required.
+    ///
+    /// The parameters are the same as for `DebugLine::program`. The
+    /// `comp_dir` and `comp_name` are applied to the returned program,
+    /// and are not stored in the cache.
+    ///
+    /// Errors during parsing are also stored in the cache.
+    pub fn program(
+        &mut self,
+        offset: DebugLineOffset<R::Offset>,
+        address_size: u8,
+        comp_dir: Option<R>,
+        comp_name: Option<R>,
+    ) -> Result<IncompleteLineProgram<R>> {
+        let debug_line = &self.debug_line;
+        let mut program = self
+            .programs
+            .entry((offset.0.into_u64(), address_size))
+            .or_insert_with(|| debug_line.program(offset, address_size, None, None))
+            .clone()?;
+        program.header.set_comp_unit(comp_dir, comp_name);
+        Ok(program)
+    }
+
+    /// Return the number of line number programs in the cache.
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Return true if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    /// Remove all line number programs from the cache.
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the cache.
+    ///
+    /// This does not include the section data that is referenced by the
+    /// line number programs, or the overhead of the internal nodes of the map.
+    pub fn allocated_bytes(&self) -> usize {
+        self.programs
+            .values()
+            .map(|program| {
+                mem::size_of::<((u64, u8), Result<IncompleteLineProgram<R>>)>()
+                    + program
+                        .as_ref()
+                        .map_or(0, |program| program.header.allocated_bytes())
+            })
+            .sum()
+    }
+}
+
 impl<R> Section<R> for DebugLine<R> {
     fn id() -> SectionId {
         SectionId::DebugLine
@@ -138,7 +228,7 @@ where
         &self.header
     }
     fn add_file(&mut self, file: FileEntry<R, Offset>) {
-        self.header.file_names.push(file);
+        Arc::make_mut(&mut self.header.file_names).push(file);
     }
 }
 
@@ -228,8 +318,9 @@ where
     /// number matrix, then `Ok(None)` is returned. If there was an error parsing
     /// an instruction, then `Err(e)` is returned.
     ///
-    /// Unfortunately, the references mean that this cannot be a
-    /// `FallibleIterator`.
+    /// Unfortunately, the references mean that this method cannot be used
+    /// to implement `FallibleIterator`. Instead, the `FallibleIterator`
+    /// implementation for `LineRows` yields a copy of each row.
     pub fn next_row(&mut self) -> Result<Option<(&LineProgramHeader<R, Offset>, &LineRow)>> {
         // Perform any reset that was required after copying the previous row.
         self.row.reset(self.program.header());
@@ -258,6 +349,28 @@ where
     }
 }
 
+#[cfg(feature = "fallible-iterator")]
+impl<R, Program, Offset> fallible_iterator::FallibleIterator for LineRows<R, Program, Offset>
+where
+    Program: LineProgram<R, Offset>,
+    R: Reader<Offset = Offset>,
+    Offset: ReaderOffset,
+{
+    type Item = LineRow;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        Ok(self.next_row()?.map(|(_, row)| *row))
+    }
+}
+
+result_iterator!(
+    [R, Program, Offset] LineRows<R, Program, Offset>,
+    LineRow,
+    |rows| Ok(rows.next_row()?.map(|(_, row)| *row)),
+    where Program: LineProgram<R, Offset>, R: Reader<Offset = Offset>, Offset: ReaderOffset
+);
+
 /// Deprecated. `Opcode` has been renamed to `LineInstruction`.
 #[deprecated(note = "Opcode has been renamed to LineInstruction, use that instead.")]
 pub type Opcode<R> = LineInstruction<R, <R as Reader>::Offset>;
@@ -629,6 +742,7 @@ pub type LineNumberRow = LineRow;
 ///
 /// Each row is a copy of the registers of the state machine, as defined in section 6.2.2.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineRow {
     tombstone: bool,
     address: Wrapping<u64>,
@@ -878,7 +992,11 @@ impl LineRow {
 
             LineInstruction::SetAddress(address) => {
                 let tombstone_address = !0 >> (64 - program.header().encoding.address_size * 8);
-                self.tombstone = address == tombstone_address;
+                self.tombstone = address == tombstone_address
+                    || program
+                        .header()
+                        .tombstones
+                        .is_tombstone(address, program.header().encoding.address_size);
                 self.address.0 = address;
                 self.op_index.0 = 0;
                 false
@@ -985,6 +1103,7 @@ impl LineRow {
 
 /// The type of column that a row is referring to.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColumnType {
     /// The `LeftEdge` means that the statement begins at the start of the new
     /// line.
@@ -1043,7 +1162,7 @@ where
     standard_opcode_lengths: R,
 
     /// "A sequence of directory entry format descriptions."
-    directory_entry_format: Vec<FileEntryFormat>,
+    directory_entry_format: Arc<Vec<FileEntryFormat>>,
 
     /// > Entries in this sequence describe each path that was searched for
     /// > included source files in this compilation. (The paths include those
@@ -1053,15 +1172,15 @@ where
     /// > of the compilation.
     /// >
     /// > The last entry is followed by a single null byte.
-    include_directories: Vec<AttributeValue<R, Offset>>,
+    include_directories: Arc<Vec<AttributeValue<R, Offset>>>,
 
     /// "A sequence of file entry format descriptions."
-    file_name_entry_format: Vec<FileEntryFormat>,
+    file_name_entry_format: Arc<Vec<FileEntryFormat>>,
 
     /// "Entries in this sequence describe source files that contribute to the
     /// line number information for this compilation unit or is used in other
     /// contexts."
-    file_names: Vec<FileEntry<R, Offset>>,
+    file_names: Arc<Vec<FileEntry<R, Offset>>>,
 
     /// The encoded line program instructions.
     program_buf: R,
@@ -1071,6 +1190,9 @@ where
 
     /// The primary source file.
     comp_file: Option<FileEntry<R, Offset>>,
+
+    /// The filter for recognizing additional tombstone addresses.
+    tombstones: TombstoneFilter,
 }
 
 impl<R, Offset> LineProgramHeader<R, Offset>
@@ -1281,7 +1403,7 @@ where
         input: &mut R,
         offset: DebugLineOffset<Offset>,
         mut address_size: u8,
-        mut comp_dir: Option<R>,
+        comp_dir: Option<R>,
         comp_name: Option<R>,
     ) -> Result<LineProgramHeader<R, Offset>> {
         let (unit_length, format) = input.read_initial_length()?;
@@ -1358,7 +1480,6 @@ where
                 include_directories.push(AttributeValue::String(directory));
             }
         } else {
-            comp_dir = None;
             directory_entry_format = FileEntryFormat::parse(rest)?;
             let count = rest.read_uleb128()?;
             for _ in 0..count {
@@ -1370,18 +1491,9 @@ where
             }
         }
 
-        let comp_file;
         let file_name_entry_format;
         let mut file_names = Vec::new();
         if version <= 4 {
-            comp_file = comp_name.map(|name| FileEntry {
-                path_name: AttributeValue::String(name),
-                directory_index: 0,
-                timestamp: 0,
-                size: 0,
-                md5: [0; 16],
-            });
-
             file_name_entry_format = Vec::new();
             loop {
                 let path_name = rest.read_null_terminated_slice()?;
@@ -1391,7 +1503,6 @@ where
                 file_names.push(FileEntry::parse(rest, path_name)?);
             }
         } else {
-            comp_file = None;
             file_name_entry_format = FileEntryFormat::parse(rest)?;
             let count = rest.read_uleb128()?;
             for _ in 0..count {
@@ -1399,7 +1510,7 @@ where
             }
         }
 
-        let header = LineProgramHeader {
+        let mut header = LineProgramHeader {
             encoding,
             offset,
             unit_length,
@@ -1407,16 +1518,47 @@ where
             line_encoding,
             opcode_base,
             standard_opcode_lengths,
-            directory_entry_format,
-            include_directories,
-            file_name_entry_format,
-            file_names,
+            directory_entry_format: Arc::new(directory_entry_format),
+            include_directories: Arc::new(include_directories),
+            file_name_entry_format: Arc::new(file_name_entry_format),
+            file_names: Arc::new(file_names),
             program_buf,
-            comp_dir,
-            comp_file,
+            comp_dir: None,
+            comp_file: None,
+            tombstones: TombstoneFilter::default(),
         };
+        header.set_comp_unit(comp_dir, comp_name);
         Ok(header)
     }
+
+    /// Return an estimate of the number of bytes of heap memory used by the header.
+    fn allocated_bytes(&self) -> usize {
+        self.directory_entry_format.capacity() * mem::size_of::<FileEntryFormat>()
+            + self.include_directories.capacity() * mem::size_of::<AttributeValue<R, Offset>>()
+            + self.file_name_entry_format.capacity() * mem::size_of::<FileEntryFormat>()
+            + self.file_names.capacity() * mem::size_of::<FileEntry<R, Offset>>()
+    }
+
+    /// Set the compilation directory and file name of the unit that this
+    /// program applies to.
+    ///
+    /// These are only used for DWARF versions prior to 5, where they are the
+    /// implicit first entries of the directory and file name tables.
+    fn set_comp_unit(&mut self, comp_dir: Option<R>, comp_name: Option<R>) {
+        if self.encoding.version <= 4 {
+            self.comp_dir = comp_dir;
+            self.comp_file = comp_name.map(|name| FileEntry {
+                path_name: AttributeValue::String(name),
+                directory_index: 0,
+                timestamp: 0,
+                size: 0,
+                md5: [0; 16],
+            });
+        } else {
+            self.comp_dir = None;
+            self.comp_file = None;
+        }
+    }
 }
 
 /// Deprecated. `IncompleteLineNumberProgram` has been renamed to `IncompleteLineProgram`.
@@ -1445,6 +1587,14 @@ where
         &self.header
     }
 
+    /// Set the filter for recognizing additional tombstone addresses.
+    ///
+    /// Sequences that set their address to a tombstone address are skipped
+    /// when executing the program.
+    pub fn set_tombstone_filter(&mut self, tombstones: TombstoneFilter) {
+        self.header.tombstones = tombstones;
+    }
+
     /// Construct a new `LineRows` for executing this program to iterate
     /// over rows in the line information matrix.
     pub fn rows(self) -> OneShotLineRows<R, Offset> {
@@ -1634,6 +1784,43 @@ where
         header.directory(self.directory_index)
     }
 
+    /// Get the full path of this file.
+    ///
+    /// The path is constructed from the `DW_AT_comp_dir` attribute of the unit,
+    /// this file's directory, and this file's path name. Later components replace
+    /// earlier components if they are absolute paths. The result is normalized
+    /// by removing `.` components and resolving `..` components lexically.
+    ///
+    /// The directory index of 0 is always the compilation directory. For DWARF
+    /// version 5, this is the first entry in the directory table, which is only
+    /// used if the unit does not have a `DW_AT_comp_dir` attribute.
+    ///
+    /// `header` must be the header that contains this file entry. Use
+    /// `LineProgramHeader::file` to find the file entry for a file index, which
+    /// correctly handles the differences in file indices between versions.
+    pub fn full_path(&self, unit: UnitRef<'_, R>, header: &LineProgramHeader<R>) -> Result<String> {
+        let mut path = String::new();
+        if let Some(comp_dir) = &unit.comp_dir {
+            path_push(&mut path, &comp_dir.to_string_lossy()?);
+        } else if header.version() >= 5 {
+            if let Some(directory) = header.directory(0) {
+                path_push(&mut path, &unit.attr_string(directory)?.to_string_lossy()?);
+            }
+        }
+
+        if self.directory_index != 0 {
+            if let Some(directory) = self.directory(header) {
+                path_push(&mut path, &unit.attr_string(directory)?.to_string_lossy()?);
+            }
+        }
+
+        path_push(
+            &mut path,
+            &unit.attr_string(self.path_name())?.to_string_lossy()?,
+        );
+        Ok(normalize_path(&path))
+    }
+
     /// The implementation-defined time of last modification of the file,
     /// or 0 if not available.
     pub fn timestamp(&self) -> u64 {
@@ -1669,6 +1856,65 @@ where
     }
 }
 
+fn has_unix_root(path: &str) -> bool {
+    path.starts_with('/')
+}
+
+fn has_windows_root(path: &str) -> bool {
+    path.starts_with('\\') || path.get(1..3) == Some(":\\")
+}
+
+/// Append `p` to `path`, replacing `path` if `p` is absolute.
+fn path_push(path: &mut String, p: &str) {
+    if p.is_empty() {
+        return;
+    }
+    if path.is_empty() || has_unix_root(p) || has_windows_root(p) {
+        *path = p.into();
+    } else {
+        let separator = if has_windows_root(path) { '\\' } else { '/' };
+        if !path.ends_with(separator) {
+            path.push(separator);
+        }
+        path.push_str(p);
+    }
+}
+
+/// Remove `.` components, and resolve `..` components without accessing the
+/// file system.
+fn normalize_path(path: &str) -> String {
+    let (root, rest, separator) = if has_unix_root(path) {
+        ("/", &path[1..], "/")
+    } else if has_windows_root(path) {
+        let len = if path.starts_with('\\') { 1 } else { 3 };
+        (&path[..len], &path[len..], "\\")
+    } else {
+        ("", path, "/")
+    };
+
+    let mut components = Vec::new();
+    for component in rest.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.last().map_or(false, |last| *last != "..") {
+                    components.pop();
+                } else if root.is_empty() {
+                    components.push(component);
+                }
+            }
+            _ => components.push(component),
+        }
+    }
+
+    let mut normalized = String::from(root);
+    normalized.push_str(&components.join(separator));
+    if normalized.is_empty() && !path.is_empty() {
+        normalized.push('.');
+    }
+    normalized
+}
+
 /// The format of a component of an include directory or file name entry.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct FileEntryFormat {
@@ -1753,13 +1999,15 @@ fn parse_file_v5<R: Reader>(
                     size = value;
                 }
             }
-            constants::DW_LNCT_MD5 => {
-                if let AttributeValue::Block(mut value) = value {
+            constants::DW_LNCT_MD5 => match value {
+                AttributeValue::Data16(value) => md5 = value,
+                AttributeValue::Block(mut value) => {
                     if value.len().into_u64() == 16 {
                         md5 = value.read_u8_array()?;
                     }
                 }
-            }
+                _ => {}
+            },
             // Ignore unknown content types.
             _ => {}
         }
@@ -1818,8 +2066,8 @@ fn parse_attribute<R: Reader>(
             AttributeValue::Data8(data)
         }
         constants::DW_FORM_data16 => {
-            let block = input.split(R::Offset::from_u8(16))?;
-            AttributeValue::Block(block)
+            let data = input.read_u8_array()?;
+            AttributeValue::Data16(data)
         }
         constants::DW_FORM_udata => {
             let data = input.read_uleb128()?;
@@ -2144,7 +2392,7 @@ mod tests {
             line_encoding,
             opcode_base: OPCODE_BASE,
             standard_opcode_lengths: EndianSlice::new(STANDARD_OPCODE_LENGTHS, LittleEndian),
-            file_names: vec![
+            file_names: Arc::new(vec![
                 FileEntry {
                     path_name: AttributeValue::String(EndianSlice::new(b"foo.c", LittleEndian)),
                     directory_index: 0,
@@ -2159,13 +2407,14 @@ mod tests {
                     size: 0,
                     md5: [0; 16],
                 },
-            ],
-            include_directories: vec![],
-            directory_entry_format: vec![],
-            file_name_entry_format: vec![],
+            ]),
+            include_directories: Arc::default(),
+            directory_entry_format: Arc::default(),
+            file_name_entry_format: Arc::default(),
             program_buf: buf,
             comp_dir: None,
             comp_file: None,
+            tombstones: TombstoneFilter::default(),
         }
     }
 
@@ -2432,7 +2681,7 @@ mod tests {
         let mut header = make_test_header(EndianSlice::new(&[], LittleEndian));
 
         let dir = AttributeValue::String(EndianSlice::new(b"dir", LittleEndian));
-        header.include_directories.push(dir);
+        Arc::make_mut(&mut header.include_directories).push(dir);
 
         assert_eq!(file.directory(&header), Some(dir));
 
@@ -2844,6 +3093,24 @@ mod tests {
         assert_exec_opcode(header, initial_registers, opcode, expected_registers, false);
     }
 
+    #[test]
+    fn test_exec_set_address_tombstone_filter() {
+        let mut header = make_test_header(EndianSlice::new(&[], LittleEndian));
+        header.tombstones = TombstoneFilter {
+            zero: false,
+            max: false,
+            min_address: 0x1000,
+        };
+        let initial_registers = LineRow::new(&header);
+        let opcode = LineInstruction::SetAddress(0x10);
+
+        let mut expected_registers = initial_registers;
+        expected_registers.tombstone = true;
+        expected_registers.address.0 = 0x10;
+
+        assert_exec_opcode(header, initial_registers, opcode, expected_registers, false);
+    }
+
     #[test]
     fn test_exec_define_file() {
         let mut program = make_test_program(EndianSlice::new(&[], LittleEndian));
@@ -2900,6 +3167,105 @@ mod tests {
         let _: &OneShotLineRows<EndianSlice<'b, LittleEndian>> = a;
     }
 
+    #[test]
+    fn test_line_program_cache() {
+        #[rustfmt::skip]
+        let buf = [
+            // 32-bit length = 32.
+            0x20, 0x00, 0x00, 0x00,
+            // Version.
+            0x04, 0x00,
+            // Header length = 22.
+            0x16, 0x00, 0x00, 0x00,
+            // Minimum instruction length.
+            0x01,
+            // Maximum operations per byte.
+            0x01,
+            // Default is_stmt.
+            0x01,
+            // Line base.
+            0x00,
+            // Line range.
+            0x01,
+            // Opcode base.
+            0x03,
+            // Standard opcode lengths for opcodes 1 .. opcode base - 1.
+            0x01, 0x02,
+            // Include directories = '/', 'i', 'n', 'c', '\0', '\0'
+            0x2f, 0x69, 0x6e, 0x63, 0x00, 0x00,
+            // File names
+                // a.c
+                0x61, 0x2e, 0x63, 0x00,
+                0x01,
+                0x00,
+                0x00,
+            // End file names.
+            0x00,
+
+            // Dummy line program data.
+            0x00, 0x00, 0x00, 0x00,
+
+            // Truncated next line program.
+            0xff, 0xff,
+        ];
+        let debug_line = DebugLine::new(&buf, LittleEndian);
+        let dir1 = EndianSlice::new(b"/dir1", LittleEndian);
+        let dir2 = EndianSlice::new(b"/dir2", LittleEndian);
+        let name = EndianSlice::new(b"main.c", LittleEndian);
+
+        let mut cache = LineProgramCache::new(debug_line);
+        assert!(cache.is_empty());
+        assert_eq!(cache.allocated_bytes(), 0);
+
+        let program = cache
+            .program(DebugLineOffset(0), 8, Some(dir1), Some(name))
+            .unwrap();
+        let header = program.header();
+        assert_eq!(header.directory(0), Some(AttributeValue::String(dir1)));
+        assert_eq!(
+            header.file(0).unwrap().path_name,
+            AttributeValue::String(name)
+        );
+        assert_eq!(header.include_directories().len(), 1);
+        assert_eq!(header.file_names().len(), 1);
+
+        let program = cache
+            .program(DebugLineOffset(0), 8, Some(dir2), None)
+            .unwrap();
+        let header = program.header();
+        assert_eq!(header.directory(0), Some(AttributeValue::String(dir2)));
+        assert_eq!(header.file(0), None);
+        assert_eq!(
+            header.file(1).unwrap().path_name,
+            AttributeValue::String(EndianSlice::new(b"a.c", LittleEndian))
+        );
+        assert_eq!(cache.len(), 1);
+
+        // The tables are shared with the cached program.
+        let cached = cache.program(DebugLineOffset(0), 8, None, None).unwrap();
+        assert!(Arc::ptr_eq(
+            &cached.header.file_names,
+            &program.header.file_names
+        ));
+        assert!(Arc::ptr_eq(
+            &cached.header.include_directories,
+            &program.header.include_directories
+        ));
+
+        let offset = DebugLineOffset(buf.len() - 2);
+        assert!(cache.program(offset, 8, None, None).is_err());
+        assert!(cache.program(offset, 8, None, None).is_err());
+        assert_eq!(cache.len(), 2);
+        assert!(
+            cache.allocated_bytes()
+                > 2 * mem::size_of::<IncompleteLineProgram<EndianSlice<'_, LittleEndian>>>()
+        );
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.allocated_bytes(), 0);
+    }
+
     #[test]
     fn test_parse_debug_line_v5_ok() {
         let expected_lengths = &[1, 2];
@@ -3117,5 +3483,139 @@ mod tests {
         assert_eq!(sequences[0].end, 4);
         assert_eq!(sequences[1].start, 11);
         assert_eq!(sequences[1].end, 14);
+
+        // The rows in tombstone sequences are skipped.
+        let rest = &mut EndianSlice::new(&buf, LittleEndian);
+        let header = LineProgramHeader::parse(rest, DebugLineOffset(0), 4, None, None)
+            .expect("should parse header ok");
+        let program = IncompleteLineProgram { header };
+        let rows = program
+            .rows()
+            .into_result_iter()
+            .map(|row| row.map(|row| (row.address(), row.end_sequence())))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (1, false),
+                (2, false),
+                (4, true),
+                (11, false),
+                (12, false),
+                (14, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let mut path = String::new();
+        path_push(&mut path, "/comp_dir");
+        path_push(&mut path, "src");
+        path_push(&mut path, "../include/./foo.h");
+        assert_eq!(path, "/comp_dir/src/../include/./foo.h");
+        assert_eq!(normalize_path(&path), "/comp_dir/include/foo.h");
+
+        path_push(&mut path, "/usr/include");
+        path_push(&mut path, "stdio.h");
+        assert_eq!(normalize_path(&path), "/usr/include/stdio.h");
+
+        let mut path = String::from("C:\\comp_dir");
+        path_push(&mut path, "src\\..\\foo.c");
+        assert_eq!(normalize_path(&path), "C:\\comp_dir\\foo.c");
+
+        assert_eq!(normalize_path("/.."), "/");
+        assert_eq!(normalize_path("../a/../../b"), "../../b");
+        assert_eq!(normalize_path("a/.."), ".");
+        assert_eq!(normalize_path(""), "");
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_full_path() {
+        use crate::read::{DebugAbbrev, DebugInfo, DebugLine, DebugLineStr, DebugStr, Dwarf};
+        use crate::write;
+
+        for version in [4, 5] {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version,
+                address_size: 8,
+            };
+            let mut dwarf = write::Dwarf::new();
+            let comp_dir = write::LineString::String(b"/comp_dir".to_vec());
+            let comp_file = write::LineString::String(b"src/main.c".to_vec());
+            let mut program = write::LineProgram::new(
+                encoding,
+                LineEncoding::default(),
+                comp_dir,
+                comp_file,
+                None,
+            );
+            let dir1 = program.add_directory(write::LineString::String(b"include".to_vec()));
+            let dir2 = program.add_directory(write::LineString::String(b"/usr/include".to_vec()));
+            let foo = program.add_file(write::LineString::String(b"../foo.h".to_vec()), dir1, None);
+            program.add_file(write::LineString::String(b"stdio.h".to_vec()), dir2, None);
+            program.add_file(
+                write::LineString::String(b"/abs/bar.h".to_vec()),
+                dir1,
+                None,
+            );
+
+            let unit_id = dwarf.units.add(write::Unit::new(encoding, program));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            unit.get_mut(root).set(
+                constants::DW_AT_comp_dir,
+                write::AttributeValue::String(b"/comp_dir".to_vec()),
+            );
+            unit.get_mut(root).set(
+                constants::DW_AT_name,
+                write::AttributeValue::String(b"src/main.c".to_vec()),
+            );
+            let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+            unit.get_mut(subprogram).set(
+                constants::DW_AT_decl_file,
+                write::AttributeValue::FileIndex(Some(foo)),
+            );
+            let mut sections = write::Sections::new(write::EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+
+            let read_dwarf = Dwarf {
+                debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+                debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+                debug_line: DebugLine::new(sections.debug_line.slice(), LittleEndian),
+                debug_line_str: DebugLineStr::new(sections.debug_line_str.slice(), LittleEndian),
+                debug_str: DebugStr::new(sections.debug_str.slice(), LittleEndian),
+                ..Default::default()
+            };
+            let header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(header).unwrap();
+            let read_unit = read_unit.unit_ref(&read_dwarf);
+            let header = read_unit.line_program.as_ref().unwrap().header();
+
+            let paths = header
+                .file_names()
+                .iter()
+                .map(|file| file.full_path(read_unit, header).unwrap())
+                .collect::<Vec<_>>();
+            let expected = [
+                "/comp_dir/src/main.c",
+                "/comp_dir/foo.h",
+                "/usr/include/stdio.h",
+                "/abs/bar.h",
+            ];
+            // Only version 5 includes the primary source file in the file table.
+            let skip = if version >= 5 { 0 } else { 1 };
+            assert_eq!(paths, expected[skip..]);
+
+            // File index 0 is the primary source file for all versions.
+            let file = header.file(0).unwrap();
+            assert_eq!(
+                file.full_path(read_unit, header).unwrap(),
+                "/comp_dir/src/main.c"
+            );
+        }
     }
 }
@@ -0,0 +1,368 @@
+use alloc::vec::Vec;
+
+use crate::common::SectionId;
+use crate::read::{Dwarf, Error, Reader, ReaderOffset, Result, Section};
+
+/// The `n_type` of an ELF note containing a build ID.
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// A reference to an external file that contains debugging information.
+///
+/// This is parsed from a section in an object file, and is passed to the
+/// resolver used by `Dwarf::load_with_resolver`. The resolver may use it to
+/// find the file on disk or to fetch it from a server such as `debuginfod`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugFileLink<R: Reader> {
+    /// The build ID of the object file.
+    ///
+    /// This is parsed from the `.note.gnu.build-id` section, and identifies a
+    /// separate file containing the debugging information for the object file.
+    BuildId(R),
+    /// The name and CRC of a separate file containing the debugging
+    /// information for the object file.
+    ///
+    /// This is parsed from the `.gnu_debuglink` section.
+    DebugLink {
+        /// The file name.
+        name: R,
+        /// The CRC-32 of the file contents.
+        crc: u32,
+    },
+    /// The name and build ID of a supplementary object file.
+    ///
+    /// This is parsed from the `.gnu_debugaltlink` section, and is typically
+    /// created by `dwz`.
+    DebugAltLink {
+        /// The file name.
+        name: R,
+        /// The build ID of the supplementary object file.
+        build_id: R,
+    },
+    /// The name and checksum of a supplementary object file.
+    ///
+    /// This is parsed from the DWARF 5 `.debug_sup` section.
+    DebugSup {
+        /// The file name.
+        name: R,
+        /// The checksum of the supplementary object file.
+        checksum: R,
+    },
+}
+
+impl<R: Reader> DebugFileLink<R> {
+    /// Parse the build ID from the contents of a `.note.gnu.build-id` section.
+    ///
+    /// Returns `None` if the section does not contain a GNU build ID note.
+    pub fn from_build_id_note(mut section: R) -> Result<Option<Self>> {
+        while !section.is_empty() {
+            let name_size = section.read_u32()?;
+            let desc_size = section.read_u32()?;
+            let note_type = section.read_u32()?;
+            let name = section.split(R::Offset::from_u32(name_size))?;
+            section.skip(R::Offset::from_u32(note_padding(name_size)))?;
+            let desc = section.split(R::Offset::from_u32(desc_size))?;
+            if note_type == NT_GNU_BUILD_ID && name.to_slice()? == &b"GNU\0"[..] {
+                return Ok(Some(DebugFileLink::BuildId(desc)));
+            }
+            let padding = R::Offset::from_u32(note_padding(desc_size));
+            if padding > section.len() {
+                break;
+            }
+            section.skip(padding)?;
+        }
+        Ok(None)
+    }
+
+    /// Parse the contents of a `.gnu_debuglink` section.
+    ///
+    /// Returns `None` if the section is empty.
+    pub fn from_gnu_debuglink(mut section: R) -> Result<Option<Self>> {
+        if section.is_empty() {
+            return Ok(None);
+        }
+        let name = section.read_null_terminated_slice()?;
+        // The CRC is aligned to 4 bytes, including the null terminator.
+        let padding = (4 - (name.len().into_u64() + 1) % 4) % 4;
+        section.skip(R::Offset::from_u64(padding)?)?;
+        let crc = section.read_u32()?;
+        Ok(Some(DebugFileLink::DebugLink { name, crc }))
+    }
+
+    /// Parse the contents of a `.gnu_debugaltlink` section.
+    ///
+    /// Returns `None` if the section is empty.
+    pub fn from_gnu_debugaltlink(mut section: R) -> Result<Option<Self>> {
+        if section.is_empty() {
+            return Ok(None);
+        }
+        let name = section.read_null_terminated_slice()?;
+        Ok(Some(DebugFileLink::DebugAltLink {
+            name,
+            build_id: section,
+        }))
+    }
+
+    /// Parse the contents of a `.debug_sup` section.
+    ///
+    /// Returns `None` if the section is empty, or if it indicates that the
+    /// object file is itself a supplementary object file.
+    pub fn from_debug_sup(mut section: R) -> Result<Option<Self>> {
+        if section.is_empty() {
+            return Ok(None);
+        }
+        let version = section.read_u16()?;
+        if version != 5 {
+            return Err(Error::UnknownVersion(u64::from(version)));
+        }
+        let is_supplementary = section.read_u8()?;
+        let name = section.read_null_terminated_slice()?;
+        let checksum_len = section.read_uleb128().and_then(R::Offset::from_u64)?;
+        let checksum = section.split(checksum_len)?;
+        if is_supplementary != 0 {
+            return Ok(None);
+        }
+        Ok(Some(DebugFileLink::DebugSup { name, checksum }))
+    }
+}
+
+/// Return the number of padding bytes after a note field of the given size.
+fn note_padding(size: u32) -> u32 {
+    (4 - size % 4) % 4
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Try to load the DWARF sections, following links to external files.
+    ///
+    /// `section` operates the same as for `Dwarf::load`. It is also used to
+    /// load the `.note.gnu.build-id`, `.gnu_debuglink`, `.gnu_debugaltlink` and
+    /// `.debug_sup` sections, which contain the links to the external files.
+    ///
+    /// `resolve` loads a section from the external file identified by a link.
+    /// It should return `None` if the file cannot be found, and an empty
+    /// section if the file does not contain the section. It is called with
+    /// `SectionId::DebugInfo` first for each file, and the file is skipped if
+    /// that returns `None`.
+    ///
+    /// If the `.debug_info` section of the object file is empty, then the
+    /// resolver is used to find a separate file containing the debugging
+    /// information, first using the build ID and then using `.gnu_debuglink`.
+    /// If found, all sections are loaded from that file instead.
+    ///
+    /// Then, if the object file (or the separate file, if found) has a
+    /// `.debug_sup` or `.gnu_debugaltlink` section, the resolver is used to
+    /// load the supplementary object file, and `self.sup` is set.
+    ///
+    /// The user should set the `file_type` field if required.
+    pub fn load_with_resolver<F, G, E>(
+        mut section: F,
+        mut resolve: G,
+    ) -> core::result::Result<Self, E>
+    where
+        F: FnMut(SectionId) -> core::result::Result<R, E>,
+        G: FnMut(&DebugFileLink<R>, SectionId) -> core::result::Result<Option<R>, E>,
+        E: From<Error>,
+    {
+        let mut dwarf = Self::load(&mut section)?;
+
+        // The link of the file that the sections were loaded from, if not
+        // the object file.
+        let mut current = None;
+        if dwarf.debug_info.reader().is_empty() {
+            let mut links = Vec::new();
+            links.extend(DebugFileLink::from_build_id_note(section(
+                SectionId::NoteGnuBuildId,
+            )?)?);
+            links.extend(DebugFileLink::from_gnu_debuglink(section(
+                SectionId::GnuDebuglink,
+            )?)?);
+            for link in links {
+                if let Some(debug) = Self::load_link(&link, &mut resolve)? {
+                    dwarf = debug;
+                    current = Some(link);
+                    break;
+                }
+            }
+        }
+
+        let mut link_section = |id| match current {
+            Some(ref link) => {
+                let data = resolve(link, id)?;
+                Ok::<_, E>(data.unwrap_or_else(|| {
+                    let mut empty = dwarf.debug_info.reader().clone();
+                    empty.empty();
+                    empty
+                }))
+            }
+            None => section(id),
+        };
+        let mut links = Vec::new();
+        links.extend(DebugFileLink::from_debug_sup(link_section(
+            SectionId::DebugSup,
+        )?)?);
+        links.extend(DebugFileLink::from_gnu_debugaltlink(link_section(
+            SectionId::GnuDebugaltlink,
+        )?)?);
+        for link in links {
+            if let Some(sup) = Self::load_link(&link, &mut resolve)? {
+                dwarf.set_sup(sup);
+                break;
+            }
+        }
+        Ok(dwarf)
+    }
+
+    /// Load the DWARF sections from the external file identified by a link.
+    fn load_link<G, E>(
+        link: &DebugFileLink<R>,
+        resolve: &mut G,
+    ) -> core::result::Result<Option<Self>, E>
+    where
+        G: FnMut(&DebugFileLink<R>, SectionId) -> core::result::Result<Option<R>, E>,
+    {
+        let debug_info = match resolve(link, SectionId::DebugInfo)? {
+            Some(debug_info) => debug_info,
+            None => return Ok(None),
+        };
+        let mut empty = debug_info.clone();
+        empty.empty();
+        Self::load(|id| {
+            if id == SectionId::DebugInfo {
+                return Ok(debug_info.clone());
+            }
+            Ok(resolve(link, id)?.unwrap_or_else(|| empty.clone()))
+        })
+        .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::EndianSlice;
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_from_build_id_note() {
+        #[rustfmt::skip]
+        let buf = [
+            // An unrelated note.
+            4, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0,
+            b'A', b'B', b'C', 0,
+            0xff, 0xff, 0, 0,
+            // The build ID note.
+            4, 0, 0, 0, 3, 0, 0, 0, 3, 0, 0, 0,
+            b'G', b'N', b'U', 0,
+            1, 2, 3, 0,
+        ];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            DebugFileLink::from_build_id_note(section),
+            Ok(Some(DebugFileLink::BuildId(EndianSlice::new(
+                &[1, 2, 3],
+                LittleEndian
+            ))))
+        );
+        assert_eq!(
+            DebugFileLink::from_build_id_note(EndianSlice::new(&buf[..20], LittleEndian)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_from_gnu_debuglink() {
+        let buf = [
+            b'a', b'.', b'd', b'b', b'g', 0, 0, 0, 0x78, 0x56, 0x34, 0x12,
+        ];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            DebugFileLink::from_gnu_debuglink(section),
+            Ok(Some(DebugFileLink::DebugLink {
+                name: EndianSlice::new(b"a.dbg", LittleEndian),
+                crc: 0x1234_5678,
+            }))
+        );
+        assert_eq!(
+            DebugFileLink::from_gnu_debuglink(EndianSlice::new(&[], LittleEndian)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_from_gnu_debugaltlink() {
+        let buf = [b's', b'u', b'p', 0, 0xab, 0xcd];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            DebugFileLink::from_gnu_debugaltlink(section),
+            Ok(Some(DebugFileLink::DebugAltLink {
+                name: EndianSlice::new(b"sup", LittleEndian),
+                build_id: EndianSlice::new(&[0xab, 0xcd], LittleEndian),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_from_debug_sup() {
+        let buf = [5, 0, 0, b's', b'u', b'p', 0, 2, 0xab, 0xcd];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            DebugFileLink::from_debug_sup(section),
+            Ok(Some(DebugFileLink::DebugSup {
+                name: EndianSlice::new(b"sup", LittleEndian),
+                checksum: EndianSlice::new(&[0xab, 0xcd], LittleEndian),
+            }))
+        );
+
+        let buf = [5, 0, 1, 0, 0];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(DebugFileLink::from_debug_sup(section), Ok(None));
+
+        let buf = [4, 0, 0, 0, 0];
+        let section = EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            DebugFileLink::from_debug_sup(section),
+            Err(Error::UnknownVersion(4))
+        );
+    }
+
+    #[test]
+    fn test_load_with_resolver() {
+        let debug_info = [1, 2, 3, 4];
+        let sup_info = [5, 6, 7, 8];
+        let debuglink = [b'a', b'.', b'd', b'b', b'g', 0, 0, 0, 0, 0, 0, 0];
+        let altlink = [b's', b'u', b'p', 0, 0xab];
+
+        let mut requests = Vec::new();
+        let dwarf = Dwarf::load_with_resolver(
+            |id| -> Result<_> {
+                let data: &[u8] = match id {
+                    SectionId::GnuDebuglink => &debuglink,
+                    _ => &[],
+                };
+                Ok(EndianSlice::new(data, LittleEndian))
+            },
+            |link, id| -> Result<_> {
+                requests.push((link.clone(), id));
+                let data: &[u8] = match (link, id) {
+                    (DebugFileLink::DebugLink { .. }, SectionId::DebugInfo) => &debug_info,
+                    (DebugFileLink::DebugLink { .. }, SectionId::GnuDebugaltlink) => &altlink,
+                    (DebugFileLink::DebugAltLink { .. }, SectionId::DebugInfo) => &sup_info,
+                    (_, SectionId::DebugInfo) => return Ok(None),
+                    _ => &[],
+                };
+                Ok(Some(EndianSlice::new(data, LittleEndian)))
+            },
+        )
+        .unwrap();
+        assert_eq!(dwarf.debug_info.reader().slice(), &debug_info);
+        let sup = dwarf.sup().unwrap();
+        assert_eq!(sup.debug_info.reader().slice(), &sup_info);
+        assert!(requests.iter().any(|(link, id)| {
+            *id == SectionId::DebugInfo
+                && *link
+                    == DebugFileLink::DebugAltLink {
+                        name: EndianSlice::new(b"sup", LittleEndian),
+                        build_id: EndianSlice::new(&[0xab], LittleEndian),
+                    }
+        }));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::common::{
     DebugAddrBase, DebugAddrIndex, DebugLocListsBase, DebugLocListsIndex, DwarfFileType, Encoding,
-    LocationListsOffset, SectionId,
+    LocationListsOffset, SectionId, TombstoneFilter,
 };
 use crate::constants;
 use crate::endianity::Endianity;
@@ -167,6 +167,16 @@ impl<R> LocationLists<R> {
             debug_loclists,
         }
     }
+
+    /// Return the `.debug_loc` section.
+    pub fn debug_loc(&self) -> &DebugLoc<R> {
+        &self.debug_loc
+    }
+
+    /// Return the `.debug_loclists` section.
+    pub fn debug_loclists(&self) -> &DebugLocLists<R> {
+        &self.debug_loclists
+    }
 }
 
 impl<T> LocationLists<T> {
@@ -325,6 +335,9 @@ enum LocListsFormat {
 ///
 /// This iterator does not perform any processing of the location entries,
 /// such as handling base addresses.
+///
+/// Use `LocListIter`, as returned by `Dwarf::locations`, to iterate over the entries
+/// with base address selection and the unit base address already applied.
 #[derive(Debug)]
 pub struct RawLocListIter<R: Reader> {
     input: R,
@@ -527,6 +540,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for RawLocListIter<R> {
     }
 }
 
+result_iterator!([R: Reader] RawLocListIter<R>, RawLocListEntry<R>);
+
 /// An iterator over a location list.
 ///
 /// This iterator internally handles processing of base address selection entries
@@ -538,6 +553,7 @@ pub struct LocListIter<R: Reader> {
     base_address: u64,
     debug_addr: DebugAddr<R>,
     debug_addr_base: DebugAddrBase<R::Offset>,
+    tombstones: TombstoneFilter,
 }
 
 impl<R: Reader> LocListIter<R> {
@@ -553,9 +569,17 @@ impl<R: Reader> LocListIter<R> {
             base_address,
             debug_addr,
             debug_addr_base,
+            tombstones: TombstoneFilter::default(),
         }
     }
 
+    /// Set the filter for recognizing additional tombstone addresses.
+    ///
+    /// Entries that begin at a tombstone address are skipped.
+    pub fn set_tombstone_filter(&mut self, tombstones: TombstoneFilter) {
+        self.tombstones = tombstones;
+    }
+
     #[inline]
     fn get_address(&self, index: DebugAddrIndex<R::Offset>) -> Result<u64> {
         self.debug_addr
@@ -600,6 +624,7 @@ impl<R: Reader> LocListIter<R> {
             mask
         };
 
+        let default = matches!(raw_loc, RawLocListEntry::DefaultLocation { .. });
         let (range, data) = match raw_loc {
             RawLocListEntry::BaseAddress { addr } => {
                 self.base_address = addr;
@@ -654,6 +679,14 @@ impl<R: Reader> LocListIter<R> {
             return Ok(None);
         }
 
+        if !default
+            && self
+                .tombstones
+                .is_tombstone(range.begin, self.raw.encoding.address_size)
+        {
+            return Ok(None);
+        }
+
         if range.begin > range.end {
             self.raw.input.empty();
             return Err(Error::InvalidLocationAddressRange);
@@ -673,6 +706,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for LocListIter<R> {
     }
 }
 
+result_iterator!([R: Reader] LocListIter<R>, LocationListEntry<R>);
+
 /// A location list entry from the `.debug_loc` or `.debug_loclists` sections.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LocationListEntry<R: Reader> {
@@ -1549,6 +1584,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_locations_tombstone_filter() {
+        #[rustfmt::skip]
+        let section = Section::with_endian(Endian::Little)
+            // A StartLength at address 0.
+            .L8(8).L32(0).uleb(0x10).uleb(1).L8(1)
+            // A StartLength at -2.
+            .L8(8).L32(0xffff_fffe).uleb(1).uleb(1).L8(2)
+            // A valid StartLength.
+            .L8(8).L32(0x0100_0000).uleb(0x100).uleb(1).L8(3)
+            // A DefaultLocation, which is never a tombstone.
+            .L8(5).uleb(1).L8(4)
+            // A list end.
+            .L8(0);
+
+        let buf = section.get_contents().unwrap();
+        let debug_loc = DebugLoc::new(&[], LittleEndian);
+        let debug_loclists = DebugLocLists::new(&buf, LittleEndian);
+        let loclists = LocationLists::new(debug_loc, debug_loclists);
+        let debug_addr = &DebugAddr::from(EndianSlice::new(&[], LittleEndian));
+        let debug_addr_base = DebugAddrBase(0);
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 4,
+        };
+
+        let mut locations = loclists
+            .locations(
+                LocationListsOffset(0x0),
+                encoding,
+                0,
+                debug_addr,
+                debug_addr_base,
+            )
+            .unwrap();
+        locations.set_tombstone_filter(TombstoneFilter {
+            zero: true,
+            max: true,
+            min_address: 0,
+        });
+        assert_eq!(
+            locations.next(),
+            Ok(Some(LocationListEntry {
+                range: Range {
+                    begin: 0x0100_0000,
+                    end: 0x0100_0100,
+                },
+                data: Expression(EndianSlice::new(&[3], LittleEndian)),
+            }))
+        );
+        assert_eq!(
+            locations.next(),
+            Ok(Some(LocationListEntry {
+                range: Range {
+                    begin: 0,
+                    end: u64::max_value(),
+                },
+                data: Expression(EndianSlice::new(&[4], LittleEndian)),
+            }))
+        );
+        assert_eq!(locations.next(), Ok(None));
+    }
+
     #[test]
     fn test_get_offset() {
         for format in [Format::Dwarf32, Format::Dwarf64] {
@@ -63,6 +63,13 @@ where
         }
     }
 
+    pub fn sets(&self) -> LookupSetIter<R, Parser> {
+        LookupSetIter {
+            remaining_input: self.input_buffer.clone(),
+            phantom: PhantomData,
+        }
+    }
+
     pub fn reader(&self) -> &R {
         &self.input_buffer
     }
@@ -125,6 +132,55 @@ where
     }
 }
 
+/// A set in a lookup section, consisting of its header and an iterator over its entries.
+pub type LookupSet<R, Parser> = (
+    <Parser as LookupParser<R>>::Header,
+    LookupEntryIter<R, Parser>,
+);
+
+/// An iterator over the sets in a lookup section.
+///
+/// Each set is returned as its header and an iterator over its entries.
+/// The entries are not parsed unless the entry iterator is used.
+#[derive(Clone, Debug)]
+pub struct LookupSetIter<R, Parser>
+where
+    R: Reader,
+    Parser: LookupParser<R>,
+{
+    remaining_input: R,
+    phantom: PhantomData<Parser>,
+}
+
+impl<R, Parser> LookupSetIter<R, Parser>
+where
+    R: Reader,
+    Parser: LookupParser<R>,
+    Parser::Header: Clone,
+{
+    /// Advance the iterator and return the next set.
+    pub fn next(&mut self) -> Result<Option<LookupSet<R, Parser>>> {
+        if self.remaining_input.is_empty() {
+            return Ok(None);
+        }
+        match Parser::parse_header(&mut self.remaining_input) {
+            Ok((input, header)) => {
+                let mut remaining_input = input.clone();
+                remaining_input.empty();
+                let entries = LookupEntryIter {
+                    current_set: Some((input, header.clone())),
+                    remaining_input,
+                };
+                Ok(Some((header, entries)))
+            }
+            Err(e) => {
+                self.remaining_input.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PubStuffHeader<T = usize> {
     format: Format,
@@ -134,12 +190,41 @@ pub struct PubStuffHeader<T = usize> {
     unit_length: T,
 }
 
+impl<T: Copy> PubStuffHeader<T> {
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn length(&self) -> T {
+        self.length
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn unit_offset(&self) -> DebugInfoOffset<T> {
+        self.unit_offset
+    }
+
+    pub fn unit_length(&self) -> T {
+        self.unit_length
+    }
+}
+
 pub trait PubStuffEntry<R: Reader> {
+    /// Whether each entry has a GNU descriptor byte between the DIE offset
+    /// and the name.
+    const HAS_DESCRIPTOR: bool = false;
+
     fn new(
         die_offset: UnitOffset<R::Offset>,
         name: R,
         unit_header_offset: DebugInfoOffset<R::Offset>,
     ) -> Self;
+
+    /// Set the GNU descriptor byte of the entry.
+    fn set_descriptor(&mut self, _descriptor: u8) {}
 }
 
 #[derive(Clone, Debug)]
@@ -191,12 +276,17 @@ where
             input.empty();
             Ok(None)
         } else {
+            let descriptor = if Entry::HAS_DESCRIPTOR {
+                Some(input.read_u8()?)
+            } else {
+                None
+            };
             let name = input.read_null_terminated_slice()?;
-            Ok(Some(Self::Entry::new(
-                UnitOffset(offset),
-                name,
-                header.unit_offset,
-            )))
+            let mut entry = Self::Entry::new(UnitOffset(offset), name, header.unit_offset);
+            if let Some(descriptor) = descriptor {
+                entry.set_descriptor(descriptor);
+            }
+            Ok(Some(entry))
         }
     }
 }
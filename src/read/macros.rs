@@ -0,0 +1,993 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::common::{
+    DebugLineOffset, DebugMacinfoOffset, DebugMacroOffset, DebugStrOffset, DebugStrOffsetsIndex,
+    Encoding, Format, SectionId,
+};
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::{
+    skip_attributes, AttributeSpecification, AttributeValue, Dwarf, EndianSlice, Error, FileEntry,
+    Reader, ReaderOffset, Result, Section, Unit,
+};
+
+/// The `DebugMacinfo` struct represents the DWARF macro information
+/// found in the `.debug_macinfo` section.
+///
+/// This section is used by DWARF version 4 and earlier.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DebugMacinfo<R> {
+    section: R,
+}
+
+impl<'input, Endian> DebugMacinfo<EndianSlice<'input, Endian>>
+where
+    Endian: Endianity,
+{
+    /// Construct a new `DebugMacinfo` instance from the data in the `.debug_macinfo`
+    /// section.
+    ///
+    /// It is the caller's responsibility to read the `.debug_macinfo` section and
+    /// present it as a `&[u8]` slice. That means using some ELF loader on
+    /// Linux, a Mach-O loader on macOS, etc.
+    ///
+    /// ```
+    /// use gimli::{DebugMacinfo, LittleEndian};
+    ///
+    /// # let buf = [];
+    /// # let read_debug_macinfo_section_somehow = || &buf;
+    /// let debug_macinfo = DebugMacinfo::new(read_debug_macinfo_section_somehow(), LittleEndian);
+    /// ```
+    pub fn new(debug_macinfo_section: &'input [u8], endian: Endian) -> Self {
+        Self::from(EndianSlice::new(debug_macinfo_section, endian))
+    }
+}
+
+impl<R: Reader> DebugMacinfo<R> {
+    /// Iterate over the macro information entries at the given offset.
+    ///
+    /// The offset is usually the value of the `DW_AT_macro_info` attribute
+    /// of a compilation unit.
+    pub fn get_macinfo(&self, offset: DebugMacinfoOffset<R::Offset>) -> Result<MacroIter<R>> {
+        let mut input = self.section.clone();
+        input.skip(offset.0)?;
+        Ok(MacroIter {
+            input,
+            offset: offset.0,
+            kind: MacroKind::Macinfo,
+            encoding: Encoding {
+                format: Format::Dwarf32,
+                version: 4,
+                address_size: 0,
+            },
+            line_program_offset: None,
+            opcodes: Vec::new(),
+        })
+    }
+}
+
+impl<T> DebugMacinfo<T> {
+    /// Create a `DebugMacinfo` section that references the data in `self`.
+    ///
+    /// This is useful when `R` implements `Reader` but `T` does not.
+    ///
+    /// Used by `DwarfSections::borrow`.
+    pub fn borrow<'a, F, R>(&'a self, mut borrow: F) -> DebugMacinfo<R>
+    where
+        F: FnMut(&'a T) -> R,
+    {
+        borrow(&self.section).into()
+    }
+}
+
+impl<R> Section<R> for DebugMacinfo<R> {
+    fn id() -> SectionId {
+        SectionId::DebugMacinfo
+    }
+
+    fn reader(&self) -> &R {
+        &self.section
+    }
+}
+
+impl<R> From<R> for DebugMacinfo<R> {
+    fn from(section: R) -> Self {
+        DebugMacinfo { section }
+    }
+}
+
+/// The `DebugMacro` struct represents the DWARF macro information
+/// found in the `.debug_macro` section.
+///
+/// This section is used by DWARF version 5, and by the GNU extension for
+/// DWARF version 4.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DebugMacro<R> {
+    section: R,
+}
+
+impl<'input, Endian> DebugMacro<EndianSlice<'input, Endian>>
+where
+    Endian: Endianity,
+{
+    /// Construct a new `DebugMacro` instance from the data in the `.debug_macro`
+    /// section.
+    ///
+    /// It is the caller's responsibility to read the `.debug_macro` section and
+    /// present it as a `&[u8]` slice. That means using some ELF loader on
+    /// Linux, a Mach-O loader on macOS, etc.
+    ///
+    /// ```
+    /// use gimli::{DebugMacro, LittleEndian};
+    ///
+    /// # let buf = [];
+    /// # let read_debug_macro_section_somehow = || &buf;
+    /// let debug_macro = DebugMacro::new(read_debug_macro_section_somehow(), LittleEndian);
+    /// ```
+    pub fn new(debug_macro_section: &'input [u8], endian: Endian) -> Self {
+        Self::from(EndianSlice::new(debug_macro_section, endian))
+    }
+}
+
+impl<R: Reader> DebugMacro<R> {
+    /// Parse the header of the macro unit at the given offset, and iterate
+    /// over its entries.
+    ///
+    /// The offset is usually the value of the `DW_AT_macros` attribute
+    /// of a compilation unit, or the offset of a `DW_MACRO_import` entry.
+    ///
+    /// The `address_size` is only used for skipping the operands of vendor
+    /// extension opcodes.
+    pub fn get_macros(
+        &self,
+        offset: DebugMacroOffset<R::Offset>,
+        address_size: u8,
+    ) -> Result<MacroIter<R>> {
+        let mut input = self.section.clone();
+        input.skip(offset.0)?;
+
+        let version = input.read_u16()?;
+        if version != 4 && version != 5 {
+            return Err(Error::UnknownVersion(u64::from(version)));
+        }
+        let flags = input.read_u8()?;
+        let format = if flags & 0x1 != 0 {
+            Format::Dwarf64
+        } else {
+            Format::Dwarf32
+        };
+        let line_program_offset = if flags & 0x2 != 0 {
+            Some(DebugLineOffset(input.read_offset(format)?))
+        } else {
+            None
+        };
+        let mut opcodes = Vec::new();
+        if flags & 0x4 != 0 {
+            let count = input.read_u8()?;
+            for _ in 0..count {
+                let opcode = constants::DwMacro(input.read_u8()?);
+                let form_count = input.read_uleb128()?;
+                let mut forms = Vec::new();
+                for _ in 0..form_count {
+                    let form = constants::DwForm(u16::from(input.read_u8()?));
+                    forms.push(AttributeSpecification::new(
+                        constants::DW_AT_null,
+                        form,
+                        None,
+                    ));
+                }
+                opcodes.push((opcode, forms));
+            }
+        }
+
+        Ok(MacroIter {
+            input,
+            offset: offset.0,
+            kind: MacroKind::Macro,
+            encoding: Encoding {
+                format,
+                version,
+                address_size,
+            },
+            line_program_offset,
+            opcodes,
+        })
+    }
+}
+
+impl<T> DebugMacro<T> {
+    /// Create a `DebugMacro` section that references the data in `self`.
+    ///
+    /// This is useful when `R` implements `Reader` but `T` does not.
+    ///
+    /// Used by `DwarfSections::borrow`.
+    pub fn borrow<'a, F, R>(&'a self, mut borrow: F) -> DebugMacro<R>
+    where
+        F: FnMut(&'a T) -> R,
+    {
+        borrow(&self.section).into()
+    }
+}
+
+impl<R> Section<R> for DebugMacro<R> {
+    fn id() -> SectionId {
+        SectionId::DebugMacro
+    }
+
+    fn reader(&self) -> &R {
+        &self.section
+    }
+}
+
+impl<R> From<R> for DebugMacro<R> {
+    fn from(section: R) -> Self {
+        DebugMacro { section }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MacroKind {
+    Macinfo,
+    Macro,
+}
+
+/// An iterator over the entries of a macro unit in the `.debug_macro` section,
+/// or a list of entries in the `.debug_macinfo` section.
+#[derive(Debug, Clone)]
+pub struct MacroIter<R: Reader> {
+    input: R,
+    offset: R::Offset,
+    kind: MacroKind,
+    encoding: Encoding,
+    line_program_offset: Option<DebugLineOffset<R::Offset>>,
+    opcodes: Vec<(constants::DwMacro, Vec<AttributeSpecification>)>,
+}
+
+impl<R: Reader> MacroIter<R> {
+    /// Return the offset of the line number program that `StartFile` entries
+    /// refer to, if given in the macro unit header.
+    ///
+    /// This is always `None` for `.debug_macinfo`, which implicitly uses the
+    /// line number program of the compilation unit.
+    pub fn line_program_offset(&self) -> Option<DebugLineOffset<R::Offset>> {
+        self.line_program_offset
+    }
+
+    /// Return the format of the offsets in the macro unit.
+    pub fn format(&self) -> Format {
+        self.encoding.format
+    }
+
+    /// Advance the iterator to the next entry.
+    pub fn next(&mut self) -> Result<Option<MacroEntry<R>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+
+        let entry = match self.kind {
+            MacroKind::Macinfo => self.parse_macinfo(),
+            MacroKind::Macro => self.parse_macro(),
+        };
+        match entry {
+            Ok(entry) => {
+                if entry.is_none() {
+                    self.input.empty();
+                }
+                Ok(entry)
+            }
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+
+    fn parse_macinfo(&mut self) -> Result<Option<MacroEntry<R>>> {
+        let input = &mut self.input;
+        let kind = constants::DwMacinfo(input.read_u8()?);
+        let entry = match kind {
+            constants::DwMacinfo(0) => return Ok(None),
+            constants::DW_MACINFO_define => MacroEntry::Define {
+                line: input.read_uleb128()?,
+                text: MacroString::Direct(input.read_null_terminated_slice()?),
+            },
+            constants::DW_MACINFO_undef => MacroEntry::Undef {
+                line: input.read_uleb128()?,
+                name: MacroString::Direct(input.read_null_terminated_slice()?),
+            },
+            constants::DW_MACINFO_start_file => MacroEntry::StartFile {
+                line: input.read_uleb128()?,
+                file: input.read_uleb128()?,
+            },
+            constants::DW_MACINFO_end_file => MacroEntry::EndFile,
+            constants::DW_MACINFO_vendor_ext => MacroEntry::VendorExt {
+                constant: input.read_uleb128()?,
+                string: input.read_null_terminated_slice()?,
+            },
+            _ => return Err(Error::UnknownMacinfoType(kind)),
+        };
+        Ok(Some(entry))
+    }
+
+    fn parse_macro(&mut self) -> Result<Option<MacroEntry<R>>> {
+        let format = self.encoding.format;
+        let input = &mut self.input;
+        let opcode = constants::DwMacro(input.read_u8()?);
+        let entry = match opcode {
+            constants::DwMacro(0) => return Ok(None),
+            constants::DW_MACRO_define => MacroEntry::Define {
+                line: input.read_uleb128()?,
+                text: MacroString::Direct(input.read_null_terminated_slice()?),
+            },
+            constants::DW_MACRO_undef => MacroEntry::Undef {
+                line: input.read_uleb128()?,
+                name: MacroString::Direct(input.read_null_terminated_slice()?),
+            },
+            constants::DW_MACRO_start_file => MacroEntry::StartFile {
+                line: input.read_uleb128()?,
+                file: input.read_uleb128()?,
+            },
+            constants::DW_MACRO_end_file => MacroEntry::EndFile,
+            constants::DW_MACRO_define_strp => MacroEntry::Define {
+                line: input.read_uleb128()?,
+                text: MacroString::StringPointer(DebugStrOffset(input.read_offset(format)?)),
+            },
+            constants::DW_MACRO_undef_strp => MacroEntry::Undef {
+                line: input.read_uleb128()?,
+                name: MacroString::StringPointer(DebugStrOffset(input.read_offset(format)?)),
+            },
+            constants::DW_MACRO_import => MacroEntry::Import {
+                offset: DebugMacroOffset(input.read_offset(format)?),
+            },
+            constants::DW_MACRO_define_sup => MacroEntry::Define {
+                line: input.read_uleb128()?,
+                text: MacroString::Supplementary(DebugStrOffset(input.read_offset(format)?)),
+            },
+            constants::DW_MACRO_undef_sup => MacroEntry::Undef {
+                line: input.read_uleb128()?,
+                name: MacroString::Supplementary(DebugStrOffset(input.read_offset(format)?)),
+            },
+            constants::DW_MACRO_import_sup => MacroEntry::ImportSup {
+                offset: DebugMacroOffset(input.read_offset(format)?),
+            },
+            constants::DW_MACRO_define_strx => MacroEntry::Define {
+                line: input.read_uleb128()?,
+                text: MacroString::IndirectStringPointer(DebugStrOffsetsIndex(
+                    input.read_uleb128().and_then(R::Offset::from_u64)?,
+                )),
+            },
+            constants::DW_MACRO_undef_strx => MacroEntry::Undef {
+                line: input.read_uleb128()?,
+                name: MacroString::IndirectStringPointer(DebugStrOffsetsIndex(
+                    input.read_uleb128().and_then(R::Offset::from_u64)?,
+                )),
+            },
+            _ => {
+                let specs = match self.opcodes.iter().find(|(x, _)| *x == opcode) {
+                    Some((_, specs)) => specs,
+                    None => return Err(Error::UnknownMacroOpcode(opcode)),
+                };
+                let mut rest = input.clone();
+                skip_attributes(&mut rest, self.encoding, specs)?;
+                let len = rest.offset_from(input);
+                MacroEntry::Vendor {
+                    opcode,
+                    operands: input.split(len)?,
+                }
+            }
+        };
+        Ok(Some(entry))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for MacroIter<R> {
+    type Item = MacroEntry<R>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        MacroIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] MacroIter<R>, MacroEntry<R>);
+
+/// An entry in a `.debug_macro` macro unit or a `.debug_macinfo` list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroEntry<R: Reader> {
+    /// A macro definition.
+    Define {
+        /// The source line of the definition, or 0 if it was given on the
+        /// command line.
+        line: u64,
+        /// The macro name, followed by the parameter list (if any), a space,
+        /// and the definition.
+        text: MacroString<R>,
+    },
+    /// A macro undefinition.
+    Undef {
+        /// The source line of the undefinition, or 0 if it was given on the
+        /// command line.
+        line: u64,
+        /// The name of the macro.
+        name: MacroString<R>,
+    },
+    /// The start of an included source file.
+    StartFile {
+        /// The source line of the include directive, or 0 for the primary
+        /// source file.
+        line: u64,
+        /// The index of the file in the line number program.
+        file: u64,
+    },
+    /// The end of the most recent `StartFile`.
+    EndFile,
+    /// The entries of another macro unit in the `.debug_macro` section.
+    Import {
+        /// The offset of the imported macro unit.
+        offset: DebugMacroOffset<R::Offset>,
+    },
+    /// The entries of another macro unit in the `.debug_macro` section of the
+    /// supplementary object file.
+    ImportSup {
+        /// The offset of the imported macro unit.
+        offset: DebugMacroOffset<R::Offset>,
+    },
+    /// A `DW_MACINFO_vendor_ext` entry in `.debug_macinfo`.
+    VendorExt {
+        /// A vendor defined constant.
+        constant: u64,
+        /// A vendor defined string.
+        string: R,
+    },
+    /// A vendor extension opcode in `.debug_macro`.
+    Vendor {
+        /// The opcode.
+        opcode: constants::DwMacro,
+        /// The raw bytes of the operands, as described by the opcode operands table.
+        operands: R,
+    },
+}
+
+/// The string operand of a `MacroEntry::Define` or `MacroEntry::Undef`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacroString<R: Reader> {
+    /// An inline string.
+    Direct(R),
+    /// An offset into the `.debug_str` section.
+    StringPointer(DebugStrOffset<R::Offset>),
+    /// An index into the `.debug_str_offsets` entries for the compilation unit.
+    IndirectStringPointer(DebugStrOffsetsIndex<R::Offset>),
+    /// An offset into the `.debug_str` section of the supplementary object file.
+    Supplementary(DebugStrOffset<R::Offset>),
+}
+
+impl<R: Reader> MacroString<R> {
+    /// Return the string, looking it up in the string sections if required.
+    pub fn string(&self, dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<R> {
+        match *self {
+            MacroString::Direct(ref string) => Ok(string.clone()),
+            MacroString::StringPointer(offset) => dwarf.string(offset),
+            MacroString::IndirectStringPointer(index) => {
+                let offset = dwarf.string_offset(unit, index)?;
+                dwarf.string(offset)
+            }
+            MacroString::Supplementary(offset) => dwarf.sup_string(offset),
+        }
+    }
+}
+
+/// A macro definition that is in scope at the end of a source file.
+///
+/// Returned by `Dwarf::resolve_macros`.
+#[derive(Debug, Clone)]
+pub struct MacroDefinition<R: Reader> {
+    /// The name of the macro.
+    pub name: R,
+    /// The macro name, followed by the parameter list (if any), a space,
+    /// and the definition.
+    pub text: R,
+    /// The index in the line number program of the file containing the
+    /// definition, or `None` if it was not within any file.
+    pub file: Option<u64>,
+    /// The source line of the definition, or 0 if it was given on the
+    /// command line.
+    pub line: u64,
+}
+
+/// The macros that are in scope for a source file.
+///
+/// Returned by `Dwarf::resolve_macros`.
+#[derive(Debug, Clone)]
+pub struct MacroFile<R: Reader> {
+    /// The index of the file in the line number program.
+    pub file: u64,
+    /// The entry for the file in the line number program, if any.
+    pub file_entry: Option<FileEntry<R>>,
+    /// The source line of the include directive, or 0 for the primary
+    /// source file.
+    pub line: u64,
+    /// The macros that are in scope at the end of the file, keyed by name.
+    ///
+    /// This includes macros that were defined before the file was included.
+    /// Files that end with the same macros in scope share the same map.
+    pub macros: Arc<BTreeMap<Vec<u8>, MacroDefinition<R>>>,
+}
+
+/// A macro operation that has been read while resolving macros.
+///
+/// The ops for each imported macro unit are only read once, and are then
+/// replayed for each import.
+#[derive(Debug)]
+enum MacroOp<R: Reader> {
+    Define { name: R, text: R, line: u64 },
+    Undef { name: R },
+    StartFile { line: u64, file: u64 },
+    EndFile,
+    Import(Arc<Vec<MacroOp<R>>>),
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Iterate over the macro information for the unit.
+    ///
+    /// This uses the `DW_AT_macros`, `DW_AT_GNU_macros`, or `DW_AT_macro_info`
+    /// attribute of the unit's root entry. Returns `None` if the unit has
+    /// none of these attributes.
+    pub fn macros(&self, unit: &Unit<R>) -> Result<Option<MacroIter<R>>> {
+        let mut entries = unit.entries();
+        let root = match entries.next_dfs()? {
+            Some((_, root)) => root,
+            None => return Ok(None),
+        };
+        if let Some(AttributeValue::DebugMacroRef(offset)) =
+            root.attr_value(constants::DW_AT_macros)?
+        {
+            return self
+                .debug_macro
+                .get_macros(offset, unit.encoding().address_size)
+                .map(Some);
+        }
+        if let Some(value) = root.attr_value(constants::DW_AT_GNU_macros)? {
+            let offset = match value {
+                AttributeValue::SecOffset(offset) => offset,
+                _ => match value.udata_value() {
+                    Some(offset) => R::Offset::from_u64(offset)?,
+                    None => return Err(Error::UnsupportedAttributeForm),
+                },
+            };
+            return self
+                .debug_macro
+                .get_macros(DebugMacroOffset(offset), unit.encoding().address_size)
+                .map(Some);
+        }
+        if let Some(AttributeValue::DebugMacinfoRef(offset)) =
+            root.attr_value(constants::DW_AT_macro_info)?
+        {
+            return self.debug_macinfo.get_macinfo(offset).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Determine the macros that are in scope for each source file of the unit.
+    ///
+    /// This follows `DW_MACRO_import` entries, ignoring any import of a macro
+    /// unit that is already being imported, and ignoring `DW_MACRO_import_sup`
+    /// entries if the supplementary object file is not loaded. Each imported
+    /// macro unit is only read once. It correlates `DW_MACRO_start_file`
+    /// entries with the files in the unit's line number program.
+    ///
+    /// The files are returned in the order that they were started.
+    pub fn resolve_macros(&self, unit: &Unit<R>) -> Result<Vec<MacroFile<R>>> {
+        let ops = match self.macros(unit)? {
+            Some(iter) => self.read_macro_ops(unit, iter)?,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut files: Vec<MacroFile<R>> = Vec::new();
+        // The indices in `files` of the files that have been started but not ended.
+        let mut file_stack = Vec::new();
+        let mut macros = Arc::new(BTreeMap::new());
+        let mut stack = vec![ops.iter()];
+        while let Some(iter) = stack.last_mut() {
+            let op = match iter.next() {
+                Some(op) => op,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            match op {
+                MacroOp::Define { name, text, line } => {
+                    let key = name.to_slice()?.into_owned();
+                    let definition = MacroDefinition {
+                        name: name.clone(),
+                        text: text.clone(),
+                        file: file_stack.last().map(|&index: &usize| files[index].file),
+                        line: *line,
+                    };
+                    Arc::make_mut(&mut macros).insert(key, definition);
+                }
+                MacroOp::Undef { name } => {
+                    let name = name.to_slice()?;
+                    if macros.contains_key(&*name) {
+                        Arc::make_mut(&mut macros).remove(&*name);
+                    }
+                }
+                MacroOp::StartFile { line, file } => {
+                    let file_entry = unit
+                        .line_program
+                        .as_ref()
+                        .and_then(|program| program.header().file(*file))
+                        .cloned();
+                    file_stack.push(files.len());
+                    files.push(MacroFile {
+                        file: *file,
+                        file_entry,
+                        line: *line,
+                        macros: Arc::default(),
+                    });
+                }
+                MacroOp::EndFile => {
+                    if let Some(index) = file_stack.pop() {
+                        files[index].macros = macros.clone();
+                    }
+                }
+                MacroOp::Import(ops) => stack.push(ops.iter()),
+            }
+        }
+        // Files that were not explicitly ended are in scope until the end of the unit.
+        for index in file_stack {
+            files[index].macros = macros.clone();
+        }
+        Ok(files)
+    }
+
+    /// Read the macro operations for a unit, following imports.
+    fn read_macro_ops(&self, unit: &Unit<R>, iter: MacroIter<R>) -> Result<Vec<MacroOp<R>>> {
+        struct Frame<'a, R: Reader> {
+            iter: MacroIter<R>,
+            dwarf: &'a Dwarf<R>,
+            // Whether `dwarf` is the supplementary object file.
+            sup: bool,
+            ops: Vec<MacroOp<R>>,
+        }
+
+        let address_size = unit.encoding().address_size;
+        // The ops of the macro units that have been read, keyed by whether
+        // they are in the supplementary object file, and their offset.
+        let mut imported = BTreeMap::new();
+        // The stack of macro units being read.
+        let mut stack = vec![Frame {
+            iter,
+            dwarf: self,
+            sup: false,
+            ops: Vec::new(),
+        }];
+        let is_active = |stack: &[Frame<'_, R>], offset, sup| {
+            stack.iter().any(|frame| {
+                frame.iter.kind == MacroKind::Macro
+                    && frame.iter.offset == offset
+                    && frame.sup == sup
+            })
+        };
+        loop {
+            let frame = stack.last_mut().unwrap();
+            let dwarf = frame.dwarf;
+            let sup = frame.sup;
+            let entry = match frame.iter.next()? {
+                Some(entry) => entry,
+                None => {
+                    let frame = stack.pop().unwrap();
+                    let parent = match stack.last_mut() {
+                        Some(parent) => parent,
+                        None => return Ok(frame.ops),
+                    };
+                    let ops = Arc::new(frame.ops);
+                    imported.insert((frame.sup, frame.iter.offset), ops.clone());
+                    parent.ops.push(MacroOp::Import(ops));
+                    continue;
+                }
+            };
+            let (dwarf, sup, offset) = match entry {
+                MacroEntry::Define { line, text } => {
+                    let text = text.string(dwarf, unit)?;
+                    let name = macro_name(&text)?;
+                    frame.ops.push(MacroOp::Define { name, text, line });
+                    continue;
+                }
+                MacroEntry::Undef { name, .. } => {
+                    let name = name.string(dwarf, unit)?;
+                    frame.ops.push(MacroOp::Undef { name });
+                    continue;
+                }
+                MacroEntry::StartFile { line, file } => {
+                    frame.ops.push(MacroOp::StartFile { line, file });
+                    continue;
+                }
+                MacroEntry::EndFile => {
+                    frame.ops.push(MacroOp::EndFile);
+                    continue;
+                }
+                MacroEntry::Import { offset } => (dwarf, sup, offset),
+                MacroEntry::ImportSup { offset } => match self.sup() {
+                    Some(sup) => (sup, true, offset),
+                    // Imports from a supplementary object file that is not
+                    // loaded are ignored.
+                    None => continue,
+                },
+                MacroEntry::VendorExt { .. } | MacroEntry::Vendor { .. } => continue,
+            };
+            if let Some(ops) = imported.get(&(sup, offset.0)) {
+                frame.ops.push(MacroOp::Import(Arc::clone(ops)));
+            } else if !is_active(&stack, offset.0, sup) {
+                let iter = dwarf.debug_macro.get_macros(offset, address_size)?;
+                stack.push(Frame {
+                    iter,
+                    dwarf,
+                    sup,
+                    ops: Vec::new(),
+                });
+            }
+        }
+    }
+}
+
+/// Return the name of a macro given the text of its definition.
+fn macro_name<R: Reader>(text: &R) -> Result<R> {
+    let mut input = text.clone();
+    let mut len = 0;
+    while !input.is_empty() {
+        let byte = input.read_u8()?;
+        if byte == b' ' || byte == b'(' {
+            break;
+        }
+        len += 1;
+    }
+    let mut name = text.clone();
+    name.truncate(R::Offset::from_u64(len)?)?;
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::GimliSectionMethods;
+    use crate::LittleEndian;
+    use test_assembler::{Endian, Section};
+
+    #[test]
+    fn test_get_macinfo() {
+        let section = Section::with_endian(Endian::Little)
+            .D8(0x05)
+            .D8(constants::DW_MACINFO_define.0)
+            .uleb(0)
+            .append_bytes(b"A 1\0")
+            .D8(constants::DW_MACINFO_start_file.0)
+            .uleb(0)
+            .uleb(1)
+            .D8(constants::DW_MACINFO_undef.0)
+            .uleb(3)
+            .append_bytes(b"A\0")
+            .D8(constants::DW_MACINFO_vendor_ext.0)
+            .uleb(7)
+            .append_bytes(b"x\0")
+            .D8(constants::DW_MACINFO_end_file.0)
+            .D8(0)
+            .D8(constants::DW_MACINFO_define.0);
+        let buf = section.get_contents().unwrap();
+        let debug_macinfo = DebugMacinfo::new(&buf, LittleEndian);
+        let mut iter = debug_macinfo.get_macinfo(DebugMacinfoOffset(1)).unwrap();
+        assert_eq!(iter.line_program_offset(), None);
+        let mut entries = Vec::new();
+        while let Some(entry) = iter.next().unwrap() {
+            entries.push(entry);
+        }
+        assert_eq!(
+            entries,
+            [
+                MacroEntry::Define {
+                    line: 0,
+                    text: MacroString::Direct(EndianSlice::new(b"A 1", LittleEndian)),
+                },
+                MacroEntry::StartFile { line: 0, file: 1 },
+                MacroEntry::Undef {
+                    line: 3,
+                    name: MacroString::Direct(EndianSlice::new(b"A", LittleEndian)),
+                },
+                MacroEntry::VendorExt {
+                    constant: 7,
+                    string: EndianSlice::new(b"x", LittleEndian),
+                },
+                MacroEntry::EndFile,
+            ]
+        );
+
+        let mut iter = debug_macinfo.get_macinfo(DebugMacinfoOffset(0)).unwrap();
+        assert_eq!(
+            iter.next(),
+            Err(Error::UnknownMacinfoType(constants::DwMacinfo(0x05)))
+        );
+        assert_eq!(iter.next(), Ok(None));
+    }
+
+    #[test]
+    fn test_get_macros() {
+        let section = Section::with_endian(Endian::Little)
+            // Header: version 5, 64-bit offsets, line offset, opcode table.
+            .D16(5)
+            .D8(0x7)
+            .D64(0x10)
+            .D8(1)
+            .D8(0xe0)
+            .uleb(2)
+            .D8(constants::DW_FORM_udata.0 as u8)
+            .D8(constants::DW_FORM_string.0 as u8)
+            .D8(constants::DW_MACRO_define_strp.0)
+            .uleb(1)
+            .D64(0x20)
+            .D8(0xe0)
+            .uleb(300)
+            .append_bytes(b"ab\0")
+            .D8(constants::DW_MACRO_undef_strx.0)
+            .uleb(2)
+            .uleb(3)
+            .D8(constants::DW_MACRO_import.0)
+            .D64(0x30)
+            .D8(0xe1)
+            .D8(0);
+        let buf = section.get_contents().unwrap();
+        let debug_macro = DebugMacro::new(&buf, LittleEndian);
+        let mut iter = debug_macro.get_macros(DebugMacroOffset(0), 8).unwrap();
+        assert_eq!(iter.format(), Format::Dwarf64);
+        assert_eq!(iter.line_program_offset(), Some(DebugLineOffset(0x10)));
+        assert_eq!(
+            iter.next(),
+            Ok(Some(MacroEntry::Define {
+                line: 1,
+                text: MacroString::StringPointer(DebugStrOffset(0x20)),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Ok(Some(MacroEntry::Vendor {
+                opcode: constants::DwMacro(0xe0),
+                operands: EndianSlice::new(&[0xac, 0x02, b'a', b'b', 0], LittleEndian),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Ok(Some(MacroEntry::Undef {
+                line: 2,
+                name: MacroString::IndirectStringPointer(DebugStrOffsetsIndex(3)),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Ok(Some(MacroEntry::Import {
+                offset: DebugMacroOffset(0x30),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Err(Error::UnknownMacroOpcode(constants::DwMacro(0xe1)))
+        );
+        assert_eq!(iter.next(), Ok(None));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_resolve_macros() {
+        use crate::common::LineEncoding;
+        use crate::write;
+
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = write::DwarfUnit::new(encoding);
+        let mut line_program = write::LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            write::LineString::String(b"dir".to_vec()),
+            write::LineString::String(b"main.c".to_vec()),
+            None,
+        );
+        let dir = line_program.default_directory();
+        let main_file =
+            line_program.add_file(write::LineString::String(b"main.c".to_vec()), dir, None);
+        let header_file =
+            line_program.add_file(write::LineString::String(b"header.h".to_vec()), dir, None);
+        dwarf.unit.line_program = line_program;
+        let string = dwarf.strings.add("B 2");
+
+        let main_id = dwarf.unit.macros.add(write::MacroUnit::new());
+        let imported_id = dwarf.unit.macros.add(write::MacroUnit::new());
+        let main = dwarf.unit.macros.get_mut(main_id);
+        let define = |line, text: &[u8]| write::Macro::Define {
+            line,
+            text: write::MacroString::String(text.to_vec()),
+        };
+        main.add(define(0, b"CMDLINE 1"));
+        main.add(write::Macro::StartFile {
+            line: 0,
+            file: main_file,
+        });
+        main.add(define(1, b"A 1"));
+        main.add(write::Macro::StartFile {
+            line: 2,
+            file: header_file,
+        });
+        main.add(define(1, b"F(x) x"));
+        main.add(write::Macro::Undef {
+            line: 2,
+            text: write::MacroString::String(b"A".to_vec()),
+        });
+        main.add(write::Macro::EndFile);
+        main.add(write::Macro::Import(imported_id));
+        main.add(define(4, b"A 4"));
+        // Repeated imports are read once.
+        main.add(write::Macro::Import(imported_id));
+        main.add(write::Macro::EndFile);
+        let imported = dwarf.unit.macros.get_mut(imported_id);
+        imported.add(write::Macro::Define {
+            line: 3,
+            text: write::MacroString::StringRef(string),
+        });
+        // Cycles are ignored.
+        imported.add(write::Macro::Import(imported_id));
+        imported.add(write::Macro::Import(main_id));
+
+        let mut sections = write::Sections::new(write::EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+
+        let files = read_dwarf.resolve_macros(&read_unit).unwrap();
+        let files = files
+            .iter()
+            .map(|file| {
+                let path = file
+                    .file_entry
+                    .as_ref()
+                    .map(|entry| read_dwarf.attr_string(&read_unit, entry.path_name()))
+                    .unwrap()
+                    .unwrap();
+                let macros = file
+                    .macros
+                    .values()
+                    .map(|x| (x.name.slice(), x.text.slice(), x.file, x.line))
+                    .collect::<Vec<_>>();
+                (path.slice(), file.line, macros)
+            })
+            .collect::<Vec<_>>();
+        let main_index = main_file.raw();
+        let header_index = header_file.raw();
+        assert_eq!(
+            files,
+            [
+                (
+                    &b"main.c"[..],
+                    0,
+                    vec![
+                        (&b"A"[..], &b"A 4"[..], Some(main_index), 4),
+                        (&b"B"[..], &b"B 2"[..], Some(main_index), 3),
+                        (&b"CMDLINE"[..], &b"CMDLINE 1"[..], None, 0),
+                        (&b"F"[..], &b"F(x) x"[..], Some(header_index), 1),
+                    ]
+                ),
+                (
+                    &b"header.h"[..],
+                    2,
+                    vec![
+                        (&b"CMDLINE"[..], &b"CMDLINE 1"[..], None, 0),
+                        (&b"F"[..], &b"F(x) x"[..], Some(header_index), 1),
+                    ]
+                ),
+            ]
+        );
+    }
+}
@@ -3,6 +3,7 @@
 //! * [Example Usage](#example-usage)
 //! * [API Structure](#api-structure)
 //! * [Using with `FallibleIterator`](#using-with-fallibleiterator)
+//! * [Using with `Iterator`](#using-with-iterator)
 //!
 //! ## Example Usage
 //!
@@ -172,6 +173,34 @@
 //! # }
 //! # fn main() {}
 //! ```
+//!
+//! ## Using with `Iterator`
+//!
+//! If you would rather not depend on the `fallible-iterator` crate, each of
+//! `gimli`'s fallible iterators has an `into_result_iter` method that converts
+//! it into a [`ResultIterator`](./struct.ResultIterator.html). This implements
+//! the standard library's `Iterator` trait with an `Item` type of `Result<T>`,
+//! so it can be used in `for` loops:
+//!
+//! ```
+//! use gimli::{DebugAranges, EndianSlice, LittleEndian};
+//!
+//! fn find_sum_of_address_range_lengths(aranges: DebugAranges<EndianSlice<LittleEndian>>)
+//!     -> gimli::Result<u64>
+//! {
+//!     let mut sum = 0;
+//!     for header in aranges.headers().into_result_iter() {
+//!         for arange in header?.entries().into_result_iter() {
+//!             sum += arange?.length();
+//!         }
+//!     }
+//!     Ok(sum)
+//! }
+//! ```
+//!
+//! `EntriesCursor` and `LineRows` return references from their `next_dfs` and
+//! `next_row` methods. Their `FallibleIterator` and `Iterator` implementations
+//! yield copies of the entries and rows instead.
 
 use core::fmt::{self, Debug};
 use core::result;
@@ -181,20 +210,75 @@ use std::{error, io};
 use crate::common::{Register, SectionId};
 use crate::constants;
 
+/// Implement `into_result_iter` and `ResultIterator` for one of the fallible iterators.
+///
+/// By default, the iterator's inherent `next` method is used to parse the next
+/// item.
+macro_rules! result_iterator {
+    ([$($generics:tt)*] $iter:ty, $item:ty, |$it:ident| $next:expr $(, where $($bounds:tt)*)?) => {
+        impl<$($generics)*> $iter $(where $($bounds)*)? {
+            /// Convert this into a standard `Iterator` which yields a `Result`
+            /// for each item.
+            ///
+            /// Iteration stops after the first error.
+            pub fn into_result_iter(self) -> $crate::read::ResultIterator<Self> {
+                $crate::read::ResultIterator::new(self)
+            }
+        }
+
+        impl<$($generics)*> Iterator for $crate::read::ResultIterator<$iter> $(where $($bounds)*)? {
+            type Item = $crate::read::Result<$item>;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next_result(|$it| $next)
+            }
+        }
+    };
+    ([$($generics:tt)*] $iter:ty, $item:ty $(, where $($bounds:tt)*)?) => {
+        result_iterator!([$($generics)*] $iter, $item, |iter| <$iter>::next(iter) $(, where $($bounds)*)?);
+    };
+}
+
 mod util;
 pub use util::*;
 
 mod addr;
 pub use self::addr::*;
 
+#[cfg(feature = "read")]
+mod apple;
+#[cfg(feature = "read")]
+pub use self::apple::*;
+
+#[cfg(feature = "read")]
+mod call_site;
+#[cfg(feature = "read")]
+pub use self::call_site::*;
+
 mod cfi;
 pub use self::cfi::*;
 
+#[cfg(feature = "read")]
+mod diff;
+#[cfg(feature = "read")]
+pub use self::diff::*;
+
+#[cfg(feature = "read")]
+mod dump;
+#[cfg(feature = "read")]
+pub use self::dump::*;
+
 #[cfg(feature = "read")]
 mod dwarf;
 #[cfg(feature = "read")]
 pub use self::dwarf::*;
 
+#[cfg(feature = "read")]
+mod dwarf_files;
+#[cfg(feature = "read")]
+pub use self::dwarf_files::*;
+
 mod endian_slice;
 pub use self::endian_slice::*;
 
@@ -203,6 +287,9 @@ mod endian_reader;
 #[cfg(feature = "endian-reader")]
 pub use self::endian_reader::*;
 
+#[cfg(feature = "read")]
+mod frame_base;
+
 mod reader;
 pub use self::reader::*;
 
@@ -214,28 +301,71 @@ mod abbrev;
 #[cfg(feature = "read")]
 pub use self::abbrev::*;
 
+#[cfg(feature = "read")]
+mod address_map;
+#[cfg(feature = "read")]
+pub use self::address_map::*;
+
 mod aranges;
 pub use self::aranges::*;
 
 mod index;
 pub use self::index::*;
 
+#[cfg(feature = "read")]
+mod gnu_pubnames;
+#[cfg(feature = "read")]
+pub use self::gnu_pubnames::*;
+
+#[cfg(feature = "read")]
+mod gdb_index;
+#[cfg(feature = "read")]
+pub use self::gdb_index::*;
+
 #[cfg(feature = "read")]
 mod line;
 #[cfg(feature = "read")]
 pub use self::line::*;
 
+#[cfg(feature = "read")]
+mod link;
+#[cfg(feature = "read")]
+pub use self::link::*;
+
 mod lists;
 
+#[cfg(feature = "read")]
+mod macros;
+#[cfg(feature = "read")]
+pub use self::macros::*;
+
 mod loclists;
 pub use self::loclists::*;
 
+#[cfg(feature = "json")]
+mod json;
+
 #[cfg(feature = "read")]
 mod lookup;
 
 mod op;
 pub use self::op::*;
 
+#[cfg(all(feature = "endian-reader", feature = "std"))]
+mod owned;
+#[cfg(all(feature = "endian-reader", feature = "std"))]
+pub use self::owned::*;
+
+#[cfg(feature = "read")]
+mod name_index;
+#[cfg(feature = "read")]
+pub use self::name_index::*;
+
+#[cfg(feature = "read")]
+mod names;
+#[cfg(feature = "read")]
+pub use self::names::*;
+
 #[cfg(feature = "read")]
 mod pubnames;
 #[cfg(feature = "read")]
@@ -249,11 +379,27 @@ pub use self::pubtypes::*;
 mod rnglists;
 pub use self::rnglists::*;
 
+#[cfg(feature = "read")]
+mod scopes;
+#[cfg(feature = "read")]
+pub use self::scopes::*;
+
+#[cfg(feature = "read")]
+mod stats;
+#[cfg(feature = "read")]
+pub use self::stats::*;
+
+#[cfg(feature = "read")]
+mod types;
+#[cfg(feature = "read")]
+pub use self::types::*;
+
 mod str;
 pub use self::str::*;
 
 /// An offset into the current compilation or type unit.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitOffset<T = usize>(pub T);
 
 #[cfg(feature = "read")]
@@ -261,6 +407,11 @@ mod unit;
 #[cfg(feature = "read")]
 pub use self::unit::*;
 
+#[cfg(feature = "read")]
+mod unit_cache;
+#[cfg(feature = "read")]
+pub use self::unit_cache::*;
+
 mod value;
 pub use self::value::*;
 
@@ -320,6 +471,14 @@ pub enum Error {
     UnknownAbbreviation,
     /// Hit the end of input before it was expected.
     UnexpectedEof(ReaderOffsetId),
+    /// The section ended before the end of a record that began at the given
+    /// offset.
+    ///
+    /// This is returned by the iterators over the top level records of a
+    /// section, such as units or CFI entries, when the section has been
+    /// truncated. All records before this one were parsed successfully, so
+    /// callers may treat them as a partial result.
+    TruncatedSection(ReaderOffsetId),
     /// Read a null entry before it was expected.
     UnexpectedNull,
     /// Found an unknown standard opcode.
@@ -356,6 +515,16 @@ pub enum Error {
     NotEnoughStackItems,
     /// Too many iterations to compute the expression.
     TooManyIterations,
+    /// Too many nested `DW_OP_call*` operations to evaluate DWARF expression.
+    CallDepthExceeded,
+    /// A DWARF expression read memory outside of the allowed ranges.
+    MemoryAccessDenied(u64),
+    /// The `DW_AT_frame_base` expression requires data that is not available.
+    UnsupportedFrameBase,
+    /// Found an unknown `DW_DSC_*` value in a `DW_AT_discr_list` attribute.
+    UnknownDiscriminantListEntry(constants::DwDsc),
+    /// The size of the storage unit containing a bit field is unknown.
+    MissingBitFieldStorageSize,
     /// An unrecognized operation was found while parsing a DWARF
     /// expression.
     InvalidExpression(constants::DwOp),
@@ -438,6 +607,19 @@ pub enum Error {
     InvalidIndexRow,
     /// Unknown section type in `.dwp` index.
     UnknownIndexSection,
+    /// Invalid area offsets in `.gdb_index` header.
+    InvalidGdbIndexHeader,
+    /// Invalid symbol table size in `.gdb_index`.
+    InvalidGdbIndexSymbolTable,
+    /// Invalid magic number or hash function in Apple accelerator table header.
+    InvalidAppleAccelTableHeader,
+    /// Found an unknown opcode in `.debug_macro` that is not described by the
+    /// opcode operands table.
+    UnknownMacroOpcode(constants::DwMacro),
+    /// Found an unknown entry type in `.debug_macinfo`.
+    UnknownMacinfoType(constants::DwMacinfo),
+    /// Found a reference to the supplementary object file, but it is not loaded.
+    MissingSupplementaryFile,
 }
 
 impl fmt::Display for Error {
@@ -492,6 +674,7 @@ impl Error {
             Error::UnknownVersion(_) => "Found an unknown DWARF version",
             Error::UnknownAbbreviation => "Found a record with an unknown abbreviation code",
             Error::UnexpectedEof(_) => "Hit the end of input before it was expected",
+            Error::TruncatedSection(_) => "The section ended before the end of a record",
             Error::UnexpectedNull => "Read a null entry before it was expected.",
             Error::UnknownStandardOpcode(_) => "Found an unknown standard opcode",
             Error::UnknownExtendedOpcode(_) => "Found an unknown extended opcode",
@@ -518,6 +701,21 @@ impl Error {
             }
             Error::NotEnoughStackItems => "Not enough items on stack when evaluating expression",
             Error::TooManyIterations => "Too many iterations to evaluate DWARF expression",
+            Error::MissingBitFieldStorageSize => {
+                "The size of the storage unit containing a bit field is unknown"
+            }
+            Error::UnknownDiscriminantListEntry(_) => {
+                "Found an unknown `DW_DSC_*` value in a `DW_AT_discr_list` attribute"
+            }
+            Error::UnsupportedFrameBase => {
+                "The `DW_AT_frame_base` expression requires data that is not available"
+            }
+            Error::CallDepthExceeded => {
+                "Too many nested `DW_OP_call*` operations to evaluate DWARF expression"
+            }
+            Error::MemoryAccessDenied(_) => {
+                "DWARF expression read memory outside of the allowed ranges"
+            }
             Error::InvalidExpression(_) => "Invalid opcode in DWARF expression",
             Error::UnsupportedEvaluation => "Unsupported operation when evaluating expression",
             Error::InvalidPiece => {
@@ -587,6 +785,16 @@ impl Error {
             Error::InvalidIndexSlotCount => "Invalid slot count in `.dwp` index.",
             Error::InvalidIndexRow => "Invalid hash row in `.dwp` index.",
             Error::UnknownIndexSection => "Unknown section type in `.dwp` index.",
+            Error::InvalidGdbIndexHeader => "Invalid area offsets in `.gdb_index` header.",
+            Error::InvalidGdbIndexSymbolTable => "Invalid symbol table size in `.gdb_index`.",
+            Error::InvalidAppleAccelTableHeader => {
+                "Invalid magic number or hash function in Apple accelerator table header."
+            }
+            Error::UnknownMacroOpcode(_) => "Found an unknown `.debug_macro` opcode.",
+            Error::UnknownMacinfoType(_) => "Found an unknown `.debug_macinfo` entry type.",
+            Error::MissingSupplementaryFile => {
+                "Found a reference to the supplementary object file, but it is not loaded."
+            }
         }
     }
 }
@@ -604,6 +812,66 @@ impl From<io::Error> for Error {
 /// The result of a parse.
 pub type Result<T> = result::Result<T, Error>;
 
+/// Convert an `UnexpectedEof` error to `TruncatedSection` if the record at the
+/// start of `input` extends past the end of `input`.
+///
+/// `input` must be the input before parsing the record, and the record must
+/// begin with an initial length.
+fn truncated_section_error<R: Reader>(input: &R, err: Error) -> Error {
+    if !matches!(err, Error::UnexpectedEof(_)) {
+        return err;
+    }
+    let mut rest = input.clone();
+    match rest.read_initial_length() {
+        Ok((length, _)) if length <= rest.len() => err,
+        Ok(_) | Err(Error::UnexpectedEof(_)) => Error::TruncatedSection(input.offset_id()),
+        Err(_) => err,
+    }
+}
+
+/// An adapter for using one of the fallible iterators in this crate as a
+/// standard `Iterator`.
+///
+/// This is returned by the `into_result_iter` method of the fallible iterators.
+/// Each item is a `Result`, so the adapter can be used in `for` loops and with
+/// the standard iterator combinators. Iteration stops after the first error.
+#[derive(Debug, Clone)]
+pub struct ResultIterator<I> {
+    iter: I,
+    done: bool,
+}
+
+impl<I> ResultIterator<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        ResultIterator { iter, done: false }
+    }
+
+    /// Return a reference to the underlying fallible iterator.
+    pub fn get_ref(&self) -> &I {
+        &self.iter
+    }
+
+    /// Return the underlying fallible iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+
+    #[inline]
+    fn next_result<T>(
+        &mut self,
+        next: impl FnOnce(&mut I) -> Result<Option<T>>,
+    ) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        let result = next(&mut self.iter).transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
 /// A convenience trait for loading DWARF sections from object files.  To be
 /// used like:
 ///
@@ -637,7 +905,21 @@ pub trait Section<R>: From<R> {
         Self::id().xcoff_name()
     }
 
+    /// Returns the Mach-O section name (if any) for this type.
+    fn macho_section_name() -> Option<&'static str> {
+        Self::id().macho_name()
+    }
+
+    /// Returns the compressed ELF section name (if any) for this type.
+    fn zdebug_section_name() -> Option<&'static str> {
+        Self::id().zdebug_name()
+    }
+
     /// Try to load the section using the given loader function.
+    ///
+    /// The loader is given the `SectionId` of the section, and can use methods
+    /// such as `SectionId::name` or `SectionId::macho_name` to find the section
+    /// data in the object file.
     fn load<F, E>(f: F) -> core::result::Result<Self, E>
     where
         F: FnOnce(SectionId) -> core::result::Result<R, E>,
@@ -831,4 +1113,24 @@ mod tests {
             otherwise => panic!("Unexpected result: {:?}", otherwise),
         };
     }
+
+    #[test]
+    fn test_result_iterator() {
+        let encoding = crate::Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        // A truncated `DW_OP_const2u` follows the `DW_OP_lit1`.
+        let buf = [constants::DW_OP_lit1.0, constants::DW_OP_const2u.0, 0x01];
+        let expression = Expression(EndianSlice::new(&buf, LittleEndian));
+        let mut iter = expression.operations(encoding).into_result_iter();
+        assert_eq!(
+            iter.next(),
+            Some(Ok(Operation::UnsignedConstant { value: 1 }))
+        );
+        assert!(matches!(iter.next(), Some(Err(Error::UnexpectedEof(_)))));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
 }
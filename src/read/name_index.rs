@@ -0,0 +1,389 @@
+use alloc::vec::Vec;
+
+use crate::common::{DebugInfoOffset, UnitSectionOffset};
+use crate::constants;
+use crate::read::{
+    AppleAccelTable, AttributeValue, DebugNames, DebugNamesUnit, DebugStr, Dwarf, Error,
+    GdbIndexTable, GdbIndexUnit, PubNameMap, Reader, ReaderOffset, Result, Unit, UnitHeader,
+    UnitOffset,
+};
+
+/// An entry found by looking up a name in a [`NameIndex`].
+///
+/// Fields are `None` if the index does not record the corresponding value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NameIndexEntry<T = usize> {
+    /// The offset of the unit containing the name.
+    pub unit: Option<UnitSectionOffset<T>>,
+    /// The offset of the debugging information entry for the name.
+    ///
+    /// This is relative to the start of the section containing the unit,
+    /// not the start of the unit.
+    pub die: Option<UnitSectionOffset<T>>,
+    /// The tag of the debugging information entry.
+    pub tag: Option<constants::DwTag>,
+    /// Whether the name is static, as opposed to global.
+    pub is_static: Option<bool>,
+}
+
+/// An index that maps names to the debugging information entries that
+/// define them.
+///
+/// This is implemented for each of the accelerator tables that a compiler or
+/// linker may generate (`.debug_pubnames` and `.debug_pubtypes` via
+/// [`PubNameMap`], [`DebugNamesIndex`], [`AppleNameIndex`], and
+/// [`GdbIndexTable`]), and for [`DieNameIndex`], which scans the units when
+/// no accelerator table is available. The tables differ in the information
+/// they record, so callers should be prepared for any field of
+/// [`NameIndexEntry`] to be `None`.
+pub trait NameIndex {
+    /// The type used for offsets in the entries.
+    type Offset: ReaderOffset;
+
+    /// Find the entries for the given name.
+    ///
+    /// Returns an empty vector if the name is not found.
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<Self::Offset>>>;
+}
+
+impl<T: ReaderOffset> NameIndex for PubNameMap<T> {
+    type Offset = T;
+
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<T>>> {
+        self.find(name)
+            .iter()
+            .map(|&(unit, die)| {
+                Ok(NameIndexEntry {
+                    unit: Some(unit.into()),
+                    die: Some(die_offset(unit, die)?.into()),
+                    tag: None,
+                    is_static: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Convert a unit-relative DIE offset to a `.debug_info` offset.
+fn die_offset<T: ReaderOffset>(
+    unit: DebugInfoOffset<T>,
+    die: UnitOffset<T>,
+) -> Result<DebugInfoOffset<T>> {
+    unit.0
+        .into_u64()
+        .checked_add(die.0.into_u64())
+        .and_then(|offset| T::from_u64(offset).ok())
+        .map(DebugInfoOffset)
+        .ok_or(Error::OffsetOutOfBounds)
+}
+
+/// A [`NameIndex`] for an Apple accelerator table.
+///
+/// The table only stores string offsets, so looking up a name also requires
+/// the `.debug_str` section.
+#[derive(Debug, Clone)]
+pub struct AppleNameIndex<R: Reader> {
+    table: AppleAccelTable<R>,
+    debug_str: DebugStr<R>,
+}
+
+impl<R: Reader> AppleNameIndex<R> {
+    /// Construct a new `AppleNameIndex` from a parsed table and the
+    /// `.debug_str` section that its names refer to.
+    pub fn new(table: AppleAccelTable<R>, debug_str: DebugStr<R>) -> Self {
+        AppleNameIndex { table, debug_str }
+    }
+
+    /// Return the underlying accelerator table.
+    pub fn table(&self) -> &AppleAccelTable<R> {
+        &self.table
+    }
+}
+
+impl<R: Reader> NameIndex for AppleNameIndex<R> {
+    type Offset = R::Offset;
+
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<R::Offset>>> {
+        let mut result = Vec::new();
+        let mut entries = self.table.lookup(name, &self.debug_str)?;
+        while let Some(entry) = entries.next()? {
+            result.push(NameIndexEntry {
+                unit: entry.cu_offset.map(Into::into),
+                die: entry.die_offset.map(Into::into),
+                tag: entry.tag,
+                is_static: None,
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// A [`NameIndex`] for the name tables in a `.debug_names` section.
+///
+/// The name tables only store string offsets, so looking up a name also
+/// requires the `.debug_str` section.
+#[derive(Debug, Clone)]
+pub struct DebugNamesIndex<R: Reader> {
+    debug_names: DebugNames<R>,
+    debug_str: DebugStr<R>,
+}
+
+impl<R: Reader> DebugNamesIndex<R> {
+    /// Construct a new `DebugNamesIndex` from a `.debug_names` section and the
+    /// `.debug_str` section that its names refer to.
+    pub fn new(debug_names: DebugNames<R>, debug_str: DebugStr<R>) -> Self {
+        DebugNamesIndex {
+            debug_names,
+            debug_str,
+        }
+    }
+}
+
+impl<R: Reader> NameIndex for DebugNamesIndex<R> {
+    type Offset = R::Offset;
+
+    /// Find the entries for the given name in every name table.
+    ///
+    /// Entries in foreign type units have a `unit` of `None`, because the
+    /// unit can only be found using its signature.
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<R::Offset>>> {
+        let mut result = Vec::new();
+        let mut tables = self.debug_names.tables();
+        while let Some(table) = tables.next()? {
+            let mut entries = table.find(name, &self.debug_str)?;
+            while let Some(entry) = entries.next()? {
+                let unit = match table.entry_unit(&entry)? {
+                    Some(DebugNamesUnit::Compilation(offset))
+                    | Some(DebugNamesUnit::LocalType(offset)) => Some(offset),
+                    Some(DebugNamesUnit::ForeignType(_)) | None => None,
+                };
+                let die = match (unit, entry.die_offset) {
+                    (Some(unit), Some(die)) => Some(die_offset(unit, die)?.into()),
+                    _ => None,
+                };
+                result.push(NameIndexEntry {
+                    unit: unit.map(Into::into),
+                    die,
+                    tag: Some(entry.tag),
+                    is_static: None,
+                });
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl<R: Reader> NameIndex for GdbIndexTable<R> {
+    type Offset = R::Offset;
+
+    /// Find the units that define the given name.
+    ///
+    /// A `.gdb_index` section does not record the debugging information
+    /// entries for a name, so `die` and `tag` are always `None`.
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<R::Offset>>> {
+        let mut result = Vec::new();
+        let mut units = match self.find(name)? {
+            Some(units) => units,
+            None => return Ok(result),
+        };
+        while let Some(entry) = units.next()? {
+            let unit = match self.unit(entry.unit_index)? {
+                GdbIndexUnit::Compilation(unit) => unit.offset.into(),
+                GdbIndexUnit::Type(unit) => unit.offset.into(),
+            };
+            result.push(NameIndexEntry {
+                unit: Some(unit),
+                die: None,
+                tag: None,
+                is_static: Some(entry.is_static),
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// A [`NameIndex`] that scans the debugging information entries of every unit.
+///
+/// This matches names against the `DW_AT_name` attribute of each entry. It is
+/// much slower than an accelerator table, and is intended as a fallback for
+/// object files that do not contain one.
+#[derive(Debug)]
+pub struct DieNameIndex<'a, R: Reader> {
+    dwarf: &'a Dwarf<R>,
+}
+
+impl<'a, R: Reader> DieNameIndex<'a, R> {
+    /// Construct a new `DieNameIndex` for the units in `dwarf`.
+    pub fn new(dwarf: &'a Dwarf<R>) -> Self {
+        DieNameIndex { dwarf }
+    }
+
+    fn find_in_unit(
+        &self,
+        header: UnitHeader<R>,
+        name: &[u8],
+        result: &mut Vec<NameIndexEntry<R::Offset>>,
+    ) -> Result<()> {
+        let unit = Unit::new(self.dwarf, header)?;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            let value = match entry.attr_value(constants::DW_AT_name)? {
+                Some(value) => value,
+                None => continue,
+            };
+            if self.dwarf.attr_string(&unit, value)?.to_slice()? != name {
+                continue;
+            }
+            let is_static = match entry.tag() {
+                constants::DW_TAG_subprogram | constants::DW_TAG_variable => Some(!matches!(
+                    entry.attr_value(constants::DW_AT_external)?,
+                    Some(AttributeValue::Flag(true))
+                )),
+                _ => None,
+            };
+            result.push(NameIndexEntry {
+                unit: Some(unit.header.offset()),
+                die: Some(entry.offset().to_unit_section_offset(&unit)),
+                tag: Some(entry.tag()),
+                is_static,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, R: Reader> NameIndex for DieNameIndex<'a, R> {
+    type Offset = R::Offset;
+
+    fn find_name(&self, name: &[u8]) -> Result<Vec<NameIndexEntry<R::Offset>>> {
+        let mut result = Vec::new();
+        let mut units = self.dwarf.units();
+        while let Some(header) = units.next()? {
+            self.find_in_unit(header, name, &mut result)?;
+        }
+        let mut units = self.dwarf.type_units();
+        while let Some(header) = units.next()? {
+            self.find_in_unit(header, name, &mut result)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::DebugInfoOffset;
+    use crate::endianity::LittleEndian;
+
+    #[test]
+    fn test_pub_name_map() {
+        let mut map = PubNameMap::<usize>::default();
+        assert_eq!(map.find_name(b"main").unwrap(), Vec::new());
+        assert_eq!(map.allocated_bytes(), 0);
+        // Add a single name using the same format as a parsed section.
+        let buf = [
+            // unit_length
+            0x17, 0, 0, 0, //
+            // version
+            2, 0, //
+            // debug_info_offset
+            0x10, 0, 0, 0, //
+            // debug_info_length
+            0x40, 0, 0, 0, //
+            // offset, name
+            0x2a, 0, 0, 0, b'm', b'a', b'i', b'n', 0, //
+            // terminator
+            0, 0, 0, 0,
+        ];
+        let section = crate::read::DebugPubNames::new(&buf, LittleEndian);
+        map.add_pubnames(&section).unwrap();
+        assert!(map.allocated_bytes() >= 4);
+        assert_eq!(
+            map.find_name(b"main").unwrap(),
+            vec![NameIndexEntry {
+                unit: Some(DebugInfoOffset(0x10).into()),
+                die: Some(DebugInfoOffset(0x3a).into()),
+                tag: None,
+                is_static: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_die_offset_overflow() {
+        assert_eq!(
+            die_offset(DebugInfoOffset(0x10u32), UnitOffset(0x2a)),
+            Ok(DebugInfoOffset(0x3a))
+        );
+        assert_eq!(
+            die_offset(DebugInfoOffset(u32::MAX), UnitOffset(1)),
+            Err(Error::OffsetOutOfBounds)
+        );
+        assert_eq!(
+            die_offset(DebugInfoOffset(u64::MAX), UnitOffset(1)),
+            Err(Error::OffsetOutOfBounds)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_die_name_index() {
+        use crate::common::Encoding;
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let f = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(f).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"f".to_vec()),
+        );
+        unit.get_mut(f)
+            .set(constants::DW_AT_external, write::AttributeValue::Flag(true));
+        let g = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(g).set(
+            constants::DW_AT_name,
+            write::AttributeValue::StringRef(dwarf.strings.add("g")),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let dwarf = sections.read_dwarf();
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+        let mut entries = unit.entries();
+        entries.next_dfs().unwrap();
+        let f_offset = entries.next_dfs().unwrap().unwrap().1.offset();
+        let g_offset = entries.next_dfs().unwrap().unwrap().1.offset();
+
+        let index = DieNameIndex::new(&dwarf);
+        assert_eq!(
+            index.find_name(b"f").unwrap(),
+            vec![NameIndexEntry {
+                unit: Some(DebugInfoOffset(0).into()),
+                die: Some(f_offset.to_unit_section_offset(&unit)),
+                tag: Some(constants::DW_TAG_subprogram),
+                is_static: Some(false),
+            }]
+        );
+        assert_eq!(
+            index.find_name(b"g").unwrap(),
+            vec![NameIndexEntry {
+                unit: Some(DebugInfoOffset(0).into()),
+                die: Some(g_offset.to_unit_section_offset(&unit)),
+                tag: Some(constants::DW_TAG_variable),
+                is_static: Some(true),
+            }]
+        );
+        assert_eq!(index.find_name(b"h").unwrap(), Vec::new());
+    }
+}
@@ -0,0 +1,920 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::common::{
+    debug_names_hash, DebugInfoOffset, DebugStrOffset, DebugTypeSignature, Format, SectionId,
+};
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::{
+    DebugStr, EndianSlice, Error, Reader, ReaderOffset, Result, Section, UnitOffset,
+};
+
+/// The data in the `.debug_names` section.
+///
+/// This section contains one or more name tables. Each name table is an
+/// accelerator table that maps names to the debugging information entries
+/// for one or more units.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DebugNames<R> {
+    section: R,
+}
+
+impl<'input, Endian> DebugNames<EndianSlice<'input, Endian>>
+where
+    Endian: Endianity,
+{
+    /// Construct a new `DebugNames` instance from the data in the `.debug_names`
+    /// section.
+    ///
+    /// ```
+    /// use gimli::{DebugNames, LittleEndian};
+    ///
+    /// # let buf = [];
+    /// # let read_debug_names_section = || &buf;
+    /// let debug_names = DebugNames::new(read_debug_names_section(), LittleEndian);
+    /// ```
+    pub fn new(section: &'input [u8], endian: Endian) -> Self {
+        Self::from(EndianSlice::new(section, endian))
+    }
+}
+
+impl<T> DebugNames<T> {
+    /// Create a `DebugNames` section that references the data in `self`.
+    ///
+    /// This is useful when `R` implements `Reader` but `T` does not.
+    pub fn borrow<'a, F, R>(&'a self, mut borrow: F) -> DebugNames<R>
+    where
+        F: FnMut(&'a T) -> R,
+    {
+        borrow(&self.section).into()
+    }
+}
+
+impl<R> Section<R> for DebugNames<R> {
+    fn id() -> SectionId {
+        SectionId::DebugNames
+    }
+
+    fn reader(&self) -> &R {
+        &self.section
+    }
+}
+
+impl<R> From<R> for DebugNames<R> {
+    fn from(section: R) -> Self {
+        DebugNames { section }
+    }
+}
+
+impl<R: Reader> DebugNames<R> {
+    /// Iterate over the name tables in the section.
+    ///
+    /// Can be [used with
+    /// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+    pub fn tables(&self) -> DebugNamesTableIter<R> {
+        DebugNamesTableIter {
+            input: self.section.clone(),
+        }
+    }
+}
+
+/// An iterator over the name tables in a `.debug_names` section.
+#[derive(Debug, Clone)]
+pub struct DebugNamesTableIter<R: Reader> {
+    input: R,
+}
+
+impl<R: Reader> DebugNamesTableIter<R> {
+    /// Advance the iterator to the next name table.
+    pub fn next(&mut self) -> Result<Option<DebugNamesTable<R>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        match DebugNamesTable::parse(&mut self.input) {
+            Ok(table) => Ok(Some(table)),
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for DebugNamesTableIter<R> {
+    type Item = DebugNamesTable<R>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        DebugNamesTableIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] DebugNamesTableIter<R>, DebugNamesTable<R>);
+
+/// An offset into the entry pool of a `.debug_names` name table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DebugNamesEntryOffset<T = usize>(pub T);
+
+/// An abbreviation in a `.debug_names` name table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugNamesAbbreviation {
+    /// The abbreviation code.
+    pub code: u64,
+    /// The tag of the entries that use this abbreviation.
+    pub tag: constants::DwTag,
+    /// The index attributes of the entries that use this abbreviation.
+    pub attributes: Vec<DebugNamesAttributeSpec>,
+}
+
+/// The description of an index attribute in a `.debug_names` abbreviation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugNamesAttributeSpec {
+    /// The kind of the attribute.
+    pub name: constants::DwIdx,
+    /// The form used to encode the attribute.
+    pub form: constants::DwForm,
+}
+
+/// A parsed name table from a `.debug_names` section.
+#[derive(Debug, Clone)]
+pub struct DebugNamesTable<R: Reader> {
+    format: Format,
+    version: u16,
+    comp_unit_count: u32,
+    local_type_unit_count: u32,
+    foreign_type_unit_count: u32,
+    bucket_count: u32,
+    name_count: u32,
+    augmentation_string: R,
+    comp_units: R,
+    local_type_units: R,
+    foreign_type_units: R,
+    buckets: R,
+    hashes: R,
+    string_offsets: R,
+    entry_offsets: R,
+    abbreviations: Vec<DebugNamesAbbreviation>,
+    entry_pool: R,
+}
+
+impl<R: Reader> DebugNamesTable<R> {
+    fn parse(input: &mut R) -> Result<DebugNamesTable<R>> {
+        let (length, format) = input.read_initial_length()?;
+        let mut rest = input.split(length)?;
+
+        let version = rest.read_u16()?;
+        if version != 5 {
+            return Err(Error::UnknownVersion(u64::from(version)));
+        }
+        let _padding = rest.read_u16()?;
+        let comp_unit_count = rest.read_u32()?;
+        let local_type_unit_count = rest.read_u32()?;
+        let foreign_type_unit_count = rest.read_u32()?;
+        let bucket_count = rest.read_u32()?;
+        let name_count = rest.read_u32()?;
+        let abbrev_table_size = rest.read_u32()?;
+        let augmentation_string_size = rest.read_u32()?;
+        let augmentation_string = rest.split(R::Offset::from_u32(augmentation_string_size))?;
+
+        let offset_size = u64::from(format.word_size());
+        let comp_units = rest.split(R::Offset::from_u64(
+            u64::from(comp_unit_count) * offset_size,
+        )?)?;
+        let local_type_units = rest.split(R::Offset::from_u64(
+            u64::from(local_type_unit_count) * offset_size,
+        )?)?;
+        let foreign_type_units =
+            rest.split(R::Offset::from_u64(u64::from(foreign_type_unit_count) * 8)?)?;
+        let buckets = rest.split(R::Offset::from_u64(u64::from(bucket_count) * 4)?)?;
+        let hash_count = if bucket_count == 0 { 0 } else { name_count };
+        let hashes = rest.split(R::Offset::from_u64(u64::from(hash_count) * 4)?)?;
+        let string_offsets =
+            rest.split(R::Offset::from_u64(u64::from(name_count) * offset_size)?)?;
+        let entry_offsets =
+            rest.split(R::Offset::from_u64(u64::from(name_count) * offset_size)?)?;
+
+        let mut abbrev_table = rest.split(R::Offset::from_u32(abbrev_table_size))?;
+        let mut abbreviations = Vec::new();
+        loop {
+            let code = abbrev_table.read_uleb128()?;
+            if code == 0 {
+                break;
+            }
+            if abbreviations
+                .iter()
+                .any(|abbrev: &DebugNamesAbbreviation| abbrev.code == code)
+            {
+                return Err(Error::DuplicateAbbreviationCode);
+            }
+            let tag = constants::DwTag(abbrev_table.read_uleb128_u16()?);
+            let mut attributes = Vec::new();
+            loop {
+                let name = constants::DwIdx(abbrev_table.read_uleb128_u16()?);
+                let form = constants::DwForm(abbrev_table.read_uleb128_u16()?);
+                if name.0 == 0 && form.0 == 0 {
+                    break;
+                }
+                attributes.push(DebugNamesAttributeSpec { name, form });
+            }
+            abbreviations.push(DebugNamesAbbreviation {
+                code,
+                tag,
+                attributes,
+            });
+        }
+
+        Ok(DebugNamesTable {
+            format,
+            version,
+            comp_unit_count,
+            local_type_unit_count,
+            foreign_type_unit_count,
+            bucket_count,
+            name_count,
+            augmentation_string,
+            comp_units,
+            local_type_units,
+            foreign_type_units,
+            buckets,
+            hashes,
+            string_offsets,
+            entry_offsets,
+            abbreviations,
+            entry_pool: rest,
+        })
+    }
+
+    /// Return the format of the name table.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Return the version of the name table.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Return the augmentation string, which identifies the producer of the
+    /// name table.
+    pub fn augmentation_string(&self) -> &R {
+        &self.augmentation_string
+    }
+
+    /// Return the number of compilation units in the name table.
+    pub fn comp_unit_count(&self) -> u32 {
+        self.comp_unit_count
+    }
+
+    /// Return the number of local type units in the name table.
+    pub fn local_type_unit_count(&self) -> u32 {
+        self.local_type_unit_count
+    }
+
+    /// Return the number of foreign type units in the name table.
+    pub fn foreign_type_unit_count(&self) -> u32 {
+        self.foreign_type_unit_count
+    }
+
+    /// Return the number of buckets in the hash table.
+    ///
+    /// This is zero if the name table does not contain a hash table.
+    pub fn bucket_count(&self) -> u32 {
+        self.bucket_count
+    }
+
+    /// Return the number of names in the name table.
+    pub fn name_count(&self) -> u32 {
+        self.name_count
+    }
+
+    /// Return the abbreviations used by the entries in the name table.
+    pub fn abbreviations(&self) -> &[DebugNamesAbbreviation] {
+        &self.abbreviations
+    }
+
+    fn offset_at(&self, input: &R, index: u32) -> Result<R::Offset> {
+        let mut input = input.clone();
+        let size = self.format.word_size();
+        input.skip(R::Offset::from_u64(u64::from(index) * u64::from(size))?)?;
+        input.read_offset(self.format)
+    }
+
+    /// Return the offset of the compilation unit at the given index.
+    pub fn comp_unit(&self, index: u32) -> Result<DebugInfoOffset<R::Offset>> {
+        self.offset_at(&self.comp_units, index).map(DebugInfoOffset)
+    }
+
+    /// Return the offset of the local type unit at the given index.
+    ///
+    /// Local type units are in the `.debug_info` section.
+    pub fn local_type_unit(&self, index: u32) -> Result<DebugInfoOffset<R::Offset>> {
+        self.offset_at(&self.local_type_units, index)
+            .map(DebugInfoOffset)
+    }
+
+    /// Return the signature of the foreign type unit at the given index.
+    ///
+    /// Foreign type units are in split DWARF object files, and must be found
+    /// using their signature.
+    pub fn foreign_type_unit(&self, index: u32) -> Result<DebugTypeSignature> {
+        let mut input = self.foreign_type_units.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 8)?)?;
+        input.read_u64().map(DebugTypeSignature)
+    }
+
+    /// Return the unit that contains the debugging information entry for the
+    /// given index entry.
+    ///
+    /// Returns `None` if the entry does not identify a unit.
+    pub fn entry_unit(
+        &self,
+        entry: &DebugNamesEntry<R::Offset>,
+    ) -> Result<Option<DebugNamesUnit<R::Offset>>> {
+        if let Some(index) = entry.type_unit {
+            let index = u32::try_from(index).map_err(|_| Error::OffsetOutOfBounds)?;
+            return if index < self.local_type_unit_count {
+                self.local_type_unit(index)
+                    .map(|offset| Some(DebugNamesUnit::LocalType(offset)))
+            } else {
+                self.foreign_type_unit(index - self.local_type_unit_count)
+                    .map(|signature| Some(DebugNamesUnit::ForeignType(signature)))
+            };
+        }
+        let index = match entry.compile_unit {
+            Some(index) => u32::try_from(index).map_err(|_| Error::OffsetOutOfBounds)?,
+            // The compilation unit index may be omitted if there is only one.
+            None if self.comp_unit_count == 1 => 0,
+            None => return Ok(None),
+        };
+        self.comp_unit(index)
+            .map(|offset| Some(DebugNamesUnit::Compilation(offset)))
+    }
+
+    /// Iterate over the names in the name table.
+    pub fn names(&self) -> DebugNamesNameIter<'_, R> {
+        DebugNamesNameIter {
+            table: self,
+            index: 0,
+        }
+    }
+
+    fn name(&self, index: u32) -> Result<DebugNamesName<R::Offset>> {
+        let name = DebugStrOffset(self.offset_at(&self.string_offsets, index)?);
+        let entries = DebugNamesEntryOffset(self.offset_at(&self.entry_offsets, index)?);
+        Ok(DebugNamesName {
+            index,
+            name,
+            entries,
+        })
+    }
+
+    fn hash(&self, index: u32) -> Result<u32> {
+        let mut input = self.hashes.clone();
+        input.skip(R::Offset::from_u64(u64::from(index) * 4)?)?;
+        input.read_u32()
+    }
+
+    /// Find the entries for the given name.
+    ///
+    /// `debug_str` is used to compare the names that have the same hash as
+    /// `name`. If the name table does not contain a hash table, then all of
+    /// the names are compared.
+    ///
+    /// Returns an empty iterator if the name is not found.
+    pub fn find(&self, name: &[u8], debug_str: &DebugStr<R>) -> Result<DebugNamesEntryIter<'_, R>> {
+        let mut empty = self.entry_pool.clone();
+        empty.empty();
+        let not_found = DebugNamesEntryIter {
+            table: self,
+            input: empty,
+        };
+
+        if self.bucket_count == 0 {
+            let mut names = self.names();
+            while let Some(entry_name) = names.next()? {
+                if debug_str.get_str(entry_name.name)?.to_slice()? == name {
+                    return self.entries(entry_name.entries);
+                }
+            }
+            return Ok(not_found);
+        }
+
+        let hash = debug_names_hash(name);
+        let bucket = hash % self.bucket_count;
+        let mut buckets = self.buckets.clone();
+        buckets.skip(R::Offset::from_u64(u64::from(bucket) * 4)?)?;
+        // Bucket entries are 1-based indices into the name arrays, and zero
+        // indicates an empty bucket.
+        let mut index = buckets.read_u32()?;
+        if index == 0 {
+            return Ok(not_found);
+        }
+        index -= 1;
+        while index < self.name_count {
+            let entry_hash = self.hash(index)?;
+            if entry_hash % self.bucket_count != bucket {
+                break;
+            }
+            if entry_hash == hash {
+                let entry_name = self.name(index)?;
+                if debug_str.get_str(entry_name.name)?.to_slice()? == name {
+                    return self.entries(entry_name.entries);
+                }
+            }
+            index += 1;
+        }
+        Ok(not_found)
+    }
+
+    /// Iterate over the series of entries at the given offset in the entry pool.
+    ///
+    /// The offset is usually obtained from `DebugNamesName::entries`.
+    pub fn entries(
+        &self,
+        offset: DebugNamesEntryOffset<R::Offset>,
+    ) -> Result<DebugNamesEntryIter<'_, R>> {
+        let mut input = self.entry_pool.clone();
+        input.skip(offset.0)?;
+        Ok(DebugNamesEntryIter { table: self, input })
+    }
+
+    /// Parse the entry at the given offset in the entry pool.
+    ///
+    /// This can be used to find the parent of an entry.
+    pub fn entry(
+        &self,
+        offset: DebugNamesEntryOffset<R::Offset>,
+    ) -> Result<DebugNamesEntry<R::Offset>> {
+        let mut entries = self.entries(offset)?;
+        entries.next()?.ok_or(Error::UnknownAbbreviation)
+    }
+
+    fn read_value(&self, input: &mut R, form: constants::DwForm) -> Result<u64> {
+        let value = match form {
+            constants::DW_FORM_flag_present => 1,
+            constants::DW_FORM_data1 | constants::DW_FORM_ref1 | constants::DW_FORM_flag => {
+                u64::from(input.read_u8()?)
+            }
+            constants::DW_FORM_data2 | constants::DW_FORM_ref2 => u64::from(input.read_u16()?),
+            constants::DW_FORM_data4 | constants::DW_FORM_ref4 => u64::from(input.read_u32()?),
+            constants::DW_FORM_data8 | constants::DW_FORM_ref8 | constants::DW_FORM_ref_sig8 => {
+                input.read_u64()?
+            }
+            constants::DW_FORM_udata | constants::DW_FORM_ref_udata => input.read_uleb128()?,
+            constants::DW_FORM_sdata => input.read_sleb128()? as u64,
+            _ => return Err(Error::UnknownForm),
+        };
+        Ok(value)
+    }
+}
+
+/// The unit that contains the debugging information entry for a `.debug_names`
+/// entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugNamesUnit<T = usize> {
+    /// A compilation unit in the `.debug_info` section.
+    Compilation(DebugInfoOffset<T>),
+    /// A type unit in the `.debug_info` section.
+    LocalType(DebugInfoOffset<T>),
+    /// A type unit in a split DWARF object file, identified by its signature.
+    ForeignType(DebugTypeSignature),
+}
+
+/// A name in a `.debug_names` name table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugNamesName<T = usize> {
+    /// The index of the name in the name table.
+    pub index: u32,
+    /// The offset of the name in the `.debug_str` section.
+    pub name: DebugStrOffset<T>,
+    /// The offset of the entries for the name in the entry pool.
+    pub entries: DebugNamesEntryOffset<T>,
+}
+
+/// An iterator over the names in a `.debug_names` name table.
+#[derive(Debug, Clone)]
+pub struct DebugNamesNameIter<'table, R: Reader> {
+    table: &'table DebugNamesTable<R>,
+    index: u32,
+}
+
+impl<'table, R: Reader> DebugNamesNameIter<'table, R> {
+    /// Advance the iterator to the next name.
+    pub fn next(&mut self) -> Result<Option<DebugNamesName<R::Offset>>> {
+        if self.index >= self.table.name_count {
+            return Ok(None);
+        }
+        match self.table.name(self.index) {
+            Ok(name) => {
+                self.index += 1;
+                Ok(Some(name))
+            }
+            Err(e) => {
+                self.index = self.table.name_count;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'table, R: Reader> fallible_iterator::FallibleIterator for DebugNamesNameIter<'table, R> {
+    type Item = DebugNamesName<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        DebugNamesNameIter::next(self)
+    }
+}
+
+result_iterator!(['table, R: Reader] DebugNamesNameIter<'table, R>, DebugNamesName<R::Offset>);
+
+/// The parent of a `.debug_names` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugNamesParent<T = usize> {
+    /// The parent of the entry is not in the name table.
+    ///
+    /// This is encoded using `DW_FORM_flag_present`.
+    NotIndexed,
+    /// The offset of the entry for the parent in the entry pool.
+    Entry(DebugNamesEntryOffset<T>),
+}
+
+/// An index attribute of a `.debug_names` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugNamesAttribute {
+    /// The kind of the attribute.
+    pub name: constants::DwIdx,
+    /// The form used to encode the attribute.
+    pub form: constants::DwForm,
+    /// The value of the attribute.
+    pub value: u64,
+}
+
+/// An entry in a `.debug_names` name table.
+///
+/// The standard index attributes are also available as typed fields, which are
+/// `None` if the entry does not contain the corresponding attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugNamesEntry<T = usize> {
+    /// The offset of the entry in the entry pool.
+    pub offset: DebugNamesEntryOffset<T>,
+    /// The abbreviation code of the entry.
+    pub code: u64,
+    /// The tag of the debugging information entry.
+    pub tag: constants::DwTag,
+    /// The index of the compilation unit (`DW_IDX_compile_unit`).
+    ///
+    /// See `DebugNamesTable::entry_unit`.
+    pub compile_unit: Option<u64>,
+    /// The index of the type unit (`DW_IDX_type_unit`).
+    ///
+    /// Indices less than the number of local type units refer to local type
+    /// units. Larger indices refer to foreign type units.
+    /// See `DebugNamesTable::entry_unit`.
+    pub type_unit: Option<u64>,
+    /// The offset of the debugging information entry within its unit
+    /// (`DW_IDX_die_offset`).
+    pub die_offset: Option<UnitOffset<T>>,
+    /// The parent of the entry (`DW_IDX_parent`).
+    pub parent: Option<DebugNamesParent<T>>,
+    /// The hash of the type signature (`DW_IDX_type_hash`).
+    pub type_hash: Option<u64>,
+    /// All of the index attributes of the entry, including vendor extensions.
+    pub attributes: Vec<DebugNamesAttribute>,
+}
+
+/// An iterator over a series of entries in a `.debug_names` name table.
+#[derive(Debug, Clone)]
+pub struct DebugNamesEntryIter<'table, R: Reader> {
+    table: &'table DebugNamesTable<R>,
+    input: R,
+}
+
+impl<'table, R: Reader> DebugNamesEntryIter<'table, R> {
+    /// Advance the iterator to the next entry.
+    pub fn next(&mut self) -> Result<Option<DebugNamesEntry<R::Offset>>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        match self.parse() {
+            Ok(Some(entry)) => Ok(Some(entry)),
+            Ok(None) => {
+                self.input.empty();
+                Ok(None)
+            }
+            Err(e) => {
+                self.input.empty();
+                Err(e)
+            }
+        }
+    }
+
+    fn parse(&mut self) -> Result<Option<DebugNamesEntry<R::Offset>>> {
+        let offset = DebugNamesEntryOffset(self.input.offset_from(&self.table.entry_pool));
+        let code = self.input.read_uleb128()?;
+        if code == 0 {
+            return Ok(None);
+        }
+        let abbrev = self
+            .table
+            .abbreviations
+            .iter()
+            .find(|abbrev| abbrev.code == code)
+            .ok_or(Error::UnknownAbbreviation)?;
+        let mut entry = DebugNamesEntry {
+            offset,
+            code,
+            tag: abbrev.tag,
+            compile_unit: None,
+            type_unit: None,
+            die_offset: None,
+            parent: None,
+            type_hash: None,
+            attributes: Vec::with_capacity(abbrev.attributes.len()),
+        };
+        for spec in &abbrev.attributes {
+            let value = self.table.read_value(&mut self.input, spec.form)?;
+            match spec.name {
+                constants::DW_IDX_compile_unit => entry.compile_unit = Some(value),
+                constants::DW_IDX_type_unit => entry.type_unit = Some(value),
+                constants::DW_IDX_die_offset => {
+                    entry.die_offset = Some(UnitOffset(R::Offset::from_u64(value)?));
+                }
+                constants::DW_IDX_parent => {
+                    entry.parent = Some(if spec.form == constants::DW_FORM_flag_present {
+                        DebugNamesParent::NotIndexed
+                    } else {
+                        DebugNamesParent::Entry(DebugNamesEntryOffset(R::Offset::from_u64(value)?))
+                    });
+                }
+                constants::DW_IDX_type_hash => entry.type_hash = Some(value),
+                _ => {}
+            }
+            entry.attributes.push(DebugNamesAttribute {
+                name: spec.name,
+                form: spec.form,
+                value,
+            });
+        }
+        Ok(Some(entry))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<'table, R: Reader> fallible_iterator::FallibleIterator for DebugNamesEntryIter<'table, R> {
+    type Item = DebugNamesEntry<R::Offset>;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        DebugNamesEntryIter::next(self)
+    }
+}
+
+result_iterator!(['table, R: Reader] DebugNamesEntryIter<'table, R>, DebugNamesEntry<R::Offset>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::apple_hash;
+    use crate::endianity::LittleEndian;
+    use crate::read::NameIndex;
+    use crate::test_util::GimliSectionMethods;
+    use test_assembler::{Endian, Label, LabelMaker, Section};
+
+    const DEBUG_STR: &[u8] = b"\0main\0Foo\0Bar\0inner\0";
+    const NAMES: [(&[u8], u32); 4] = [(b"main", 1), (b"Foo", 6), (b"Bar", 10), (b"inner", 14)];
+
+    fn debug_names(bucket_count: u32) -> Vec<u8> {
+        let mut names = NAMES
+            .iter()
+            .map(|&(name, offset)| (debug_names_hash(name), name, offset))
+            .collect::<Vec<_>>();
+        if bucket_count != 0 {
+            names.sort_by_key(|&(hash, _, _)| (hash % bucket_count, hash));
+        }
+
+        let series = [Label::new(), Label::new(), Label::new(), Label::new()];
+        let foo_entry = Label::new();
+        let pool = Section::with_endian(Endian::Little);
+        pool.start().set_const(0);
+        let mut pool = pool;
+        for (&(_, name, _), label) in names.iter().zip(series.iter()) {
+            pool = pool.mark(label);
+            pool = match name {
+                b"main" => pool.uleb(1).D32(0x20),
+                b"Foo" => pool.mark(&foo_entry).uleb(2).D8(0).D32(0x30),
+                b"Bar" => pool.uleb(3).D8(1).D64(0x5678),
+                b"inner" => pool.uleb(4).uleb(0).D32(0x40).D32(&foo_entry),
+                _ => unreachable!(),
+            };
+            pool = pool.uleb(0);
+        }
+
+        let length = Label::new();
+        let start = Label::new();
+        let end = Label::new();
+        let abbrev_start = Label::new();
+        let abbrev_end = Label::new();
+        let abbrev_size = Label::new();
+        let mut section = Section::with_endian(Endian::Little)
+            .D32(&length)
+            .mark(&start)
+            .D16(5)
+            .D16(0)
+            // Compilation units, local type units, foreign type units.
+            .D32(1)
+            .D32(1)
+            .D32(1)
+            .D32(bucket_count)
+            .D32(names.len() as u32)
+            .D32(&abbrev_size)
+            .D32(4)
+            .append_bytes(b"test")
+            .D32(0)
+            .D32(0x100)
+            .D64(0x1234);
+        for bucket in 0..bucket_count {
+            section = match names
+                .iter()
+                .position(|&(hash, _, _)| hash % bucket_count == bucket)
+            {
+                Some(index) => section.D32(index as u32 + 1),
+                None => section.D32(0),
+            };
+        }
+        if bucket_count != 0 {
+            for &(hash, _, _) in &names {
+                section = section.D32(hash);
+            }
+        }
+        for &(_, _, offset) in &names {
+            section = section.D32(offset);
+        }
+        for label in &series {
+            section = section.D32(label);
+        }
+        section = section
+            .mark(&abbrev_start)
+            .uleb(1)
+            .uleb(constants::DW_TAG_subprogram.0.into())
+            .uleb(constants::DW_IDX_die_offset.0.into())
+            .uleb(constants::DW_FORM_ref4.0.into())
+            .uleb(constants::DW_IDX_parent.0.into())
+            .uleb(constants::DW_FORM_flag_present.0.into())
+            .uleb(0)
+            .uleb(0)
+            .uleb(2)
+            .uleb(constants::DW_TAG_structure_type.0.into())
+            .uleb(constants::DW_IDX_type_unit.0.into())
+            .uleb(constants::DW_FORM_data1.0.into())
+            .uleb(constants::DW_IDX_die_offset.0.into())
+            .uleb(constants::DW_FORM_ref4.0.into())
+            .uleb(0)
+            .uleb(0)
+            .uleb(3)
+            .uleb(constants::DW_TAG_structure_type.0.into())
+            .uleb(constants::DW_IDX_type_unit.0.into())
+            .uleb(constants::DW_FORM_data1.0.into())
+            .uleb(constants::DW_IDX_type_hash.0.into())
+            .uleb(constants::DW_FORM_data8.0.into())
+            .uleb(0)
+            .uleb(0)
+            .uleb(4)
+            .uleb(constants::DW_TAG_variable.0.into())
+            .uleb(constants::DW_IDX_compile_unit.0.into())
+            .uleb(constants::DW_FORM_udata.0.into())
+            .uleb(constants::DW_IDX_die_offset.0.into())
+            .uleb(constants::DW_FORM_ref4.0.into())
+            .uleb(constants::DW_IDX_parent.0.into())
+            .uleb(constants::DW_FORM_ref4.0.into())
+            .uleb(0)
+            .uleb(0)
+            .uleb(0)
+            .mark(&abbrev_end)
+            .append_section(pool)
+            .mark(&end);
+        abbrev_size.set_const((&abbrev_end - &abbrev_start) as u64);
+        length.set_const((&end - &start) as u64);
+        section.get_contents().unwrap()
+    }
+
+    #[test]
+    fn test_debug_names_hash() {
+        assert_eq!(debug_names_hash(b""), 5381);
+        assert_eq!(debug_names_hash(b"Main"), apple_hash(b"main"));
+    }
+
+    fn check_table(buf: &[u8], bucket_count: u32) {
+        let debug_str = DebugStr::new(DEBUG_STR, LittleEndian);
+        let debug_names = DebugNames::new(buf, LittleEndian);
+        let mut tables = debug_names.tables();
+        let table = tables.next().unwrap().unwrap();
+        assert!(tables.next().unwrap().is_none());
+
+        assert_eq!(table.version(), 5);
+        assert_eq!(table.format(), Format::Dwarf32);
+        assert_eq!(table.augmentation_string().slice(), b"test");
+        assert_eq!(table.comp_unit_count(), 1);
+        assert_eq!(table.local_type_unit_count(), 1);
+        assert_eq!(table.foreign_type_unit_count(), 1);
+        assert_eq!(table.bucket_count(), bucket_count);
+        assert_eq!(table.name_count(), 4);
+        assert_eq!(table.abbreviations().len(), 4);
+
+        let mut entries = table.find(b"main", &debug_str).unwrap();
+        let entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.tag, constants::DW_TAG_subprogram);
+        assert_eq!(entry.die_offset, Some(UnitOffset(0x20)));
+        assert_eq!(entry.parent, Some(DebugNamesParent::NotIndexed));
+        assert_eq!(entry.compile_unit, None);
+        assert_eq!(
+            table.entry_unit(&entry).unwrap(),
+            Some(DebugNamesUnit::Compilation(DebugInfoOffset(0)))
+        );
+        assert_eq!(entries.next().unwrap(), None);
+
+        let mut entries = table.find(b"Foo", &debug_str).unwrap();
+        let foo = entries.next().unwrap().unwrap();
+        assert_eq!(foo.tag, constants::DW_TAG_structure_type);
+        assert_eq!(foo.type_unit, Some(0));
+        assert_eq!(
+            table.entry_unit(&foo).unwrap(),
+            Some(DebugNamesUnit::LocalType(DebugInfoOffset(0x100)))
+        );
+        assert_eq!(entries.next().unwrap(), None);
+        let mut invalid = foo.clone();
+        invalid.type_unit = Some(1 << 32);
+        assert_eq!(
+            table.entry_unit(&invalid),
+            Err(crate::read::Error::OffsetOutOfBounds)
+        );
+
+        let bar = table
+            .find(b"Bar", &debug_str)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(bar.type_hash, Some(0x5678));
+        assert_eq!(bar.die_offset, None);
+        assert_eq!(
+            table.entry_unit(&bar).unwrap(),
+            Some(DebugNamesUnit::ForeignType(DebugTypeSignature(0x1234)))
+        );
+
+        let inner = table
+            .find(b"inner", &debug_str)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(inner.compile_unit, Some(0));
+        assert_eq!(inner.die_offset, Some(UnitOffset(0x40)));
+        assert_eq!(inner.attributes.len(), 3);
+        assert_eq!(inner.parent, Some(DebugNamesParent::Entry(foo.offset)));
+        assert_eq!(table.entry(foo.offset).unwrap(), foo);
+
+        // Names are case sensitive, even though the hash is not.
+        assert_eq!(
+            table.find(b"foo", &debug_str).unwrap().next().unwrap(),
+            None
+        );
+        assert_eq!(
+            table.find(b"baz", &debug_str).unwrap().next().unwrap(),
+            None
+        );
+
+        let mut count = 0;
+        let mut names = table.names();
+        while let Some(name) = names.next().unwrap() {
+            let s = debug_str.get_str(name.name).unwrap();
+            let mut entries = table.find(s.slice(), &debug_str).unwrap();
+            assert_eq!(entries.next().unwrap().unwrap().offset, name.entries);
+            count += 1;
+        }
+        assert_eq!(count, 4);
+
+        let index = crate::read::DebugNamesIndex::new(debug_names, debug_str);
+        let entries = index.find_name(b"inner").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].unit, Some(DebugInfoOffset(0).into()));
+        assert_eq!(entries[0].die, Some(DebugInfoOffset(0x40).into()));
+        assert_eq!(entries[0].tag, Some(constants::DW_TAG_variable));
+        let entries = index.find_name(b"Bar").unwrap();
+        assert_eq!(entries[0].unit, None);
+        assert_eq!(entries[0].die, None);
+    }
+
+    #[test]
+    fn test_debug_names_find() {
+        check_table(&debug_names(3), 3);
+    }
+
+    #[test]
+    fn test_debug_names_find_without_hash_table() {
+        check_table(&debug_names(0), 0);
+    }
+}
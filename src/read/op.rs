@@ -1,12 +1,20 @@
 //! Functions for parsing and evaluating DWARF expressions.
 
+#[cfg(feature = "read")]
+use alloc::boxed::Box;
+#[cfg(feature = "read")]
+use alloc::sync::Arc;
 #[cfg(feature = "read")]
 use alloc::vec::Vec;
+#[cfg(feature = "read")]
+use core::fmt;
 use core::mem;
 
 use super::util::{ArrayLike, ArrayVec};
 use crate::common::{DebugAddrIndex, DebugInfoOffset, Encoding, Register};
 use crate::constants;
+#[cfg(feature = "read")]
+use crate::read::Range;
 use crate::read::{Error, Reader, ReaderOffset, Result, StoreOnHeap, UnitOffset, Value, ValueType};
 
 /// A reference to a DIE, either relative to the current CU or
@@ -31,6 +39,7 @@ pub enum DieReference<T = usize> {
 /// example, both `DW_OP_deref` and `DW_OP_xderef` are represented
 /// using `Operation::Deref`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Operation<R, Offset = <R as Reader>::Offset>
 where
     R: Reader<Offset = Offset>,
@@ -290,14 +299,34 @@ where
         /// The index of the stack item. 0 is the bottom of the operand stack.
         index: u32,
     },
+    /// An operation that is not known by this library, such as a
+    /// vendor extension.
+    ///
+    /// The size of the operands of an unknown operation cannot be
+    /// determined, so `data` contains all of the remaining bytes of the
+    /// expression, and no further operations can be parsed.
+    ///
+    /// When evaluated, this calls the handler set by
+    /// `Evaluation::set_unknown_operation_handler`.
+    Unknown {
+        /// The opcode of the operation.
+        opcode: constants::DwOp,
+        /// The remaining bytes of the expression, starting with the
+        /// operands of the operation.
+        data: R,
+    },
 }
 
 #[derive(Debug)]
 enum OperationEvaluationResult<R: Reader> {
     Piece,
     Incomplete,
-    Complete { location: Location<R> },
+    Complete {
+        location: Location<R>,
+    },
     Waiting(EvaluationWaiting<R>, EvaluationResult<R>),
+    #[cfg(feature = "read")]
+    Unavailable,
 }
 
 /// A single location of a piece of the result of a DWARF expression.
@@ -807,7 +836,13 @@ where
                 }
                 _ => Err(Error::InvalidExpression(name)),
             },
-            _ => Err(Error::InvalidExpression(name)),
+            _ => {
+                let data = bytes.clone();
+                // Skip rather than empty, so that offsets remain relative to
+                // the start of the expression.
+                bytes.skip(bytes.len())?;
+                Ok(Operation::Unknown { opcode: name, data })
+            }
         }
     }
 }
@@ -838,10 +873,33 @@ enum EvaluationWaiting<R: Reader> {
     Reinterpret,
 }
 
+/// An event recorded by an `Evaluation` when tracing is enabled.
+///
+/// See `Evaluation::enable_trace`.
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationTraceEvent<R: Reader> {
+    /// An operation is about to be evaluated.
+    Operation {
+        /// The offset of the operation within the expression that contains it.
+        offset: R::Offset,
+        /// The number of `DW_OP_call*` expressions that are being evaluated.
+        /// This is 0 for operations in the initial expression.
+        call_depth: usize,
+        /// The operation.
+        operation: Operation<R>,
+        /// The stack before the operation is evaluated, with the top of the
+        /// stack last.
+        stack: Vec<Value>,
+    },
+    /// The evaluation requires more data from the caller.
+    Request(EvaluationResult<R>),
+}
+
 /// The state of an `Evaluation` after evaluating a DWARF expression.
 /// The evaluation is either `Complete`, or it requires more data
 /// to continue, as described by the variant.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum EvaluationResult<R: Reader> {
     /// The `Evaluation` is complete, and `Evaluation::result()` can be called.
     Complete,
@@ -921,6 +979,14 @@ pub enum EvaluationResult<R: Reader> {
 
 /// The bytecode for a DWARF expression or location description.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize",
+        deserialize = "R: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Expression<R: Reader>(pub R);
 
 impl<R: Reader> Expression<R> {
@@ -948,6 +1014,18 @@ impl<R: Reader> Expression<R> {
         Evaluation::new(self.0, encoding)
     }
 
+    /// Decode the operations in this expression into a form that can be
+    /// evaluated repeatedly without parsing the bytecode again.
+    ///
+    /// This validates all of the operations, and resolves the targets of
+    /// `DW_OP_bra` and `DW_OP_skip` to operation indices. Branches to
+    /// offsets that are not the start of an operation are an error, as are
+    /// operations that are not known by this library.
+    #[cfg(feature = "read")]
+    pub fn compile(self, encoding: Encoding) -> Result<CompiledExpression<R>> {
+        CompiledExpression::new(self, encoding)
+    }
+
     /// Return an iterator for the operations in the expression.
     pub fn operations(self, encoding: Encoding) -> OperationIter<R> {
         OperationIter {
@@ -995,6 +1073,114 @@ impl<R: Reader> fallible_iterator::FallibleIterator for OperationIter<R> {
     }
 }
 
+result_iterator!([R: Reader] OperationIter<R>, Operation<R>);
+
+/// A DWARF expression that has been decoded for repeated evaluation.
+///
+/// This is created by [`Expression::compile`]. Cloning is cheap, since the
+/// operations are shared.
+#[cfg(feature = "read")]
+#[derive(Debug, Clone)]
+pub struct CompiledExpression<R: Reader> {
+    operations: Arc<[CompiledOperation<R>]>,
+    bytecode: R,
+    encoding: Encoding,
+}
+
+#[cfg(feature = "read")]
+#[derive(Debug, Clone)]
+struct CompiledOperation<R: Reader> {
+    operation: Operation<R>,
+    /// The offset of the operation in the bytecode.
+    offset: R::Offset,
+    /// The index of the target operation for `DW_OP_bra` and `DW_OP_skip`.
+    target: usize,
+}
+
+#[cfg(feature = "read")]
+impl<R: Reader> CompiledExpression<R> {
+    fn new(expression: Expression<R>, encoding: Encoding) -> Result<Self> {
+        let bytecode = expression.0;
+        let mut pc = bytecode.clone();
+        let mut operations = Vec::new();
+        let mut targets = Vec::new();
+        while !pc.is_empty() {
+            let offset = pc.offset_from(&bytecode);
+            let operation = Operation::parse(&mut pc, encoding)?;
+            if let Operation::Unknown { opcode, .. } = operation {
+                return Err(Error::InvalidExpression(opcode));
+            }
+            if let Operation::Bra { target } | Operation::Skip { target } = operation {
+                let target = pc
+                    .offset_from(&bytecode)
+                    .wrapping_add(R::Offset::from_i16(target));
+                targets.push((operations.len(), target));
+            }
+            operations.push(CompiledOperation {
+                operation,
+                offset,
+                target: 0,
+            });
+        }
+        for (index, target) in targets {
+            let target_index = if target == bytecode.len() {
+                operations.len()
+            } else {
+                operations
+                    .binary_search_by_key(&target, |op| op.offset)
+                    .map_err(|_| Error::BadBranchTarget(target.into_u64()))?
+            };
+            operations[index].target = target_index;
+        }
+        Ok(CompiledExpression {
+            operations: operations.into(),
+            bytecode,
+            encoding,
+        })
+    }
+
+    /// Return the bytecode that this expression was compiled from.
+    pub fn expression(&self) -> Expression<R> {
+        Expression(self.bytecode.clone())
+    }
+
+    /// Return the encoding that this expression was compiled with.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Return an iterator for the decoded operations in the expression.
+    pub fn operations(&self) -> impl Iterator<Item = &Operation<R>> {
+        self.operations.iter().map(|op| &op.operation)
+    }
+
+    /// Create an evaluation for this expression.
+    ///
+    /// The evaluation behaves the same as one created by
+    /// [`Expression::evaluation`], but does not parse the bytecode.
+    #[inline]
+    pub fn evaluation(&self) -> Evaluation<R> {
+        Evaluation::from_compiled(self)
+    }
+
+    /// Return the offset of the end of the operation at `index`.
+    fn end_offset(&self, index: usize) -> R::Offset {
+        match self.operations.get(index + 1) {
+            Some(op) => op.offset,
+            None => self.bytecode.len(),
+        }
+    }
+}
+
+/// The position of an `Evaluation` within a compiled expression.
+#[cfg(feature = "read")]
+#[derive(Debug)]
+struct CompiledPc<R: Reader> {
+    expression: CompiledExpression<R>,
+    /// The index of the next operation to evaluate.
+    index: usize,
+}
+
 /// Specification of what storage should be used for [`Evaluation`].
 ///
 #[cfg_attr(
@@ -1057,6 +1243,77 @@ impl<R: Reader> EvaluationStorage<R> for StoreOnHeap {
     type Result = Vec<Piece<R>>;
 }
 
+/// What an `Evaluation` does when an expression reads memory outside of
+/// the ranges set by `Evaluation::set_memory_ranges`.
+#[cfg(feature = "read")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeniedMemoryAccess {
+    /// Fail the evaluation with `Error::MemoryAccessDenied`.
+    Error,
+    /// Complete the evaluation with a single piece with a location of
+    /// `Location::Empty`, indicating that the value is unavailable.
+    Unavailable,
+}
+
+/// An operation that is not known by `Evaluation`.
+///
+/// This is passed to the handler set by
+/// `Evaluation::set_unknown_operation_handler`.
+#[cfg(feature = "read")]
+#[derive(Debug)]
+pub struct UnknownOperation<'a, R: Reader, S: EvaluationStorage<R> = StoreOnHeap> {
+    opcode: constants::DwOp,
+    operands: R,
+    stack: &'a mut ArrayVec<S::Stack>,
+    addr_mask: u64,
+}
+
+#[cfg(feature = "read")]
+impl<'a, R: Reader, S: EvaluationStorage<R>> UnknownOperation<'a, R, S> {
+    /// Return the opcode of the operation.
+    pub fn opcode(&self) -> constants::DwOp {
+        self.opcode
+    }
+
+    /// Return the bytes following the opcode.
+    ///
+    /// The handler must read the operands of the operation from this, so
+    /// that evaluation can continue with the next operation.
+    pub fn operands(&mut self) -> &mut R {
+        &mut self.operands
+    }
+
+    /// Return the mask for the address size of the expression.
+    ///
+    /// This should be passed to the `Value` methods for generic values.
+    pub fn addr_mask(&self) -> u64 {
+        self.addr_mask
+    }
+
+    /// Pop a value from the stack.
+    pub fn pop(&mut self) -> Result<Value> {
+        self.stack.pop().ok_or(Error::NotEnoughStackItems)
+    }
+
+    /// Push a value onto the stack.
+    pub fn push(&mut self, value: Value) -> Result<()> {
+        self.stack.try_push(value).map_err(|_| Error::StackFull)
+    }
+}
+
+#[cfg(feature = "read")]
+#[allow(clippy::type_complexity)]
+struct UnknownOperationHandler<R: Reader, S: EvaluationStorage<R>>(
+    Box<dyn FnMut(&mut UnknownOperation<'_, R, S>) -> Result<()>>,
+);
+
+#[cfg(feature = "read")]
+impl<R: Reader, S: EvaluationStorage<R>> fmt::Debug for UnknownOperationHandler<R, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UnknownOperationHandler").finish()
+    }
+}
+
 /// A DWARF expression evaluator.
 ///
 /// # Usage
@@ -1109,6 +1366,15 @@ pub struct Evaluation<R: Reader, S: EvaluationStorage<R> = StoreOnHeap> {
     object_address: Option<u64>,
     max_iterations: Option<u32>,
     iteration: u32,
+    max_call_depth: Option<u32>,
+    #[cfg(feature = "read")]
+    trace: Option<Vec<EvaluationTraceEvent<R>>>,
+    #[cfg(feature = "read")]
+    memory_ranges: Option<Vec<Range>>,
+    #[cfg(feature = "read")]
+    denied_memory_access: DeniedMemoryAccess,
+    #[cfg(feature = "read")]
+    unknown_operation_handler: Option<UnknownOperationHandler<R, S>>,
     state: EvaluationState<R>,
 
     // Stack operations are done on word-sized values.  We do all
@@ -1126,6 +1392,11 @@ pub struct Evaluation<R: Reader, S: EvaluationStorage<R> = StoreOnHeap> {
     // is stored here while evaluating the subroutine.
     expression_stack: ArrayVec<S::ExpressionStack>,
 
+    // If the expression was compiled, the next operation is taken from
+    // here instead of `pc` while the expression stack is empty.
+    #[cfg(feature = "read")]
+    compiled: Option<CompiledPc<R>>,
+
     value_result: Option<Value>,
     result: ArrayVec<S::Result>,
 }
@@ -1135,11 +1406,26 @@ impl<R: Reader> Evaluation<R> {
     /// Create a new DWARF expression evaluator.
     ///
     /// The new evaluator is created without an initial value, without
-    /// an object address, and without a maximum number of iterations.
+    /// an object address, and without a maximum number of iterations
+    /// or call depth.
     pub fn new(bytecode: R, encoding: Encoding) -> Self {
         Self::new_in(bytecode, encoding)
     }
 
+    /// Create a new DWARF expression evaluator for a compiled expression.
+    ///
+    /// This is the same as `new`, except that the operations are taken from
+    /// `expression` instead of being parsed from the bytecode.
+    pub fn from_compiled(expression: &CompiledExpression<R>) -> Self {
+        let mut evaluation = Self::new_in(expression.bytecode.clone(), expression.encoding);
+        evaluation.pc.empty();
+        evaluation.compiled = Some(CompiledPc {
+            expression: expression.clone(),
+            index: 0,
+        });
+        evaluation
+    }
+
     /// Get the result of this `Evaluation`.
     ///
     /// # Panics
@@ -1158,7 +1444,8 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
     /// Create a new DWARF expression evaluator.
     ///
     /// The new evaluator is created without an initial value, without
-    /// an object address, and without a maximum number of iterations.
+    /// an object address, and without a maximum number of iterations
+    /// or call depth.
     pub fn new_in(bytecode: R, encoding: Encoding) -> Self {
         let pc = bytecode.clone();
         Evaluation {
@@ -1167,6 +1454,15 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
             object_address: None,
             max_iterations: None,
             iteration: 0,
+            max_call_depth: None,
+            #[cfg(feature = "read")]
+            trace: None,
+            #[cfg(feature = "read")]
+            memory_ranges: None,
+            #[cfg(feature = "read")]
+            denied_memory_access: DeniedMemoryAccess::Error,
+            #[cfg(feature = "read")]
+            unknown_operation_handler: None,
             state: EvaluationState::Start(None),
             addr_mask: if encoding.address_size == 8 {
                 !0u64
@@ -1175,6 +1471,8 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
             },
             stack: Default::default(),
             expression_stack: Default::default(),
+            #[cfg(feature = "read")]
+            compiled: None,
             pc,
             value_result: None,
             result: Default::default(),
@@ -1223,6 +1521,108 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
         self.max_iterations = Some(value);
     }
 
+    /// Set the maximum depth of nested `DW_OP_call*` operations to be
+    /// allowed by the expression evaluator.
+    ///
+    /// Each call to `resume_with_at_location` with a non-empty
+    /// expression increases the depth until that expression completes.
+    /// If the maximum depth is exceeded, evaluation fails with
+    /// `Error::CallDepthExceeded`.  The default is not to have a
+    /// maximum; once set, it's not possible to go back to this default
+    /// state.  This value can be set to avoid unbounded recursion when
+    /// an expression calls itself.
+    pub fn set_max_call_depth(&mut self, value: u32) {
+        self.max_call_depth = Some(value);
+    }
+
+    /// Enable recording of a trace of the evaluation.
+    ///
+    /// Requires the `read` feature.
+    ///
+    /// When enabled, each operation is recorded along with the stack
+    /// before it is evaluated, as well as each request for data from
+    /// the caller.  The trace can be retrieved with `trace`, including
+    /// after an error.  This is intended for debugging expressions
+    /// that produce unexpected results.
+    #[cfg(feature = "read")]
+    pub fn enable_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(Vec::new());
+        }
+    }
+
+    /// Return the events that have been recorded since tracing was enabled.
+    ///
+    /// Returns an empty slice if tracing is not enabled.
+    #[cfg(feature = "read")]
+    pub fn trace(&self) -> &[EvaluationTraceEvent<R>] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    /// Restrict the memory that the expression is allowed to read.
+    ///
+    /// Requires the `read` feature.
+    ///
+    /// Reads of memory that are not entirely within one of `ranges` are
+    /// not returned to the caller as `EvaluationResult::RequiresMemory`, and
+    /// are instead handled as specified by `set_denied_memory_access`. The
+    /// ranges apply to all address spaces. An empty slice denies all memory
+    /// reads. The default is to allow all memory reads.
+    ///
+    /// This can be used to bound what an expression from an untrusted
+    /// source can read.
+    #[cfg(feature = "read")]
+    pub fn set_memory_ranges(&mut self, ranges: &[Range]) {
+        self.memory_ranges = Some(ranges.to_vec());
+    }
+
+    /// Set what happens when the expression reads memory outside of the
+    /// ranges set by `set_memory_ranges`.
+    ///
+    /// Requires the `read` feature.
+    ///
+    /// The default is `DeniedMemoryAccess::Error`.
+    #[cfg(feature = "read")]
+    pub fn set_denied_memory_access(&mut self, value: DeniedMemoryAccess) {
+        self.denied_memory_access = value;
+    }
+
+    /// Set a handler for operations that are not known by the evaluator.
+    ///
+    /// Requires the `read` feature.
+    ///
+    /// The handler is called for each `Operation::Unknown`, such as a vendor
+    /// extension. It must read the operands of the operation, and may pop
+    /// and push values on the stack. Evaluation then continues with the
+    /// operation following the operands. If the handler returns an error,
+    /// then evaluation fails with that error.
+    ///
+    /// If no handler is set, then evaluation of an unknown operation fails
+    /// with `Error::InvalidExpression`.
+    #[cfg(feature = "read")]
+    pub fn set_unknown_operation_handler<F>(&mut self, handler: F)
+    where
+        F: FnMut(&mut UnknownOperation<'_, R, S>) -> Result<()> + 'static,
+    {
+        self.unknown_operation_handler = Some(UnknownOperationHandler(Box::new(handler)));
+    }
+
+    /// Return true if the expression is allowed to read `size` bytes at `address`.
+    #[cfg(feature = "read")]
+    fn is_memory_allowed(&self, address: u64, size: u8) -> bool {
+        let ranges = match &self.memory_ranges {
+            Some(ranges) => ranges,
+            None => return true,
+        };
+        let end = match address.checked_add(u64::from(size)) {
+            Some(end) => end,
+            None => return false,
+        };
+        ranges
+            .iter()
+            .any(|range| range.begin <= address && end <= range.end)
+    }
+
     fn pop(&mut self) -> Result<Value> {
         match self.stack.pop() {
             Some(value) => Ok(value),
@@ -1234,8 +1634,41 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
         self.stack.try_push(value).map_err(|_| Error::StackFull)
     }
 
+    /// Return the compiled expression position, if the current expression
+    /// is a compiled expression.
+    #[cfg(feature = "read")]
+    fn compiled_pc(&mut self) -> Option<&mut CompiledPc<R>> {
+        if self.expression_stack.is_empty() {
+            self.compiled.as_mut()
+        } else {
+            None
+        }
+    }
+
+    fn next_operation(&mut self) -> Result<Operation<R>> {
+        #[cfg(feature = "read")]
+        if let Some(compiled) = self.compiled_pc() {
+            let operation = compiled.expression.operations[compiled.index]
+                .operation
+                .clone();
+            compiled.index += 1;
+            return Ok(operation);
+        }
+        Operation::parse(&mut self.pc, self.encoding)
+    }
+
+    fn branch(&mut self, target: i16) -> Result<()> {
+        #[cfg(feature = "read")]
+        if let Some(compiled) = self.compiled_pc() {
+            compiled.index = compiled.expression.operations[compiled.index - 1].target;
+            return Ok(());
+        }
+        self.pc = compute_pc(&self.pc, &self.bytecode, target)?;
+        Ok(())
+    }
+
     fn evaluate_one_operation(&mut self) -> Result<OperationEvaluationResult<R>> {
-        let operation = Operation::parse(&mut self.pc, self.encoding)?;
+        let operation = self.next_operation()?;
 
         match operation {
             Operation::Deref {
@@ -1252,6 +1685,15 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                 } else {
                     None
                 };
+                #[cfg(feature = "read")]
+                if !self.is_memory_allowed(addr, size) {
+                    return match self.denied_memory_access {
+                        DeniedMemoryAccess::Error => Err(Error::MemoryAccessDenied(addr)),
+                        DeniedMemoryAccess::Unavailable => {
+                            Ok(OperationEvaluationResult::Unavailable)
+                        }
+                    };
+                }
                 return Ok(OperationEvaluationResult::Waiting(
                     EvaluationWaiting::Memory,
                     EvaluationResult::RequiresMemory {
@@ -1382,7 +1824,7 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                 let entry = self.pop()?;
                 let v = entry.to_u64(self.addr_mask)?;
                 if v != 0 {
-                    self.pc = compute_pc(&self.pc, &self.bytecode, target)?;
+                    self.branch(target)?;
                 }
             }
 
@@ -1424,7 +1866,7 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
             }
 
             Operation::Skip { target } => {
-                self.pc = compute_pc(&self.pc, &self.bytecode, target)?;
+                self.branch(target)?;
             }
 
             Operation::UnsignedConstant { value } => {
@@ -1597,6 +2039,24 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
             | Operation::WasmStack { .. } => {
                 return Err(Error::UnsupportedEvaluation);
             }
+
+            Operation::Unknown { opcode, data } => {
+                #[cfg(feature = "read")]
+                if let Some(handler) = &mut self.unknown_operation_handler {
+                    let mut operation = UnknownOperation {
+                        opcode,
+                        operands: data,
+                        stack: &mut self.stack,
+                        addr_mask: self.addr_mask,
+                    };
+                    (handler.0)(&mut operation)?;
+                    self.pc = operation.operands;
+                    return Ok(OperationEvaluationResult::Incomplete);
+                }
+                #[cfg(not(feature = "read"))]
+                let _ = data;
+                return Err(Error::InvalidExpression(opcode));
+            }
         }
 
         Ok(OperationEvaluationResult::Incomplete)
@@ -1780,6 +2240,11 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
             EvaluationState::Error(err) => return Err(err),
             EvaluationState::Waiting(EvaluationWaiting::AtLocation) => {
                 if !bytes.is_empty() {
+                    if let Some(max_call_depth) = self.max_call_depth {
+                        if self.expression_stack.len() >= max_call_depth as usize {
+                            return Err(Error::CallDepthExceeded);
+                        }
+                    }
                     let mut pc = bytes.clone();
                     mem::swap(&mut pc, &mut self.pc);
                     mem::swap(&mut bytes, &mut self.bytecode);
@@ -1919,7 +2384,13 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                     self.pc = newpc;
                     self.bytecode = newbytes;
                 }
-                None => return true,
+                None => {
+                    #[cfg(feature = "read")]
+                    if let Some(compiled) = &self.compiled {
+                        return compiled.index >= compiled.expression.operations.len();
+                    }
+                    return true;
+                }
             }
         }
         false
@@ -1934,6 +2405,29 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                 }
             }
 
+            #[cfg(feature = "read")]
+            if let Some(trace) = &mut self.trace {
+                let compiled = match &self.compiled {
+                    Some(compiled) if self.expression_stack.is_empty() => {
+                        Some(&compiled.expression.operations[compiled.index])
+                    }
+                    _ => None,
+                };
+                let (offset, operation) = match compiled {
+                    Some(op) => (op.offset, op.operation.clone()),
+                    None => (
+                        self.pc.offset_from(&self.bytecode),
+                        Operation::parse(&mut self.pc.clone(), self.encoding)?,
+                    ),
+                };
+                trace.push(EvaluationTraceEvent::Operation {
+                    offset,
+                    call_depth: self.expression_stack.len(),
+                    operation,
+                    stack: self.stack.iter().copied().collect(),
+                });
+            }
+
             let op_result = self.evaluate_one_operation()?;
             match op_result {
                 OperationEvaluationResult::Piece => {}
@@ -1963,7 +2457,7 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                     } else {
                         // If there are more operations, then the next operation must
                         // be a Piece.
-                        match Operation::parse(&mut self.pc, self.encoding)? {
+                        match self.next_operation()? {
                             Operation::Piece {
                                 size_in_bits,
                                 bit_offset,
@@ -1977,6 +2471,12 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                                     .map_err(|_| Error::StackFull)?;
                             }
                             _ => {
+                                #[cfg(feature = "read")]
+                                if let Some(compiled) = self.compiled_pc() {
+                                    let end = compiled.expression.end_offset(compiled.index - 1);
+                                    let value = end.into_u64() - 1;
+                                    return Err(Error::InvalidExpressionTerminator(value));
+                                }
                                 let value =
                                     self.bytecode.len().into_u64() - self.pc.len().into_u64() - 1;
                                 return Err(Error::InvalidExpressionTerminator(value));
@@ -1984,8 +2484,25 @@ impl<R: Reader, S: EvaluationStorage<R>> Evaluation<R, S> {
                         }
                     }
                 }
+                #[cfg(feature = "read")]
+                OperationEvaluationResult::Unavailable => {
+                    self.result.clear();
+                    self.result
+                        .try_push(Piece {
+                            size_in_bits: None,
+                            bit_offset: None,
+                            location: Location::Empty,
+                        })
+                        .map_err(|_| Error::StackFull)?;
+                    self.state = EvaluationState::Complete;
+                    return Ok(EvaluationResult::Complete);
+                }
                 OperationEvaluationResult::Waiting(waiting, result) => {
                     self.state = EvaluationState::Waiting(waiting);
+                    #[cfg(feature = "read")]
+                    if let Some(trace) = &mut self.trace {
+                        trace.push(EvaluationTraceEvent::Request(result.clone()));
+                    }
                     return Ok(result);
                 }
             }
@@ -2842,6 +3359,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_op_unknown() {
+        let encoding = encoding4();
+        let bytes = [0xf0, 1, 2, constants::DW_OP_nop.0];
+        let expression = Expression(EndianSlice::new(&bytes, LittleEndian));
+        let mut operations = expression.operations(encoding);
+        assert_eq!(
+            operations.next(),
+            Ok(Some(Operation::Unknown {
+                opcode: constants::DwOp(0xf0),
+                data: EndianSlice::new(&bytes[1..], LittleEndian),
+            }))
+        );
+        assert_eq!(operations.next(), Ok(None));
+        assert_eq!(
+            expression.compile(encoding).unwrap_err(),
+            Error::InvalidExpression(constants::DwOp(0xf0))
+        );
+    }
+
     enum AssemblerEntry {
         Op(constants::DwOp),
         Mark(u8),
@@ -2933,35 +3470,41 @@ mod tests {
         let bytes = assemble(program);
         let bytes = EndianSlice::new(&bytes, LittleEndian);
 
-        let mut eval = Evaluation::new(bytes, encoding);
-
-        if let Some(val) = object_address {
-            eval.set_object_address(val);
-        }
-        if let Some(val) = initial_value {
-            eval.set_initial_value(val);
-        }
-        if let Some(val) = max_iterations {
-            eval.set_max_iterations(val);
+        // Check both the bytecode and the compiled expression.
+        let mut evals = vec![Evaluation::new(bytes, encoding)];
+        match Expression(bytes).compile(encoding) {
+            Ok(compiled) => evals.push(compiled.evaluation()),
+            Err(e) => assert!(expect.is_err(), "Unexpected compile error: {:?}", e),
         }
+        for mut eval in evals {
+            if let Some(val) = object_address {
+                eval.set_object_address(val);
+            }
+            if let Some(val) = initial_value {
+                eval.set_initial_value(val);
+            }
+            if let Some(val) = max_iterations {
+                eval.set_max_iterations(val);
+            }
 
-        let result = match eval.evaluate() {
-            Err(e) => Err(e),
-            Ok(r) => f(&mut eval, r),
-        };
+            let result = match eval.evaluate() {
+                Err(e) => Err(e),
+                Ok(r) => f(&mut eval, r),
+            };
 
-        match (result, expect) {
-            (Ok(EvaluationResult::Complete), Ok(pieces)) => {
-                let vec = eval.result();
-                assert_eq!(vec.len(), pieces.len());
-                for i in 0..pieces.len() {
-                    assert_eq!(vec[i], pieces[i]);
+            match (result, expect) {
+                (Ok(EvaluationResult::Complete), Ok(pieces)) => {
+                    let vec = eval.result();
+                    assert_eq!(vec.len(), pieces.len());
+                    for i in 0..pieces.len() {
+                        assert_eq!(vec[i], pieces[i]);
+                    }
                 }
+                (Err(f1), Err(f2)) => {
+                    assert_eq!(f1, f2);
+                }
+                otherwise => panic!("Unexpected result: {:?}", otherwise),
             }
-            (Err(f1), Err(f2)) => {
-                assert_eq!(f1, f2);
-            }
-            otherwise => panic!("Unexpected result: {:?}", otherwise),
         }
     }
 
@@ -3510,6 +4053,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_eval_memory_ranges() {
+        // It's nice if an operation and its arguments can fit on a single
+        // line in the test program.
+        use self::AssemblerEntry::*;
+        use crate::constants::*;
+
+        #[rustfmt::skip]
+        let program = [
+            Op(DW_OP_const2u), U16(0x1008),
+            Op(DW_OP_deref),
+            Op(DW_OP_piece), Uleb(4),
+            Op(DW_OP_const2u), U16(0x100e),
+            Op(DW_OP_deref),
+            Op(DW_OP_piece), Uleb(4),
+        ];
+        let bytes = assemble(&program);
+        let bytes = EndianSlice::new(&bytes, LittleEndian);
+        let ranges = [Range {
+            begin: 0x1000,
+            end: 0x1010,
+        }];
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_memory_ranges(&ranges);
+        assert_eq!(
+            eval.evaluate(),
+            Ok(EvaluationResult::RequiresMemory {
+                address: 0x1008,
+                size: 4,
+                space: None,
+                base_type: UnitOffset(0),
+            })
+        );
+        assert_eq!(
+            eval.resume_with_memory(Value::Generic(0x2000)),
+            Err(Error::MemoryAccessDenied(0x100e))
+        );
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_memory_ranges(&ranges);
+        eval.set_denied_memory_access(DeniedMemoryAccess::Unavailable);
+        assert!(matches!(
+            eval.evaluate(),
+            Ok(EvaluationResult::RequiresMemory { .. })
+        ));
+        assert_eq!(
+            eval.resume_with_memory(Value::Generic(0x2000)),
+            Ok(EvaluationResult::Complete)
+        );
+        assert_eq!(
+            eval.result(),
+            [Piece {
+                size_in_bits: None,
+                bit_offset: None,
+                location: Location::Empty,
+            }]
+        );
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_memory_ranges(&[]);
+        assert_eq!(eval.evaluate(), Err(Error::MemoryAccessDenied(0x1008)));
+    }
+
+    #[test]
+    fn test_eval_unknown_operation() {
+        // It's nice if an operation and its arguments can fit on a single
+        // line in the test program.
+        use self::AssemblerEntry::*;
+        use crate::constants::*;
+
+        #[rustfmt::skip]
+        let program = [
+            Op(DW_OP_lit5),
+            Op(DwOp(0xf0)), U8(3),
+            Op(DW_OP_plus_uconst), Uleb(1),
+        ];
+        let bytes = assemble(&program);
+        let bytes = EndianSlice::new(&bytes, LittleEndian);
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        assert_eq!(eval.evaluate(), Err(Error::InvalidExpression(DwOp(0xf0))));
+
+        // Multiply by the operand.
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_unknown_operation_handler(|op| {
+            assert_eq!(op.opcode(), DwOp(0xf0));
+            let operand = Value::Generic(op.operands().read_u8()?.into());
+            let value = op.pop()?;
+            let mask = op.addr_mask();
+            op.push(value.mul(operand, mask)?)
+        });
+        assert_eq!(eval.evaluate(), Ok(EvaluationResult::Complete));
+        assert_eq!(eval.value_result(), Some(Value::Generic(16)));
+    }
+
     #[test]
     fn test_eval_register() {
         // It's nice if an operation and its arguments can fit on a single
@@ -4019,6 +4658,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_eval_compiled() {
+        // It's nice if an operation and its arguments can fit on a single
+        // line in the test program.
+        use self::AssemblerEntry::*;
+        use crate::constants::*;
+
+        // Sum the integers from 1 to 10.
+        #[rustfmt::skip]
+        let program = [
+            Op(DW_OP_lit0),
+            Op(DW_OP_lit10),
+            Mark(1),
+            Op(DW_OP_dup),
+            Op(DW_OP_rot),
+            Op(DW_OP_plus),
+            Op(DW_OP_swap),
+            Op(DW_OP_lit1),
+            Op(DW_OP_minus),
+            Op(DW_OP_dup),
+            Op(DW_OP_bra), Branch(1),
+            Op(DW_OP_drop),
+        ];
+        let bytes = assemble(&program);
+        let bytes = EndianSlice::new(&bytes, LittleEndian);
+        let compiled = Expression(bytes).compile(encoding4()).unwrap();
+        assert_eq!(compiled.expression(), Expression(bytes));
+        assert_eq!(compiled.operations().count(), 11);
+
+        // Evaluate the same compiled expression more than once.
+        for _ in 0..2 {
+            let mut eval = compiled.evaluation();
+            assert_eq!(eval.evaluate(), Ok(EvaluationResult::Complete));
+            assert_eq!(eval.value_result(), Some(Value::Generic(55)));
+        }
+
+        // Branching into the middle of an operation is an error.
+        #[rustfmt::skip]
+        let program = [
+            Op(DW_OP_const1u), Mark(1), U8(0),
+            Op(DW_OP_skip), Branch(1),
+        ];
+        let bytes = assemble(&program);
+        let bytes = EndianSlice::new(&bytes, LittleEndian);
+        assert_eq!(
+            Expression(bytes).compile(encoding4()).unwrap_err(),
+            Error::BadBranchTarget(1)
+        );
+    }
+
+    #[test]
+    fn test_eval_max_call_depth() {
+        // DW_OP_call2 0x10
+        const RECURSIVE: &[u8] = &[0x98, 0x10, 0x00];
+        let bytes = EndianSlice::new(RECURSIVE, LittleEndian);
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_max_call_depth(5);
+        let mut result = eval.evaluate();
+        let mut depth = 0;
+        while let Ok(EvaluationResult::RequiresAtLocation(_)) = result {
+            result = eval.resume_with_at_location(bytes);
+            depth += 1;
+        }
+        assert_eq!(result, Err(Error::CallDepthExceeded));
+        assert_eq!(depth, 6);
+
+        // The iteration count includes operations in called expressions.
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.set_max_iterations(10);
+        let mut result = eval.evaluate();
+        while let Ok(EvaluationResult::RequiresAtLocation(_)) = result {
+            result = eval.resume_with_at_location(bytes);
+        }
+        assert_eq!(result, Err(Error::TooManyIterations));
+    }
+
+    #[test]
+    fn test_eval_trace() {
+        // DW_OP_lit4 DW_OP_deref DW_OP_lit1 DW_OP_plus DW_OP_stack_value
+        const PROGRAM: &[u8] = &[0x34, 0x06, 0x31, 0x22, 0x9f];
+        let bytes = EndianSlice::new(PROGRAM, LittleEndian);
+
+        let mut eval = Evaluation::new(bytes, encoding4());
+        eval.enable_trace();
+        let result = eval.evaluate().unwrap();
+        assert!(matches!(result, EvaluationResult::RequiresMemory { .. }));
+        let result = eval.resume_with_memory(Value::Generic(10)).unwrap();
+        assert_eq!(result, EvaluationResult::Complete);
+
+        let operation = |offset, operation, stack: &[u64]| EvaluationTraceEvent::Operation {
+            offset,
+            call_depth: 0,
+            operation,
+            stack: stack.iter().map(|v| Value::Generic(*v)).collect(),
+        };
+        assert_eq!(
+            eval.trace(),
+            &[
+                operation(0, Operation::UnsignedConstant { value: 4 }, &[]),
+                operation(
+                    1,
+                    Operation::Deref {
+                        base_type: UnitOffset(0),
+                        size: 4,
+                        space: false,
+                    },
+                    &[4]
+                ),
+                EvaluationTraceEvent::Request(EvaluationResult::RequiresMemory {
+                    address: 4,
+                    size: 4,
+                    space: None,
+                    base_type: UnitOffset(0),
+                }),
+                operation(2, Operation::UnsignedConstant { value: 1 }, &[10]),
+                operation(3, Operation::Plus, &[10, 1]),
+                operation(4, Operation::StackValue, &[11]),
+            ]
+        );
+    }
+
     #[test]
     fn test_eval_typed_stack() {
         use self::AssemblerEntry::*;
@@ -0,0 +1,418 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::common::{
+    DebugAddrBase, DebugAddrIndex, DebugInfoOffset, DebugLineOffset, DebugLocListsBase,
+    DebugLocListsIndex, DebugMacinfoOffset, DebugMacroOffset, DebugRngListsBase,
+    DebugRngListsIndex, DebugStrOffsetsBase, LocationListsOffset, RawRangeListsOffset,
+};
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::{
+    AttributeValue, DebuggingInformationEntry, EndianArcSlice, EntriesCursor, Error, Expression,
+    Reader, ReaderOffset, Result, UnitOffset, UnitRef,
+};
+
+/// An owned copy of a Debugging Information Entry (DIE) and its children.
+///
+/// Unlike `DebuggingInformationEntry`, this does not borrow from the unit or
+/// the section data, so it can be kept after the section data has been
+/// dropped, or sent to another thread.
+///
+/// Returned by `UnitRef::owned_entries` and `UnitRef::owned_subtree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedEntry<Endian: Endianity> {
+    offset: UnitOffset,
+    tag: constants::DwTag,
+    attrs: Vec<OwnedAttribute<Endian>>,
+    children: Vec<OwnedEntry<Endian>>,
+}
+
+impl<Endian: Endianity> OwnedEntry<Endian> {
+    /// Get this entry's offset within its unit.
+    pub fn offset(&self) -> UnitOffset {
+        self.offset
+    }
+
+    /// Get this entry's `DW_TAG_whatever` tag.
+    pub fn tag(&self) -> constants::DwTag {
+        self.tag
+    }
+
+    /// Get this entry's attributes.
+    pub fn attrs(&self) -> &[OwnedAttribute<Endian>] {
+        &self.attrs
+    }
+
+    /// Find the first attribute in this entry which has the given name.
+    pub fn attr(&self, name: constants::DwAt) -> Option<&OwnedAttribute<Endian>> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+
+    /// Find the first attribute in this entry which has the given name,
+    /// and return its value.
+    pub fn attr_value(
+        &self,
+        name: constants::DwAt,
+    ) -> Option<&AttributeValue<EndianArcSlice<Endian>>> {
+        self.attr(name).map(OwnedAttribute::value)
+    }
+
+    /// Get this entry's children.
+    pub fn children(&self) -> &[OwnedEntry<Endian>] {
+        &self.children
+    }
+}
+
+/// An owned copy of an attribute in an `OwnedEntry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedAttribute<Endian: Endianity> {
+    name: constants::DwAt,
+    value: AttributeValue<EndianArcSlice<Endian>>,
+}
+
+impl<Endian: Endianity> OwnedAttribute<Endian> {
+    /// Get this attribute's name.
+    pub fn name(&self) -> constants::DwAt {
+        self.name
+    }
+
+    /// Get this attribute's value.
+    ///
+    /// This is the normalized value, as returned by `Attribute::value`.
+    /// Values that are references to strings in other sections have been
+    /// resolved and are returned as `AttributeValue::String`.
+    pub fn value(&self) -> &AttributeValue<EndianArcSlice<Endian>> {
+        &self.value
+    }
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Return an owned copy of all of the entries in this unit.
+    ///
+    /// The returned entry is the root of the unit.
+    pub fn owned_entries(&self) -> Result<OwnedEntry<R::Endian>> {
+        self.owned_tree(self.entries())
+    }
+
+    /// Return an owned copy of the entry at the given offset and all of
+    /// its children.
+    pub fn owned_subtree(&self, offset: UnitOffset<R::Offset>) -> Result<OwnedEntry<R::Endian>> {
+        self.owned_tree(self.entries_at_offset(offset)?)
+    }
+
+    /// Copy the entry at the cursor and its children, using an explicit stack
+    /// of the entries that are still being built instead of recursion.
+    fn owned_tree(&self, mut entries: EntriesCursor<'_, '_, R>) -> Result<OwnedEntry<R::Endian>> {
+        entries.next_entry()?;
+        let root = entries.current().ok_or(Error::UnexpectedNull)?;
+        let mut stack = vec![self.owned_entry(root)?];
+        let mut depth = 0;
+        while let Some((delta_depth, entry)) = entries.next_dfs()? {
+            depth += delta_depth;
+            if depth <= 0 {
+                break;
+            }
+            // Finish the previous entry and any ancestors that have ended.
+            for _ in delta_depth..=0 {
+                finish_owned_entry(&mut stack);
+            }
+            stack.push(self.owned_entry(entry)?);
+        }
+        while stack.len() > 1 {
+            finish_owned_entry(&mut stack);
+        }
+        stack.pop().ok_or(Error::UnexpectedNull)
+    }
+
+    fn owned_entry(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<OwnedEntry<R::Endian>> {
+        let offset = UnitOffset(owned_offset(entry.offset().0)?);
+        let tag = entry.tag();
+        let mut attrs = Vec::new();
+        let mut iter = entry.attrs();
+        while let Some(attr) = iter.next()? {
+            attrs.push(OwnedAttribute {
+                name: attr.name(),
+                value: self.owned_value(attr.value())?,
+            });
+        }
+
+        Ok(OwnedEntry {
+            offset,
+            tag,
+            attrs,
+            children: Vec::new(),
+        })
+    }
+
+    fn owned_value(
+        &self,
+        value: AttributeValue<R>,
+    ) -> Result<AttributeValue<EndianArcSlice<R::Endian>>> {
+        Ok(match value {
+            AttributeValue::String(_)
+            | AttributeValue::DebugStrRef(_)
+            | AttributeValue::DebugStrRefSup(_)
+            | AttributeValue::DebugStrOffsetsIndex(_)
+            | AttributeValue::DebugLineStrRef(_) => {
+                AttributeValue::String(owned_reader(&self.attr_string(value)?)?)
+            }
+            AttributeValue::Addr(val) => AttributeValue::Addr(val),
+            AttributeValue::Block(val) => AttributeValue::Block(owned_reader(&val)?),
+            AttributeValue::Data1(val) => AttributeValue::Data1(val),
+            AttributeValue::Data2(val) => AttributeValue::Data2(val),
+            AttributeValue::Data4(val) => AttributeValue::Data4(val),
+            AttributeValue::Data8(val) => AttributeValue::Data8(val),
+            AttributeValue::Data16(val) => AttributeValue::Data16(val),
+            AttributeValue::Sdata(val) => AttributeValue::Sdata(val),
+            AttributeValue::Udata(val) => AttributeValue::Udata(val),
+            AttributeValue::Exprloc(val) => {
+                AttributeValue::Exprloc(Expression(owned_reader(&val.0)?))
+            }
+            AttributeValue::Flag(val) => AttributeValue::Flag(val),
+            AttributeValue::SecOffset(val) => AttributeValue::SecOffset(owned_offset(val)?),
+            AttributeValue::DebugAddrBase(val) => {
+                AttributeValue::DebugAddrBase(DebugAddrBase(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugAddrIndex(val) => {
+                AttributeValue::DebugAddrIndex(DebugAddrIndex(owned_offset(val.0)?))
+            }
+            AttributeValue::UnitRef(val) => {
+                AttributeValue::UnitRef(UnitOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugInfoRef(val) => {
+                AttributeValue::DebugInfoRef(DebugInfoOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugInfoRefSup(val) => {
+                AttributeValue::DebugInfoRefSup(DebugInfoOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugLineRef(val) => {
+                AttributeValue::DebugLineRef(DebugLineOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::LocationListsRef(val) => {
+                AttributeValue::LocationListsRef(LocationListsOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugLocListsBase(val) => {
+                AttributeValue::DebugLocListsBase(DebugLocListsBase(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugLocListsIndex(val) => {
+                AttributeValue::DebugLocListsIndex(DebugLocListsIndex(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugMacinfoRef(val) => {
+                AttributeValue::DebugMacinfoRef(DebugMacinfoOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugMacroRef(val) => {
+                AttributeValue::DebugMacroRef(DebugMacroOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::RangeListsRef(val) => {
+                AttributeValue::RangeListsRef(RawRangeListsOffset(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugRngListsBase(val) => {
+                AttributeValue::DebugRngListsBase(DebugRngListsBase(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugRngListsIndex(val) => {
+                AttributeValue::DebugRngListsIndex(DebugRngListsIndex(owned_offset(val.0)?))
+            }
+            AttributeValue::DebugTypesRef(val) => AttributeValue::DebugTypesRef(val),
+            AttributeValue::DebugStrOffsetsBase(val) => {
+                AttributeValue::DebugStrOffsetsBase(DebugStrOffsetsBase(owned_offset(val.0)?))
+            }
+            AttributeValue::Encoding(val) => AttributeValue::Encoding(val),
+            AttributeValue::DecimalSign(val) => AttributeValue::DecimalSign(val),
+            AttributeValue::Endianity(val) => AttributeValue::Endianity(val),
+            AttributeValue::Accessibility(val) => AttributeValue::Accessibility(val),
+            AttributeValue::Visibility(val) => AttributeValue::Visibility(val),
+            AttributeValue::Virtuality(val) => AttributeValue::Virtuality(val),
+            AttributeValue::Language(val) => AttributeValue::Language(val),
+            AttributeValue::AddressClass(val) => AttributeValue::AddressClass(val),
+            AttributeValue::IdentifierCase(val) => AttributeValue::IdentifierCase(val),
+            AttributeValue::CallingConvention(val) => AttributeValue::CallingConvention(val),
+            AttributeValue::Inline(val) => AttributeValue::Inline(val),
+            AttributeValue::Ordering(val) => AttributeValue::Ordering(val),
+            AttributeValue::FileIndex(val) => AttributeValue::FileIndex(val),
+            AttributeValue::DwoId(val) => AttributeValue::DwoId(val),
+            AttributeValue::Unknown(val) => AttributeValue::Unknown(owned_reader(&val)?),
+        })
+    }
+}
+
+/// Move the last entry in `stack` to the children of its parent.
+fn finish_owned_entry<Endian: Endianity>(stack: &mut Vec<OwnedEntry<Endian>>) {
+    if let Some(entry) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(entry);
+        }
+    }
+}
+
+fn owned_reader<R: Reader>(reader: &R) -> Result<EndianArcSlice<R::Endian>> {
+    Ok(EndianArcSlice::new(
+        Arc::from(&*reader.to_slice()?),
+        reader.endian(),
+    ))
+}
+
+fn owned_offset<Offset: ReaderOffset>(offset: Offset) -> Result<usize> {
+    usize::from_u64(offset.into_u64())
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::{DebugAbbrev, DebugInfo, DebugStr, Dwarf};
+    use crate::write::{self, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_owned_entries() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+        let name = dwarf.strings.add("main");
+        unit.get_mut(subprogram).set(
+            constants::DW_AT_name,
+            write::AttributeValue::StringRef(name),
+        );
+        let variable = unit.add(subprogram, constants::DW_TAG_variable);
+        unit.get_mut(variable).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"x".to_vec()),
+        );
+        unit.get_mut(variable).set(
+            constants::DW_AT_type,
+            write::AttributeValue::UnitRef(subprogram),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let owned = {
+            let debug_abbrev = sections.debug_abbrev.slice().to_vec();
+            let debug_info = sections.debug_info.slice().to_vec();
+            let debug_str = sections.debug_str.slice().to_vec();
+            let read_dwarf = Dwarf {
+                debug_abbrev: DebugAbbrev::new(&debug_abbrev, LittleEndian),
+                debug_info: DebugInfo::new(&debug_info, LittleEndian),
+                debug_str: DebugStr::new(&debug_str, LittleEndian),
+                ..Default::default()
+            };
+            let header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(header).unwrap();
+            let read_unit = read_unit.unit_ref(&read_dwarf);
+            let owned = read_unit.owned_entries().unwrap();
+
+            let subtree_offset = owned.children()[0].offset();
+            let subtree = read_unit
+                .owned_subtree(UnitOffset(subtree_offset.0))
+                .unwrap();
+            assert_eq!(&subtree, &owned.children()[0]);
+            owned
+        };
+
+        fn assert_send_sync<T: Send + Sync + 'static>(_: &T) {}
+        assert_send_sync(&owned);
+
+        assert_eq!(owned.tag(), constants::DW_TAG_compile_unit);
+        assert_eq!(owned.children().len(), 1);
+        let owned_subprogram = &owned.children()[0];
+        assert_eq!(owned_subprogram.tag(), constants::DW_TAG_subprogram);
+        let name = match owned_subprogram.attr_value(constants::DW_AT_name) {
+            Some(AttributeValue::String(name)) => name,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        assert_eq!(name.to_slice().unwrap(), &b"main"[..]);
+
+        assert_eq!(owned_subprogram.children().len(), 1);
+        let owned_variable = &owned_subprogram.children()[0];
+        assert!(owned_variable.children().is_empty());
+        assert_eq!(owned_variable.attrs().len(), 2);
+        assert_eq!(owned_variable.attrs()[0].name(), constants::DW_AT_name);
+        let name = match owned_variable.attr_value(constants::DW_AT_name) {
+            Some(AttributeValue::String(name)) => name,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        assert_eq!(name.to_slice().unwrap(), &b"x"[..]);
+        assert_eq!(
+            owned_variable.attr_value(constants::DW_AT_type),
+            Some(&AttributeValue::UnitRef(owned_subprogram.offset()))
+        );
+        assert_eq!(owned_variable.attr_value(constants::DW_AT_byte_size), None);
+    }
+
+    #[test]
+    fn test_owned_entries_nesting() {
+        #[rustfmt::skip]
+        let debug_abbrev = [
+            1, 0x11, 1, 0, 0, // DW_TAG_compile_unit, children
+            2, 0x0b, 1, 0, 0, // DW_TAG_lexical_block, children
+            3, 0x34, 0, 0, 0, // DW_TAG_variable, no children
+            0,
+        ];
+        let read_dwarf = |entries: &[u8]| {
+            let mut debug_info = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 8];
+            debug_info.extend_from_slice(entries);
+            let length = (debug_info.len() - 4) as u32;
+            debug_info[..4].copy_from_slice(&length.to_le_bytes());
+            let read_dwarf = Dwarf {
+                debug_abbrev: DebugAbbrev::new(&debug_abbrev[..], LittleEndian),
+                debug_info: DebugInfo::new(&debug_info[..], LittleEndian),
+                ..Default::default()
+            };
+            let header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(header).unwrap();
+            let read_unit = read_unit.unit_ref(&read_dwarf);
+            let owned = read_unit.owned_entries().unwrap();
+            let subtrees = (12..14)
+                .map(|offset| read_unit.owned_subtree(UnitOffset(offset)).unwrap())
+                .collect::<Vec<_>>();
+            (owned, subtrees)
+        };
+
+        // 0x0b: compile unit
+        //   0x0c: block
+        //     0x0d: block
+        //       0x0e: variable
+        //     0x10: variable
+        //   0x12: variable
+        let (owned, subtrees) = read_dwarf(&[1, 2, 2, 3, 0, 3, 0, 3, 0]);
+        let offsets = |entry: &OwnedEntry<_>| {
+            entry
+                .children()
+                .iter()
+                .map(|child| child.offset().0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offsets(&owned), [0x0c, 0x12]);
+        assert_eq!(&subtrees[0], &owned.children()[0]);
+        assert_eq!(offsets(&subtrees[0]), [0x0d, 0x10]);
+        assert_eq!(offsets(&subtrees[1]), [0x0e]);
+        assert!(subtrees[1].children()[0].children().is_empty());
+
+        // Deep nesting must not overflow the stack.
+        let depth = 10_000;
+        let mut entries = vec![1];
+        entries.resize(1 + depth, 2);
+        entries.resize(2 + 2 * depth, 0);
+        let (owned, _) = read_dwarf(&entries);
+        let mut entry = &owned;
+        let mut count = 0;
+        while let Some(child) = entry.children().first() {
+            entry = child;
+            count += 1;
+        }
+        assert_eq!(count, depth);
+    }
+}
@@ -1,7 +1,13 @@
-use crate::common::{DebugInfoOffset, SectionId};
+use alloc::collections::btree_map::{self, BTreeMap};
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::common::{DebugInfoOffset, Format, SectionId};
 use crate::endianity::Endianity;
-use crate::read::lookup::{DebugLookup, LookupEntryIter, PubStuffEntry, PubStuffParser};
-use crate::read::{EndianSlice, Reader, Result, Section, UnitOffset};
+use crate::read::lookup::{
+    DebugLookup, LookupEntryIter, LookupSetIter, PubStuffEntry, PubStuffHeader, PubStuffParser,
+};
+use crate::read::{DebugPubTypes, EndianSlice, Reader, ReaderOffset, Result, Section, UnitOffset};
 
 /// A single parsed pubname.
 #[derive(Debug, Clone)]
@@ -92,6 +98,14 @@ impl<R: Reader> DebugPubNames<R> {
     pub fn items(&self) -> PubNamesEntryIter<R> {
         PubNamesEntryIter(self.0.items())
     }
+
+    /// Iterate the sets of pubnames in the `.debug_pubnames` section.
+    ///
+    /// Each set contains the pubnames for a single unit. The pubnames in a set
+    /// are not parsed unless `PubNamesSet::entries` is used.
+    pub fn sets(&self) -> PubNamesSetIter<R> {
+        PubNamesSetIter(self.0.sets())
+    }
 }
 
 impl<R: Reader> Section<R> for DebugPubNames<R> {
@@ -139,3 +153,200 @@ impl<R: Reader> fallible_iterator::FallibleIterator for PubNamesEntryIter<R> {
         self.0.next()
     }
 }
+
+result_iterator!([R: Reader] PubNamesEntryIter<R>, PubNamesEntry<R>);
+
+/// A set of pubnames in the `.debug_pubnames` section.
+///
+/// All of the pubnames in a set belong to a single unit.
+#[derive(Debug, Clone)]
+pub struct PubNamesSet<R: Reader> {
+    header: PubStuffHeader<R::Offset>,
+    entries: PubNamesEntryIter<R>,
+}
+
+impl<R: Reader> PubNamesSet<R> {
+    /// Return the DWARF format of this set.
+    pub fn format(&self) -> Format {
+        self.header.format()
+    }
+
+    /// Return the length of this set, not including the length field.
+    pub fn length(&self) -> R::Offset {
+        self.header.length()
+    }
+
+    /// Return the version of this set.
+    pub fn version(&self) -> u16 {
+        self.header.version()
+    }
+
+    /// Return the offset into the .debug_info section for the header of the
+    /// compilation unit which contains the pubnames in this set.
+    pub fn unit_header_offset(&self) -> DebugInfoOffset<R::Offset> {
+        self.header.unit_offset()
+    }
+
+    /// Return the length of the compilation unit which contains the pubnames
+    /// in this set.
+    pub fn unit_length(&self) -> R::Offset {
+        self.header.unit_length()
+    }
+
+    /// Iterate the pubnames in this set.
+    pub fn entries(&self) -> PubNamesEntryIter<R> {
+        self.entries.clone()
+    }
+}
+
+/// An iterator over the sets of pubnames in a `.debug_pubnames` section.
+///
+/// Can be [used with
+/// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+#[derive(Debug, Clone)]
+pub struct PubNamesSetIter<R: Reader>(LookupSetIter<R, PubStuffParser<R, PubNamesEntry<R>>>);
+
+impl<R: Reader> PubNamesSetIter<R> {
+    /// Advance the iterator and return the next set.
+    ///
+    /// Returns `Ok(None)` when iteration is complete. If an error occurs
+    /// while parsing the next set header, then this error is returned as
+    /// `Err(e)`, and all subsequent calls return `Ok(None)`.
+    pub fn next(&mut self) -> Result<Option<PubNamesSet<R>>> {
+        Ok(self.0.next()?.map(|(header, entries)| PubNamesSet {
+            header,
+            entries: PubNamesEntryIter(entries),
+        }))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for PubNamesSetIter<R> {
+    type Item = PubNamesSet<R>;
+    type Error = crate::read::Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        PubNamesSetIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] PubNamesSetIter<R>, PubNamesSet<R>);
+
+/// The location of a debugging information entry in a `PubNameMap`.
+///
+/// This is the offset of the unit header in `.debug_info`, and the offset of
+/// the entry within that unit.
+pub type PubNameLocation<T = usize> = (DebugInfoOffset<T>, UnitOffset<T>);
+
+/// A map from names to the debugging information entries with that name.
+///
+/// This is built from the `.debug_pubnames` and `.debug_pubtypes` sections,
+/// and allows names to be found without scanning the sections for each query.
+/// A name may refer to multiple entries.
+#[derive(Debug, Clone)]
+pub struct PubNameMap<T = usize> {
+    names: BTreeMap<Vec<u8>, Vec<PubNameLocation<T>>>,
+}
+
+impl<T> Default for PubNameMap<T> {
+    fn default() -> Self {
+        PubNameMap {
+            names: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: ReaderOffset> PubNameMap<T> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add all of the names in a `.debug_pubnames` section to the map.
+    pub fn add_pubnames<R: Reader<Offset = T>>(
+        &mut self,
+        section: &DebugPubNames<R>,
+    ) -> Result<()> {
+        let mut items = section.items();
+        while let Some(item) = items.next()? {
+            self.insert(item.name(), item.unit_header_offset(), item.die_offset())?;
+        }
+        Ok(())
+    }
+
+    /// Add all of the names in a `.debug_pubtypes` section to the map.
+    pub fn add_pubtypes<R: Reader<Offset = T>>(
+        &mut self,
+        section: &DebugPubTypes<R>,
+    ) -> Result<()> {
+        let mut items = section.items();
+        while let Some(item) = items.next()? {
+            self.insert(item.name(), item.unit_header_offset(), item.die_offset())?;
+        }
+        Ok(())
+    }
+
+    fn insert<R: Reader<Offset = T>>(
+        &mut self,
+        name: &R,
+        unit_header_offset: DebugInfoOffset<T>,
+        die_offset: UnitOffset<T>,
+    ) -> Result<()> {
+        let name = name.to_slice()?.into_owned();
+        self.names
+            .entry(name)
+            .or_default()
+            .push((unit_header_offset, die_offset));
+        Ok(())
+    }
+
+    /// Find the entries with the given name.
+    ///
+    /// The entries are in the order that they were added.
+    pub fn find(&self, name: &[u8]) -> &[PubNameLocation<T>] {
+        self.names.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Return the number of distinct names in the map.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Return true if the map contains no names.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the map.
+    ///
+    /// This does not include the overhead of the internal nodes of the map.
+    pub fn allocated_bytes(&self) -> usize {
+        self.names
+            .iter()
+            .map(|(name, locations)| {
+                mem::size_of::<(Vec<u8>, Vec<PubNameLocation<T>>)>()
+                    + name.capacity()
+                    + locations.capacity() * mem::size_of::<PubNameLocation<T>>()
+            })
+            .sum()
+    }
+
+    /// Iterate over the names in the map, in sorted order.
+    pub fn iter(&self) -> PubNameMapIter<'_, T> {
+        PubNameMapIter(self.names.iter())
+    }
+}
+
+/// An iterator over the names in a `PubNameMap`.
+#[derive(Debug, Clone)]
+pub struct PubNameMapIter<'a, T>(btree_map::Iter<'a, Vec<u8>, Vec<PubNameLocation<T>>>);
+
+impl<'a, T> Iterator for PubNameMapIter<'a, T> {
+    type Item = (&'a [u8], &'a [PubNameLocation<T>]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .next()
+            .map(|(name, entries)| (name.as_slice(), entries.as_slice()))
+    }
+}
@@ -1,6 +1,8 @@
-use crate::common::{DebugInfoOffset, SectionId};
+use crate::common::{DebugInfoOffset, Format, SectionId};
 use crate::endianity::Endianity;
-use crate::read::lookup::{DebugLookup, LookupEntryIter, PubStuffEntry, PubStuffParser};
+use crate::read::lookup::{
+    DebugLookup, LookupEntryIter, LookupSetIter, PubStuffEntry, PubStuffHeader, PubStuffParser,
+};
 use crate::read::{EndianSlice, Reader, Result, Section, UnitOffset};
 
 /// A single parsed pubtype.
@@ -92,6 +94,14 @@ impl<R: Reader> DebugPubTypes<R> {
     pub fn items(&self) -> PubTypesEntryIter<R> {
         PubTypesEntryIter(self.0.items())
     }
+
+    /// Iterate the sets of pubtypes in the `.debug_pubtypes` section.
+    ///
+    /// Each set contains the pubtypes for a single unit. The pubtypes in a set
+    /// are not parsed unless `PubTypesSet::entries` is used.
+    pub fn sets(&self) -> PubTypesSetIter<R> {
+        PubTypesSetIter(self.0.sets())
+    }
 }
 
 impl<R: Reader> Section<R> for DebugPubTypes<R> {
@@ -139,3 +149,81 @@ impl<R: Reader> fallible_iterator::FallibleIterator for PubTypesEntryIter<R> {
         self.0.next()
     }
 }
+
+result_iterator!([R: Reader] PubTypesEntryIter<R>, PubTypesEntry<R>);
+
+/// A set of pubtypes in the `.debug_pubtypes` section.
+///
+/// All of the pubtypes in a set belong to a single unit.
+#[derive(Debug, Clone)]
+pub struct PubTypesSet<R: Reader> {
+    header: PubStuffHeader<R::Offset>,
+    entries: PubTypesEntryIter<R>,
+}
+
+impl<R: Reader> PubTypesSet<R> {
+    /// Return the DWARF format of this set.
+    pub fn format(&self) -> Format {
+        self.header.format()
+    }
+
+    /// Return the length of this set, not including the length field.
+    pub fn length(&self) -> R::Offset {
+        self.header.length()
+    }
+
+    /// Return the version of this set.
+    pub fn version(&self) -> u16 {
+        self.header.version()
+    }
+
+    /// Return the offset into the .debug_info section for the header of the
+    /// compilation unit which contains the pubtypes in this set.
+    pub fn unit_header_offset(&self) -> DebugInfoOffset<R::Offset> {
+        self.header.unit_offset()
+    }
+
+    /// Return the length of the compilation unit which contains the pubtypes
+    /// in this set.
+    pub fn unit_length(&self) -> R::Offset {
+        self.header.unit_length()
+    }
+
+    /// Iterate the pubtypes in this set.
+    pub fn entries(&self) -> PubTypesEntryIter<R> {
+        self.entries.clone()
+    }
+}
+
+/// An iterator over the sets of pubtypes in a `.debug_pubtypes` section.
+///
+/// Can be [used with
+/// `FallibleIterator`](./index.html#using-with-fallibleiterator).
+#[derive(Debug, Clone)]
+pub struct PubTypesSetIter<R: Reader>(LookupSetIter<R, PubStuffParser<R, PubTypesEntry<R>>>);
+
+impl<R: Reader> PubTypesSetIter<R> {
+    /// Advance the iterator and return the next set.
+    ///
+    /// Returns `Ok(None)` when iteration is complete. If an error occurs
+    /// while parsing the next set header, then this error is returned as
+    /// `Err(e)`, and all subsequent calls return `Ok(None)`.
+    pub fn next(&mut self) -> Result<Option<PubTypesSet<R>>> {
+        Ok(self.0.next()?.map(|(header, entries)| PubTypesSet {
+            header,
+            entries: PubTypesEntryIter(entries),
+        }))
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for PubTypesSetIter<R> {
+    type Item = PubTypesSet<R>;
+    type Error = crate::read::Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        PubTypesSetIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] PubTypesSetIter<R>, PubTypesSet<R>);
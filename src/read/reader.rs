@@ -459,6 +459,7 @@ pub trait Reader: Debug + Clone {
             2 => self.read_u16().map(u64::from),
             4 => self.read_u32().map(u64::from),
             8 => self.read_u64(),
+            3 | 5 | 6 | 7 => self.read_uint(usize::from(address_size)),
             otherwise => Err(Error::UnsupportedAddressSize(otherwise)),
         }
     }
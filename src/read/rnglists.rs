@@ -1,6 +1,6 @@
 use crate::common::{
     DebugAddrBase, DebugAddrIndex, DebugRngListsBase, DebugRngListsIndex, DwarfFileType, Encoding,
-    RangeListsOffset, SectionId,
+    RangeListsOffset, SectionId, TombstoneFilter,
 };
 use crate::constants;
 use crate::endianity::Endianity;
@@ -302,6 +302,9 @@ enum RangeListsFormat {
 ///
 /// This iterator does not perform any processing of the range entries,
 /// such as handling base addresses.
+///
+/// Use `RngListIter`, as returned by `Dwarf::ranges`, to iterate over the entries
+/// with base address selection and the unit base address already applied.
 #[derive(Debug)]
 pub struct RawRngListIter<R: Reader> {
     input: R,
@@ -464,6 +467,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for RawRngListIter<R> {
     }
 }
 
+result_iterator!([R: Reader] RawRngListIter<R>, RawRngListEntry<R::Offset>);
+
 /// An iterator over an address range list.
 ///
 /// This iterator internally handles processing of base addresses and different
@@ -475,6 +480,7 @@ pub struct RngListIter<R: Reader> {
     base_address: u64,
     debug_addr: DebugAddr<R>,
     debug_addr_base: DebugAddrBase<R::Offset>,
+    tombstones: TombstoneFilter,
 }
 
 impl<R: Reader> RngListIter<R> {
@@ -490,9 +496,17 @@ impl<R: Reader> RngListIter<R> {
             base_address,
             debug_addr,
             debug_addr_base,
+            tombstones: TombstoneFilter::default(),
         }
     }
 
+    /// Set the filter for recognizing additional tombstone addresses.
+    ///
+    /// Entries that begin at a tombstone address are skipped.
+    pub fn set_tombstone_filter(&mut self, tombstones: TombstoneFilter) {
+        self.tombstones = tombstones;
+    }
+
     #[inline]
     fn get_address(&self, index: DebugAddrIndex<R::Offset>) -> Result<u64> {
         self.debug_addr
@@ -569,7 +583,11 @@ impl<R: Reader> RngListIter<R> {
             }
         };
 
-        if range.begin == tombstone {
+        if range.begin == tombstone
+            || self
+                .tombstones
+                .is_tombstone(range.begin, self.raw.encoding.address_size)
+        {
             return Ok(None);
         }
 
@@ -592,6 +610,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for RngListIter<R> {
     }
 }
 
+result_iterator!([R: Reader] RngListIter<R>, Range);
+
 /// A raw address range from the `.debug_ranges` section.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct RawRange {
@@ -630,6 +650,7 @@ impl RawRange {
 
 /// An address range from the `.debug_ranges`, `.debug_rnglists`, or `.debug_aranges` sections.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Range {
     /// The beginning address of the range.
     pub begin: u64,
@@ -1429,6 +1450,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ranges_tombstone_filter() {
+        #[rustfmt::skip]
+        let section = Section::with_endian(Endian::Little)
+            // A range at address 0.
+            .L32(0).L32(0x10)
+            // A range at -2, which is always a tombstone.
+            .L32(0xffff_fffe).L32(0xffff_ffff)
+            // A range below the minimum address.
+            .L32(0x100).L32(0x200)
+            // A valid range.
+            .L32(0x0100_0000).L32(0x0100_0100)
+            // A range list end.
+            .L32(0).L32(0);
+
+        let buf = section.get_contents().unwrap();
+        let debug_ranges = DebugRanges::new(&buf, LittleEndian);
+        let debug_rnglists = DebugRngLists::new(&[], LittleEndian);
+        let rnglists = RangeLists::new(debug_ranges, debug_rnglists);
+        let debug_addr = &DebugAddr::from(EndianSlice::new(&[], LittleEndian));
+        let debug_addr_base = DebugAddrBase(0);
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+
+        let mut ranges = rnglists
+            .ranges(
+                RangeListsOffset(0x0),
+                encoding,
+                0,
+                debug_addr,
+                debug_addr_base,
+            )
+            .unwrap();
+        ranges.set_tombstone_filter(TombstoneFilter {
+            zero: true,
+            max: true,
+            min_address: 0x1000,
+        });
+        assert_eq!(
+            ranges.next(),
+            Ok(Some(Range {
+                begin: 0x0100_0000,
+                end: 0x0100_0100,
+            }))
+        );
+        assert_eq!(ranges.next(), Ok(None));
+
+        // By default, the ranges at 0 and below the minimum address are returned.
+        let mut ranges = rnglists
+            .ranges(
+                RangeListsOffset(0x0),
+                encoding,
+                0,
+                debug_addr,
+                debug_addr_base,
+            )
+            .unwrap();
+        assert_eq!(
+            ranges.next(),
+            Ok(Some(Range {
+                begin: 0,
+                end: 0x10
+            }))
+        );
+        assert_eq!(
+            ranges.next(),
+            Ok(Some(Range {
+                begin: 0x100,
+                end: 0x200,
+            }))
+        );
+    }
+
     #[test]
     fn test_get_offset() {
         for format in [Format::Dwarf32, Format::Dwarf64] {
@@ -0,0 +1,329 @@
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::common::DebugInfoOffset;
+use crate::constants;
+use crate::read::{Dwarf, Error, Range, Reader, ReaderOffset, Result, Unit, UnitOffset};
+
+/// A Debugging Information Entry (DIE) whose address ranges cover an address.
+///
+/// Returned by `Dwarf::dies_at_address` and `ScopeIndex::dies_at_address`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope<T = usize> {
+    /// The offset of the header of the unit that contains the entry.
+    pub unit_offset: DebugInfoOffset<T>,
+    /// The offset of the entry within its unit.
+    pub offset: UnitOffset<T>,
+    /// The tag of the entry.
+    pub tag: constants::DwTag,
+}
+
+#[derive(Debug, Clone)]
+struct ScopeNode<T> {
+    scope: Scope<T>,
+    depth: usize,
+    parent: Option<usize>,
+}
+
+/// An index of the address ranges of the Debugging Information Entries (DIEs)
+/// in the `.debug_info` section.
+///
+/// This allows efficiently finding the nesting chain of DIEs that cover an
+/// address. Use `Dwarf::scope_index` to build the index.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeIndex<T = usize> {
+    nodes: Vec<ScopeNode<T>>,
+    // Sorted, non-overlapping address ranges, each mapped to the deepest node
+    // that covers the range.
+    ranges: Vec<(Range, usize)>,
+}
+
+impl<T: ReaderOffset> ScopeIndex<T> {
+    /// Return the chain of entries whose address ranges cover the given address.
+    ///
+    /// The chain begins with the root entry of the unit, and each following
+    /// entry is nested within the previous entry. Entries without address ranges,
+    /// such as namespaces, are not included, except for the unit's root entry.
+    ///
+    /// Returns an empty chain if no entries cover the address.
+    pub fn dies_at_address(&self, address: u64) -> Vec<Scope<T>> {
+        let mut chain = Vec::new();
+        let index = self
+            .ranges
+            .partition_point(|(range, _)| range.begin <= address);
+        if index == 0 {
+            return chain;
+        }
+        let (range, mut node) = self.ranges[index - 1];
+        if address >= range.end {
+            return chain;
+        }
+        loop {
+            chain.push(self.nodes[node].scope);
+            match self.nodes[node].parent {
+                Some(parent) => node = parent,
+                None => break,
+            }
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the index.
+    pub fn allocated_bytes(&self) -> usize {
+        self.nodes.capacity() * mem::size_of::<ScopeNode<T>>()
+            + self.ranges.capacity() * mem::size_of::<(Range, usize)>()
+    }
+
+    fn add_unit<R>(
+        &mut self,
+        dwarf: &Dwarf<R>,
+        unit: &Unit<R>,
+        unit_offset: DebugInfoOffset<T>,
+        ranges: &mut Vec<(Range, usize)>,
+    ) -> Result<()>
+    where
+        R: Reader<Offset = T>,
+    {
+        // The nodes of the ancestors of the current entry.
+        let mut parents: Vec<usize> = Vec::new();
+        let mut depth = 0;
+        let mut entries = unit.entries();
+        while let Some((delta_depth, entry)) = entries.next_dfs()? {
+            depth += delta_depth;
+            if depth < 0 {
+                break;
+            }
+            let depth = depth as usize;
+            while parents
+                .last()
+                .map_or(false, |&parent| self.nodes[parent].depth >= depth)
+            {
+                parents.pop();
+            }
+
+            let mut entry_ranges = dwarf.die_ranges(unit, entry)?;
+            let node = self.nodes.len();
+            let mut has_ranges = false;
+            while let Some(range) = entry_ranges.next()? {
+                if range.begin < range.end {
+                    ranges.push((range, node));
+                    has_ranges = true;
+                }
+            }
+            // Always add the root entry, so that the chain begins with the unit.
+            if has_ranges || depth == 0 {
+                self.nodes.push(ScopeNode {
+                    scope: Scope {
+                        unit_offset,
+                        offset: entry.offset(),
+                        tag: entry.tag(),
+                    },
+                    depth,
+                    parent: parents.last().copied(),
+                });
+                parents.push(node);
+            }
+        }
+        Ok(())
+    }
+
+    fn build<R>(dwarf: &Dwarf<R>, address: Option<u64>) -> Result<Self>
+    where
+        R: Reader<Offset = T>,
+    {
+        let mut index = ScopeIndex {
+            nodes: Vec::new(),
+            ranges: Vec::new(),
+        };
+        let mut ranges = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit_offset = header
+                .offset()
+                .as_debug_info_offset()
+                .ok_or(Error::UnsupportedOffset)?;
+            let unit = dwarf.unit(header)?;
+            if let Some(address) = address {
+                // Skip units that have ranges which don't cover the address.
+                let mut unit_ranges = dwarf.unit_ranges(&unit)?;
+                let mut has_ranges = false;
+                let mut covered = false;
+                while let Some(range) = unit_ranges.next()? {
+                    has_ranges = true;
+                    if range.begin <= address && address < range.end {
+                        covered = true;
+                        break;
+                    }
+                }
+                if has_ranges && !covered {
+                    continue;
+                }
+            }
+            index.add_unit(dwarf, &unit, unit_offset, &mut ranges)?;
+        }
+
+        // Split the ranges into non-overlapping ranges, and map each one to
+        // the deepest node that covers it.
+        let mut events = Vec::with_capacity(ranges.len() * 2);
+        for (range, node) in ranges {
+            events.push((range.begin, true, node));
+            events.push((range.end, false, node));
+        }
+        events.sort_unstable();
+        let mut active = BTreeMap::new();
+        let mut i = 0;
+        while i < events.len() {
+            let begin = events[i].0;
+            while i < events.len() && events[i].0 == begin {
+                let (_, start, node) = events[i];
+                let key = (index.nodes[node].depth, node);
+                if start {
+                    *active.entry(key).or_insert(0) += 1;
+                } else if let Some(count) = active.get_mut(&key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        active.remove(&key);
+                    }
+                }
+                i += 1;
+            }
+            if let (Some(&(_, node)), Some(&(end, _, _))) =
+                (active.keys().next_back(), events.get(i))
+            {
+                match index.ranges.last_mut() {
+                    Some((last, last_node)) if *last_node == node && last.end == begin => {
+                        last.end = end;
+                    }
+                    _ => index.ranges.push((Range { begin, end }, node)),
+                }
+            }
+        }
+        Ok(index)
+    }
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Build an index of the address ranges of the entries in all of the units
+    /// in the `.debug_info` section.
+    ///
+    /// Use this instead of `Dwarf::dies_at_address` when querying many addresses.
+    pub fn scope_index(&self) -> Result<ScopeIndex<R::Offset>> {
+        ScopeIndex::build(self, None)
+    }
+
+    /// Return the chain of entries whose address ranges cover the given address.
+    ///
+    /// The chain begins with the root entry of the unit, and is followed by
+    /// the nested subprograms, lexical blocks, and inlined subroutines that
+    /// contain the address. Entries without address ranges, such as namespaces,
+    /// are not included, except for the unit's root entry.
+    ///
+    /// This searches all units each time it is called. Use `Dwarf::scope_index`
+    /// to build an index for repeated queries.
+    pub fn dies_at_address(&self, address: u64) -> Result<Vec<Scope<R::Offset>>> {
+        Ok(ScopeIndex::build(self, Some(address))?.dies_at_address(address))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::{DebugAbbrev, DebugInfo};
+    use crate::write::{self, Address, AttributeValue, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_dies_at_address() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = write::UnitTable::default();
+        let set_range = |unit: &mut write::Unit, id, begin, end| {
+            let entry = unit.get_mut(id);
+            entry.set(
+                constants::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(begin)),
+            );
+            entry.set(constants::DW_AT_high_pc, AttributeValue::Udata(end - begin));
+        };
+
+        let unit_id1 = units.add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id1);
+        let root1 = unit.root();
+        set_range(unit, root1, 0x1000, 0x2000);
+        let namespace = unit.add(root1, constants::DW_TAG_namespace);
+        let subprogram1 = unit.add(namespace, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram1, 0x1000, 0x1100);
+        let block = unit.add(subprogram1, constants::DW_TAG_lexical_block);
+        set_range(unit, block, 0x1010, 0x1080);
+        let inlined = unit.add(block, constants::DW_TAG_inlined_subroutine);
+        set_range(unit, inlined, 0x1020, 0x1030);
+        unit.add(block, constants::DW_TAG_variable);
+        let subprogram2 = unit.add(root1, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram2, 0x1100, 0x1200);
+
+        // A unit without ranges.
+        let unit_id2 = units.add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id2);
+        let root2 = unit.root();
+        let subprogram3 = unit.add(root2, constants::DW_TAG_subprogram);
+        set_range(unit, subprogram3, 0x3000, 0x3100);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let offsets = units
+            .write(
+                &mut sections,
+                &write::DebugLineStrOffsets::none(),
+                &write::DebugStrOffsets::none(),
+            )
+            .unwrap();
+        let scope = |unit_id, entry, tag| Scope {
+            unit_offset: offsets.unit(unit_id),
+            offset: UnitOffset(offsets.entry(unit_id, entry).0 - offsets.unit(unit_id).0),
+            tag,
+        };
+        let root1 = scope(unit_id1, root1, constants::DW_TAG_compile_unit);
+        let subprogram1 = scope(unit_id1, subprogram1, constants::DW_TAG_subprogram);
+        let block = scope(unit_id1, block, constants::DW_TAG_lexical_block);
+        let inlined = scope(unit_id1, inlined, constants::DW_TAG_inlined_subroutine);
+        let subprogram2 = scope(unit_id1, subprogram2, constants::DW_TAG_subprogram);
+        let root2 = scope(unit_id2, root2, constants::DW_TAG_compile_unit);
+        let subprogram3 = scope(unit_id2, subprogram3, constants::DW_TAG_subprogram);
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let index = dwarf.scope_index().unwrap();
+        assert!(index.allocated_bytes() > 0);
+        assert_eq!(ScopeIndex::<usize>::default().allocated_bytes(), 0);
+        for (address, chain) in [
+            (0xfff, &[][..]),
+            (0x1000, &[root1, subprogram1][..]),
+            (0x1010, &[root1, subprogram1, block][..]),
+            (0x1020, &[root1, subprogram1, block, inlined][..]),
+            (0x1030, &[root1, subprogram1, block][..]),
+            (0x1080, &[root1, subprogram1][..]),
+            (0x1100, &[root1, subprogram2][..]),
+            (0x1200, &[root1][..]),
+            (0x2000, &[][..]),
+            (0x3000, &[root2, subprogram3][..]),
+            (0x3100, &[][..]),
+        ] {
+            assert_eq!(index.dies_at_address(address), chain, "{:#x}", address);
+            assert_eq!(
+                dwarf.dies_at_address(address).unwrap(),
+                chain,
+                "{:#x}",
+                address
+            );
+        }
+    }
+}
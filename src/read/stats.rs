@@ -0,0 +1,370 @@
+use alloc::collections::btree_map::{self, BTreeMap};
+use alloc::collections::BTreeSet;
+
+use crate::common::SectionId;
+use crate::constants;
+use crate::read::{Dwarf, Reader, ReaderOffset, Result, Section, UnitHeader};
+
+/// The number of occurrences and the encoded size of an item.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ItemStatistics {
+    /// The number of times the item occurred.
+    pub count: u64,
+    /// The total number of bytes used to encode the item.
+    pub bytes: u64,
+}
+
+impl ItemStatistics {
+    #[inline]
+    fn add(&mut self, bytes: u64) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Statistics for the abbreviations used by the units in a `Dwarf`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AbbreviationStatistics {
+    /// The number of distinct abbreviation tables referenced by units.
+    pub tables: u64,
+    /// The number of units that share an abbreviation table with an earlier unit.
+    pub shared_tables: u64,
+    /// The total number of abbreviation declarations in the referenced tables.
+    pub declarations: u64,
+    /// The number of abbreviation declarations that were not used by any entry.
+    pub unused_declarations: u64,
+    /// The total number of entries that used an abbreviation declaration.
+    pub uses: u64,
+}
+
+/// Statistics for the strings in the `.debug_str` section.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StringStatistics {
+    /// The number of strings, including duplicates.
+    pub count: u64,
+    /// The number of bytes used by all strings, including null terminators.
+    pub bytes: u64,
+    /// The number of distinct strings.
+    pub unique_count: u64,
+    /// The number of bytes that would be saved by removing duplicate strings.
+    pub duplicate_bytes: u64,
+}
+
+/// Statistics about the size and composition of DWARF debugging information.
+///
+/// This is similar to the output of `llvm-dwarfdump --statistics`, but is
+/// focused on the encoded size of the information.
+///
+/// Returned by `Dwarf::statistics`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    /// The size of each non-empty section.
+    pub section_sizes: BTreeMap<SectionId, u64>,
+    /// The number of units in the `.debug_info` and `.debug_types` sections.
+    pub units: u64,
+    /// The number of bytes used by unit headers.
+    pub unit_header_bytes: u64,
+    /// The number of entries, excluding null entries.
+    pub entries: u64,
+    /// The number of bytes used by entries, excluding null entries.
+    ///
+    /// This includes the abbreviation code of each entry.
+    pub entry_bytes: u64,
+    /// The number of null entries.
+    pub null_entries: u64,
+    /// The number of entries and their encoded size for each tag.
+    pub tags: BTreeMap<constants::DwTag, ItemStatistics>,
+    /// The number of attributes and their encoded size for each attribute name.
+    ///
+    /// The size does not include any data stored in other sections, such as
+    /// strings in `.debug_str`.
+    pub attributes: BTreeMap<constants::DwAt, ItemStatistics>,
+    /// The number of attributes and their encoded size for each form.
+    pub forms: BTreeMap<constants::DwForm, ItemStatistics>,
+    /// Statistics for abbreviations.
+    pub abbreviations: AbbreviationStatistics,
+    /// Statistics for the `.debug_str` section.
+    pub strings: StringStatistics,
+}
+
+impl Statistics {
+    /// Return the average size in bytes of an entry, excluding null entries.
+    pub fn average_entry_size(&self) -> f64 {
+        if self.entries == 0 {
+            0.0
+        } else {
+            self.entry_bytes as f64 / self.entries as f64
+        }
+    }
+
+    fn add_section<R: Reader, S: Section<R>>(&mut self, section: &S) {
+        let size = section.reader().len().into_u64();
+        if size != 0 {
+            self.section_sizes.insert(S::id(), size);
+        }
+    }
+
+    fn add_unit<R: Reader>(
+        &mut self,
+        dwarf: &Dwarf<R>,
+        header: &UnitHeader<R>,
+        abbrev_uses: &mut BTreeMap<R::Offset, BTreeMap<u64, u64>>,
+    ) -> Result<()> {
+        self.units += 1;
+        self.unit_header_bytes += header.header_size().into_u64();
+
+        let abbreviations = dwarf.abbreviations(header)?;
+        let uses = match abbrev_uses.entry(header.debug_abbrev_offset().0) {
+            btree_map::Entry::Occupied(entry) => {
+                self.abbreviations.shared_tables += 1;
+                entry.into_mut()
+            }
+            btree_map::Entry::Vacant(entry) => entry.insert(
+                abbreviations
+                    .iter()
+                    .map(|abbrev| (abbrev.code(), 0))
+                    .collect(),
+            ),
+        };
+
+        let mut entries = header.entries_raw(&abbreviations, None)?;
+        while !entries.is_empty() {
+            let entry_offset = entries.next_offset();
+            let abbrev = match entries.read_abbreviation()? {
+                Some(abbrev) => abbrev,
+                None => {
+                    self.null_entries += 1;
+                    continue;
+                }
+            };
+            if let Some(count) = uses.get_mut(&abbrev.code()) {
+                *count += 1;
+            }
+            self.abbreviations.uses += 1;
+            for spec in abbrev.attributes() {
+                let attr_offset = entries.next_offset();
+                let attr = entries.read_attribute(*spec)?;
+                let size = (entries.next_offset().0 - attr_offset.0).into_u64();
+                self.attributes.entry(attr.name()).or_default().add(size);
+                self.forms.entry(spec.form()).or_default().add(size);
+            }
+            let size = (entries.next_offset().0 - entry_offset.0).into_u64();
+            self.entries += 1;
+            self.entry_bytes += size;
+            self.tags.entry(abbrev.tag()).or_default().add(size);
+        }
+        Ok(())
+    }
+
+    fn add_strings<R: Reader>(&mut self, dwarf: &Dwarf<R>) -> Result<()> {
+        let mut strings = BTreeSet::new();
+        let mut input = dwarf.debug_str.reader().clone();
+        while !input.is_empty() {
+            let string = input.read_null_terminated_slice()?;
+            let size = string.len().into_u64() + 1;
+            self.strings.count += 1;
+            self.strings.bytes += size;
+            if !strings.insert(string.to_slice()?.into_owned()) {
+                self.strings.duplicate_bytes += size;
+            }
+        }
+        self.strings.unique_count = strings.len() as u64;
+        Ok(())
+    }
+}
+
+impl<R: Reader> Dwarf<R> {
+    /// Collect statistics about the size and composition of the debugging information.
+    ///
+    /// This parses every entry in the `.debug_info` and `.debug_types` sections,
+    /// and every string in the `.debug_str` section.
+    ///
+    /// ```rust,no_run
+    /// # fn example<R: gimli::Reader>(dwarf: &gimli::Dwarf<R>) -> gimli::Result<()> {
+    /// let stats = dwarf.statistics()?;
+    /// println!("{} entries, average size {:.1}", stats.entries, stats.average_entry_size());
+    /// for (tag, tag_stats) in &stats.tags {
+    ///     println!("{}: {} bytes", tag, tag_stats.bytes);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn statistics(&self) -> Result<Statistics> {
+        let mut stats = Statistics::default();
+        stats.add_section(&self.debug_abbrev);
+        stats.add_section(&self.debug_addr);
+        stats.add_section(&self.debug_aranges);
+        stats.add_section(&self.debug_info);
+        stats.add_section(&self.debug_line);
+        stats.add_section(&self.debug_line_str);
+        stats.add_section(&self.debug_str);
+        stats.add_section(&self.debug_str_offsets);
+        stats.add_section(&self.debug_types);
+        stats.add_section(self.locations.debug_loc());
+        stats.add_section(self.locations.debug_loclists());
+        stats.add_section(self.ranges.debug_ranges());
+        stats.add_section(self.ranges.debug_rnglists());
+
+        let mut abbrev_uses = BTreeMap::new();
+        let mut units = self.units();
+        while let Some(header) = units.next()? {
+            stats.add_unit(self, &header, &mut abbrev_uses)?;
+        }
+        let mut type_units = self.type_units();
+        while let Some(header) = type_units.next()? {
+            stats.add_unit(self, &header, &mut abbrev_uses)?;
+        }
+        stats.abbreviations.tables = abbrev_uses.len() as u64;
+        for uses in abbrev_uses.values() {
+            stats.abbreviations.declarations += uses.len() as u64;
+            stats.abbreviations.unused_declarations +=
+                uses.values().filter(|count| **count == 0).count() as u64;
+        }
+
+        stats.add_strings(self)?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read::abbrev::tests::AbbrevSectionMethods;
+    use crate::read::{DebugAbbrev, DebugInfo, DebugStr};
+    use crate::test_util::GimliSectionMethods;
+    use crate::write::{self, EndianVec, LineProgram, Sections, Writer};
+    use crate::LittleEndian;
+    use test_assembler::{Endian, Label, LabelMaker, Section as TestSection};
+
+    #[test]
+    fn test_statistics() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        for _ in 0..2 {
+            let unit_id = dwarf
+                .units
+                .add(write::Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            let child = unit.add(root, constants::DW_TAG_base_type);
+            let name = dwarf.strings.add("int");
+            let entry = unit.get_mut(child);
+            entry.set(
+                constants::DW_AT_name,
+                write::AttributeValue::StringRef(name),
+            );
+            entry.set(constants::DW_AT_byte_size, write::AttributeValue::Data1(4));
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        // Append a duplicate string.
+        sections.debug_str.write(b"int\0").unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            debug_str: DebugStr::new(sections.debug_str.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let stats = read_dwarf.statistics().unwrap();
+
+        assert_eq!(
+            stats.section_sizes.get(&SectionId::DebugInfo),
+            Some(&(sections.debug_info.len() as u64))
+        );
+        assert_eq!(stats.section_sizes.get(&SectionId::DebugLine), None);
+        assert_eq!(stats.units, 2);
+        assert_eq!(stats.unit_header_bytes, 2 * 11);
+        assert_eq!(stats.entries, 4);
+        assert_eq!(stats.null_entries, 2);
+        // Each unit: 11 byte header, 1 byte root, 1 + 4 + 1 byte child, 1 byte null.
+        assert_eq!(stats.entry_bytes, 2 * (1 + 6));
+        assert_eq!(stats.average_entry_size(), 3.5);
+        assert_eq!(
+            stats.tags.get(&constants::DW_TAG_base_type),
+            Some(&ItemStatistics {
+                count: 2,
+                bytes: 12
+            })
+        );
+        assert_eq!(
+            stats.attributes.get(&constants::DW_AT_name),
+            Some(&ItemStatistics { count: 2, bytes: 8 })
+        );
+        assert_eq!(
+            stats.forms.get(&constants::DW_FORM_data1),
+            Some(&ItemStatistics { count: 2, bytes: 2 })
+        );
+        assert_eq!(
+            stats.abbreviations,
+            AbbreviationStatistics {
+                tables: 2,
+                shared_tables: 0,
+                declarations: 4,
+                unused_declarations: 0,
+                uses: 4,
+            }
+        );
+        assert_eq!(
+            stats.strings,
+            StringStatistics {
+                count: 2,
+                bytes: 8,
+                unique_count: 1,
+                duplicate_bytes: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_statistics_sparse_abbreviations() {
+        #[rustfmt::skip]
+        let debug_abbrev = TestSection::with_endian(Endian::Little)
+            .abbrev(1, constants::DW_TAG_compile_unit, constants::DW_CHILDREN_yes)
+                .abbrev_attr_null()
+            .abbrev(2, constants::DW_TAG_base_type, constants::DW_CHILDREN_no)
+                .abbrev_attr_null()
+            .abbrev(100, constants::DW_TAG_variable, constants::DW_CHILDREN_no)
+                .abbrev_attr_null()
+            .abbrev_null()
+            .get_contents()
+            .unwrap();
+        let length = Label::new();
+        let start = Label::new();
+        let end = Label::new();
+        #[rustfmt::skip]
+        let debug_info = TestSection::with_endian(Endian::Little)
+            .initial_length(Format::Dwarf32, &length, &start)
+            .D16(4).D32(0).D8(8)
+            .uleb(1)
+            .uleb(100)
+            .uleb(100)
+            .D8(0)
+            .mark(&end);
+        length.set_const((&end - &start) as u64);
+        let debug_info = debug_info.get_contents().unwrap();
+
+        let dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(&debug_abbrev, LittleEndian),
+            debug_info: DebugInfo::new(&debug_info, LittleEndian),
+            ..Default::default()
+        };
+        let stats = dwarf.statistics().unwrap();
+        assert_eq!(
+            stats.abbreviations,
+            AbbreviationStatistics {
+                tables: 1,
+                shared_tables: 0,
+                declarations: 3,
+                unused_declarations: 1,
+                uses: 3,
+            }
+        );
+    }
+}
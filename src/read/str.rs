@@ -1,3 +1,12 @@
+#[cfg(feature = "read")]
+use alloc::collections::btree_map::BTreeMap;
+#[cfg(feature = "read")]
+use alloc::vec::Vec;
+#[cfg(feature = "read")]
+use core::mem;
+#[cfg(feature = "read")]
+use core::ops::Bound;
+
 use crate::common::{
     DebugLineStrOffset, DebugStrOffset, DebugStrOffsetsBase, DebugStrOffsetsIndex, DwarfFileType,
     Encoding, SectionId,
@@ -54,6 +63,98 @@ impl<R: Reader> DebugStr<R> {
         input.skip(offset.0)?;
         input.read_null_terminated_slice()
     }
+
+    /// Find the offset of a string in the `.debug_str` section.
+    ///
+    /// The string may also be found as the tail of a longer string. If the
+    /// string occurs more than once, then the first occurrence is returned.
+    ///
+    /// This scans the whole section for each call. Use `DebugStr::index` to
+    /// build an index if many strings need to be found.
+    #[cfg(feature = "read")]
+    pub fn find(&self, bytes: &[u8]) -> Result<Option<DebugStrOffset<R::Offset>>> {
+        let mut input = self.debug_str_section.clone();
+        while !input.is_empty() {
+            let offset = input.offset_from(&self.debug_str_section);
+            let string = input.read_null_terminated_slice()?;
+            let string = string.to_slice()?;
+            if string.ends_with(bytes) {
+                let tail = R::Offset::from_u64((string.len() - bytes.len()) as u64)?;
+                return Ok(Some(DebugStrOffset(offset + tail)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Build an index of the strings in the `.debug_str` section.
+    ///
+    /// See `DebugStrIndex`.
+    #[cfg(feature = "read")]
+    pub fn index(&self) -> Result<DebugStrIndex> {
+        let mut strings = BTreeMap::new();
+        let mut input = self.debug_str_section.clone();
+        while !input.is_empty() {
+            let offset = input.offset_from(&self.debug_str_section);
+            let string = input.read_null_terminated_slice()?;
+            let key = string.to_slice()?.iter().rev().copied().collect();
+            strings.entry(key).or_insert(offset.into_u64());
+        }
+        Ok(DebugStrIndex { strings })
+    }
+}
+
+/// An index of the strings in a `.debug_str` section.
+///
+/// This allows the offset of a string to be found without scanning the
+/// section. The index is created by `DebugStr::index`, and can be stored in
+/// `Dwarf::debug_str_index` so that it is used by `Dwarf::find_string`.
+#[cfg(feature = "read")]
+#[derive(Debug, Default, Clone)]
+pub struct DebugStrIndex {
+    // The strings are stored reversed, so that a string which is the tail of
+    // a longer string can be found using a range lookup.
+    strings: BTreeMap<Vec<u8>, u64>,
+}
+
+#[cfg(feature = "read")]
+impl DebugStrIndex {
+    /// Find the offset of a string in the `.debug_str` section.
+    ///
+    /// The string may also be found as the tail of a longer string.
+    /// If the string occurs more than once, then the offset of any of the
+    /// occurrences may be returned.
+    pub fn find<T: ReaderOffset>(&self, bytes: &[u8]) -> Option<DebugStrOffset<T>> {
+        let key = bytes.iter().rev().copied().collect::<Vec<_>>();
+        let (string, offset) = self
+            .strings
+            .range::<[u8], _>((Bound::Included(key.as_slice()), Bound::Unbounded))
+            .next()?;
+        if !string.starts_with(&key) {
+            return None;
+        }
+        let offset = offset + (string.len() - key.len()) as u64;
+        T::from_u64(offset).ok().map(DebugStrOffset)
+    }
+
+    /// Return the number of distinct strings in the index.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Return true if the index contains no strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the index.
+    ///
+    /// This does not include the overhead of the internal nodes of the map.
+    pub fn allocated_bytes(&self) -> usize {
+        self.strings
+            .keys()
+            .map(|string| mem::size_of::<(Vec<u8>, u64)>() + string.capacity())
+            .sum()
+    }
 }
 
 impl<T> DebugStr<T> {
@@ -288,4 +389,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_debug_str_find() {
+        let buf = b"main\0int\0unsigned int\0\0main\0";
+        let debug_str = DebugStr::new(buf, LittleEndian);
+        let index = debug_str.index().unwrap();
+        // "unsigned int", "int", "main", and "".
+        assert_eq!(index.len(), 4);
+        assert!(index.allocated_bytes() > 0);
+        for (string, offset) in [
+            (&b"main"[..], Some(0)),
+            (b"int", Some(5)),
+            (b"unsigned int", Some(9)),
+            (b"signed int", Some(11)),
+            (b"nt", Some(6)),
+            (b"long int", None),
+            (b"mai", None),
+        ] {
+            let offset = offset.map(DebugStrOffset);
+            assert_eq!(debug_str.find(string), Ok(offset), "{:?}", string);
+            assert_eq!(index.find(string), offset, "{:?}", string);
+            if let Some(offset) = offset {
+                assert_eq!(debug_str.get_str(offset).unwrap().slice(), string);
+            }
+        }
+        assert!(DebugStr::new(b"main", LittleEndian).find(b"x").is_err());
+        assert!(DebugStr::new(b"", LittleEndian).index().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,1031 @@
+use alloc::vec::Vec;
+
+use crate::common::Encoding;
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::{
+    AttributeValue, DebuggingInformationEntry, DieReference, Error, Expression, Operation, Reader,
+    Result, UnitOffset, UnitRef,
+};
+
+/// A discriminant value of a variant part.
+///
+/// Discriminant values are signed or unsigned depending on the type of the
+/// discriminant member, which is referenced by the `DW_AT_discr` attribute of
+/// the `DW_TAG_variant_part` entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscriminantValue {
+    /// An unsigned discriminant value.
+    Unsigned(u64),
+    /// A signed discriminant value.
+    Signed(i64),
+}
+
+impl DiscriminantValue {
+    /// Return the value of a `DW_AT_discr_value` attribute.
+    ///
+    /// `signed` is whether the type of the discriminant is signed.
+    ///
+    /// Returns `None` if the attribute value is not a constant.
+    pub fn from_attr<R: Reader>(value: &AttributeValue<R>, signed: bool) -> Option<Self> {
+        if signed {
+            value.sdata_value().map(DiscriminantValue::Signed)
+        } else {
+            value.udata_value().map(DiscriminantValue::Unsigned)
+        }
+    }
+}
+
+/// An entry in a `DW_AT_discr_list` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscriminantListEntry {
+    /// A single discriminant value (`DW_DSC_label`).
+    Label(DiscriminantValue),
+    /// An inclusive range of discriminant values (`DW_DSC_range`).
+    Range {
+        /// The lowest value in the range.
+        low: DiscriminantValue,
+        /// The highest value in the range.
+        high: DiscriminantValue,
+    },
+}
+
+impl DiscriminantListEntry {
+    /// Return true if the given discriminant value matches this entry.
+    ///
+    /// Signed and unsigned values never match each other.
+    pub fn contains(&self, value: DiscriminantValue) -> bool {
+        use DiscriminantValue::*;
+        match (*self, value) {
+            (DiscriminantListEntry::Label(label), value) => label == value,
+            (
+                DiscriminantListEntry::Range {
+                    low: Unsigned(low),
+                    high: Unsigned(high),
+                },
+                Unsigned(value),
+            ) => low <= value && value <= high,
+            (
+                DiscriminantListEntry::Range {
+                    low: Signed(low),
+                    high: Signed(high),
+                },
+                Signed(value),
+            ) => low <= value && value <= high,
+            _ => false,
+        }
+    }
+}
+
+/// An iterator over the entries of a `DW_AT_discr_list` attribute of a
+/// `DW_TAG_variant` entry.
+#[derive(Debug, Clone)]
+pub struct DiscriminantListIter<R: Reader> {
+    input: R,
+    signed: bool,
+}
+
+impl<R: Reader> DiscriminantListIter<R> {
+    /// Construct a new iterator over the entries in the block of a
+    /// `DW_AT_discr_list` attribute.
+    ///
+    /// `signed` is whether the type of the discriminant is signed.
+    pub fn new(input: R, signed: bool) -> Self {
+        DiscriminantListIter { input, signed }
+    }
+
+    /// Construct a new iterator over the entries of a `DW_AT_discr_list`
+    /// attribute value.
+    ///
+    /// `signed` is whether the type of the discriminant is signed.
+    ///
+    /// Returns `None` if the attribute value is not a block.
+    pub fn from_attr(value: &AttributeValue<R>, signed: bool) -> Option<Self> {
+        match *value {
+            AttributeValue::Block(ref data) => Some(Self::new(data.clone(), signed)),
+            _ => None,
+        }
+    }
+
+    fn read_value(&mut self) -> Result<DiscriminantValue> {
+        if self.signed {
+            self.input.read_sleb128().map(DiscriminantValue::Signed)
+        } else {
+            self.input.read_uleb128().map(DiscriminantValue::Unsigned)
+        }
+    }
+
+    /// Advance the iterator to the next entry.
+    pub fn next(&mut self) -> Result<Option<DiscriminantListEntry>> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        let result = self.parse_entry();
+        if result.is_err() {
+            self.input.empty();
+        }
+        result.map(Some)
+    }
+
+    fn parse_entry(&mut self) -> Result<DiscriminantListEntry> {
+        let kind = constants::DwDsc(self.input.read_u8()?);
+        match kind {
+            constants::DW_DSC_label => Ok(DiscriminantListEntry::Label(self.read_value()?)),
+            constants::DW_DSC_range => {
+                let low = self.read_value()?;
+                let high = self.read_value()?;
+                Ok(DiscriminantListEntry::Range { low, high })
+            }
+            _ => Err(Error::UnknownDiscriminantListEntry(kind)),
+        }
+    }
+
+    /// Return true if the given discriminant value matches any entry.
+    pub fn contains(mut self, value: DiscriminantValue) -> Result<bool> {
+        while let Some(entry) = self.next()? {
+            if entry.contains(value) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+impl<R: Reader> fallible_iterator::FallibleIterator for DiscriminantListIter<R> {
+    type Item = DiscriminantListEntry;
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        DiscriminantListIter::next(self)
+    }
+}
+
+result_iterator!([R: Reader] DiscriminantListIter<R>, DiscriminantListEntry);
+
+/// The location of a data member within its containing object.
+///
+/// This is parsed from the `DW_AT_data_member_location` attribute of a
+/// `DW_TAG_member` or `DW_TAG_inheritance` entry.
+/// Returned by `DebuggingInformationEntry::data_member_location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataMemberLocation<R: Reader> {
+    /// The member is at a constant byte offset from the start of the
+    /// containing object.
+    ///
+    /// This is used for constant attribute values, and for expressions that
+    /// only add a constant to the address of the containing object, such as
+    /// `DW_OP_plus_uconst`.
+    ConstantOffset(u64),
+    /// The address of the member is computed by evaluating the expression
+    /// with the address of the containing object pushed on the stack.
+    Expression(Expression<R>),
+}
+
+impl<R: Reader> DataMemberLocation<R> {
+    /// Convert a `DW_AT_data_member_location` attribute value.
+    ///
+    /// Returns `Error::UnsupportedAttributeForm` for other forms, such as
+    /// location lists.
+    pub fn from_attr(value: &AttributeValue<R>, encoding: Encoding) -> Result<Self> {
+        if let Some(offset) = value.udata_value() {
+            return Ok(DataMemberLocation::ConstantOffset(offset));
+        }
+        let expression = value
+            .exprloc_value()
+            .ok_or(Error::UnsupportedAttributeForm)?;
+        let mut operations = expression.clone().operations(encoding);
+        let offset = match operations.next()? {
+            None => Some(0),
+            Some(Operation::PlusConstant { value }) => Some(value),
+            Some(Operation::UnsignedConstant { value }) => match operations.next()? {
+                Some(Operation::Plus) => Some(value),
+                _ => None,
+            },
+            Some(_) => None,
+        };
+        match offset {
+            Some(offset) if operations.next()?.is_none() => {
+                Ok(DataMemberLocation::ConstantOffset(offset))
+            }
+            _ => Ok(DataMemberLocation::Expression(expression)),
+        }
+    }
+}
+
+/// An array bound that cannot be determined without more information.
+///
+/// This is passed to the evaluation hook of `UnitRef::array_dimensions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicBound<R: Reader> {
+    /// The bound is the value of the referenced variable or member entry.
+    Reference(DieReference<R::Offset>),
+    /// The bound is the value computed by evaluating the expression.
+    Expression(Expression<R>),
+}
+
+/// The bounds of one dimension of an array type.
+///
+/// Returned by `UnitRef::array_dimensions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayDimension<T = usize> {
+    /// The offset of the `DW_TAG_subrange_type` entry.
+    pub offset: UnitOffset<T>,
+    /// The lower bound of the index.
+    ///
+    /// This is from `DW_AT_lower_bound`, or the default lower bound for the
+    /// language of the unit. `None` if the bound is not known.
+    pub lower_bound: Option<i64>,
+    /// The number of elements.
+    ///
+    /// This is from `DW_AT_count`, or computed from `DW_AT_upper_bound` and
+    /// the lower bound. `None` if the count is not known, such as for a
+    /// flexible array member.
+    pub count: Option<u64>,
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Return the dimensions of the `DW_TAG_array_type` entry at the given
+    /// offset.
+    ///
+    /// The dimensions are parsed from the `DW_TAG_subrange_type` children of
+    /// the entry, in order. Other children, such as `DW_TAG_enumeration_type`
+    /// entries that define the index of an array, are ignored.
+    ///
+    /// Constant bounds are resolved directly, as are references to entries
+    /// with a constant `DW_AT_const_value`. Other bounds are passed to
+    /// `evaluate`, which returns `None` if the bound cannot be determined.
+    pub fn array_dimensions<F>(
+        &self,
+        offset: UnitOffset<R::Offset>,
+        mut evaluate: F,
+    ) -> Result<Vec<ArrayDimension<R::Offset>>>
+    where
+        F: FnMut(DynamicBound<R>) -> Result<Option<i64>>,
+    {
+        let mut dimensions = Vec::new();
+        let mut tree = self.entries_tree(Some(offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != constants::DW_TAG_subrange_type {
+                continue;
+            }
+            let signed = self.is_signed_type(entry)?;
+            let lower_bound = match entry.attr_value(constants::DW_AT_lower_bound)? {
+                Some(value) => self.array_bound(value, signed, &mut evaluate)?,
+                None => self
+                    .language
+                    .and_then(constants::DwLang::default_lower_bound)
+                    .map(|bound| bound as i64),
+            };
+            let count = match entry.attr_value(constants::DW_AT_count)? {
+                Some(value) => self
+                    .array_bound(value, signed, &mut evaluate)?
+                    .map(|count| count.max(0) as u64),
+                None => match (entry.attr_value(constants::DW_AT_upper_bound)?, lower_bound) {
+                    (Some(value), Some(lower_bound)) => self
+                        .array_bound(value, signed, &mut evaluate)?
+                        .and_then(|upper_bound| {
+                            if upper_bound < lower_bound {
+                                Some(0)
+                            } else {
+                                (upper_bound.wrapping_sub(lower_bound) as u64).checked_add(1)
+                            }
+                        }),
+                    _ => None,
+                },
+            };
+            dimensions.push(ArrayDimension {
+                offset: entry.offset(),
+                lower_bound,
+                count,
+            });
+        }
+        Ok(dimensions)
+    }
+
+    /// Return true if the `DW_AT_type` of the entry is a signed base type.
+    fn is_signed_type(&self, entry: &DebuggingInformationEntry<'_, '_, R>) -> Result<bool> {
+        if let Some(AttributeValue::UnitRef(offset)) = entry.attr_value(constants::DW_AT_type)? {
+            let entry = self.entry(offset)?;
+            if let Some(AttributeValue::Encoding(encoding)) =
+                entry.attr_value(constants::DW_AT_encoding)?
+            {
+                return Ok(matches!(
+                    encoding,
+                    constants::DW_ATE_signed | constants::DW_ATE_signed_char
+                ));
+            }
+        }
+        Ok(false)
+    }
+
+    fn array_bound<F>(
+        &self,
+        value: AttributeValue<R>,
+        signed: bool,
+        evaluate: &mut F,
+    ) -> Result<Option<i64>>
+    where
+        F: FnMut(DynamicBound<R>) -> Result<Option<i64>>,
+    {
+        let constant = |value: &AttributeValue<R>| match *value {
+            AttributeValue::Sdata(value) => Some(value),
+            _ if signed => value.sdata_value(),
+            _ => value.udata_value().map(|value| value as i64),
+        };
+        match value {
+            AttributeValue::UnitRef(offset) => {
+                let entry = self.entry(offset)?;
+                if let Some(value) = entry.attr_value(constants::DW_AT_const_value)? {
+                    if let Some(value) = constant(&value) {
+                        return Ok(Some(value));
+                    }
+                }
+                evaluate(DynamicBound::Reference(DieReference::UnitRef(offset)))
+            }
+            AttributeValue::DebugInfoRef(offset) => {
+                evaluate(DynamicBound::Reference(DieReference::DebugInfoRef(offset)))
+            }
+            AttributeValue::Exprloc(expression) => evaluate(DynamicBound::Expression(expression)),
+            value => Ok(constant(&value)),
+        }
+    }
+}
+
+/// The location of a bit field member within its containing object.
+///
+/// Returned by `UnitRef::bit_field`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    /// The offset in bits of the field from the start of the containing object.
+    ///
+    /// This uses the same bit numbering as `DW_AT_data_bit_offset`. For
+    /// little endian targets, bit 0 is the least significant bit of the first
+    /// byte. For big endian targets, bit 0 is the most significant bit of the
+    /// first byte.
+    pub bit_offset: u64,
+    /// The size of the field in bits.
+    pub bit_size: u64,
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Return the location of a bit field member.
+    ///
+    /// This handles both the `DW_AT_data_bit_offset` attribute used by
+    /// DWARF 4 and later, and the `DW_AT_bit_offset` attribute used by earlier
+    /// versions. `DW_AT_bit_offset` counts from the most significant bit of the
+    /// storage unit, so converting it requires the endianness of the target.
+    ///
+    /// Returns `None` if the entry does not have a `DW_AT_bit_size` attribute.
+    pub fn bit_field<Endian: Endianity>(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+        endian: Endian,
+    ) -> Result<Option<BitField>> {
+        let bit_size = match entry.attr_value(constants::DW_AT_bit_size)? {
+            Some(value) => value.udata_value().ok_or(Error::UnsupportedAttributeForm)?,
+            None => return Ok(None),
+        };
+        if let Some(value) = entry.attr_value(constants::DW_AT_data_bit_offset)? {
+            let bit_offset = value.udata_value().ok_or(Error::UnsupportedAttributeForm)?;
+            return Ok(Some(BitField {
+                bit_offset,
+                bit_size,
+            }));
+        }
+
+        let byte_offset = match entry.data_member_location()? {
+            Some(DataMemberLocation::ConstantOffset(offset)) => offset,
+            Some(DataMemberLocation::Expression(_)) => return Err(Error::UnsupportedAttributeForm),
+            None => 0,
+        };
+        let storage_bit_offset = match entry.attr_value(constants::DW_AT_bit_offset)? {
+            Some(AttributeValue::Sdata(value)) => value,
+            Some(value) => value.udata_value().ok_or(Error::UnsupportedAttributeForm)? as i64,
+            None => 0,
+        };
+        let bit_offset = if endian.is_big_endian() {
+            storage_bit_offset
+        } else {
+            let byte_size = self
+                .storage_byte_size(entry)?
+                .ok_or(Error::MissingBitFieldStorageSize)?;
+            (byte_size as i64 * 8)
+                .wrapping_sub(storage_bit_offset)
+                .wrapping_sub(bit_size as i64)
+        };
+        let bit_offset = (byte_offset as i64 * 8).wrapping_add(bit_offset);
+        Ok(Some(BitField {
+            bit_offset: bit_offset as u64,
+            bit_size,
+        }))
+    }
+
+    /// Return the `DW_AT_byte_size` of the entry, or of its type.
+    fn storage_byte_size(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<u64>> {
+        if let Some(value) = entry.attr_value(constants::DW_AT_byte_size)? {
+            return Ok(value.udata_value());
+        }
+        let mut type_offset = entry.attr_value(constants::DW_AT_type)?;
+        // Limit the number of typedefs and qualifiers to avoid cycles.
+        for _ in 0..16 {
+            let offset = match type_offset {
+                Some(AttributeValue::UnitRef(offset)) => offset,
+                _ => return Ok(None),
+            };
+            let entry = self.entry(offset)?;
+            if let Some(value) = entry.attr_value(constants::DW_AT_byte_size)? {
+                return Ok(value.udata_value());
+            }
+            type_offset = entry.attr_value(constants::DW_AT_type)?;
+        }
+        Ok(None)
+    }
+}
+
+/// The scale factor of a fixed-point or decimal base type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedPointScale<T = usize> {
+    /// The value is the stored integer multiplied by `2^scale`.
+    ///
+    /// This is from `DW_AT_binary_scale`.
+    Binary(i64),
+    /// The value is the stored integer multiplied by `10^scale`.
+    ///
+    /// This is from `DW_AT_decimal_scale`.
+    Decimal(i64),
+    /// The value is the stored integer multiplied by the value of the
+    /// referenced `DW_TAG_constant` entry.
+    ///
+    /// This is from `DW_AT_small`.
+    Small(UnitOffset<T>),
+}
+
+/// The attributes of a fixed-point or decimal base type.
+///
+/// These are used by base types with an encoding of `DW_ATE_signed_fixed`,
+/// `DW_ATE_unsigned_fixed`, `DW_ATE_packed_decimal`, `DW_ATE_numeric_string`,
+/// or `DW_ATE_edited`.
+///
+/// Returned by `UnitRef::fixed_point_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedPointType<R: Reader> {
+    /// The encoding of the type, from `DW_AT_encoding`.
+    pub encoding: constants::DwAte,
+    /// The scale factor of the type.
+    pub scale: Option<FixedPointScale<R::Offset>>,
+    /// The number of digits in a decimal type, from `DW_AT_digit_count`.
+    pub digit_count: Option<u64>,
+    /// The representation of the sign of a decimal type, from
+    /// `DW_AT_decimal_sign`.
+    pub decimal_sign: Option<constants::DwDs>,
+    /// The picture string of an edited type, from `DW_AT_picture_string`.
+    pub picture_string: Option<R>,
+}
+
+impl<'a, R: Reader> UnitRef<'a, R> {
+    /// Return the attributes of a fixed-point or decimal base type.
+    ///
+    /// Returns `None` if the entry does not have one of the encodings listed
+    /// in the documentation of `FixedPointType`.
+    pub fn fixed_point_type(
+        &self,
+        entry: &DebuggingInformationEntry<'_, '_, R>,
+    ) -> Result<Option<FixedPointType<R>>> {
+        let encoding = match entry.attr_value(constants::DW_AT_encoding)? {
+            Some(AttributeValue::Encoding(
+                encoding @ (constants::DW_ATE_signed_fixed
+                | constants::DW_ATE_unsigned_fixed
+                | constants::DW_ATE_packed_decimal
+                | constants::DW_ATE_numeric_string
+                | constants::DW_ATE_edited),
+            )) => encoding,
+            _ => return Ok(None),
+        };
+
+        // The scales are signed, so sign extend fixed size constants.
+        let scale_value =
+            |value: AttributeValue<R>| value.sdata_value().ok_or(Error::UnsupportedAttributeForm);
+        let scale = if let Some(value) = entry.attr_value(constants::DW_AT_binary_scale)? {
+            Some(FixedPointScale::Binary(scale_value(value)?))
+        } else if let Some(value) = entry.attr_value(constants::DW_AT_decimal_scale)? {
+            Some(FixedPointScale::Decimal(scale_value(value)?))
+        } else {
+            match entry.attr_value(constants::DW_AT_small)? {
+                Some(AttributeValue::UnitRef(offset)) => Some(FixedPointScale::Small(offset)),
+                Some(_) => return Err(Error::UnsupportedAttributeForm),
+                None => None,
+            }
+        };
+        let digit_count = match entry.attr_value(constants::DW_AT_digit_count)? {
+            Some(value) => Some(value.udata_value().ok_or(Error::UnsupportedAttributeForm)?),
+            None => None,
+        };
+        let decimal_sign = match entry.attr_value(constants::DW_AT_decimal_sign)? {
+            Some(AttributeValue::DecimalSign(sign)) => Some(sign),
+            Some(_) => return Err(Error::UnsupportedAttributeForm),
+            None => None,
+        };
+        let picture_string = match entry.attr_value(constants::DW_AT_picture_string)? {
+            Some(value) => Some(self.attr_string(value)?),
+            None => None,
+        };
+        Ok(Some(FixedPointType {
+            encoding,
+            scale,
+            digit_count,
+            decimal_sign,
+            picture_string,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::EndianSlice;
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_discriminant_list() {
+        use DiscriminantValue::*;
+
+        // DW_DSC_label 5, DW_DSC_range 10 20, DW_DSC_label 0x80
+        let data = [0x00, 0x05, 0x01, 0x0a, 0x14, 0x00, 0x80, 0x01];
+        let block = AttributeValue::Block(EndianSlice::new(&data, LittleEndian));
+        let mut iter = DiscriminantListIter::from_attr(&block, false).unwrap();
+        assert_eq!(
+            iter.next(),
+            Ok(Some(DiscriminantListEntry::Label(Unsigned(5))))
+        );
+        assert_eq!(
+            iter.next(),
+            Ok(Some(DiscriminantListEntry::Range {
+                low: Unsigned(10),
+                high: Unsigned(20),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Ok(Some(DiscriminantListEntry::Label(Unsigned(0x80))))
+        );
+        assert_eq!(iter.next(), Ok(None));
+
+        let iter = DiscriminantListIter::from_attr(&block, false).unwrap();
+        for (value, expected) in [(4, false), (5, true), (10, true), (20, true), (21, false)] {
+            assert_eq!(iter.clone().contains(Unsigned(value)), Ok(expected));
+        }
+        assert_eq!(iter.clone().contains(Signed(5)), Ok(false));
+
+        // The same bytes as signed values.
+        let mut iter = DiscriminantListIter::from_attr(&block, true).unwrap();
+        iter.next().unwrap();
+        iter.next().unwrap();
+        assert_eq!(
+            iter.next(),
+            Ok(Some(DiscriminantListEntry::Label(Signed(0x80))))
+        );
+
+        // DW_DSC_range -3 -1
+        let data = [0x01, 0x7d, 0x7f];
+        let iter = DiscriminantListIter::new(EndianSlice::new(&data, LittleEndian), true);
+        assert_eq!(iter.clone().contains(Signed(-2)), Ok(true));
+        assert_eq!(iter.clone().contains(Signed(0)), Ok(false));
+
+        let data = [0x02, 0x00];
+        let mut iter = DiscriminantListIter::new(EndianSlice::new(&data, LittleEndian), false);
+        assert_eq!(
+            iter.next(),
+            Err(Error::UnknownDiscriminantListEntry(constants::DwDsc(2)))
+        );
+        assert_eq!(iter.next(), Ok(None));
+
+        assert_eq!(
+            DiscriminantValue::from_attr(
+                &AttributeValue::<EndianSlice<'_, LittleEndian>>::Data1(0xff),
+                true
+            ),
+            Some(Signed(-1))
+        );
+    }
+
+    #[test]
+    fn test_data_member_location() {
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let location = |value| DataMemberLocation::from_attr(&value, encoding);
+        let exprloc =
+            |data| AttributeValue::Exprloc(Expression(EndianSlice::new(data, LittleEndian)));
+
+        assert_eq!(
+            location(AttributeValue::Udata(8)),
+            Ok(DataMemberLocation::ConstantOffset(8))
+        );
+        // DW_OP_plus_uconst 0x10
+        assert_eq!(
+            location(exprloc(&[0x23, 0x10])),
+            Ok(DataMemberLocation::ConstantOffset(0x10))
+        );
+        // DW_OP_constu 0x20 DW_OP_plus
+        assert_eq!(
+            location(exprloc(&[0x10, 0x20, 0x22])),
+            Ok(DataMemberLocation::ConstantOffset(0x20))
+        );
+        // A virtual base class: DW_OP_dup DW_OP_deref DW_OP_lit8 DW_OP_minus
+        // DW_OP_deref DW_OP_plus
+        let data = [0x12, 0x06, 0x38, 0x1c, 0x06, 0x22];
+        assert_eq!(
+            location(exprloc(&data)),
+            Ok(DataMemberLocation::Expression(Expression(
+                EndianSlice::new(&data, LittleEndian)
+            )))
+        );
+        // DW_OP_plus_uconst 0x10 DW_OP_deref
+        let data = [0x23, 0x10, 0x06];
+        assert_eq!(
+            location(exprloc(&data)),
+            Ok(DataMemberLocation::Expression(Expression(
+                EndianSlice::new(&data, LittleEndian)
+            )))
+        );
+        assert_eq!(
+            location(AttributeValue::LocationListsRef(
+                crate::common::LocationListsOffset(0)
+            )),
+            Err(Error::UnsupportedAttributeForm)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_array_dimensions() {
+        use crate::read::{DebugAbbrev, DebugInfo, Dwarf};
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        for language in [constants::DW_LANG_C99, constants::DW_LANG_Fortran90] {
+            let unit_id = dwarf
+                .units
+                .add(write::Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            unit.get_mut(root).set(
+                constants::DW_AT_language,
+                write::AttributeValue::Language(language),
+            );
+            let int = unit.add(root, constants::DW_TAG_base_type);
+            unit.get_mut(int).set(
+                constants::DW_AT_encoding,
+                write::AttributeValue::Encoding(constants::DW_ATE_signed),
+            );
+            let constant = unit.add(root, constants::DW_TAG_variable);
+            unit.get_mut(constant).set(
+                constants::DW_AT_const_value,
+                write::AttributeValue::Udata(7),
+            );
+            let array = unit.add(root, constants::DW_TAG_array_type);
+            let mut subrange = |attrs: &[(constants::DwAt, write::AttributeValue)]| {
+                let subrange = unit.add(array, constants::DW_TAG_subrange_type);
+                for (name, value) in attrs {
+                    unit.get_mut(subrange).set(*name, value.clone());
+                }
+            };
+            subrange(&[(
+                constants::DW_AT_upper_bound,
+                write::AttributeValue::Udata(9),
+            )]);
+            subrange(&[(constants::DW_AT_count, write::AttributeValue::Udata(3))]);
+            subrange(&[
+                (constants::DW_AT_type, write::AttributeValue::UnitRef(int)),
+                (
+                    constants::DW_AT_upper_bound,
+                    write::AttributeValue::Data4(0xffff_ffff),
+                ),
+            ]);
+            let mut expression = write::Expression::new();
+            expression.op_fbreg(-8);
+            expression.op_deref();
+            subrange(&[(
+                constants::DW_AT_upper_bound,
+                write::AttributeValue::Exprloc(expression),
+            )]);
+            subrange(&[(
+                constants::DW_AT_upper_bound,
+                write::AttributeValue::UnitRef(constant),
+            )]);
+            subrange(&[]);
+            subrange(&[
+                (
+                    constants::DW_AT_lower_bound,
+                    write::AttributeValue::Sdata(-2),
+                ),
+                (
+                    constants::DW_AT_upper_bound,
+                    write::AttributeValue::Sdata(2),
+                ),
+            ]);
+            unit.add(array, constants::DW_TAG_enumeration_type);
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let mut units = read_dwarf.parsed_units();
+        for lower in [0, 1] {
+            let unit = units.next().unwrap().unwrap();
+            let unit = unit.unit_ref(&read_dwarf);
+            let mut entries = unit.entries();
+            let offset = loop {
+                let (_, entry) = entries.next_dfs().unwrap().unwrap();
+                if entry.tag() == constants::DW_TAG_array_type {
+                    break entry.offset();
+                }
+            };
+            let mut hooks = 0;
+            let dimensions = unit
+                .array_dimensions(offset, |bound| {
+                    hooks += 1;
+                    assert!(matches!(bound, DynamicBound::Expression(_)));
+                    Ok(Some(lower + 4))
+                })
+                .unwrap();
+            assert_eq!(hooks, 1);
+            let dimensions: Vec<_> = dimensions
+                .iter()
+                .map(|dimension| (dimension.lower_bound, dimension.count))
+                .collect();
+            assert_eq!(
+                dimensions,
+                [
+                    (Some(lower), Some(10 - lower as u64)),
+                    (Some(lower), Some(3)),
+                    (Some(lower), Some(0)),
+                    (Some(lower), Some(5)),
+                    (Some(lower), Some(8 - lower as u64)),
+                    (Some(lower), None),
+                    (Some(-2), Some(5)),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_bit_field() {
+        use crate::read::{DebugAbbrev, DebugInfo, Dwarf};
+        use crate::write::{self, EndianVec, LineProgram, Sections};
+        use crate::BigEndian;
+
+        // The example from DWARF 4 section D.2.8:
+        // struct S { int j:5; int k:6; int m:5; int n:8; };
+        let bit_sizes = [5, 6, 5, 8];
+        let big_endian_offsets = [0, 5, 11, 16];
+        let little_endian_offsets = [27, 21, 16, 8];
+        let expected: Vec<_> = big_endian_offsets
+            .iter()
+            .zip(bit_sizes)
+            .map(|(&bit_offset, bit_size)| BitField {
+                bit_offset,
+                bit_size,
+            })
+            .collect();
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 3,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let int = unit.add(root, constants::DW_TAG_base_type);
+        unit.get_mut(int)
+            .set(constants::DW_AT_byte_size, write::AttributeValue::Udata(4));
+        for (name, offsets) in [
+            (constants::DW_AT_bit_offset, big_endian_offsets),
+            (constants::DW_AT_bit_offset, little_endian_offsets),
+            (constants::DW_AT_data_bit_offset, big_endian_offsets),
+        ] {
+            let structure = unit.add(root, constants::DW_TAG_structure_type);
+            for (i, (offset, bit_size)) in offsets.iter().zip(bit_sizes).enumerate() {
+                let member = unit.add(structure, constants::DW_TAG_member);
+                let member = unit.get_mut(member);
+                member.set(constants::DW_AT_type, write::AttributeValue::UnitRef(int));
+                member.set(
+                    constants::DW_AT_bit_size,
+                    write::AttributeValue::Udata(bit_size),
+                );
+                member.set(name, write::AttributeValue::Udata(*offset));
+                if name == constants::DW_AT_bit_offset {
+                    member.set(
+                        constants::DW_AT_data_member_location,
+                        write::AttributeValue::Udata(0),
+                    );
+                    // Use the size of the type for some members.
+                    if i % 2 == 0 {
+                        member.set(constants::DW_AT_byte_size, write::AttributeValue::Udata(4));
+                    }
+                }
+            }
+        }
+        let member = unit.add(root, constants::DW_TAG_member);
+        unit.get_mut(member)
+            .set(constants::DW_AT_bit_size, write::AttributeValue::Udata(1));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let unit = read_dwarf.parsed_units().next().unwrap().unwrap();
+        let unit = unit.unit_ref(&read_dwarf);
+        let mut members = Vec::new();
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if entry.tag() == constants::DW_TAG_member {
+                members.push(entry.clone());
+            }
+        }
+        let (big_endian, rest) = members.split_at(4);
+        let (little_endian, rest) = rest.split_at(4);
+        let (data_bit_offset, rest) = rest.split_at(4);
+        for (members, big) in [
+            (big_endian, true),
+            (little_endian, false),
+            (data_bit_offset, true),
+            (data_bit_offset, false),
+        ] {
+            let bit_fields: Vec<_> = members
+                .iter()
+                .map(|member| {
+                    if big {
+                        unit.bit_field(member, BigEndian).unwrap().unwrap()
+                    } else {
+                        unit.bit_field(member, LittleEndian).unwrap().unwrap()
+                    }
+                })
+                .collect();
+            assert_eq!(bit_fields, expected);
+        }
+
+        // A member without a size for its storage unit.
+        assert_eq!(
+            unit.bit_field(&rest[0], LittleEndian),
+            Err(Error::MissingBitFieldStorageSize)
+        );
+        assert_eq!(
+            unit.bit_field(&rest[0], BigEndian),
+            Ok(Some(BitField {
+                bit_offset: 0,
+                bit_size: 1
+            }))
+        );
+        // Not a bit field.
+        let mut entries = unit.entries();
+        let (_, root) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(unit.bit_field(root, LittleEndian), Ok(None));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn test_fixed_point_type() {
+        use crate::read::{DebugAbbrev, DebugInfo, Dwarf};
+        use crate::write::{self, AttributeValue as Value, EndianVec, LineProgram, Sections};
+
+        let encoding = Encoding {
+            format: crate::common::Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let small = unit.add(root, constants::DW_TAG_constant);
+        let types = [
+            vec![
+                (
+                    constants::DW_AT_encoding,
+                    Value::Encoding(constants::DW_ATE_signed_fixed),
+                ),
+                (constants::DW_AT_binary_scale, Value::Sdata(-7)),
+            ],
+            vec![
+                (
+                    constants::DW_AT_encoding,
+                    Value::Encoding(constants::DW_ATE_packed_decimal),
+                ),
+                (constants::DW_AT_decimal_scale, Value::Data1(0xfe)),
+                (constants::DW_AT_digit_count, Value::Udata(5)),
+                (
+                    constants::DW_AT_decimal_sign,
+                    Value::DecimalSign(constants::DW_DS_leading_overpunch),
+                ),
+            ],
+            vec![
+                (
+                    constants::DW_AT_encoding,
+                    Value::Encoding(constants::DW_ATE_edited),
+                ),
+                (
+                    constants::DW_AT_picture_string,
+                    Value::String(b"9(5)V99".to_vec()),
+                ),
+            ],
+            vec![
+                (
+                    constants::DW_AT_encoding,
+                    Value::Encoding(constants::DW_ATE_unsigned_fixed),
+                ),
+                (constants::DW_AT_small, Value::UnitRef(small)),
+            ],
+            vec![(
+                constants::DW_AT_encoding,
+                Value::Encoding(constants::DW_ATE_signed),
+            )],
+        ];
+        for attrs in types {
+            let base_type = unit.add(root, constants::DW_TAG_base_type);
+            for (name, value) in attrs {
+                unit.get_mut(base_type).set(name, value);
+            }
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = Dwarf {
+            debug_abbrev: DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let unit = read_dwarf.parsed_units().next().unwrap().unwrap();
+        let unit = unit.unit_ref(&read_dwarf);
+        let mut entries = unit.entries();
+        let mut small = None;
+        let mut types = Vec::new();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            match entry.tag() {
+                constants::DW_TAG_constant => small = Some(entry.offset()),
+                constants::DW_TAG_base_type => types.push(unit.fixed_point_type(entry).unwrap()),
+                _ => {}
+            }
+        }
+        let fixed_point = |encoding, scale| FixedPointType {
+            encoding,
+            scale,
+            digit_count: None,
+            decimal_sign: None,
+            picture_string: None,
+        };
+        assert_eq!(
+            types,
+            [
+                Some(fixed_point(
+                    constants::DW_ATE_signed_fixed,
+                    Some(FixedPointScale::Binary(-7))
+                )),
+                Some(FixedPointType {
+                    digit_count: Some(5),
+                    decimal_sign: Some(constants::DW_DS_leading_overpunch),
+                    ..fixed_point(
+                        constants::DW_ATE_packed_decimal,
+                        Some(FixedPointScale::Decimal(-2))
+                    )
+                }),
+                Some(FixedPointType {
+                    picture_string: Some(EndianSlice::new(b"9(5)V99", LittleEndian)),
+                    ..fixed_point(constants::DW_ATE_edited, None)
+                }),
+                Some(fixed_point(
+                    constants::DW_ATE_unsigned_fixed,
+                    Some(FixedPointScale::Small(small.unwrap()))
+                )),
+                None,
+            ]
+        );
+    }
+}
@@ -15,8 +15,9 @@ use crate::constants;
 use crate::endianity::Endianity;
 use crate::read::abbrev::get_attribute_size;
 use crate::read::{
-    Abbreviation, Abbreviations, AttributeSpecification, DebugAbbrev, DebugStr, EndianSlice, Error,
-    Expression, Reader, ReaderOffset, Result, Section, UnitOffset,
+    truncated_section_error, Abbreviation, Abbreviations, AttributeSpecification,
+    DataMemberLocation, DebugAbbrev, DebugStr, Dwarf, EndianSlice, Error, Expression, Reader,
+    ReaderOffset, Result, Section, Unit, UnitOffset,
 };
 
 impl<T: ReaderOffset> DebugTypesOffset<T> {
@@ -56,23 +57,31 @@ impl<T: ReaderOffset> DebugInfoOffset<T> {
 impl<T: ReaderOffset> UnitOffset<T> {
     /// Convert an offset to be relative to the start of the .debug_info section,
     /// instead of relative to the start of the given unit. Returns None if the
-    /// provided unit lives in the .debug_types section.
+    /// provided unit lives in the .debug_types section, or if the offset is not
+    /// within the unit entries.
     pub fn to_debug_info_offset<R>(&self, unit: &UnitHeader<R>) -> Option<DebugInfoOffset<T>>
     where
         R: Reader<Offset = T>,
     {
         let unit_offset = unit.offset().as_debug_info_offset()?;
+        if !unit.is_valid_offset(*self) {
+            return None;
+        }
         Some(DebugInfoOffset(unit_offset.0 + self.0))
     }
 
     /// Convert an offset to be relative to the start of the .debug_types section,
     /// instead of relative to the start of the given unit. Returns None if the
-    /// provided unit lives in the .debug_info section.
+    /// provided unit lives in the .debug_info section, or if the offset is not
+    /// within the unit entries.
     pub fn to_debug_types_offset<R>(&self, unit: &UnitHeader<R>) -> Option<DebugTypesOffset<T>>
     where
         R: Reader<Offset = T>,
     {
         let unit_offset = unit.offset().as_debug_types_offset()?;
+        if !unit.is_valid_offset(*self) {
+            return None;
+        }
         Some(DebugTypesOffset(unit_offset.0 + self.0))
     }
 }
@@ -189,6 +198,7 @@ impl<R: Reader> DebugInfoUnitHeadersIter<R> {
             Ok(None)
         } else {
             let len = self.input.len();
+            let input = self.input.clone();
             match parse_unit_header(&mut self.input, self.offset.into()) {
                 Ok(header) => {
                     self.offset.0 += len - self.input.len();
@@ -196,7 +206,7 @@ impl<R: Reader> DebugInfoUnitHeadersIter<R> {
                 }
                 Err(e) => {
                     self.input.empty();
-                    Err(e)
+                    Err(truncated_section_error(&input, e))
                 }
             }
         }
@@ -213,6 +223,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for DebugInfoUnitHeadersIter
     }
 }
 
+result_iterator!([R: Reader] DebugInfoUnitHeadersIter<R>, UnitHeader<R>);
+
 /// Parse the unit type from the unit header.
 fn parse_unit_type<R: Reader>(input: &mut R) -> Result<constants::DwUt> {
     let val = input.read_u8()?;
@@ -872,6 +884,18 @@ where
         self.attr(name).map(|attr| attr.map(|attr| attr.value()))
     }
 
+    /// Find the `DW_AT_data_member_location` attribute and return its value
+    /// as a `DataMemberLocation`.
+    ///
+    /// Returns `None` if the entry does not have this attribute. Note that
+    /// a missing location for a member of a union means an offset of 0.
+    pub fn data_member_location(&self) -> Result<Option<DataMemberLocation<R>>> {
+        match self.attr_value(constants::DW_AT_data_member_location)? {
+            Some(value) => DataMemberLocation::from_attr(&value, self.unit.encoding()).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Return the input buffer after the last attribute.
     #[inline(always)]
     fn after_attrs(&self) -> Result<R> {
@@ -888,16 +912,22 @@ where
 
     /// Use the `DW_AT_sibling` attribute to find the input buffer for the
     /// next sibling. Returns `None` if the attribute is missing or invalid.
+    ///
+    /// The sibling must be within the unit, and must follow the attributes
+    /// of this entry and the null entry that terminates its children, so
+    /// that skipping to it always moves forward.
     fn sibling(&self) -> Option<R> {
-        let attr = self.attr_value(constants::DW_AT_sibling);
-        if let Ok(Some(AttributeValue::UnitRef(offset))) = attr {
-            if offset.0 > self.offset.0 {
-                if let Ok(input) = self.unit.range_from(offset..) {
-                    return Some(input);
-                }
-            }
+        let offset = match self.attr_value(constants::DW_AT_sibling) {
+            Ok(Some(AttributeValue::UnitRef(offset))) => offset,
+            Ok(Some(AttributeValue::DebugInfoRef(offset))) => offset.to_unit_offset(self.unit)?,
+            _ => return None,
+        };
+        let after_attrs = self.after_attrs().ok()?;
+        let children = self.unit.header_size() + after_attrs.offset_from(&self.unit.entries_buf);
+        if offset.0 <= children {
+            return None;
         }
-        None
+        self.unit.range_from(offset..).ok()
     }
 
     /// Parse an entry. Returns `Ok(None)` for null entries.
@@ -929,6 +959,14 @@ where
 // for their data.  This gives better code generation in `parse_attribute`.
 #[repr(u64)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize, Offset: serde::Serialize",
+        deserialize = "R: serde::Deserialize<'de>, Offset: serde::Deserialize<'de>"
+    ))
+)]
 pub enum AttributeValue<R, Offset = <R as Reader>::Offset>
 where
     R: Reader<Offset = Offset>,
@@ -977,6 +1015,15 @@ where
     /// anything else."
     Data8(u64),
 
+    /// A sixteen byte constant data value. How to interpret the bytes depends on context.
+    ///
+    /// These bytes are in the order that they appear in the section, and have not
+    /// been converted from `R::Endian`.
+    ///
+    /// This is used for values such as MD5 digests and the constant values of
+    /// 128-bit types.
+    Data16([u8; 16]),
+
     /// A signed integer constant.
     Sdata(i64),
 
@@ -1102,13 +1149,29 @@ where
     /// An implementation-defined identifier uniquely identifying a compilation
     /// unit.
     DwoId(DwoId),
+
+    /// The raw bytes of a value that uses a form not known to gimli.
+    ///
+    /// These are only produced if the size of the form was provided by
+    /// `DebugAbbrev::set_unknown_form_size`. The form is available from
+    /// `Attribute::form`.
+    Unknown(R),
 }
 
 /// An attribute in a `DebuggingInformationEntry`, consisting of a name and
 /// associated value.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "R: serde::Serialize, R::Offset: serde::Serialize",
+        deserialize = "R: serde::Deserialize<'de>, R::Offset: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Attribute<R: Reader> {
     name: constants::DwAt,
+    form: constants::DwForm,
     value: AttributeValue<R>,
 }
 
@@ -1118,6 +1181,13 @@ impl<R: Reader> Attribute<R> {
         self.name
     }
 
+    /// Get the form that this attribute's value was encoded with.
+    ///
+    /// For `DW_FORM_indirect`, this is the form that was read from the entry.
+    pub fn form(&self) -> constants::DwForm {
+        self.form
+    }
+
     /// Get this attribute's raw value.
     pub fn raw_value(&self) -> AttributeValue<R> {
         self.value.clone()
@@ -1655,19 +1725,19 @@ impl<R: Reader> Attribute<R> {
             constants::DW_AT_macros => {
                 macroptr!();
             }
-            constants::DW_AT_call_all_calls => {
+            constants::DW_AT_call_all_calls | constants::DW_AT_GNU_all_call_sites => {
                 flag!();
             }
             constants::DW_AT_call_all_source_calls => {
                 flag!();
             }
-            constants::DW_AT_call_all_tail_calls => {
+            constants::DW_AT_call_all_tail_calls | constants::DW_AT_GNU_all_tail_call_sites => {
                 flag!();
             }
             constants::DW_AT_call_return_pc => {
                 address!();
             }
-            constants::DW_AT_call_value => {
+            constants::DW_AT_call_value | constants::DW_AT_GNU_call_site_value => {
                 exprloc!();
             }
             constants::DW_AT_call_origin => {
@@ -1679,19 +1749,20 @@ impl<R: Reader> Attribute<R> {
             constants::DW_AT_call_pc => {
                 address!();
             }
-            constants::DW_AT_call_tail_call => {
+            constants::DW_AT_call_tail_call | constants::DW_AT_GNU_tail_call => {
                 flag!();
             }
-            constants::DW_AT_call_target => {
+            constants::DW_AT_call_target | constants::DW_AT_GNU_call_site_target => {
                 exprloc!();
             }
-            constants::DW_AT_call_target_clobbered => {
+            constants::DW_AT_call_target_clobbered
+            | constants::DW_AT_GNU_call_site_target_clobbered => {
                 exprloc!();
             }
             constants::DW_AT_call_data_location => {
                 exprloc!();
             }
-            constants::DW_AT_call_data_value => {
+            constants::DW_AT_call_data_value | constants::DW_AT_GNU_call_site_data_value => {
                 exprloc!();
             }
             constants::DW_AT_noreturn => {
@@ -1750,6 +1821,33 @@ impl<R: Reader> Attribute<R> {
         self.value.offset_value()
     }
 
+    /// Try to convert this attribute's value to a flag.
+    #[inline]
+    pub fn flag_value(&self) -> Option<bool> {
+        self.value.flag_value()
+    }
+
+    /// Try to return this attribute's value as a block of bytes.
+    #[inline]
+    pub fn block_value(&self) -> Option<R> {
+        self.value.block_value()
+    }
+
+    /// Try to convert this attribute's value to an offset of an entry within the
+    /// given unit.
+    #[inline]
+    pub fn unit_offset_value(&self, unit: &Unit<R>) -> Option<UnitOffset<R::Offset>> {
+        self.value.unit_offset_value(unit)
+    }
+
+    /// Try to convert this attribute's value to an address.
+    ///
+    /// See `AttributeValue::address_value` for more information.
+    #[inline]
+    pub fn address_value(&self, dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<Option<u64>> {
+        self.value.address_value(dwarf, unit)
+    }
+
     /// Try to convert this attribute's value to an expression or location buffer.
     ///
     /// Expressions and locations may be `DW_FORM_block*` or `DW_FORM_exprloc`.
@@ -1768,7 +1866,7 @@ impl<R: Reader> Attribute<R> {
     /// value forms are returned as `None`.
     ///
     /// Warning: this function does not handle all possible string forms.
-    /// Use `Dwarf::attr_string` instead.
+    /// Use `resolved_string_value` or `Dwarf::attr_string` instead.
     #[inline]
     pub fn string_value(&self, debug_str: &DebugStr<R>) -> Option<R> {
         self.value.string_value(debug_str)
@@ -1783,7 +1881,7 @@ impl<R: Reader> Attribute<R> {
     /// value forms are returned as `None`.
     ///
     /// Warning: this function does not handle all possible string forms.
-    /// Use `Dwarf::attr_string` instead.
+    /// Use `resolved_string_value` or `Dwarf::attr_string` instead.
     #[inline]
     pub fn string_value_sup(
         &self,
@@ -1792,6 +1890,15 @@ impl<R: Reader> Attribute<R> {
     ) -> Option<R> {
         self.value.string_value_sup(debug_str, debug_str_sup)
     }
+
+    /// Try to return this attribute's value as a string slice, resolving
+    /// any string form.
+    ///
+    /// See `AttributeValue::resolved_string_value` for more information.
+    #[inline]
+    pub fn resolved_string_value(&self, dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<Option<R>> {
+        self.value.resolved_string_value(dwarf, unit)
+    }
 }
 
 impl<R, Offset> AttributeValue<R, Offset>
@@ -1868,6 +1975,50 @@ where
         }
     }
 
+    /// Try to convert this attribute's value to a flag.
+    ///
+    /// `DW_FORM_flag_present` values have already been converted to `Flag(true)`.
+    pub fn flag_value(&self) -> Option<bool> {
+        if let AttributeValue::Flag(flag) = *self {
+            Some(flag)
+        } else {
+            None
+        }
+    }
+
+    /// Try to return this attribute's value as a block of bytes.
+    pub fn block_value(&self) -> Option<R> {
+        if let AttributeValue::Block(ref data) = *self {
+            Some(data.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Try to convert this attribute's value to an offset of an entry within the
+    /// given unit.
+    ///
+    /// Returns `None` if the value is not a reference, or if it is a
+    /// `DW_FORM_ref_addr` reference to an entry in a different unit.
+    pub fn unit_offset_value(&self, unit: &Unit<R, Offset>) -> Option<UnitOffset<Offset>> {
+        match *self {
+            AttributeValue::UnitRef(offset) => Some(offset),
+            AttributeValue::DebugInfoRef(offset) => offset.to_unit_offset(&unit.header),
+            _ => None,
+        }
+    }
+
+    /// Try to convert this attribute's value to an address.
+    ///
+    /// If the value is a `DW_FORM_addr`, or a `DW_FORM_addrx` index into the
+    /// `.debug_addr` entries for the unit, then return the address.
+    /// Returns `None` for other forms.
+    ///
+    /// This is the same as `Dwarf::attr_address`.
+    pub fn address_value(&self, dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<Option<u64>> {
+        dwarf.attr_address(unit, self.clone())
+    }
+
     /// Try to convert this attribute's value to an expression or location buffer.
     ///
     /// Expressions and locations may be `DW_FORM_block*` or `DW_FORM_exprloc`.
@@ -1889,7 +2040,7 @@ where
     /// value forms are returned as `None`.
     ///
     /// Warning: this function does not handle all possible string forms.
-    /// Use `Dwarf::attr_string` instead.
+    /// Use `resolved_string_value` or `Dwarf::attr_string` instead.
     pub fn string_value(&self, debug_str: &DebugStr<R>) -> Option<R> {
         match *self {
             AttributeValue::String(ref string) => Some(string.clone()),
@@ -1907,7 +2058,7 @@ where
     /// value forms are returned as `None`.
     ///
     /// Warning: this function does not handle all possible string forms.
-    /// Use `Dwarf::attr_string` instead.
+    /// Use `resolved_string_value` or `Dwarf::attr_string` instead.
     pub fn string_value_sup(
         &self,
         debug_str: &DebugStr<R>,
@@ -1922,6 +2073,26 @@ where
             _ => None,
         }
     }
+
+    /// Try to return this attribute's value as a string slice, resolving
+    /// any string form.
+    ///
+    /// This handles all of the string forms that `Dwarf::attr_string` handles,
+    /// including `DW_FORM_strx` indices into the `.debug_str_offsets` entries
+    /// for the unit. Returns `None` for values that do not have a string form,
+    /// and an error if a string form has an invalid value.
+    pub fn resolved_string_value(&self, dwarf: &Dwarf<R>, unit: &Unit<R>) -> Result<Option<R>> {
+        match *self {
+            AttributeValue::String(_)
+            | AttributeValue::DebugStrRef(_)
+            | AttributeValue::DebugStrRefSup(_)
+            | AttributeValue::DebugLineStrRef(_)
+            | AttributeValue::DebugStrOffsetsIndex(_) => {
+                dwarf.attr_string(unit, self.clone()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
 }
 
 fn length_u8_value<R: Reader>(input: &mut R) -> Result<R> {
@@ -2034,8 +2205,8 @@ pub(crate) fn parse_attribute<R: Reader>(
                 }
             }
             constants::DW_FORM_data16 => {
-                let block = input.split(R::Offset::from_u8(16))?;
-                AttributeValue::Block(block)
+                let data = input.read_u8_array()?;
+                AttributeValue::Data16(data)
             }
             constants::DW_FORM_udata => {
                 let data = input.read_uleb128()?;
@@ -2179,12 +2350,17 @@ pub(crate) fn parse_attribute<R: Reader>(
                 let index = input.read_uleb128().and_then(R::Offset::from_u64)?;
                 AttributeValue::DebugRngListsIndex(DebugRngListsIndex(index))
             }
-            _ => {
-                return Err(Error::UnknownForm);
-            }
+            _ => match spec.unknown_form_size() {
+                Some(size) if form == spec.form() => {
+                    let data = input.split(R::Offset::from_u8(size))?;
+                    AttributeValue::Unknown(data)
+                }
+                _ => return Err(Error::UnknownForm),
+            },
         };
         let attr = Attribute {
             name: spec.name(),
+            form,
             value,
         };
         return Ok(attr);
@@ -2245,9 +2421,12 @@ pub(crate) fn skip_attributes<R: Reader>(
                 | constants::DW_FORM_rnglistx => {
                     input.skip_leb128()?;
                 }
-                _ => {
-                    return Err(Error::UnknownForm);
-                }
+                _ => match spec.unknown_form_size() {
+                    Some(size) if form == spec.form() => {
+                        skip_bytes = R::Offset::from_u8(size);
+                    }
+                    _ => return Err(Error::UnknownForm),
+                },
             };
             break;
         }
@@ -2325,6 +2504,11 @@ impl<'abbrev, 'entry, 'unit, R: Reader> fallible_iterator::FallibleIterator
     }
 }
 
+result_iterator!(
+    ['abbrev, 'entry, 'unit, R: Reader] AttrsIter<'abbrev, 'entry, 'unit, R>,
+    Attribute<R>
+);
+
 /// A raw reader of the data that defines the Debugging Information Entries.
 ///
 /// `EntriesRaw` provides primitives to read the components of Debugging Information
@@ -2800,8 +2984,101 @@ impl<'abbrev, 'unit, R: Reader> EntriesCursor<'abbrev, 'unit, R> {
             }
         }
     }
+
+    /// Return a bookmark for the current position of the cursor.
+    ///
+    /// The cursor can be returned to this position later using `restore`.
+    /// This is cheaper than creating a new cursor with
+    /// `UnitHeader::entries_at_offset` and moving it to the same position,
+    /// and also preserves the depth information that is returned by
+    /// `next_dfs`.
+    #[inline]
+    pub fn bookmark(&self) -> EntriesCursorBookmark<R::Offset> {
+        let (offset, current) = match self.cached_current {
+            Some(ref current) => (current.offset(), true),
+            None => (
+                UnitOffset(
+                    self.unit.header_size() + self.input.offset_from(&self.unit.entries_buf),
+                ),
+                false,
+            ),
+        };
+        EntriesCursorBookmark {
+            offset,
+            current,
+            delta_depth: self.delta_depth,
+        }
+    }
+
+    /// Move the cursor to a position previously returned by `bookmark`.
+    ///
+    /// The bookmark must have been created by a cursor for the same unit.
+    /// The current entry, if any, is parsed again.
+    pub fn restore(&mut self, bookmark: EntriesCursorBookmark<R::Offset>) -> Result<()> {
+        let header_size = self.unit.header_size();
+        if bookmark.offset.0 < header_size {
+            return Err(Error::OffsetOutOfBounds);
+        }
+        let mut input = self.unit.entries_buf.clone();
+        input.skip(bookmark.offset.0 - header_size)?;
+        self.input = input;
+        self.cached_current = None;
+        if bookmark.current {
+            self.next_entry()?;
+        }
+        self.delta_depth = bookmark.delta_depth;
+        Ok(())
+    }
+}
+
+/// A saved position of an `EntriesCursor`.
+///
+/// See `EntriesCursor::bookmark`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntriesCursorBookmark<T = usize> {
+    /// The offset of the current entry, or of the next entry if there is no
+    /// current entry.
+    offset: UnitOffset<T>,
+    /// Whether there is a current entry.
+    current: bool,
+    delta_depth: isize,
 }
 
+impl<T: ReaderOffset> EntriesCursorBookmark<T> {
+    /// Return the offset of the entry that the cursor was pointing to, or
+    /// of the next entry if the cursor was not pointing to an entry.
+    #[inline]
+    pub fn offset(&self) -> UnitOffset<T> {
+        self.offset
+    }
+}
+
+/// Iterate over the entries in depth-first order.
+///
+/// Each item is the same as the result of `next_dfs`, except that the entry is
+/// cloned so that it is not borrowed from the cursor.
+#[cfg(feature = "fallible-iterator")]
+impl<'abbrev, 'unit, R: Reader> fallible_iterator::FallibleIterator
+    for EntriesCursor<'abbrev, 'unit, R>
+{
+    type Item = (isize, DebuggingInformationEntry<'abbrev, 'unit, R>);
+    type Error = Error;
+
+    fn next(&mut self) -> ::core::result::Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .next_dfs()?
+            .map(|(delta_depth, entry)| (delta_depth, entry.clone())))
+    }
+}
+
+result_iterator!(
+    ['abbrev, 'unit, R: Reader] EntriesCursor<'abbrev, 'unit, R>,
+    (isize, DebuggingInformationEntry<'abbrev, 'unit, R>),
+    |cursor| Ok(cursor
+        .next_dfs()?
+        .map(|(delta_depth, entry)| (delta_depth, entry.clone())))
+);
+
 /// The state information for a tree view of the Debugging Information Entries.
 ///
 /// The `EntriesTree` can be used to recursively iterate through the DIE
@@ -3160,6 +3437,7 @@ impl<R: Reader> DebugTypesUnitHeadersIter<R> {
             Ok(None)
         } else {
             let len = self.input.len();
+            let input = self.input.clone();
             match parse_unit_header(&mut self.input, self.offset.into()) {
                 Ok(header) => {
                     self.offset.0 += len - self.input.len();
@@ -3167,7 +3445,7 @@ impl<R: Reader> DebugTypesUnitHeadersIter<R> {
                 }
                 Err(e) => {
                     self.input.empty();
-                    Err(e)
+                    Err(truncated_section_error(&input, e))
                 }
             }
         }
@@ -3184,6 +3462,8 @@ impl<R: Reader> fallible_iterator::FallibleIterator for DebugTypesUnitHeadersIte
     }
 }
 
+result_iterator!([R: Reader] DebugTypesUnitHeadersIter<R>, UnitHeader<R>);
+
 #[cfg(test)]
 // Tests require leb128::write.
 #[cfg(feature = "write")]
@@ -4226,6 +4506,7 @@ mod tests {
             let (value, expect_udata, expect_sdata) = *test;
             let attribute = Attribute {
                 name: DW_AT_data_member_location,
+                form: constants::DW_FORM_udata,
                 value,
             };
             assert_eq!(attribute.udata_value(), expect_udata);
@@ -4271,14 +4552,18 @@ mod tests {
     {
         let spec = AttributeSpecification::new(constants::DW_AT_low_pc, form, None);
 
-        let expect = Attribute {
-            name: constants::DW_AT_low_pc,
-            value,
-        };
-
         let rest = &mut EndianSlice::new(buf, Endian::default());
         match parse_attribute(rest, unit.encoding(), spec) {
             Ok(attr) => {
+                let expect = Attribute {
+                    name: constants::DW_AT_low_pc,
+                    form: if form == constants::DW_FORM_indirect {
+                        attr.form
+                    } else {
+                        form
+                    },
+                    value,
+                };
                 assert_eq!(attr, expect);
                 assert_eq!(*rest, EndianSlice::new(&buf[len..], Endian::default()));
                 if let Some(size) = spec.size(unit) {
@@ -4385,6 +4670,20 @@ mod tests {
         test_parse_attribute(&buf, 8, &unit, form, value);
     }
 
+    #[test]
+    fn test_parse_attribute_data16() {
+        let buf = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+            0x0f, 0x10, 0x99, 0x99,
+        ];
+        let unit = test_parse_attribute_unit_default();
+        let form = constants::DW_FORM_data16;
+        let mut data = [0; 16];
+        data.copy_from_slice(&buf[..16]);
+        let value = AttributeValue::Data16(data);
+        test_parse_attribute(&buf, 16, &unit, form, value);
+    }
+
     #[test]
     fn test_parse_attribute_udata() {
         let mut buf = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
@@ -4847,6 +5146,178 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_skip_attributes_indirect() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let specs = [
+            AttributeSpecification::new(constants::DW_AT_name, constants::DW_FORM_indirect, None),
+            AttributeSpecification::new(constants::DW_AT_low_pc, constants::DW_FORM_data4, None),
+            AttributeSpecification::new(
+                constants::DW_AT_location,
+                constants::DW_FORM_indirect,
+                None,
+            ),
+            AttributeSpecification::new(
+                constants::DW_AT_high_pc,
+                constants::DW_FORM_indirect,
+                None,
+            ),
+        ];
+        let section = Section::with_endian(Endian::Little)
+            .uleb(constants::DW_FORM_string.0.into())
+            .append_bytes(b"foo\0")
+            .L32(0x1234_5678)
+            .uleb(constants::DW_FORM_block1.0.into())
+            .D8(3)
+            .append_bytes(&[1, 2, 3])
+            .uleb(constants::DW_FORM_data2.0.into())
+            .L16(0x9abc)
+            .D8(0xaa);
+        let buf = section.get_contents().unwrap();
+
+        let mut input = EndianSlice::new(&buf, LittleEndian);
+        skip_attributes(&mut input, encoding, &specs).unwrap();
+        assert_eq!(input, EndianSlice::new(&[0xaa], LittleEndian));
+
+        let mut input = EndianSlice::new(&buf, LittleEndian);
+        let mut values = Vec::new();
+        for spec in &specs {
+            values.push(
+                parse_attribute(&mut input, encoding, *spec)
+                    .unwrap()
+                    .raw_value(),
+            );
+        }
+        assert_eq!(
+            values,
+            [
+                AttributeValue::String(EndianSlice::new(b"foo", LittleEndian)),
+                AttributeValue::Data4(0x1234_5678),
+                AttributeValue::Block(EndianSlice::new(&[1, 2, 3], LittleEndian)),
+                AttributeValue::Data2(0x9abc),
+            ]
+        );
+        assert_eq!(input, EndianSlice::new(&[0xaa], LittleEndian));
+    }
+
+    #[test]
+    fn test_parse_attribute_unknown_form() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let form = constants::DwForm(0x1f80);
+        let buf = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+
+        let spec = AttributeSpecification::new(constants::DW_AT_name, form, None);
+        let input = &mut EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            parse_attribute(input, encoding, spec),
+            Err(Error::UnknownForm)
+        );
+        let input = &mut EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            skip_attributes(input, encoding, &[spec]),
+            Err(Error::UnknownForm)
+        );
+
+        let spec = spec.with_unknown_form_size(3);
+        let input = &mut EndianSlice::new(&buf, LittleEndian);
+        let attr = parse_attribute(input, encoding, spec).unwrap();
+        assert_eq!(attr.name(), constants::DW_AT_name);
+        assert_eq!(attr.form(), form);
+        assert_eq!(
+            attr.value(),
+            AttributeValue::Unknown(EndianSlice::new(&buf[..3], LittleEndian))
+        );
+        assert_eq!(*input, EndianSlice::new(&buf[3..], LittleEndian));
+        let input = &mut EndianSlice::new(&buf, LittleEndian);
+        skip_attributes(input, encoding, &[spec, spec]).unwrap();
+        assert_eq!(*input, EndianSlice::new(&[], LittleEndian));
+
+        // The size only applies to the form in the abbreviation.
+        let spec =
+            AttributeSpecification::new(constants::DW_AT_name, constants::DW_FORM_indirect, None)
+                .with_unknown_form_size(3);
+        let mut buf = [0; 8];
+        leb128::write::unsigned(&mut &mut buf[..], form.0.into()).unwrap();
+        let input = &mut EndianSlice::new(&buf, LittleEndian);
+        assert_eq!(
+            parse_attribute(input, encoding, spec),
+            Err(Error::UnknownForm)
+        );
+    }
+
+    #[test]
+    fn test_attribute_value_accessors() {
+        let debug_str = [b'f', b'o', b'o', 0, b'b', b'a', b'r', 0];
+        let debug_str_offsets = [4, 0, 0, 0, 0, 0, 0, 0];
+        let debug_addr = [0x00, 0x10, 0, 0, 0x00, 0x20, 0, 0];
+        let dwarf = crate::read::Dwarf {
+            debug_str: DebugStr::new(&debug_str, LittleEndian),
+            debug_str_offsets: EndianSlice::new(&debug_str_offsets, LittleEndian).into(),
+            debug_addr: EndianSlice::new(&debug_addr, LittleEndian).into(),
+            ..Default::default()
+        };
+        let unit = crate::read::Unit {
+            header: test_parse_attribute_unit(4, Format::Dwarf32, LittleEndian),
+            abbreviations: Default::default(),
+            name: None,
+            comp_dir: None,
+            language: None,
+            low_pc: 0,
+            str_offsets_base: DebugStrOffsetsBase(0),
+            addr_base: DebugAddrBase(0),
+            loclists_base: DebugLocListsBase(0),
+            rnglists_base: DebugRngListsBase(0),
+            line_program: None,
+            dwo_id: None,
+        };
+
+        let value = AttributeValue::DebugStrOffsetsIndex(DebugStrOffsetsIndex(0));
+        assert_eq!(
+            value.resolved_string_value(&dwarf, &unit),
+            Ok(Some(EndianSlice::new(b"bar", LittleEndian)))
+        );
+        assert_eq!(value.string_value(&dwarf.debug_str), None);
+        let value = AttributeValue::DebugStrRef(DebugStrOffset(0));
+        assert_eq!(
+            value.resolved_string_value(&dwarf, &unit),
+            Ok(Some(EndianSlice::new(b"foo", LittleEndian)))
+        );
+        let value = AttributeValue::DebugStrOffsetsIndex(DebugStrOffsetsIndex(2));
+        assert!(value.resolved_string_value(&dwarf, &unit).is_err());
+        let value = AttributeValue::Udata(1);
+        assert_eq!(value.resolved_string_value(&dwarf, &unit), Ok(None));
+
+        let value = AttributeValue::DebugAddrIndex(DebugAddrIndex(1));
+        assert_eq!(value.address_value(&dwarf, &unit), Ok(Some(0x2000)));
+        let value = AttributeValue::Addr(0x3000);
+        assert_eq!(value.address_value(&dwarf, &unit), Ok(Some(0x3000)));
+        let value = AttributeValue::Udata(1);
+        assert_eq!(value.address_value(&dwarf, &unit), Ok(None));
+
+        let value = AttributeValue::UnitRef(UnitOffset(5));
+        assert_eq!(value.unit_offset_value(&unit), Some(UnitOffset(5)));
+        let value = AttributeValue::DebugInfoRef(DebugInfoOffset(100));
+        assert_eq!(value.unit_offset_value(&unit), None);
+
+        let value = AttributeValue::<EndianSlice<'_, LittleEndian>>::Flag(true);
+        assert_eq!(value.flag_value(), Some(true));
+        assert_eq!(value.block_value(), None);
+        let value = AttributeValue::Block(EndianSlice::new(&debug_addr, LittleEndian));
+        assert_eq!(
+            value.block_value(),
+            Some(EndianSlice::new(&debug_addr, LittleEndian))
+        );
+        assert_eq!(value.flag_value(), None);
+    }
+
     #[test]
     fn test_attrs_iter() {
         let encoding = Encoding {
@@ -4905,6 +5376,7 @@ mod tests {
                     attr,
                     Attribute {
                         name: constants::DW_AT_name,
+                        form: constants::DW_FORM_string,
                         value: AttributeValue::String(EndianSlice::new(b"foo", LittleEndian)),
                     }
                 );
@@ -4922,6 +5394,7 @@ mod tests {
                     attr,
                     Attribute {
                         name: constants::DW_AT_low_pc,
+                        form: constants::DW_FORM_addr,
                         value: AttributeValue::Addr(0x2a),
                     }
                 );
@@ -4939,6 +5412,7 @@ mod tests {
                     attr,
                     Attribute {
                         name: constants::DW_AT_high_pc,
+                        form: constants::DW_FORM_addr,
                         value: AttributeValue::Addr(0x539),
                     }
                 );
@@ -5013,6 +5487,7 @@ mod tests {
                     attr,
                     Attribute {
                         name: constants::DW_AT_name,
+                        form: constants::DW_FORM_string,
                         value: AttributeValue::String(EndianSlice::new(b"foo", LittleEndian)),
                     }
                 );
@@ -5333,6 +5808,94 @@ mod tests {
         assert!(cursor.current().is_none());
     }
 
+    #[test]
+    fn test_cursor_bookmark() {
+        let info_buf = &entries_cursor_tests_debug_info_buf();
+        let debug_info = DebugInfo::new(info_buf, LittleEndian);
+
+        let unit = debug_info
+            .units()
+            .next()
+            .expect("should have a unit result")
+            .expect("and it should be ok");
+
+        let abbrevs_buf = &entries_cursor_tests_abbrev_buf();
+        let debug_abbrev = DebugAbbrev::new(abbrevs_buf, LittleEndian);
+
+        let abbrevs = unit
+            .abbreviations(&debug_abbrev)
+            .expect("Should parse abbreviations");
+
+        let mut cursor = unit.entries(&abbrevs);
+
+        let start = cursor.bookmark();
+        assert_eq!(start.offset(), UnitOffset(unit.header_size()));
+        assert_next_dfs(&mut cursor, "001", 0);
+        assert_next_dfs(&mut cursor, "002", 1);
+        let bookmark = cursor.bookmark();
+        assert_eq!(bookmark.offset(), cursor.current().unwrap().offset());
+        assert_next_dfs(&mut cursor, "003", 1);
+        assert_next_dfs(&mut cursor, "004", -1);
+
+        // Rewind to an entry, and check that the depth changes are the same.
+        cursor.restore(bookmark).expect("Should restore bookmark");
+        assert_eq!(cursor.bookmark(), bookmark);
+        assert_eq!(cursor.current().unwrap().offset(), bookmark.offset());
+        assert_next_dfs(&mut cursor, "003", 1);
+        assert_next_dfs(&mut cursor, "004", -1);
+
+        // Bookmark a null entry.
+        assert_next_entry(&mut cursor, "005");
+        assert_next_entry_null(&mut cursor);
+        let null = cursor.bookmark();
+        assert_next_entry(&mut cursor, "006");
+        cursor.restore(null).expect("Should restore bookmark");
+        assert!(cursor.current().is_none());
+        assert_next_entry(&mut cursor, "006");
+
+        // Bookmark the end of the entries.
+        while cursor.next_dfs().expect("Should parse next dfs").is_some() {}
+        let end = cursor.bookmark();
+        cursor.restore(start).expect("Should restore bookmark");
+        assert_next_dfs(&mut cursor, "001", 0);
+        cursor.restore(end).expect("Should restore bookmark");
+        assert!(cursor.next_dfs().expect("Should parse next dfs").is_none());
+    }
+
+    #[test]
+    fn test_cursor_iterator() {
+        let info_buf = &entries_cursor_tests_debug_info_buf();
+        let debug_info = DebugInfo::new(info_buf, LittleEndian);
+
+        let unit = debug_info
+            .units()
+            .into_result_iter()
+            .next()
+            .expect("should have a unit result")
+            .expect("and it should be ok");
+
+        let abbrevs_buf = &entries_cursor_tests_abbrev_buf();
+        let debug_abbrev = DebugAbbrev::new(abbrevs_buf, LittleEndian);
+
+        let abbrevs = unit
+            .abbreviations(&debug_abbrev)
+            .expect("Should parse abbreviations");
+
+        let mut depths = Vec::new();
+        for (index, item) in unit.entries(&abbrevs).into_result_iter().enumerate() {
+            let (delta_depth, entry) = item.expect("Should parse next dfs");
+            assert_entry_name(&entry, &format!("{:03}", index + 1));
+            let attrs = entry
+                .attrs()
+                .into_result_iter()
+                .collect::<Result<Vec<_>>>()
+                .expect("Should parse attributes");
+            assert_eq!(attrs.len(), 1);
+            depths.push(delta_depth);
+        }
+        assert_eq!(depths, [0, 1, 1, -1, 1, 0, -1, 1, 1, -2]);
+    }
+
     #[test]
     fn test_cursor_next_sibling_no_sibling_ptr() {
         let info_buf = &entries_cursor_tests_debug_info_buf();
@@ -5566,6 +6129,142 @@ mod tests {
         test_cursor_next_sibling_with_ptr(&mut cursor);
     }
 
+    #[test]
+    fn test_cursor_next_sibling_ptr_validation() {
+        #[rustfmt::skip]
+        let abbrev_buf = Section::with_endian(Endian::Little)
+            .abbrev(1, DW_TAG_subprogram, DW_CHILDREN_yes)
+                .abbrev_attr(DW_AT_name, DW_FORM_string)
+                .abbrev_attr(DW_AT_sibling, DW_FORM_ref1)
+                .abbrev_attr_null()
+            .abbrev(2, DW_TAG_subprogram, DW_CHILDREN_yes)
+                .abbrev_attr(DW_AT_name, DW_FORM_string)
+                .abbrev_attr_null()
+            .abbrev(3, DW_TAG_subprogram, DW_CHILDREN_yes)
+                .abbrev_attr(DW_AT_name, DW_FORM_string)
+                .abbrev_attr(DW_AT_sibling, DW_FORM_ref_addr)
+                .abbrev_attr_null()
+            .abbrev_null()
+            .get_contents()
+            .unwrap();
+
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut unit = UnitHeader {
+            encoding,
+            unit_length: 0,
+            unit_type: UnitType::Compilation,
+            debug_abbrev_offset: DebugAbbrevOffset(0),
+            unit_offset: DebugInfoOffset(0).into(),
+            entries_buf: EndianSlice::new(&[], LittleEndian),
+        };
+        let header_size = unit.size_of_header();
+
+        let start = Label::new();
+        let sibling004_ref = Label::new();
+        let sibling004 = Label::new();
+        let attrs004_ref = Label::new();
+        let attrs004 = Label::new();
+        #[rustfmt::skip]
+        let section = Section::with_endian(Endian::Little)
+            .mark(&start)
+            .die(2, |s| s.attr_string("001"))
+                // Valid sibling attribute using `DW_FORM_ref_addr`.
+                .die(3, |s| s.attr_string("002").D32(&sibling004_ref))
+                    // Invalid code to ensure the sibling attribute was used.
+                    .die(10, |s| s.attr_string("003"))
+                        .die_null()
+                    .die_null()
+                .mark(&sibling004)
+                // Invalid sibling attribute that refers to its own attributes.
+                .die(1, |s| s.mark(&attrs004).attr_string("004").D8(&attrs004_ref))
+                    .die(2, |s| s.attr_string("005"))
+                        .die_null()
+                    .die_null()
+                .die(2, |s| s.attr_string("006"))
+                    .die_null()
+                .die_null();
+        sibling004_ref.set_const(header_size as u64 + (&sibling004 - &start) as u64);
+        attrs004_ref.set_const(header_size as u64 + (&attrs004 - &start) as u64);
+        let entries_buf = section.get_contents().unwrap();
+
+        unit.entries_buf = EndianSlice::new(&entries_buf, LittleEndian);
+        let info_buf = Section::with_endian(Endian::Little)
+            .unit(&mut unit)
+            .get_contents()
+            .unwrap();
+        let debug_info = DebugInfo::new(&info_buf, LittleEndian);
+        let unit = debug_info.units().next().unwrap().unwrap();
+        let debug_abbrev = DebugAbbrev::new(&abbrev_buf, LittleEndian);
+        let abbrevs = unit.abbreviations(&debug_abbrev).unwrap();
+
+        let mut cursor = unit.entries(&abbrevs);
+        assert_next_dfs(&mut cursor, "001", 0);
+        assert_next_dfs(&mut cursor, "002", 1);
+        assert_next_sibling(&mut cursor, "004");
+        assert_next_sibling(&mut cursor, "006");
+        assert!(cursor.next_sibling().unwrap().is_none());
+
+        let mut tree = unit.entries_tree(&abbrevs, None).unwrap();
+        let mut names = Vec::new();
+        let mut children = tree.root().unwrap().children();
+        while let Some(child) = children.next().unwrap() {
+            let name = child.entry().attr_value(DW_AT_name).unwrap().unwrap();
+            names.push(name.string_value(&DebugStr::default()).unwrap().slice());
+        }
+        assert_eq!(names, [&b"002"[..], b"004", b"006"]);
+    }
+
+    #[test]
+    fn test_debug_info_units_truncated() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut unit = UnitHeader {
+            encoding,
+            unit_length: 0,
+            unit_type: UnitType::Compilation,
+            debug_abbrev_offset: DebugAbbrevOffset(0),
+            unit_offset: DebugInfoOffset(0).into(),
+            entries_buf: EndianSlice::new(&[1, 2, 3, 4, 5, 6], LittleEndian),
+        };
+        let section = Section::with_endian(Endian::Little).unit(&mut unit);
+        let unit_buf = section.get_contents().unwrap();
+
+        // Truncated within the entries, within the header, and within the length.
+        for truncated_len in [unit_buf.len() - 1, 6, 2] {
+            let mut buf = unit_buf.clone();
+            buf.extend_from_slice(&unit_buf[..truncated_len]);
+            let debug_info = DebugInfo::new(&buf, LittleEndian);
+            let mut units = debug_info.units();
+            assert_eq!(units.next().unwrap().unwrap().offset(), unit.offset());
+            match units.next() {
+                Err(Error::TruncatedSection(id)) => {
+                    assert_eq!(
+                        crate::read::Section::lookup_offset_id(&debug_info, id),
+                        Some((SectionId::DebugInfo, unit_buf.len()))
+                    );
+                }
+                otherwise => panic!("Unexpected result: {:?}", otherwise),
+            }
+            assert_eq!(units.next(), Ok(None));
+        }
+
+        // A unit length that is too short for the header is not a truncation.
+        let mut buf = unit_buf.clone();
+        buf[0] = 4;
+        let debug_info = DebugInfo::new(&buf, LittleEndian);
+        assert!(matches!(
+            debug_info.units().next(),
+            Err(Error::UnexpectedEof(_))
+        ));
+    }
+
     #[test]
     fn test_debug_types_next_sibling_with_ptr() {
         let encoding = Encoding {
@@ -5958,106 +6657,124 @@ mod tests {
 
     #[test]
     fn test_debug_info_offset() {
-        let padding = &[0; 10];
-        let entries = &[0; 20];
-        let encoding = Encoding {
-            format: Format::Dwarf32,
-            version: 4,
-            address_size: 4,
-        };
-        let mut unit = UnitHeader {
-            encoding,
-            unit_length: 0,
-            unit_type: UnitType::Compilation,
-            debug_abbrev_offset: DebugAbbrevOffset(0),
-            unit_offset: DebugInfoOffset(0).into(),
-            entries_buf: EndianSlice::new(entries, LittleEndian),
-        };
-        Section::with_endian(Endian::Little)
-            .append_bytes(padding)
-            .unit(&mut unit);
-        let offset = padding.len();
-        let header_length = unit.size_of_header();
-        let length = unit.length_including_self();
-        assert_eq!(DebugInfoOffset(0).to_unit_offset(&unit), None);
-        assert_eq!(DebugInfoOffset(offset - 1).to_unit_offset(&unit), None);
-        assert_eq!(DebugInfoOffset(offset).to_unit_offset(&unit), None);
-        assert_eq!(
-            DebugInfoOffset(offset + header_length - 1).to_unit_offset(&unit),
-            None
-        );
-        assert_eq!(
-            DebugInfoOffset(offset + header_length).to_unit_offset(&unit),
-            Some(UnitOffset(header_length))
-        );
-        assert_eq!(
-            DebugInfoOffset(offset + length - 1).to_unit_offset(&unit),
-            Some(UnitOffset(length - 1))
-        );
-        assert_eq!(DebugInfoOffset(offset + length).to_unit_offset(&unit), None);
-        assert_eq!(
-            UnitOffset(header_length).to_debug_info_offset(&unit),
-            Some(DebugInfoOffset(offset + header_length))
-        );
-        assert_eq!(
-            UnitOffset(length - 1).to_debug_info_offset(&unit),
-            Some(DebugInfoOffset(offset + length - 1))
-        );
+        for format in [Format::Dwarf32, Format::Dwarf64] {
+            let padding = &[0; 10];
+            let entries = &[0; 20];
+            let encoding = Encoding {
+                format,
+                version: 4,
+                address_size: 4,
+            };
+            let mut unit = UnitHeader {
+                encoding,
+                unit_length: 0,
+                unit_type: UnitType::Compilation,
+                debug_abbrev_offset: DebugAbbrevOffset(0),
+                unit_offset: DebugInfoOffset(0).into(),
+                entries_buf: EndianSlice::new(entries, LittleEndian),
+            };
+            Section::with_endian(Endian::Little)
+                .append_bytes(padding)
+                .unit(&mut unit);
+            let offset = padding.len();
+            let header_length = unit.size_of_header();
+            let length = unit.length_including_self();
+            assert_eq!(DebugInfoOffset(0).to_unit_offset(&unit), None);
+            assert_eq!(DebugInfoOffset(offset - 1).to_unit_offset(&unit), None);
+            assert_eq!(DebugInfoOffset(offset).to_unit_offset(&unit), None);
+            assert_eq!(
+                DebugInfoOffset(offset + header_length - 1).to_unit_offset(&unit),
+                None
+            );
+            assert_eq!(
+                DebugInfoOffset(offset + header_length).to_unit_offset(&unit),
+                Some(UnitOffset(header_length))
+            );
+            assert_eq!(
+                DebugInfoOffset(offset + length - 1).to_unit_offset(&unit),
+                Some(UnitOffset(length - 1))
+            );
+            assert_eq!(DebugInfoOffset(offset + length).to_unit_offset(&unit), None);
+            assert_eq!(
+                UnitOffset(header_length).to_debug_info_offset(&unit),
+                Some(DebugInfoOffset(offset + header_length))
+            );
+            assert_eq!(
+                UnitOffset(length - 1).to_debug_info_offset(&unit),
+                Some(DebugInfoOffset(offset + length - 1))
+            );
+            assert_eq!(UnitOffset(0).to_debug_info_offset(&unit), None);
+            assert_eq!(
+                UnitOffset(header_length - 1).to_debug_info_offset(&unit),
+                None
+            );
+            assert_eq!(UnitOffset(length).to_debug_info_offset(&unit), None);
+            assert_eq!(UnitOffset(usize::MAX).to_debug_info_offset(&unit), None);
+        }
     }
 
     #[test]
     fn test_debug_types_offset() {
-        let padding = &[0; 10];
-        let entries = &[0; 20];
-        let encoding = Encoding {
-            format: Format::Dwarf32,
-            version: 4,
-            address_size: 4,
-        };
-        let mut unit = UnitHeader {
-            encoding,
-            unit_length: 0,
-            unit_type: UnitType::Type {
-                type_signature: DebugTypeSignature(0),
-                type_offset: UnitOffset(0),
-            },
-            debug_abbrev_offset: DebugAbbrevOffset(0),
-            unit_offset: DebugTypesOffset(0).into(),
-            entries_buf: EndianSlice::new(entries, LittleEndian),
-        };
-        Section::with_endian(Endian::Little)
-            .append_bytes(padding)
-            .unit(&mut unit);
-        let offset = padding.len();
-        let header_length = unit.size_of_header();
-        let length = unit.length_including_self();
-        assert_eq!(DebugTypesOffset(0).to_unit_offset(&unit), None);
-        assert_eq!(DebugTypesOffset(offset - 1).to_unit_offset(&unit), None);
-        assert_eq!(DebugTypesOffset(offset).to_unit_offset(&unit), None);
-        assert_eq!(
-            DebugTypesOffset(offset + header_length - 1).to_unit_offset(&unit),
-            None
-        );
-        assert_eq!(
-            DebugTypesOffset(offset + header_length).to_unit_offset(&unit),
-            Some(UnitOffset(header_length))
-        );
-        assert_eq!(
-            DebugTypesOffset(offset + length - 1).to_unit_offset(&unit),
-            Some(UnitOffset(length - 1))
-        );
-        assert_eq!(
-            DebugTypesOffset(offset + length).to_unit_offset(&unit),
-            None
-        );
-        assert_eq!(
-            UnitOffset(header_length).to_debug_types_offset(&unit),
-            Some(DebugTypesOffset(offset + header_length))
-        );
-        assert_eq!(
-            UnitOffset(length - 1).to_debug_types_offset(&unit),
-            Some(DebugTypesOffset(offset + length - 1))
-        );
+        for format in [Format::Dwarf32, Format::Dwarf64] {
+            let padding = &[0; 10];
+            let entries = &[0; 20];
+            let encoding = Encoding {
+                format,
+                version: 4,
+                address_size: 4,
+            };
+            let mut unit = UnitHeader {
+                encoding,
+                unit_length: 0,
+                unit_type: UnitType::Type {
+                    type_signature: DebugTypeSignature(0),
+                    type_offset: UnitOffset(0),
+                },
+                debug_abbrev_offset: DebugAbbrevOffset(0),
+                unit_offset: DebugTypesOffset(0).into(),
+                entries_buf: EndianSlice::new(entries, LittleEndian),
+            };
+            Section::with_endian(Endian::Little)
+                .append_bytes(padding)
+                .unit(&mut unit);
+            let offset = padding.len();
+            let header_length = unit.size_of_header();
+            let length = unit.length_including_self();
+            assert_eq!(DebugTypesOffset(0).to_unit_offset(&unit), None);
+            assert_eq!(DebugTypesOffset(offset - 1).to_unit_offset(&unit), None);
+            assert_eq!(DebugTypesOffset(offset).to_unit_offset(&unit), None);
+            assert_eq!(
+                DebugTypesOffset(offset + header_length - 1).to_unit_offset(&unit),
+                None
+            );
+            assert_eq!(
+                DebugTypesOffset(offset + header_length).to_unit_offset(&unit),
+                Some(UnitOffset(header_length))
+            );
+            assert_eq!(
+                DebugTypesOffset(offset + length - 1).to_unit_offset(&unit),
+                Some(UnitOffset(length - 1))
+            );
+            assert_eq!(
+                DebugTypesOffset(offset + length).to_unit_offset(&unit),
+                None
+            );
+            assert_eq!(
+                UnitOffset(header_length).to_debug_types_offset(&unit),
+                Some(DebugTypesOffset(offset + header_length))
+            );
+            assert_eq!(
+                UnitOffset(length - 1).to_debug_types_offset(&unit),
+                Some(DebugTypesOffset(offset + length - 1))
+            );
+            assert_eq!(UnitOffset(0).to_debug_types_offset(&unit), None);
+            assert_eq!(
+                UnitOffset(header_length - 1).to_debug_types_offset(&unit),
+                None
+            );
+            assert_eq!(UnitOffset(length).to_debug_types_offset(&unit), None);
+            assert_eq!(UnitOffset(usize::MAX).to_debug_types_offset(&unit), None);
+        }
     }
 
     #[test]
@@ -6128,4 +6845,46 @@ mod tests {
         let debug_abbrev = DebugAbbrev::new(read_debug_abbrev_section_somehow(), LittleEndian);
         let _abbrevs_for_unit = unit.abbreviations(&debug_abbrev).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_attribute_value_serde() {
+        use serde_test::{assert_tokens, Token};
+
+        let value = AttributeValue::String(EndianSlice::new(b"abc", LittleEndian));
+        assert_tokens(
+            &value,
+            &[
+                Token::NewtypeVariant {
+                    name: "AttributeValue",
+                    variant: "String",
+                },
+                Token::Struct {
+                    name: "EndianSlice",
+                    len: 2,
+                },
+                Token::Str("slice"),
+                Token::BorrowedBytes(b"abc"),
+                Token::Str("endian"),
+                Token::UnitStruct {
+                    name: "LittleEndian",
+                },
+                Token::StructEnd,
+            ],
+        );
+
+        let value =
+            AttributeValue::<EndianSlice<'_, LittleEndian>>::Language(constants::DW_LANG_C99);
+        assert_tokens(
+            &value,
+            &[
+                Token::NewtypeVariant {
+                    name: "AttributeValue",
+                    variant: "Language",
+                },
+                Token::NewtypeStruct { name: "DwLang" },
+                Token::U16(constants::DW_LANG_C99.0),
+            ],
+        );
+    }
 }
@@ -0,0 +1,196 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::slice;
+
+use crate::common::DebugInfoOffset;
+use crate::read::{Dwarf, Error, LineProgramCache, Reader, Result, Unit, UnitOffset};
+
+/// The offset of a unit and the result of parsing it.
+type UnitCacheEntry<R> = (DebugInfoOffset<<R as Reader>::Offset>, Result<Arc<Unit<R>>>);
+
+/// A cache of the parsed units in the `.debug_info` section.
+///
+/// All of the units are parsed when the cache is created, and the cache is
+/// not modified after that. This means it can be shared between threads
+/// without locking, provided that `R` is `Send` and `Sync`, such as for
+/// `EndianSlice` or `EndianArcSlice`. The units are stored in an `Arc`
+/// so that they can be returned from a lookup and kept after the lookup.
+///
+/// Use this when many queries will be made for the same units, such as when
+/// symbolicating many addresses. Combine it with `Dwarf::address_map` to find
+/// the unit for an address.
+#[derive(Debug)]
+pub struct UnitCache<R: Reader> {
+    /// The offset of each unit and the result of parsing it, sorted by offset.
+    units: Vec<UnitCacheEntry<R>>,
+}
+
+impl<R: Reader> UnitCache<R> {
+    /// Parse all of the units in the `.debug_info` section of `dwarf`.
+    ///
+    /// Line number program headers that are shared between units are only
+    /// parsed once, and their directory and file name tables are shared
+    /// between the units. Errors during parsing a unit are stored in the cache.
+    /// Errors during iterating over the unit headers are returned.
+    pub fn new(dwarf: &Dwarf<R>) -> Result<Self> {
+        let mut line_programs = LineProgramCache::new(dwarf.debug_line.clone());
+        let mut units = Vec::new();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            let offset = header
+                .offset()
+                .as_debug_info_offset()
+                .ok_or(Error::UnsupportedOffset)?;
+            let unit = dwarf
+                .unit_with_line_program_cache(header, &mut line_programs)
+                .map(Arc::new);
+            units.push((offset, unit));
+        }
+        Ok(UnitCache { units })
+    }
+
+    /// Return the number of units in the cache.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Return true if the cache contains no units.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Return the unit whose header is at the given offset.
+    ///
+    /// Returns `Error::NoEntryAtGivenOffset` if there is no unit at the offset,
+    /// or the error that occurred when parsing the unit.
+    pub fn unit(&self, offset: DebugInfoOffset<R::Offset>) -> Result<Arc<Unit<R>>> {
+        match self.units.binary_search_by_key(&offset, |unit| unit.0) {
+            Ok(index) => self.units[index].1.clone(),
+            Err(_) => Err(Error::NoEntryAtGivenOffset),
+        }
+    }
+
+    /// Return the unit that contains the given offset, and the offset
+    /// converted to be relative to the start of that unit.
+    ///
+    /// This can be used to resolve `AttributeValue::DebugInfoRef`.
+    ///
+    /// Returns `Error::NoEntryAtGivenOffset` if the offset is not within the
+    /// entries of any unit, or the error that occurred when parsing the unit.
+    #[allow(clippy::type_complexity)]
+    pub fn find(
+        &self,
+        offset: DebugInfoOffset<R::Offset>,
+    ) -> Result<(Arc<Unit<R>>, UnitOffset<R::Offset>)> {
+        let index = match self.units.binary_search_by_key(&offset, |unit| unit.0) {
+            Ok(index) => index,
+            Err(0) => return Err(Error::NoEntryAtGivenOffset),
+            Err(index) => index - 1,
+        };
+        let unit = self.units[index].1.clone()?;
+        let unit_offset = offset
+            .to_unit_offset(&unit.header)
+            .ok_or(Error::NoEntryAtGivenOffset)?;
+        Ok((unit, unit_offset))
+    }
+
+    /// Iterate over the results of parsing the units, in the order of their
+    /// offsets.
+    pub fn iter(&self) -> UnitCacheIter<'_, R> {
+        UnitCacheIter {
+            iter: self.units.iter(),
+        }
+    }
+}
+
+/// An iterator over the units in a `UnitCache`.
+///
+/// Returned by `UnitCache::iter`.
+#[derive(Debug, Clone)]
+pub struct UnitCacheIter<'a, R: Reader> {
+    iter: slice::Iter<'a, UnitCacheEntry<R>>,
+}
+
+impl<'a, R: Reader> Iterator for UnitCacheIter<'a, R> {
+    type Item = (DebugInfoOffset<R::Offset>, Result<&'a Arc<Unit<R>>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(offset, unit)| (*offset, unit.as_ref().map_err(|e| *e)))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "write")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format, UnitSectionOffset};
+    use crate::constants;
+    use crate::write::{self, AttributeValue, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+    use std::thread;
+
+    #[test]
+    fn test_unit_cache() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        for name in ["a.c", "b.c"] {
+            let unit_id = dwarf
+                .units
+                .add(write::Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            unit.get_mut(root)
+                .set(constants::DW_AT_name, AttributeValue::String(name.into()));
+            unit.add(root, constants::DW_TAG_variable);
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let cache = UnitCache::new(&read_dwarf).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.is_empty());
+
+        let offsets = cache.iter().map(|(offset, _)| offset).collect::<Vec<_>>();
+        thread::scope(|scope| {
+            for (offset, name) in offsets.iter().copied().zip(["a.c", "b.c"]) {
+                let cache = &cache;
+                scope.spawn(move || {
+                    let unit = cache.unit(offset).unwrap();
+                    assert_eq!(unit.header.offset(), UnitSectionOffset::from(offset));
+                    assert_eq!(unit.name.unwrap().slice(), name.as_bytes());
+                });
+            }
+        });
+
+        // The offset of the variable entry in the second unit.
+        let unit = cache.unit(offsets[1]).unwrap();
+        let mut cursor = unit.entries();
+        cursor.next_dfs().unwrap();
+        cursor.next_dfs().unwrap();
+        let variable = cursor.current().unwrap().offset();
+        let offset = variable.to_debug_info_offset(&unit.header).unwrap();
+        let (found, found_offset) = cache.find(offset).unwrap();
+        assert!(Arc::ptr_eq(&found, &unit));
+        assert_eq!(found_offset, variable);
+
+        assert_eq!(
+            cache.unit(DebugInfoOffset(offsets[1].0 + 1)).err(),
+            Some(Error::NoEntryAtGivenOffset)
+        );
+        assert_eq!(
+            cache
+                .find(DebugInfoOffset(sections.debug_info.slice().len()))
+                .err(),
+            Some(Error::NoEntryAtGivenOffset)
+        );
+    }
+}
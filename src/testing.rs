@@ -0,0 +1,560 @@
+//! Builders for constructing DWARF sections in tests.
+//!
+//! These builders give explicit control over the encoding of the sections,
+//! including the unit version, the format, the forms of attribute values, and
+//! offsets. Unlike the `write` module, they don't check that the output is
+//! valid, so they can be used to construct malformed fixtures, such as units
+//! with bad lengths or references to missing abbreviations.
+//!
+//! The builder methods don't return errors. Instead, the first error that
+//! occurs is returned by `build`, and any later writes are ignored.
+//!
+//! Requires the `testing` feature.
+//!
+//! ```
+//! use gimli::testing::{AbbrevBuilder, UnitBuilder};
+//! use gimli::{constants, DebugAbbrev, DebugInfo, Encoding, Format, LittleEndian};
+//!
+//! let debug_abbrev = AbbrevBuilder::new(LittleEndian)
+//!     .abbrev(1, constants::DW_TAG_compile_unit, false)
+//!     .attr(constants::DW_AT_name, constants::DW_FORM_string)
+//!     .attr(constants::DW_AT_low_pc, constants::DW_FORM_addr)
+//!     .end_abbrev()
+//!     .end_table()
+//!     .build()
+//!     .unwrap();
+//!
+//! let encoding = Encoding {
+//!     format: Format::Dwarf32,
+//!     version: 4,
+//!     address_size: 8,
+//! };
+//! let debug_info = UnitBuilder::new(encoding, LittleEndian)
+//!     .entry(1)
+//!     .string(b"main.c")
+//!     .address(0x1000)
+//!     .build()
+//!     .unwrap();
+//!
+//! let debug_abbrev = DebugAbbrev::new(&debug_abbrev, LittleEndian);
+//! let debug_info = DebugInfo::new(&debug_info, LittleEndian);
+//! let header = debug_info.units().next().unwrap().unwrap();
+//! let abbrevs = header.abbreviations(&debug_abbrev).unwrap();
+//! let mut entries = header.entries(&abbrevs);
+//! let (_, entry) = entries.next_dfs().unwrap().unwrap();
+//! assert_eq!(entry.tag(), constants::DW_TAG_compile_unit);
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::common::{DebugAbbrevOffset, Encoding};
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::read::UnitOffset;
+use crate::write::{EndianVec, Result, Writer};
+
+/// A builder for the contents of a `.debug_abbrev` section.
+///
+/// Abbreviations are written in the order in which they are added, using the
+/// given codes, so the section may contain duplicate or out of order codes.
+#[derive(Debug)]
+pub struct AbbrevBuilder<Endian: Endianity> {
+    w: EndianVec<Endian>,
+    result: Result<()>,
+}
+
+impl<Endian: Endianity> AbbrevBuilder<Endian> {
+    /// Create a new, empty builder.
+    pub fn new(endian: Endian) -> Self {
+        AbbrevBuilder {
+            w: EndianVec::new(endian),
+            result: Ok(()),
+        }
+    }
+
+    fn write<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut EndianVec<Endian>) -> Result<()>,
+    {
+        if self.result.is_ok() {
+            self.result = f(&mut self.w);
+        }
+        self
+    }
+
+    /// Return the offset of the next abbreviation table.
+    ///
+    /// Use this as the `debug_abbrev_offset` of units that use tables after
+    /// the first.
+    pub fn offset(&self) -> DebugAbbrevOffset {
+        DebugAbbrevOffset(self.w.len())
+    }
+
+    /// Begin an abbreviation declaration.
+    ///
+    /// This must be followed by the attribute specifications for the
+    /// abbreviation, and then `end_abbrev`.
+    pub fn abbrev(self, code: u64, tag: constants::DwTag, has_children: bool) -> Self {
+        let children = if has_children {
+            constants::DW_CHILDREN_yes
+        } else {
+            constants::DW_CHILDREN_no
+        };
+        self.write(|w| {
+            w.write_uleb128(code)?;
+            w.write_uleb128(tag.0.into())?;
+            w.write_u8(children.0)
+        })
+    }
+
+    /// Add an attribute specification to the current abbreviation.
+    pub fn attr(self, name: constants::DwAt, form: constants::DwForm) -> Self {
+        self.write(|w| {
+            w.write_uleb128(name.0.into())?;
+            w.write_uleb128(form.0.into())
+        })
+    }
+
+    /// Add an attribute specification using `DW_FORM_implicit_const` to the
+    /// current abbreviation.
+    pub fn attr_implicit_const(self, name: constants::DwAt, value: i64) -> Self {
+        self.attr(name, constants::DW_FORM_implicit_const)
+            .write(|w| w.write_sleb128(value))
+    }
+
+    /// End the current abbreviation.
+    pub fn end_abbrev(self) -> Self {
+        self.write(|w| {
+            w.write_u8(0)?;
+            w.write_u8(0)
+        })
+    }
+
+    /// End the current abbreviation table.
+    pub fn end_table(self) -> Self {
+        self.write(|w| w.write_u8(0))
+    }
+
+    /// Append raw bytes to the section.
+    pub fn bytes(self, bytes: &[u8]) -> Self {
+        self.write(|w| w.write(bytes))
+    }
+
+    /// Return the contents of the section, or the first error that occurred
+    /// while writing it.
+    pub fn build(self) -> Result<Vec<u8>> {
+        self.result?;
+        Ok(self.w.into_vec())
+    }
+}
+
+/// A builder for a unit in a `.debug_info` or `.debug_types` section.
+///
+/// The unit header is written by `build`, using the encoding and header fields
+/// that have been set. The entries are written using the methods for each
+/// kind of value, which must match the forms in the abbreviations that are
+/// being used.
+///
+/// The methods that write words or addresses result in an error from `build`
+/// if the size in the encoding is not supported.
+#[derive(Debug)]
+pub struct UnitBuilder<Endian: Endianity> {
+    encoding: Encoding,
+    unit_type: constants::DwUt,
+    debug_abbrev_offset: DebugAbbrevOffset,
+    unit_length: Option<u64>,
+    dwo_id: u64,
+    type_signature: u64,
+    type_offset: u64,
+    w: EndianVec<Endian>,
+    result: Result<()>,
+}
+
+impl<Endian: Endianity> UnitBuilder<Endian> {
+    /// Create a new builder for a compilation unit with the given encoding.
+    pub fn new(encoding: Encoding, endian: Endian) -> Self {
+        UnitBuilder {
+            encoding,
+            unit_type: constants::DW_UT_compile,
+            debug_abbrev_offset: DebugAbbrevOffset(0),
+            unit_length: None,
+            dwo_id: 0,
+            type_signature: 0,
+            type_offset: 0,
+            w: EndianVec::new(endian),
+            result: Ok(()),
+        }
+    }
+
+    fn write<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut EndianVec<Endian>) -> Result<()>,
+    {
+        if self.result.is_ok() {
+            self.result = f(&mut self.w);
+        }
+        self
+    }
+
+    /// Set the unit type.
+    ///
+    /// For version 5, this is written in the header, and determines which
+    /// other header fields are written. For earlier versions, setting this to
+    /// `DW_UT_type` writes the header of a unit in the `.debug_types` section.
+    pub fn unit_type(mut self, unit_type: constants::DwUt) -> Self {
+        self.unit_type = unit_type;
+        self
+    }
+
+    /// Set the offset of the abbreviations for the unit. Defaults to 0.
+    pub fn abbrev_offset(mut self, offset: DebugAbbrevOffset) -> Self {
+        self.debug_abbrev_offset = offset;
+        self
+    }
+
+    /// Set the value of the `unit_length` field in the header.
+    ///
+    /// By default, the length is the actual length of the unit.
+    pub fn unit_length(mut self, length: u64) -> Self {
+        self.unit_length = Some(length);
+        self
+    }
+
+    /// Set the DWO ID of a skeleton or split compilation unit.
+    pub fn dwo_id(mut self, dwo_id: u64) -> Self {
+        self.dwo_id = dwo_id;
+        self
+    }
+
+    /// Set the type signature and type offset of a type unit.
+    pub fn type_signature(mut self, signature: u64, type_offset: UnitOffset<u64>) -> Self {
+        self.type_signature = signature;
+        self.type_offset = type_offset.0;
+        self
+    }
+
+    fn has_dwo_id(&self) -> bool {
+        self.encoding.version >= 5
+            && matches!(
+                self.unit_type,
+                constants::DW_UT_skeleton | constants::DW_UT_split_compile
+            )
+    }
+
+    fn has_type_signature(&self) -> bool {
+        matches!(
+            self.unit_type,
+            constants::DW_UT_type | constants::DW_UT_split_type
+        )
+    }
+
+    /// Return the size of the unit header.
+    pub fn header_size(&self) -> usize {
+        let mut size = self.encoding.format.initial_length_size() as usize + 2 + 1;
+        size += self.encoding.format.word_size() as usize;
+        if self.encoding.version >= 5 {
+            size += 1;
+        }
+        if self.has_dwo_id() {
+            size += 8;
+        }
+        if self.has_type_signature() {
+            size += 8 + self.encoding.format.word_size() as usize;
+        }
+        size
+    }
+
+    /// Return the offset within the unit of the next value that is written.
+    ///
+    /// Use this to find the offset of an entry before writing it, so that
+    /// other entries can refer to it.
+    pub fn offset(&self) -> UnitOffset {
+        UnitOffset(self.header_size() + self.w.len())
+    }
+
+    /// Write the abbreviation code for an entry.
+    ///
+    /// This must be followed by the values for the attributes of the entry.
+    pub fn entry(self, code: u64) -> Self {
+        self.write(|w| w.write_uleb128(code))
+    }
+
+    /// Write a null entry, which ends a list of children.
+    pub fn null(self) -> Self {
+        self.write(|w| w.write_u8(0))
+    }
+
+    /// Write a value for `DW_FORM_data1`, `DW_FORM_flag`, `DW_FORM_ref1`,
+    /// `DW_FORM_strx1`, or `DW_FORM_addrx1`.
+    pub fn u8(self, val: u8) -> Self {
+        self.write(|w| w.write_u8(val))
+    }
+
+    /// Write a value for `DW_FORM_data2`, `DW_FORM_ref2`, `DW_FORM_strx2`,
+    /// or `DW_FORM_addrx2`.
+    pub fn u16(self, val: u16) -> Self {
+        self.write(|w| w.write_u16(val))
+    }
+
+    /// Write a value for `DW_FORM_data4`, `DW_FORM_ref4`, `DW_FORM_strx4`,
+    /// or `DW_FORM_addrx4`.
+    pub fn u32(self, val: u32) -> Self {
+        self.write(|w| w.write_u32(val))
+    }
+
+    /// Write a value for `DW_FORM_data8`, `DW_FORM_ref8`, or `DW_FORM_ref_sig8`.
+    pub fn u64(self, val: u64) -> Self {
+        self.write(|w| w.write_u64(val))
+    }
+
+    /// Write a value for `DW_FORM_udata`, `DW_FORM_ref_udata`, `DW_FORM_strx`,
+    /// `DW_FORM_addrx`, `DW_FORM_loclistx`, or `DW_FORM_rnglistx`.
+    pub fn uleb(self, val: u64) -> Self {
+        self.write(|w| w.write_uleb128(val))
+    }
+
+    /// Write a value for `DW_FORM_sdata`.
+    pub fn sleb(self, val: i64) -> Self {
+        self.write(|w| w.write_sleb128(val))
+    }
+
+    /// Write an offset using the word size of the format.
+    ///
+    /// Use this for `DW_FORM_sec_offset`, `DW_FORM_strp`, `DW_FORM_line_strp`,
+    /// and `DW_FORM_ref_addr` (for version 3 and later).
+    pub fn offset_value(self, val: u64) -> Self {
+        let size = self.encoding.format.word_size();
+        self.write(|w| w.write_udata(val, size))
+    }
+
+    /// Write an address using the address size of the encoding.
+    ///
+    /// Use this for `DW_FORM_addr`, and for `DW_FORM_ref_addr` in version 2.
+    pub fn address(self, val: u64) -> Self {
+        let size = self.encoding.address_size;
+        self.write(|w| w.write_udata(val, size))
+    }
+
+    /// Write a null terminated string for `DW_FORM_string`.
+    pub fn string(self, val: &[u8]) -> Self {
+        self.write(|w| {
+            w.write(val)?;
+            w.write_u8(0)
+        })
+    }
+
+    /// Write a block with a ULEB128 length, for `DW_FORM_block` or
+    /// `DW_FORM_exprloc`.
+    pub fn block(self, val: &[u8]) -> Self {
+        self.write(|w| {
+            w.write_uleb128(val.len() as u64)?;
+            w.write(val)
+        })
+    }
+
+    /// Write raw bytes.
+    pub fn bytes(self, val: &[u8]) -> Self {
+        self.write(|w| w.write(val))
+    }
+
+    /// Return the contents of the unit, including its header, or the first
+    /// error that occurred while writing it.
+    pub fn build(self) -> Result<Vec<u8>> {
+        self.result?;
+        let format = self.encoding.format;
+        let mut w = EndianVec::new(self.w.endian());
+        let length_offset = w.write_initial_length(format)?;
+        let length_base = w.len();
+        w.write_u16(self.encoding.version)?;
+        if self.encoding.version >= 5 {
+            w.write_u8(self.unit_type.0)?;
+            w.write_u8(self.encoding.address_size)?;
+            w.write_udata(self.debug_abbrev_offset.0 as u64, format.word_size())?;
+        } else {
+            w.write_udata(self.debug_abbrev_offset.0 as u64, format.word_size())?;
+            w.write_u8(self.encoding.address_size)?;
+        }
+        if self.has_dwo_id() {
+            w.write_u64(self.dwo_id)?;
+        }
+        if self.has_type_signature() {
+            w.write_u64(self.type_signature)?;
+            w.write_udata(self.type_offset, format.word_size())?;
+        }
+        w.write(self.w.slice())?;
+
+        let length = match self.unit_length {
+            Some(length) => length,
+            None => (w.len() - length_base) as u64,
+        };
+        w.write_initial_length_at(length_offset, length, format)?;
+        Ok(w.into_vec())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::read::{AttributeValue, DebugAbbrev, DebugInfo, DebugTypes, Error};
+    use crate::{DebugInfoOffset, DebugTypeSignature, DwoId, Format, LittleEndian, UnitType};
+
+    #[test]
+    fn test_builders() {
+        let abbrev_builder = AbbrevBuilder::new(LittleEndian)
+            .abbrev(1, constants::DW_TAG_compile_unit, true)
+            .attr(constants::DW_AT_name, constants::DW_FORM_strp)
+            .attr_implicit_const(constants::DW_AT_language, 0x1c)
+            .end_abbrev()
+            .abbrev(2, constants::DW_TAG_variable, false)
+            .attr(constants::DW_AT_type, constants::DW_FORM_ref2)
+            .attr(constants::DW_AT_location, constants::DW_FORM_exprloc)
+            .end_abbrev()
+            .end_table();
+        let abbrev_offset = abbrev_builder.offset();
+        let debug_abbrev = abbrev_builder
+            .abbrev(1, constants::DW_TAG_type_unit, false)
+            .end_abbrev()
+            .end_table()
+            .build()
+            .unwrap();
+        let debug_abbrev = DebugAbbrev::new(&debug_abbrev, LittleEndian);
+
+        for format in [Format::Dwarf32, Format::Dwarf64] {
+            for version in [2, 3, 4, 5] {
+                let encoding = Encoding {
+                    format,
+                    version,
+                    address_size: 4,
+                };
+                let mut unit = UnitBuilder::new(encoding, LittleEndian);
+                if version >= 5 {
+                    unit = unit.unit_type(constants::DW_UT_skeleton).dwo_id(0x1234);
+                }
+                unit = unit.entry(1).offset_value(0x20);
+                let variable = unit.offset();
+                let unit = unit
+                    .entry(2)
+                    .u16(variable.0 as u16)
+                    .block(&[constants::DW_OP_lit1.0])
+                    .null();
+                let size = unit.header_size();
+                let data = unit.build().unwrap();
+                let debug_info = DebugInfo::new(&data, LittleEndian);
+
+                let header = debug_info.units().next().unwrap().unwrap();
+                assert_eq!(header.encoding(), encoding);
+                assert_eq!(header.header_size(), size);
+                assert_eq!(header.length_including_self(), data.len());
+                if version >= 5 {
+                    assert_eq!(header.type_(), UnitType::Skeleton(DwoId(0x1234)));
+                }
+                let abbrevs = header.abbreviations(&debug_abbrev).unwrap();
+                let mut entries = header.entries(&abbrevs);
+                let (_, entry) = entries.next_dfs().unwrap().unwrap();
+                assert_eq!(
+                    entry.attr_value(constants::DW_AT_name).unwrap(),
+                    Some(AttributeValue::DebugStrRef(crate::DebugStrOffset(0x20)))
+                );
+                assert_eq!(
+                    entry.attr_value(constants::DW_AT_language).unwrap(),
+                    Some(AttributeValue::Language(constants::DW_LANG_Rust))
+                );
+                let (_, entry) = entries.next_dfs().unwrap().unwrap();
+                assert_eq!(entry.offset(), variable);
+                assert_eq!(
+                    entry.attr_value(constants::DW_AT_type).unwrap(),
+                    Some(AttributeValue::UnitRef(variable))
+                );
+                assert!(entries.next_dfs().unwrap().is_none());
+            }
+        }
+
+        // A type unit in `.debug_types`.
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let unit = UnitBuilder::new(encoding, LittleEndian)
+            .unit_type(constants::DW_UT_type)
+            .type_signature(0xabcd, UnitOffset(0x17))
+            .abbrev_offset(abbrev_offset);
+        let type_offset = unit.offset();
+        let data = unit.entry(1).build().unwrap();
+        let debug_types = DebugTypes::new(&data, LittleEndian);
+        let header = debug_types.units().next().unwrap().unwrap();
+        assert_eq!(header.debug_abbrev_offset(), abbrev_offset);
+        assert_eq!(
+            header.type_(),
+            UnitType::Type {
+                type_signature: DebugTypeSignature(0xabcd),
+                type_offset,
+            }
+        );
+        let abbrevs = header.abbreviations(&debug_abbrev).unwrap();
+        let mut entries = header.entries(&abbrevs);
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), constants::DW_TAG_type_unit);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+
+        // A unit length that is past the end of the section.
+        let data = UnitBuilder::new(encoding, LittleEndian)
+            .unit_length(0x100)
+            .build()
+            .unwrap();
+        let debug_info = DebugInfo::new(&data, LittleEndian);
+        assert!(matches!(
+            debug_info.units().next(),
+            Err(Error::TruncatedSection(_))
+        ));
+
+        // An abbreviation offset that is past the end of the section.
+        let debug_abbrev = AbbrevBuilder::new(LittleEndian)
+            .end_table()
+            .build()
+            .unwrap();
+        let debug_abbrev = DebugAbbrev::new(&debug_abbrev, LittleEndian);
+        let data = UnitBuilder::new(encoding, LittleEndian)
+            .abbrev_offset(DebugAbbrevOffset(0x10))
+            .entry(1)
+            .build()
+            .unwrap();
+        let debug_info = DebugInfo::new(&data, LittleEndian);
+        let header = debug_info.header_from_offset(DebugInfoOffset(0)).unwrap();
+        assert!(header.abbreviations(&debug_abbrev).is_err());
+
+        // Errors are returned by `build`.
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let unit = UnitBuilder::new(encoding, LittleEndian)
+            .entry(1)
+            .address(1 << 40)
+            .u8(0);
+        assert_eq!(unit.build(), Err(crate::write::Error::ValueTooLarge));
+        let unit = UnitBuilder::new(
+            Encoding {
+                address_size: 9,
+                ..encoding
+            },
+            LittleEndian,
+        )
+        .entry(1)
+        .address(0);
+        assert_eq!(
+            unit.build(),
+            Err(crate::write::Error::UnsupportedWordSize(9))
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use indexmap::IndexSet;
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
 use crate::common::{DebugAbbrevOffset, SectionId};
@@ -11,30 +13,99 @@ use crate::write::{Result, Section, Writer};
 // - values are `Abbreviation`
 // - insertion returns an abbreviation code for use in writing a DIE
 // - inserting a duplicate returns the code of the existing value
+// - a preferred code can be requested for a new value
 #[derive(Debug, Default)]
 pub(crate) struct AbbreviationTable {
     abbrevs: IndexSet<Abbreviation>,
+    /// The code of each abbreviation in `abbrevs`.
+    codes: Vec<u64>,
+    /// The index in `abbrevs` for each code.
+    indices: BTreeMap<u64, usize>,
 }
 
 impl AbbreviationTable {
     /// Add an abbreviation to the table and return its code.
-    pub fn add(&mut self, abbrev: Abbreviation) -> u64 {
-        let (code, _) = self.abbrevs.insert_full(abbrev);
-        // Code must be non-zero
-        (code + 1) as u64
+    ///
+    /// If the abbreviation is not already in the table, then `code` is used
+    /// for it if given and not already in use. Otherwise, the code following
+    /// the highest code in use is assigned.
+    pub fn add_with_code(&mut self, abbrev: Abbreviation, code: Option<u64>) -> u64 {
+        let (index, inserted) = self.abbrevs.insert_full(abbrev);
+        if !inserted {
+            return self.codes[index];
+        }
+        let code = match code {
+            // Code must be non-zero
+            Some(code) if code != 0 && !self.indices.contains_key(&code) => code,
+            _ => self.indices.keys().next_back().map_or(1, |code| code + 1),
+        };
+        self.codes.push(code);
+        self.indices.insert(code, index);
+        code
     }
 
     /// Write the abbreviation table to the `.debug_abbrev` section.
+    ///
+    /// The abbreviations are written in order of their codes.
     pub fn write<W: Writer>(&self, w: &mut DebugAbbrev<W>) -> Result<()> {
-        for (code, abbrev) in self.abbrevs.iter().enumerate() {
-            w.write_uleb128((code + 1) as u64)?;
-            abbrev.write(w)?;
+        for (&code, &index) in &self.indices {
+            w.write_uleb128(code)?;
+            self.abbrevs[index].write(w)?;
         }
         // Null abbreviation code
         w.write_u8(0)
     }
 }
 
+/// The abbreviation tables that have been written to a `.debug_abbrev` section.
+///
+/// If sharing is enabled, then identical tables are only written once, and
+/// units with identical tables refer to the same offset.
+#[derive(Debug, Default)]
+pub(crate) struct AbbreviationTables {
+    share: bool,
+    offsets: HashMap<Vec<(u64, Abbreviation)>, DebugAbbrevOffset>,
+}
+
+impl AbbreviationTables {
+    /// Create a new set of abbreviation tables.
+    pub fn new(share: bool) -> Self {
+        AbbreviationTables {
+            share,
+            offsets: HashMap::new(),
+        }
+    }
+
+    /// Write an abbreviation table to the `.debug_abbrev` section, and return
+    /// its offset.
+    ///
+    /// If sharing is enabled and an identical table has already been written,
+    /// then the offset of that table is returned instead.
+    pub fn write<W: Writer>(
+        &mut self,
+        w: &mut DebugAbbrev<W>,
+        abbrevs: &AbbreviationTable,
+    ) -> Result<DebugAbbrevOffset> {
+        if !self.share {
+            let offset = w.offset();
+            abbrevs.write(w)?;
+            return Ok(offset);
+        }
+        let key = abbrevs
+            .indices
+            .iter()
+            .map(|(&code, &index)| (code, abbrevs.abbrevs[index].clone()))
+            .collect::<Vec<_>>();
+        if let Some(offset) = self.offsets.get(&key) {
+            return Ok(*offset);
+        }
+        let offset = w.offset();
+        abbrevs.write(w)?;
+        self.offsets.insert(key, offset);
+        Ok(offset)
+    }
+}
+
 /// An abbreviation describes the shape of a `DebuggingInformationEntry`'s type:
 /// its tag type, whether it has children, and its set of attributes.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -77,25 +148,44 @@ impl Abbreviation {
 }
 
 /// The description of an attribute in an abbreviated type.
-// TODO: support implicit const
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct AttributeSpecification {
     name: constants::DwAt,
     form: constants::DwForm,
+    implicit_const_value: Option<i64>,
 }
 
 impl AttributeSpecification {
     /// Construct a new `AttributeSpecification`.
+    ///
+    /// `implicit_const_value` must be present if and only if `form` is
+    /// `DW_FORM_implicit_const`.
     #[inline]
-    pub fn new(name: constants::DwAt, form: constants::DwForm) -> AttributeSpecification {
-        AttributeSpecification { name, form }
+    pub fn new(
+        name: constants::DwAt,
+        form: constants::DwForm,
+        implicit_const_value: Option<i64>,
+    ) -> AttributeSpecification {
+        debug_assert_eq!(
+            form == constants::DW_FORM_implicit_const,
+            implicit_const_value.is_some()
+        );
+        AttributeSpecification {
+            name,
+            form,
+            implicit_const_value,
+        }
     }
 
     /// Write the attribute specification to the `.debug_abbrev` section.
     #[inline]
     pub fn write<W: Writer>(&self, w: &mut DebugAbbrev<W>) -> Result<()> {
         w.write_uleb128(self.name.0.into())?;
-        w.write_uleb128(self.form.0.into())
+        w.write_uleb128(self.form.0.into())?;
+        if let Some(val) = self.implicit_const_value {
+            w.write_sleb128(val)?;
+        }
+        Ok(())
     }
 }
 
@@ -123,22 +213,31 @@ mod tests {
             vec![AttributeSpecification::new(
                 constants::DW_AT_name,
                 constants::DW_FORM_string,
+                None,
             )],
         );
         let abbrev2 = Abbreviation::new(
             constants::DW_TAG_compile_unit,
             true,
             vec![
-                AttributeSpecification::new(constants::DW_AT_producer, constants::DW_FORM_strp),
-                AttributeSpecification::new(constants::DW_AT_language, constants::DW_FORM_data2),
+                AttributeSpecification::new(
+                    constants::DW_AT_producer,
+                    constants::DW_FORM_strp,
+                    None,
+                ),
+                AttributeSpecification::new(
+                    constants::DW_AT_language,
+                    constants::DW_FORM_data2,
+                    None,
+                ),
             ],
         );
-        let code1 = abbrevs.add(abbrev1.clone());
+        let code1 = abbrevs.add_with_code(abbrev1.clone(), None);
         assert_eq!(code1, 1);
-        let code2 = abbrevs.add(abbrev2.clone());
+        let code2 = abbrevs.add_with_code(abbrev2.clone(), None);
         assert_eq!(code2, 2);
-        assert_eq!(abbrevs.add(abbrev1.clone()), code1);
-        assert_eq!(abbrevs.add(abbrev2.clone()), code2);
+        assert_eq!(abbrevs.add_with_code(abbrev1.clone(), None), code1);
+        assert_eq!(abbrevs.add_with_code(abbrev2.clone(), None), code2);
 
         let mut debug_abbrev = DebugAbbrev::from(EndianVec::new(LittleEndian));
         let debug_abbrev_offset = debug_abbrev.offset();
@@ -185,4 +284,54 @@ mod tests {
             read_abbrev2.attributes()[1].form()
         );
     }
+
+    #[test]
+    fn test_abbreviation_table_codes() {
+        let abbrev = |tag| Abbreviation::new(tag, false, Vec::new());
+        let mut abbrevs = AbbreviationTable::default();
+        assert_eq!(
+            abbrevs.add_with_code(abbrev(constants::DW_TAG_base_type), Some(5)),
+            5
+        );
+        assert_eq!(
+            abbrevs.add_with_code(abbrev(constants::DW_TAG_variable), Some(2)),
+            2
+        );
+        // Existing abbreviations keep their code.
+        assert_eq!(
+            abbrevs.add_with_code(abbrev(constants::DW_TAG_base_type), Some(3)),
+            5
+        );
+        // Codes that are in use are not reused.
+        assert_eq!(
+            abbrevs.add_with_code(abbrev(constants::DW_TAG_member), Some(2)),
+            6
+        );
+        assert_eq!(
+            abbrevs.add_with_code(abbrev(constants::DW_TAG_typedef), None),
+            7
+        );
+
+        let mut debug_abbrev = DebugAbbrev::from(EndianVec::new(LittleEndian));
+        abbrevs.write(&mut debug_abbrev).unwrap();
+        let read_debug_abbrev = read::DebugAbbrev::new(debug_abbrev.slice(), LittleEndian);
+        let read_abbrevs = read_debug_abbrev
+            .abbreviations(DebugAbbrevOffset(0))
+            .unwrap();
+        assert_eq!(
+            read_abbrevs.get(2).unwrap().tag(),
+            constants::DW_TAG_variable
+        );
+        assert_eq!(
+            read_abbrevs.get(5).unwrap().tag(),
+            constants::DW_TAG_base_type
+        );
+        assert_eq!(read_abbrevs.get(6).unwrap().tag(), constants::DW_TAG_member);
+        assert_eq!(
+            read_abbrevs.get(7).unwrap().tag(),
+            constants::DW_TAG_typedef
+        );
+        // Abbreviations are written in code order.
+        assert_eq!(debug_abbrev.slice()[0], 2);
+    }
 }
@@ -0,0 +1,38 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::common::{DebugAddrBase, Encoding, SectionId};
+use crate::write::{Address, Result, Section, Writer};
+
+define_section!(
+    DebugAddr,
+    DebugAddrBase,
+    "A writable `.debug_addr` section."
+);
+
+impl<W: Writer> DebugAddr<W> {
+    /// Write an address table containing the given addresses.
+    ///
+    /// Returns the base of the table, which is the value for the
+    /// `DW_AT_addr_base` attribute.
+    pub(crate) fn write_table<I>(
+        &mut self,
+        encoding: Encoding,
+        addresses: I,
+    ) -> Result<DebugAddrBase>
+    where
+        I: IntoIterator<Item = Address>,
+    {
+        let length_offset = self.write_initial_length(encoding.format)?;
+        let length_base = self.len();
+        self.write_u16(5)?;
+        self.write_u8(encoding.address_size)?;
+        self.write_u8(0)?; // segment_selector_size
+        let base = self.offset();
+        for address in addresses {
+            self.write_address(address, encoding.address_size)?;
+        }
+        let length = (self.len() - length_base) as u64;
+        self.write_initial_length_at(length_offset, length, encoding.format)?;
+        Ok(base)
+    }
+}
@@ -0,0 +1,572 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use indexmap::IndexMap;
+
+use crate::common::apple_hash;
+use crate::constants;
+use crate::write::{
+    AttributeValue, DebugInfoOffsets, DebugStrOffsets, Error, Result, StringId, StringTable,
+    UnitEntryId, UnitId, UnitTable, Writer,
+};
+
+/// The magic number at the start of an Apple accelerator table ("HASH").
+const APPLE_HASH_MAGIC: u32 = 0x4841_5348;
+
+/// The hash function used by an Apple accelerator table (Bernstein's DJB hash).
+const APPLE_HASH_FUNCTION_DJB: u16 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AppleAccelEntry {
+    unit: UnitId,
+    entry: UnitEntryId,
+    tag: constants::DwTag,
+    type_flags: u8,
+}
+
+/// A writable Apple accelerator table.
+///
+/// This can be used to write the `.apple_names`, `.apple_types`,
+/// `.apple_namespac`, and `.apple_objc` sections. These map names in the
+/// `.debug_str` section to debugging information entries.
+#[derive(Debug)]
+pub struct AppleAccelTable {
+    atoms: &'static [(constants::DwAtom, constants::DwForm)],
+    names: IndexMap<StringId, Vec<AppleAccelEntry>>,
+}
+
+impl AppleAccelTable {
+    /// Create a table for the `.apple_names`, `.apple_namespac`, or `.apple_objc` sections.
+    ///
+    /// Each entry contains only the DIE offset.
+    pub fn names() -> Self {
+        AppleAccelTable {
+            atoms: &[(constants::DW_ATOM_die_offset, constants::DW_FORM_data4)],
+            names: IndexMap::new(),
+        }
+    }
+
+    /// Create a table for the `.apple_types` section.
+    ///
+    /// Each entry contains the DIE offset, the DIE tag, and the type flags.
+    pub fn types() -> Self {
+        AppleAccelTable {
+            atoms: &[
+                (constants::DW_ATOM_die_offset, constants::DW_FORM_data4),
+                (constants::DW_ATOM_die_tag, constants::DW_FORM_data2),
+                (constants::DW_ATOM_type_flags, constants::DW_FORM_data1),
+            ],
+            names: IndexMap::new(),
+        }
+    }
+
+    /// Add an entry for a DIE with the given name.
+    ///
+    /// `tag` is the tag of the DIE, and is only written for type tables.
+    /// Duplicate entries are ignored.
+    pub fn add(&mut self, name: StringId, unit: UnitId, entry: UnitEntryId, tag: constants::DwTag) {
+        let entry = AppleAccelEntry {
+            unit,
+            entry,
+            tag,
+            type_flags: 0,
+        };
+        let entries = self.names.entry(name).or_default();
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+
+    /// Return the number of names in the table.
+    pub fn count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Write the table.
+    ///
+    /// `debug_str_offsets` must contain the `.debug_str` offsets of `strings`,
+    /// and `debug_info_offsets` must contain the `.debug_info` offsets of the
+    /// units that were used when adding entries.
+    ///
+    /// Returns `Error::OffsetOutOfBounds` if a name is at offset 0 in the
+    /// `.debug_str` section, since that offset is used to terminate the list
+    /// of names for a hash.
+    pub fn write<W: Writer>(
+        &self,
+        w: &mut W,
+        strings: &StringTable,
+        debug_str_offsets: &DebugStrOffsets,
+        debug_info_offsets: &DebugInfoOffsets,
+    ) -> Result<()> {
+        // Group the names by hash, and sort the hashes by bucket.
+        let mut hashes = BTreeMap::new();
+        for (index, name) in self.names.keys().enumerate() {
+            let hash = apple_hash(strings.get(*name));
+            hashes.entry(hash).or_insert_with(Vec::new).push(index);
+        }
+        let hash_count = hashes.len();
+        let bucket_count = if hash_count > 1024 {
+            hash_count / 4
+        } else if hash_count > 16 {
+            hash_count / 2
+        } else {
+            core::cmp::max(hash_count, 1)
+        };
+        let mut hashes = hashes.into_iter().collect::<Vec<_>>();
+        hashes.sort_by_key(|&(hash, _)| (hash as usize % bucket_count, hash));
+
+        let header_data_length = 8 + self.atoms.len() * 4;
+        let hash_data_offset = 20 + header_data_length + bucket_count * 4 + hash_count * 8;
+        let entry_size = self
+            .atoms
+            .iter()
+            .map(|(_, form)| match *form {
+                constants::DW_FORM_data1 => 1,
+                constants::DW_FORM_data2 => 2,
+                _ => 4,
+            })
+            .sum::<usize>();
+        let u32_value = |value: usize| u32::try_from(value).map_err(|_| Error::ValueTooLarge);
+
+        w.write_u32(APPLE_HASH_MAGIC)?;
+        w.write_u16(1)?;
+        w.write_u16(APPLE_HASH_FUNCTION_DJB)?;
+        w.write_u32(u32_value(bucket_count)?)?;
+        w.write_u32(u32_value(hash_count)?)?;
+        w.write_u32(u32_value(header_data_length)?)?;
+        // Header data: die_offset_base, then the atoms.
+        w.write_u32(0)?;
+        w.write_u32(self.atoms.len() as u32)?;
+        for &(atom, form) in self.atoms {
+            w.write_u16(atom.0)?;
+            w.write_u16(form.0)?;
+        }
+
+        // Buckets contain the index of the first hash in the bucket.
+        let mut index = 0;
+        for bucket in 0..bucket_count {
+            if index < hash_count && hashes[index].0 as usize % bucket_count == bucket {
+                w.write_u32(index as u32)?;
+                while index < hash_count && hashes[index].0 as usize % bucket_count == bucket {
+                    index += 1;
+                }
+            } else {
+                w.write_u32(u32::MAX)?;
+            }
+        }
+        for (hash, _) in &hashes {
+            w.write_u32(*hash)?;
+        }
+        let mut offset = hash_data_offset;
+        for (_, names) in &hashes {
+            w.write_u32(u32_value(offset)?)?;
+            for name in names {
+                let entries = &self.names[*name];
+                offset += 8 + entries.len() * entry_size;
+            }
+            offset += 4;
+        }
+
+        for (_, names) in &hashes {
+            for name in names {
+                let (string, entries) = self.names.get_index(*name).unwrap();
+                let string_offset = debug_str_offsets.get(*string).0;
+                if string_offset == 0 {
+                    return Err(Error::OffsetOutOfBounds);
+                }
+                w.write_u32(u32_value(string_offset)?)?;
+                w.write_u32(u32_value(entries.len())?)?;
+                for entry in entries {
+                    for &(atom, _) in self.atoms {
+                        match atom {
+                            constants::DW_ATOM_die_offset => {
+                                let offset = debug_info_offsets.entry(entry.unit, entry.entry);
+                                w.write_u32(u32_value(offset.0)?)?;
+                            }
+                            constants::DW_ATOM_die_tag => w.write_u16(entry.tag.0)?,
+                            constants::DW_ATOM_type_flags => w.write_u8(entry.type_flags)?,
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            }
+            w.write_u32(0)?;
+        }
+        Ok(())
+    }
+}
+
+/// The Apple accelerator tables for a `UnitTable`.
+#[derive(Debug)]
+pub struct AppleAccelTables {
+    /// The table for the `.apple_names` section.
+    pub names: AppleAccelTable,
+    /// The table for the `.apple_types` section.
+    pub types: AppleAccelTable,
+    /// The table for the `.apple_namespac` section.
+    pub namespaces: AppleAccelTable,
+    /// The table for the `.apple_objc` section.
+    pub objc: AppleAccelTable,
+}
+
+impl Default for AppleAccelTables {
+    fn default() -> Self {
+        AppleAccelTables {
+            names: AppleAccelTable::names(),
+            types: AppleAccelTable::types(),
+            namespaces: AppleAccelTable::names(),
+            objc: AppleAccelTable::names(),
+        }
+    }
+}
+
+impl AppleAccelTables {
+    /// Create the accelerator tables for the entries in a `UnitTable`.
+    ///
+    /// This adds:
+    /// - functions with code and variables with a location to `names`,
+    ///   using both their name and linkage name
+    /// - type definitions to `types`
+    /// - namespaces to `namespaces`
+    /// - Objective-C methods to `objc` using their class name, and to `names`
+    ///   using their selector
+    ///
+    /// Names that are not already in `strings` are added to it, so this must
+    /// be called before the string table is written.
+    pub fn from_units(units: &UnitTable, strings: &mut StringTable) -> Self {
+        let mut tables = AppleAccelTables::default();
+        for index in 0..units.count() {
+            let unit_id = units.id(index);
+            let unit = units.get(unit_id);
+            let mut stack = vec![unit.root()];
+            while let Some(entry_id) = stack.pop() {
+                let entry = unit.get(entry_id);
+                tables.add_entry(strings, unit_id, entry);
+                // Don't index local declarations.
+                if entry.tag() != constants::DW_TAG_subprogram {
+                    stack.extend(entry.children().rev());
+                }
+            }
+        }
+        tables
+    }
+
+    fn add_entry(
+        &mut self,
+        strings: &mut StringTable,
+        unit: UnitId,
+        entry: &crate::write::DebuggingInformationEntry,
+    ) {
+        let flag = |name| matches!(entry.get(name), Some(AttributeValue::Flag(true)));
+        let mut string = |name| match entry.get(name) {
+            Some(AttributeValue::StringRef(id)) => Some(*id),
+            Some(AttributeValue::String(bytes)) => Some(strings.add(bytes.clone())),
+            _ => None,
+        };
+        let tag = entry.tag();
+        let id = entry.id();
+        match tag {
+            constants::DW_TAG_subprogram => {
+                let has_code = entry.get(constants::DW_AT_low_pc).is_some()
+                    || entry.get(constants::DW_AT_ranges).is_some();
+                if !has_code || flag(constants::DW_AT_declaration) {
+                    return;
+                }
+                let name = string(constants::DW_AT_name);
+                let linkage_name = string(constants::DW_AT_linkage_name);
+                if let Some(name) = name {
+                    self.names.add(name, unit, id, tag);
+                    let bytes = strings.get(name);
+                    if let Some((class, selector)) = objc_method_name(bytes) {
+                        let (class, selector) = (class.to_vec(), selector.to_vec());
+                        self.objc.add(strings.add(class), unit, id, tag);
+                        self.names.add(strings.add(selector), unit, id, tag);
+                    }
+                }
+                if let Some(linkage_name) = linkage_name {
+                    self.names.add(linkage_name, unit, id, tag);
+                }
+            }
+            constants::DW_TAG_variable => {
+                if entry.get(constants::DW_AT_location).is_none()
+                    || flag(constants::DW_AT_declaration)
+                {
+                    return;
+                }
+                if let Some(name) = string(constants::DW_AT_name) {
+                    self.names.add(name, unit, id, tag);
+                }
+                if let Some(linkage_name) = string(constants::DW_AT_linkage_name) {
+                    self.names.add(linkage_name, unit, id, tag);
+                }
+            }
+            constants::DW_TAG_base_type
+            | constants::DW_TAG_class_type
+            | constants::DW_TAG_enumeration_type
+            | constants::DW_TAG_structure_type
+            | constants::DW_TAG_typedef
+            | constants::DW_TAG_union_type
+            | constants::DW_TAG_unspecified_type => {
+                if flag(constants::DW_AT_declaration) {
+                    return;
+                }
+                if let Some(name) = string(constants::DW_AT_name) {
+                    self.types.add(name, unit, id, tag);
+                }
+            }
+            constants::DW_TAG_namespace => {
+                let name = match string(constants::DW_AT_name) {
+                    Some(name) => name,
+                    None => strings.add("(anonymous namespace)"),
+                };
+                self.namespaces.add(name, unit, id, tag);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Split an Objective-C method name such as `-[Class(Category) selector:]`
+/// into its class name and selector.
+fn objc_method_name(name: &[u8]) -> Option<(&[u8], &[u8])> {
+    if name.len() < 4
+        || (name[0] != b'-' && name[0] != b'+')
+        || name[1] != b'['
+        || name[name.len() - 1] != b']'
+    {
+        return None;
+    }
+    let name = &name[2..name.len() - 1];
+    let space = name.iter().position(|&c| c == b' ')?;
+    let (class, selector) = (&name[..space], &name[space + 1..]);
+    let class = match class.iter().position(|&c| c == b'(') {
+        Some(paren) => &class[..paren],
+        None => class,
+    };
+    Some((class, selector))
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{DebugInfoOffset, Encoding, Format};
+    use crate::read;
+    use crate::write::{Address, EndianVec, LineProgram, LineStringTable, Sections, Unit};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_objc_method_name() {
+        assert_eq!(
+            objc_method_name(b"-[Foo(Bar) baz:qux:]"),
+            Some((&b"Foo"[..], &b"baz:qux:"[..]))
+        );
+        assert_eq!(
+            objc_method_name(b"+[Foo new]"),
+            Some((&b"Foo"[..], &b"new"[..]))
+        );
+        assert_eq!(objc_method_name(b"main"), None);
+    }
+
+    #[test]
+    fn test_apple_accel_tables() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = UnitTable::default();
+        let mut strings = StringTable::default();
+        // Names can't be at offset 0.
+        strings.add("producer");
+        let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id);
+        let root = unit.root();
+
+        let main = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(main);
+        entry.set(
+            constants::DW_AT_name,
+            AttributeValue::StringRef(strings.add("main")),
+        );
+        entry.set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x1000)),
+        );
+        let local = unit.add(main, constants::DW_TAG_variable);
+        let entry = unit.get_mut(local);
+        entry.set(constants::DW_AT_name, AttributeValue::String(b"x".to_vec()));
+        entry.set(
+            constants::DW_AT_location,
+            AttributeValue::Exprloc(crate::write::Expression::new()),
+        );
+
+        let method = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(method);
+        entry.set(
+            constants::DW_AT_name,
+            AttributeValue::String(b"-[Foo bar]".to_vec()),
+        );
+        entry.set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x2000)),
+        );
+
+        let int = unit.add(root, constants::DW_TAG_base_type);
+        unit.get_mut(int).set(
+            constants::DW_AT_name,
+            AttributeValue::StringRef(strings.add("int")),
+        );
+        let declaration = unit.add(root, constants::DW_TAG_structure_type);
+        let entry = unit.get_mut(declaration);
+        entry.set(constants::DW_AT_name, AttributeValue::String(b"S".to_vec()));
+        entry.set(constants::DW_AT_declaration, AttributeValue::Flag(true));
+
+        let namespace = unit.add(root, constants::DW_TAG_namespace);
+        let int2 = unit.add(namespace, constants::DW_TAG_base_type);
+        unit.get_mut(int2).set(
+            constants::DW_AT_name,
+            AttributeValue::StringRef(strings.add("int")),
+        );
+
+        let tables = AppleAccelTables::from_units(&units, &mut strings);
+        assert_eq!(tables.names.count(), 3);
+        assert_eq!(tables.types.count(), 1);
+        assert_eq!(tables.namespaces.count(), 1);
+        assert_eq!(tables.objc.count(), 1);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let line_strings = LineStringTable::default()
+            .write(&mut sections.debug_line_str)
+            .unwrap();
+        let debug_str_offsets = strings.write(&mut sections.debug_str).unwrap();
+        let debug_info_offsets = units
+            .write(&mut sections, &line_strings, &debug_str_offsets)
+            .unwrap();
+        let write_table = |table: &AppleAccelTable| {
+            let mut w = EndianVec::new(LittleEndian);
+            table
+                .write(&mut w, &strings, &debug_str_offsets, &debug_info_offsets)
+                .unwrap();
+            w.into_vec()
+        };
+        let names = write_table(&tables.names);
+        let types = write_table(&tables.types);
+        let objc = write_table(&tables.objc);
+        let namespaces = write_table(&tables.namespaces);
+
+        let debug_str = read::DebugStr::new(sections.debug_str.slice(), LittleEndian);
+        let offset = |entry| DebugInfoOffset(debug_info_offsets.entry(unit_id, entry).0);
+
+        let names = read::AppleNames::new(&names, LittleEndian).table().unwrap();
+        let mut entries = names.lookup(b"main", &debug_str).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries.next().unwrap().unwrap().die_offset,
+            Some(offset(main))
+        );
+        assert!(names.lookup(b"x", &debug_str).unwrap().is_empty());
+        let mut entries = names.lookup(b"bar", &debug_str).unwrap();
+        assert_eq!(
+            entries.next().unwrap().unwrap().die_offset,
+            Some(offset(method))
+        );
+        let entries = names.lookup(b"-[Foo bar]", &debug_str).unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let types = read::AppleTypes::new(&types, LittleEndian).table().unwrap();
+        let mut entries = types.lookup(b"int", &debug_str).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries.next().unwrap(),
+            Some(read::AppleAccelEntry {
+                die_offset: Some(offset(int)),
+                cu_offset: None,
+                tag: Some(constants::DW_TAG_base_type),
+                type_flags: Some(0),
+                qual_name_hash: None,
+            })
+        );
+        assert!(types.lookup(b"S", &debug_str).unwrap().is_empty());
+
+        let objc = read::AppleObjc::new(&objc, LittleEndian).table().unwrap();
+        let mut entries = objc.lookup(b"Foo", &debug_str).unwrap();
+        assert_eq!(
+            entries.next().unwrap().unwrap().die_offset,
+            Some(offset(method))
+        );
+
+        let namespaces = read::AppleNamespaces::new(&namespaces, LittleEndian)
+            .table()
+            .unwrap();
+        let mut entries = namespaces
+            .lookup(b"(anonymous namespace)", &debug_str)
+            .unwrap();
+        assert_eq!(
+            entries.next().unwrap().unwrap().die_offset,
+            Some(offset(namespace))
+        );
+    }
+
+    #[test]
+    fn test_apple_accel_table_buckets() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = UnitTable::default();
+        let mut strings = StringTable::default();
+        // Names can't be at offset 0.
+        strings.add("producer");
+        let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id);
+        let root = unit.root();
+        let mut table = AppleAccelTable::types();
+        let mut names = Vec::new();
+        for i in 0..100 {
+            let name = format!("type{}", i);
+            let id = unit.add(root, constants::DW_TAG_base_type);
+            table.add(
+                strings.add(name.clone()),
+                unit_id,
+                id,
+                constants::DW_TAG_base_type,
+            );
+            names.push((name, id));
+        }
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let line_strings = LineStringTable::default()
+            .write(&mut sections.debug_line_str)
+            .unwrap();
+        let debug_str_offsets = strings.write(&mut sections.debug_str).unwrap();
+        let debug_info_offsets = units
+            .write(&mut sections, &line_strings, &debug_str_offsets)
+            .unwrap();
+        let mut w = EndianVec::new(LittleEndian);
+        table
+            .write(&mut w, &strings, &debug_str_offsets, &debug_info_offsets)
+            .unwrap();
+
+        let debug_str = read::DebugStr::new(sections.debug_str.slice(), LittleEndian);
+        let read_table = read::AppleTypes::new(w.slice(), LittleEndian)
+            .table()
+            .unwrap();
+        assert_eq!(read_table.bucket_count(), 50);
+        assert_eq!(read_table.hash_count(), 100);
+        for (name, id) in names {
+            let mut entries = read_table.lookup(name.as_bytes(), &debug_str).unwrap();
+            assert_eq!(
+                entries.next().unwrap().unwrap().die_offset,
+                Some(DebugInfoOffset(debug_info_offsets.entry(unit_id, id).0))
+            );
+        }
+        let mut count = 0;
+        let mut iter = read_table.names();
+        while iter.next().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 100);
+    }
+}
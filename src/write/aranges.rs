@@ -0,0 +1,278 @@
+use alloc::vec::Vec;
+use std::ops::{Deref, DerefMut};
+
+use crate::common::{DebugArangesOffset, SectionId};
+use crate::constants;
+use crate::write::{
+    Address, AttributeValue, DebugInfoOffsets, Error, Range, Result, Section, Unit, UnitTable,
+    Writer,
+};
+
+define_section!(
+    DebugAranges,
+    DebugArangesOffset,
+    "A writable `.debug_aranges` section."
+);
+
+impl<W: Writer> DebugAranges<W> {
+    /// Write an address range table for each compilation unit in `units`.
+    ///
+    /// The address ranges of a unit are determined from the `DW_AT_low_pc`,
+    /// `DW_AT_high_pc`, and `DW_AT_ranges` attributes of its root entry.
+    /// Units without address ranges and partial units are skipped.
+    ///
+    /// Returns `Error::UnsupportedVerbatimUnit` if any unit was copied
+    /// verbatim, since its entries are not available.
+    ///
+    /// `debug_info_offsets` must be the offsets returned when writing `units`.
+    pub fn write_units(
+        &mut self,
+        units: &UnitTable,
+        debug_info_offsets: &DebugInfoOffsets,
+    ) -> Result<()> {
+        for index in 0..units.count() {
+            let unit_id = units.id(index);
+            let unit = units.get(unit_id);
+            if unit.is_verbatim() {
+                return Err(Error::UnsupportedVerbatimUnit);
+            }
+            if unit.is_partial() {
+                continue;
+            }
+            let ranges = unit_ranges(unit)?;
+            if ranges.is_empty() {
+                continue;
+            }
+            self.write_set(unit, debug_info_offsets.unit(unit_id).0, &ranges)?;
+        }
+        Ok(())
+    }
+
+    /// Write the address range table for a single unit.
+    fn write_set(
+        &mut self,
+        unit: &Unit,
+        debug_info_offset: usize,
+        ranges: &[(Address, u64)],
+    ) -> Result<()> {
+        let format = unit.format();
+        let address_size = unit.address_size();
+        let set_start = self.len();
+        let length_offset = self.write_initial_length(format)?;
+        let length_base = self.len();
+
+        self.write_u16(2)?;
+        self.write_offset(debug_info_offset, SectionId::DebugInfo, format.word_size())?;
+        self.write_u8(address_size)?;
+        // Segment selector size.
+        self.write_u8(0)?;
+        // The tuples must be aligned to twice the address size, relative to
+        // the start of the set.
+        let tuple_size = 2 * address_size as usize;
+        let header_size = self.len() - set_start;
+        let padding = (tuple_size - header_size % tuple_size) % tuple_size;
+        for _ in 0..padding {
+            self.write_u8(0)?;
+        }
+
+        for &(address, length) in ranges {
+            self.write_address(address, address_size)?;
+            self.write_udata(length, address_size)?;
+        }
+        self.write_udata(0, address_size)?;
+        self.write_udata(0, address_size)?;
+
+        let length = (self.len() - length_base) as u64;
+        self.write_initial_length_at(length_offset, length, format)?;
+        Ok(())
+    }
+}
+
+/// Return the address ranges of a unit as a list of start addresses and lengths.
+fn unit_ranges(unit: &Unit) -> Result<Vec<(Address, u64)>> {
+    let root = unit.get(unit.root());
+    let low_pc = match root.get(constants::DW_AT_low_pc) {
+        Some(AttributeValue::Address(address)) => Some(*address),
+        _ => None,
+    };
+    let mut ranges = Vec::new();
+    if let Some(AttributeValue::RangeListRef(id)) = root.get(constants::DW_AT_ranges) {
+        let mut base = low_pc.unwrap_or(Address::Constant(0));
+        for range in &unit.ranges.get(*id).0 {
+            match *range {
+                Range::BaseAddress { address } => base = address,
+                Range::OffsetPair { begin, end } => {
+                    ranges.push((address_add(base, begin), end.wrapping_sub(begin)));
+                }
+                Range::StartEnd { begin, end } => {
+                    ranges.push((begin, address_difference(begin, end)?));
+                }
+                Range::StartLength { begin, length } => ranges.push((begin, length)),
+            }
+        }
+    } else if let Some(low_pc) = low_pc {
+        let length = match root.get(constants::DW_AT_high_pc) {
+            Some(AttributeValue::Address(high_pc)) => address_difference(low_pc, *high_pc)?,
+            Some(AttributeValue::Data1(val)) => u64::from(*val),
+            Some(AttributeValue::Data2(val)) => u64::from(*val),
+            Some(AttributeValue::Data4(val)) => u64::from(*val),
+            Some(AttributeValue::Data8(val)) => *val,
+            Some(AttributeValue::Udata(val)) => *val,
+            _ => return Ok(ranges),
+        };
+        ranges.push((low_pc, length));
+    }
+    // Empty ranges would be mistaken for the terminator.
+    ranges.retain(|&(_, length)| length != 0);
+    Ok(ranges)
+}
+
+fn address_add(address: Address, offset: u64) -> Address {
+    match address {
+        Address::Constant(val) => Address::Constant(val.wrapping_add(offset)),
+        Address::Symbol { symbol, addend } => Address::Symbol {
+            symbol,
+            addend: addend.wrapping_add(offset as i64),
+        },
+    }
+}
+
+fn address_difference(begin: Address, end: Address) -> Result<u64> {
+    match (begin, end) {
+        (Address::Constant(begin), Address::Constant(end)) => Ok(end.wrapping_sub(begin)),
+        (
+            Address::Symbol {
+                symbol: begin_symbol,
+                addend: begin,
+            },
+            Address::Symbol {
+                symbol: end_symbol,
+                addend: end,
+            },
+        ) if begin_symbol == end_symbol => Ok(end.wrapping_sub(begin) as u64),
+        _ => Err(Error::InvalidAddress),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read;
+    use crate::write::{
+        DebugLineStrOffsets, DebugStrOffsets, EndianVec, LineProgram, RangeList, Sections,
+    };
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_write_units() {
+        fn ranges(
+            header: &read::ArangeHeader<read::EndianSlice<'_, LittleEndian>>,
+        ) -> Vec<read::Range> {
+            let mut entries = header.entries();
+            let mut ranges = Vec::new();
+            while let Some(entry) = entries.next().unwrap() {
+                ranges.push(entry.range());
+            }
+            ranges
+        }
+
+        for format in [Format::Dwarf32, Format::Dwarf64] {
+            for address_size in [4, 8] {
+                let encoding = Encoding {
+                    format,
+                    version: 4,
+                    address_size,
+                };
+                let mut units = UnitTable::default();
+
+                let unit_id1 = units.add(Unit::new(encoding, LineProgram::none()));
+                let unit = units.get_mut(unit_id1);
+                let root = unit.get_mut(unit.root());
+                root.set(
+                    constants::DW_AT_low_pc,
+                    AttributeValue::Address(Address::Constant(0x1000)),
+                );
+                root.set(constants::DW_AT_high_pc, AttributeValue::Udata(0x10));
+
+                // A unit without any addresses.
+                units.add(Unit::new(encoding, LineProgram::none()));
+
+                let unit_id3 = units.add(Unit::new(encoding, LineProgram::none()));
+                let unit = units.get_mut(unit_id3);
+                let range_list_id = unit.ranges.add(RangeList(vec![
+                    Range::StartLength {
+                        begin: Address::Constant(0x2000),
+                        length: 0x20,
+                    },
+                    Range::BaseAddress {
+                        address: Address::Constant(0x3000),
+                    },
+                    Range::OffsetPair {
+                        begin: 0x10,
+                        end: 0x18,
+                    },
+                    Range::StartEnd {
+                        begin: Address::Constant(0x4000),
+                        end: Address::Constant(0x4008),
+                    },
+                ]));
+                let root = unit.root();
+                unit.get_mut(root).set(
+                    constants::DW_AT_ranges,
+                    AttributeValue::RangeListRef(range_list_id),
+                );
+
+                let mut sections = Sections::new(EndianVec::new(LittleEndian));
+                let offsets = units
+                    .write(
+                        &mut sections,
+                        &DebugLineStrOffsets::none(),
+                        &DebugStrOffsets::none(),
+                    )
+                    .unwrap();
+                let mut debug_aranges = DebugAranges::from(EndianVec::new(LittleEndian));
+                debug_aranges.write_units(&units, &offsets).unwrap();
+
+                let read_aranges = read::DebugAranges::new(debug_aranges.slice(), LittleEndian);
+                let mut headers = read_aranges.headers();
+                let header = headers.next().unwrap().unwrap();
+                assert_eq!(header.encoding().format, format);
+                assert_eq!(header.encoding().address_size, address_size);
+                assert_eq!(header.debug_info_offset(), offsets.unit(unit_id1));
+                let entries = ranges(&header);
+                assert_eq!(
+                    entries,
+                    [read::Range {
+                        begin: 0x1000,
+                        end: 0x1010,
+                    }]
+                );
+
+                let header = headers.next().unwrap().unwrap();
+                assert_eq!(header.debug_info_offset(), offsets.unit(unit_id3));
+                let entries = ranges(&header);
+                assert_eq!(
+                    entries,
+                    [
+                        read::Range {
+                            begin: 0x2000,
+                            end: 0x2020,
+                        },
+                        read::Range {
+                            begin: 0x3010,
+                            end: 0x3018,
+                        },
+                        read::Range {
+                            begin: 0x4000,
+                            end: 0x4008,
+                        },
+                    ]
+                );
+
+                assert!(headers.next().unwrap().is_none());
+            }
+        }
+    }
+}
@@ -416,6 +416,9 @@ pub enum CallFrameInstruction {
 
     /// AAarch64 extension: negate the `RA_SIGN_STATE` pseudo-register.
     NegateRaState,
+
+    /// SPARC extension: the register window has been saved.
+    WindowSave,
 }
 
 impl CallFrameInstruction {
@@ -532,6 +535,9 @@ impl CallFrameInstruction {
             CallFrameInstruction::NegateRaState => {
                 w.write_u8(constants::DW_CFA_AARCH64_negate_ra_state.0)?;
             }
+            CallFrameInstruction::WindowSave => {
+                w.write_u8(constants::DW_CFA_GNU_window_save.0)?;
+            }
         }
         Ok(())
     }
@@ -563,8 +569,9 @@ fn write_advance_loc<W: Writer>(
 }
 
 fn write_nop<W: Writer>(w: &mut W, len: usize, align: u8) -> Result<()> {
-    debug_assert_eq!(align & (align - 1), 0);
-    let tail_len = (!len + 1) & (align as usize - 1);
+    // The alignment is the address size, which may not be a power of two.
+    let align = usize::from(align);
+    let tail_len = (align - len % align) % align;
     for _ in 0..tail_len {
         w.write_u8(constants::DW_CFA_nop.0)?;
     }
@@ -618,11 +625,30 @@ pub(crate) mod convert {
             Section::Offset: read::UnwindOffset<usize>,
         {
             let bases = read::BaseAddresses::default().set_eh_frame(0);
+            FrameTable::from_with_bases(frame, &bases, convert_address)
+        }
 
+        /// Create a frame table by reading the data in the given section,
+        /// using the given base addresses for relative pointers.
+        ///
+        /// `FrameTable::from` assumes that the section address is 0. Use this
+        /// instead when converting a `.eh_frame` section from a linked file,
+        /// so that `DW_EH_PE_pcrel` and other relative pointers are resolved to
+        /// their actual addresses before they are passed to `convert_address`.
+        pub fn from_with_bases<R, Section>(
+            frame: &Section,
+            bases: &read::BaseAddresses,
+            convert_address: &dyn Fn(u64) -> Option<Address>,
+        ) -> ConvertResult<FrameTable>
+        where
+            R: Reader<Offset = usize>,
+            Section: read::UnwindSection<R>,
+            Section::Offset: read::UnwindOffset<usize>,
+        {
             let mut frame_table = FrameTable::default();
 
             let mut cie_ids = HashMap::new();
-            let mut entries = frame.entries(&bases);
+            let mut entries = frame.entries(bases);
             while let Some(entry) = entries.next()? {
                 let partial = match entry {
                     read::CieOrFde::Cie(_) => continue,
@@ -637,13 +663,13 @@ pub(crate) mod convert {
                     hash_map::Entry::Occupied(o) => *o.get(),
                     hash_map::Entry::Vacant(e) => {
                         let cie =
-                            CommonInformationEntry::from(from_cie, frame, &bases, convert_address)?;
+                            CommonInformationEntry::from(from_cie, frame, bases, convert_address)?;
                         let cie_id = frame_table.add_cie(cie);
                         e.insert(cie_id);
                         cie_id
                     }
                 };
-                let fde = FrameDescriptionEntry::from(&from_fde, frame, &bases, convert_address)?;
+                let fde = FrameDescriptionEntry::from(&from_fde, frame, bases, convert_address)?;
                 frame_table.add_fde(cie_id, fde);
             }
 
@@ -858,6 +884,7 @@ pub(crate) mod convert {
                     CallFrameInstruction::ArgsSize(size as u32)
                 }
                 read::CallFrameInstruction::NegateRaState => CallFrameInstruction::NegateRaState,
+                read::CallFrameInstruction::WindowSave => CallFrameInstruction::WindowSave,
                 read::CallFrameInstruction::Nop => return Ok(None),
             }))
         }
@@ -869,14 +896,14 @@ pub(crate) mod convert {
 mod tests {
     use super::*;
     use crate::arch::X86_64;
-    use crate::read;
+    use crate::read::{self, UnwindSection};
     use crate::write::EndianVec;
     use crate::{LittleEndian, Vendor};
 
     #[test]
     fn test_frame_table() {
         for &version in &[1, 3, 4] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -969,6 +996,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_frame_table_convert_bases() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 1,
+            address_size: 8,
+        };
+        let mut frames = FrameTable::default();
+        let mut cie = CommonInformationEntry::new(encoding, 1, -8, X86_64::RA);
+        cie.fde_address_encoding = constants::DW_EH_PE_pcrel | constants::DW_EH_PE_sdata4;
+        cie.add_instruction(CallFrameInstruction::Cfa(X86_64::RSP, 8));
+        let cie_id = frames.add_cie(cie);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x1000), 0x10);
+        fde.add_instruction(4, CallFrameInstruction::CfaOffset(16));
+        frames.add_fde(cie_id, fde);
+
+        // This assumes the `.eh_frame` address is 0.
+        let mut eh_frame = EhFrame::from(EndianVec::new(LittleEndian));
+        frames.write_eh_frame(&mut eh_frame).unwrap();
+
+        // Convert as if the `.eh_frame` address is 0x8000, and relocate
+        // the code by 0x100.
+        let mut read_eh_frame = read::EhFrame::new(eh_frame.slice(), LittleEndian);
+        read_eh_frame.set_address_size(8);
+        let bases = read::BaseAddresses::default().set_eh_frame(0x8000);
+        let convert_frames = FrameTable::from_with_bases(&read_eh_frame, &bases, &|address| {
+            Some(Address::Constant(address + 0x100))
+        })
+        .unwrap();
+        assert_eq!(convert_frames.cie_count(), 1);
+        assert_eq!(convert_frames.fde_count(), 1);
+
+        let mut debug_frame = DebugFrame::from(EndianVec::new(LittleEndian));
+        convert_frames.write_debug_frame(&mut debug_frame).unwrap();
+        let mut read_debug_frame = read::DebugFrame::new(debug_frame.slice(), LittleEndian);
+        read_debug_frame.set_address_size(8);
+        let bases = read::BaseAddresses::default().set_eh_frame(0);
+        let mut ctx = read::UnwindContext::new();
+        let row = read_debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, 0x9104, read::DebugFrame::cie_from_offset)
+            .unwrap();
+        assert_eq!(row.start_address(), 0x9104);
+        assert_eq!(row.end_address(), 0x9110);
+        assert_eq!(
+            *row.cfa(),
+            read::CfaRule::RegisterAndOffset {
+                register: X86_64::RSP,
+                offset: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn test_call_frame_info() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 1,
+            address_size: 8,
+        };
+
+        // `.eh_frame` covers 0x1000 and 0x2000.
+        let mut frames = FrameTable::default();
+        let cie = CommonInformationEntry::new(encoding, 1, -8, X86_64::RA);
+        let cie_id = frames.add_cie(cie);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x1000), 0x10);
+        fde.add_instruction(0, CallFrameInstruction::Cfa(X86_64::RSP, 8));
+        frames.add_fde(cie_id, fde);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x2000), 0x10);
+        fde.add_instruction(0, CallFrameInstruction::Cfa(X86_64::RSP, 16));
+        frames.add_fde(cie_id, fde);
+        let mut eh_frame = EhFrame::from(EndianVec::new(LittleEndian));
+        frames.write_eh_frame(&mut eh_frame).unwrap();
+
+        // `.debug_frame` covers 0x2000 and 0x3000.
+        let mut frames = FrameTable::default();
+        let cie = CommonInformationEntry::new(encoding, 1, -8, X86_64::RA);
+        let cie_id = frames.add_cie(cie);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x2000), 0x10);
+        fde.add_instruction(0, CallFrameInstruction::Cfa(X86_64::RSP, 32));
+        frames.add_fde(cie_id, fde);
+        let mut fde = FrameDescriptionEntry::new(Address::Constant(0x3000), 0x10);
+        fde.add_instruction(0, CallFrameInstruction::Cfa(X86_64::RSP, 24));
+        frames.add_fde(cie_id, fde);
+        let mut debug_frame = DebugFrame::from(EndianVec::new(LittleEndian));
+        frames.write_debug_frame(&mut debug_frame).unwrap();
+
+        let mut read_eh_frame = read::EhFrame::new(eh_frame.slice(), LittleEndian);
+        read_eh_frame.set_address_size(8);
+        let mut read_debug_frame = read::DebugFrame::new(debug_frame.slice(), LittleEndian);
+        read_debug_frame.set_address_size(8);
+        let cfi = read::CallFrameInfo::new(Some(read_debug_frame), Some(read_eh_frame));
+        let bases = read::BaseAddresses::default();
+        let mut ctx = read::UnwindContext::new();
+
+        let cfa = |row: &read::UnwindTableRow<usize>| match *row.cfa() {
+            read::CfaRule::RegisterAndOffset { offset, .. } => offset,
+            _ => panic!("unexpected CFA rule"),
+        };
+        let row = cfi
+            .unwind_info_for_address(&bases, &mut ctx, 0x1004)
+            .unwrap();
+        assert_eq!(cfa(row), 8);
+        let row = cfi
+            .unwind_info_for_address(&bases, &mut ctx, 0x2004)
+            .unwrap();
+        assert_eq!(cfa(row), 16);
+        let row = cfi
+            .unwind_info_for_address(&bases, &mut ctx, 0x3004)
+            .unwrap();
+        assert_eq!(cfa(row), 24);
+        assert_eq!(
+            cfi.fde_for_address(&bases, 0x4000).unwrap_err(),
+            read::Error::NoUnwindInfoForAddress
+        );
+
+        let table = cfi.fde_table(&bases).unwrap();
+        assert_eq!(
+            table
+                .entries()
+                .iter()
+                .map(|entry| entry.initial_address)
+                .collect::<Vec<_>>(),
+            [0x1000, 0x2000, 0x3000]
+        );
+        let entry = table.find(0x2008).unwrap();
+        assert!(matches!(entry.offset, read::CallFrameOffset::EhFrame(_)));
+        let entry = table.find(0x3008).unwrap();
+        assert!(matches!(entry.offset, read::CallFrameOffset::DebugFrame(_)));
+        let row = cfi
+            .unwind_info_for_fde_offset(&bases, &mut ctx, entry.offset, 0x3008)
+            .unwrap();
+        assert_eq!(cfa(row), 24);
+    }
+
     #[test]
     fn test_frame_instruction() {
         let mut expression = Expression::new();
@@ -1012,7 +1173,7 @@ mod tests {
         let fde_instructions_aarch64 = [(0, CallFrameInstruction::NegateRaState)];
 
         for &version in &[1, 3, 4] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &vendor in &[Vendor::Default, Vendor::AArch64] {
                     for &format in &[Format::Dwarf32, Format::Dwarf64] {
                         let encoding = Encoding {
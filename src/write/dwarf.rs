@@ -1,9 +1,10 @@
 use alloc::vec::Vec;
 
-use crate::common::Encoding;
+use crate::common::{Encoding, SectionId};
 use crate::write::{
-    AbbreviationTable, LineProgram, LineStringTable, Result, Sections, StringTable, Unit,
-    UnitTable, Writer,
+    vec_allocated_bytes, AbbreviationTables, Address, DebugInfoOffsets, DebugLineStrOffsets,
+    DebugStrOffsets, Error, LineProgram, LineStringTable, Result, Sections, StringTable,
+    TombstonePolicy, Unit, UnitTable, Writer,
 };
 
 /// Writable DWARF information for more than one unit.
@@ -15,8 +16,8 @@ pub struct Dwarf {
 
     /// Extra line number programs that are not associated with a unit.
     ///
-    /// These should only be used when generating DWARF5 line-only debug
-    /// information.
+    /// These are used when generating DWARF5 line-only debug information,
+    /// and for `AttributeValue::DebugLineRef` references.
     pub line_programs: Vec<LineProgram>,
 
     /// A table of strings that will be stored in the `.debug_line_str` section.
@@ -24,6 +25,24 @@ pub struct Dwarf {
 
     /// A table of strings that will be stored in the `.debug_str` section.
     pub strings: StringTable,
+
+    /// Whether `Dwarf::write` also writes a `.debug_aranges` section derived
+    /// from the units.
+    ///
+    /// See `DebugAranges::write_units`.
+    pub write_aranges: bool,
+
+    /// Whether `Dwarf::write` also writes a `.debug_pubnames` section derived
+    /// from the units.
+    ///
+    /// See `DebugPubNames::write_units`.
+    pub write_pubnames: bool,
+
+    /// Whether `Dwarf::write` also writes a `.debug_pubtypes` section derived
+    /// from the units.
+    ///
+    /// See `DebugPubTypes::write_units`.
+    pub write_pubtypes: bool,
 }
 
 impl Dwarf {
@@ -37,17 +56,189 @@ impl Dwarf {
     pub fn write<W: Writer>(&mut self, sections: &mut Sections<W>) -> Result<()> {
         let line_strings = self.line_strings.write(&mut sections.debug_line_str)?;
         let strings = self.strings.write(&mut sections.debug_str)?;
-        self.units.write(sections, &line_strings, &strings)?;
+        let debug_info_offsets = self.units.write(sections, &line_strings, &strings)?;
+        self.write_line_programs(sections, &line_strings, &strings)?;
+        self.write_accelerators(sections, &debug_info_offsets)
+    }
+
+    /// Write the DWARF information to the given sections, writing the units
+    /// in parallel.
+    ///
+    /// This produces the same output as `Dwarf::write`.
+    /// See `UnitTable::write_parallel`.
+    #[cfg(feature = "rayon")]
+    pub fn write_parallel<W: Writer>(&mut self, sections: &mut Sections<W>) -> Result<()>
+    where
+        W::Endian: Send + Sync,
+    {
+        let line_strings = self.line_strings.write(&mut sections.debug_line_str)?;
+        let strings = self.strings.write(&mut sections.debug_str)?;
+        let debug_info_offsets = self
+            .units
+            .write_parallel(sections, &line_strings, &strings)?;
+        self.write_line_programs(sections, &line_strings, &strings)?;
+        self.write_accelerators(sections, &debug_info_offsets)
+    }
+
+    /// Write the line programs that are not associated with a unit, and
+    /// resolve references to them.
+    fn write_line_programs<W: Writer>(
+        &self,
+        sections: &mut Sections<W>,
+        line_strings: &DebugLineStrOffsets,
+        strings: &DebugStrOffsets,
+    ) -> Result<()> {
+        let mut line_program_offsets = Vec::with_capacity(self.line_programs.len());
         for line_program in &self.line_programs {
-            line_program.write(
+            line_program_offsets.push(line_program.write(
                 &mut sections.debug_line,
                 line_program.encoding(),
-                &line_strings,
-                &strings,
+                line_strings,
+                strings,
+            )?);
+        }
+        for r in sections.debug_line_refs.drain(..) {
+            let offset = line_program_offsets
+                .get(r.program.index())
+                .ok_or(Error::InvalidReference)?;
+            sections.debug_info.write_offset_at(
+                r.offset,
+                offset.0,
+                SectionId::DebugLine,
+                r.size,
             )?;
         }
         Ok(())
     }
+
+    /// Write the `.debug_aranges`, `.debug_pubnames` and `.debug_pubtypes`
+    /// sections that were requested.
+    fn write_accelerators<W: Writer>(
+        &self,
+        sections: &mut Sections<W>,
+        debug_info_offsets: &DebugInfoOffsets,
+    ) -> Result<()> {
+        if self.write_aranges {
+            sections
+                .debug_aranges
+                .write_units(&self.units, debug_info_offsets)?;
+        }
+        if self.write_pubnames {
+            sections
+                .debug_pubnames
+                .write_units(&self.units, &self.strings, debug_info_offsets)?;
+        }
+        if self.write_pubtypes {
+            sections
+                .debug_pubtypes
+                .write_units(&self.units, &self.strings, debug_info_offsets)?;
+        }
+        Ok(())
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the
+    /// units, line programs, and string tables.
+    ///
+    /// See `UnitTable::allocated_bytes`.
+    pub fn allocated_bytes(&self) -> usize {
+        self.units.allocated_bytes()
+            + vec_allocated_bytes(&self.line_programs)
+            + self
+                .line_programs
+                .iter()
+                .map(LineProgram::allocated_bytes)
+                .sum::<usize>()
+            + self.line_strings.allocated_bytes()
+            + self.strings.allocated_bytes()
+    }
+
+    /// Change the DWARF version of all units and line programs.
+    ///
+    /// See `Unit::set_version`. No units are modified if an error is returned.
+    pub fn set_version(&mut self, version: u16) -> Result<()> {
+        for i in 0..self.units.count() {
+            self.units.get(self.units.id(i)).check_version(version)?;
+        }
+        for i in 0..self.units.count() {
+            let id = self.units.id(i);
+            self.units
+                .get_mut(id)
+                .set_version(version, &self.line_strings, &mut self.strings)?;
+        }
+        for line_program in &mut self.line_programs {
+            line_program.set_version(version, &self.line_strings, &self.strings);
+        }
+        if version < 5 {
+            // All references to `.debug_line_str` have been replaced.
+            self.line_strings = LineStringTable::default();
+        }
+        Ok(())
+    }
+
+    /// Sort the output so that it does not depend on the order in which
+    /// strings and line number program files were added.
+    ///
+    /// This sets the string tables to be sorted when writing, and sorts the
+    /// files in the line number program of each unit. See `StringTable::set_sorted`
+    /// and `Unit::sort_files`.
+    ///
+    /// The abbreviations and their codes are already determined only by the
+    /// order and contents of the entries in each unit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line number program sequence has begun.
+    pub fn sort(&mut self) {
+        self.line_strings.set_sorted(true);
+        self.strings.set_sorted(true);
+        for i in 0..self.units.count() {
+            let id = self.units.id(i);
+            self.units
+                .get_mut(id)
+                .sort_files(&self.line_strings, &self.strings);
+        }
+    }
+
+    /// Delete or tombstone the debugging information for addresses for which
+    /// `live` returns false.
+    ///
+    /// See `UnitTable::discard_addresses`.
+    pub fn discard_addresses<F>(&mut self, mut live: F, policy: TombstonePolicy)
+    where
+        F: FnMut(Address) -> bool,
+    {
+        self.units.discard_addresses(&mut live, policy);
+        for line_program in &mut self.line_programs {
+            line_program.discard_addresses(&mut live, policy);
+        }
+    }
+
+    /// Add a line number program to `line_programs`.
+    ///
+    /// The returned identifier can be used in `AttributeValue::DebugLineRef`.
+    pub fn add_line_program(&mut self, line_program: LineProgram) -> LineProgramId {
+        let id = LineProgramId::new(self.line_programs.len());
+        self.line_programs.push(line_program);
+        id
+    }
+}
+
+/// An identifier for a line number program in `Dwarf::line_programs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineProgramId(usize);
+
+impl LineProgramId {
+    /// Create a `LineProgramId` given an index into `Dwarf::line_programs`.
+    #[inline]
+    pub fn new(index: usize) -> Self {
+        LineProgramId(index)
+    }
+
+    /// The index of the line program in `Dwarf::line_programs`.
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0
+    }
 }
 
 /// Writable DWARF information for a single unit.
@@ -84,22 +275,17 @@ impl DwarfUnit {
         let line_strings = self.line_strings.write(&mut sections.debug_line_str)?;
         let strings = self.strings.write(&mut sections.debug_str)?;
 
-        let abbrev_offset = sections.debug_abbrev.offset();
-        let mut abbrevs = AbbreviationTable::default();
-
-        self.unit.write(
-            sections,
-            abbrev_offset,
-            &mut abbrevs,
-            &line_strings,
-            &strings,
-        )?;
+        let mut abbrev_tables = AbbreviationTables::default();
+        self.unit
+            .write(sections, &mut abbrev_tables, &line_strings, &strings)?;
         // None should exist because we didn't give out any UnitId.
         assert!(sections.debug_info_refs.is_empty());
         assert!(sections.debug_loc_refs.is_empty());
+        // There are no line programs other than the unit's.
+        if !sections.debug_line_refs.is_empty() {
+            return Err(Error::InvalidReference);
+        }
         assert!(sections.debug_loclists_refs.is_empty());
-
-        abbrevs.write(&mut sections.debug_abbrev)?;
         Ok(())
     }
 }
@@ -108,7 +294,7 @@ impl DwarfUnit {
 pub(crate) mod convert {
     use super::*;
     use crate::read::{self, Reader};
-    use crate::write::{Address, ConvertResult};
+    use crate::write::{Address, ConvertLinePrograms, ConvertResult};
 
     impl Dwarf {
         /// Create a `write::Dwarf` by converting a `read::Dwarf`.
@@ -118,21 +304,178 @@ pub(crate) mod convert {
         /// `Address::Constant(address)`. For relocatable addresses, it is the caller's
         /// responsibility to determine the symbol and addend corresponding to the address
         /// and return `Address::Symbol { symbol, addend }`.
+        ///
+        /// The `.debug_macinfo` entries of units with version 4 or later are
+        /// converted to `Unit::macros`, which are written to `.debug_macro`.
         pub fn from<R: Reader<Offset = usize>>(
             dwarf: &read::Dwarf<R>,
             convert_address: &dyn Fn(u64) -> Option<Address>,
         ) -> ConvertResult<Dwarf> {
             let mut line_strings = LineStringTable::default();
             let mut strings = StringTable::default();
-            let units = UnitTable::from(dwarf, &mut line_strings, &mut strings, convert_address)?;
+            let mut line_programs = ConvertLinePrograms::default();
+            let units = UnitTable::convert(
+                dwarf,
+                &mut line_strings,
+                &mut strings,
+                Some(&mut line_programs),
+                &|_| true,
+                convert_address,
+            )?;
             // TODO: convert the line programs that were not referenced by a unit.
-            let line_programs = Vec::new();
+            let line_programs = line_programs.programs;
             Ok(Dwarf {
                 units,
                 line_programs,
                 line_strings,
                 strings,
+                write_aranges: false,
+                write_pubnames: false,
+                write_pubtypes: false,
             })
         }
+
+        /// Create a `write::Dwarf` by converting only the units of a `read::Dwarf`
+        /// that will be modified.
+        ///
+        /// Units for which `modified` returns true are converted in the same way
+        /// as for `Dwarf::from`. Other units are copied without conversion if
+        /// possible: their entries, abbreviations and line number program are
+        /// written as raw bytes, and only the section offsets of their strings,
+        /// line number program, range lists and location lists are updated.
+        /// This avoids the cost of converting units that are not changed.
+        ///
+        /// A unit is converted even if `modified` returns false when it contains
+        /// values that would be invalidated by copying it, such as references to
+        /// other units, or indices into `.debug_addr` or `.debug_str_offsets`.
+        /// Use `Unit::is_verbatim` to determine whether a unit was copied.
+        ///
+        /// Addresses in the entries of a copied unit are not passed to
+        /// `convert_address`. Entries in a copied unit other than its root
+        /// entry cannot be referenced by other units, so if a converted unit
+        /// refers to such an entry then `ConvertError::InvalidDebugInfoRef` is
+        /// returned, and the caller must also mark the referenced unit as
+        /// modified.
+        pub fn from_modified<R: Reader<Offset = usize>>(
+            dwarf: &read::Dwarf<R>,
+            convert_address: &dyn Fn(u64) -> Option<Address>,
+            modified: &dyn Fn(&read::UnitHeader<R>) -> bool,
+        ) -> ConvertResult<Dwarf> {
+            let mut line_strings = LineStringTable::default();
+            let mut strings = StringTable::default();
+            let mut line_programs = ConvertLinePrograms::default();
+            let units = UnitTable::convert(
+                dwarf,
+                &mut line_strings,
+                &mut strings,
+                Some(&mut line_programs),
+                modified,
+                convert_address,
+            )?;
+            let line_programs = line_programs.programs;
+            Ok(Dwarf {
+                units,
+                line_programs,
+                line_strings,
+                strings,
+                write_aranges: false,
+                write_pubnames: false,
+                write_pubtypes: false,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{DebugInfoOffset, Format};
+    use crate::constants;
+    use crate::read;
+    use crate::write::{AttributeValue, EndianVec};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_write_accelerators() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let entry = unit.get_mut(root);
+        entry.set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x1000)),
+        );
+        entry.set(constants::DW_AT_high_pc, AttributeValue::Udata(0x10));
+        let function = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(function);
+        entry.set(
+            constants::DW_AT_name,
+            AttributeValue::StringRef(dwarf.strings.add("function")),
+        );
+        entry.set(constants::DW_AT_external, AttributeValue::FlagPresent);
+        let base_type = unit.add(root, constants::DW_TAG_base_type);
+        unit.get_mut(base_type).set(
+            constants::DW_AT_name,
+            AttributeValue::String(b"int".to_vec()),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        assert!(sections.debug_aranges.slice().is_empty());
+        assert!(sections.debug_pubnames.slice().is_empty());
+        assert!(sections.debug_pubtypes.slice().is_empty());
+
+        dwarf.write_aranges = true;
+        dwarf.write_pubnames = true;
+        dwarf.write_pubtypes = true;
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_aranges = read::DebugAranges::new(sections.debug_aranges.slice(), LittleEndian);
+        let header = read_aranges.headers().next().unwrap().unwrap();
+        assert_eq!(header.debug_info_offset(), DebugInfoOffset(0));
+        let entry = header.entries().next().unwrap().unwrap();
+        assert_eq!(
+            entry.range(),
+            read::Range {
+                begin: 0x1000,
+                end: 0x1010,
+            }
+        );
+
+        let read_pubnames = read::DebugPubNames::new(sections.debug_pubnames.slice(), LittleEndian);
+        let item = read_pubnames.items().next().unwrap().unwrap();
+        assert_eq!(item.name().slice(), b"function");
+
+        let read_pubtypes = read::DebugPubTypes::new(sections.debug_pubtypes.slice(), LittleEndian);
+        let item = read_pubtypes.items().next().unwrap().unwrap();
+        assert_eq!(item.name().slice(), b"int");
+
+        // The entries of units that are copied verbatim are not available.
+        let read_dwarf = sections.read_dwarf();
+        let convert_address = &|address| Some(Address::Constant(address));
+        for (write_aranges, write_pubnames, write_pubtypes) in [
+            (true, false, false),
+            (false, true, false),
+            (false, false, true),
+        ] {
+            let mut dwarf = Dwarf::from_modified(&read_dwarf, convert_address, &|_| false).unwrap();
+            assert!(dwarf.units.get(dwarf.units.id(0)).is_verbatim());
+            dwarf.write_aranges = write_aranges;
+            dwarf.write_pubnames = write_pubnames;
+            dwarf.write_pubtypes = write_pubtypes;
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            assert_eq!(
+                dwarf.write(&mut sections),
+                Err(Error::UnsupportedVerbatimUnit)
+            );
+        }
     }
 }
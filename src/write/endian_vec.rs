@@ -28,6 +28,16 @@ where
         }
     }
 
+    /// Construct an `EndianVec` containing existing section data.
+    ///
+    /// Any data that is written will be appended after the existing data,
+    /// and offsets will be relative to the start of the existing data.
+    /// This can be used to append new units to a section without rewriting
+    /// the units that it already contains.
+    pub fn from_vec(vec: Vec<u8>, endian: Endian) -> EndianVec<Endian> {
+        EndianVec { vec, endian }
+    }
+
     /// Return a reference to the raw slice.
     pub fn slice(&self) -> &[u8] {
         &self.vec
@@ -114,4 +124,50 @@ mod tests {
 
         assert_eq!(w.into_vec(), vec![6, 7, 3, 8, 9]);
     }
+
+    #[test]
+    #[cfg(feature = "read")]
+    fn test_endian_vec_append_units() {
+        use crate::write::{AttributeValue, Dwarf, LineProgram, Sections, Unit};
+        use crate::{constants, Encoding, Format};
+
+        fn dwarf(name: &str) -> Dwarf {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version: 4,
+                address_size: 8,
+            };
+            let mut dwarf = Dwarf::new();
+            let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            let name = dwarf.strings.add(name);
+            unit.get_mut(root)
+                .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+            dwarf
+        }
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf("first.c").write(&mut sections).unwrap();
+
+        // Append a second unit to the existing section data.
+        let mut sections = Sections::from_fn(|id| {
+            let data = sections.get_mut(id).unwrap().take();
+            EndianVec::from_vec(data, LittleEndian)
+        });
+        dwarf("second.c").write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let mut names = Vec::new();
+        let mut units = read_dwarf.units();
+        while let Some(header) = units.next().unwrap() {
+            let unit = read_dwarf.unit(header).unwrap();
+            let mut entries = unit.entries();
+            let (_, root) = entries.next_dfs().unwrap().unwrap();
+            let name = root.attr_value(constants::DW_AT_name).unwrap().unwrap();
+            let name = read_dwarf.attr_string(&unit, name).unwrap();
+            names.push(name.slice().to_vec());
+        }
+        assert_eq!(names, [b"first.c".to_vec(), b"second.c".to_vec()]);
+    }
 }
@@ -0,0 +1,615 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use indexmap::IndexMap;
+
+use crate::common::{
+    gdb_index_hash, DebugInfoOffset, DebugTypeSignature, DebugTypesOffset, GdbIndexSymbolKind,
+};
+use crate::write::{BaseId, Error, Result, Writer};
+
+define_id!(GdbIndexUnitId, "An identifier for a unit in a `GdbIndex`.");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GdbIndexUnit {
+    Compilation {
+        offset: DebugInfoOffset,
+        length: u64,
+    },
+    Type {
+        offset: DebugTypesOffset,
+        type_offset: u64,
+        signature: DebugTypeSignature,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GdbIndexSymbolEntry {
+    unit: GdbIndexUnitId,
+    kind: GdbIndexSymbolKind,
+    is_static: bool,
+}
+
+/// A writable `.gdb_index` section.
+///
+/// This writes version 8 of the index format. The section is always little
+/// endian, so the writer passed to `GdbIndex::write` should be little endian.
+#[derive(Debug, Default)]
+pub struct GdbIndex {
+    base_id: BaseId,
+    units: Vec<GdbIndexUnit>,
+    addresses: Vec<(u64, u64, GdbIndexUnitId)>,
+    symbols: IndexMap<Vec<u8>, Vec<GdbIndexSymbolEntry>>,
+}
+
+impl GdbIndex {
+    /// Create a new empty index.
+    pub fn new() -> Self {
+        GdbIndex::default()
+    }
+
+    /// Add a compilation unit to the CU list.
+    ///
+    /// `length` is the length of the unit including its header.
+    pub fn add_compilation_unit(&mut self, offset: DebugInfoOffset, length: u64) -> GdbIndexUnitId {
+        self.add_unit(GdbIndexUnit::Compilation { offset, length })
+    }
+
+    /// Add a type unit in the `.debug_types` section to the TU list.
+    ///
+    /// `type_offset` is the offset of the type within the type unit.
+    pub fn add_type_unit(
+        &mut self,
+        offset: DebugTypesOffset,
+        type_offset: u64,
+        signature: DebugTypeSignature,
+    ) -> GdbIndexUnitId {
+        self.add_unit(GdbIndexUnit::Type {
+            offset,
+            type_offset,
+            signature,
+        })
+    }
+
+    fn add_unit(&mut self, unit: GdbIndexUnit) -> GdbIndexUnitId {
+        let id = GdbIndexUnitId::new(self.base_id, self.units.len());
+        self.units.push(unit);
+        id
+    }
+
+    /// Add an address range for a compilation unit to the address area.
+    ///
+    /// `end` is the first address past the end of the range.
+    pub fn add_address_range(&mut self, unit: GdbIndexUnitId, begin: u64, end: u64) {
+        debug_assert_eq!(self.base_id, unit.base_id);
+        self.addresses.push((begin, end, unit));
+    }
+
+    /// Add a symbol to the symbol table.
+    ///
+    /// Duplicate entries for the same symbol are ignored.
+    pub fn add_symbol<T>(
+        &mut self,
+        name: T,
+        unit: GdbIndexUnitId,
+        kind: GdbIndexSymbolKind,
+        is_static: bool,
+    ) where
+        T: Into<Vec<u8>>,
+    {
+        debug_assert_eq!(self.base_id, unit.base_id);
+        let entry = GdbIndexSymbolEntry {
+            unit,
+            kind,
+            is_static,
+        };
+        let entries = self.symbols.entry(name.into()).or_default();
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+
+    /// Return the number of symbols in the symbol table.
+    pub fn symbol_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Write the index.
+    pub fn write<W: Writer>(&self, w: &mut W) -> Result<()> {
+        // Compilation units are numbered before type units.
+        let mut indices = vec![0u32; self.units.len()];
+        let mut cu_count = 0u32;
+        for (index, unit) in self.units.iter().enumerate() {
+            if let GdbIndexUnit::Compilation { .. } = unit {
+                indices[index] = cu_count;
+                cu_count += 1;
+            }
+        }
+        let mut tu_count = 0u32;
+        for (index, unit) in self.units.iter().enumerate() {
+            if let GdbIndexUnit::Type { .. } = unit {
+                indices[index] = cu_count + tu_count;
+                tu_count += 1;
+            }
+        }
+        if cu_count + tu_count > 0x00ff_ffff {
+            return Err(Error::ValueTooLarge);
+        }
+
+        let slot_count = (self.symbols.len() * 4 / 3 + 1).next_power_of_two();
+        let cu_list_offset = 6 * 4;
+        let tu_list_offset = cu_list_offset + cu_count as usize * 16;
+        let address_area_offset = tu_list_offset + tu_count as usize * 24;
+        let symbol_table_offset = address_area_offset + self.addresses.len() * 20;
+        let constant_pool_offset = symbol_table_offset + slot_count * 8;
+
+        // Layout the constant pool: all CU vectors, followed by all names.
+        let mut cu_vector_offsets = Vec::with_capacity(self.symbols.len());
+        let mut offset = 0;
+        for entries in self.symbols.values() {
+            cu_vector_offsets.push(offset);
+            offset += 4 + entries.len() * 4;
+        }
+        let mut name_offsets = Vec::with_capacity(self.symbols.len());
+        for name in self.symbols.keys() {
+            name_offsets.push(offset);
+            offset += name.len() + 1;
+        }
+        let u32_offset = |offset: usize| u32::try_from(offset).map_err(|_| Error::ValueTooLarge);
+        u32_offset(constant_pool_offset + offset)?;
+
+        let mut slots = vec![None; slot_count];
+        let mask = slot_count as u32 - 1;
+        for (index, name) in self.symbols.keys().enumerate() {
+            let hash = gdb_index_hash(name);
+            let mut slot = hash & mask;
+            let step = (hash.wrapping_mul(17) & mask) | 1;
+            while slots[slot as usize].is_some() {
+                slot = slot.wrapping_add(step) & mask;
+            }
+            slots[slot as usize] = Some(index);
+        }
+
+        w.write_u32(8)?;
+        w.write_u32(u32_offset(cu_list_offset)?)?;
+        w.write_u32(u32_offset(tu_list_offset)?)?;
+        w.write_u32(u32_offset(address_area_offset)?)?;
+        w.write_u32(u32_offset(symbol_table_offset)?)?;
+        w.write_u32(u32_offset(constant_pool_offset)?)?;
+
+        for unit in &self.units {
+            if let GdbIndexUnit::Compilation { offset, length } = *unit {
+                w.write_u64(offset.0 as u64)?;
+                w.write_u64(length)?;
+            }
+        }
+        for unit in &self.units {
+            if let GdbIndexUnit::Type {
+                offset,
+                type_offset,
+                signature,
+            } = *unit
+            {
+                w.write_u64(offset.0 as u64)?;
+                w.write_u64(type_offset)?;
+                w.write_u64(signature.0)?;
+            }
+        }
+
+        for &(begin, end, unit) in &self.addresses {
+            if let GdbIndexUnit::Type { .. } = self.units[unit.index] {
+                return Err(Error::InvalidReference);
+            }
+            w.write_u64(begin)?;
+            w.write_u64(end)?;
+            w.write_u32(indices[unit.index])?;
+        }
+
+        for slot in slots {
+            match slot {
+                Some(index) => {
+                    w.write_u32(u32_offset(name_offsets[index])?)?;
+                    w.write_u32(u32_offset(cu_vector_offsets[index])?)?;
+                }
+                None => {
+                    w.write_u32(0)?;
+                    w.write_u32(0)?;
+                }
+            }
+        }
+
+        for entries in self.symbols.values() {
+            w.write_u32(entries.len() as u32)?;
+            for entry in entries {
+                let mut value =
+                    indices[entry.unit.index] | (u32::from(entry.kind.to_u8() & 7) << 28);
+                if entry.is_static {
+                    value |= 0x8000_0000;
+                }
+                w.write_u32(value)?;
+            }
+        }
+        for name in self.symbols.keys() {
+            w.write(name)?;
+            w.write_u8(0)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "read")]
+pub(crate) mod convert {
+    use super::*;
+    use crate::common::UnitSectionOffset;
+    use crate::constants;
+    use crate::read::{self, AttributeValue, Reader, ReaderOffset};
+    use crate::write::ConvertResult;
+
+    struct Scope {
+        unit: GdbIndexUnitId,
+        cplus: bool,
+        qualify: bool,
+    }
+
+    impl GdbIndex {
+        /// Create a `GdbIndex` by reading the units in a `read::Dwarf`.
+        ///
+        /// This adds all units, the address ranges of each compilation unit,
+        /// and the names of the functions, variables, and types defined by
+        /// each unit.
+        ///
+        /// Names are qualified with the names of their enclosing namespaces
+        /// and types, except for C units.
+        pub fn from<R: Reader>(dwarf: &read::Dwarf<R>) -> ConvertResult<GdbIndex> {
+            let mut index = GdbIndex::new();
+            let mut units = dwarf.units();
+            while let Some(header) = units.next()? {
+                let offset = match header.offset() {
+                    UnitSectionOffset::DebugInfoOffset(offset) => offset,
+                    UnitSectionOffset::DebugTypesOffset(_) => continue,
+                };
+                let length = header.length_including_self().into_u64();
+                let id = index
+                    .add_compilation_unit(DebugInfoOffset(offset.0.into_u64() as usize), length);
+                let unit = dwarf.unit(header)?;
+                let unit = unit.unit_ref(dwarf);
+                let mut ranges = unit.unit_ranges()?;
+                while let Some(range) = ranges.next()? {
+                    if range.begin < range.end {
+                        index.add_address_range(id, range.begin, range.end);
+                    }
+                }
+                index.add_unit_symbols(unit, id)?;
+            }
+            let mut type_units = dwarf.type_units();
+            while let Some(header) = type_units.next()? {
+                let offset = match header.offset() {
+                    UnitSectionOffset::DebugTypesOffset(offset) => offset,
+                    UnitSectionOffset::DebugInfoOffset(_) => continue,
+                };
+                let (signature, type_offset) = match header.type_() {
+                    read::UnitType::Type {
+                        type_signature,
+                        type_offset,
+                    } => (type_signature, type_offset),
+                    _ => continue,
+                };
+                let id = index.add_type_unit(
+                    DebugTypesOffset(offset.0.into_u64() as usize),
+                    type_offset.0.into_u64(),
+                    signature,
+                );
+                let unit = dwarf.unit(header)?;
+                index.add_unit_symbols(unit.unit_ref(dwarf), id)?;
+            }
+            Ok(index)
+        }
+
+        fn add_unit_symbols<R: Reader>(
+            &mut self,
+            unit: read::UnitRef<'_, R>,
+            id: GdbIndexUnitId,
+        ) -> ConvertResult<()> {
+            let mut tree = unit.entries_tree(None)?;
+            let root = tree.root()?;
+            let language = match root.entry().attr_value(constants::DW_AT_language)? {
+                Some(AttributeValue::Language(language)) => Some(language),
+                _ => None,
+            };
+            let cplus = matches!(
+                language,
+                Some(constants::DW_LANG_C_plus_plus)
+                    | Some(constants::DW_LANG_C_plus_plus_03)
+                    | Some(constants::DW_LANG_C_plus_plus_11)
+                    | Some(constants::DW_LANG_C_plus_plus_14)
+            );
+            let qualify = !matches!(
+                language,
+                Some(constants::DW_LANG_C89)
+                    | Some(constants::DW_LANG_C)
+                    | Some(constants::DW_LANG_C99)
+                    | Some(constants::DW_LANG_C11)
+                    | Some(constants::DW_LANG_C17)
+            );
+            let scope = Scope {
+                unit: id,
+                cplus,
+                qualify,
+            };
+            self.add_children_symbols(&unit, &scope, root, &[])
+        }
+
+        fn add_children_symbols<R: Reader>(
+            &mut self,
+            unit: &read::UnitRef<'_, R>,
+            scope: &Scope,
+            node: read::EntriesTreeNode<'_, '_, '_, R>,
+            prefix: &[u8],
+        ) -> ConvertResult<()> {
+            let mut children = node.children();
+            while let Some(child) = children.next()? {
+                let entry = child.entry();
+                let tag = entry.tag();
+                let name = match entry.attr_value(constants::DW_AT_name)? {
+                    Some(attr) => Some(unit.attr_string(attr)?.to_slice()?.into_owned()),
+                    None => None,
+                };
+                let is_declaration = matches!(
+                    entry.attr_value(constants::DW_AT_declaration)?,
+                    Some(AttributeValue::Flag(true))
+                );
+                let is_external = matches!(
+                    entry.attr_value(constants::DW_AT_external)?,
+                    Some(AttributeValue::Flag(true))
+                );
+
+                let mut qualified_name = Vec::new();
+                if let Some(name) = &name {
+                    if scope.qualify && !prefix.is_empty() {
+                        qualified_name.extend_from_slice(prefix);
+                        qualified_name.extend_from_slice(b"::");
+                    }
+                    qualified_name.extend_from_slice(name);
+                }
+
+                let symbol = match tag {
+                    constants::DW_TAG_subprogram => {
+                        let has_code = entry.attr_value(constants::DW_AT_low_pc)?.is_some()
+                            || entry.attr_value(constants::DW_AT_ranges)?.is_some();
+                        if has_code && !is_declaration {
+                            Some((GdbIndexSymbolKind::Function, !is_external))
+                        } else {
+                            None
+                        }
+                    }
+                    constants::DW_TAG_variable => {
+                        let has_value = entry.attr_value(constants::DW_AT_location)?.is_some()
+                            || entry.attr_value(constants::DW_AT_const_value)?.is_some();
+                        if has_value && !is_declaration {
+                            Some((GdbIndexSymbolKind::Variable, !is_external))
+                        } else {
+                            None
+                        }
+                    }
+                    constants::DW_TAG_enumerator => {
+                        Some((GdbIndexSymbolKind::Variable, !scope.cplus))
+                    }
+                    constants::DW_TAG_base_type
+                    | constants::DW_TAG_typedef
+                    | constants::DW_TAG_subrange_type
+                    | constants::DW_TAG_structure_type
+                    | constants::DW_TAG_class_type
+                    | constants::DW_TAG_union_type
+                    | constants::DW_TAG_enumeration_type => {
+                        if is_declaration {
+                            None
+                        } else {
+                            Some((GdbIndexSymbolKind::Type, !scope.cplus))
+                        }
+                    }
+                    constants::DW_TAG_namespace => Some((GdbIndexSymbolKind::Type, false)),
+                    _ => None,
+                };
+                if let (Some((kind, is_static)), Some(_)) = (symbol, &name) {
+                    self.add_symbol(qualified_name.clone(), scope.unit, kind, is_static);
+                }
+
+                match tag {
+                    constants::DW_TAG_namespace
+                    | constants::DW_TAG_structure_type
+                    | constants::DW_TAG_class_type
+                    | constants::DW_TAG_union_type => {
+                        let prefix = if name.is_some() {
+                            &qualified_name[..]
+                        } else {
+                            prefix
+                        };
+                        self.add_children_symbols(unit, scope, child, prefix)?;
+                    }
+                    constants::DW_TAG_enumeration_type => {
+                        // Enumerators of unscoped enumerations are in the enclosing scope.
+                        let enum_class = matches!(
+                            entry.attr_value(constants::DW_AT_enum_class)?,
+                            Some(AttributeValue::Flag(true))
+                        );
+                        let prefix = if enum_class && name.is_some() {
+                            &qualified_name[..]
+                        } else {
+                            prefix
+                        };
+                        self.add_children_symbols(unit, scope, child, prefix)?;
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::constants;
+    use crate::read;
+    use crate::write::{self, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_gdb_index() {
+        let mut index = GdbIndex::new();
+        let cu1 = index.add_compilation_unit(DebugInfoOffset(0), 0x30);
+        let tu = index.add_type_unit(DebugTypesOffset(0), 0x1d, DebugTypeSignature(0x1234));
+        let cu2 = index.add_compilation_unit(DebugInfoOffset(0x30), 0x40);
+        index.add_address_range(cu1, 0x1000, 0x1100);
+        index.add_address_range(cu2, 0x2000, 0x2200);
+        index.add_symbol("main", cu1, GdbIndexSymbolKind::Function, false);
+        index.add_symbol("foo", cu1, GdbIndexSymbolKind::Function, true);
+        index.add_symbol("foo", cu2, GdbIndexSymbolKind::Function, true);
+        index.add_symbol("foo", cu2, GdbIndexSymbolKind::Function, true);
+        index.add_symbol("int", tu, GdbIndexSymbolKind::Type, true);
+        assert_eq!(index.symbol_count(), 3);
+
+        let mut w = EndianVec::new(LittleEndian);
+        index.write(&mut w).unwrap();
+
+        let read_index = read::GdbIndex::new(w.slice()).index().unwrap();
+        assert_eq!(read_index.version(), 8);
+        assert_eq!(read_index.compilation_unit_count(), 2);
+        assert_eq!(read_index.type_unit_count(), 1);
+        assert_eq!(
+            read_index.compilation_unit(1).unwrap(),
+            read::GdbIndexCompilationUnit {
+                offset: DebugInfoOffset(0x30),
+                length: 0x40,
+            }
+        );
+        assert_eq!(read_index.find_address(0x2100).unwrap(), Some(1));
+
+        let mut foo = read_index.find(b"foo").unwrap().unwrap();
+        assert_eq!(foo.len(), 2);
+        assert_eq!(
+            foo.next().unwrap(),
+            Some(read::GdbIndexCuVectorEntry {
+                unit_index: 0,
+                kind: GdbIndexSymbolKind::Function,
+                is_static: true,
+            })
+        );
+        assert_eq!(foo.next().unwrap().unwrap().unit_index, 1);
+        assert!(read_index.find(b"INT").unwrap().is_none());
+        let mut int = read_index.find(b"int").unwrap().unwrap();
+        assert_eq!(
+            read_index
+                .unit(int.next().unwrap().unwrap().unit_index)
+                .unwrap(),
+            read::GdbIndexUnit::Type(read::GdbIndexTypeUnit {
+                offset: DebugTypesOffset(0),
+                type_offset: read::UnitOffset(0x1d),
+                signature: DebugTypeSignature(0x1234),
+            })
+        );
+        assert!(read_index.find(b"bar").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_gdb_index_invalid_address_range() {
+        let mut index = GdbIndex::new();
+        let tu = index.add_type_unit(DebugTypesOffset(0), 0x1d, DebugTypeSignature(0x1234));
+        index.add_address_range(tu, 0x1000, 0x1100);
+        let mut w = EndianVec::new(LittleEndian);
+        assert_eq!(index.write(&mut w), Err(Error::InvalidReference));
+    }
+
+    #[test]
+    fn test_gdb_index_from_dwarf() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = write::Dwarf::new();
+        let unit_id = dwarf
+            .units
+            .add(write::Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_language,
+            write::AttributeValue::Language(constants::DW_LANG_C_plus_plus),
+        );
+        let namespace = unit.add(root, constants::DW_TAG_namespace);
+        unit.get_mut(namespace).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"ns".to_vec()),
+        );
+        let function = unit.add(namespace, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(function);
+        entry.set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"foo".to_vec()),
+        );
+        entry.set(constants::DW_AT_external, write::AttributeValue::Flag(true));
+        entry.set(
+            constants::DW_AT_low_pc,
+            write::AttributeValue::Address(write::Address::Constant(0x1000)),
+        );
+        entry.set(constants::DW_AT_high_pc, write::AttributeValue::Udata(0x10));
+        let declaration = unit.add(root, constants::DW_TAG_subprogram);
+        let entry = unit.get_mut(declaration);
+        entry.set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"bar".to_vec()),
+        );
+        entry.set(
+            constants::DW_AT_declaration,
+            write::AttributeValue::Flag(true),
+        );
+        let base_type = unit.add(root, constants::DW_TAG_base_type);
+        unit.get_mut(base_type).set(
+            constants::DW_AT_name,
+            write::AttributeValue::String(b"int".to_vec()),
+        );
+        unit.get_mut(root).set(
+            constants::DW_AT_low_pc,
+            write::AttributeValue::Address(write::Address::Constant(0x1000)),
+        );
+        unit.get_mut(root)
+            .set(constants::DW_AT_high_pc, write::AttributeValue::Udata(0x10));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = read::Dwarf {
+            debug_abbrev: read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: read::DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            ..Default::default()
+        };
+        let index = GdbIndex::from(&read_dwarf).unwrap();
+        let mut w = EndianVec::new(LittleEndian);
+        index.write(&mut w).unwrap();
+
+        let read_index = read::GdbIndex::new(w.slice()).index().unwrap();
+        assert_eq!(
+            read_index.compilation_unit(0).unwrap(),
+            read::GdbIndexCompilationUnit {
+                offset: DebugInfoOffset(0),
+                length: sections.debug_info.len() as u64,
+            }
+        );
+        assert_eq!(read_index.find_address(0x1008).unwrap(), Some(0));
+        let mut foo = read_index.find(b"ns::foo").unwrap().unwrap();
+        assert_eq!(
+            foo.next().unwrap(),
+            Some(read::GdbIndexCuVectorEntry {
+                unit_index: 0,
+                kind: GdbIndexSymbolKind::Function,
+                is_static: false,
+            })
+        );
+        assert!(read_index.find(b"ns").unwrap().is_some());
+        assert!(read_index.find(b"int").unwrap().is_some());
+        assert!(read_index.find(b"foo").unwrap().is_none());
+        assert!(read_index.find(b"bar").unwrap().is_none());
+    }
+}
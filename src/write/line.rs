@@ -1,13 +1,15 @@
 use alloc::vec::Vec;
 use indexmap::{IndexMap, IndexSet};
+use std::mem;
 use std::ops::{Deref, DerefMut};
 
 use crate::common::{DebugLineOffset, Encoding, Format, LineEncoding, SectionId};
 use crate::constants;
 use crate::leb128;
 use crate::write::{
-    Address, DebugLineStrOffsets, DebugStrOffsets, Error, LineStringId, LineStringTable, Result,
-    Section, StringId, Writer,
+    index_map_allocated_bytes, index_set_allocated_bytes, vec_allocated_bytes, Address,
+    DebugLineStrOffsets, DebugStrOffsets, Error, LineStringId, LineStringTable, Result, Section,
+    StringId, StringTable, TombstonePolicy, Writer,
 };
 
 /// The number assigned to the first special opcode.
@@ -42,6 +44,12 @@ pub struct LineProgram {
     /// Does not include comp_file, even for version >= 5.
     files: IndexMap<(LineString, DirectoryId), FileInfo>,
 
+    /// The index in `files` of the first file that is defined by a
+    /// `DW_LNE_define_file` instruction instead of in the header.
+    ///
+    /// For version >= 5, all files are written in the header.
+    first_defined_file: Option<usize>,
+
     /// The primary source file of the compilation unit.
     /// This is required for version >= 5, but we never reference it elsewhere
     /// because DWARF defines DW_AT_decl_file=0 to mean not specified.
@@ -111,6 +119,7 @@ impl LineProgram {
             line_encoding,
             directories: IndexSet::new(),
             files: IndexMap::new(),
+            first_defined_file: None,
             comp_file: (comp_file, comp_file_info.unwrap_or_default()),
             prev_row: LineRow::initial_state(line_encoding),
             row: LineRow::initial_state(line_encoding),
@@ -127,6 +136,23 @@ impl LineProgram {
         program
     }
 
+    /// Create a new `LineProgram` with the same encoding, working directory
+    /// and primary source file as this one, but no other directories, files
+    /// or rows.
+    pub(crate) fn empty_copy(&self) -> LineProgram {
+        let mut program = LineProgram::new(
+            self.encoding,
+            self.line_encoding,
+            self.get_directory(self.default_directory()).clone(),
+            self.comp_file.0.clone(),
+            Some(self.comp_file.1),
+        );
+        program.file_has_timestamp = self.file_has_timestamp;
+        program.file_has_size = self.file_has_size;
+        program.file_has_md5 = self.file_has_md5;
+        program
+    }
+
     /// Create a new `LineProgram` with no fields set.
     ///
     /// This can be used when the `LineProgram` will not be used.
@@ -145,6 +171,7 @@ impl LineProgram {
             line_encoding,
             directories: IndexSet::new(),
             files: IndexMap::new(),
+            first_defined_file: None,
             comp_file: (LineString::String(Vec::new()), FileInfo::default()),
             prev_row: LineRow::initial_state(line_encoding),
             row: LineRow::initial_state(line_encoding),
@@ -174,6 +201,44 @@ impl LineProgram {
         self.encoding.version
     }
 
+    /// Change the DWARF version for this line program.
+    ///
+    /// For versions before 5, strings in `line_strings` are moved to `strings`,
+    /// since `DW_FORM_line_strp` requires version 5.
+    ///
+    /// This has no effect if this line program was created with `LineProgram::none()`.
+    pub fn set_version(
+        &mut self,
+        version: u16,
+        line_strings: &LineStringTable,
+        strings: &StringTable,
+    ) {
+        if self.none {
+            return;
+        }
+        self.encoding.version = version;
+        if version >= 5 {
+            return;
+        }
+
+        // Version 4 and earlier can only store strings inline.
+        let convert = |string: LineString| match string {
+            LineString::StringRef(id) => LineString::String(strings.get(id).to_vec()),
+            LineString::LineStringRef(id) => LineString::String(line_strings.get(id).to_vec()),
+            string => string,
+        };
+        // All strings must use the same form, so converting them can't
+        // introduce duplicates, and the directory and file ids don't change.
+        self.directories = self.directories.drain(..).map(convert).collect();
+        self.files = self
+            .files
+            .drain(..)
+            .map(|((name, dir), info)| ((convert(name), dir), info))
+            .collect();
+        let comp_name = mem::replace(&mut self.comp_file.0, LineString::String(Vec::new()));
+        self.comp_file.0 = convert(comp_name);
+    }
+
     /// Return the address size in bytes for this line program.
     #[inline]
     pub fn address_size(&self) -> u8 {
@@ -238,6 +303,9 @@ impl LineProgram {
     /// If `info` is not `None`, then it is always assigned to the
     /// entry, even if the entry already exists.
     ///
+    /// If `LineProgram::define_file` has been called, then new entries
+    /// are also defined by `DW_LNE_define_file` instructions.
+    ///
     /// # Panics
     ///
     /// Panics if 'file' is empty or contains a null byte.
@@ -265,6 +333,48 @@ impl LineProgram {
         FileId::new(index)
     }
 
+    /// Add a file entry that is defined by a `DW_LNE_define_file` instruction
+    /// instead of in the line number program header, and return its id.
+    ///
+    /// If the file already exists, then return the id of the existing entry.
+    ///
+    /// `DW_LNE_define_file` was removed in DWARF version 5, so for version 5
+    /// the file is written in the header instead. For earlier versions, the
+    /// instructions are written at the start of the line number program, and
+    /// any files added after this are also defined by instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if 'file' is empty or contains a null byte.
+    pub fn define_file(
+        &mut self,
+        file: LineString,
+        directory: DirectoryId,
+        info: FileInfo,
+    ) -> FileId {
+        if let Some(index) = self.files.get_index_of(&(file.clone(), directory)) {
+            return FileId::new(index);
+        }
+        if self.first_defined_file.is_none() {
+            self.first_defined_file = Some(self.files.len());
+        }
+        self.add_file(file, directory, Some(info))
+    }
+
+    /// Return the id for the primary source file of the compilation unit.
+    ///
+    /// For version 5, this is file index 0. For earlier versions, the
+    /// primary source file is not implicitly included in the file entries,
+    /// so a file entry is added for it.
+    pub fn primary_file(&mut self) -> FileId {
+        if self.version() >= 5 {
+            FileId::zero()
+        } else {
+            let (file, info) = self.comp_file.clone();
+            self.add_file(file, self.default_directory(), Some(info))
+        }
+    }
+
     /// Get a reference to a file entry.
     ///
     /// # Panics
@@ -309,6 +419,104 @@ impl LineProgram {
         }
     }
 
+    /// Sort the directory and file entries by their path names.
+    ///
+    /// Entries are normally written in the order in which they were added.
+    /// Sorting them ensures that the line number program does not depend on
+    /// that order. The first directory, which is the working directory of the
+    /// compilation unit, is not moved. Files that are defined by
+    /// `DW_LNE_define_file` instructions are not moved either.
+    ///
+    /// The file register in the line number program rows is updated, and the
+    /// returned vector maps the index of each file before sorting to its new id.
+    /// Any other `DirectoryId` or `FileId` values for this program are invalidated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sequence has begun.
+    pub(crate) fn sort_files(
+        &mut self,
+        line_strings: &LineStringTable,
+        strings: &StringTable,
+    ) -> Vec<FileId> {
+        assert!(!self.in_sequence);
+        if self.none {
+            return Vec::new();
+        }
+
+        let directories = mem::take(&mut self.directories)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut order = (0..directories.len()).collect::<Vec<_>>();
+        order[1..].sort_by(|&a, &b| {
+            let a = directories[a].bytes(line_strings, strings);
+            let b = directories[b].bytes(line_strings, strings);
+            a.cmp(b)
+        });
+        let mut directory_ids = vec![DirectoryId(0); directories.len()];
+        for (new, &old) in order.iter().enumerate() {
+            directory_ids[old] = DirectoryId(new);
+        }
+        self.directories = order.iter().map(|&old| directories[old].clone()).collect();
+
+        let files = mem::take(&mut self.files)
+            .into_iter()
+            .map(|((name, dir), info)| ((name, directory_ids[dir.0]), info))
+            .collect::<Vec<_>>();
+        let mut order = (0..files.len()).collect::<Vec<_>>();
+        let header_files = self.first_defined_file.unwrap_or(files.len());
+        order[..header_files].sort_by(|&a, &b| {
+            let ((a_name, a_dir), _) = &files[a];
+            let ((b_name, b_dir), _) = &files[b];
+            let a_dir = self.get_directory(*a_dir).bytes(line_strings, strings);
+            let b_dir = self.get_directory(*b_dir).bytes(line_strings, strings);
+            let a_name = a_name.bytes(line_strings, strings);
+            let b_name = b_name.bytes(line_strings, strings);
+            (a_dir, a_name).cmp(&(b_dir, b_name))
+        });
+        let mut file_ids = vec![FileId::zero(); files.len()];
+        for (new, &old) in order.iter().enumerate() {
+            file_ids[old] = FileId::new(new);
+        }
+        self.files = order.iter().map(|&old| files[old].clone()).collect();
+
+        let map_file = |file: FileId| match file.index() {
+            Some(index) => file_ids[index],
+            None => file,
+        };
+        // Each sequence begins with the file register set to the first file,
+        // so sequences that don't set the file register before their first row
+        // need to set it if the first file has moved.
+        let initial_file = FileId::initial_state();
+        let first_file = map_file(initial_file);
+        let mut instructions = Vec::with_capacity(self.instructions.len());
+        let mut sequence_start = true;
+        for instruction in mem::take(&mut self.instructions) {
+            if sequence_start && first_file != initial_file {
+                match instruction {
+                    LineInstruction::SetFile(_) => sequence_start = false,
+                    LineInstruction::Special(_)
+                    | LineInstruction::Copy
+                    | LineInstruction::EndSequence => {
+                        instructions.push(LineInstruction::SetFile(first_file));
+                        sequence_start = false;
+                    }
+                    _ => {}
+                }
+            }
+            instructions.push(match instruction {
+                LineInstruction::SetFile(file) => LineInstruction::SetFile(map_file(file)),
+                LineInstruction::EndSequence => {
+                    sequence_start = true;
+                    instruction
+                }
+                _ => instruction,
+            });
+        }
+        self.instructions = instructions;
+        file_ids
+    }
+
     /// Begin a new sequence and set its base address.
     ///
     /// # Panics
@@ -478,6 +686,41 @@ impl LineProgram {
             - self.prev_row.op_index
     }
 
+    /// Delete or tombstone the sequences that begin at an address for which
+    /// `live` returns false.
+    ///
+    /// Sequences that don't begin with an address are always kept.
+    pub(crate) fn discard_addresses<F>(&mut self, live: &mut F, policy: TombstonePolicy)
+    where
+        F: FnMut(Address) -> bool,
+    {
+        let mut instructions = Vec::with_capacity(self.instructions.len());
+        let mut sequence = Vec::new();
+        for instruction in self.instructions.drain(..) {
+            let end = instruction == LineInstruction::EndSequence;
+            sequence.push(instruction);
+            if !end {
+                continue;
+            }
+            match sequence[0] {
+                LineInstruction::SetAddress(address) if !live(address) => match policy {
+                    TombstonePolicy::Delete => {
+                        sequence.clear();
+                        continue;
+                    }
+                    TombstonePolicy::Address(tombstone) => {
+                        sequence[0] = LineInstruction::SetAddress(Address::Constant(tombstone));
+                    }
+                },
+                _ => {}
+            }
+            instructions.append(&mut sequence);
+        }
+        // Keep any incomplete sequence.
+        instructions.append(&mut sequence);
+        self.instructions = instructions;
+    }
+
     /// Returns true if the line number program has no instructions.
     ///
     /// Does not check the file or directory entries.
@@ -486,6 +729,27 @@ impl LineProgram {
         self.instructions.is_empty()
     }
 
+    /// Return an estimate of the number of bytes of heap memory used by the
+    /// line number program.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        let directories = self
+            .directories
+            .iter()
+            .map(LineString::allocated_bytes)
+            .sum::<usize>();
+        let files = self
+            .files
+            .keys()
+            .map(|(file, _)| file.allocated_bytes())
+            .sum::<usize>();
+        index_set_allocated_bytes(&self.directories)
+            + directories
+            + index_map_allocated_bytes(&self.files)
+            + files
+            + self.comp_file.0.allocated_bytes()
+            + vec_allocated_bytes(&self.instructions)
+    }
+
     /// Write the line number program to the given section.
     ///
     /// # Panics
@@ -556,7 +820,7 @@ impl LineProgram {
             }
             w.write_u8(0)?;
 
-            for ((file, dir), info) in self.files.iter() {
+            for ((file, dir), info) in self.files.iter().take(self.header_file_count()) {
                 file.write(
                     w,
                     constants::DW_FORM_string,
@@ -647,6 +911,30 @@ impl LineProgram {
             self.format().word_size(),
         )?;
 
+        if self.version() <= 4 {
+            for ((file, dir), info) in self.files.iter().skip(self.header_file_count()) {
+                let val = match file {
+                    LineString::String(val) => val,
+                    _ => return Err(Error::LineStringFormMismatch),
+                };
+                let dir = dir.0 as u64;
+                let len = 1
+                    + val.len()
+                    + 1
+                    + leb128::write::uleb128_size(dir)
+                    + leb128::write::uleb128_size(info.timestamp)
+                    + leb128::write::uleb128_size(info.size);
+                w.write_u8(0)?;
+                w.write_uleb128(len as u64)?;
+                w.write_u8(constants::DW_LNE_define_file.0)?;
+                w.write(val)?;
+                w.write_u8(0)?;
+                w.write_uleb128(dir)?;
+                w.write_uleb128(info.timestamp)?;
+                w.write_uleb128(info.size)?;
+            }
+        }
+
         for instruction in &self.instructions {
             instruction.write(w, self.address_size())?;
         }
@@ -656,6 +944,14 @@ impl LineProgram {
 
         Ok(offset)
     }
+
+    /// Return the number of file entries that are written in the header.
+    fn header_file_count(&self) -> usize {
+        match self.first_defined_file {
+            Some(index) if self.version() <= 4 => index,
+            _ => self.files.len(),
+        }
+    }
 }
 
 /// A row in the line number table that corresponds to a machine instruction.
@@ -746,7 +1042,7 @@ enum LineInstruction {
     EndSequence,
     // TODO: this doubles the size of this enum.
     SetAddress(Address),
-    // DW_LNE_define_file is not supported.
+    // DW_LNE_define_file is written from `LineProgram::files`.
     SetDiscriminator(u64),
 }
 
@@ -822,6 +1118,13 @@ pub enum LineString {
 }
 
 impl LineString {
+    fn allocated_bytes(&self) -> usize {
+        match self {
+            LineString::String(val) => val.capacity(),
+            _ => 0,
+        }
+    }
+
     /// Create a `LineString` using the normal form for the given encoding.
     pub fn new<T>(val: T, encoding: Encoding, line_strings: &mut LineStringTable) -> Self
     where
@@ -835,6 +1138,20 @@ impl LineString {
         }
     }
 
+    /// Return the bytes of the string, looking them up in the string tables
+    /// if needed.
+    fn bytes<'a>(
+        &'a self,
+        line_strings: &'a LineStringTable,
+        strings: &'a StringTable,
+    ) -> &'a [u8] {
+        match *self {
+            LineString::String(ref val) => val,
+            LineString::StringRef(id) => strings.get(id),
+            LineString::LineStringRef(id) => line_strings.get(id),
+        }
+    }
+
     fn form(&self) -> constants::DwForm {
         match *self {
             LineString::String(..) => constants::DW_FORM_string,
@@ -907,7 +1224,7 @@ mod id {
         }
 
         /// The index of the file in `LineProgram::files`.
-        pub(super) fn index(self) -> Option<usize> {
+        pub(crate) fn index(self) -> Option<usize> {
             if self.0 == 0 {
                 None
             } else {
@@ -926,9 +1243,6 @@ mod id {
         }
 
         /// The id for file index 0 in DWARF version 5.
-        /// Only used when converting.
-        // Used for tests only.
-        #[allow(unused)]
         pub(super) fn zero() -> Self {
             FileId(0)
         }
@@ -1076,8 +1390,21 @@ mod convert {
                         }
                         from_row.execute(read::LineInstruction::SetAddress(0), &mut from_program);
                     }
-                    read::LineInstruction::DefineFile(_) => {
-                        return Err(ConvertError::UnsupportedLineInstruction);
+                    read::LineInstruction::DefineFile(ref from_file) => {
+                        let from_name =
+                            LineString::from(from_file.path_name(), dwarf, line_strings, strings)?;
+                        let from_dir = from_file.directory_index();
+                        if from_dir >= dirs.len() as u64 {
+                            return Err(ConvertError::InvalidDirectoryIndex);
+                        }
+                        let from_dir = dirs[from_dir as usize];
+                        let from_info = FileInfo {
+                            timestamp: from_file.timestamp(),
+                            size: from_file.size(),
+                            md5: *from_file.md5(),
+                        };
+                        files.push(program.define_file(from_name, from_dir, from_info));
+                        from_row.execute(instruction, &mut from_program);
                     }
                     _ => {
                         if from_row.execute(instruction, &mut from_program) {
@@ -1167,7 +1494,7 @@ mod tests {
 
         let mut programs = Vec::new();
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -1284,6 +1611,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_define_file() {
+        let dir1 = LineString::String(b"dir1".to_vec());
+        let file1 = LineString::String(b"file1".to_vec());
+        let file2 = LineString::String(b"file2".to_vec());
+        let file3 = LineString::String(b"file3".to_vec());
+        let file4 = LineString::String(b"file4".to_vec());
+
+        for &version in &[2, 3, 4, 5] {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version,
+                address_size: 8,
+            };
+            let mut program = LineProgram::new(
+                encoding,
+                LineEncoding::default(),
+                dir1.clone(),
+                file1.clone(),
+                None,
+            );
+            let dir_id = program.default_directory();
+            let file_info = FileInfo {
+                timestamp: 1,
+                size: 2,
+                md5: [0; 16],
+            };
+            let primary_id = program.primary_file();
+            let file2_id = program.add_file(file2.clone(), dir_id, None);
+            let file3_id = program.define_file(file3.clone(), dir_id, file_info);
+            assert_eq!(
+                file2_id,
+                program.define_file(file2.clone(), dir_id, file_info)
+            );
+            assert_eq!(
+                file3_id,
+                program.define_file(file3.clone(), dir_id, file_info)
+            );
+            assert_eq!(FileInfo::default(), *program.get_file_info(file2_id));
+            assert_eq!(file_info, *program.get_file_info(file3_id));
+            let file4_id = program.add_file(file4.clone(), dir_id, None);
+
+            program.begin_sequence(Some(Address::Constant(0x1000)));
+            for (address_offset, &file) in [primary_id, file2_id, file3_id, file4_id]
+                .iter()
+                .enumerate()
+            {
+                program.row().address_offset = address_offset as u64;
+                program.row().file = file;
+                program.generate_row();
+            }
+            program.end_sequence(4);
+
+            let debug_line_str_offsets = DebugLineStrOffsets::none();
+            let debug_str_offsets = DebugStrOffsets::none();
+            let mut debug_line = DebugLine::from(EndianVec::new(LittleEndian));
+            let offset = program
+                .write(
+                    &mut debug_line,
+                    encoding,
+                    &debug_line_str_offsets,
+                    &debug_str_offsets,
+                )
+                .unwrap();
+
+            let read_debug_line = read::DebugLine::new(debug_line.slice(), LittleEndian);
+            let read_program = read_debug_line
+                .program(
+                    offset,
+                    8,
+                    Some(read::EndianSlice::new(b"dir1", LittleEndian)),
+                    Some(read::EndianSlice::new(b"file1", LittleEndian)),
+                )
+                .unwrap();
+            let header = read_program.header();
+            let header_files = if version >= 5 { 4 } else { 2 };
+            assert_eq!(header.file_names().len(), header_files);
+            let mut define_files = 0;
+            let mut instructions = header.instructions();
+            while let Some(instruction) = instructions.next_instruction(header).unwrap() {
+                if let read::LineInstruction::DefineFile(_) = instruction {
+                    define_files += 1;
+                }
+            }
+            assert_eq!(define_files, 4 - header_files);
+
+            let mut rows = read_program.clone().rows();
+            let mut names = Vec::new();
+            while let Some((header, row)) = rows.next_row().unwrap() {
+                if !row.end_sequence() {
+                    let file = row.file(header).unwrap();
+                    let name = match file.path_name() {
+                        read::AttributeValue::String(name) => name.slice(),
+                        _ => panic!("unexpected path name form"),
+                    };
+                    names.push(name);
+                }
+            }
+            assert_eq!(names, [&b"file1"[..], b"file2", b"file3", b"file4"]);
+
+            // Converting the program preserves the defined files.
+            let dwarf = read::Dwarf::default();
+            let mut convert_line_strings = LineStringTable::default();
+            let mut convert_strings = StringTable::default();
+            let (convert_program, _) = LineProgram::from(
+                read_program,
+                &dwarf,
+                &mut convert_line_strings,
+                &mut convert_strings,
+                &|address| Some(Address::Constant(address)),
+            )
+            .unwrap();
+            let mut convert_debug_line = DebugLine::from(EndianVec::new(LittleEndian));
+            convert_program
+                .write(
+                    &mut convert_debug_line,
+                    encoding,
+                    &debug_line_str_offsets,
+                    &debug_str_offsets,
+                )
+                .unwrap();
+            assert_eq!(convert_debug_line.slice(), debug_line.slice());
+        }
+    }
+
     #[test]
     fn test_line_row() {
         let dir1 = &b"dir1"[..];
@@ -1295,7 +1747,7 @@ mod tests {
         let debug_str_offsets = DebugStrOffsets::none();
 
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -1601,7 +2053,7 @@ mod tests {
         let debug_str_offsets = DebugStrOffsets::none();
 
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -1843,7 +2295,7 @@ mod tests {
         let mut debug_line_str = DebugLineStr::from(EndianVec::new(LittleEndian));
         let debug_line_str_offsets = line_strings.write(&mut debug_line_str).unwrap();
 
-        for &address_size in &[4, 8] {
+        for &address_size in &[2, 3, 4, 6, 8] {
             for &format in &[Format::Dwarf32, Format::Dwarf64] {
                 let encoding = Encoding {
                     format,
@@ -1896,13 +2348,141 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sort_files() {
+        use crate::alloc::string::String;
+        use crate::write::{AttributeValue, Dwarf, Sections, Unit};
+
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let paths = [("b", "b.h"), ("a", "c.h"), ("a", "a.h")];
+        let build = |order: &[usize], implicit_first_file: bool| {
+            let mut dwarf = Dwarf::new();
+            let comp_dir = LineString::new("/src", encoding, &mut dwarf.line_strings);
+            let comp_file = LineString::new("main.c", encoding, &mut dwarf.line_strings);
+            let mut program =
+                LineProgram::new(encoding, LineEncoding::default(), comp_dir, comp_file, None);
+            let mut files = [FileId::zero(); 3];
+            for &i in order {
+                let (dir, file) = paths[i];
+                let dir = LineString::new(dir, encoding, &mut dwarf.line_strings);
+                let dir = program.add_directory(dir);
+                let file = LineString::new(file, encoding, &mut dwarf.line_strings);
+                files[i] = program.add_file(file, dir, None);
+            }
+
+            // Set the file register explicitly for every row.
+            program.begin_sequence(Some(Address::Constant(0x1000)));
+            for (i, &file) in files.iter().enumerate() {
+                program.row().file = file;
+                program.row().address_offset = i as u64 * 4;
+                program.row().line = i as u64 + 1;
+                program.generate_row();
+            }
+            program.end_sequence(12);
+            if implicit_first_file {
+                // Use the initial value of the file register.
+                program.begin_sequence(Some(Address::Constant(0x2000)));
+                program.row().line = 10;
+                program.generate_row();
+                program.end_sequence(4);
+            }
+
+            let unit_id = dwarf.units.add(Unit::new(encoding, program));
+            let unit = dwarf.units.get_mut(unit_id);
+            for file in files {
+                let subprogram = unit.add(unit.root(), constants::DW_TAG_subprogram);
+                unit.get_mut(subprogram).set(
+                    constants::DW_AT_decl_file,
+                    AttributeValue::FileIndex(Some(file)),
+                );
+            }
+            dwarf.strings.add("unused");
+            dwarf.sort();
+
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+            sections
+        };
+        let read_paths = |sections: &Sections<EndianVec<LittleEndian>>| {
+            let read_dwarf = read::Dwarf {
+                debug_abbrev: read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+                debug_info: read::DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+                debug_line: read::DebugLine::new(sections.debug_line.slice(), LittleEndian),
+                debug_line_str: read::DebugLineStr::new(
+                    sections.debug_line_str.slice(),
+                    LittleEndian,
+                ),
+                ..Default::default()
+            };
+            let header = read_dwarf.units().next().unwrap().unwrap();
+            let unit = read_dwarf.unit(header).unwrap();
+            let unit = unit.unit_ref(&read_dwarf);
+            let program = unit.line_program.clone().unwrap();
+            let header = program.header();
+
+            let mut decl_files = Vec::new();
+            let mut entries = unit.entries();
+            while let Some((_, entry)) = entries.next_dfs().unwrap() {
+                if let Some(read::AttributeValue::FileIndex(index)) =
+                    entry.attr_value(constants::DW_AT_decl_file).unwrap()
+                {
+                    let file = header.file(index).unwrap();
+                    decl_files.push(file.full_path(unit, header).unwrap());
+                }
+            }
+            let mut row_files = Vec::new();
+            let mut rows = program.clone().rows();
+            while let Some((header, row)) = rows.next_row().unwrap() {
+                if !row.end_sequence() {
+                    let file = row.file(header).unwrap();
+                    row_files.push(file.full_path(unit, header).unwrap());
+                }
+            }
+            (decl_files, row_files)
+        };
+
+        let expected = vec![
+            String::from("/src/b/b.h"),
+            String::from("/src/a/c.h"),
+            String::from("/src/a/a.h"),
+        ];
+        let sections1 = build(&[0, 1, 2], false);
+        let sections2 = build(&[2, 1, 0], false);
+        assert_eq!(read_paths(&sections1), (expected.clone(), expected.clone()));
+        for id in [
+            SectionId::DebugAbbrev,
+            SectionId::DebugInfo,
+            SectionId::DebugLine,
+            SectionId::DebugLineStr,
+            SectionId::DebugStr,
+        ] {
+            assert_eq!(
+                sections1.get(id).unwrap().slice(),
+                sections2.get(id).unwrap().slice(),
+                "{:?}",
+                id
+            );
+        }
+
+        // Sequences that use the initial file register value must still
+        // refer to the first file that was added.
+        let mut expected_rows = expected.clone();
+        expected_rows.push(String::from("/src/a/c.h"));
+        let sections = build(&[1, 0, 2], true);
+        assert_eq!(read_paths(&sections), (expected, expected_rows));
+    }
+
     #[test]
     fn test_missing_comp_dir() {
         let debug_line_str_offsets = DebugLineStrOffsets::none();
         let debug_str_offsets = DebugStrOffsets::none();
 
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -4,8 +4,8 @@ use std::ops::{Deref, DerefMut};
 
 use crate::common::{Encoding, LocationListsOffset, SectionId};
 use crate::write::{
-    Address, BaseId, DebugInfoReference, Error, Expression, Result, Section, Sections, UnitOffsets,
-    Writer,
+    index_set_allocated_bytes, vec_allocated_bytes, Address, BaseId, DebugInfoReference, Error,
+    Expression, Result, Section, Sections, TombstonePolicy, UnitOffsets, Writer,
 };
 
 define_section!(
@@ -29,6 +29,16 @@ define_id!(
     "An identifier for a location list in a `LocationListTable`."
 );
 
+impl LocationListId {
+    /// Return the index of the location list within its table.
+    ///
+    /// This is the index used by `DW_FORM_loclistx`.
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        self.index
+    }
+}
+
 /// A table of location lists that will be stored in a `.debug_loc` or `.debug_loclists` section.
 #[derive(Debug, Default)]
 pub struct LocationListTable {
@@ -43,12 +53,49 @@ impl LocationListTable {
         LocationListId::new(self.base_id, index)
     }
 
+    /// Get a reference to a location list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is invalid.
+    pub(crate) fn get(&self, id: LocationListId) -> &LocationList {
+        debug_assert_eq!(self.base_id, id.base_id);
+        &self.locations[id.index]
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the table.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        let locations = self
+            .locations
+            .iter()
+            .flat_map(|location_list| location_list.0.iter())
+            .map(|location| match location {
+                Location::BaseAddress { .. } => 0,
+                Location::OffsetPair { data, .. }
+                | Location::StartEnd { data, .. }
+                | Location::StartLength { data, .. }
+                | Location::DefaultLocation { data } => data.allocated_bytes(),
+            })
+            .sum::<usize>();
+        let lists = self
+            .locations
+            .iter()
+            .map(|location_list| vec_allocated_bytes(&location_list.0))
+            .sum::<usize>();
+        index_set_allocated_bytes(&self.locations) + lists + locations
+    }
+
     /// Write the location list table to the appropriate section for the given DWARF version.
+    ///
+    /// If `offset_entries` is true, then the table includes an offset array so that
+    /// the location lists can be referenced using `DW_FORM_loclistx`.
+    /// This is ignored for versions before 5.
     pub(crate) fn write<W: Writer>(
         &self,
         sections: &mut Sections<W>,
         encoding: Encoding,
         unit_offsets: Option<&UnitOffsets>,
+        offset_entries: bool,
     ) -> Result<LocationListOffsets> {
         if self.locations.is_empty() {
             return Ok(LocationListOffsets::none());
@@ -66,6 +113,7 @@ impl LocationListTable {
                 &mut sections.debug_loclists_refs,
                 encoding,
                 unit_offsets,
+                offset_entries,
             ),
             _ => Err(Error::UnsupportedVersion(encoding.version)),
         }
@@ -157,6 +205,7 @@ impl LocationListTable {
         refs: &mut Vec<DebugInfoReference>,
         encoding: Encoding,
         unit_offsets: Option<&UnitOffsets>,
+        offset_entries: bool,
     ) -> Result<LocationListOffsets> {
         let mut offsets = Vec::new();
 
@@ -170,10 +219,27 @@ impl LocationListTable {
         w.write_u16(encoding.version)?;
         w.write_u8(encoding.address_size)?;
         w.write_u8(0)?; // segment_selector_size
-        w.write_u32(0)?; // offset_entry_count (when set to zero DW_FORM_rnglistx can't be used, see section 7.28)
-                         // FIXME implement DW_FORM_rnglistx writing and implement the offset entry list
+        let offset_entry_count = if offset_entries {
+            self.locations.len()
+        } else {
+            0
+        };
+        w.write_u32(offset_entry_count as u32)?;
+        let offset_entries_base = w.len();
+        let offset_size = encoding.format.word_size();
+        for _ in 0..offset_entry_count {
+            w.write_udata(0, offset_size)?;
+        }
 
-        for loc_list in self.locations.iter() {
+        for (i, loc_list) in self.locations.iter().enumerate() {
+            if offset_entries {
+                let offset = (w.len() - offset_entries_base) as u64;
+                w.write_udata_at(
+                    offset_entries_base + i * offset_size as usize,
+                    offset,
+                    offset_size,
+                )?;
+            }
             offsets.push(w.offset());
             for loc in &loc_list.0 {
                 match *loc {
@@ -277,6 +343,79 @@ pub enum Location {
     },
 }
 
+impl LocationList {
+    /// Return a copy of the location list without the locations that begin at
+    /// an address for which `live` returns false.
+    ///
+    /// `base` is the initial base address for offset pairs.
+    /// The returned list is empty if no locations remain.
+    pub(crate) fn discard_addresses<F>(
+        &self,
+        mut base: Option<Address>,
+        live: &mut F,
+        policy: TombstonePolicy,
+    ) -> LocationList
+    where
+        F: FnMut(Address) -> bool,
+    {
+        let mut locations = Vec::new();
+        let mut empty = true;
+        for location in &self.0 {
+            let begin = match *location {
+                Location::BaseAddress { address } => {
+                    base = Some(address);
+                    locations.push(location.clone());
+                    continue;
+                }
+                Location::OffsetPair { begin, .. } => base.map(|base| base.add(begin)),
+                Location::StartEnd { begin, .. } | Location::StartLength { begin, .. } => {
+                    Some(begin)
+                }
+                Location::DefaultLocation { .. } => None,
+            };
+            if !begin.map_or(true, &mut *live) {
+                continue;
+            }
+            let mut location = location.clone();
+            if let Some(data) = location.data_mut() {
+                if !data.discard_addresses(live, policy) {
+                    continue;
+                }
+            }
+            locations.push(location);
+            empty = false;
+        }
+        if empty {
+            locations.clear();
+        }
+        LocationList(locations)
+    }
+}
+
+impl Location {
+    /// Return the location description, if any.
+    pub(crate) fn data(&self) -> Option<&Expression> {
+        match *self {
+            Location::BaseAddress { .. } => None,
+            Location::OffsetPair { ref data, .. }
+            | Location::StartEnd { ref data, .. }
+            | Location::StartLength { ref data, .. }
+            | Location::DefaultLocation { ref data } => Some(data),
+        }
+    }
+
+    /// Return a mutable reference to the location description, if any.
+    pub(crate) fn data_mut(&mut self) -> Option<&mut Expression> {
+        match *self {
+            Location::BaseAddress { .. } => None,
+            Location::OffsetPair { ref mut data, .. }
+            | Location::StartEnd { ref mut data, .. }
+            | Location::StartLength { ref mut data, .. }
+            | Location::DefaultLocation { ref mut data } => Some(data),
+        }
+    }
+}
+
 fn write_expression<W: Writer>(
     w: &mut W,
     refs: &mut Vec<DebugInfoReference>,
@@ -307,10 +446,18 @@ mod convert {
             mut from: read::RawLocListIter<R>,
             context: &ConvertUnitContext<'_, R>,
         ) -> ConvertResult<Self> {
-            let mut have_base_address = context.base_address != Address::Constant(0);
+            // Check the unconverted address, since a zero base address may be
+            // converted to a non-zero address.
+            let mut have_base_address = match context.base_address {
+                Address::Constant(_) => context.unit.low_pc != 0,
+                Address::Symbol { .. } => true,
+            };
             let convert_address =
                 |x| (context.convert_address)(x).ok_or(ConvertError::InvalidAddress);
-            let convert_expression = |x| {
+            let convert_expression = |x: read::Expression<R>| {
+                if context.raw_expressions {
+                    return Ok(Expression::raw(x.0.to_slice()?.into()));
+                }
                 Expression::from(
                     x,
                     context.unit.encoding(),
@@ -325,12 +472,16 @@ mod convert {
                 let loc = match from_loc {
                     read::RawLocListEntry::AddressOrOffsetPair { begin, end, data } => {
                         // These were parsed as addresses, even if they are offsets.
+                        let begin_offset = begin;
+                        let end_offset = end;
                         let begin = convert_address(begin)?;
                         let end = convert_address(end)?;
                         let data = convert_expression(data)?;
                         match (begin, end) {
-                            (Address::Constant(begin_offset), Address::Constant(end_offset)) => {
+                            (Address::Constant(_), Address::Constant(_)) => {
                                 if have_base_address {
+                                    // The base address has already been converted,
+                                    // so use the unconverted offsets.
                                     Location::OffsetPair {
                                         begin: begin_offset,
                                         end: end_offset,
@@ -446,7 +597,7 @@ mod tests {
         expression.op_constu(0);
 
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -484,7 +635,9 @@ mod tests {
                     let loc_list_id = locations.add(loc_list.clone());
 
                     let mut sections = Sections::new(EndianVec::new(LittleEndian));
-                    let loc_list_offsets = locations.write(&mut sections, encoding, None).unwrap();
+                    let loc_list_offsets = locations
+                        .write(&mut sections, encoding, None, false)
+                        .unwrap();
                     assert!(sections.debug_loc_refs.is_empty());
                     assert!(sections.debug_loclists_refs.is_empty());
 
@@ -512,6 +665,7 @@ mod tests {
                         abbreviations: Arc::new(read::Abbreviations::default()),
                         name: None,
                         comp_dir: None,
+                        language: None,
                         low_pc: 0,
                         str_offsets_base: DebugStrOffsetsBase(0),
                         addr_base: DebugAddrBase(0),
@@ -531,6 +685,8 @@ mod tests {
                         base_address: Address::Constant(0),
                         line_program_offset: None,
                         line_program_files: Vec::new(),
+                        line_programs: None,
+                        raw_expressions: false,
                         entry_ids: &HashMap::new(),
                     };
                     let convert_loc_list = LocationList::from(read_loc_list, &context).unwrap();
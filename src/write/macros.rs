@@ -0,0 +1,536 @@
+use alloc::vec::Vec;
+use std::ops::{Deref, DerefMut};
+
+use crate::common::{DebugLineOffset, DebugMacroOffset, DebugStrOffsetsIndex, Encoding, SectionId};
+use crate::constants;
+use crate::write::{
+    vec_allocated_bytes, BaseId, DebugStrOffsets, Error, FileId, Result, Section, StringId, Writer,
+};
+
+define_section!(
+    DebugMacro,
+    DebugMacroOffset,
+    "A writable `.debug_macro` section."
+);
+
+define_offsets!(
+    MacroUnitOffsets: MacroUnitId => DebugMacroOffset,
+    "The section offsets of a series of macro units within the `.debug_macro` section."
+);
+
+define_id!(
+    MacroUnitId,
+    "An identifier for a macro unit in a `MacroTable`."
+);
+
+/// A table of macro units that will be stored in a `.debug_macro` section.
+///
+/// The first macro unit that is added is the one that is referenced by the
+/// `DW_AT_macros` attribute of the compilation unit. Other macro units may be
+/// referenced using `Macro::Import`.
+#[derive(Debug, Default)]
+pub struct MacroTable {
+    base_id: BaseId,
+    units: Vec<MacroUnit>,
+}
+
+impl MacroTable {
+    /// Add a macro unit to the table.
+    pub fn add(&mut self, unit: MacroUnit) -> MacroUnitId {
+        let id = MacroUnitId::new(self.base_id, self.units.len());
+        self.units.push(unit);
+        id
+    }
+
+    /// Return true if the table contains no macro units.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the table.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        let units = self
+            .units
+            .iter()
+            .map(|unit| {
+                let opcodes = unit
+                    .opcodes
+                    .iter()
+                    .map(|(_, forms)| vec_allocated_bytes(forms))
+                    .sum::<usize>();
+                let entries = unit
+                    .entries
+                    .iter()
+                    .map(|entry| match entry {
+                        Macro::Define {
+                            text: MacroString::String(text),
+                            ..
+                        }
+                        | Macro::Undef {
+                            text: MacroString::String(text),
+                            ..
+                        }
+                        | Macro::Vendor { data: text, .. } => text.capacity(),
+                        _ => 0,
+                    })
+                    .sum::<usize>();
+                vec_allocated_bytes(&unit.opcodes)
+                    + opcodes
+                    + vec_allocated_bytes(&unit.entries)
+                    + entries
+            })
+            .sum::<usize>();
+        vec_allocated_bytes(&self.units) + units
+    }
+
+    /// Get a reference to a macro unit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is invalid.
+    #[inline]
+    pub fn get(&self, id: MacroUnitId) -> &MacroUnit {
+        debug_assert_eq!(self.base_id, id.base_id);
+        &self.units[id.index]
+    }
+
+    /// Get a mutable reference to a macro unit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is invalid.
+    #[inline]
+    pub fn get_mut(&mut self, id: MacroUnitId) -> &mut MacroUnit {
+        debug_assert_eq!(self.base_id, id.base_id);
+        &mut self.units[id.index]
+    }
+
+    /// Return the id of the macro unit that is referenced by the compilation unit.
+    pub(crate) fn root(&self) -> MacroUnitId {
+        MacroUnitId::new(self.base_id, 0)
+    }
+
+    /// Return true if any macro unit refers to a file in the line number program.
+    pub(crate) fn uses_files(&self) -> bool {
+        self.units.iter().any(MacroUnit::uses_files)
+    }
+
+    /// Replace the files that are referred to by `Macro::StartFile` entries.
+    pub(crate) fn map_files<F>(&mut self, mut map: F)
+    where
+        F: FnMut(FileId) -> FileId,
+    {
+        for unit in &mut self.units {
+            for entry in &mut unit.entries {
+                if let Macro::StartFile { file, .. } = entry {
+                    *file = map(*file);
+                }
+            }
+        }
+    }
+
+    /// Write the macro units to the `.debug_macro` section.
+    ///
+    /// `line_program` is the offset of the line number program of the
+    /// compilation unit, which is required if any macro unit contains
+    /// `Macro::StartFile`.
+    pub(crate) fn write<W: Writer>(
+        &self,
+        w: &mut DebugMacro<W>,
+        encoding: Encoding,
+        line_program: Option<DebugLineOffset>,
+        strings: &DebugStrOffsets,
+    ) -> Result<MacroUnitOffsets> {
+        if self.units.is_empty() {
+            return Ok(MacroUnitOffsets::none());
+        }
+
+        // Version 4 uses the GNU extension, which has the same encoding.
+        let version = match encoding.version {
+            4 | 5 => encoding.version,
+            2 | 3 => return Err(Error::NeedVersion(4)),
+            _ => return Err(Error::UnsupportedVersion(encoding.version)),
+        };
+        let offset_size = encoding.format.word_size();
+
+        let mut offsets = Vec::with_capacity(self.units.len());
+        let mut imports = Vec::new();
+        for unit in &self.units {
+            offsets.push(w.offset());
+
+            w.write_u16(version)?;
+            let mut flags = 0;
+            if offset_size == 8 {
+                flags |= 0x1;
+            }
+            let line_program = if unit.uses_files() {
+                Some(line_program.ok_or(Error::InvalidReference)?)
+            } else {
+                None
+            };
+            if line_program.is_some() {
+                flags |= 0x2;
+            }
+            if !unit.opcodes.is_empty() {
+                flags |= 0x4;
+            }
+            w.write_u8(flags)?;
+            if let Some(line_program) = line_program {
+                w.write_offset(line_program.0, SectionId::DebugLine, offset_size)?;
+            }
+            if !unit.opcodes.is_empty() {
+                w.write_u8(unit.opcodes.len() as u8)?;
+                for (opcode, forms) in &unit.opcodes {
+                    w.write_u8(opcode.0)?;
+                    w.write_uleb128(forms.len() as u64)?;
+                    for form in forms {
+                        w.write_u8(form.0 as u8)?;
+                    }
+                }
+            }
+
+            for entry in &unit.entries {
+                match *entry {
+                    Macro::Define { line, ref text } => {
+                        text.write(w, line, constants::DW_MACRO_define, encoding, strings)?;
+                    }
+                    Macro::Undef { line, ref text } => {
+                        text.write(w, line, constants::DW_MACRO_undef, encoding, strings)?;
+                    }
+                    Macro::StartFile { line, file } => {
+                        w.write_u8(constants::DW_MACRO_start_file.0)?;
+                        w.write_uleb128(line)?;
+                        w.write_uleb128(file.raw())?;
+                    }
+                    Macro::EndFile => {
+                        w.write_u8(constants::DW_MACRO_end_file.0)?;
+                    }
+                    Macro::Import(id) => {
+                        debug_assert_eq!(self.base_id, id.base_id);
+                        if id.index >= self.units.len() {
+                            return Err(Error::InvalidReference);
+                        }
+                        w.write_u8(constants::DW_MACRO_import.0)?;
+                        imports.push((w.len(), id));
+                        w.write_udata(0, offset_size)?;
+                    }
+                    Macro::Vendor { opcode, ref data } => {
+                        if !unit.opcodes.iter().any(|(x, _)| *x == opcode) {
+                            return Err(Error::InvalidReference);
+                        }
+                        w.write_u8(opcode.0)?;
+                        w.write(data)?;
+                    }
+                }
+            }
+            w.write_u8(0)?;
+        }
+
+        // Imports may refer to macro units that were written after them.
+        for (offset, id) in imports {
+            w.write_offset_at(
+                offset,
+                offsets[id.index].0,
+                SectionId::DebugMacro,
+                offset_size,
+            )?;
+        }
+
+        Ok(MacroUnitOffsets {
+            base_id: self.base_id,
+            offsets,
+        })
+    }
+}
+
+/// A macro unit, which is a sequence of macro entries in the `.debug_macro` section.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MacroUnit {
+    opcodes: Vec<(constants::DwMacro, Vec<constants::DwForm>)>,
+    entries: Vec<Macro>,
+}
+
+impl MacroUnit {
+    /// Create a new, empty `MacroUnit`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entry to the opcode operands table.
+    ///
+    /// This describes the forms of the operands of a vendor extension opcode,
+    /// so that consumers can skip it. The opcode must be in the range
+    /// `DW_MACRO_lo_user` to `DW_MACRO_hi_user`.
+    pub fn add_opcode(
+        &mut self,
+        opcode: constants::DwMacro,
+        forms: Vec<constants::DwForm>,
+    ) -> Result<()> {
+        if opcode < constants::DW_MACRO_lo_user || forms.iter().any(|form| form.0 > 0xff) {
+            return Err(Error::InvalidAttributeValue);
+        }
+        match self.opcodes.iter_mut().find(|(x, _)| *x == opcode) {
+            Some(entry) => entry.1 = forms,
+            None => self.opcodes.push((opcode, forms)),
+        }
+        Ok(())
+    }
+
+    /// Add an entry to the macro unit.
+    #[inline]
+    pub fn add(&mut self, entry: Macro) {
+        self.entries.push(entry);
+    }
+
+    /// Return the entries in the macro unit.
+    #[inline]
+    pub fn entries(&self) -> &[Macro] {
+        &self.entries
+    }
+
+    fn uses_files(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| matches!(entry, Macro::StartFile { .. }))
+    }
+}
+
+/// An entry in a `MacroUnit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Macro {
+    /// A macro definition, using `DW_MACRO_define`, `DW_MACRO_define_strp`,
+    /// or `DW_MACRO_define_strx`.
+    ///
+    /// The text is the macro name, followed by the parameter list (if any),
+    /// a space, and the definition.
+    Define {
+        /// The source line of the definition, or 0 if it was given on the
+        /// command line.
+        line: u64,
+        /// The text of the definition.
+        text: MacroString,
+    },
+    /// A macro undefinition, using `DW_MACRO_undef`, `DW_MACRO_undef_strp`,
+    /// or `DW_MACRO_undef_strx`.
+    Undef {
+        /// The source line of the undefinition, or 0 if it was given on the
+        /// command line.
+        line: u64,
+        /// The name of the macro.
+        text: MacroString,
+    },
+    /// The start of an included source file, using `DW_MACRO_start_file`.
+    StartFile {
+        /// The source line of the include directive, or 0 for the primary
+        /// source file.
+        line: u64,
+        /// The file in the line number program of the compilation unit.
+        file: FileId,
+    },
+    /// The end of the most recent `StartFile`, using `DW_MACRO_end_file`.
+    EndFile,
+    /// The entries of another macro unit in the same table, using `DW_MACRO_import`.
+    Import(MacroUnitId),
+    /// A vendor extension opcode.
+    ///
+    /// The opcode must have been added using `MacroUnit::add_opcode`.
+    /// The operands are written as is, so they must already be in the target
+    /// format and match the forms in the opcode operands table.
+    Vendor {
+        /// The opcode.
+        opcode: constants::DwMacro,
+        /// The raw bytes of the operands.
+        data: Vec<u8>,
+    },
+}
+
+/// The string operand of a `Macro::Define` or `Macro::Undef`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroString {
+    /// An inline string.
+    String(Vec<u8>),
+    /// A reference to a string in the `.debug_str` section.
+    StringRef(StringId),
+    /// An index into the `.debug_str_offsets` section.
+    ///
+    /// The string offsets table must be constructed separately, and the
+    /// compilation unit must have a `DW_AT_str_offsets_base` attribute.
+    /// This requires DWARF version 5.
+    StringIndex(DebugStrOffsetsIndex),
+}
+
+impl MacroString {
+    fn write<W: Writer>(
+        &self,
+        w: &mut DebugMacro<W>,
+        line: u64,
+        opcode: constants::DwMacro,
+        encoding: Encoding,
+        strings: &DebugStrOffsets,
+    ) -> Result<()> {
+        let define = opcode == constants::DW_MACRO_define;
+        match *self {
+            MacroString::String(ref val) => {
+                w.write_u8(opcode.0)?;
+                w.write_uleb128(line)?;
+                w.write(val)?;
+                w.write_u8(0)?;
+            }
+            MacroString::StringRef(id) => {
+                w.write_u8(if define {
+                    constants::DW_MACRO_define_strp.0
+                } else {
+                    constants::DW_MACRO_undef_strp.0
+                })?;
+                w.write_uleb128(line)?;
+                w.write_offset(
+                    strings.get(id).0,
+                    SectionId::DebugStr,
+                    encoding.format.word_size(),
+                )?;
+            }
+            MacroString::StringIndex(index) => {
+                if encoding.version < 5 {
+                    return Err(Error::NeedVersion(5));
+                }
+                w.write_u8(if define {
+                    constants::DW_MACRO_define_strx.0
+                } else {
+                    constants::DW_MACRO_undef_strx.0
+                })?;
+                w.write_uleb128(line)?;
+                w.write_uleb128(index.0 as u64)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{Format, LineEncoding};
+    use crate::read;
+    use crate::write::{DwarfUnit, EndianVec, LineProgram, LineString, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_macro_table() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let mut line_program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"dir".to_vec()),
+            LineString::String(b"file".to_vec()),
+            None,
+        );
+        let dir = line_program.default_directory();
+        let file = line_program.add_file(LineString::String(b"file".to_vec()), dir, None);
+        dwarf.unit.line_program = line_program;
+        let string = dwarf.strings.add("B 2");
+
+        let mut main = MacroUnit::new();
+        let mut imported = MacroUnit::new();
+        imported.add(Macro::Undef {
+            line: 4,
+            text: MacroString::StringIndex(DebugStrOffsetsIndex(3)),
+        });
+        main.add_opcode(constants::DW_MACRO_lo_user, vec![constants::DW_FORM_data1])
+            .unwrap();
+        main.add(Macro::StartFile { line: 0, file });
+        main.add(Macro::Define {
+            line: 1,
+            text: MacroString::String(b"A 1".to_vec()),
+        });
+        main.add(Macro::Define {
+            line: 2,
+            text: MacroString::StringRef(string),
+        });
+        main.add(Macro::Undef {
+            line: 3,
+            text: MacroString::String(b"A".to_vec()),
+        });
+        main.add(Macro::Vendor {
+            opcode: constants::DW_MACRO_lo_user,
+            data: vec![0x2a],
+        });
+        let main_id = dwarf.unit.macros.add(main);
+        let imported_id = dwarf.unit.macros.add(imported);
+        dwarf
+            .unit
+            .macros
+            .get_mut(main_id)
+            .add(Macro::Import(imported_id));
+        dwarf.unit.macros.get_mut(main_id).add(Macro::EndFile);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut expect = vec![
+            // Header: version, flags, line offset, opcode operands table.
+            0x05, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x01, 0xe0, 0x01, 0x0b,
+        ];
+        expect.extend_from_slice(&[0x03, 0x00, file.raw() as u8]);
+        expect.extend_from_slice(&[0x01, 0x01, b'A', b' ', b'1', 0x00]);
+        expect.extend_from_slice(&[0x05, 0x02, 0x00, 0x00, 0x00, 0x00]);
+        expect.extend_from_slice(&[0x02, 0x03, b'A', 0x00]);
+        expect.extend_from_slice(&[0xe0, 0x2a]);
+        let imported_offset = expect.len() as u8 + 7;
+        expect.extend_from_slice(&[0x07, imported_offset, 0x00, 0x00, 0x00]);
+        expect.extend_from_slice(&[0x04, 0x00]);
+        assert_eq!(expect.len() as u8, imported_offset);
+        expect.extend_from_slice(&[0x05, 0x00, 0x00, 0x0c, 0x04, 0x03, 0x00]);
+        assert_eq!(sections.debug_macro.slice(), &*expect);
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        let (_, root) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(
+            root.attr_value(constants::DW_AT_macros).unwrap(),
+            Some(read::AttributeValue::DebugMacroRef(DebugMacroOffset(0)))
+        );
+        assert!(root.attr(constants::DW_AT_stmt_list).unwrap().is_some());
+
+        // Version 4 uses the GNU extension.
+        dwarf
+            .unit
+            .set_version(4, &dwarf.line_strings, &mut dwarf.strings)
+            .unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        assert_eq!(dwarf.write(&mut sections), Err(Error::NeedVersion(5)));
+        dwarf.unit.macros.get_mut(imported_id).entries[0] = Macro::Undef {
+            line: 4,
+            text: MacroString::String(b"A".to_vec()),
+        };
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        assert_eq!(&sections.debug_macro.slice()[..2], &[0x04, 0x00]);
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        let (_, root) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(root.attr(constants::DW_AT_macros).unwrap(), None);
+        assert_eq!(
+            root.attr_value(constants::DW_AT_GNU_macros).unwrap(),
+            Some(read::AttributeValue::SecOffset(0))
+        );
+
+        dwarf
+            .unit
+            .set_version(3, &dwarf.line_strings, &mut dwarf.strings)
+            .unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        assert_eq!(dwarf.write(&mut sections), Err(Error::NeedVersion(4)));
+    }
+}
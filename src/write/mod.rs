@@ -70,6 +70,9 @@ pub use self::endian_vec::*;
 mod writer;
 pub use self::writer::*;
 
+mod stream;
+pub use self::stream::*;
+
 mod relocate;
 pub use self::relocate::*;
 
@@ -138,24 +141,47 @@ macro_rules! define_offsets {
 mod abbrev;
 pub use self::abbrev::*;
 
+mod addr;
+pub use self::addr::*;
+
+mod apple;
+pub use self::apple::*;
+
+mod aranges;
+pub use self::aranges::*;
+
 mod cfi;
 pub use self::cfi::*;
 
 mod dwarf;
 pub use self::dwarf::*;
 
+mod gdb_index;
+pub use self::gdb_index::*;
+
 mod line;
 pub use self::line::*;
 
 mod loc;
 pub use self::loc::*;
 
+mod macros;
+pub use self::macros::*;
+
 mod op;
 pub use self::op::*;
 
+mod profile;
+pub use self::profile::*;
+
+mod pubnames;
+pub use self::pubnames::*;
+
 mod range;
 pub use self::range::*;
 
+mod signature;
+
 mod str;
 pub use self::str::*;
 
@@ -201,6 +227,12 @@ pub enum Error {
     UnsupportedCfiExpressionReference,
     /// Unsupported forward reference in expression.
     UnsupportedExpressionForwardReference,
+    /// An I/O error occurred when writing to a stream.
+    Io(std::io::ErrorKind),
+    /// A `.debug_macinfo` reference cannot be changed to DWARF version 5.
+    UnsupportedMacinfo,
+    /// The operation does not support units that were copied verbatim.
+    UnsupportedVerbatimUnit,
 }
 
 impl fmt::Display for Error {
@@ -254,6 +286,15 @@ impl fmt::Display for Error {
             Error::UnsupportedExpressionForwardReference => {
                 write!(f, "Unsupported forward reference in expression.")
             }
+            Error::Io(kind) => write!(f, "An I/O error occurred: {:?}.", kind),
+            Error::UnsupportedMacinfo => write!(
+                f,
+                "A `.debug_macinfo` reference cannot be changed to DWARF version 5."
+            ),
+            Error::UnsupportedVerbatimUnit => write!(
+                f,
+                "The operation does not support units that were copied verbatim."
+            ),
         }
     }
 }
@@ -282,6 +323,19 @@ pub enum Address {
     },
 }
 
+impl Address {
+    /// Return the address plus the given offset.
+    pub(crate) fn add(self, offset: u64) -> Address {
+        match self {
+            Address::Constant(address) => Address::Constant(address.wrapping_add(offset)),
+            Address::Symbol { symbol, addend } => Address::Symbol {
+                symbol,
+                addend: addend.wrapping_add(offset as i64),
+            },
+        }
+    }
+}
+
 /// A reference to a `.debug_info` entry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Reference {
@@ -296,6 +350,28 @@ pub enum Reference {
     Entry(UnitId, UnitEntryId),
 }
 
+/// Return an estimate of the heap memory used by the elements of a `Vec`.
+///
+/// This does not include memory owned by the elements.
+fn vec_allocated_bytes<T>(vec: &alloc::vec::Vec<T>) -> usize {
+    vec.capacity() * std::mem::size_of::<T>()
+}
+
+/// Return an estimate of the heap memory used by the entries of an `IndexMap`.
+///
+/// This includes the hash and index of each entry, but does not include
+/// memory owned by the keys or values.
+fn index_map_allocated_bytes<K, V>(map: &indexmap::IndexMap<K, V>) -> usize {
+    map.capacity() * (std::mem::size_of::<(K, V)>() + 2 * std::mem::size_of::<usize>())
+}
+
+/// Return an estimate of the heap memory used by the entries of an `IndexSet`.
+///
+/// This does not include memory owned by the values.
+fn index_set_allocated_bytes<T>(set: &indexmap::IndexSet<T>) -> usize {
+    set.capacity() * (std::mem::size_of::<T>() + 2 * std::mem::size_of::<usize>())
+}
+
 // This type is only used in debug assertions.
 #[cfg(not(debug_assertions))]
 type BaseId = ();
@@ -361,6 +437,8 @@ mod convert {
         InvalidBranchTarget,
         /// Writing this unit type is not supported yet.
         UnsupportedUnitType,
+        /// Converting this `.debug_macinfo` entry is not supported.
+        UnsupportedMacinfo,
     }
 
     impl fmt::Display for ConvertError {
@@ -409,6 +487,12 @@ mod convert {
                 ),
                 InvalidBranchTarget => write!(f, "Operation branch target is invalid."),
                 UnsupportedUnitType => write!(f, "Writing this unit type is not supported yet."),
+                UnsupportedMacinfo => {
+                    write!(
+                        f,
+                        "Converting this `.debug_macinfo` entry is not supported."
+                    )
+                }
             }
         }
     }
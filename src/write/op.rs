@@ -5,7 +5,8 @@ use crate::common::{Encoding, Register};
 use crate::constants::{self, DwOp};
 use crate::leb128::write::{sleb128_size, uleb128_size};
 use crate::write::{
-    Address, DebugInfoReference, Error, Reference, Result, UnitEntryId, UnitOffsets, Writer,
+    vec_allocated_bytes, Address, DebugInfoReference, Error, Reference, Result, TombstonePolicy,
+    UnitEntryId, UnitId, UnitOffsets, Writer,
 };
 
 /// The bytecode for a DWARF expression or location description.
@@ -255,6 +256,80 @@ impl Expression {
         self.operations.push(Operation::WasmStack(index));
     }
 
+    /// Return true if `f` returns true for all entries referenced by the expression.
+    ///
+    /// The unit is `None` for references to entries in the same unit.
+    pub(crate) fn all_references<F>(&self, f: &mut F) -> bool
+    where
+        F: FnMut(Option<UnitId>, UnitEntryId) -> bool,
+    {
+        self.operations
+            .iter()
+            .all(|operation| operation.all_references(f))
+    }
+
+    /// Replace the unit ids in references to entries in other units.
+    pub(crate) fn convert_unit_ids<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(UnitId) -> UnitId,
+    {
+        for operation in &mut self.operations {
+            match *operation {
+                Operation::CallRef(Reference::Entry(ref mut unit, _))
+                | Operation::ImplicitPointer {
+                    entry: Reference::Entry(ref mut unit, _),
+                    ..
+                } => *unit = f(*unit),
+                Operation::EntryValue(ref mut expression) => expression.convert_unit_ids(f),
+                _ => {}
+            }
+        }
+    }
+
+    /// Replace the addresses for which `live` returns false with the tombstone.
+    ///
+    /// Returns false if there is no tombstone and the expression contains
+    /// such an address.
+    pub(crate) fn discard_addresses<F>(&mut self, live: &mut F, policy: TombstonePolicy) -> bool
+    where
+        F: FnMut(Address) -> bool,
+    {
+        for operation in &mut self.operations {
+            match *operation {
+                Operation::Address(ref mut address) if !live(*address) => match policy {
+                    TombstonePolicy::Delete => return false,
+                    TombstonePolicy::Address(tombstone) => {
+                        *address = Address::Constant(tombstone);
+                    }
+                },
+                Operation::EntryValue(ref mut expression) => {
+                    let keep = expression.discard_addresses(live, policy);
+                    if !keep {
+                        return false;
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the
+    /// expression.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        let operations = self
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Raw(bytecode) => bytecode.capacity(),
+                Operation::ConstantType(_, value) | Operation::ImplicitValue(value) => value.len(),
+                Operation::EntryValue(expression) => expression.allocated_bytes(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        vec_allocated_bytes(&self.operations) + operations
+    }
+
     pub(crate) fn size(&self, encoding: Encoding, unit_offsets: Option<&UnitOffsets>) -> usize {
         let mut size = 0;
         for operation in &self.operations {
@@ -498,11 +573,36 @@ enum Operation {
 }
 
 impl Operation {
+    fn all_references<F>(&self, f: &mut F) -> bool
+    where
+        F: FnMut(Option<UnitId>, UnitEntryId) -> bool,
+    {
+        match *self {
+            Operation::ConstantType(entry, _)
+            | Operation::RegisterType(_, entry)
+            | Operation::DerefType { base: entry, .. }
+            | Operation::Call(entry)
+            | Operation::Convert(Some(entry))
+            | Operation::Reinterpret(Some(entry))
+            | Operation::ParameterRef(entry) => f(None, entry),
+            Operation::CallRef(Reference::Entry(unit, entry))
+            | Operation::ImplicitPointer {
+                entry: Reference::Entry(unit, entry),
+                ..
+            } => f(Some(unit), entry),
+            Operation::EntryValue(ref expression) => expression.all_references(f),
+            _ => true,
+        }
+    }
+
     fn size(&self, encoding: Encoding, unit_offsets: Option<&UnitOffsets>) -> usize {
         let base_size = |base| {
             // Errors are handled during writes.
             match unit_offsets {
-                Some(offsets) => uleb128_size(offsets.unit_offset(base)),
+                // The entry may not have an offset yet if base types have not
+                // been moved to the front, in which case `Unit::write` will
+                // recalculate the size.
+                Some(offsets) => uleb128_size(offsets.estimated_unit_offset(base)),
                 None => 0,
             }
         };
@@ -1050,6 +1150,11 @@ pub(crate) mod convert {
                     read::Operation::WasmLocal { index } => Operation::WasmLocal(index),
                     read::Operation::WasmGlobal { index } => Operation::WasmGlobal(index),
                     read::Operation::WasmStack { index } => Operation::WasmStack(index),
+                    // The operands of an unknown operation may contain addresses
+                    // or references that would need to be converted.
+                    read::Operation::Unknown { .. } => {
+                        return Err(ConvertError::UnsupportedOperation);
+                    }
                 };
                 operations.push(operation);
             }
@@ -1068,7 +1173,8 @@ mod tests {
     };
     use crate::read;
     use crate::write::{
-        DebugLineStrOffsets, DebugStrOffsets, EndianVec, LineProgram, Sections, Unit, UnitTable,
+        ConvertError, DebugLineStrOffsets, DebugStrOffsets, EndianVec, LineProgram, Sections, Unit,
+        UnitTable,
     };
     use crate::LittleEndian;
     use std::collections::HashMap;
@@ -1584,6 +1690,7 @@ mod tests {
                         abbreviations: Arc::new(read::Abbreviations::default()),
                         name: None,
                         comp_dir: None,
+                        language: None,
                         low_pc: 0,
                         str_offsets_base: DebugStrOffsetsBase(0),
                         addr_base: DebugAddrBase(0),
@@ -1621,4 +1728,28 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_convert_unknown_operation() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        // An unknown operation followed by `DW_OP_addr`, which can't be converted.
+        let mut bytecode = vec![0xf0, constants::DW_OP_addr.0];
+        bytecode.extend_from_slice(&0x1000u64.to_le_bytes());
+        let read_expression = read::Expression(read::EndianSlice::new(&bytecode, LittleEndian));
+        assert_eq!(
+            Expression::from(
+                read_expression,
+                encoding,
+                None, /* dwarf */
+                None, /* unit */
+                None, /* entry_ids */
+                &|address| Some(Address::Constant(address)),
+            ),
+            Err(ConvertError::UnsupportedOperation)
+        );
+    }
 }
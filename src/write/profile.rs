@@ -0,0 +1,401 @@
+use alloc::vec::Vec;
+use indexmap::IndexMap;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::constants;
+use crate::leb128::write::uleb128_size;
+use crate::write::{AttributeValue, Result, UnitEntryId, UnitId, UnitTable};
+
+/// An estimate of the encoded size of the entries in a `UnitTable`.
+///
+/// This can be used to find the tags and attributes that contribute the most
+/// to the size of the `.debug_info` section, and the attribute values that
+/// could be encoded more compactly.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SizeProfile {
+    /// The sizes of the entries for each tag, in decreasing order of size.
+    pub tags: Vec<TagSize>,
+
+    /// The sizes of the attributes for each combination of tag, attribute
+    /// name, and form, in decreasing order of size.
+    pub attributes: Vec<AttributeSize>,
+
+    /// The attribute values that could be encoded more compactly, in
+    /// decreasing order of the number of bytes that would be saved.
+    pub warnings: Vec<SizeWarning>,
+}
+
+impl SizeProfile {
+    /// Estimate the encoded size of the entries in `units`.
+    ///
+    /// This does not write or modify the units. The sizes are estimates:
+    /// attributes that are only added when writing, such as `DW_AT_stmt_list`,
+    /// are not included, and writing may change the order of the entries.
+    /// Units that were copied verbatim are skipped.
+    pub fn new(units: &UnitTable) -> Result<Self> {
+        let mut tags = BTreeMap::new();
+        let mut attributes = BTreeMap::new();
+        let mut warnings = Vec::new();
+        // The number of occurrences of each inline string, and the total size
+        // of the `DW_FORM_strp` references that could replace them.
+        let mut strings = IndexMap::<&[u8], (usize, usize)>::new();
+
+        for index in 0..units.count() {
+            let unit_id = units.id(index);
+            let unit = units.get(unit_id);
+            let word_size = unit.format().word_size() as usize;
+            unit.estimate_sizes(|entry, entry_size, attrs| {
+                let tag = entry.tag();
+                let mut size = entry_size;
+                for &(name, form, attr_size) in attrs {
+                    size += attr_size;
+                    let total = attributes
+                        .entry((tag, name, form))
+                        .or_insert(AttributeSize {
+                            tag,
+                            name,
+                            form,
+                            count: 0,
+                            size: 0,
+                        });
+                    total.count += 1;
+                    total.size += attr_size;
+
+                    match entry.get(name) {
+                        Some(AttributeValue::String(string)) => {
+                            let total = strings.entry(string).or_insert((0, 0));
+                            total.0 += 1;
+                            total.1 += word_size;
+                        }
+                        Some(value) => {
+                            if let Some((suggested, saved)) =
+                                smaller_constant_form(name, value, form, attr_size, unit.version())
+                            {
+                                warnings.push(SizeWarning::OversizedConstant {
+                                    unit: unit_id,
+                                    entry: entry.id(),
+                                    name,
+                                    form,
+                                    suggested,
+                                    saved,
+                                });
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                let total = tags.entry(tag).or_insert(TagSize {
+                    tag,
+                    count: 0,
+                    size: 0,
+                });
+                total.count += 1;
+                total.size += size;
+            })?;
+        }
+
+        for (string, (count, strp_size)) in strings {
+            if count < 2 {
+                continue;
+            }
+            // Each inline copy is replaced by a reference, and the string is
+            // stored once in `.debug_str`.
+            let inline_size = count * (string.len() + 1);
+            let strp_size = strp_size + string.len() + 1;
+            if inline_size > strp_size {
+                warnings.push(SizeWarning::DuplicateString {
+                    string: string.to_vec(),
+                    count,
+                    saved: inline_size - strp_size,
+                });
+            }
+        }
+
+        let mut tags = tags.into_values().collect::<Vec<_>>();
+        tags.sort_by_key(|tag| Reverse(tag.size));
+        let mut attributes = attributes.into_values().collect::<Vec<_>>();
+        attributes.sort_by_key(|attribute| Reverse(attribute.size));
+        warnings.sort_by_key(|warning| Reverse(warning.saved()));
+        Ok(SizeProfile {
+            tags,
+            attributes,
+            warnings,
+        })
+    }
+
+    /// Return the total estimated size of the entries.
+    ///
+    /// This does not include the unit headers.
+    pub fn size(&self) -> usize {
+        self.tags.iter().map(|tag| tag.size).sum()
+    }
+}
+
+/// The estimated size of the entries with a given tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagSize {
+    /// The tag of the entries.
+    pub tag: constants::DwTag,
+    /// The number of entries.
+    pub count: usize,
+    /// The total size of the entries, including their abbreviation codes
+    /// and attributes.
+    pub size: usize,
+}
+
+/// The estimated size of the attributes with a given tag, name, and form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeSize {
+    /// The tag of the entries containing the attributes.
+    pub tag: constants::DwTag,
+    /// The name of the attributes.
+    pub name: constants::DwAt,
+    /// The form used to encode the attributes.
+    pub form: constants::DwForm,
+    /// The number of attributes.
+    pub count: usize,
+    /// The total size of the attribute values.
+    pub size: usize,
+}
+
+/// An attribute value that could be encoded more compactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SizeWarning {
+    /// A constant is encoded using a form that is larger than needed.
+    ///
+    /// The suggested form treats the constant as unsigned. It can be chosen
+    /// by using a different `AttributeValue` variant, or with a `FormSelector`.
+    /// Attributes for which the form may determine whether the constant is
+    /// signed, such as `DW_AT_const_value`, are not reported.
+    OversizedConstant {
+        /// The unit containing the entry.
+        unit: UnitId,
+        /// The entry containing the attribute.
+        entry: UnitEntryId,
+        /// The name of the attribute.
+        name: constants::DwAt,
+        /// The form that is used.
+        form: constants::DwForm,
+        /// The smallest form that can encode the value.
+        suggested: constants::DwForm,
+        /// The number of bytes that would be saved.
+        saved: usize,
+    },
+
+    /// The same string is stored inline in more than one attribute.
+    ///
+    /// Using `AttributeValue::StringRef` would store the string once in the
+    /// `.debug_str` section.
+    DuplicateString {
+        /// The string.
+        string: Vec<u8>,
+        /// The number of attributes that contain the string.
+        count: usize,
+        /// The number of bytes that would be saved.
+        saved: usize,
+    },
+}
+
+impl SizeWarning {
+    /// Return the number of bytes that would be saved.
+    pub fn saved(&self) -> usize {
+        match *self {
+            SizeWarning::OversizedConstant { saved, .. }
+            | SizeWarning::DuplicateString { saved, .. } => saved,
+        }
+    }
+}
+
+/// Return the smallest form that can encode a constant value and the number
+/// of bytes that using it would save, if it is smaller than `size`.
+fn smaller_constant_form(
+    name: constants::DwAt,
+    value: &AttributeValue,
+    form: constants::DwForm,
+    size: usize,
+    version: u16,
+) -> Option<(constants::DwForm, usize)> {
+    // The candidate forms, their sizes, and the maximum value they can encode.
+    // `DW_FORM_udata` is handled separately because its size depends on the value.
+    const CANDIDATES: &[(constants::DwForm, usize, u64)] = &[
+        (constants::DW_FORM_data1, 1, u8::MAX as u64),
+        (constants::DW_FORM_data2, 2, u16::MAX as u64),
+        (constants::DW_FORM_data4, 4, u32::MAX as u64),
+    ];
+
+    // For these attributes, a consumer may use the size of a `DW_FORM_data*`
+    // form to determine whether the value is signed, so changing the form
+    // may change the value.
+    match name {
+        constants::DW_AT_const_value
+        | constants::DW_AT_discr_value
+        | constants::DW_AT_lower_bound
+        | constants::DW_AT_upper_bound => return None,
+        _ => {}
+    }
+    // For version <= 3, `DW_FORM_data4` and `DW_FORM_data8` may be section
+    // offsets, so don't suggest replacing them or replacing them with them.
+    let offset_forms = version <= 3;
+    match form {
+        constants::DW_FORM_data1 | constants::DW_FORM_data2 | constants::DW_FORM_udata => {}
+        constants::DW_FORM_data4 | constants::DW_FORM_data8 if !offset_forms => {}
+        _ => return None,
+    }
+    let value = u64::try_from(value.constant_value()?).ok()?;
+    let candidates = if offset_forms {
+        &CANDIDATES[..2]
+    } else {
+        CANDIDATES
+    };
+    let (suggested, suggested_size) = candidates
+        .iter()
+        .filter(|&&(_, _, max)| value <= max)
+        .map(|&(form, size, _)| (form, size))
+        .chain(Some((constants::DW_FORM_udata, uleb128_size(value))))
+        .min_by_key(|&(_, size)| size)?;
+    if suggested_size < size {
+        Some((suggested, size - suggested_size))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::write::{
+        DebugLineStrOffsets, DebugStrOffsets, EndianVec, LineProgram, Sections, Unit,
+    };
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_size_profile() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = UnitTable::default();
+        let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_name,
+            AttributeValue::String(b"file.c".to_vec()),
+        );
+        let mut variables = Vec::new();
+        for _ in 0..3 {
+            let id = unit.add(root, constants::DW_TAG_variable);
+            let entry = unit.get_mut(id);
+            entry.set(
+                constants::DW_AT_name,
+                AttributeValue::String(b"variable".to_vec()),
+            );
+            entry.set(constants::DW_AT_decl_line, AttributeValue::Data8(5));
+            variables.push(id);
+        }
+        let id = unit.add(root, constants::DW_TAG_base_type);
+        unit.get_mut(id)
+            .set(constants::DW_AT_byte_size, AttributeValue::Data1(4));
+
+        let profile = SizeProfile::new(&units).unwrap();
+        assert_eq!(
+            profile.tags,
+            [
+                TagSize {
+                    tag: constants::DW_TAG_variable,
+                    count: 3,
+                    size: 3 * (1 + 9 + 8),
+                },
+                TagSize {
+                    tag: constants::DW_TAG_compile_unit,
+                    count: 1,
+                    size: 1 + 7 + 1,
+                },
+                TagSize {
+                    tag: constants::DW_TAG_base_type,
+                    count: 1,
+                    size: 1 + 1,
+                },
+            ]
+        );
+        assert_eq!(
+            profile.attributes[0],
+            AttributeSize {
+                tag: constants::DW_TAG_variable,
+                name: constants::DW_AT_name,
+                form: constants::DW_FORM_string,
+                count: 3,
+                size: 27,
+            }
+        );
+        assert_eq!(
+            profile.attributes[1],
+            AttributeSize {
+                tag: constants::DW_TAG_variable,
+                name: constants::DW_AT_decl_line,
+                form: constants::DW_FORM_data8,
+                count: 3,
+                size: 24,
+            }
+        );
+        assert_eq!(profile.attributes.len(), 4);
+
+        let mut warnings = Vec::new();
+        for &entry in &variables {
+            warnings.push(SizeWarning::OversizedConstant {
+                unit: unit_id,
+                entry,
+                name: constants::DW_AT_decl_line,
+                form: constants::DW_FORM_data8,
+                suggested: constants::DW_FORM_data1,
+                saved: 7,
+            });
+        }
+        warnings.push(SizeWarning::DuplicateString {
+            string: b"variable".to_vec(),
+            count: 3,
+            saved: 27 - (3 * 4 + 9),
+        });
+        assert_eq!(profile.warnings, warnings);
+
+        // The size of the form may determine the signedness of these values.
+        let value = AttributeValue::Data8(5);
+        assert_eq!(
+            smaller_constant_form(
+                constants::DW_AT_decl_line,
+                &value,
+                constants::DW_FORM_data8,
+                8,
+                4
+            ),
+            Some((constants::DW_FORM_data1, 7))
+        );
+        assert_eq!(
+            smaller_constant_form(
+                constants::DW_AT_const_value,
+                &value,
+                constants::DW_FORM_data8,
+                8,
+                4
+            ),
+            None
+        );
+
+        // The estimate matches the size of the written entries.
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let offsets = units
+            .write(
+                &mut sections,
+                &DebugLineStrOffsets::none(),
+                &DebugStrOffsets::none(),
+            )
+            .unwrap();
+        let header_size = 11;
+        assert_eq!(profile.size(), offsets.unit_length(unit_id) - header_size);
+    }
+}
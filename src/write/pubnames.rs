@@ -0,0 +1,323 @@
+use alloc::vec::Vec;
+use std::ops::{Deref, DerefMut};
+
+use crate::common::{DebugPubNamesOffset, DebugPubTypesOffset, SectionId};
+use crate::constants;
+use crate::write::{
+    AttributeValue, DebugInfoOffsets, DebuggingInformationEntry, Error, Result, Section,
+    StringTable, Unit, UnitEntryId, UnitTable, Writer,
+};
+
+define_section!(
+    DebugPubNames,
+    DebugPubNamesOffset,
+    "A writable `.debug_pubnames` section."
+);
+
+define_section!(
+    DebugPubTypes,
+    DebugPubTypesOffset,
+    "A writable `.debug_pubtypes` section."
+);
+
+impl<W: Writer> DebugPubNames<W> {
+    /// Write a table of the names of global objects and functions for each
+    /// compilation unit in `units`.
+    ///
+    /// This includes the subprograms and variables that have a
+    /// `DW_AT_external` attribute and are not declarations. Only entries that
+    /// are children of the root entry or of a namespace are included, and their
+    /// names are qualified by the names of their enclosing namespaces.
+    /// Partial units are skipped.
+    ///
+    /// Returns `Error::UnsupportedVerbatimUnit` if any unit was copied
+    /// verbatim, since its entries are not available.
+    ///
+    /// `debug_info_offsets` must be the offsets returned when writing `units`.
+    pub fn write_units(
+        &mut self,
+        units: &UnitTable,
+        strings: &StringTable,
+        debug_info_offsets: &DebugInfoOffsets,
+    ) -> Result<()> {
+        write_units(
+            &mut self.0,
+            units,
+            strings,
+            debug_info_offsets,
+            |entry| match entry.tag() {
+                constants::DW_TAG_subprogram | constants::DW_TAG_variable => {
+                    flag(entry, constants::DW_AT_external)
+                        && !flag(entry, constants::DW_AT_declaration)
+                }
+                _ => false,
+            },
+        )
+    }
+}
+
+impl<W: Writer> DebugPubTypes<W> {
+    /// Write a table of the names of global types for each compilation unit
+    /// in `units`.
+    ///
+    /// This includes the type definitions that are not declarations. Only
+    /// entries that are children of the root entry or of a namespace are
+    /// included, and their names are qualified by the names of their
+    /// enclosing namespaces. Partial units are skipped.
+    ///
+    /// Returns `Error::UnsupportedVerbatimUnit` if any unit was copied
+    /// verbatim, since its entries are not available.
+    ///
+    /// `debug_info_offsets` must be the offsets returned when writing `units`.
+    pub fn write_units(
+        &mut self,
+        units: &UnitTable,
+        strings: &StringTable,
+        debug_info_offsets: &DebugInfoOffsets,
+    ) -> Result<()> {
+        write_units(
+            &mut self.0,
+            units,
+            strings,
+            debug_info_offsets,
+            |entry| match entry.tag() {
+                constants::DW_TAG_base_type
+                | constants::DW_TAG_class_type
+                | constants::DW_TAG_enumeration_type
+                | constants::DW_TAG_structure_type
+                | constants::DW_TAG_typedef
+                | constants::DW_TAG_union_type
+                | constants::DW_TAG_unspecified_type => !flag(entry, constants::DW_AT_declaration),
+                _ => false,
+            },
+        )
+    }
+}
+
+fn flag(entry: &DebuggingInformationEntry, name: constants::DwAt) -> bool {
+    matches!(
+        entry.get(name),
+        Some(AttributeValue::Flag(true)) | Some(AttributeValue::FlagPresent)
+    )
+}
+
+fn name<'a>(entry: &'a DebuggingInformationEntry, strings: &'a StringTable) -> Option<&'a [u8]> {
+    match entry.get(constants::DW_AT_name) {
+        Some(AttributeValue::String(bytes)) => Some(bytes),
+        Some(AttributeValue::StringRef(id)) => Some(strings.get(*id)),
+        _ => None,
+    }
+}
+
+/// Write a `.debug_pubnames` or `.debug_pubtypes` section for the entries
+/// for which `include` returns true.
+fn write_units<W, F>(
+    w: &mut W,
+    units: &UnitTable,
+    strings: &StringTable,
+    debug_info_offsets: &DebugInfoOffsets,
+    include: F,
+) -> Result<()>
+where
+    W: Writer,
+    F: Fn(&DebuggingInformationEntry) -> bool,
+{
+    for index in 0..units.count() {
+        let unit_id = units.id(index);
+        let unit = units.get(unit_id);
+        if unit.is_verbatim() {
+            return Err(Error::UnsupportedVerbatimUnit);
+        }
+        if unit.is_partial() {
+            continue;
+        }
+        let unit_offset = debug_info_offsets.unit(unit_id).0;
+        let mut names = Vec::new();
+        add_names(unit, unit.root(), &[], strings, &include, &mut names);
+        if names.is_empty() {
+            continue;
+        }
+
+        let format = unit.format();
+        let length_offset = w.write_initial_length(format)?;
+        let length_base = w.len();
+        w.write_u16(2)?;
+        w.write_offset(unit_offset, SectionId::DebugInfo, format.word_size())?;
+        w.write_udata(
+            debug_info_offsets.unit_length(unit_id) as u64,
+            format.word_size(),
+        )?;
+        for (entry, name) in names {
+            let offset = debug_info_offsets.entry(unit_id, entry).0 - unit_offset;
+            w.write_udata(offset as u64, format.word_size())?;
+            w.write(&name)?;
+            w.write_u8(0)?;
+        }
+        w.write_udata(0, format.word_size())?;
+        let length = (w.len() - length_base) as u64;
+        w.write_initial_length_at(length_offset, length, format)?;
+    }
+    Ok(())
+}
+
+/// Add the names of the children of `parent`, and of the children of any
+/// namespaces within it.
+fn add_names<F>(
+    unit: &Unit,
+    parent: UnitEntryId,
+    prefix: &[u8],
+    strings: &StringTable,
+    include: &F,
+    names: &mut Vec<(UnitEntryId, Vec<u8>)>,
+) where
+    F: Fn(&DebuggingInformationEntry) -> bool,
+{
+    for &child in unit.get(parent).children() {
+        let entry = unit.get(child);
+        if entry.tag() == constants::DW_TAG_namespace {
+            let mut prefix = prefix.to_vec();
+            prefix.extend_from_slice(name(entry, strings).unwrap_or(b"(anonymous namespace)"));
+            prefix.extend_from_slice(b"::");
+            add_names(unit, child, &prefix, strings, include, names);
+        } else if include(entry) {
+            if let Some(name) = name(entry, strings) {
+                let mut qualified = prefix.to_vec();
+                qualified.extend_from_slice(name);
+                names.push((child, qualified));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "read")]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::read;
+    use crate::write::{DebugLineStrOffsets, EndianVec, LineProgram, Sections};
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_write_units() {
+        for format in [Format::Dwarf32, Format::Dwarf64] {
+            let encoding = Encoding {
+                format,
+                version: 4,
+                address_size: 8,
+            };
+            let mut strings = StringTable::default();
+            let mut units = UnitTable::default();
+            let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = units.get_mut(unit_id);
+            let root = unit.root();
+
+            let mut add = |unit: &mut Unit, parent, tag, name: &str, attrs: &[constants::DwAt]| {
+                let id = unit.add(parent, tag);
+                let entry = unit.get_mut(id);
+                entry.set(
+                    constants::DW_AT_name,
+                    AttributeValue::StringRef(strings.add(name)),
+                );
+                for attr in attrs {
+                    entry.set(*attr, AttributeValue::FlagPresent);
+                }
+                id
+            };
+            let function = add(
+                unit,
+                root,
+                constants::DW_TAG_subprogram,
+                "function",
+                &[constants::DW_AT_external],
+            );
+            add(
+                unit,
+                function,
+                constants::DW_TAG_variable,
+                "local",
+                &[constants::DW_AT_external],
+            );
+            add(unit, root, constants::DW_TAG_subprogram, "static", &[]);
+            add(
+                unit,
+                root,
+                constants::DW_TAG_variable,
+                "declaration",
+                &[constants::DW_AT_external, constants::DW_AT_declaration],
+            );
+            let namespace = add(unit, root, constants::DW_TAG_namespace, "ns", &[]);
+            let variable = add(
+                unit,
+                namespace,
+                constants::DW_TAG_variable,
+                "variable",
+                &[constants::DW_AT_external],
+            );
+            let base_type = add(unit, root, constants::DW_TAG_base_type, "int", &[]);
+            let structure = add(unit, namespace, constants::DW_TAG_structure_type, "S", &[]);
+            add(
+                unit,
+                root,
+                constants::DW_TAG_structure_type,
+                "D",
+                &[constants::DW_AT_declaration],
+            );
+
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            let debug_str_offsets = strings.write(&mut sections.debug_str).unwrap();
+            let offsets = units
+                .write(
+                    &mut sections,
+                    &DebugLineStrOffsets::none(),
+                    &debug_str_offsets,
+                )
+                .unwrap();
+            let mut debug_pubnames = DebugPubNames::from(EndianVec::new(LittleEndian));
+            debug_pubnames
+                .write_units(&units, &strings, &offsets)
+                .unwrap();
+            let mut debug_pubtypes = DebugPubTypes::from(EndianVec::new(LittleEndian));
+            debug_pubtypes
+                .write_units(&units, &strings, &offsets)
+                .unwrap();
+
+            let read_pubnames = read::DebugPubNames::new(debug_pubnames.slice(), LittleEndian);
+            let mut items = read_pubnames.items();
+            let mut read_names = Vec::new();
+            while let Some(item) = items.next().unwrap() {
+                read_names.push((
+                    item.name().slice(),
+                    item.die_offset(),
+                    item.unit_header_offset(),
+                ));
+            }
+            let unit_offset = offsets.unit(unit_id);
+            let die_offset =
+                |entry| read::UnitOffset(offsets.entry(unit_id, entry).0 - unit_offset.0);
+            let set = read_pubnames.sets().next().unwrap().unwrap();
+            assert_eq!(set.unit_length(), offsets.unit_length(unit_id));
+            assert_eq!(
+                read_names,
+                [
+                    (&b"function"[..], die_offset(function), unit_offset),
+                    (&b"ns::variable"[..], die_offset(variable), unit_offset),
+                ]
+            );
+
+            let read_pubtypes = read::DebugPubTypes::new(debug_pubtypes.slice(), LittleEndian);
+            let mut items = read_pubtypes.items();
+            let mut read_types = Vec::new();
+            while let Some(item) = items.next().unwrap() {
+                read_types.push((item.name().slice(), item.die_offset()));
+            }
+            assert_eq!(
+                read_types,
+                [
+                    (&b"int"[..], die_offset(base_type)),
+                    (&b"ns::S"[..], die_offset(structure)),
+                ]
+            );
+        }
+    }
+}
@@ -3,7 +3,10 @@ use indexmap::IndexSet;
 use std::ops::{Deref, DerefMut};
 
 use crate::common::{Encoding, RangeListsOffset, SectionId};
-use crate::write::{Address, BaseId, Error, Result, Section, Sections, Writer};
+use crate::write::{
+    index_set_allocated_bytes, vec_allocated_bytes, Address, BaseId, Error, Result, Section,
+    Sections, Writer,
+};
 
 define_section!(
     DebugRanges,
@@ -26,6 +29,16 @@ define_id!(
     "An identifier for a range list in a `RangeListTable`."
 );
 
+impl RangeListId {
+    /// Return the index of the range list within its table.
+    ///
+    /// This is the index used by `DW_FORM_rnglistx`.
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        self.index
+    }
+}
+
 /// A table of range lists that will be stored in a `.debug_ranges` or `.debug_rnglists` section.
 #[derive(Debug, Default)]
 pub struct RangeListTable {
@@ -40,11 +53,36 @@ impl RangeListTable {
         RangeListId::new(self.base_id, index)
     }
 
+    /// Get a reference to a range list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is invalid.
+    pub(crate) fn get(&self, id: RangeListId) -> &RangeList {
+        debug_assert_eq!(self.base_id, id.base_id);
+        &self.ranges[id.index]
+    }
+
+    /// Return an estimate of the number of bytes of heap memory used by the table.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        index_set_allocated_bytes(&self.ranges)
+            + self
+                .ranges
+                .iter()
+                .map(|range_list| vec_allocated_bytes(&range_list.0))
+                .sum::<usize>()
+    }
+
     /// Write the range list table to the appropriate section for the given DWARF version.
+    ///
+    /// If `offset_entries` is true, then the table includes an offset array so that
+    /// the range lists can be referenced using `DW_FORM_rnglistx`.
+    /// This is ignored for versions before 5.
     pub(crate) fn write<W: Writer>(
         &self,
         sections: &mut Sections<W>,
         encoding: Encoding,
+        offset_entries: bool,
     ) -> Result<RangeListOffsets> {
         if self.ranges.is_empty() {
             return Ok(RangeListOffsets::none());
@@ -52,7 +90,7 @@ impl RangeListTable {
 
         match encoding.version {
             2..=4 => self.write_ranges(&mut sections.debug_ranges, encoding.address_size),
-            5 => self.write_rnglists(&mut sections.debug_rnglists, encoding),
+            5 => self.write_rnglists(&mut sections.debug_rnglists, encoding, offset_entries),
             _ => Err(Error::UnsupportedVersion(encoding.version)),
         }
     }
@@ -120,6 +158,7 @@ impl RangeListTable {
         &self,
         w: &mut DebugRngLists<W>,
         encoding: Encoding,
+        offset_entries: bool,
     ) -> Result<RangeListOffsets> {
         let mut offsets = Vec::new();
 
@@ -133,10 +172,23 @@ impl RangeListTable {
         w.write_u16(encoding.version)?;
         w.write_u8(encoding.address_size)?;
         w.write_u8(0)?; // segment_selector_size
-        w.write_u32(0)?; // offset_entry_count (when set to zero DW_FORM_rnglistx can't be used, see section 7.28)
-                         // FIXME implement DW_FORM_rnglistx writing and implement the offset entry list
+        let offset_entry_count = if offset_entries { self.ranges.len() } else { 0 };
+        w.write_u32(offset_entry_count as u32)?;
+        let offset_entries_base = w.len();
+        let offset_size = encoding.format.word_size();
+        for _ in 0..offset_entry_count {
+            w.write_udata(0, offset_size)?;
+        }
 
-        for range_list in self.ranges.iter() {
+        for (i, range_list) in self.ranges.iter().enumerate() {
+            if offset_entries {
+                let offset = (w.len() - offset_entries_base) as u64;
+                w.write_udata_at(
+                    offset_entries_base + i * offset_size as usize,
+                    offset,
+                    offset_size,
+                )?;
+            }
             offsets.push(w.offset());
             for range in &range_list.0 {
                 match *range {
@@ -179,6 +231,40 @@ impl RangeListTable {
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct RangeList(pub Vec<Range>);
 
+impl RangeList {
+    /// Return a copy of the range list without the ranges that begin at an
+    /// address for which `live` returns false.
+    ///
+    /// `base` is the initial base address for offset pairs.
+    /// The returned list is empty if no ranges remain.
+    pub(crate) fn discard_addresses<F>(&self, mut base: Option<Address>, live: &mut F) -> RangeList
+    where
+        F: FnMut(Address) -> bool,
+    {
+        let mut ranges = Vec::new();
+        let mut empty = true;
+        for range in &self.0 {
+            let begin = match *range {
+                Range::BaseAddress { address } => {
+                    base = Some(address);
+                    ranges.push(range.clone());
+                    continue;
+                }
+                Range::OffsetPair { begin, .. } => base.map(|base| base.add(begin)),
+                Range::StartEnd { begin, .. } | Range::StartLength { begin, .. } => Some(begin),
+            };
+            if begin.map_or(true, &mut *live) {
+                ranges.push(range.clone());
+                empty = false;
+            }
+        }
+        if empty {
+            ranges.clear();
+        }
+        RangeList(ranges)
+    }
+}
+
 /// A single range.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Range {
@@ -223,7 +309,12 @@ mod convert {
             mut from: read::RawRngListIter<R>,
             context: &ConvertUnitContext<'_, R>,
         ) -> ConvertResult<Self> {
-            let mut have_base_address = context.base_address != Address::Constant(0);
+            // Check the unconverted address, since a zero base address may be
+            // converted to a non-zero address.
+            let mut have_base_address = match context.base_address {
+                Address::Constant(_) => context.unit.low_pc != 0,
+                Address::Symbol { .. } => true,
+            };
             let convert_address =
                 |x| (context.convert_address)(x).ok_or(ConvertError::InvalidAddress);
             let mut ranges = Vec::new();
@@ -231,11 +322,15 @@ mod convert {
                 let range = match from_range {
                     read::RawRngListEntry::AddressOrOffsetPair { begin, end } => {
                         // These were parsed as addresses, even if they are offsets.
+                        let begin_offset = begin;
+                        let end_offset = end;
                         let begin = convert_address(begin)?;
                         let end = convert_address(end)?;
                         match (begin, end) {
-                            (Address::Constant(begin_offset), Address::Constant(end_offset)) => {
+                            (Address::Constant(_), Address::Constant(_)) => {
                                 if have_base_address {
+                                    // The base address has already been converted,
+                                    // so use the unconverted offsets.
                                     Range::OffsetPair {
                                         begin: begin_offset,
                                         end: end_offset,
@@ -323,7 +418,7 @@ mod tests {
         let mut strings = StringTable::default();
 
         for &version in &[2, 3, 4, 5] {
-            for &address_size in &[4, 8] {
+            for &address_size in &[2, 3, 4, 6, 8] {
                 for &format in &[Format::Dwarf32, Format::Dwarf64] {
                     let encoding = Encoding {
                         format,
@@ -353,7 +448,7 @@ mod tests {
                     let range_list_id = ranges.add(range_list.clone());
 
                     let mut sections = Sections::new(EndianVec::new(LittleEndian));
-                    let range_list_offsets = ranges.write(&mut sections, encoding).unwrap();
+                    let range_list_offsets = ranges.write(&mut sections, encoding, false).unwrap();
 
                     let read_debug_ranges =
                         read::DebugRanges::new(sections.debug_ranges.slice(), LittleEndian);
@@ -379,6 +474,7 @@ mod tests {
                         abbreviations: Arc::new(read::Abbreviations::default()),
                         name: None,
                         comp_dir: None,
+                        language: None,
                         low_pc: 0,
                         str_offsets_base: DebugStrOffsetsBase(0),
                         addr_base: DebugAddrBase(0),
@@ -398,6 +494,8 @@ mod tests {
                         base_address: Address::Constant(0),
                         line_program_offset: None,
                         line_program_files: Vec::new(),
+                        line_programs: None,
+                        raw_expressions: false,
                         entry_ids: &HashMap::new(),
                     };
                     let convert_range_list = RangeList::from(read_range_list, &context).unwrap();
@@ -142,10 +142,13 @@ impl<T: RelocateWriter> Writer for T {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::write::EndianVec;
-    use crate::{LittleEndian, SectionId};
+    use crate::write::{
+        AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Range, RangeList, Sections,
+    };
+    use crate::{Encoding, Format, LineEncoding, LittleEndian, SectionId};
     use alloc::vec::Vec;
 
+    #[derive(Clone)]
     struct Section {
         writer: EndianVec<LittleEndian>,
         relocations: Vec<Relocation>,
@@ -277,4 +280,73 @@ mod tests {
         assert_eq!(section.writer.into_vec(), expected_data);
         assert_eq!(section.relocations, expected_relocations);
     }
+
+    #[test]
+    fn test_relocate_sections() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"dir".to_vec()),
+            LineString::String(b"file".to_vec()),
+            None,
+        );
+        program.begin_sequence(Some(Address::Symbol {
+            symbol: 1,
+            addend: 0,
+        }));
+        program.generate_row();
+        program.end_sequence(0x10);
+
+        let mut dwarf = DwarfUnit::new(encoding);
+        dwarf.unit.line_program = program;
+        let root = dwarf.unit.root();
+        dwarf.unit.get_mut(root).set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Symbol {
+                symbol: 1,
+                addend: 0,
+            }),
+        );
+        let range_list_id = dwarf.unit.ranges.add(RangeList(vec![Range::StartLength {
+            begin: Address::Symbol {
+                symbol: 2,
+                addend: 4,
+            },
+            length: 0x10,
+        }]));
+        let subprogram = dwarf.unit.add(root, constants::DW_TAG_subprogram);
+        dwarf.unit.get_mut(subprogram).set(
+            constants::DW_AT_ranges,
+            AttributeValue::RangeListRef(range_list_id),
+        );
+
+        let mut sections = Sections::new(Section {
+            writer: EndianVec::new(LittleEndian),
+            relocations: Vec::new(),
+        });
+        dwarf.write(&mut sections).unwrap();
+
+        let targets = |section: &Section| {
+            section
+                .relocations
+                .iter()
+                .map(|relocation| (relocation.target, relocation.addend))
+                .collect::<Vec<_>>()
+        };
+        let debug_info = targets(&sections.debug_info);
+        assert!(debug_info.contains(&(RelocationTarget::Section(SectionId::DebugAbbrev), 0)));
+        assert!(debug_info.contains(&(RelocationTarget::Section(SectionId::DebugLine), 0)));
+        assert!(debug_info.contains(&(RelocationTarget::Symbol(1), 0)));
+        assert!(debug_info.contains(&(RelocationTarget::Section(SectionId::DebugRngLists), 12)));
+        assert!(targets(&sections.debug_line).contains(&(RelocationTarget::Symbol(1), 0)));
+        assert_eq!(
+            targets(&sections.debug_rnglists),
+            vec![(RelocationTarget::Symbol(2), 4)]
+        );
+    }
 }
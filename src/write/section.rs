@@ -4,12 +4,16 @@ use std::vec::Vec;
 
 use crate::common::SectionId;
 use crate::write::{
-    DebugAbbrev, DebugFrame, DebugInfo, DebugInfoReference, DebugLine, DebugLineStr, DebugLoc,
-    DebugLocLists, DebugRanges, DebugRngLists, DebugStr, EhFrame, Writer,
+    DebugAbbrev, DebugAddr, DebugAranges, DebugFrame, DebugInfo, DebugInfoReference, DebugLine,
+    DebugLineReference, DebugLineStr, DebugLoc, DebugLocLists, DebugMacro, DebugPubNames,
+    DebugPubTypes, DebugRanges, DebugRngLists, DebugStr, DebugStrOffsetsSection, EhFrame, Writer,
 };
 
 macro_rules! define_section {
     ($name:ident, $offset:ident, $docs:expr) => {
+        define_section!($name, $name, $offset, $docs);
+    };
+    ($name:ident, $id:ident, $offset:ident, $docs:expr) => {
         #[doc=$docs]
         #[derive(Debug, Default)]
         pub struct $name<W: Writer>(pub W);
@@ -47,7 +51,7 @@ macro_rules! define_section {
         impl<W: Writer> Section<W> for $name<W> {
             #[inline]
             fn id(&self) -> SectionId {
-                SectionId::$name
+                SectionId::$id
             }
         }
     };
@@ -65,16 +69,54 @@ pub trait Section<W: Writer>: DerefMut<Target = W> {
 }
 
 /// All of the writable DWARF sections.
+///
+/// This is typically used with [`Dwarf::write`](crate::write::Dwarf::write), which
+/// writes all sections in the correct order. The resulting section data can then
+/// be added to an object file using [`Sections::for_each`]. If the writer is a
+/// [`RelocateWriter`](crate::write::RelocateWriter), then it will also contain the
+/// relocations for each section.
+///
+/// ```
+/// use gimli::write::{Dwarf, EndianVec, Sections};
+/// use gimli::LittleEndian;
+///
+/// # fn f() -> gimli::write::Result<()> {
+/// let mut dwarf = Dwarf::new();
+/// // Add units, line programs, and strings to `dwarf` here...
+///
+/// let mut sections = Sections::new(EndianVec::new(LittleEndian));
+/// dwarf.write(&mut sections)?;
+/// sections.for_each(|id, data| -> gimli::write::Result<()> {
+///     if !data.slice().is_empty() {
+///         // Add the section to the object file here...
+/// #       let _ = id.name();
+///     }
+///     Ok(())
+/// })?;
+/// # Ok(())
+/// # }
+/// # f().unwrap();
+/// ```
 #[derive(Debug, Default)]
 pub struct Sections<W: Writer> {
     /// The `.debug_abbrev` section.
     pub debug_abbrev: DebugAbbrev<W>,
+    /// The `.debug_addr` section.
+    pub debug_addr: DebugAddr<W>,
+    /// The `.debug_aranges` section.
+    pub debug_aranges: DebugAranges<W>,
     /// The `.debug_info` section.
     pub debug_info: DebugInfo<W>,
     /// The `.debug_line` section.
     pub debug_line: DebugLine<W>,
     /// The `.debug_line_str` section.
     pub debug_line_str: DebugLineStr<W>,
+    /// The `.debug_macro` section.
+    pub debug_macro: DebugMacro<W>,
+    /// The `.debug_pubnames` section.
+    pub debug_pubnames: DebugPubNames<W>,
+    /// The `.debug_pubtypes` section.
+    pub debug_pubtypes: DebugPubTypes<W>,
     /// The `.debug_ranges` section.
     pub debug_ranges: DebugRanges<W>,
     /// The `.debug_rnglists` section.
@@ -85,12 +127,16 @@ pub struct Sections<W: Writer> {
     pub debug_loclists: DebugLocLists<W>,
     /// The `.debug_str` section.
     pub debug_str: DebugStr<W>,
+    /// The `.debug_str_offsets` section.
+    pub debug_str_offsets: DebugStrOffsetsSection<W>,
     /// The `.debug_frame` section.
     pub debug_frame: DebugFrame<W>,
     /// The `.eh_frame` section.
     pub eh_frame: EhFrame<W>,
     /// Unresolved references in the `.debug_info` section.
     pub(crate) debug_info_refs: Vec<DebugInfoReference>,
+    /// Unresolved `.debug_line` references in the `.debug_info` section.
+    pub(crate) debug_line_refs: Vec<DebugLineReference>,
     /// Unresolved references in the `.debug_loc` section.
     pub(crate) debug_loc_refs: Vec<DebugInfoReference>,
     /// Unresolved references in the `.debug_loclists` section.
@@ -102,17 +148,24 @@ impl<W: Writer + Clone> Sections<W> {
     pub fn new(section: W) -> Self {
         Sections {
             debug_abbrev: DebugAbbrev(section.clone()),
+            debug_addr: DebugAddr(section.clone()),
+            debug_aranges: DebugAranges(section.clone()),
             debug_info: DebugInfo(section.clone()),
             debug_line: DebugLine(section.clone()),
             debug_line_str: DebugLineStr(section.clone()),
+            debug_macro: DebugMacro(section.clone()),
+            debug_pubnames: DebugPubNames(section.clone()),
+            debug_pubtypes: DebugPubTypes(section.clone()),
             debug_ranges: DebugRanges(section.clone()),
             debug_rnglists: DebugRngLists(section.clone()),
             debug_loc: DebugLoc(section.clone()),
             debug_loclists: DebugLocLists(section.clone()),
             debug_str: DebugStr(section.clone()),
+            debug_str_offsets: DebugStrOffsetsSection(section.clone()),
             debug_frame: DebugFrame(section.clone()),
             eh_frame: EhFrame(section),
             debug_info_refs: Vec::new(),
+            debug_line_refs: Vec::new(),
             debug_loc_refs: Vec::new(),
             debug_loclists_refs: Vec::new(),
         }
@@ -120,18 +173,58 @@ impl<W: Writer + Clone> Sections<W> {
 }
 
 impl<W: Writer> Sections<W> {
+    /// Create a new `Sections` using the writer returned by `f` for each
+    /// section.
+    ///
+    /// This is useful for writers that cannot be cloned, such as a
+    /// [`StreamWriter`](crate::write::StreamWriter).
+    pub fn from_fn<F>(mut f: F) -> Self
+    where
+        F: FnMut(SectionId) -> W,
+    {
+        Sections {
+            debug_abbrev: DebugAbbrev(f(SectionId::DebugAbbrev)),
+            debug_addr: DebugAddr(f(SectionId::DebugAddr)),
+            debug_aranges: DebugAranges(f(SectionId::DebugAranges)),
+            debug_info: DebugInfo(f(SectionId::DebugInfo)),
+            debug_line: DebugLine(f(SectionId::DebugLine)),
+            debug_line_str: DebugLineStr(f(SectionId::DebugLineStr)),
+            debug_macro: DebugMacro(f(SectionId::DebugMacro)),
+            debug_pubnames: DebugPubNames(f(SectionId::DebugPubNames)),
+            debug_pubtypes: DebugPubTypes(f(SectionId::DebugPubTypes)),
+            debug_ranges: DebugRanges(f(SectionId::DebugRanges)),
+            debug_rnglists: DebugRngLists(f(SectionId::DebugRngLists)),
+            debug_loc: DebugLoc(f(SectionId::DebugLoc)),
+            debug_loclists: DebugLocLists(f(SectionId::DebugLocLists)),
+            debug_str: DebugStr(f(SectionId::DebugStr)),
+            debug_str_offsets: DebugStrOffsetsSection(f(SectionId::DebugStrOffsets)),
+            debug_frame: DebugFrame(f(SectionId::DebugFrame)),
+            eh_frame: EhFrame(f(SectionId::EhFrame)),
+            debug_info_refs: Vec::new(),
+            debug_line_refs: Vec::new(),
+            debug_loc_refs: Vec::new(),
+            debug_loclists_refs: Vec::new(),
+        }
+    }
+
     /// Get the section with the given `id`.
     pub fn get(&self, id: SectionId) -> Option<&W> {
         match id {
             SectionId::DebugAbbrev => Some(&self.debug_abbrev.0),
+            SectionId::DebugAddr => Some(&self.debug_addr.0),
+            SectionId::DebugAranges => Some(&self.debug_aranges.0),
             SectionId::DebugInfo => Some(&self.debug_info.0),
             SectionId::DebugLine => Some(&self.debug_line.0),
             SectionId::DebugLineStr => Some(&self.debug_line_str.0),
+            SectionId::DebugMacro => Some(&self.debug_macro.0),
+            SectionId::DebugPubNames => Some(&self.debug_pubnames.0),
+            SectionId::DebugPubTypes => Some(&self.debug_pubtypes.0),
             SectionId::DebugRanges => Some(&self.debug_ranges.0),
             SectionId::DebugRngLists => Some(&self.debug_rnglists.0),
             SectionId::DebugLoc => Some(&self.debug_loc.0),
             SectionId::DebugLocLists => Some(&self.debug_loclists.0),
             SectionId::DebugStr => Some(&self.debug_str.0),
+            SectionId::DebugStrOffsets => Some(&self.debug_str_offsets.0),
             SectionId::DebugFrame => Some(&self.debug_frame.0),
             SectionId::EhFrame => Some(&self.eh_frame.0),
             _ => None,
@@ -142,14 +235,20 @@ impl<W: Writer> Sections<W> {
     pub fn get_mut(&mut self, id: SectionId) -> Option<&mut W> {
         match id {
             SectionId::DebugAbbrev => Some(&mut self.debug_abbrev.0),
+            SectionId::DebugAddr => Some(&mut self.debug_addr.0),
+            SectionId::DebugAranges => Some(&mut self.debug_aranges.0),
             SectionId::DebugInfo => Some(&mut self.debug_info.0),
             SectionId::DebugLine => Some(&mut self.debug_line.0),
             SectionId::DebugLineStr => Some(&mut self.debug_line_str.0),
+            SectionId::DebugMacro => Some(&mut self.debug_macro.0),
+            SectionId::DebugPubNames => Some(&mut self.debug_pubnames.0),
+            SectionId::DebugPubTypes => Some(&mut self.debug_pubtypes.0),
             SectionId::DebugRanges => Some(&mut self.debug_ranges.0),
             SectionId::DebugRngLists => Some(&mut self.debug_rnglists.0),
             SectionId::DebugLoc => Some(&mut self.debug_loc.0),
             SectionId::DebugLocLists => Some(&mut self.debug_loclists.0),
             SectionId::DebugStr => Some(&mut self.debug_str.0),
+            SectionId::DebugStrOffsets => Some(&mut self.debug_str_offsets.0),
             SectionId::DebugFrame => Some(&mut self.debug_frame.0),
             SectionId::EhFrame => Some(&mut self.eh_frame.0),
             _ => None,
@@ -169,13 +268,19 @@ impl<W: Writer> Sections<W> {
         // Ordered so that earlier sections do not reference later sections.
         f!(self.debug_abbrev)?;
         f!(self.debug_str)?;
+        f!(self.debug_str_offsets)?;
         f!(self.debug_line_str)?;
         f!(self.debug_line)?;
+        f!(self.debug_macro)?;
         f!(self.debug_ranges)?;
         f!(self.debug_rnglists)?;
         f!(self.debug_loc)?;
         f!(self.debug_loclists)?;
+        f!(self.debug_addr)?;
         f!(self.debug_info)?;
+        f!(self.debug_aranges)?;
+        f!(self.debug_pubnames)?;
+        f!(self.debug_pubtypes)?;
         f!(self.debug_frame)?;
         f!(self.eh_frame)?;
         Ok(())
@@ -194,15 +299,36 @@ impl<W: Writer> Sections<W> {
         // Ordered so that earlier sections do not reference later sections.
         f!(self.debug_abbrev)?;
         f!(self.debug_str)?;
+        f!(self.debug_str_offsets)?;
         f!(self.debug_line_str)?;
         f!(self.debug_line)?;
+        f!(self.debug_macro)?;
         f!(self.debug_ranges)?;
         f!(self.debug_rnglists)?;
         f!(self.debug_loc)?;
         f!(self.debug_loclists)?;
+        f!(self.debug_addr)?;
         f!(self.debug_info)?;
+        f!(self.debug_aranges)?;
+        f!(self.debug_pubnames)?;
+        f!(self.debug_pubtypes)?;
         f!(self.debug_frame)?;
         f!(self.eh_frame)?;
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "read"))]
+impl<E: crate::Endianity> Sections<crate::write::EndianVec<E>> {
+    /// Load the written sections for reading.
+    pub(crate) fn read_dwarf(&self) -> crate::read::Dwarf<crate::read::EndianSlice<'_, E>> {
+        crate::read::Dwarf::load(|id| -> crate::read::Result<_> {
+            let data = self.get(id).map(|w| w.slice()).unwrap_or(&[]);
+            Ok(crate::read::EndianSlice::new(
+                data,
+                self.debug_info.0.endian(),
+            ))
+        })
+        .unwrap()
+    }
+}
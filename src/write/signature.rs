@@ -0,0 +1,545 @@
+use alloc::vec::Vec;
+use std::collections::HashMap;
+
+use crate::common::DebugTypeSignature;
+use crate::constants;
+use crate::endianity::Endianity;
+use crate::write::{
+    is_type_tag, AttributeValue, DebuggingInformationEntry, Dwarf, DwarfUnit, EndianVec, Error,
+    LineStringTable, Reference, Result, StringTable, Unit, UnitEntryId, UnitId, UnitTable, Writer,
+};
+
+impl Dwarf {
+    /// Compute the type signature of a type entry.
+    ///
+    /// This uses the algorithm in section 7.27 of the DWARF 4 standard,
+    /// and produces the same signatures as LLVM.
+    ///
+    /// `endian` is used for the encoding of expressions.
+    pub fn type_signature<E: Endianity>(
+        &self,
+        unit: UnitId,
+        entry: UnitEntryId,
+        endian: E,
+    ) -> Result<DebugTypeSignature> {
+        let mut hasher =
+            TypeSignature::new(Some(&self.units), &self.line_strings, &self.strings, endian);
+        hasher.compute(self.units.get(unit), Some(unit), entry)
+    }
+}
+
+impl DwarfUnit {
+    /// Compute the type signature of a type entry.
+    ///
+    /// See `Dwarf::type_signature`.
+    pub fn type_signature<E: Endianity>(
+        &self,
+        entry: UnitEntryId,
+        endian: E,
+    ) -> Result<DebugTypeSignature> {
+        let mut hasher = TypeSignature::new(None, &self.line_strings, &self.strings, endian);
+        hasher.compute(&self.unit, None, entry)
+    }
+}
+
+/// The attributes that are included in a type signature, in the order
+/// that they are added.
+///
+/// `DW_AT_type` is not part of this list in the standard, but LLVM adds it last.
+const SIGNATURE_ATTRIBUTES: [constants::DwAt; 49] = [
+    constants::DW_AT_name,
+    constants::DW_AT_accessibility,
+    constants::DW_AT_address_class,
+    constants::DW_AT_allocated,
+    constants::DW_AT_artificial,
+    constants::DW_AT_associated,
+    constants::DW_AT_binary_scale,
+    constants::DW_AT_bit_offset,
+    constants::DW_AT_bit_size,
+    constants::DW_AT_bit_stride,
+    constants::DW_AT_byte_size,
+    constants::DW_AT_byte_stride,
+    constants::DW_AT_const_expr,
+    constants::DW_AT_const_value,
+    constants::DW_AT_containing_type,
+    constants::DW_AT_count,
+    constants::DW_AT_data_bit_offset,
+    constants::DW_AT_data_location,
+    constants::DW_AT_data_member_location,
+    constants::DW_AT_decimal_scale,
+    constants::DW_AT_decimal_sign,
+    constants::DW_AT_default_value,
+    constants::DW_AT_digit_count,
+    constants::DW_AT_discr,
+    constants::DW_AT_discr_list,
+    constants::DW_AT_discr_value,
+    constants::DW_AT_encoding,
+    constants::DW_AT_enum_class,
+    constants::DW_AT_endianity,
+    constants::DW_AT_explicit,
+    constants::DW_AT_is_optional,
+    constants::DW_AT_location,
+    constants::DW_AT_lower_bound,
+    constants::DW_AT_mutable,
+    constants::DW_AT_ordering,
+    constants::DW_AT_picture_string,
+    constants::DW_AT_prototyped,
+    constants::DW_AT_small,
+    constants::DW_AT_segment,
+    constants::DW_AT_string_length,
+    constants::DW_AT_threads_scaled,
+    constants::DW_AT_upper_bound,
+    constants::DW_AT_use_location,
+    constants::DW_AT_use_UTF8,
+    constants::DW_AT_variable_parameter,
+    constants::DW_AT_virtuality,
+    constants::DW_AT_visibility,
+    constants::DW_AT_vtable_elem_location,
+    constants::DW_AT_type,
+];
+
+/// The state for computing a type signature.
+struct TypeSignature<'a, E: Endianity> {
+    units: Option<&'a UnitTable>,
+    line_strings: &'a LineStringTable,
+    strings: &'a StringTable,
+    /// The bytes to be hashed.
+    data: EndianVec<E>,
+    /// The numbering of the type entries that have been visited.
+    visited: HashMap<(Option<UnitId>, UnitEntryId), u64>,
+}
+
+impl<'a, E: Endianity> TypeSignature<'a, E> {
+    fn new(
+        units: Option<&'a UnitTable>,
+        line_strings: &'a LineStringTable,
+        strings: &'a StringTable,
+        endian: E,
+    ) -> Self {
+        TypeSignature {
+            units,
+            line_strings,
+            strings,
+            data: EndianVec::new(endian),
+            visited: HashMap::new(),
+        }
+    }
+
+    fn compute(
+        &mut self,
+        unit: &'a Unit,
+        unit_id: Option<UnitId>,
+        id: UnitEntryId,
+    ) -> Result<DebugTypeSignature> {
+        self.visited.insert((unit_id, id), 1);
+        let entry = unit.get(id);
+        if let Some(parent) = entry.parent() {
+            self.context(unit, parent)?;
+        }
+        self.entry(unit, unit_id, entry)?;
+        let digest = md5(self.data.slice());
+        let mut signature = [0; 8];
+        signature.copy_from_slice(&digest[8..]);
+        Ok(DebugTypeSignature(u64::from_le_bytes(signature)))
+    }
+
+    /// Add the tags and names of the entries containing a type entry,
+    /// beginning with the outermost.
+    fn context(&mut self, unit: &Unit, id: UnitEntryId) -> Result<()> {
+        let mut context = Vec::new();
+        let mut entry = unit.get(id);
+        while let Some(parent) = entry.parent() {
+            context.push(entry);
+            entry = unit.get(parent);
+        }
+        for entry in context.into_iter().rev() {
+            self.data.write_uleb128(u64::from(b'C'))?;
+            self.data.write_uleb128(entry.tag().0.into())?;
+            self.name(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Add the name of an entry as a null-terminated string.
+    fn name(&mut self, entry: &DebuggingInformationEntry) -> Result<()> {
+        if let Some(value) = entry.get(constants::DW_AT_name) {
+            if let Some(name) = self.string(value) {
+                self.data.write(name)?;
+            }
+        }
+        self.data.write_u8(0)
+    }
+
+    fn string<'b>(&self, value: &'b AttributeValue) -> Option<&'b [u8]>
+    where
+        'a: 'b,
+    {
+        match *value {
+            AttributeValue::String(ref name) => Some(name),
+            AttributeValue::StringRef(id) => Some(self.strings.get(id)),
+            AttributeValue::LineStringRef(id) => Some(self.line_strings.get(id)),
+            _ => None,
+        }
+    }
+
+    /// Add an entry and its children.
+    fn entry(
+        &mut self,
+        unit: &'a Unit,
+        unit_id: Option<UnitId>,
+        entry: &'a DebuggingInformationEntry,
+    ) -> Result<()> {
+        self.data.write_uleb128(u64::from(b'D'))?;
+        self.data.write_uleb128(entry.tag().0.into())?;
+        for name in SIGNATURE_ATTRIBUTES.iter() {
+            if let Some(value) = entry.get(*name) {
+                self.attribute(unit, unit_id, entry.tag(), *name, value)?;
+            }
+        }
+
+        for child in entry.children() {
+            let child = unit.get(*child);
+            // Named nested types and member functions are only added by name.
+            if is_type_tag(child.tag())
+                || (child.tag() == constants::DW_TAG_subprogram && is_type_tag(entry.tag()))
+            {
+                if let Some(name) = child
+                    .get(constants::DW_AT_name)
+                    .and_then(|x| self.string(x))
+                {
+                    if !name.is_empty() {
+                        self.data.write_uleb128(u64::from(b'S'))?;
+                        self.data.write_uleb128(child.tag().0.into())?;
+                        self.data.write(name)?;
+                        self.data.write_u8(0)?;
+                        continue;
+                    }
+                }
+            }
+            self.entry(unit, unit_id, child)?;
+        }
+        self.data.write_u8(0)
+    }
+
+    fn attribute(
+        &mut self,
+        unit: &'a Unit,
+        unit_id: Option<UnitId>,
+        tag: constants::DwTag,
+        name: constants::DwAt,
+        value: &AttributeValue,
+    ) -> Result<()> {
+        let constant = match *value {
+            AttributeValue::UnitRef(id) => {
+                return self.reference(unit, unit_id, tag, name, id);
+            }
+            AttributeValue::DebugInfoRef(Reference::Entry(ref_unit_id, id)) => {
+                let units = self.units.ok_or(Error::InvalidReference)?;
+                let ref_unit = units.get(ref_unit_id);
+                return self.reference(ref_unit, Some(ref_unit_id), tag, name, id);
+            }
+            AttributeValue::Data1(val) => u64::from(val),
+            AttributeValue::Data2(val) => u64::from(val),
+            AttributeValue::Data4(val) => u64::from(val),
+            AttributeValue::Data8(val) => val,
+            AttributeValue::Udata(val) => val,
+            AttributeValue::Sdata(val) | AttributeValue::ImplicitConst(val) => val as u64,
+            AttributeValue::Flag(val) => {
+                return self.flag(name, val);
+            }
+            AttributeValue::FlagPresent => {
+                return self.flag(name, true);
+            }
+            AttributeValue::Block(ref data) => {
+                return self.block(name, data);
+            }
+            AttributeValue::Data16(ref data) => {
+                return self.block(name, data);
+            }
+            AttributeValue::Exprloc(ref expression) => {
+                let encoding = unit.encoding();
+                let mut data = EndianVec::new(self.data.endian());
+                expression.write(&mut data, None, encoding, None)?;
+                return self.block(name, data.slice());
+            }
+            AttributeValue::String(_)
+            | AttributeValue::StringRef(_)
+            | AttributeValue::LineStringRef(_) => {
+                self.data.write_uleb128(u64::from(b'A'))?;
+                self.data.write_uleb128(name.0.into())?;
+                self.data
+                    .write_uleb128(constants::DW_FORM_string.0.into())?;
+                if let Some(string) = self.string(value) {
+                    self.data.write(string)?;
+                }
+                return self.data.write_u8(0);
+            }
+            AttributeValue::Encoding(val) => val.0.into(),
+            AttributeValue::DecimalSign(val) => val.0.into(),
+            AttributeValue::Endianity(val) => val.0.into(),
+            AttributeValue::Accessibility(val) => val.0.into(),
+            AttributeValue::Visibility(val) => val.0.into(),
+            AttributeValue::Virtuality(val) => val.0.into(),
+            AttributeValue::Language(val) => val.0.into(),
+            AttributeValue::AddressClass(val) => val.0,
+            AttributeValue::IdentifierCase(val) => val.0.into(),
+            AttributeValue::CallingConvention(val) => val.0.into(),
+            AttributeValue::Inline(val) => val.0.into(),
+            AttributeValue::Ordering(val) => val.0.into(),
+            _ => return Err(Error::InvalidAttributeValue),
+        };
+        self.data.write_uleb128(u64::from(b'A'))?;
+        self.data.write_uleb128(name.0.into())?;
+        self.data.write_uleb128(constants::DW_FORM_sdata.0.into())?;
+        self.data.write_sleb128(constant as i64)
+    }
+
+    fn flag(&mut self, name: constants::DwAt, val: bool) -> Result<()> {
+        self.data.write_uleb128(u64::from(b'A'))?;
+        self.data.write_uleb128(name.0.into())?;
+        self.data.write_uleb128(constants::DW_FORM_flag.0.into())?;
+        self.data.write_uleb128(val.into())
+    }
+
+    fn block(&mut self, name: constants::DwAt, data: &[u8]) -> Result<()> {
+        self.data.write_uleb128(u64::from(b'A'))?;
+        self.data.write_uleb128(name.0.into())?;
+        self.data.write_uleb128(constants::DW_FORM_block.0.into())?;
+        self.data.write_uleb128(data.len() as u64)?;
+        self.data.write(data)
+    }
+
+    /// Add an attribute that refers to another entry.
+    fn reference(
+        &mut self,
+        unit: &'a Unit,
+        unit_id: Option<UnitId>,
+        tag: constants::DwTag,
+        name: constants::DwAt,
+        id: UnitEntryId,
+    ) -> Result<()> {
+        let entry = unit.get(id);
+        match tag {
+            constants::DW_TAG_pointer_type
+            | constants::DW_TAG_reference_type
+            | constants::DW_TAG_rvalue_reference_type
+            | constants::DW_TAG_ptr_to_member_type
+                if name == constants::DW_AT_type =>
+            {
+                let type_name = entry
+                    .get(constants::DW_AT_name)
+                    .and_then(|x| self.string(x))
+                    .filter(|x| !x.is_empty());
+                if let Some(type_name) = type_name {
+                    self.data.write_uleb128(u64::from(b'N'))?;
+                    self.data.write_uleb128(name.0.into())?;
+                    if let Some(parent) = entry.parent() {
+                        self.context(unit, parent)?;
+                    }
+                    self.data.write_uleb128(u64::from(b'E'))?;
+                    self.data.write(type_name)?;
+                    return self.data.write_u8(0);
+                }
+            }
+            _ => {}
+        }
+
+        let count = self.visited.len() as u64;
+        if let Some(&number) = self.visited.get(&(unit_id, id)) {
+            self.data.write_uleb128(u64::from(b'R'))?;
+            self.data.write_uleb128(name.0.into())?;
+            return self.data.write_uleb128(number);
+        }
+        self.visited.insert((unit_id, id), count + 1);
+        self.data.write_uleb128(u64::from(b'T'))?;
+        self.data.write_uleb128(name.0.into())?;
+        self.entry(unit, unit_id, entry)
+    }
+}
+
+/// Compute the MD5 digest of the data, as described in RFC 1321.
+fn md5(data: &[u8]) -> [u8; 16] {
+    const S: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613,
+        0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193,
+        0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d,
+        0x02441453, 0xd8a1e681, 0xe7d3fbc8, 0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+        0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122,
+        0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa,
+        0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665, 0xf4292244,
+        0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb,
+        0xeb86d391,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let mut m = [0u32; 16];
+        for (m, bytes) in m.iter_mut().zip(chunk.chunks(4)) {
+            *m = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(S[i]));
+        }
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Encoding, Format};
+    use crate::write::LineProgram;
+    use crate::LittleEndian;
+
+    #[test]
+    fn test_md5() {
+        assert_eq!(
+            md5(b""),
+            [
+                0xd4, 0x1d, 0x8c, 0xd9, 0x8f, 0x00, 0xb2, 0x04, 0xe9, 0x80, 0x09, 0x98, 0xec, 0xf8,
+                0x42, 0x7e
+            ]
+        );
+        assert_eq!(
+            md5(b"abc"),
+            [
+                0x90, 0x01, 0x50, 0x98, 0x3c, 0xd2, 0x4f, 0xb0, 0xd6, 0x96, 0x3f, 0x7d, 0x28, 0xe1,
+                0x7f, 0x72
+            ]
+        );
+        assert_eq!(md5(&[b'a'; 100])[..4], [0x36, 0xa9, 0x2c, 0xc9]);
+    }
+
+    #[test]
+    fn test_type_signature() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let root = dwarf.unit.root();
+
+        // These signatures match those produced by GCC and LLVM.
+        let base = dwarf.unit.add(root, constants::DW_TAG_base_type);
+        let entry = dwarf.unit.get_mut(base);
+        entry.set(constants::DW_AT_byte_size, AttributeValue::Data1(4));
+        assert_eq!(
+            dwarf.type_signature(base, LittleEndian),
+            Ok(DebugTypeSignature(0x1AFE116E83701108))
+        );
+
+        // struct {};
+        let unnamed = dwarf.unit.add(root, constants::DW_TAG_structure_type);
+        let entry = dwarf.unit.get_mut(unnamed);
+        entry.set(constants::DW_AT_byte_size, AttributeValue::Data1(1));
+        entry.set(constants::DW_AT_decl_file, AttributeValue::Data1(1));
+        entry.set(constants::DW_AT_decl_line, AttributeValue::Data1(1));
+        assert_eq!(
+            dwarf.type_signature(unnamed, LittleEndian),
+            Ok(DebugTypeSignature(0x715305ce6cfd9ad1))
+        );
+
+        // namespace space { struct foo {}; }
+        let space = dwarf.unit.add(root, constants::DW_TAG_namespace);
+        let name = dwarf.strings.add("space");
+        let entry = dwarf.unit.get_mut(space);
+        entry.set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        entry.set(constants::DW_AT_declaration, AttributeValue::FlagPresent);
+        let foo = dwarf.unit.add(space, constants::DW_TAG_structure_type);
+        let name = dwarf.strings.add("foo");
+        let entry = dwarf.unit.get_mut(foo);
+        entry.set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        entry.set(constants::DW_AT_byte_size, AttributeValue::Data1(1));
+        assert_eq!(
+            dwarf.type_signature(foo, LittleEndian),
+            Ok(DebugTypeSignature(0x7b80381fd17f1e33))
+        );
+    }
+
+    #[test]
+    fn test_type_signature_reference() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let name = dwarf.strings.add("list");
+        let mut add_unit = || {
+            let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            // struct list { struct list *next; const struct list *prev; };
+            let list = unit.add(root, constants::DW_TAG_structure_type);
+            unit.get_mut(list)
+                .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+            let pointer = unit.add(root, constants::DW_TAG_pointer_type);
+            unit.get_mut(pointer)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(list));
+            let constant = unit.add(root, constants::DW_TAG_const_type);
+            unit.get_mut(constant)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(list));
+            let const_pointer = unit.add(root, constants::DW_TAG_pointer_type);
+            unit.get_mut(const_pointer)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(constant));
+            for &ty in &[pointer, const_pointer] {
+                let member = unit.add(list, constants::DW_TAG_member);
+                unit.get_mut(member)
+                    .set(constants::DW_AT_type, AttributeValue::UnitRef(ty));
+            }
+            (unit_id, list)
+        };
+        let (unit1, list1) = add_unit();
+        let (unit2, list2) = add_unit();
+        let signature = dwarf.type_signature(unit1, list1, LittleEndian).unwrap();
+        assert_eq!(
+            dwarf.type_signature(unit2, list2, LittleEndian),
+            Ok(signature)
+        );
+
+        let name = dwarf.strings.add("other");
+        dwarf
+            .units
+            .get_mut(unit2)
+            .get_mut(list2)
+            .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        assert_ne!(
+            dwarf.type_signature(unit2, list2, LittleEndian),
+            Ok(signature)
+        );
+    }
+}
@@ -2,8 +2,8 @@ use alloc::vec::Vec;
 use indexmap::IndexSet;
 use std::ops::{Deref, DerefMut};
 
-use crate::common::{DebugLineStrOffset, DebugStrOffset, SectionId};
-use crate::write::{BaseId, Result, Section, Writer};
+use crate::common::{DebugLineStrOffset, DebugStrOffset, DebugStrOffsetsBase, Format, SectionId};
+use crate::write::{index_set_allocated_bytes, BaseId, Result, Section, Writer};
 
 // Requirements:
 // - values are `[u8]`, null bytes are not allowed
@@ -29,9 +29,35 @@ macro_rules! define_string_table {
         pub struct $name {
             base_id: BaseId,
             strings: IndexSet<Vec<u8>>,
+            sorted: bool,
+            tail_merge: bool,
         }
 
         impl $name {
+            /// Set whether the strings are sorted when writing.
+            ///
+            /// By default, strings are written in the order in which they were
+            /// added. If this is set, then strings are written in byte order
+            /// instead, so that the section contents do not depend on the order
+            /// in which strings were added. This does not change the string ids.
+            #[inline]
+            pub fn set_sorted(&mut self, sorted: bool) {
+                self.sorted = sorted;
+            }
+
+            /// Set whether strings that are the tail of another string share
+            /// the storage of that string when writing.
+            ///
+            /// By default, each distinct string is written separately. If this
+            /// is set, then a string such as `int` is not written if another
+            /// string such as `unsigned int` ends with it, and its offset refers
+            /// to the tail of the longer string instead. This does not change
+            /// the string ids.
+            #[inline]
+            pub fn set_tail_merge(&mut self, tail_merge: bool) {
+                self.tail_merge = tail_merge;
+            }
+
             /// Add a string to the string table and return its id.
             ///
             /// If the string already exists, then return the id of the existing string.
@@ -65,22 +91,72 @@ macro_rules! define_string_table {
                 self.strings.get_index(id.index).map(Vec::as_slice).unwrap()
             }
 
+            /// Return an estimate of the number of bytes of heap memory used
+            /// by the table.
+            pub fn allocated_bytes(&self) -> usize {
+                index_set_allocated_bytes(&self.strings)
+                    + self.strings.iter().map(Vec::capacity).sum::<usize>()
+            }
+
             /// Write the string table to the `.debug_str` section.
             ///
             /// Returns the offsets at which the strings are written.
             pub fn write<W: Writer>(&self, w: &mut $section<W>) -> Result<$offsets> {
-                let mut offsets = Vec::new();
-                for bytes in self.strings.iter() {
-                    offsets.push(w.offset());
-                    w.write(bytes)?;
+                let mut order = (0..self.strings.len()).collect::<Vec<_>>();
+                if self.sorted {
+                    order.sort_by_key(|&index| &self.strings[index]);
+                }
+                let containers = if self.tail_merge {
+                    let containers = self.tail_merge_containers();
+                    order.retain(|&index| containers[index].is_none());
+                    containers
+                } else {
+                    Vec::new()
+                };
+
+                let mut offsets = vec![w.offset(); self.strings.len()];
+                for index in order {
+                    offsets[index] = w.offset();
+                    w.write(&self.strings[index])?;
                     w.write_u8(0)?;
                 }
+                for (index, container) in containers.into_iter().enumerate() {
+                    if let Some(container) = container {
+                        let mut offset = offsets[container];
+                        offset.0 += self.strings[container].len() - self.strings[index].len();
+                        offsets[index] = offset;
+                    }
+                }
 
                 Ok($offsets {
                     base_id: self.base_id,
                     offsets,
                 })
             }
+
+            /// For each string, return the index of a longer string that ends
+            /// with it, if any.
+            ///
+            /// The returned strings are never themselves the tail of another string.
+            fn tail_merge_containers(&self) -> Vec<Option<usize>> {
+                // Sort by the reversed bytes, in descending order. Any string
+                // which ends with a given string will then be immediately
+                // before it, or before another string which ends with it.
+                let mut order = (0..self.strings.len()).collect::<Vec<_>>();
+                order.sort_by(|&a, &b| {
+                    let a = self.strings[a].iter().rev();
+                    let b = self.strings[b].iter().rev();
+                    b.cmp(a)
+                });
+                let mut containers = vec![None; self.strings.len()];
+                for pair in order.windows(2) {
+                    let (prev, index) = (pair[0], pair[1]);
+                    if self.strings[prev].ends_with(&self.strings[index]) {
+                        containers[index] = Some(containers[prev].unwrap_or(prev));
+                    }
+                }
+                containers
+            }
         }
     };
 }
@@ -102,6 +178,41 @@ define_offsets!(
     "The section offsets of all strings within a `.debug_str` section."
 );
 
+define_section!(
+    DebugStrOffsetsSection,
+    DebugStrOffsets,
+    DebugStrOffsetsBase,
+    "A writable `.debug_str_offsets` section."
+);
+
+impl<W: Writer> DebugStrOffsetsSection<W> {
+    /// Write a string offsets table containing the given strings.
+    ///
+    /// Returns the base of the table, which is the value for the
+    /// `DW_AT_str_offsets_base` attribute.
+    pub(crate) fn write_table<I>(
+        &mut self,
+        format: Format,
+        ids: I,
+        offsets: &DebugStrOffsets,
+    ) -> Result<DebugStrOffsetsBase>
+    where
+        I: IntoIterator<Item = StringId>,
+    {
+        let length_offset = self.write_initial_length(format)?;
+        let length_base = self.len();
+        self.write_u16(5)?;
+        self.write_u16(0)?; // padding
+        let base = self.offset();
+        for id in ids {
+            self.write_offset(offsets.get(id).0, SectionId::DebugStr, format.word_size())?;
+        }
+        let length = (self.len() - length_base) as u64;
+        self.write_initial_length_at(length_offset, length, format)?;
+        Ok(base)
+    }
+}
+
 define_id!(
     LineStringId,
     "An identifier for a string in a `LineStringTable`."
@@ -138,6 +249,7 @@ mod tests {
     fn test_string_table() {
         let mut strings = StringTable::default();
         assert_eq!(strings.count(), 0);
+        assert_eq!(strings.allocated_bytes(), 0);
         let id1 = strings.add(&b"one"[..]);
         let id2 = strings.add(&b"two"[..]);
         assert_eq!(strings.add(&b"one"[..]), id1);
@@ -145,6 +257,7 @@ mod tests {
         assert_eq!(strings.get(id1), &b"one"[..]);
         assert_eq!(strings.get(id2), &b"two"[..]);
         assert_eq!(strings.count(), 2);
+        assert!(strings.allocated_bytes() >= 6);
 
         let mut debug_str = DebugStr::from(EndianVec::new(LittleEndian));
         let offsets = strings.write(&mut debug_str).unwrap();
@@ -154,6 +267,67 @@ mod tests {
         assert_eq!(offsets.count(), 2);
     }
 
+    #[test]
+    fn test_string_table_sorted() {
+        let mut strings1 = StringTable::default();
+        strings1.set_sorted(true);
+        let id1 = strings1.add(&b"two"[..]);
+        let id2 = strings1.add(&b"one"[..]);
+        let id3 = strings1.add(&b"three"[..]);
+
+        let mut strings2 = StringTable::default();
+        strings2.set_sorted(true);
+        for s in [&b"one"[..], &b"three"[..], &b"two"[..]] {
+            strings2.add(s);
+        }
+
+        let mut debug_str1 = DebugStr::from(EndianVec::new(LittleEndian));
+        let offsets = strings1.write(&mut debug_str1).unwrap();
+        assert_eq!(debug_str1.slice(), b"one\0three\0two\0");
+        assert_eq!(offsets.get(id1), DebugStrOffset(10));
+        assert_eq!(offsets.get(id2), DebugStrOffset(0));
+        assert_eq!(offsets.get(id3), DebugStrOffset(4));
+
+        let mut debug_str2 = DebugStr::from(EndianVec::new(LittleEndian));
+        strings2.write(&mut debug_str2).unwrap();
+        assert_eq!(debug_str1.slice(), debug_str2.slice());
+    }
+
+    #[test]
+    fn test_string_table_tail_merge() {
+        let mut strings = StringTable::default();
+        strings.set_tail_merge(true);
+        let id1 = strings.add(&b"int"[..]);
+        let id2 = strings.add(&b"long"[..]);
+        let id3 = strings.add(&b"unsigned int"[..]);
+        let id4 = strings.add(&b"nt"[..]);
+        let id5 = strings.add(&b"signed int"[..]);
+        let id6 = strings.add(&b"long long"[..]);
+
+        let mut debug_str = DebugStr::from(EndianVec::new(LittleEndian));
+        let offsets = strings.write(&mut debug_str).unwrap();
+        assert_eq!(debug_str.slice(), b"unsigned int\0long long\0");
+        assert_eq!(offsets.get(id1), DebugStrOffset(9));
+        assert_eq!(offsets.get(id2), DebugStrOffset(18));
+        assert_eq!(offsets.get(id3), DebugStrOffset(0));
+        assert_eq!(offsets.get(id4), DebugStrOffset(10));
+        assert_eq!(offsets.get(id5), DebugStrOffset(2));
+        assert_eq!(offsets.get(id6), DebugStrOffset(13));
+
+        let read_debug_str = read::DebugStr::new(debug_str.slice(), LittleEndian);
+        for id in [id1, id2, id3, id4, id5, id6] {
+            let string = read_debug_str.get_str(offsets.get(id)).unwrap();
+            assert_eq!(string.slice(), strings.get(id));
+        }
+
+        strings.set_sorted(true);
+        let mut debug_str = DebugStr::from(EndianVec::new(LittleEndian));
+        let offsets = strings.write(&mut debug_str).unwrap();
+        assert_eq!(debug_str.slice(), b"long long\0unsigned int\0");
+        assert_eq!(offsets.get(id1), DebugStrOffset(19));
+        assert_eq!(offsets.get(id2), DebugStrOffset(5));
+    }
+
     #[test]
     fn test_string_table_read() {
         let mut strings = StringTable::default();
@@ -0,0 +1,174 @@
+use std::io;
+
+use crate::endianity::Endianity;
+use crate::write::{Error, Result, Writer};
+
+/// A `Writer` that streams data to an `io::Write` with endianity metadata.
+///
+/// Unlike `EndianVec`, this does not keep the section data in memory. Writes
+/// to earlier offsets, such as when patching a unit length after the unit has
+/// been written, are handled by seeking backwards in the stream, writing the
+/// bytes, and then seeking back to the end of the data.
+///
+/// The stream should usually be buffered (for example, using `io::BufWriter`)
+/// to avoid many small writes. The data is written relative to the position of
+/// the stream when the `StreamWriter` is created, so multiple sections may be
+/// written to a single stream one after another.
+#[derive(Debug)]
+pub struct StreamWriter<W, Endian>
+where
+    W: io::Write + io::Seek,
+    Endian: Endianity,
+{
+    inner: W,
+    endian: Endian,
+    len: usize,
+}
+
+impl<W, Endian> StreamWriter<W, Endian>
+where
+    W: io::Write + io::Seek,
+    Endian: Endianity,
+{
+    /// Construct a new `StreamWriter` that writes to `inner` with the given
+    /// endianity.
+    pub fn new(inner: W, endian: Endian) -> Self {
+        StreamWriter {
+            inner,
+            endian,
+            len: 0,
+        }
+    }
+
+    /// Return a reference to the underlying stream.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flush the underlying stream.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush().map_err(io_error)
+    }
+
+    /// Flush and return the underlying stream.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+}
+
+fn io_error(error: io::Error) -> Error {
+    Error::Io(error.kind())
+}
+
+impl<W, Endian> Writer for StreamWriter<W, Endian>
+where
+    W: io::Write + io::Seek,
+    Endian: Endianity,
+{
+    type Endian = Endian;
+
+    #[inline]
+    fn endian(&self) -> Self::Endian {
+        self.endian
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.write_all(bytes).map_err(io_error)?;
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    fn write_at(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+        if offset > self.len {
+            return Err(Error::OffsetOutOfBounds);
+        }
+        if bytes.len() > self.len - offset {
+            return Err(Error::LengthOutOfBounds);
+        }
+        let back = (self.len - offset) as i64;
+        let forward = (self.len - offset - bytes.len()) as i64;
+        self.inner
+            .seek(io::SeekFrom::Current(-back))
+            .map_err(io_error)?;
+        self.inner.write_all(bytes).map_err(io_error)?;
+        self.inner
+            .seek(io::SeekFrom::Current(forward))
+            .map_err(io_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write::{AttributeValue, Dwarf, EndianVec, LineProgram, Sections, Unit};
+    use crate::{constants, Encoding, Format, LittleEndian, SectionId};
+    use alloc::vec::Vec;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stream_writer() {
+        let mut w = StreamWriter::new(Cursor::new(vec![0xff]), LittleEndian);
+        w.inner.set_position(1);
+        assert_eq!(w.endian(), LittleEndian);
+        assert_eq!(w.len(), 0);
+
+        w.write(&[1, 2]).unwrap();
+        w.write(&[3, 4, 5]).unwrap();
+        assert_eq!(w.len(), 5);
+
+        w.write_at(0, &[6, 7]).unwrap();
+        w.write_at(3, &[8, 9]).unwrap();
+        assert_eq!(w.len(), 5);
+
+        assert_eq!(w.write_at(4, &[6, 7]), Err(Error::LengthOutOfBounds));
+        assert_eq!(w.write_at(5, &[6, 7]), Err(Error::LengthOutOfBounds));
+        assert_eq!(w.write_at(6, &[6, 7]), Err(Error::OffsetOutOfBounds));
+
+        w.write(&[10]).unwrap();
+        assert_eq!(
+            w.into_inner().unwrap().into_inner(),
+            vec![0xff, 6, 7, 3, 8, 9, 10]
+        );
+    }
+
+    #[test]
+    fn test_stream_writer_sections() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        for i in 0..3 {
+            let child = unit.add(root, constants::DW_TAG_variable);
+            unit.get_mut(child).set(
+                constants::DW_AT_name,
+                AttributeValue::String(format!("var{}", i).into_bytes()),
+            );
+        }
+
+        let mut vec_sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut vec_sections).unwrap();
+
+        let mut stream_sections =
+            Sections::from_fn(|_| StreamWriter::new(Cursor::new(Vec::new()), LittleEndian));
+        dwarf.write(&mut stream_sections).unwrap();
+
+        for id in [SectionId::DebugAbbrev, SectionId::DebugInfo] {
+            let expected = vec_sections.get(id).unwrap().slice();
+            let stream = stream_sections.get(id).unwrap();
+            assert_eq!(stream.len(), expected.len());
+            assert_eq!(stream.get_ref().get_ref().as_slice(), expected);
+        }
+    }
+}
@@ -1,18 +1,24 @@
 use alloc::vec::Vec;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::ops::{Deref, DerefMut};
-use std::{slice, usize};
+use std::{mem, slice, usize};
 
 use crate::common::{
-    DebugAbbrevOffset, DebugInfoOffset, DebugLineOffset, DebugMacinfoOffset, DebugMacroOffset,
-    DebugStrOffset, DebugTypeSignature, Encoding, Format, SectionId,
+    DebugAddrBase, DebugInfoOffset, DebugLineOffset, DebugLocListsBase, DebugMacinfoOffset,
+    DebugMacroOffset, DebugRngListsBase, DebugStrOffset, DebugStrOffsetsBase, DebugTypeSignature,
+    Encoding, Format, SectionId,
 };
 use crate::constants;
 use crate::leb128::write::{sleb128_size, uleb128_size};
 use crate::write::{
-    Abbreviation, AbbreviationTable, Address, AttributeSpecification, BaseId, DebugLineStrOffsets,
-    DebugStrOffsets, Error, Expression, FileId, LineProgram, LineStringId, LocationListId,
-    LocationListOffsets, LocationListTable, RangeListId, RangeListOffsets, RangeListTable,
-    Reference, Result, Section, Sections, StringId, Writer,
+    vec_allocated_bytes, Abbreviation, AbbreviationTable, AbbreviationTables, Address,
+    AttributeSpecification, BaseId, DebugLineStrOffsets, DebugStrOffsets, Error, Expression,
+    FileId, LineProgram, LineProgramId, LineString, LineStringId, LineStringTable, Location,
+    LocationListId, LocationListOffsets, LocationListTable, MacroTable, RangeListId,
+    RangeListOffsets, RangeListTable, Reference, Result, Section, Sections, StringId, StringTable,
+    Writer,
 };
 
 define_id!(UnitId, "An identifier for a unit in a `UnitTable`.");
@@ -24,6 +30,7 @@ define_id!(UnitEntryId, "An identifier for an entry in a `Unit`.");
 pub struct UnitTable {
     base_id: BaseId,
     units: Vec<Unit>,
+    share_abbreviations: bool,
 }
 
 impl UnitTable {
@@ -45,6 +52,30 @@ impl UnitTable {
         self.units.len()
     }
 
+    /// Return an estimate of the number of bytes of heap memory used by the
+    /// units in the table.
+    ///
+    /// This can be used to track and limit memory usage when converting or
+    /// building large amounts of debugging information. The estimate is based
+    /// on the capacity of the containers used by the units, and does not
+    /// include allocator overhead. It does not include the string tables,
+    /// which are stored separately.
+    pub fn allocated_bytes(&self) -> usize {
+        vec_allocated_bytes(&self.units)
+            + self.units.iter().map(Unit::allocated_bytes).sum::<usize>()
+    }
+
+    /// Set whether units with identical abbreviation tables share a single
+    /// `.debug_abbrev` contribution when written.
+    ///
+    /// This is disabled by default. Enabling it can significantly reduce the
+    /// size of `.debug_abbrev` when there are many similar units, such as
+    /// after merging units from multiple objects.
+    #[inline]
+    pub fn set_share_abbreviations(&mut self, share: bool) {
+        self.share_abbreviations = share;
+    }
+
     /// Return the id of a unit.
     ///
     /// # Panics
@@ -78,6 +109,654 @@ impl UnitTable {
         &mut self.units[id.index]
     }
 
+    /// Delete all entries for which `f` returns true, along with their children.
+    ///
+    /// The root entry of a unit is never deleted.
+    ///
+    /// Attributes in any unit that refer to a deleted entry are also deleted.
+    pub fn delete_entries<F>(&mut self, mut f: F)
+    where
+        F: FnMut(UnitId, &DebuggingInformationEntry) -> bool,
+    {
+        for (index, unit) in self.units.iter_mut().enumerate() {
+            let id = UnitId::new(self.base_id, index);
+            unit.detach_entries(|entry| f(id, entry));
+        }
+        let live: Vec<_> = self.units.iter().map(Unit::live_entries).collect();
+        for (index, unit) in self.units.iter_mut().enumerate() {
+            unit.delete_references(&mut |unit, entry| {
+                let unit = unit.map_or(index, |unit| unit.index);
+                live.get(unit)
+                    .and_then(|live| live.get(entry.index))
+                    .copied()
+                    .unwrap_or(false)
+            });
+        }
+    }
+
+    /// Append the units from another table, and deduplicate type entries.
+    ///
+    /// Both tables must use the same string tables, such as by converting
+    /// each object with the same `LineStringTable` and `StringTable`.
+    /// References to `Dwarf::line_programs` are not updated.
+    ///
+    /// Named type entries that are declared at namespace scope are assumed
+    /// to follow the C++ One Definition Rule. If multiple units contain a
+    /// definition of a type with the same qualified name and the same
+    /// structure, then only the first definition is kept, and references to
+    /// the other definitions are replaced with references to the first.
+    ///
+    /// Returns the ids of the appended units.
+    pub fn merge(&mut self, other: UnitTable) -> Vec<UnitId> {
+        let offset = self.units.len();
+        let base_id = self.base_id;
+        let other_base_id = other.base_id;
+        self.units.extend(other.units);
+        let mut convert_id = |id: UnitId| {
+            if id.base_id == other_base_id {
+                UnitId::new(base_id, offset + id.index)
+            } else {
+                id
+            }
+        };
+        for unit in &mut self.units[offset..] {
+            unit.convert_unit_ids(&mut convert_id);
+        }
+        self.deduplicate_types();
+        (offset..self.units.len())
+            .map(|index| UnitId::new(base_id, index))
+            .collect()
+    }
+
+    /// Replace references to duplicate type definitions with references to
+    /// the first definition, and delete the duplicates.
+    fn deduplicate_types(&mut self) {
+        // Find the canonical definition for each type entry.
+        let mut canonical_ids = HashMap::new();
+        let mut replacements: Vec<Vec<Option<(UnitId, UnitEntryId)>>> = self
+            .units
+            .iter()
+            .map(|unit| vec![None; unit.entries.len()])
+            .collect();
+        for (index, unit) in self.units.iter().enumerate() {
+            let mut stack = vec![(unit.root, Vec::new())];
+            while let Some((id, context)) = stack.pop() {
+                for child in &unit.entries[id.index].children {
+                    let entry = &unit.entries[child.index];
+                    let name = entry.get(constants::DW_AT_name);
+                    if entry.tag == constants::DW_TAG_namespace {
+                        // Types in anonymous namespaces are local to the unit.
+                        if let Some(name) = name {
+                            let mut context = context.clone();
+                            context.push(name.clone());
+                            stack.push((*child, context));
+                        }
+                    } else if name.is_some()
+                        && is_type_tag(entry.tag)
+                        // Base types may be referenced by expressions in the same unit.
+                        && entry.tag != constants::DW_TAG_base_type
+                        && entry.get(constants::DW_AT_declaration).is_none()
+                    {
+                        let key = (context.clone(), self.type_key(index, *child));
+                        match canonical_ids.get(&key) {
+                            Some(&(canonical_index, canonical)) => {
+                                let canonical_id = UnitId::new(self.base_id, canonical_index);
+                                let canonical_unit = &self.units[canonical_index];
+                                let mut canonical_entries = Vec::new();
+                                canonical_unit.subtree(canonical, &mut canonical_entries);
+                                let mut entries = Vec::new();
+                                unit.subtree(*child, &mut entries);
+                                for (entry, canonical) in entries.into_iter().zip(canonical_entries)
+                                {
+                                    replacements[index][entry.index] =
+                                        Some((canonical_id, canonical));
+                                }
+                            }
+                            None => {
+                                canonical_ids.insert(key, (index, *child));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.replace_entries(&replacements);
+    }
+
+    /// Replace attribute references to entries that have a replacement, and
+    /// delete the replaced entries.
+    ///
+    /// `replacements` is indexed by unit index and entry index.
+    fn replace_entries(&mut self, replacements: &[Vec<Option<(UnitId, UnitEntryId)>>]) {
+        let replacement = |unit: UnitId, entry: UnitEntryId| {
+            replacements
+                .get(unit.index)
+                .and_then(|replacements| replacements.get(entry.index))
+                .copied()
+                .flatten()
+        };
+        for (index, unit) in self.units.iter_mut().enumerate() {
+            let unit_id = UnitId::new(self.base_id, index);
+            for entry in &mut unit.entries {
+                for attr in &mut entry.attrs {
+                    let (unit, entry) = match attr.value {
+                        AttributeValue::UnitRef(entry) => (unit_id, entry),
+                        AttributeValue::DebugInfoRef(Reference::Entry(unit, entry))
+                            if unit.base_id == self.base_id =>
+                        {
+                            (unit, entry)
+                        }
+                        _ => continue,
+                    };
+                    if let Some((canonical_unit, canonical)) = replacement(unit, entry) {
+                        attr.value = if canonical_unit == unit_id {
+                            AttributeValue::UnitRef(canonical)
+                        } else {
+                            AttributeValue::DebugInfoRef(Reference::Entry(
+                                canonical_unit,
+                                canonical,
+                            ))
+                        };
+                    }
+                }
+            }
+        }
+
+        self.delete_entries(|unit, entry| replacement(unit, entry.id).is_some());
+    }
+
+    /// Return a value that is equal for type entries with the same structure.
+    ///
+    /// This ignores source locations, and uses the names of referenced types
+    /// instead of their structure.
+    fn type_key(&self, index: usize, id: UnitEntryId) -> Vec<(usize, TypeKeyEntry)> {
+        let unit = &self.units[index];
+        let mut entries = Vec::new();
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            let entry = &unit.entries[id.index];
+            let mut attrs = Vec::new();
+            for attr in &entry.attrs {
+                let value = match attr.name {
+                    constants::DW_AT_decl_file
+                    | constants::DW_AT_decl_line
+                    | constants::DW_AT_decl_column => continue,
+                    _ => match attr.value {
+                        AttributeValue::UnitRef(entry) => {
+                            TypeKeyValue::Reference(self.type_name(index, entry))
+                        }
+                        AttributeValue::DebugInfoRef(Reference::Entry(unit, entry))
+                            if unit.base_id == self.base_id && unit.index < self.units.len() =>
+                        {
+                            TypeKeyValue::Reference(self.type_name(unit.index, entry))
+                        }
+                        ref value => TypeKeyValue::Value(value.clone()),
+                    },
+                };
+                attrs.push((attr.name, value));
+            }
+            entries.push((depth, TypeKeyEntry(entry.tag, attrs)));
+            for child in entry.children.iter().rev() {
+                stack.push((*child, depth + 1));
+            }
+        }
+        entries
+    }
+
+    /// Return the name of a type, following references from unnamed types
+    /// such as pointers.
+    fn type_name(
+        &self,
+        mut index: usize,
+        mut id: UnitEntryId,
+    ) -> Vec<(constants::DwTag, Option<AttributeValue>)> {
+        let mut name = Vec::new();
+        // Limit the length in case of cycles.
+        for _ in 0..16 {
+            let entry = &self.units[index].entries[id.index];
+            let entry_name = entry.get(constants::DW_AT_name).cloned();
+            let named = entry_name.is_some();
+            name.push((entry.tag, entry_name));
+            if named {
+                break;
+            }
+            match entry.get(constants::DW_AT_type) {
+                Some(AttributeValue::UnitRef(entry)) => id = *entry,
+                Some(AttributeValue::DebugInfoRef(Reference::Entry(unit, entry)))
+                    if unit.base_id == self.base_id && unit.index < self.units.len() =>
+                {
+                    index = unit.index;
+                    id = *entry;
+                }
+                _ => break,
+            }
+        }
+        name
+    }
+
+    /// Delete or tombstone the debugging information for addresses for which
+    /// `live` returns false.
+    ///
+    /// This is intended for use by linkers that discard unused sections.
+    /// `live` is called with the start address of each entry, range, location,
+    /// and line sequence.
+    ///
+    /// Entries with a `DW_AT_low_pc` that is not live, or with a `DW_AT_ranges`
+    /// that contains no live ranges, are either deleted or have their addresses
+    /// replaced, depending on `policy`. Ranges and locations that are not live
+    /// are always deleted.
+    ///
+    /// After this, type entries that are not referenced by any remaining entry
+    /// are also deleted.
+    pub fn discard_addresses<F>(&mut self, mut live: F, policy: TombstonePolicy)
+    where
+        F: FnMut(Address) -> bool,
+    {
+        for unit in &mut self.units {
+            unit.discard_addresses(&mut live, policy);
+        }
+        self.delete_unreferenced_types();
+    }
+
+    /// Delete all type entries that are not referenced by a non-type entry,
+    /// either directly or indirectly.
+    fn delete_unreferenced_types(&mut self) {
+        let mut marked: Vec<Vec<bool>> = Vec::with_capacity(self.units.len());
+        let mut in_type: Vec<Vec<bool>> = Vec::with_capacity(self.units.len());
+        let mut stack = Vec::new();
+        for (index, unit) in self.units.iter().enumerate() {
+            marked.push(vec![false; unit.entries.len()]);
+            let mut unit_in_type = vec![false; unit.entries.len()];
+            let mut tree = vec![unit.root];
+            while let Some(id) = tree.pop() {
+                let entry = &unit.entries[id.index];
+                let is_type = unit_in_type[id.index] || is_type_tag(entry.tag);
+                if !is_type {
+                    stack.push((index, id));
+                }
+                for child in &entry.children {
+                    unit_in_type[child.index] = is_type;
+                    tree.push(*child);
+                }
+            }
+            in_type.push(unit_in_type);
+        }
+
+        while let Some((index, id)) = stack.pop() {
+            if marked[index][id.index] {
+                continue;
+            }
+            marked[index][id.index] = true;
+            let unit = &self.units[index];
+            let entry = &unit.entries[id.index];
+            stack.extend(entry.parent.map(|parent| (index, parent)));
+            if in_type[index][id.index] || is_type_tag(entry.tag) {
+                stack.extend(entry.children.iter().map(|child| (index, *child)));
+            }
+            let mut mark = |unit: Option<UnitId>, entry: UnitEntryId| {
+                let unit = unit.map_or(index, |unit| unit.index);
+                if unit < marked.len() {
+                    stack.push((unit, entry));
+                }
+                true
+            };
+            for attr in &entry.attrs {
+                match attr.value {
+                    AttributeValue::UnitRef(entry) => {
+                        mark(None, entry);
+                    }
+                    AttributeValue::DebugInfoRef(Reference::Entry(unit, entry)) => {
+                        mark(Some(unit), entry);
+                    }
+                    AttributeValue::Exprloc(ref expression) => {
+                        expression.all_references(&mut mark);
+                    }
+                    AttributeValue::LocationListRef(id) => {
+                        for expression in unit.locations.get(id).0.iter().filter_map(Location::data)
+                        {
+                            expression.all_references(&mut mark);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.delete_entries(|unit, entry| !marked[unit.index][entry.id.index]);
+    }
+
+    /// Move type entries that are duplicated in multiple units into shared
+    /// partial units.
+    ///
+    /// This is similar to the transformation performed by the `dwz` tool, and
+    /// can significantly reduce the size of debugging information that was
+    /// produced by merging many units, such as with `UnitTable::merge`.
+    ///
+    /// A type entry that is a child of the root entry of a unit is moved if an
+    /// identical entry, including its children, is found in another unit with
+    /// the same encoding and language. The entry is moved to a new unit with a
+    /// `DW_TAG_partial_unit` root, and each unit that contained it is given a
+    /// `DW_TAG_imported_unit` entry that refers to the partial unit. References
+    /// to the moved entries are updated. A separate partial unit is created
+    /// for each set of units that share entries.
+    ///
+    /// Entries can only be moved if they do not refer to addresses, range
+    /// lists, location lists, or entries in the unit that are not also being
+    /// moved, and if they are not referred to by expressions. Entries that
+    /// refer to other moved entries are moved in later passes, so for example
+    /// a structure type may be moved after the base types of its members.
+    ///
+    /// Returns the ids of the new partial units.
+    pub fn create_partial_units(&mut self) -> Vec<UnitId> {
+        let first_partial = self.units.len();
+        let mut partial_units = HashMap::new();
+        let mut imports = Vec::new();
+        loop {
+            let pinned = self.expression_references();
+            let mut groups = IndexMap::new();
+            for (index, unit) in self.units[..first_partial].iter().enumerate() {
+                if unit.is_verbatim() {
+                    continue;
+                }
+                let language = unit.entries[unit.root.index]
+                    .get(constants::DW_AT_language)
+                    .cloned();
+                for child in &unit.entries[unit.root.index].children {
+                    if let Some(key) = self.partial_key(index, *child, &pinned[index]) {
+                        groups
+                            .entry((unit.encoding, language.clone(), key))
+                            .or_insert_with(Vec::new)
+                            .push((index, *child));
+                    }
+                }
+            }
+
+            let mut replacements: Vec<Vec<Option<(UnitId, UnitEntryId)>>> = self
+                .units
+                .iter()
+                .map(|unit| vec![None; unit.entries.len()])
+                .collect();
+            let mut changed = false;
+            for ((encoding, language, _), occurrences) in groups {
+                let mut units: Vec<usize> = occurrences.iter().map(|(index, _)| *index).collect();
+                units.dedup();
+                if units.len() < 2 {
+                    continue;
+                }
+                changed = true;
+                let partial = *partial_units
+                    .entry((encoding, language.clone(), units.clone()))
+                    .or_insert_with(|| {
+                        let mut unit = Unit::new(encoding, LineProgram::none());
+                        let root = unit.root;
+                        unit.entries[root.index].tag = constants::DW_TAG_partial_unit;
+                        if let Some(language) = language {
+                            unit.get_mut(root).set(constants::DW_AT_language, language);
+                        }
+                        for index in &units {
+                            imports.push((*index, self.units.len()));
+                        }
+                        self.units.push(unit);
+                        self.units.len() - 1
+                    });
+                let partial_id = UnitId::new(self.base_id, partial);
+
+                let (index, canonical) = occurrences[0];
+                let partial_entry = self.copy_to_partial(index, canonical, partial);
+                let mut partial_entries = Vec::new();
+                self.units[partial].subtree(partial_entry, &mut partial_entries);
+                for (index, id) in occurrences {
+                    let mut entries = Vec::new();
+                    self.units[index].subtree(id, &mut entries);
+                    for (entry, partial_entry) in entries.into_iter().zip(&partial_entries) {
+                        replacements[index][entry.index] = Some((partial_id, *partial_entry));
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+            self.replace_entries(&replacements);
+        }
+
+        // Insert in reverse so that the imports are in the same order as the
+        // partial units.
+        for (index, partial) in imports.into_iter().rev() {
+            let partial_id = UnitId::new(self.base_id, partial);
+            let partial_root = self.units[partial].root;
+            let unit = &mut self.units[index];
+            let root = unit.root;
+            let import = unit.add(root, constants::DW_TAG_imported_unit);
+            unit.get_mut(import).set(
+                constants::DW_AT_import,
+                AttributeValue::DebugInfoRef(Reference::Entry(partial_id, partial_root)),
+            );
+            // Place the imports before the other children, as `dwz` does.
+            let children = &mut unit.entries[root.index].children;
+            children.pop();
+            children.insert(0, import);
+        }
+
+        (first_partial..self.units.len())
+            .map(|index| UnitId::new(self.base_id, index))
+            .collect()
+    }
+
+    /// Return a flag for each entry indicating whether it is referenced by an
+    /// expression.
+    fn expression_references(&self) -> Vec<Vec<bool>> {
+        let mut pinned: Vec<Vec<bool>> = self
+            .units
+            .iter()
+            .map(|unit| vec![false; unit.entries.len()])
+            .collect();
+        for (index, unit) in self.units.iter().enumerate() {
+            let mut mark = |unit: Option<UnitId>, entry: UnitEntryId| {
+                let unit = unit.map_or(index, |unit| unit.index);
+                if let Some(flag) = pinned
+                    .get_mut(unit)
+                    .and_then(|pinned| pinned.get_mut(entry.index))
+                {
+                    *flag = true;
+                }
+                true
+            };
+            for entry in &unit.entries {
+                for attr in &entry.attrs {
+                    match attr.value {
+                        AttributeValue::Exprloc(ref expression) => {
+                            expression.all_references(&mut mark);
+                        }
+                        AttributeValue::LocationListRef(id) => {
+                            for expression in
+                                unit.locations.get(id).0.iter().filter_map(Location::data)
+                            {
+                                expression.all_references(&mut mark);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        pinned
+    }
+
+    /// Return a value that is equal for entries that can be moved to the same
+    /// partial unit.
+    ///
+    /// Returns `None` if the entry cannot be moved.
+    fn partial_key(
+        &self,
+        index: usize,
+        id: UnitEntryId,
+        pinned: &[bool],
+    ) -> Option<Vec<(usize, PartialKeyEntry)>> {
+        let unit = &self.units[index];
+        if !is_type_tag(unit.entries[id.index].tag) {
+            return None;
+        }
+        let mut ids = Vec::new();
+        let mut depths = Vec::new();
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            ids.push(id);
+            depths.push(depth);
+            for child in unit.entries[id.index].children.iter().rev() {
+                stack.push((*child, depth + 1));
+            }
+        }
+        let positions: HashMap<UnitEntryId, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(position, id)| (*id, position))
+            .collect();
+
+        let mut entries = Vec::with_capacity(ids.len());
+        for (id, depth) in ids.into_iter().zip(depths) {
+            if pinned[id.index] {
+                return None;
+            }
+            let entry = &unit.entries[id.index];
+            let mut attrs = Vec::with_capacity(entry.attrs.len());
+            for attr in &entry.attrs {
+                let value = match attr.value {
+                    AttributeValue::UnitRef(entry) => {
+                        PartialKeyValue::Internal(*positions.get(&entry)?)
+                    }
+                    AttributeValue::DebugInfoRef(Reference::Entry(unit_id, entry))
+                        if unit_id.base_id == self.base_id =>
+                    {
+                        if unit_id.index == index {
+                            PartialKeyValue::Internal(*positions.get(&entry)?)
+                        } else if self
+                            .units
+                            .get(unit_id.index)
+                            .map_or(false, |unit| unit.is_partial())
+                        {
+                            PartialKeyValue::Value(attr.value.clone())
+                        } else {
+                            return None;
+                        }
+                    }
+                    AttributeValue::FileIndex(Some(file)) => {
+                        if unit.line_program.is_none() {
+                            return None;
+                        }
+                        let program = &unit.line_program;
+                        let (path, directory) = program.get_file(file);
+                        // Relative paths are resolved against the directory,
+                        // and relative directories against the unit's
+                        // compilation directory.
+                        let mut file = vec![path.clone()];
+                        for directory in [directory, program.default_directory()] {
+                            if is_absolute_path(file.last().unwrap()) {
+                                break;
+                            }
+                            file.push(program.get_directory(directory).clone());
+                        }
+                        PartialKeyValue::File(file)
+                    }
+                    AttributeValue::Exprloc(ref expression) => {
+                        if !expression.all_references(&mut |_, _| false) {
+                            return None;
+                        }
+                        PartialKeyValue::Value(attr.value.clone())
+                    }
+                    AttributeValue::Address(_)
+                    | AttributeValue::LineProgramRef
+                    | AttributeValue::DebugLineRef(_)
+                    | AttributeValue::LocationListRef(_)
+                    | AttributeValue::RangeListRef(_)
+                    | AttributeValue::DebugMacinfoRef(_)
+                    | AttributeValue::DebugMacroRef(_)
+                    | AttributeValue::DebugStrOffsetsBase(_)
+                    | AttributeValue::DebugAddrBase(_)
+                    | AttributeValue::DebugRngListsBase(_)
+                    | AttributeValue::DebugLocListsBase(_) => return None,
+                    ref value => PartialKeyValue::Value(value.clone()),
+                };
+                attrs.push((attr.name, value));
+            }
+            entries.push((depth, PartialKeyEntry(entry.tag, attrs)));
+        }
+        Some(entries)
+    }
+
+    /// Copy an entry and its children to the root of a partial unit.
+    ///
+    /// The entry must have been accepted by `partial_key`.
+    fn copy_to_partial(&mut self, index: usize, id: UnitEntryId, partial: usize) -> UnitEntryId {
+        debug_assert!(index < partial);
+        let base_id = self.base_id;
+        let (units, partial_units) = self.units.split_at_mut(partial);
+        let unit = &units[index];
+        let partial_unit = &mut partial_units[0];
+
+        let mut ids = Vec::new();
+        unit.subtree(id, &mut ids);
+        let mut new_ids = HashMap::new();
+        for id in &ids {
+            let parent = match unit.entries[id.index].parent {
+                Some(parent) if *id != ids[0] => new_ids[&parent],
+                _ => partial_unit.root,
+            };
+            let new_id = partial_unit.add(parent, unit.entries[id.index].tag);
+            partial_unit.entries[new_id.index].sibling = unit.entries[id.index].sibling;
+            new_ids.insert(*id, new_id);
+        }
+
+        for id in &ids {
+            let mut attrs = Vec::with_capacity(unit.entries[id.index].attrs.len());
+            for attr in &unit.entries[id.index].attrs {
+                let value = match attr.value {
+                    AttributeValue::UnitRef(entry) => AttributeValue::UnitRef(new_ids[&entry]),
+                    AttributeValue::DebugInfoRef(Reference::Entry(unit_id, entry))
+                        if unit_id.base_id == base_id && unit_id.index == index =>
+                    {
+                        AttributeValue::UnitRef(new_ids[&entry])
+                    }
+                    AttributeValue::DebugInfoRef(Reference::Entry(unit_id, entry))
+                        if unit_id.base_id == base_id && unit_id.index == partial =>
+                    {
+                        AttributeValue::UnitRef(entry)
+                    }
+                    AttributeValue::FileIndex(Some(file)) => {
+                        if partial_unit.line_program.is_none() {
+                            partial_unit.line_program = unit.line_program.empty_copy();
+                            if let Some(comp_dir) =
+                                unit.entries[unit.root.index].get(constants::DW_AT_comp_dir)
+                            {
+                                let root = partial_unit.root;
+                                partial_unit
+                                    .get_mut(root)
+                                    .set(constants::DW_AT_comp_dir, comp_dir.clone());
+                            }
+                        }
+                        let (path, directory) = unit.line_program.get_file(file);
+                        let info = *unit.line_program.get_file_info(file);
+                        let directory = unit.line_program.get_directory(directory).clone();
+                        let directory = partial_unit.line_program.add_directory(directory);
+                        AttributeValue::FileIndex(Some(partial_unit.line_program.add_file(
+                            path.clone(),
+                            directory,
+                            Some(info),
+                        )))
+                    }
+                    ref value => value.clone(),
+                };
+                attrs.push(Attribute {
+                    name: attr.name,
+                    value,
+                    form: None,
+                });
+            }
+            partial_unit.entries[new_ids[id].index].attrs = attrs;
+        }
+        new_ids[&id]
+    }
+
     /// Write the units to the given sections.
     ///
     /// `strings` must contain the `.debug_str` offsets of the corresponding
@@ -92,20 +771,11 @@ impl UnitTable {
             base_id: self.base_id,
             units: Vec::new(),
         };
+        let mut abbrev_tables = AbbreviationTables::new(self.share_abbreviations);
         for unit in &mut self.units {
-            // TODO: maybe share abbreviation tables
-            let abbrev_offset = sections.debug_abbrev.offset();
-            let mut abbrevs = AbbreviationTable::default();
-
-            offsets.units.push(unit.write(
-                sections,
-                abbrev_offset,
-                &mut abbrevs,
-                line_strings,
-                strings,
-            )?);
-
-            abbrevs.write(&mut sections.debug_abbrev)?;
+            offsets
+                .units
+                .push(unit.write(sections, &mut abbrev_tables, line_strings, strings)?);
         }
 
         write_section_refs(
@@ -153,6 +823,28 @@ pub struct Unit {
     pub ranges: RangeListTable,
     /// A table of location lists used by this unit.
     pub locations: LocationListTable,
+    /// The macro information for this unit.
+    ///
+    /// If this is not empty, then the `DW_AT_macros` attribute of the root entry
+    /// (or `DW_AT_GNU_macros` for DWARF version 4) is set when writing.
+    pub macros: MacroTable,
+    /// A function that selects the forms used to encode attribute values.
+    ///
+    /// If `None`, then the default form for each value is used.
+    pub form_selector: Option<FormSelector>,
+    /// Whether to preserve the encoding of entries that were converted from
+    /// the input.
+    ///
+    /// If `true`, then converted entries are written using their original
+    /// abbreviation codes, attribute forms, and `DW_AT_sibling` position, and
+    /// base types are not moved to the start of the unit. Forms that cannot
+    /// encode the current value of an attribute are replaced using the form
+    /// selector or the default form. This allows an unmodified unit to be
+    /// written with the same `.debug_info` and `.debug_abbrev` contents as the
+    /// input, provided that the other sections are also unchanged.
+    ///
+    /// Defaults to `false`.
+    pub preserve_encoding: bool,
     /// All entries in this unit. The order is unrelated to the tree order.
     // Requirements:
     // - entries form a tree
@@ -164,6 +856,9 @@ pub struct Unit {
     entries: Vec<DebuggingInformationEntry>,
     /// The index of the root entry in entries.
     root: UnitEntryId,
+    /// The raw data to write instead of the entries, if this unit was
+    /// copied without conversion.
+    verbatim: Option<VerbatimUnit>,
 }
 
 impl Unit {
@@ -185,8 +880,12 @@ impl Unit {
             line_program,
             ranges,
             locations,
+            macros: MacroTable::default(),
+            form_selector: None,
+            preserve_encoding: false,
             entries,
             root,
+            verbatim: None,
         }
     }
 
@@ -214,6 +913,185 @@ impl Unit {
         self.encoding.format
     }
 
+    /// Return true if this unit was copied from the input without conversion.
+    ///
+    /// The entries of a verbatim unit are not available. Only its root entry
+    /// exists, and it has no attributes or children. Changes to the entries
+    /// and the line program of a verbatim unit are not written, and
+    /// `UnitTable::discard_addresses` does not modify it.
+    ///
+    /// See `Dwarf::from_modified`.
+    #[inline]
+    pub fn is_verbatim(&self) -> bool {
+        self.verbatim.is_some()
+    }
+
+    /// Change the DWARF version of this unit and its line program.
+    ///
+    /// Attribute forms are selected based on the version when writing, so
+    /// most attribute values do not need to be changed. For example, range
+    /// lists are written to `.debug_rnglists` instead of `.debug_ranges`,
+    /// and section offsets use `DW_FORM_sec_offset`.
+    ///
+    /// When changing to version 5, this also renames the GNU extensions that
+    /// were standardized in DWARF 5, such as `DW_TAG_GNU_call_site`. If the
+    /// unit does not have a `form_selector`, then one is set that selects
+    /// `DW_FORM_strx` for `AttributeValue::StringRef` values, so that they
+    /// are written using the `.debug_str_offsets` section. An existing
+    /// `form_selector` is not changed.
+    ///
+    /// Macro information in `.debug_macinfo` is not converted, since the
+    /// unit only contains its offset. Changing to version 5 returns
+    /// `Error::UnsupportedMacinfo` if the unit has a `DW_AT_macro_info`
+    /// attribute. The macro information can instead be added to `Unit::macros`,
+    /// and the attribute deleted. Units with version 4 or later that are
+    /// converted from `read` units have already been changed in this way.
+    ///
+    /// When changing to an earlier version, this renames the DWARF 5 tags
+    /// and attributes back to the GNU extensions, and replaces references to
+    /// `line_strings`, since `DW_FORM_line_strp` requires version 5. Values
+    /// that require version 5 forms are also replaced: `AttributeValue::ImplicitConst`
+    /// with `AttributeValue::Sdata`, and `AttributeValue::Data16` for
+    /// `DW_AT_const_value` with `AttributeValue::Block`. Changing to an earlier
+    /// version returns `Error::NeedVersion(5)` for `AttributeValue::Data16`
+    /// values of other attributes. The `form_selector` is not changed, so it
+    /// must not select forms that require version 5.
+    ///
+    /// The unit is not modified if an error is returned.
+    pub fn set_version(
+        &mut self,
+        version: u16,
+        line_strings: &LineStringTable,
+        strings: &mut StringTable,
+    ) -> Result<()> {
+        self.check_version(version)?;
+        self.encoding.version = version;
+        self.line_program
+            .set_version(version, line_strings, strings);
+        if version >= 5 && self.form_selector.is_none() {
+            self.form_selector = Some(select_string_index);
+        }
+        for entry in &mut self.entries {
+            entry.rename_gnu_extensions(version);
+            if version < 5 {
+                for attr in &mut entry.attrs {
+                    match attr.value {
+                        AttributeValue::LineStringRef(id) => {
+                            attr.value =
+                                AttributeValue::StringRef(strings.add(line_strings.get(id)));
+                        }
+                        AttributeValue::ImplicitConst(val) => {
+                            attr.value = AttributeValue::Sdata(val);
+                        }
+                        AttributeValue::Data16(ref val) => {
+                            attr.value = AttributeValue::Block(val.to_vec());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `set_version` can change this unit to the given version.
+    pub(crate) fn check_version(&self, version: u16) -> Result<()> {
+        if !(2..=5).contains(&version) || (self.is_verbatim() && version != self.version()) {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        if version >= 5
+            && self.entries.iter().any(|entry| {
+                entry.attrs.iter().any(|attr| {
+                    attr.name == constants::DW_AT_macro_info
+                        || matches!(attr.value, AttributeValue::DebugMacinfoRef(_))
+                })
+            })
+        {
+            return Err(Error::UnsupportedMacinfo);
+        }
+        if version < 5
+            && self.entries.iter().any(|entry| {
+                entry.attrs.iter().any(|attr| {
+                    attr.name != constants::DW_AT_const_value
+                        && matches!(attr.value, AttributeValue::Data16(_))
+                })
+            })
+        {
+            return Err(Error::NeedVersion(5));
+        }
+        Ok(())
+    }
+
+    /// Sort the directory and file entries of the line number program by their
+    /// path names.
+    ///
+    /// Entries are normally written in the order in which they were added.
+    /// Sorting them ensures that the output does not depend on that order.
+    ///
+    /// `AttributeValue::FileIndex` values and `Macro::StartFile` entries are
+    /// updated to refer to the new file ids. Any other `DirectoryId` or `FileId`
+    /// values for the line number program are invalidated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line number program sequence has begun.
+    pub fn sort_files(&mut self, line_strings: &LineStringTable, strings: &StringTable) {
+        let file_ids = self.line_program.sort_files(line_strings, strings);
+        let map_file = |file: FileId| match file.index() {
+            Some(index) => file_ids[index],
+            None => file,
+        };
+        for entry in &mut self.entries {
+            for attr in &mut entry.attrs {
+                if let AttributeValue::FileIndex(Some(ref mut file)) = attr.value {
+                    *file = map_file(*file);
+                }
+            }
+        }
+        self.macros.map_files(map_file);
+    }
+
+    /// Choose whether each string attribute value is stored inline or in the
+    /// `.debug_str` section.
+    ///
+    /// `f` is called with the attribute name and the string for each
+    /// `AttributeValue::String` and `AttributeValue::StringRef` value in the
+    /// unit. If it returns `true`, then the value is replaced with a
+    /// `StringRef` for the string in `strings`, which is written using
+    /// `DW_FORM_strp`, or an index form chosen by the `FormSelector`.
+    /// Otherwise, the value is replaced with a `String`, which is written using
+    /// `DW_FORM_string`.
+    ///
+    /// `strings` must be the table that the `StringRef` values in this unit
+    /// refer to.
+    pub fn select_string_forms<F>(&mut self, strings: &mut StringTable, mut f: F)
+    where
+        F: FnMut(constants::DwAt, &[u8]) -> bool,
+    {
+        for entry in &mut self.entries {
+            for attr in &mut entry.attrs {
+                let value = match attr.value {
+                    AttributeValue::String(ref string) => {
+                        if !f(attr.name, string) {
+                            continue;
+                        }
+                        AttributeValue::StringRef(strings.add(&string[..]))
+                    }
+                    AttributeValue::StringRef(id) => {
+                        let string = strings.get(id);
+                        if f(attr.name, string) {
+                            continue;
+                        }
+                        AttributeValue::String(string.to_vec())
+                    }
+                    _ => continue,
+                };
+                attr.value = value;
+                attr.form = None;
+            }
+        }
+    }
+
     /// Return the number of `DebuggingInformationEntry`s created for this unit.
     ///
     /// This includes entries that no longer have a parent.
@@ -222,6 +1100,44 @@ impl Unit {
         self.entries.len()
     }
 
+    /// Return an estimate of the number of bytes of heap memory used by this unit.
+    ///
+    /// See `UnitTable::allocated_bytes`.
+    pub fn allocated_bytes(&self) -> usize {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let attrs = entry
+                    .attrs
+                    .iter()
+                    .map(|attr| match attr.value {
+                        AttributeValue::Block(ref data) | AttributeValue::String(ref data) => {
+                            data.capacity()
+                        }
+                        AttributeValue::Exprloc(ref expression) => expression.allocated_bytes(),
+                        _ => 0,
+                    })
+                    .sum::<usize>();
+                vec_allocated_bytes(&entry.attrs) + attrs + vec_allocated_bytes(&entry.children)
+            })
+            .sum::<usize>();
+        let verbatim = self.verbatim.as_ref().map_or(0, |verbatim| {
+            verbatim.data.capacity()
+                + verbatim.abbreviations.capacity()
+                + verbatim.line_program.capacity()
+                + vec_allocated_bytes(&verbatim.fixups)
+                + vec_allocated_bytes(&verbatim.line_program_fixups)
+        });
+        vec_allocated_bytes(&self.entries)
+            + entries
+            + self.line_program.allocated_bytes()
+            + self.ranges.allocated_bytes()
+            + self.locations.allocated_bytes()
+            + self.macros.allocated_bytes()
+            + verbatim
+    }
+
     /// Return the id of the root entry.
     #[inline]
     pub fn root(&self) -> UnitEntryId {
@@ -263,35 +1179,305 @@ impl Unit {
         &mut self.entries[id.index]
     }
 
-    /// Return true if `self.line_program` is used by a DIE.
-    fn line_program_in_use(&self) -> bool {
-        if self.line_program.is_none() {
-            return false;
-        }
-        if !self.line_program.is_empty() {
-            return true;
+    /// Move an entry and all of its children to a new parent.
+    ///
+    /// The entry is removed from the children of its current parent, and added
+    /// as the last child of `parent`. This may also be used to reattach an
+    /// entry that was removed with `DebuggingInformationEntry::delete_child`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` or `parent` is invalid, if `id` is the root entry, or if
+    /// `parent` is `id` or one of its descendants.
+    pub fn set_parent(&mut self, id: UnitEntryId, parent: UnitEntryId) {
+        debug_assert_eq!(self.base_id, id.base_id);
+        debug_assert_eq!(self.base_id, parent.base_id);
+        assert_ne!(id, self.root, "cannot move the root entry");
+        let mut ancestor = Some(parent);
+        while let Some(ancestor_id) = ancestor {
+            assert_ne!(ancestor_id, id, "cannot move an entry into itself");
+            ancestor = self.entries[ancestor_id.index].parent;
         }
-
-        for entry in &self.entries {
-            for attr in &entry.attrs {
-                if let AttributeValue::FileIndex(Some(_)) = attr.value {
-                    return true;
-                }
-            }
+        if let Some(old_parent) = self.entries[id.index].parent {
+            self.entries[old_parent.index]
+                .children
+                .retain(|&child| child != id);
+        }
+        self.entries[id.index].parent = Some(parent);
+        self.entries[parent.index].children.push(id);
+    }
+
+    /// Delete all entries for which `f` returns true, along with their children.
+    ///
+    /// The root entry is never deleted.
+    ///
+    /// Attributes in this unit that refer to a deleted entry are also deleted.
+    /// Use `UnitTable::delete_entries` if entries may be referred to by other units.
+    pub fn delete_entries<F>(&mut self, f: F)
+    where
+        F: FnMut(&DebuggingInformationEntry) -> bool,
+    {
+        self.detach_entries(f);
+        let live = self.live_entries();
+        self.delete_references(&mut |unit, entry| unit.is_some() || live[entry.index]);
+    }
+
+    /// Delete all attributes for which `f` returns true.
+    pub fn delete_attributes<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&DebuggingInformationEntry, &Attribute) -> bool,
+    {
+        for entry in &mut self.entries {
+            let delete: Vec<bool> = entry.attrs.iter().map(|attr| f(entry, attr)).collect();
+            let mut delete = delete.into_iter();
+            entry.attrs.retain(|_| !delete.next().unwrap());
+        }
+    }
+
+    /// Delete or tombstone the debugging information for addresses for which
+    /// `live` returns false.
+    ///
+    /// See `UnitTable::discard_addresses`.
+    fn discard_addresses<F>(&mut self, live: &mut F, policy: TombstonePolicy)
+    where
+        F: FnMut(Address) -> bool,
+    {
+        if self.is_verbatim() {
+            return;
+        }
+        self.line_program.discard_addresses(live, policy);
+
+        let base = match self.entries[self.root.index].get(constants::DW_AT_low_pc) {
+            Some(AttributeValue::Address(address)) => Some(*address),
+            _ => None,
+        };
+        let mut ranges = RangeListTable::default();
+        let mut locations = LocationListTable::default();
+        let mut dead = vec![false; self.entries.len()];
+        for entry in &mut self.entries {
+            let mut discard = false;
+            let attrs = mem::take(&mut entry.attrs);
+            for mut attr in attrs {
+                match attr.value {
+                    AttributeValue::Address(address)
+                        if attr.name == constants::DW_AT_low_pc && !live(address) =>
+                    {
+                        discard = true;
+                    }
+                    AttributeValue::RangeListRef(id) => {
+                        let range_list = self.ranges.get(id);
+                        let live_list = range_list.discard_addresses(base, live);
+                        if live_list.0.is_empty() && !range_list.0.is_empty() {
+                            discard = true;
+                        }
+                        attr.value = AttributeValue::RangeListRef(ranges.add(live_list));
+                    }
+                    AttributeValue::LocationListRef(id) => {
+                        let live_list =
+                            self.locations.get(id).discard_addresses(base, live, policy);
+                        if live_list.0.is_empty() {
+                            continue;
+                        }
+                        attr.value = AttributeValue::LocationListRef(locations.add(live_list));
+                    }
+                    AttributeValue::Exprloc(ref mut expression) => {
+                        let keep = expression.discard_addresses(live, policy);
+                        if !keep {
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+                entry.attrs.push(attr);
+            }
+
+            if discard {
+                match policy {
+                    TombstonePolicy::Delete => dead[entry.id.index] = true,
+                    TombstonePolicy::Address(tombstone) => {
+                        for attr in &mut entry.attrs {
+                            if attr.name == constants::DW_AT_low_pc
+                                || attr.name == constants::DW_AT_high_pc
+                            {
+                                if let AttributeValue::Address(ref mut address) = attr.value {
+                                    *address = Address::Constant(tombstone);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.ranges = ranges;
+        self.locations = locations;
+        self.detach_entries(|entry| dead[entry.id.index]);
+    }
+
+    /// Add the ids of an entry and its descendants in depth-first order.
+    fn subtree(&self, id: UnitEntryId, ids: &mut Vec<UnitEntryId>) {
+        ids.push(id);
+        for child in &self.entries[id.index].children {
+            self.subtree(*child, ids);
+        }
+    }
+
+    /// Replace the unit ids in references to entries in other units.
+    fn convert_unit_ids<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(UnitId) -> UnitId,
+    {
+        let mut locations = LocationListTable::default();
+        for entry in &mut self.entries {
+            for attr in &mut entry.attrs {
+                match attr.value {
+                    AttributeValue::DebugInfoRef(Reference::Entry(ref mut unit, _)) => {
+                        *unit = f(*unit);
+                    }
+                    AttributeValue::Exprloc(ref mut expression) => {
+                        expression.convert_unit_ids(f);
+                    }
+                    AttributeValue::LocationListRef(ref mut id) => {
+                        let mut location_list = self.locations.get(*id).clone();
+                        for location in &mut location_list.0 {
+                            if let Some(data) = location.data_mut() {
+                                data.convert_unit_ids(f);
+                            }
+                        }
+                        *id = locations.add(location_list);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.locations = locations;
+    }
+
+    /// Remove the entries for which `f` returns true from the tree.
+    fn detach_entries<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&DebuggingInformationEntry) -> bool,
+    {
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let mut children = mem::take(&mut self.entries[id.index].children);
+            children.retain(|child| !f(&self.entries[child.index]));
+            stack.extend(children.iter().copied());
+            self.entries[id.index].children = children;
+        }
+    }
+
+    /// Return a flag for each entry indicating whether it is reachable from the root.
+    fn live_entries(&self) -> Vec<bool> {
+        let mut live = vec![false; self.entries.len()];
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            live[id.index] = true;
+            stack.extend(self.entries[id.index].children.iter().copied());
+        }
+        live
+    }
+
+    /// Delete all attributes that refer to an entry for which `live` returns false.
+    ///
+    /// The unit is `None` for references to entries in the same unit.
+    fn delete_references<F>(&mut self, live: &mut F)
+    where
+        F: FnMut(Option<UnitId>, UnitEntryId) -> bool,
+    {
+        // Location lists are rebuilt so that lists which are only used by the
+        // deleted attributes are not written.
+        let mut locations = LocationListTable::default();
+        for entry in &mut self.entries {
+            let attrs = mem::take(&mut entry.attrs);
+            for mut attr in attrs {
+                let keep = match attr.value {
+                    AttributeValue::UnitRef(entry) => live(None, entry),
+                    AttributeValue::DebugInfoRef(Reference::Entry(unit, entry)) => {
+                        live(Some(unit), entry)
+                    }
+                    AttributeValue::Exprloc(ref expression) => expression.all_references(live),
+                    AttributeValue::LocationListRef(ref mut id) => {
+                        let location_list = self.locations.get(*id);
+                        let keep = location_list
+                            .0
+                            .iter()
+                            .filter_map(Location::data)
+                            .all(|expression| expression.all_references(live));
+                        if keep {
+                            *id = locations.add(location_list.clone());
+                        }
+                        keep
+                    }
+                    _ => true,
+                };
+                if keep {
+                    entry.attrs.push(attr);
+                }
+            }
+        }
+        self.locations = locations;
+    }
+
+    /// Return true if the root entry is a `DW_TAG_partial_unit`.
+    pub(crate) fn is_partial(&self) -> bool {
+        self.entries[self.root.index].tag == constants::DW_TAG_partial_unit
+    }
+
+    /// Return true if `self.line_program` is used by a DIE.
+    fn line_program_in_use(&self) -> bool {
+        if self.line_program.is_none() {
+            return false;
+        }
+        if !self.line_program.is_empty() || self.macros.uses_files() {
+            return true;
+        }
+
+        for entry in &self.entries {
+            for attr in &entry.attrs {
+                if let AttributeValue::FileIndex(Some(_)) = attr.value {
+                    return true;
+                }
+            }
         }
 
         false
     }
 
     /// Write the unit to the given sections.
+    ///
+    /// The unit's abbreviation table is written to `.debug_abbrev` via `abbrev_tables`.
     pub(crate) fn write<W: Writer>(
         &mut self,
         sections: &mut Sections<W>,
-        abbrev_offset: DebugAbbrevOffset,
-        abbrevs: &mut AbbreviationTable,
+        abbrev_tables: &mut AbbreviationTables,
         line_strings: &DebugLineStrOffsets,
         strings: &DebugStrOffsets,
     ) -> Result<UnitOffsets> {
+        if let Some(ref verbatim) = self.verbatim {
+            let mut offsets = UnitOffsets {
+                base_id: self.base_id,
+                unit: sections.debug_info.offset(),
+                length: 0,
+                entries: vec![EntryOffset::none(); self.entries.len()],
+                strings: IndexSet::new(),
+                addresses: IndexSet::new(),
+            };
+            offsets.entries[self.root.index] = EntryOffset {
+                offset: DebugInfoOffset(offsets.unit.0 + verbatim.root_offset),
+                abbrev: 0,
+            };
+            verbatim.write(
+                sections,
+                self.encoding,
+                &self.ranges,
+                &self.locations,
+                line_strings,
+                strings,
+            )?;
+            offsets.length = sections.debug_info.len() - offsets.unit.0;
+            return Ok(offsets);
+        }
+
         let line_program = if self.line_program_in_use() {
             self.entries[self.root.index]
                 .set(constants::DW_AT_stmt_list, AttributeValue::LineProgramRef);
@@ -306,62 +1492,176 @@ impl Unit {
             None
         };
 
+        if !self.macros.is_empty() {
+            let macro_offsets = self.macros.write(
+                &mut sections.debug_macro,
+                self.encoding,
+                line_program,
+                strings,
+            )?;
+            let name = if self.version() >= 5 {
+                self.entries[self.root.index].delete(constants::DW_AT_GNU_macros);
+                constants::DW_AT_macros
+            } else {
+                self.entries[self.root.index].delete(constants::DW_AT_macros);
+                constants::DW_AT_GNU_macros
+            };
+            self.entries[self.root.index].set(
+                name,
+                AttributeValue::DebugMacroRef(macro_offsets.get(self.macros.root())),
+            );
+        }
+
+        // Collect the values that the form selector has chosen to encode using
+        // index forms, and write the tables that the indices refer to.
+        let IndexValues {
+            strings: index_strings,
+            addresses: index_addresses,
+            rnglistx: use_rnglistx,
+            loclistx: use_loclistx,
+        } = self.index_values()?;
+        if index_strings.is_empty() {
+            self.entries[self.root.index].delete(constants::DW_AT_str_offsets_base);
+        } else {
+            let base = sections.debug_str_offsets.write_table(
+                self.format(),
+                index_strings.iter().copied(),
+                strings,
+            )?;
+            self.entries[self.root.index].set(
+                constants::DW_AT_str_offsets_base,
+                AttributeValue::DebugStrOffsetsBase(base),
+            );
+        }
+        if index_addresses.is_empty() {
+            self.entries[self.root.index].delete(constants::DW_AT_addr_base);
+        } else {
+            let base = sections
+                .debug_addr
+                .write_table(self.encoding, index_addresses.iter().copied())?;
+            self.entries[self.root.index].set(
+                constants::DW_AT_addr_base,
+                AttributeValue::DebugAddrBase(base),
+            );
+        }
+        // The bases of the offset arrays follow the list table headers.
+        let list_header_size = self.format().initial_length_size() as usize + 8;
+        if use_rnglistx {
+            let base = sections.debug_rnglists.offset().0 + list_header_size;
+            self.entries[self.root.index].set(
+                constants::DW_AT_rnglists_base,
+                AttributeValue::DebugRngListsBase(DebugRngListsBase(base)),
+            );
+        } else {
+            self.entries[self.root.index].delete(constants::DW_AT_rnglists_base);
+        }
+        let range_lists = self.ranges.write(sections, self.encoding, use_rnglistx)?;
+        // Location lists can't be written until we have DIE offsets, but the
+        // base of their offset array must be known before calculating offsets.
+        let loclists_base = sections.debug_loclists.offset().0 + list_header_size;
+        if use_loclistx {
+            self.entries[self.root.index].set(
+                constants::DW_AT_loclists_base,
+                AttributeValue::DebugLocListsBase(DebugLocListsBase(loclists_base)),
+            );
+        } else {
+            self.entries[self.root.index].delete(constants::DW_AT_loclists_base);
+        }
+
         // TODO: use .debug_types for type units in DWARF v4.
         let w = &mut sections.debug_info;
 
         let mut offsets = UnitOffsets {
             base_id: self.base_id,
             unit: w.offset(),
+            length: 0,
             // Entries can be written in any order, so create the complete vec now.
             entries: vec![EntryOffset::none(); self.entries.len()],
+            strings: index_strings,
+            addresses: index_addresses,
         };
 
         let length_offset = w.write_initial_length(self.format())?;
         let length_base = w.len();
 
+        // The abbreviation offset isn't known until the abbreviations have been
+        // calculated, so write a placeholder for now.
         w.write_u16(self.version())?;
+        let abbrev_offset_offset;
         if 2 <= self.version() && self.version() <= 4 {
-            w.write_offset(
-                abbrev_offset.0,
-                SectionId::DebugAbbrev,
-                self.format().word_size(),
-            )?;
+            abbrev_offset_offset = w.len();
+            w.write_udata(0, self.format().word_size())?;
             w.write_u8(self.address_size())?;
         } else if self.version() == 5 {
-            w.write_u8(constants::DW_UT_compile.0)?;
+            let unit_type = if self.is_partial() {
+                constants::DW_UT_partial
+            } else {
+                constants::DW_UT_compile
+            };
+            w.write_u8(unit_type.0)?;
             w.write_u8(self.address_size())?;
-            w.write_offset(
-                abbrev_offset.0,
-                SectionId::DebugAbbrev,
-                self.format().word_size(),
-            )?;
+            abbrev_offset_offset = w.len();
+            w.write_udata(0, self.format().word_size())?;
         } else {
             return Err(Error::UnsupportedVersion(self.version()));
         }
 
         // Calculate all DIE offsets, so that we are able to output references to them.
         // However, references to base types in expressions use ULEB128, so base types
-        // must be moved to the front before we can calculate offsets.
-        self.reorder_base_types();
-        let mut offset = w.len();
+        // must be moved to the front before we can calculate offsets, unless the
+        // order of the entries is being preserved.
+        if !self.preserve_encoding {
+            self.reorder_base_types();
+        }
+        let mut abbrevs = AbbreviationTable::default();
         self.entries[self.root.index].calculate_offsets(
             self,
-            &mut offset,
+            &mut w.len(),
             &mut offsets,
-            abbrevs,
+            &mut abbrevs,
+        )?;
+        if self.form_selector.is_some() || self.preserve_encoding {
+            // The size of a `DW_FORM_ref_udata` reference depends on the offset
+            // of the entry it refers to, and so does the size of a base type
+            // reference that has not been moved to the front, so repeat until
+            // the offsets are stable.
+            // Sizes can only grow with each pass, so this terminates.
+            loop {
+                let previous = offsets.entries.clone();
+                abbrevs = AbbreviationTable::default();
+                self.entries[self.root.index].calculate_offsets(
+                    self,
+                    &mut w.len(),
+                    &mut offsets,
+                    &mut abbrevs,
+                )?;
+                if offsets.entries == previous {
+                    break;
+                }
+            }
+        }
+        let abbrev_offset = abbrev_tables.write(&mut sections.debug_abbrev, &abbrevs)?;
+        let w = &mut sections.debug_info;
+        w.write_offset_at(
+            abbrev_offset_offset,
+            abbrev_offset.0,
+            SectionId::DebugAbbrev,
+            self.format().word_size(),
         )?;
 
-        let range_lists = self.ranges.write(sections, self.encoding)?;
-        // Location lists can't be written until we have DIE offsets.
-        let loc_lists = self
-            .locations
-            .write(sections, self.encoding, Some(&offsets))?;
+        debug_assert!(
+            !use_loclistx || sections.debug_loclists.offset().0 == loclists_base - list_header_size
+        );
+        let loc_lists =
+            self.locations
+                .write(sections, self.encoding, Some(&offsets), use_loclistx)?;
 
         let w = &mut sections.debug_info;
         let mut unit_refs = Vec::new();
         self.entries[self.root.index].write(
             w,
             &mut sections.debug_info_refs,
+            &mut sections.debug_line_refs,
             &mut unit_refs,
             self,
             &mut offsets,
@@ -374,6 +1674,7 @@ impl Unit {
 
         let length = (w.len() - length_base) as u64;
         w.write_initial_length_at(length_offset, length, self.format())?;
+        offsets.length = w.len() - offsets.unit.0;
 
         for (offset, entry) in unit_refs {
             // This does not need relocation.
@@ -387,6 +1688,115 @@ impl Unit {
         Ok(offsets)
     }
 
+    /// Collect the values that are encoded using index forms.
+    fn index_values(&self) -> Result<IndexValues> {
+        let mut values = IndexValues::default();
+        if self.form_selector.is_none() && !self.preserve_encoding {
+            return Ok(values);
+        }
+        for entry in &self.entries {
+            for attr in &entry.attrs {
+                if attr.selected_form(self)?.is_none() {
+                    continue;
+                }
+                match attr.value {
+                    AttributeValue::StringRef(id) => {
+                        values.strings.insert(id);
+                    }
+                    AttributeValue::Address(address) => {
+                        values.addresses.insert(address);
+                    }
+                    AttributeValue::RangeListRef(_) => values.rnglistx = true,
+                    AttributeValue::LocationListRef(_) => values.loclistx = true,
+                    _ => {}
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Estimate the encoded size of each entry without writing the unit.
+    ///
+    /// For each entry, `f` is called with the entry, the size of the entry
+    /// excluding its attributes, and the name, form, and size of each of its
+    /// attributes, including `DW_AT_sibling`.
+    ///
+    /// Attributes that are only added by `Unit::write`, such as
+    /// `DW_AT_stmt_list`, are not included. Units that were copied verbatim
+    /// are skipped.
+    pub(crate) fn estimate_sizes<'a, F>(&'a self, mut f: F) -> Result<()>
+    where
+        F: FnMut(
+            &'a DebuggingInformationEntry,
+            usize,
+            &[(constants::DwAt, constants::DwForm, usize)],
+        ),
+    {
+        if self.verbatim.is_some() {
+            return Ok(());
+        }
+        let values = self.index_values()?;
+        let mut offsets = UnitOffsets {
+            base_id: self.base_id,
+            unit: DebugInfoOffset(0),
+            length: 0,
+            entries: vec![EntryOffset::none(); self.entries.len()],
+            strings: values.strings,
+            addresses: values.addresses,
+        };
+        let header_size = self.format().initial_length_size() as usize
+            + 2
+            + self.format().word_size() as usize
+            + 1
+            + if self.version() >= 5 { 1 } else { 0 };
+        // Repeat until the offsets are stable, as for `Unit::write`.
+        let root = &self.entries[self.root.index];
+        loop {
+            let previous = offsets.entries.clone();
+            let mut offset = header_size;
+            let mut abbrevs = AbbreviationTable::default();
+            root.calculate_offsets(self, &mut offset, &mut offsets, &mut abbrevs)?;
+            if (self.form_selector.is_none() && !self.preserve_encoding)
+                || offsets.entries == previous
+            {
+                break;
+            }
+        }
+
+        let mut attrs = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let entry = &self.entries[id.index];
+            attrs.clear();
+            for attr in &entry.attrs {
+                let (form, size) = match attr.selected_form(self)? {
+                    Some(form) => (form, attr.value.selected_size(form, &offsets)),
+                    None => (
+                        attr.value.form(self.encoding())?,
+                        attr.value.size(self, &offsets),
+                    ),
+                };
+                attrs.push((attr.name, form, size));
+            }
+            if let Some(index) = entry.sibling_index(self) {
+                let form = match self.format() {
+                    Format::Dwarf32 => constants::DW_FORM_ref4,
+                    Format::Dwarf64 => constants::DW_FORM_ref8,
+                };
+                let size = self.format().word_size() as usize;
+                attrs.insert(index, (constants::DW_AT_sibling, form, size));
+            }
+            let mut size = uleb128_size(offsets.abbrev(id));
+            if !entry.children.is_empty() {
+                // Null child
+                size += 1;
+            }
+            f(entry, size, &attrs);
+            stack.extend(entry.children.iter().rev());
+        }
+        Ok(())
+    }
+
     /// Reorder base types to come first so that typed stack operations
     /// can get their offset.
     fn reorder_base_types(&mut self) {
@@ -419,6 +1829,10 @@ pub struct DebuggingInformationEntry {
     tag: constants::DwTag,
     /// Whether to emit `DW_AT_sibling`.
     sibling: bool,
+    /// The index in `attrs` at which `DW_AT_sibling` was in the input.
+    sibling_index: usize,
+    /// The abbreviation code of the entry in the input.
+    abbrev_code: Option<u64>,
     attrs: Vec<Attribute>,
     children: Vec<UnitEntryId>,
 }
@@ -442,6 +1856,8 @@ impl DebuggingInformationEntry {
             parent,
             tag,
             sibling: false,
+            sibling_index: 0,
+            abbrev_code: None,
             attrs: Vec::new(),
             children: Vec::new(),
         });
@@ -453,6 +1869,36 @@ impl DebuggingInformationEntry {
         id
     }
 
+    /// Rename GNU extensions that were standardized in DWARF 5.
+    ///
+    /// For version 5, the GNU extensions are renamed to their DWARF 5
+    /// equivalents. For earlier versions, the reverse is done.
+    fn rename_gnu_extensions(&mut self, version: u16) {
+        fn rename<T: Copy + PartialEq>(names: &[(T, T)], name: &mut T, version: u16) {
+            for &(gnu, standard) in names {
+                let (from, to) = if version >= 5 {
+                    (gnu, standard)
+                } else {
+                    (standard, gnu)
+                };
+                if *name == from {
+                    *name = to;
+                    return;
+                }
+            }
+        }
+
+        let call_site =
+            self.tag == constants::DW_TAG_GNU_call_site || self.tag == constants::DW_TAG_call_site;
+        rename(&GNU_TAGS, &mut self.tag, version);
+        for attr in &mut self.attrs {
+            if call_site {
+                rename(&GNU_CALL_SITE_ATTRIBUTES, &mut attr.name, version);
+            }
+            rename(&GNU_ATTRIBUTES, &mut attr.name, version);
+        }
+    }
+
     /// Return the id of this entry.
     #[inline]
     pub fn id(&self) -> UnitEntryId {
@@ -526,7 +1972,11 @@ impl DebuggingInformationEntry {
             attr.value = value;
             return;
         }
-        self.attrs.push(Attribute { name, value });
+        self.attrs.push(Attribute {
+            name,
+            value,
+            form: None,
+        });
     }
 
     /// Delete an attribute.
@@ -549,20 +1999,34 @@ impl DebuggingInformationEntry {
         self.children.retain(|&child| child != id);
     }
 
+    /// Return the index in `attrs` before which `DW_AT_sibling` is written,
+    /// or `None` if it is not written.
+    fn sibling_index(&self, unit: &Unit) -> Option<usize> {
+        if !self.sibling || self.children.is_empty() {
+            None
+        } else if unit.preserve_encoding {
+            Some(self.sibling_index.min(self.attrs.len()))
+        } else {
+            Some(0)
+        }
+    }
+
     /// Return the type abbreviation for this DIE.
-    fn abbreviation(&self, encoding: Encoding) -> Result<Abbreviation> {
+    fn abbreviation(&self, unit: &Unit) -> Result<Abbreviation> {
         let mut attrs = Vec::new();
+        for attr in &self.attrs {
+            attrs.push(attr.specification(unit)?);
+        }
 
-        if self.sibling && !self.children.is_empty() {
-            let form = match encoding.format {
+        if let Some(index) = self.sibling_index(unit) {
+            let form = match unit.format() {
                 Format::Dwarf32 => constants::DW_FORM_ref4,
                 Format::Dwarf64 => constants::DW_FORM_ref8,
             };
-            attrs.push(AttributeSpecification::new(constants::DW_AT_sibling, form));
-        }
-
-        for attr in &self.attrs {
-            attrs.push(attr.specification(encoding)?);
+            attrs.insert(
+                index,
+                AttributeSpecification::new(constants::DW_AT_sibling, form, None),
+            );
         }
 
         Ok(Abbreviation::new(
@@ -580,8 +2044,14 @@ impl DebuggingInformationEntry {
         abbrevs: &mut AbbreviationTable,
     ) -> Result<()> {
         offsets.entries[self.id.index].offset = DebugInfoOffset(*offset);
-        offsets.entries[self.id.index].abbrev = abbrevs.add(self.abbreviation(unit.encoding())?);
-        *offset += self.size(unit, offsets);
+        let code = if unit.preserve_encoding {
+            self.abbrev_code
+        } else {
+            None
+        };
+        offsets.entries[self.id.index].abbrev =
+            abbrevs.add_with_code(self.abbreviation(unit)?, code);
+        *offset += self.size(unit, offsets)?;
         if !self.children.is_empty() {
             for child in &self.children {
                 unit.entries[child.index].calculate_offsets(unit, offset, offsets, abbrevs)?;
@@ -592,15 +2062,18 @@ impl DebuggingInformationEntry {
         Ok(())
     }
 
-    fn size(&self, unit: &Unit, offsets: &UnitOffsets) -> usize {
+    fn size(&self, unit: &Unit, offsets: &UnitOffsets) -> Result<usize> {
         let mut size = uleb128_size(offsets.abbrev(self.id));
-        if self.sibling && !self.children.is_empty() {
+        if self.sibling_index(unit).is_some() {
             size += unit.format().word_size() as usize;
         }
         for attr in &self.attrs {
-            size += attr.value.size(unit, offsets);
+            size += match attr.selected_form(unit)? {
+                Some(form) => attr.value.selected_size(form, offsets),
+                None => attr.value.size(unit, offsets),
+            };
         }
-        size
+        Ok(size)
     }
 
     /// Write the entry to the given sections.
@@ -608,6 +2081,7 @@ impl DebuggingInformationEntry {
         &self,
         w: &mut DebugInfo<W>,
         debug_info_refs: &mut Vec<DebugInfoReference>,
+        debug_line_refs: &mut Vec<DebugLineReference>,
         unit_refs: &mut Vec<(DebugInfoOffset, UnitEntryId)>,
         unit: &Unit,
         offsets: &mut UnitOffsets,
@@ -620,18 +2094,21 @@ impl DebuggingInformationEntry {
         debug_assert_eq!(offsets.debug_info_offset(self.id), w.offset());
         w.write_uleb128(offsets.abbrev(self.id))?;
 
-        let sibling_offset = if self.sibling && !self.children.is_empty() {
-            let offset = w.offset();
-            w.write_udata(0, unit.format().word_size())?;
-            Some(offset)
-        } else {
-            None
-        };
-
-        for attr in &self.attrs {
+        let sibling_index = self.sibling_index(unit);
+        let mut sibling_offset = None;
+        for (index, attr) in self.attrs.iter().enumerate() {
+            if sibling_index == Some(index) {
+                sibling_offset = Some(w.offset());
+                w.write_udata(0, unit.format().word_size())?;
+            }
+            if let Some(form) = attr.selected_form(unit)? {
+                attr.value.write_selected(w, form, offsets)?;
+                continue;
+            }
             attr.value.write(
                 w,
                 debug_info_refs,
+                debug_line_refs,
                 unit_refs,
                 unit,
                 offsets,
@@ -642,12 +2119,17 @@ impl DebuggingInformationEntry {
                 loc_lists,
             )?;
         }
+        if sibling_index == Some(self.attrs.len()) {
+            sibling_offset = Some(w.offset());
+            w.write_udata(0, unit.format().word_size())?;
+        }
 
         if !self.children.is_empty() {
             for child in &self.children {
                 unit.entries[child.index].write(
                     w,
                     debug_info_refs,
+                    debug_line_refs,
                     unit_refs,
                     unit,
                     offsets,
@@ -671,14 +2153,168 @@ impl DebuggingInformationEntry {
     }
 }
 
+/// How to handle debugging information for addresses that have been discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TombstonePolicy {
+    /// Delete the entries and line sequences.
+    Delete,
+    /// Replace the addresses of the entries and line sequences with the given value.
+    ///
+    /// This is typically `0`, `!0`, or the lowest address of the discarded section.
+    Address(u64),
+}
+
+/// An entry in the value returned by `UnitTable::type_key`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TypeKeyEntry(constants::DwTag, Vec<(constants::DwAt, TypeKeyValue)>);
+
+/// An attribute value in the value returned by `UnitTable::type_key`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum TypeKeyValue {
+    Value(AttributeValue),
+    Reference(Vec<(constants::DwTag, Option<AttributeValue>)>),
+}
+
+/// An entry in the value returned by `UnitTable::partial_key`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct PartialKeyEntry(constants::DwTag, Vec<(constants::DwAt, PartialKeyValue)>);
+
+/// An attribute value in the value returned by `UnitTable::partial_key`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum PartialKeyValue {
+    Value(AttributeValue),
+    /// A reference to the entry at the given position within the same subtree.
+    Internal(usize),
+    /// A file index, identified by its path followed by the directories that
+    /// are needed to make it absolute.
+    File(Vec<LineString>),
+}
+
+/// Return true if the line string is known to be an absolute path.
+///
+/// Strings in the string tables are conservatively treated as relative.
+fn is_absolute_path(path: &LineString) -> bool {
+    match path {
+        LineString::String(path) => {
+            path.first() == Some(&b'/')
+                || path.first() == Some(&b'\\')
+                || (path.get(1) == Some(&b':')
+                    && path.first().map_or(false, u8::is_ascii_alphabetic))
+        }
+        _ => false,
+    }
+}
+
+/// Return true if the tag is for a type entry.
+pub(crate) fn is_type_tag(tag: constants::DwTag) -> bool {
+    matches!(
+        tag,
+        constants::DW_TAG_array_type
+            | constants::DW_TAG_atomic_type
+            | constants::DW_TAG_base_type
+            | constants::DW_TAG_class_type
+            | constants::DW_TAG_const_type
+            | constants::DW_TAG_enumeration_type
+            | constants::DW_TAG_file_type
+            | constants::DW_TAG_immutable_type
+            | constants::DW_TAG_interface_type
+            | constants::DW_TAG_packed_type
+            | constants::DW_TAG_pointer_type
+            | constants::DW_TAG_ptr_to_member_type
+            | constants::DW_TAG_reference_type
+            | constants::DW_TAG_restrict_type
+            | constants::DW_TAG_rvalue_reference_type
+            | constants::DW_TAG_set_type
+            | constants::DW_TAG_shared_type
+            | constants::DW_TAG_string_type
+            | constants::DW_TAG_structure_type
+            | constants::DW_TAG_subrange_type
+            | constants::DW_TAG_subroutine_type
+            | constants::DW_TAG_typedef
+            | constants::DW_TAG_union_type
+            | constants::DW_TAG_unspecified_type
+            | constants::DW_TAG_volatile_type
+    )
+}
+
+/// GNU tags and the DWARF 5 tags that they are equivalent to.
+const GNU_TAGS: [(constants::DwTag, constants::DwTag); 2] = [
+    (constants::DW_TAG_GNU_call_site, constants::DW_TAG_call_site),
+    (
+        constants::DW_TAG_GNU_call_site_parameter,
+        constants::DW_TAG_call_site_parameter,
+    ),
+];
+
+/// Attributes of `DW_TAG_GNU_call_site` and the equivalent attributes of
+/// `DW_TAG_call_site`.
+const GNU_CALL_SITE_ATTRIBUTES: [(constants::DwAt, constants::DwAt); 2] = [
+    (constants::DW_AT_low_pc, constants::DW_AT_call_return_pc),
+    (
+        constants::DW_AT_abstract_origin,
+        constants::DW_AT_call_origin,
+    ),
+];
+
+/// GNU attributes and the DWARF 5 attributes that they are equivalent to.
+const GNU_ATTRIBUTES: [(constants::DwAt, constants::DwAt); 10] = [
+    (
+        constants::DW_AT_GNU_call_site_value,
+        constants::DW_AT_call_value,
+    ),
+    (
+        constants::DW_AT_GNU_call_site_data_value,
+        constants::DW_AT_call_data_value,
+    ),
+    (
+        constants::DW_AT_GNU_call_site_target,
+        constants::DW_AT_call_target,
+    ),
+    (
+        constants::DW_AT_GNU_call_site_target_clobbered,
+        constants::DW_AT_call_target_clobbered,
+    ),
+    (
+        constants::DW_AT_GNU_tail_call,
+        constants::DW_AT_call_tail_call,
+    ),
+    (
+        constants::DW_AT_GNU_all_tail_call_sites,
+        constants::DW_AT_call_all_tail_calls,
+    ),
+    (
+        constants::DW_AT_GNU_all_call_sites,
+        constants::DW_AT_call_all_calls,
+    ),
+    (
+        constants::DW_AT_GNU_all_source_call_sites,
+        constants::DW_AT_call_all_source_calls,
+    ),
+    (constants::DW_AT_GNU_macros, constants::DW_AT_macros),
+    (constants::DW_AT_GNU_deleted, constants::DW_AT_deleted),
+];
+
 /// An attribute in a `DebuggingInformationEntry`, consisting of a name and
 /// associated value.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Attribute {
     name: constants::DwAt,
     value: AttributeValue,
+    /// The form of the attribute in the input, if it was converted.
+    ///
+    /// This is only used if `Unit::preserve_encoding` is set, and is not
+    /// compared for equality.
+    form: Option<constants::DwForm>,
 }
 
+impl PartialEq for Attribute {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value
+    }
+}
+
+impl Eq for Attribute {}
+
 impl Attribute {
     /// Get the name of this attribute.
     #[inline]
@@ -699,16 +2335,106 @@ impl Attribute {
     }
 
     /// Return the type specification for this attribute.
-    fn specification(&self, encoding: Encoding) -> Result<AttributeSpecification> {
+    fn specification(&self, unit: &Unit) -> Result<AttributeSpecification> {
+        let form = match self.selected_form(unit)? {
+            Some(form) => form,
+            None => self.value.form(unit.encoding())?,
+        };
+        let implicit_const_value = match self.value {
+            AttributeValue::ImplicitConst(val) => Some(val),
+            _ if form == constants::DW_FORM_implicit_const => {
+                self.value.constant_value().map(|val| val as i64)
+            }
+            _ => None,
+        };
         Ok(AttributeSpecification::new(
             self.name,
-            self.value.form(encoding)?,
+            form,
+            implicit_const_value,
         ))
     }
+
+    /// Return the form chosen by the unit's form selector, or the form of
+    /// the input if the unit preserves encodings, if it differs from the
+    /// default form for the value.
+    fn selected_form(&self, unit: &Unit) -> Result<Option<constants::DwForm>> {
+        if let (true, Some(form)) = (unit.preserve_encoding, self.form) {
+            if form == self.value.form(unit.encoding())? {
+                return Ok(None);
+            }
+            if self
+                .value
+                .check_selected_form(form, unit.encoding())
+                .is_ok()
+            {
+                return Ok(Some(form));
+            }
+        }
+        let select = match unit.form_selector {
+            Some(select) => select,
+            None => return Ok(None),
+        };
+        let default = self.value.form(unit.encoding())?;
+        match select(self.name, &self.value, default) {
+            Some(form) if form != default => {
+                self.value.check_selected_form(form, unit.encoding())?;
+                Ok(Some(form))
+            }
+            _ => Ok(None),
+        }
+    }
 }
 
+/// The `FormSelector` that is set by `Unit::set_version` when changing to
+/// version 5.
+fn select_string_index(
+    _name: constants::DwAt,
+    value: &AttributeValue,
+    _default: constants::DwForm,
+) -> Option<constants::DwForm> {
+    match *value {
+        AttributeValue::StringRef(_) => Some(constants::DW_FORM_strx),
+        _ => None,
+    }
+}
+
+/// A function that selects the form used to encode an attribute value.
+///
+/// The function is given the attribute name, the value, and the form that would
+/// be used by default. It returns the form to use instead, or `None` to use the
+/// default form.
+///
+/// The following alternative forms are supported:
+/// - constant values (such as `Data1`, `Udata`, `Sdata`, and `Language`) may use
+///   `DW_FORM_data1`, `DW_FORM_data2`, `DW_FORM_data4`, `DW_FORM_data8`,
+///   `DW_FORM_udata`, `DW_FORM_sdata`, or `DW_FORM_implicit_const`, provided
+///   that the value fits
+/// - `Flag(true)` values may use `DW_FORM_flag_present`
+/// - `Block` values may use `DW_FORM_block1`, `DW_FORM_block2`, or
+///   `DW_FORM_block4`, provided that the length fits
+/// - `UnitRef` values may use `DW_FORM_ref1`, `DW_FORM_ref2`, `DW_FORM_ref4`,
+///   `DW_FORM_ref8`, or `DW_FORM_ref_udata`
+/// - `StringRef` values may use `DW_FORM_strx`, `DW_FORM_strx1`, `DW_FORM_strx2`,
+///   or `DW_FORM_strx4`
+/// - `Address` values may use `DW_FORM_addrx`, `DW_FORM_addrx1`, `DW_FORM_addrx2`,
+///   or `DW_FORM_addrx4`
+/// - `RangeListRef` values may use `DW_FORM_rnglistx`
+/// - `LocationListRef` values may use `DW_FORM_loclistx`
+///
+/// The index forms require DWARF version 5. When they are used, the unit
+/// writes the corresponding `.debug_str_offsets`, `.debug_addr`,
+/// `.debug_rnglists`, or `.debug_loclists` table, and sets the matching
+/// `DW_AT_*_base` attribute on the root entry.
+///
+/// Selecting any other form is an error. The choice between inline and
+/// `.debug_str` strings is made by using `AttributeValue::String` or
+/// `AttributeValue::StringRef`, and `Unit::select_string_forms` can be used to
+/// change that choice for existing values.
+pub type FormSelector =
+    fn(constants::DwAt, &AttributeValue, constants::DwForm) -> Option<constants::DwForm>;
+
 /// The value of an attribute in a `DebuggingInformationEntry`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AttributeValue {
     /// "Refers to some location in the address space of the described program."
     Address(Address),
@@ -750,9 +2476,23 @@ pub enum AttributeValue {
     /// anything else."
     Data8(u64),
 
+    /// A sixteen byte constant data value. How to interpret the bytes depends on context.
+    ///
+    /// These bytes are written as is, without any endian conversion.
+    ///
+    /// This requires DWARF version 5.
+    Data16([u8; 16]),
+
     /// A signed integer constant.
     Sdata(i64),
 
+    /// A signed integer constant that is stored in the abbreviation instead
+    /// of in the entry.
+    ///
+    /// This requires DWARF version 5. Entries with different values will
+    /// need different abbreviations.
+    ImplicitConst(i64),
+
     /// An unsigned integer constant.
     Udata(u64),
 
@@ -782,6 +2522,11 @@ pub enum AttributeValue {
     /// A reference to a line number program.
     LineProgramRef,
 
+    /// A reference to a line number program in `Dwarf::line_programs`.
+    ///
+    /// This is only resolved when writing with `Dwarf::write`.
+    DebugLineRef(LineProgramId),
+
     /// A reference to a location list.
     LocationListRef(LocationListId),
 
@@ -794,17 +2539,40 @@ pub enum AttributeValue {
 
     /// An offset into the `.debug_macro` section.
     ///
-    /// The API does not currently assist with generating this offset.
-    /// This variant will be removed from the API once support for writing
-    /// `.debug_macro` sections is implemented.
+    /// This is set automatically for the root entry if `Unit::macros` is not
+    /// empty. Otherwise, the API does not assist with generating this offset.
     DebugMacroRef(DebugMacroOffset),
 
+    /// The base of the unit's string offsets in the `.debug_str_offsets` section.
+    ///
+    /// This is set automatically for the root entry if the unit's `FormSelector`
+    /// selects a `DW_FORM_strx` form.
+    DebugStrOffsetsBase(DebugStrOffsetsBase),
+
+    /// The base of the unit's addresses in the `.debug_addr` section.
+    ///
+    /// This is set automatically for the root entry if the unit's `FormSelector`
+    /// selects a `DW_FORM_addrx` form.
+    DebugAddrBase(DebugAddrBase),
+
+    /// The base of the unit's range list offsets in the `.debug_rnglists` section.
+    ///
+    /// This is set automatically for the root entry if the unit's `FormSelector`
+    /// selects the `DW_FORM_rnglistx` form.
+    DebugRngListsBase(DebugRngListsBase),
+
+    /// The base of the unit's location list offsets in the `.debug_loclists` section.
+    ///
+    /// This is set automatically for the root entry if the unit's `FormSelector`
+    /// selects the `DW_FORM_loclistx` form.
+    DebugLocListsBase(DebugLocListsBase),
+
     /// A reference to a range list.
     RangeListRef(RangeListId),
 
     /// A type signature.
     ///
-    /// The API does not currently assist with generating this signature.
+    /// Use `Dwarf::type_signature` to generate this signature.
     /// This variant will be removed from the API once support for writing
     /// `.debug_types` sections is implemented.
     DebugTypesRef(DebugTypeSignature),
@@ -865,6 +2633,12 @@ pub enum AttributeValue {
     /// An index into the filename entries from the line number information
     /// table for the unit containing this value.
     FileIndex(Option<FileId>),
+
+    /// The raw bytes of a value that uses a form not known to gimli.
+    ///
+    /// The bytes are written as is, so they must already be in the target
+    /// format.
+    Unknown(constants::DwForm, Vec<u8>),
 }
 
 impl AttributeValue {
@@ -872,11 +2646,9 @@ impl AttributeValue {
     pub fn form(&self, encoding: Encoding) -> Result<constants::DwForm> {
         // TODO: missing forms:
         // - DW_FORM_indirect
-        // - DW_FORM_implicit_const
         // - FW_FORM_block1/block2/block4
         // - DW_FORM_str/strx1/strx2/strx3/strx4
         // - DW_FORM_addrx/addrx1/addrx2/addrx3/addrx4
-        // - DW_FORM_data16
         // - DW_FORM_line_strp
         // - DW_FORM_loclistx
         // - DW_FORM_rnglistx
@@ -887,6 +2659,12 @@ impl AttributeValue {
             AttributeValue::Data2(_) => constants::DW_FORM_data2,
             AttributeValue::Data4(_) => constants::DW_FORM_data4,
             AttributeValue::Data8(_) => constants::DW_FORM_data8,
+            AttributeValue::Data16(_) => {
+                if encoding.version < 5 {
+                    return Err(Error::NeedVersion(5));
+                }
+                constants::DW_FORM_data16
+            }
             AttributeValue::Exprloc(_) => constants::DW_FORM_exprloc,
             AttributeValue::Flag(_) => constants::DW_FORM_flag,
             AttributeValue::FlagPresent => constants::DW_FORM_flag_present,
@@ -907,9 +2685,14 @@ impl AttributeValue {
                 }
             }
             AttributeValue::LineProgramRef
+            | AttributeValue::DebugLineRef(_)
             | AttributeValue::LocationListRef(_)
             | AttributeValue::DebugMacinfoRef(_)
             | AttributeValue::DebugMacroRef(_)
+            | AttributeValue::DebugStrOffsetsBase(_)
+            | AttributeValue::DebugAddrBase(_)
+            | AttributeValue::DebugRngListsBase(_)
+            | AttributeValue::DebugLocListsBase(_)
             | AttributeValue::RangeListRef(_) => {
                 if encoding.version == 2 || encoding.version == 3 {
                     match encoding.format {
@@ -940,6 +2723,13 @@ impl AttributeValue {
             | AttributeValue::FileIndex(_)
             | AttributeValue::Udata(_) => constants::DW_FORM_udata,
             AttributeValue::Sdata(_) => constants::DW_FORM_sdata,
+            AttributeValue::ImplicitConst(_) => {
+                if encoding.version < 5 {
+                    return Err(Error::NeedVersion(5));
+                }
+                constants::DW_FORM_implicit_const
+            }
+            AttributeValue::Unknown(form, _) => form,
         };
         Ok(form)
     }
@@ -975,10 +2765,18 @@ impl AttributeValue {
                 debug_assert_form!(constants::DW_FORM_data8);
                 8
             }
+            AttributeValue::Data16(_) => {
+                debug_assert_form!(constants::DW_FORM_data16);
+                16
+            }
             AttributeValue::Sdata(val) => {
                 debug_assert_form!(constants::DW_FORM_sdata);
                 sleb128_size(val)
             }
+            AttributeValue::ImplicitConst(_) => {
+                debug_assert_form!(constants::DW_FORM_implicit_const);
+                0
+            }
             AttributeValue::Udata(val) => {
                 debug_assert_form!(constants::DW_FORM_udata);
                 uleb128_size(val)
@@ -1018,7 +2816,7 @@ impl AttributeValue {
                 }
                 unit.format().word_size() as usize
             }
-            AttributeValue::LineProgramRef => {
+            AttributeValue::LineProgramRef | AttributeValue::DebugLineRef(_) => {
                 if unit.version() >= 4 {
                     debug_assert_form!(constants::DW_FORM_sec_offset);
                 }
@@ -1036,7 +2834,11 @@ impl AttributeValue {
                 }
                 unit.format().word_size() as usize
             }
-            AttributeValue::DebugMacroRef(_) => {
+            AttributeValue::DebugMacroRef(_)
+            | AttributeValue::DebugStrOffsetsBase(_)
+            | AttributeValue::DebugAddrBase(_)
+            | AttributeValue::DebugRngListsBase(_)
+            | AttributeValue::DebugLocListsBase(_) => {
                 if unit.version() >= 4 {
                     debug_assert_form!(constants::DW_FORM_sec_offset);
                 }
@@ -1120,6 +2922,10 @@ impl AttributeValue {
                 debug_assert_form!(constants::DW_FORM_udata);
                 uleb128_size(val.map(FileId::raw).unwrap_or(0))
             }
+            AttributeValue::Unknown(form, ref data) => {
+                debug_assert_form!(form);
+                data.len()
+            }
         }
     }
 
@@ -1128,6 +2934,7 @@ impl AttributeValue {
         &self,
         w: &mut DebugInfo<W>,
         debug_info_refs: &mut Vec<DebugInfoReference>,
+        debug_line_refs: &mut Vec<DebugLineReference>,
         unit_refs: &mut Vec<(DebugInfoOffset, UnitEntryId)>,
         unit: &Unit,
         offsets: &UnitOffsets,
@@ -1168,10 +2975,17 @@ impl AttributeValue {
                 debug_assert_form!(constants::DW_FORM_data8);
                 w.write_u64(val)?;
             }
+            AttributeValue::Data16(ref val) => {
+                debug_assert_form!(constants::DW_FORM_data16);
+                w.write(val)?;
+            }
             AttributeValue::Sdata(val) => {
                 debug_assert_form!(constants::DW_FORM_sdata);
                 w.write_sleb128(val)?;
             }
+            AttributeValue::ImplicitConst(_) => {
+                debug_assert_form!(constants::DW_FORM_implicit_const);
+            }
             AttributeValue::Udata(val) => {
                 debug_assert_form!(constants::DW_FORM_udata);
                 w.write_uleb128(val)?;
@@ -1243,6 +3057,18 @@ impl AttributeValue {
                     None => return Err(Error::InvalidAttributeValue),
                 }
             }
+            AttributeValue::DebugLineRef(program) => {
+                if unit.version() >= 4 {
+                    debug_assert_form!(constants::DW_FORM_sec_offset);
+                }
+                let size = unit.format().word_size();
+                debug_line_refs.push(DebugLineReference {
+                    offset: w.len(),
+                    size,
+                    program,
+                });
+                w.write_udata(0, size)?;
+            }
             AttributeValue::LocationListRef(val) => {
                 if unit.version() >= 4 {
                     debug_assert_form!(constants::DW_FORM_sec_offset);
@@ -1266,6 +3092,30 @@ impl AttributeValue {
                 }
                 w.write_offset(val.0, SectionId::DebugMacro, unit.format().word_size())?;
             }
+            AttributeValue::DebugStrOffsetsBase(val) => {
+                if unit.version() >= 4 {
+                    debug_assert_form!(constants::DW_FORM_sec_offset);
+                }
+                w.write_offset(val.0, SectionId::DebugStrOffsets, unit.format().word_size())?;
+            }
+            AttributeValue::DebugAddrBase(val) => {
+                if unit.version() >= 4 {
+                    debug_assert_form!(constants::DW_FORM_sec_offset);
+                }
+                w.write_offset(val.0, SectionId::DebugAddr, unit.format().word_size())?;
+            }
+            AttributeValue::DebugRngListsBase(val) => {
+                if unit.version() >= 4 {
+                    debug_assert_form!(constants::DW_FORM_sec_offset);
+                }
+                w.write_offset(val.0, SectionId::DebugRngLists, unit.format().word_size())?;
+            }
+            AttributeValue::DebugLocListsBase(val) => {
+                if unit.version() >= 4 {
+                    debug_assert_form!(constants::DW_FORM_sec_offset);
+                }
+                w.write_offset(val.0, SectionId::DebugLocLists, unit.format().word_size())?;
+            }
             AttributeValue::RangeListRef(val) => {
                 if unit.version() >= 4 {
                     debug_assert_form!(constants::DW_FORM_sec_offset);
@@ -1358,9 +3208,258 @@ impl AttributeValue {
                 debug_assert_form!(constants::DW_FORM_udata);
                 w.write_uleb128(val.map(FileId::raw).unwrap_or(0))?;
             }
+            AttributeValue::Unknown(form, ref data) => {
+                debug_assert_form!(form);
+                w.write(data)?;
+            }
         }
         Ok(())
     }
+
+    /// Return the value of a constant class attribute.
+    pub(crate) fn constant_value(&self) -> Option<i128> {
+        let val = match *self {
+            AttributeValue::Data1(val) => i128::from(val),
+            AttributeValue::Data2(val) => i128::from(val),
+            AttributeValue::Data4(val) => i128::from(val),
+            AttributeValue::Data8(val) => i128::from(val),
+            AttributeValue::Udata(val) => i128::from(val),
+            AttributeValue::Sdata(val) => i128::from(val),
+            AttributeValue::Encoding(val) => i128::from(val.0),
+            AttributeValue::DecimalSign(val) => i128::from(val.0),
+            AttributeValue::Endianity(val) => i128::from(val.0),
+            AttributeValue::Accessibility(val) => i128::from(val.0),
+            AttributeValue::Visibility(val) => i128::from(val.0),
+            AttributeValue::Virtuality(val) => i128::from(val.0),
+            AttributeValue::Language(val) => i128::from(val.0),
+            AttributeValue::AddressClass(val) => i128::from(val.0),
+            AttributeValue::IdentifierCase(val) => i128::from(val.0),
+            AttributeValue::CallingConvention(val) => i128::from(val.0),
+            AttributeValue::Inline(val) => i128::from(val.0),
+            AttributeValue::Ordering(val) => i128::from(val.0),
+            AttributeValue::FileIndex(val) => i128::from(val.map(FileId::raw).unwrap_or(0)),
+            _ => return None,
+        };
+        Some(val)
+    }
+
+    /// Check that the value can be encoded using a form chosen by a `FormSelector`.
+    fn check_selected_form(&self, form: constants::DwForm, encoding: Encoding) -> Result<()> {
+        let index_forms: &[constants::DwForm] = match *self {
+            AttributeValue::StringRef(_) => &[
+                constants::DW_FORM_strx,
+                constants::DW_FORM_strx1,
+                constants::DW_FORM_strx2,
+                constants::DW_FORM_strx4,
+            ],
+            AttributeValue::Address(_) => &[
+                constants::DW_FORM_addrx,
+                constants::DW_FORM_addrx1,
+                constants::DW_FORM_addrx2,
+                constants::DW_FORM_addrx4,
+            ],
+            AttributeValue::RangeListRef(_) => &[constants::DW_FORM_rnglistx],
+            AttributeValue::LocationListRef(_) => &[constants::DW_FORM_loclistx],
+            _ => &[],
+        };
+        if !index_forms.is_empty() {
+            if !index_forms.contains(&form) {
+                return Err(Error::InvalidAttributeValue);
+            }
+            if encoding.version < 5 {
+                return Err(Error::NeedVersion(5));
+            }
+            return Ok(());
+        }
+        match *self {
+            AttributeValue::Flag(val) => {
+                return match form {
+                    constants::DW_FORM_flag => Ok(()),
+                    constants::DW_FORM_flag_present if val => {
+                        if encoding.version < 4 {
+                            Err(Error::NeedVersion(4))
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    _ => Err(Error::InvalidAttributeValue),
+                };
+            }
+            AttributeValue::Block(ref data) => {
+                let len = data.len() as u64;
+                let fits = match form {
+                    constants::DW_FORM_block1 => len <= 0xff,
+                    constants::DW_FORM_block2 => len <= 0xffff,
+                    constants::DW_FORM_block4 => len <= 0xffff_ffff,
+                    constants::DW_FORM_block => true,
+                    _ => return Err(Error::InvalidAttributeValue),
+                };
+                return if fits {
+                    Ok(())
+                } else {
+                    Err(Error::ValueTooLarge)
+                };
+            }
+            _ => {}
+        }
+        if let AttributeValue::UnitRef(_) = *self {
+            return match form {
+                constants::DW_FORM_ref1
+                | constants::DW_FORM_ref2
+                | constants::DW_FORM_ref4
+                | constants::DW_FORM_ref8
+                | constants::DW_FORM_ref_udata => Ok(()),
+                _ => Err(Error::InvalidAttributeValue),
+            };
+        }
+        let val = self.constant_value().ok_or(Error::InvalidAttributeValue)?;
+        if form == constants::DW_FORM_implicit_const && encoding.version < 5 {
+            return Err(Error::NeedVersion(5));
+        }
+        let fits = match form {
+            constants::DW_FORM_data1 => -(1 << 7) <= val && val < (1 << 8),
+            constants::DW_FORM_data2 => -(1 << 15) <= val && val < (1 << 16),
+            constants::DW_FORM_data4 => -(1 << 31) <= val && val < (1 << 32),
+            constants::DW_FORM_data8 => -(1 << 63) <= val && val < (1 << 64),
+            constants::DW_FORM_udata => val >= 0,
+            constants::DW_FORM_sdata | constants::DW_FORM_implicit_const => {
+                i64::try_from(val).is_ok()
+            }
+            _ => return Err(Error::InvalidAttributeValue),
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(Error::ValueTooLarge)
+        }
+    }
+
+    /// Return the size of the value when encoded using a form chosen by a
+    /// `FormSelector`.
+    ///
+    /// The form must have been checked by `check_selected_form`.
+    fn selected_size(&self, form: constants::DwForm, offsets: &UnitOffsets) -> usize {
+        match form {
+            constants::DW_FORM_strx
+            | constants::DW_FORM_addrx
+            | constants::DW_FORM_rnglistx
+            | constants::DW_FORM_loclistx => uleb128_size(self.selected_index(offsets)),
+            constants::DW_FORM_strx1 | constants::DW_FORM_addrx1 => 1,
+            constants::DW_FORM_strx2 | constants::DW_FORM_addrx2 => 2,
+            constants::DW_FORM_strx4 | constants::DW_FORM_addrx4 => 4,
+            constants::DW_FORM_data1 | constants::DW_FORM_ref1 => 1,
+            constants::DW_FORM_data2 | constants::DW_FORM_ref2 => 2,
+            constants::DW_FORM_data4 | constants::DW_FORM_ref4 => 4,
+            constants::DW_FORM_data8 | constants::DW_FORM_ref8 => 8,
+            constants::DW_FORM_udata => uleb128_size(self.constant_value().unwrap() as u64),
+            constants::DW_FORM_sdata => sleb128_size(self.constant_value().unwrap() as i64),
+            constants::DW_FORM_flag => 1,
+            constants::DW_FORM_flag_present | constants::DW_FORM_implicit_const => 0,
+            constants::DW_FORM_block1
+            | constants::DW_FORM_block2
+            | constants::DW_FORM_block4
+            | constants::DW_FORM_block => match *self {
+                AttributeValue::Block(ref data) => {
+                    let len_size = match form {
+                        constants::DW_FORM_block1 => 1,
+                        constants::DW_FORM_block2 => 2,
+                        constants::DW_FORM_block4 => 4,
+                        _ => uleb128_size(data.len() as u64),
+                    };
+                    len_size + data.len()
+                }
+                _ => unreachable!(),
+            },
+            constants::DW_FORM_ref_udata => match *self {
+                // The entry may not have an offset yet, in which case this is
+                // only an estimate, and `Unit::write` will recalculate it.
+                AttributeValue::UnitRef(id) => uleb128_size(offsets.estimated_unit_offset(id)),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return the index of the value for an index form chosen by a `FormSelector`.
+    ///
+    /// The values must have been collected into `offsets` by `Unit::write`.
+    fn selected_index(&self, offsets: &UnitOffsets) -> u64 {
+        let index = match *self {
+            AttributeValue::StringRef(id) => offsets.strings.get_index_of(&id).unwrap(),
+            AttributeValue::Address(address) => offsets.addresses.get_index_of(&address).unwrap(),
+            AttributeValue::RangeListRef(id) => id.index(),
+            AttributeValue::LocationListRef(id) => id.index(),
+            _ => unreachable!(),
+        };
+        index as u64
+    }
+
+    /// Write the value using a form chosen by a `FormSelector`.
+    ///
+    /// The form must have been checked by `check_selected_form`.
+    fn write_selected<W: Writer>(
+        &self,
+        w: &mut DebugInfo<W>,
+        form: constants::DwForm,
+        offsets: &UnitOffsets,
+    ) -> Result<()> {
+        match form {
+            constants::DW_FORM_strx
+            | constants::DW_FORM_addrx
+            | constants::DW_FORM_rnglistx
+            | constants::DW_FORM_loclistx => {
+                return w.write_uleb128(self.selected_index(offsets));
+            }
+            constants::DW_FORM_strx1 | constants::DW_FORM_addrx1 => {
+                return w.write_udata(self.selected_index(offsets), 1);
+            }
+            constants::DW_FORM_strx2 | constants::DW_FORM_addrx2 => {
+                return w.write_udata(self.selected_index(offsets), 2);
+            }
+            constants::DW_FORM_strx4 | constants::DW_FORM_addrx4 => {
+                return w.write_udata(self.selected_index(offsets), 4);
+            }
+            constants::DW_FORM_flag_present | constants::DW_FORM_implicit_const => {
+                return Ok(());
+            }
+            _ => {}
+        }
+        match *self {
+            AttributeValue::Flag(val) => return w.write_u8(val.into()),
+            AttributeValue::Block(ref data) => {
+                let len = data.len() as u64;
+                match form {
+                    constants::DW_FORM_block1 => w.write_udata(len, 1)?,
+                    constants::DW_FORM_block2 => w.write_udata(len, 2)?,
+                    constants::DW_FORM_block4 => w.write_udata(len, 4)?,
+                    _ => w.write_uleb128(len)?,
+                }
+                return w.write(data);
+            }
+            _ => {}
+        }
+        if let AttributeValue::UnitRef(id) = *self {
+            let val = offsets.unit_offset(id);
+            return match form {
+                constants::DW_FORM_ref1 => w.write_udata(val, 1),
+                constants::DW_FORM_ref2 => w.write_udata(val, 2),
+                constants::DW_FORM_ref4 => w.write_udata(val, 4),
+                constants::DW_FORM_ref8 => w.write_udata(val, 8),
+                constants::DW_FORM_ref_udata => w.write_uleb128(val),
+                _ => Err(Error::InvalidAttributeValue),
+            };
+        }
+        let val = self.constant_value().ok_or(Error::InvalidAttributeValue)?;
+        match form {
+            constants::DW_FORM_data1 => w.write_u8(val as u8),
+            constants::DW_FORM_data2 => w.write_u16(val as u16),
+            constants::DW_FORM_data4 => w.write_u32(val as u32),
+            constants::DW_FORM_data8 => w.write_u64(val as u64),
+            constants::DW_FORM_udata => w.write_uleb128(val as u64),
+            constants::DW_FORM_sdata => w.write_sleb128(val as i64),
+            _ => Err(Error::InvalidAttributeValue),
+        }
+    }
 }
 
 define_section!(
@@ -1391,6 +3490,14 @@ impl DebugInfoOffsets {
         self.units[unit.index].unit
     }
 
+    /// Get the size of the given unit in the `.debug_info` section, including
+    /// the initial length field.
+    #[inline]
+    pub fn unit_length(&self, unit: UnitId) -> usize {
+        debug_assert_eq!(self.base_id, unit.base_id);
+        self.units[unit.index].length
+    }
+
     /// Get the `.debug_info` section offset for the given entry.
     #[inline]
     pub fn entry(&self, unit: UnitId, entry: UnitEntryId) -> DebugInfoOffset {
@@ -1399,12 +3506,27 @@ impl DebugInfoOffsets {
     }
 }
 
+/// The values of a unit that are encoded using index forms.
+#[derive(Debug, Default)]
+struct IndexValues {
+    strings: IndexSet<StringId>,
+    addresses: IndexSet<Address>,
+    rnglistx: bool,
+    loclistx: bool,
+}
+
 /// The section offsets of all elements of a unit within a `.debug_info` section.
 #[derive(Debug)]
 pub(crate) struct UnitOffsets {
     base_id: BaseId,
     unit: DebugInfoOffset,
+    /// The size of the unit, including the initial length field.
+    length: usize,
     entries: Vec<EntryOffset>,
+    /// The strings that are referenced using `DW_FORM_strx*`, in index order.
+    strings: IndexSet<StringId>,
+    /// The addresses that are referenced using `DW_FORM_addrx*`, in index order.
+    addresses: IndexSet<Address>,
 }
 
 impl UnitOffsets {
@@ -1414,7 +3536,10 @@ impl UnitOffsets {
         UnitOffsets {
             base_id: BaseId::default(),
             unit: DebugInfoOffset(0),
+            length: 0,
             entries: Vec::new(),
+            strings: IndexSet::new(),
+            addresses: IndexSet::new(),
         }
     }
 
@@ -1434,6 +3559,15 @@ impl UnitOffsets {
         (offset.0 - self.unit.0) as u64
     }
 
+    /// Get the unit offset for the given entry, or an estimate of it if the
+    /// offset has not been calculated yet.
+    #[inline]
+    pub(crate) fn estimated_unit_offset(&self, entry: UnitEntryId) -> u64 {
+        debug_assert_eq!(self.base_id, entry.base_id);
+        let offset = self.entries[entry.index].offset;
+        offset.0.saturating_sub(self.unit.0) as u64
+    }
+
     /// Get the abbreviation code for the given entry.
     #[inline]
     pub(crate) fn abbrev(&self, entry: UnitEntryId) -> u64 {
@@ -1442,7 +3576,7 @@ impl UnitOffsets {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct EntryOffset {
     offset: DebugInfoOffset,
     abbrev: u64,
@@ -1457,6 +3591,120 @@ impl EntryOffset {
     }
 }
 
+/// The raw data of a unit that is copied from the input without conversion.
+#[derive(Debug, Default)]
+pub(crate) struct VerbatimUnit {
+    /// The unit header and entries, including the initial length.
+    data: Vec<u8>,
+    /// The offset in `data` of the `.debug_abbrev` offset in the unit header.
+    abbrev_offset: usize,
+    /// The offset in `data` of the root entry.
+    root_offset: usize,
+    /// The abbreviations used by the unit, including the null terminator.
+    abbreviations: Vec<u8>,
+    /// The line number program of the unit, including the initial length.
+    ///
+    /// This is empty if the unit does not have a line number program.
+    line_program: Vec<u8>,
+    /// The section offsets in `data` that must be updated when writing.
+    fixups: Vec<VerbatimFixup>,
+    /// The section offsets in `line_program` that must be updated when writing.
+    line_program_fixups: Vec<VerbatimFixup>,
+}
+
+/// A section offset in the data of a `VerbatimUnit`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VerbatimFixup {
+    /// The offset of the value within the data.
+    offset: usize,
+    /// The size of the value.
+    size: u8,
+    /// The value that the section offset refers to.
+    value: VerbatimValue,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "read"), allow(dead_code))]
+pub(crate) enum VerbatimValue {
+    String(StringId),
+    LineString(LineStringId),
+    LineProgram,
+    RangeList(RangeListId),
+    LocationList(LocationListId),
+}
+
+impl VerbatimUnit {
+    fn write<W: Writer>(
+        &self,
+        sections: &mut Sections<W>,
+        encoding: Encoding,
+        ranges: &RangeListTable,
+        locations: &LocationListTable,
+        line_strings: &DebugLineStrOffsets,
+        strings: &DebugStrOffsets,
+    ) -> Result<()> {
+        let range_lists = ranges.write(sections, encoding, false)?;
+        let loc_lists = locations.write(sections, encoding, None, false)?;
+        let resolve = |value: VerbatimValue, line_program: Option<DebugLineOffset>| {
+            Ok(match value {
+                VerbatimValue::String(id) => (strings.get(id).0, SectionId::DebugStr),
+                VerbatimValue::LineString(id) => (line_strings.get(id).0, SectionId::DebugLineStr),
+                VerbatimValue::LineProgram => (
+                    line_program.ok_or(Error::InvalidAttributeValue)?.0,
+                    SectionId::DebugLine,
+                ),
+                VerbatimValue::RangeList(id) => {
+                    let section = if encoding.version <= 4 {
+                        SectionId::DebugRanges
+                    } else {
+                        SectionId::DebugRngLists
+                    };
+                    (range_lists.get(id).0, section)
+                }
+                VerbatimValue::LocationList(id) => {
+                    let section = if encoding.version <= 4 {
+                        SectionId::DebugLoc
+                    } else {
+                        SectionId::DebugLocLists
+                    };
+                    (loc_lists.get(id).0, section)
+                }
+            })
+        };
+
+        let line_program = if self.line_program.is_empty() {
+            None
+        } else {
+            let w = &mut sections.debug_line;
+            let offset = w.offset();
+            w.write(&self.line_program)?;
+            for fixup in &self.line_program_fixups {
+                let (val, section) = resolve(fixup.value, None)?;
+                w.write_offset_at(offset.0 + fixup.offset, val, section, fixup.size)?;
+            }
+            Some(offset)
+        };
+
+        let abbrev_offset = sections.debug_abbrev.offset();
+        sections.debug_abbrev.write(&self.abbreviations)?;
+
+        let w = &mut sections.debug_info;
+        let offset = w.offset();
+        w.write(&self.data)?;
+        w.write_offset_at(
+            offset.0 + self.abbrev_offset,
+            abbrev_offset.0,
+            SectionId::DebugAbbrev,
+            encoding.format.word_size(),
+        )?;
+        for fixup in &self.fixups {
+            let (val, section) = resolve(fixup.value, line_program)?;
+            w.write_offset_at(offset.0 + fixup.offset, val, section, fixup.size)?;
+        }
+        Ok(())
+    }
+}
+
 /// A reference to a `.debug_info` entry that has yet to be resolved.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct DebugInfoReference {
@@ -1470,12 +3718,258 @@ pub(crate) struct DebugInfoReference {
     pub entry: UnitEntryId,
 }
 
+/// A reference to a `.debug_line` program that has yet to be resolved.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DebugLineReference {
+    /// The offset within the section of the reference.
+    pub offset: usize,
+    /// The size of the reference.
+    pub size: u8,
+    /// The line program being referenced.
+    pub program: LineProgramId,
+}
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    use super::*;
+    use crate::endianity::Endianity;
+    use crate::write::EndianVec;
+    use rayon::prelude::*;
+    use std::collections::BTreeMap;
+
+    impl UnitTable {
+        /// Write the units to the given sections, encoding the units in parallel.
+        ///
+        /// This produces the same output as `UnitTable::write`. Each unit is
+        /// first written to its own buffers, and then the buffers are appended
+        /// to `sections` in order while adjusting section offsets and resolving
+        /// references between units.
+        ///
+        /// `strings` must contain the `.debug_str` offsets of the corresponding
+        /// `StringTable`.
+        pub fn write_parallel<W: Writer>(
+            &mut self,
+            sections: &mut Sections<W>,
+            line_strings: &DebugLineStrOffsets,
+            strings: &DebugStrOffsets,
+        ) -> Result<DebugInfoOffsets>
+        where
+            W::Endian: Send + Sync,
+        {
+            let endian = sections.debug_info.endian();
+            let chunks = self
+                .units
+                .par_iter_mut()
+                .map(|unit| {
+                    let mut chunk = Sections::new(ChunkWriter::new(endian));
+                    let offsets = unit.write(
+                        &mut chunk,
+                        &mut AbbreviationTables::new(false),
+                        line_strings,
+                        strings,
+                    )?;
+                    Ok((chunk, offsets))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut offsets = DebugInfoOffsets {
+                base_id: self.base_id,
+                units: Vec::with_capacity(chunks.len()),
+            };
+            let mut abbrev_tables = HashMap::new();
+            for (mut chunk, mut unit_offsets) in chunks {
+                // Find where each section of the chunk will be placed.
+                let mut bases = HashMap::new();
+                chunk.for_each(|id, w| {
+                    if w.len() != 0 {
+                        let base = sections.get(id).map(|w| w.len()).unwrap_or(0);
+                        bases.insert(id, base);
+                    }
+                    Ok::<_, Error>(())
+                })?;
+                if self.share_abbreviations {
+                    let abbrevs = chunk.debug_abbrev.0.writer.take();
+                    match abbrev_tables.get(&abbrevs) {
+                        Some(&base) => {
+                            bases.insert(SectionId::DebugAbbrev, base);
+                        }
+                        None => {
+                            let base = sections.debug_abbrev.len();
+                            sections.debug_abbrev.write(&abbrevs)?;
+                            bases.insert(SectionId::DebugAbbrev, base);
+                            abbrev_tables.insert(abbrevs, base);
+                        }
+                    }
+                }
+
+                chunk.for_each(|id, w| match sections.get_mut(id) {
+                    Some(section) => w.append(section, &bases),
+                    None => Ok(()),
+                })?;
+
+                let base = bases.get(&SectionId::DebugInfo).copied().unwrap_or(0);
+                unit_offsets.unit.0 += base;
+                for entry in &mut unit_offsets.entries {
+                    if entry.offset.0 != 0 {
+                        entry.offset.0 += base;
+                    }
+                }
+                offsets.units.push(unit_offsets);
+
+                for mut r in chunk.debug_info_refs.drain(..) {
+                    r.offset += base;
+                    sections.debug_info_refs.push(r);
+                }
+                for mut r in chunk.debug_line_refs.drain(..) {
+                    r.offset += base;
+                    sections.debug_line_refs.push(r);
+                }
+                let base = bases.get(&SectionId::DebugLoc).copied().unwrap_or(0);
+                for mut r in chunk.debug_loc_refs.drain(..) {
+                    r.offset += base;
+                    sections.debug_loc_refs.push(r);
+                }
+                let base = bases.get(&SectionId::DebugLocLists).copied().unwrap_or(0);
+                for mut r in chunk.debug_loclists_refs.drain(..) {
+                    r.offset += base;
+                    sections.debug_loclists_refs.push(r);
+                }
+            }
+
+            write_section_refs(
+                &mut sections.debug_info_refs,
+                &mut sections.debug_info.0,
+                &offsets,
+            )?;
+            write_section_refs(
+                &mut sections.debug_loc_refs,
+                &mut sections.debug_loc.0,
+                &offsets,
+            )?;
+            write_section_refs(
+                &mut sections.debug_loclists_refs,
+                &mut sections.debug_loclists.0,
+                &offsets,
+            )?;
+
+            Ok(offsets)
+        }
+    }
+
+    /// A value in a `ChunkWriter` that must be written by the final writer.
+    #[derive(Debug, Clone, Copy)]
+    enum ChunkFixup {
+        Address { symbol: usize, addend: i64 },
+        Offset { val: usize, section: SectionId },
+        Reference { symbol: usize },
+    }
+
+    /// A writer for the sections of a single unit.
+    ///
+    /// Section offsets are relative to the start of the unit's data in each
+    /// section, and values that the final writer may need to handle, such as
+    /// relocations, are recorded so they can be replayed.
+    #[derive(Debug, Clone)]
+    struct ChunkWriter<E: Endianity> {
+        writer: EndianVec<E>,
+        fixups: BTreeMap<usize, (u8, ChunkFixup)>,
+    }
+
+    impl<E: Endianity> ChunkWriter<E> {
+        fn new(endian: E) -> Self {
+            ChunkWriter {
+                writer: EndianVec::new(endian),
+                fixups: BTreeMap::new(),
+            }
+        }
+
+        /// Append the data to `w`, adjusting the section offsets for sections
+        /// which this unit has data in.
+        fn append<W: Writer>(&self, w: &mut W, bases: &HashMap<SectionId, usize>) -> Result<()> {
+            let data = self.writer.slice();
+            let mut pos = 0;
+            for (&offset, &(size, fixup)) in &self.fixups {
+                w.write(&data[pos..offset])?;
+                match fixup {
+                    ChunkFixup::Address { symbol, addend } => {
+                        w.write_address(Address::Symbol { symbol, addend }, size)?
+                    }
+                    ChunkFixup::Offset { val, section } => {
+                        let base = bases.get(&section).copied().unwrap_or(0);
+                        w.write_offset(val + base, section, size)?
+                    }
+                    ChunkFixup::Reference { symbol } => w.write_reference(symbol, size)?,
+                }
+                pos = offset + usize::from(size);
+            }
+            w.write(&data[pos..])
+        }
+    }
+
+    impl<E: Endianity> Writer for ChunkWriter<E> {
+        type Endian = E;
+
+        fn endian(&self) -> Self::Endian {
+            self.writer.endian()
+        }
+
+        fn len(&self) -> usize {
+            self.writer.len()
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<()> {
+            self.writer.write(bytes)
+        }
+
+        fn write_at(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+            self.writer.write_at(offset, bytes)
+        }
+
+        fn write_address(&mut self, address: Address, size: u8) -> Result<()> {
+            match address {
+                Address::Constant(val) => self.writer.write_udata(val, size),
+                Address::Symbol { symbol, addend } => {
+                    let fixup = ChunkFixup::Address { symbol, addend };
+                    self.fixups.insert(self.len(), (size, fixup));
+                    self.writer.write_udata(0, size)
+                }
+            }
+        }
+
+        fn write_offset(&mut self, val: usize, section: SectionId, size: u8) -> Result<()> {
+            let fixup = ChunkFixup::Offset { val, section };
+            self.fixups.insert(self.len(), (size, fixup));
+            self.writer.write_offset(val, section, size)
+        }
+
+        fn write_offset_at(
+            &mut self,
+            offset: usize,
+            val: usize,
+            section: SectionId,
+            size: u8,
+        ) -> Result<()> {
+            let fixup = ChunkFixup::Offset { val, section };
+            self.fixups.insert(offset, (size, fixup));
+            self.writer.write_offset_at(offset, val, section, size)
+        }
+
+        fn write_reference(&mut self, symbol: usize, size: u8) -> Result<()> {
+            let fixup = ChunkFixup::Reference { symbol };
+            self.fixups.insert(self.len(), (size, fixup));
+            self.writer.write_udata(0, size)
+        }
+    }
+}
+
 #[cfg(feature = "read")]
 pub(crate) mod convert {
     use super::*;
     use crate::common::{DwoId, UnitSectionOffset};
-    use crate::read::{self, Reader};
-    use crate::write::{self, ConvertError, ConvertResult, LocationList, RangeList};
+    use crate::read::{self, Reader, Section as _};
+    use crate::write::{
+        self, ConvertError, ConvertResult, LocationList, Macro, MacroString, MacroUnit, RangeList,
+    };
     use std::collections::HashMap;
 
     pub(crate) struct ConvertUnit<R: Reader<Offset = usize>> {
@@ -1498,7 +3992,18 @@ pub(crate) mod convert {
         pub base_address: Address,
         pub line_program_offset: Option<DebugLineOffset>,
         pub line_program_files: Vec<FileId>,
+        pub line_programs: Option<&'a mut ConvertLinePrograms>,
         pub entry_ids: &'a HashMap<UnitSectionOffset, (UnitId, UnitEntryId)>,
+        /// Copy expressions in location lists without converting them.
+        pub raw_expressions: bool,
+    }
+
+    /// Line programs that are referenced by attributes other than the
+    /// unit's own `DW_AT_stmt_list`.
+    #[derive(Default)]
+    pub(crate) struct ConvertLinePrograms {
+        pub programs: Vec<LineProgram>,
+        pub ids: HashMap<DebugLineOffset, LineProgramId>,
     }
 
     impl UnitTable {
@@ -1517,6 +4022,31 @@ pub(crate) mod convert {
             line_strings: &mut write::LineStringTable,
             strings: &mut write::StringTable,
             convert_address: &dyn Fn(u64) -> Option<Address>,
+        ) -> ConvertResult<UnitTable> {
+            UnitTable::convert(
+                dwarf,
+                line_strings,
+                strings,
+                None,
+                &|_| true,
+                convert_address,
+            )
+        }
+
+        /// Create a unit table by reading the data in the given sections.
+        ///
+        /// If `line_programs` is given, then references to line programs other
+        /// than a unit's own line program are converted and added to it.
+        ///
+        /// Units for which `modified` returns false are copied verbatim if
+        /// possible. See `Dwarf::from_modified`.
+        pub(crate) fn convert<R: Reader<Offset = usize>>(
+            dwarf: &read::Dwarf<R>,
+            line_strings: &mut write::LineStringTable,
+            strings: &mut write::StringTable,
+            mut line_programs: Option<&mut ConvertLinePrograms>,
+            modified: &dyn Fn(&read::UnitHeader<R>) -> bool,
+            convert_address: &dyn Fn(u64) -> Option<Address>,
         ) -> ConvertResult<UnitTable> {
             let base_id = BaseId::default();
             let mut unit_entries = Vec::new();
@@ -1525,33 +4055,118 @@ pub(crate) mod convert {
             let mut from_units = dwarf.units();
             while let Some(from_unit) = from_units.next()? {
                 let unit_id = UnitId::new(base_id, unit_entries.len());
-                unit_entries.push(Unit::convert_entries(
-                    from_unit,
-                    unit_id,
+                if !modified(&from_unit) {
+                    if let Some(unit) = Unit::convert_verbatim(
+                        from_unit.clone(),
+                        dwarf,
+                        line_strings,
+                        strings,
+                        convert_address,
+                    )? {
+                        // Only the root entry of a verbatim unit can be referenced.
+                        let root = read::UnitOffset(from_unit.header_size());
+                        if let Some(offset) = root.to_debug_info_offset(&from_unit) {
+                            entry_ids.insert(
+                                UnitSectionOffset::DebugInfoOffset(offset),
+                                (unit_id, unit.root),
+                            );
+                        }
+                        unit_entries.push(Err(unit));
+                        continue;
+                    }
+                }
+                unit_entries.push(Ok(Unit::convert_entries(
+                    from_unit,
+                    unit_id,
                     &mut entry_ids,
                     dwarf,
-                )?);
+                )?));
             }
 
             // Attributes must be converted in a separate pass so that we can handle
             // references to other compilation units.
             let mut units = Vec::new();
             for unit_entries in unit_entries.drain(..) {
+                let unit_entries = match unit_entries {
+                    Ok(unit_entries) => unit_entries,
+                    Err(unit) => {
+                        units.push(unit);
+                        continue;
+                    }
+                };
                 units.push(Unit::convert_attributes(
                     unit_entries,
                     &entry_ids,
                     dwarf,
                     line_strings,
                     strings,
+                    line_programs.as_deref_mut(),
                     convert_address,
                 )?);
             }
 
-            Ok(UnitTable { base_id, units })
+            Ok(UnitTable {
+                base_id,
+                units,
+                share_abbreviations: false,
+            })
         }
     }
 
     impl Unit {
+        /// Create a unit by reading the data for a single unit in the given sections.
+        ///
+        /// This also updates the given tables with the values that are referenced from
+        /// attributes in this unit.
+        ///
+        /// `convert_address` is a function to convert read addresses into the `Address`
+        /// type. See `UnitTable::from`.
+        ///
+        /// Only the given unit is converted, so references to entries in other units
+        /// return `ConvertError::InvalidDebugInfoRef`, and references to line number
+        /// programs other than the unit's own program return
+        /// `ConvertError::InvalidLineRef`. Use `UnitTable::from` or `Dwarf::from`
+        /// to convert units that contain these references.
+        pub fn from<R: Reader<Offset = usize>>(
+            from_header: read::UnitHeader<R>,
+            dwarf: &read::Dwarf<R>,
+            line_strings: &mut write::LineStringTable,
+            strings: &mut write::StringTable,
+            convert_address: &dyn Fn(u64) -> Option<Address>,
+        ) -> ConvertResult<Unit> {
+            let unit_id = UnitId::new(BaseId::default(), 0);
+            let mut entry_ids = HashMap::new();
+            let unit = Unit::convert_entries(from_header, unit_id, &mut entry_ids, dwarf)?;
+            let mut unit = Unit::convert_attributes(
+                unit,
+                &entry_ids,
+                dwarf,
+                line_strings,
+                strings,
+                None,
+                convert_address,
+            )?;
+
+            // The unit does not have an id yet, so references to entries within
+            // the unit must not use `Reference`.
+            for entry in &mut unit.entries {
+                for attr in &mut entry.attrs {
+                    if let AttributeValue::DebugInfoRef(Reference::Entry(_, id)) = attr.value {
+                        attr.value = AttributeValue::UnitRef(id);
+                    }
+                }
+            }
+            let mut valid = true;
+            unit.convert_unit_ids(&mut |id| {
+                valid = false;
+                id
+            });
+            if !valid {
+                return Err(ConvertError::InvalidDebugInfoRef);
+            }
+            Ok(unit)
+        }
+
         /// Create a unit by reading the data in the input sections.
         ///
         /// Does not add entry attributes.
@@ -1596,6 +4211,189 @@ pub(crate) mod convert {
             })
         }
 
+        /// Create a unit that copies the raw data of the given unit.
+        ///
+        /// Section offsets for strings, the line number program, range lists and
+        /// location lists are updated when writing. Returns `None` if the unit
+        /// contains other values that would be invalidated by copying it.
+        pub(crate) fn convert_verbatim<R: Reader<Offset = usize>>(
+            from_header: read::UnitHeader<R>,
+            dwarf: &read::Dwarf<R>,
+            line_strings: &mut write::LineStringTable,
+            strings: &mut write::StringTable,
+            convert_address: &dyn Fn(u64) -> Option<Address>,
+        ) -> ConvertResult<Option<Unit>> {
+            if from_header.type_() != read::UnitType::Compilation {
+                return Ok(None);
+            }
+            let from_offset = match from_header.offset().as_debug_info_offset() {
+                Some(offset) => offset,
+                None => return Ok(None),
+            };
+            let from_unit = dwarf.unit(from_header)?;
+            let encoding = from_unit.encoding();
+            let word_size = encoding.format.word_size();
+
+            let mut data = dwarf.debug_info.reader().clone();
+            data.skip(from_offset.0)?;
+            let data = data.split(from_unit.header.length_including_self())?;
+
+            let mut abbreviations = dwarf.debug_abbrev.reader().clone();
+            abbreviations.skip(from_unit.header.debug_abbrev_offset().0)?;
+            let size = abbreviations_size(&mut abbreviations.clone())?;
+            let abbreviations = abbreviations.split(size)?;
+
+            let mut verbatim = VerbatimUnit {
+                data: data.to_slice()?.into(),
+                abbrev_offset: if encoding.version >= 5 {
+                    encoding.format.initial_length_size() as usize + 4
+                } else {
+                    encoding.format.initial_length_size() as usize + 2
+                },
+                root_offset: from_unit.header.header_size(),
+                abbreviations: abbreviations.to_slice()?.into(),
+                ..Default::default()
+            };
+
+            let line_program_offset = match from_unit.line_program {
+                Some(ref program) => {
+                    let header = program.header();
+                    let mut input = dwarf.debug_line.reader().clone();
+                    input.skip(header.offset().0)?;
+                    let length =
+                        header.unit_length() + header.format().initial_length_size() as usize;
+                    let input = input.split(length)?;
+                    if !verbatim_line_program(
+                        input.clone(),
+                        header.encoding(),
+                        dwarf,
+                        line_strings,
+                        strings,
+                        &mut verbatim.line_program_fixups,
+                    )? {
+                        return Ok(None);
+                    }
+                    verbatim.line_program = input.to_slice()?.into();
+                    Some(header.offset())
+                }
+                None => None,
+            };
+
+            let mut ranges = RangeListTable::default();
+            let mut locations = LocationListTable::default();
+            let entry_ids = HashMap::new();
+            let context = ConvertUnitContext {
+                entry_ids: &entry_ids,
+                dwarf,
+                unit: &from_unit,
+                line_strings,
+                strings,
+                ranges: &mut ranges,
+                locations: &mut locations,
+                convert_address,
+                base_address: convert_address(from_unit.low_pc)
+                    .ok_or(ConvertError::InvalidAddress)?,
+                line_program_offset,
+                line_program_files: Vec::new(),
+                line_programs: None,
+                raw_expressions: true,
+            };
+
+            let mut entries = from_unit.entries_raw(None)?;
+            while !entries.is_empty() {
+                let abbrev = match entries.read_abbreviation()? {
+                    Some(abbrev) => abbrev,
+                    None => continue,
+                };
+                for spec in abbrev.attributes() {
+                    let attr = entries.read_attribute(*spec)?;
+                    let size = match attr.form() {
+                        constants::DW_FORM_data4 => 4,
+                        constants::DW_FORM_data8 => 8,
+                        constants::DW_FORM_sec_offset
+                        | constants::DW_FORM_strp
+                        | constants::DW_FORM_line_strp => word_size,
+                        _ => 0,
+                    };
+                    let value = match attr.value() {
+                        read::AttributeValue::DebugStrRef(offset) => {
+                            let r = dwarf.string(offset)?;
+                            VerbatimValue::String(context.strings.add(r.to_slice()?))
+                        }
+                        read::AttributeValue::DebugLineStrRef(offset) => {
+                            let r = dwarf.line_string(offset)?;
+                            VerbatimValue::LineString(context.line_strings.add(r.to_slice()?))
+                        }
+                        read::AttributeValue::DebugLineRef(offset) => {
+                            if Some(offset) != line_program_offset {
+                                return Ok(None);
+                            }
+                            VerbatimValue::LineProgram
+                        }
+                        read::AttributeValue::RangeListsRef(offset) => {
+                            let offset = dwarf.ranges_offset_from_raw(&from_unit, offset);
+                            let iter = dwarf.raw_ranges(&from_unit, offset)?;
+                            let range_list = RangeList::from(iter, &context)?;
+                            VerbatimValue::RangeList(context.ranges.add(range_list))
+                        }
+                        read::AttributeValue::LocationListsRef(offset) => {
+                            let mut iter = dwarf.locations.raw_locations(offset, encoding)?;
+                            while let Some(location) = iter.next()? {
+                                let data = match location {
+                                    read::RawLocListEntry::BaseAddress { .. } => continue,
+                                    read::RawLocListEntry::AddressOrOffsetPair { data, .. }
+                                    | read::RawLocListEntry::OffsetPair { data, .. }
+                                    | read::RawLocListEntry::StartEnd { data, .. }
+                                    | read::RawLocListEntry::StartLength { data, .. }
+                                    | read::RawLocListEntry::DefaultLocation { data } => data,
+                                    _ => return Ok(None),
+                                };
+                                if !verbatim_expression(data, encoding)? {
+                                    return Ok(None);
+                                }
+                            }
+                            let iter = dwarf.locations.raw_locations(offset, encoding)?;
+                            let loc_list = LocationList::from(iter, &context)?;
+                            VerbatimValue::LocationList(context.locations.add(loc_list))
+                        }
+                        read::AttributeValue::Exprloc(expression) => {
+                            if !verbatim_expression(expression, encoding)? {
+                                return Ok(None);
+                            }
+                            continue;
+                        }
+                        read::AttributeValue::DebugInfoRef(_)
+                        | read::AttributeValue::DebugAddrBase(_)
+                        | read::AttributeValue::DebugAddrIndex(_)
+                        | read::AttributeValue::DebugLocListsBase(_)
+                        | read::AttributeValue::DebugLocListsIndex(_)
+                        | read::AttributeValue::DebugRngListsBase(_)
+                        | read::AttributeValue::DebugRngListsIndex(_)
+                        | read::AttributeValue::DebugStrOffsetsBase(_)
+                        | read::AttributeValue::DebugStrOffsetsIndex(_)
+                        | read::AttributeValue::DebugMacroRef(_)
+                        | read::AttributeValue::DebugMacinfoRef(_)
+                        | read::AttributeValue::SecOffset(_) => return Ok(None),
+                        _ => continue,
+                    };
+                    if size == 0 {
+                        return Ok(None);
+                    }
+                    verbatim.fixups.push(VerbatimFixup {
+                        offset: entries.next_offset().0 - size as usize,
+                        size,
+                        value,
+                    });
+                }
+            }
+
+            let mut unit = Unit::new(encoding, LineProgram::none());
+            unit.ranges = ranges;
+            unit.locations = locations;
+            unit.verbatim = Some(verbatim);
+            Ok(Some(unit))
+        }
+
         /// Create entry attributes by reading the data in the input sections.
         fn convert_attributes<R: Reader<Offset = usize>>(
             unit: ConvertUnit<R>,
@@ -1603,6 +4401,7 @@ pub(crate) mod convert {
             dwarf: &read::Dwarf<R>,
             line_strings: &mut write::LineStringTable,
             strings: &mut write::StringTable,
+            line_programs: Option<&mut ConvertLinePrograms>,
             convert_address: &dyn Fn(u64) -> Option<Address>,
         ) -> ConvertResult<Unit> {
             let from_unit = unit.from_unit;
@@ -1641,6 +4440,8 @@ pub(crate) mod convert {
                 base_address,
                 line_program_offset,
                 line_program_files,
+                line_programs,
+                raw_expressions: false,
             };
 
             let mut entries = unit.entries;
@@ -1648,14 +4449,31 @@ pub(crate) mod convert {
                 entry.convert_attributes(&mut context, &unit.entry_offsets)?;
             }
 
+            // `.debug_macinfo` is not written, so convert it to `.debug_macro`
+            // where possible. This requires version 4.
+            let mut macros = MacroTable::default();
+            if unit.encoding.version >= 4 {
+                let root = &mut entries[unit.root.index];
+                if let Some(&AttributeValue::DebugMacinfoRef(offset)) =
+                    root.get(constants::DW_AT_macro_info)
+                {
+                    macros.add(convert_macinfo(&context, offset)?);
+                    root.delete(constants::DW_AT_macro_info);
+                }
+            }
+
             Ok(Unit {
                 base_id: unit.base_id,
                 encoding: unit.encoding,
                 line_program,
                 ranges,
                 locations,
+                macros,
+                form_selector: None,
+                preserve_encoding: false,
                 entries,
                 root: unit.root,
+                verbatim: None,
             })
         }
     }
@@ -1704,13 +4522,20 @@ pub(crate) mod convert {
         ) -> ConvertResult<()> {
             let offset = entry_offsets[self.id.index];
             let from = context.unit.entry(offset)?;
+            // Record the encoding of the input for `Unit::preserve_encoding`.
+            self.abbrev_code = Some(from.code());
             let mut from_attrs = from.attrs();
             while let Some(from_attr) = from_attrs.next()? {
                 if from_attr.name() == constants::DW_AT_sibling {
                     // This may point to a null entry, so we have to treat it differently.
                     self.set_sibling(true);
-                } else if let Some(attr) = Attribute::from(context, &from_attr)? {
-                    self.set(attr.name, attr.value);
+                    self.sibling_index = self.attrs.len();
+                } else if let Some(mut attr) = Attribute::from(context, &from_attr)? {
+                    attr.form = Some(from_attr.form());
+                    match self.attrs.iter_mut().find(|x| x.name == attr.name) {
+                        Some(x) => *x = attr,
+                        None => self.attrs.push(attr),
+                    }
                 }
             }
             Ok(())
@@ -1723,14 +4548,207 @@ pub(crate) mod convert {
             context: &mut ConvertUnitContext<'_, R>,
             from: &read::Attribute<R>,
         ) -> ConvertResult<Option<Attribute>> {
-            let value = AttributeValue::from(context, from.value())?;
+            let value = match from.value() {
+                read::AttributeValue::Unknown(data) => Some(AttributeValue::Unknown(
+                    from.form(),
+                    data.to_slice()?.into(),
+                )),
+                value => AttributeValue::from(context, value)?,
+            };
             Ok(value.map(|value| Attribute {
                 name: from.name(),
                 value,
+                form: None,
             }))
         }
     }
 
+    /// Convert the `.debug_macinfo` entries at the given offset to a macro unit.
+    fn convert_macinfo<R: Reader<Offset = usize>>(
+        context: &ConvertUnitContext<'_, R>,
+        offset: DebugMacinfoOffset,
+    ) -> ConvertResult<MacroUnit> {
+        let convert_string = |string: read::MacroString<R>| -> ConvertResult<MacroString> {
+            let string = string.string(context.dwarf, context.unit)?;
+            Ok(MacroString::String(string.to_slice()?.into()))
+        };
+        let mut macro_unit = MacroUnit::new();
+        let mut from_entries = context.dwarf.debug_macinfo.get_macinfo(offset)?;
+        while let Some(from_entry) = from_entries.next()? {
+            let entry = match from_entry {
+                read::MacroEntry::Define { line, text } => Macro::Define {
+                    line,
+                    text: convert_string(text)?,
+                },
+                read::MacroEntry::Undef { line, name } => Macro::Undef {
+                    line,
+                    text: convert_string(name)?,
+                },
+                read::MacroEntry::StartFile { line, file } => {
+                    match context.line_program_files.get(file as usize) {
+                        Some(file) => Macro::StartFile { line, file: *file },
+                        None => return Err(ConvertError::InvalidFileIndex),
+                    }
+                }
+                read::MacroEntry::EndFile => Macro::EndFile,
+                _ => return Err(ConvertError::UnsupportedMacinfo),
+            };
+            macro_unit.add(entry);
+        }
+        Ok(macro_unit)
+    }
+
+    /// Return the size of the abbreviations table at the start of `input`,
+    /// including the null terminator.
+    fn abbreviations_size<R: Reader<Offset = usize>>(input: &mut R) -> read::Result<usize> {
+        let start = input.clone();
+        while input.read_uleb128()? != 0 {
+            // Tag.
+            input.read_uleb128()?;
+            // Children.
+            input.read_u8()?;
+            loop {
+                let name = input.read_uleb128()?;
+                let form = input.read_uleb128()?;
+                if name == 0 && form == 0 {
+                    break;
+                }
+                if form == u64::from(constants::DW_FORM_implicit_const.0) {
+                    input.read_sleb128()?;
+                }
+            }
+        }
+        Ok(input.offset_from(&start))
+    }
+
+    /// Return true if the expression can be copied without conversion.
+    ///
+    /// This is false for expressions that contain `.debug_info` offsets or
+    /// `.debug_addr` indices.
+    fn verbatim_expression<R: Reader>(
+        expression: read::Expression<R>,
+        encoding: Encoding,
+    ) -> read::Result<bool> {
+        let mut operations = expression.operations(encoding);
+        while let Some(operation) = operations.next()? {
+            match operation {
+                read::Operation::Call {
+                    offset: read::DieReference::DebugInfoRef(_),
+                }
+                | read::Operation::ImplicitPointer { .. }
+                | read::Operation::AddressIndex { .. }
+                | read::Operation::ConstantIndex { .. }
+                | read::Operation::Unknown { .. } => return Ok(false),
+                read::Operation::EntryValue { expression }
+                    if !verbatim_expression(read::Expression(expression.clone()), encoding)? =>
+                {
+                    return Ok(false)
+                }
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    /// Find the string references in the header of a line number program.
+    ///
+    /// Returns false if the line number program contains values that would be
+    /// invalidated by copying it.
+    fn verbatim_line_program<R: Reader<Offset = usize>>(
+        mut input: R,
+        encoding: Encoding,
+        dwarf: &read::Dwarf<R>,
+        line_strings: &mut write::LineStringTable,
+        strings: &mut write::StringTable,
+        fixups: &mut Vec<VerbatimFixup>,
+    ) -> ConvertResult<bool> {
+        if encoding.version <= 4 {
+            // Versions before 5 store strings inline.
+            return Ok(true);
+        }
+        let start = input.clone();
+        input.read_initial_length()?;
+        // Version, address size and segment selector size.
+        input.skip(4)?;
+        input.read_offset(encoding.format)?;
+        // Minimum instruction length, maximum operations per instruction,
+        // default is_stmt, line base and line range.
+        input.skip(5)?;
+        let opcode_base = input.read_u8()?;
+        input.skip(usize::from(opcode_base.saturating_sub(1)))?;
+        // The directory table, followed by the file name table.
+        for _ in 0..2 {
+            let format_count = input.read_u8()?;
+            let mut forms = Vec::with_capacity(format_count.into());
+            for _ in 0..format_count {
+                input.read_uleb128()?;
+                forms.push(constants::DwForm(input.read_uleb128_u16()?));
+            }
+            let count = input.read_uleb128()?;
+            for _ in 0..count {
+                for form in &forms {
+                    let spec =
+                        read::AttributeSpecification::new(constants::DW_AT_null, *form, None);
+                    let attr = read::parse_attribute(&mut input, encoding, spec)?;
+                    let offset = input.offset_from(&start) - encoding.format.word_size() as usize;
+                    let value = match (attr.form(), attr.raw_value()) {
+                        (constants::DW_FORM_strp, read::AttributeValue::DebugStrRef(val)) => {
+                            let r = dwarf.string(val)?;
+                            VerbatimValue::String(strings.add(r.to_slice()?))
+                        }
+                        (
+                            constants::DW_FORM_line_strp,
+                            read::AttributeValue::DebugLineStrRef(val),
+                        ) => {
+                            let r = dwarf.line_string(val)?;
+                            VerbatimValue::LineString(line_strings.add(r.to_slice()?))
+                        }
+                        (_, read::AttributeValue::DebugStrOffsetsIndex(_))
+                        | (_, read::AttributeValue::DebugStrRefSup(_)) => return Ok(false),
+                        _ => continue,
+                    };
+                    fixups.push(VerbatimFixup {
+                        offset,
+                        size: encoding.format.word_size(),
+                        value,
+                    });
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Convert a line program that is not the unit's own line program.
+    fn convert_line_program<R: Reader<Offset = usize>>(
+        context: &mut ConvertUnitContext<'_, R>,
+        offset: DebugLineOffset,
+    ) -> ConvertResult<LineProgramId> {
+        let line_programs = match context.line_programs {
+            Some(ref mut line_programs) => line_programs,
+            None => return Err(ConvertError::InvalidLineRef),
+        };
+        if let Some(id) = line_programs.ids.get(&offset) {
+            return Ok(*id);
+        }
+        let from_program = context.dwarf.debug_line.program(
+            offset,
+            context.unit.header.address_size(),
+            context.unit.comp_dir.clone(),
+            context.unit.name.clone(),
+        )?;
+        let (line_program, _) = LineProgram::from(
+            from_program,
+            context.dwarf,
+            context.line_strings,
+            context.strings,
+            context.convert_address,
+        )?;
+        let id = LineProgramId::new(line_programs.programs.len());
+        line_programs.programs.push(line_program);
+        line_programs.ids.insert(offset, id);
+        Ok(id)
+    }
+
     impl AttributeValue {
         /// Create an attribute value by reading the data in the given sections.
         pub(crate) fn from<R: Reader<Offset = usize>>(
@@ -1747,6 +4765,7 @@ pub(crate) mod convert {
                 read::AttributeValue::Data2(val) => AttributeValue::Data2(val),
                 read::AttributeValue::Data4(val) => AttributeValue::Data4(val),
                 read::AttributeValue::Data8(val) => AttributeValue::Data8(val),
+                read::AttributeValue::Data16(val) => AttributeValue::Data16(val),
                 read::AttributeValue::Sdata(val) => AttributeValue::Sdata(val),
                 read::AttributeValue::Udata(val) => AttributeValue::Udata(val),
                 read::AttributeValue::Exprloc(expression) => {
@@ -1799,7 +4818,7 @@ pub(crate) mod convert {
                     if Some(val) == context.line_program_offset {
                         AttributeValue::LineProgramRef
                     } else {
-                        return Err(ConvertError::InvalidLineRef);
+                        AttributeValue::DebugLineRef(convert_line_program(context, val)?)
                     }
                 }
                 read::AttributeValue::DebugMacinfoRef(val) => AttributeValue::DebugMacinfoRef(val),
@@ -1904,6 +4923,10 @@ pub(crate) mod convert {
                     return Err(ConvertError::InvalidAttributeValue);
                 }
                 read::AttributeValue::DwoId(DwoId(val)) => AttributeValue::Udata(val),
+                // Requires the form, which is handled by `Attribute::from`.
+                read::AttributeValue::Unknown(_) => {
+                    return Err(ConvertError::UnsupportedAttributeValue);
+                }
             };
             Ok(Some(to))
         }
@@ -1915,16 +4938,18 @@ pub(crate) mod convert {
 mod tests {
     use super::*;
     use crate::common::{
-        DebugAddrBase, DebugLocListsBase, DebugRngListsBase, DebugStrOffsetsBase, LineEncoding,
+        DebugAbbrevOffset, DebugAddrBase, DebugAddrIndex, DebugLocListsBase, DebugLocListsIndex,
+        DebugRngListsBase, DebugRngListsIndex, DebugStrOffsetsBase, DebugStrOffsetsIndex,
+        LineEncoding,
     };
     use crate::constants;
     use crate::read;
     use crate::write::{
-        DebugLine, DebugLineStr, DebugStr, DwarfUnit, EndianVec, LineString, LineStringTable,
-        Location, LocationList, LocationListTable, Range, RangeList, RangeListOffsets,
-        RangeListTable, StringTable,
+        ConvertError, DebugLine, DebugLineStr, DebugStr, Dwarf, DwarfUnit, EndianVec, LineString,
+        LineStringTable, Location, LocationList, LocationListTable, Macro, MacroString, Range,
+        RangeList, RangeListOffsets, RangeListTable, StringTable,
     };
-    use crate::LittleEndian;
+    use crate::{BigEndian, LittleEndian};
     use std::collections::HashMap;
     use std::mem;
     use std::sync::Arc;
@@ -2283,8 +5308,10 @@ mod tests {
                     };
 
                     let mut sections = Sections::new(EndianVec::new(LittleEndian));
-                    let range_list_offsets = ranges.write(&mut sections, encoding).unwrap();
-                    let loc_list_offsets = locations.write(&mut sections, encoding, None).unwrap();
+                    let range_list_offsets = ranges.write(&mut sections, encoding, false).unwrap();
+                    let loc_list_offsets = locations
+                        .write(&mut sections, encoding, None, false)
+                        .unwrap();
 
                     let read_debug_ranges =
                         read::DebugRanges::new(sections.debug_ranges.slice(), LittleEndian);
@@ -2489,17 +5516,20 @@ mod tests {
                         let attr = Attribute {
                             name: *name,
                             value: value.clone(),
+                            form: None,
                         };
 
                         let offsets = UnitOffsets::none();
                         let line_program_offset = None;
                         let mut debug_info_refs = Vec::new();
+                        let mut debug_line_refs = Vec::new();
                         let mut unit_refs = Vec::new();
                         let mut debug_info = DebugInfo::from(EndianVec::new(LittleEndian));
                         attr.value
                             .write(
                                 &mut debug_info,
                                 &mut debug_info_refs,
+                                &mut debug_line_refs,
                                 &mut unit_refs,
                                 unit,
                                 &offsets,
@@ -2541,6 +5571,7 @@ mod tests {
                             abbreviations: Arc::new(read::Abbreviations::default()),
                             name: None,
                             comp_dir: None,
+                            language: None,
                             low_pc: 0,
                             str_offsets_base: DebugStrOffsetsBase(0),
                             addr_base: DebugAddrBase(0),
@@ -2561,6 +5592,8 @@ mod tests {
                             base_address: Address::Constant(0),
                             line_program_offset: None,
                             line_program_files: Vec::new(),
+                            line_programs: None,
+                            raw_expressions: false,
                             entry_ids: &HashMap::new(),
                         };
 
@@ -2969,9 +6002,11 @@ mod tests {
                         let attr = Attribute {
                             name: *name,
                             value: value.clone(),
+                            form: None,
                         };
 
                         let mut debug_info_refs = Vec::new();
+                        let mut debug_line_refs = Vec::new();
                         let mut unit_refs = Vec::new();
                         let mut debug_info = DebugInfo::from(EndianVec::new(LittleEndian));
                         let offsets = UnitOffsets::none();
@@ -2983,6 +6018,7 @@ mod tests {
                             .write(
                                 &mut debug_info,
                                 &mut debug_info_refs,
+                                &mut debug_line_refs,
                                 &mut unit_refs,
                                 unit,
                                 &offsets,
@@ -3013,6 +6049,7 @@ mod tests {
                             abbreviations: Arc::new(read::Abbreviations::default()),
                             name: None,
                             comp_dir: None,
+                            language: None,
                             low_pc: 0,
                             str_offsets_base: DebugStrOffsetsBase(0),
                             addr_base: DebugAddrBase(0),
@@ -3033,6 +6070,8 @@ mod tests {
                             base_address: Address::Constant(0),
                             line_program_offset: Some(line_program_offset),
                             line_program_files: line_program_files.clone(),
+                            line_programs: None,
+                            raw_expressions: false,
                             entry_ids: &HashMap::new(),
                         };
 
@@ -3084,69 +6123,2579 @@ mod tests {
     }
 
     #[test]
-    fn test_delete_child() {
-        fn set_name(unit: &mut Unit, id: UnitEntryId, name: &str) {
-            let entry = unit.get_mut(id);
-            entry.set(constants::DW_AT_name, AttributeValue::String(name.into()));
-        }
-        fn check_name<R: read::Reader>(
-            entry: &read::DebuggingInformationEntry<'_, '_, R>,
-            debug_str: &read::DebugStr<R>,
-            name: &str,
-        ) {
-            let name_attr = entry.attr(constants::DW_AT_name).unwrap().unwrap();
-            let entry_name = name_attr.string_value(debug_str).unwrap();
-            let entry_name_str = entry_name.to_string().unwrap();
-            assert_eq!(entry_name_str, name);
-        }
+    fn test_debug_line_ref() {
         let encoding = Encoding {
             format: Format::Dwarf32,
             version: 4,
             address_size: 8,
         };
-        let mut dwarf = DwarfUnit::new(encoding);
-        let root = dwarf.unit.root();
+        let line_program = |name: &[u8], address| {
+            let mut program = LineProgram::new(
+                encoding,
+                LineEncoding::default(),
+                LineString::String(b"/dir".to_vec()),
+                LineString::String(name.to_vec()),
+                None,
+            );
+            let dir = program.default_directory();
+            let file = program.add_file(LineString::String(name.to_vec()), dir, None);
+            program.begin_sequence(Some(Address::Constant(address)));
+            program.row().file = file;
+            program.generate_row();
+            program.end_sequence(0x10);
+            program
+        };
 
-        // Add and delete entries in the root unit
-        let child1 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
-        set_name(&mut dwarf.unit, child1, "child1");
-        let grandchild1 = dwarf.unit.add(child1, constants::DW_TAG_variable);
-        set_name(&mut dwarf.unit, grandchild1, "grandchild1");
-        let child2 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
-        set_name(&mut dwarf.unit, child2, "child2");
-        // This deletes both `child1` and its child `grandchild1`
-        dwarf.unit.get_mut(root).delete_child(child1);
-        let child3 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
-        set_name(&mut dwarf.unit, child3, "child3");
-        let child4 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
-        set_name(&mut dwarf.unit, child4, "child4");
-        let grandchild4 = dwarf.unit.add(child4, constants::DW_TAG_variable);
-        set_name(&mut dwarf.unit, grandchild4, "grandchild4");
-        dwarf.unit.get_mut(child4).delete_child(grandchild4);
+        let mut dwarf = Dwarf::new();
+        let program_id = dwarf.add_line_program(line_program(b"b.c", 0x2000));
+        let unit_id = dwarf
+            .units
+            .add(Unit::new(encoding, line_program(b"a.c", 0x1000)));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let child = unit.add(root, constants::DW_TAG_module);
+        unit.get_mut(child).set(
+            constants::DW_AT_stmt_list,
+            AttributeValue::DebugLineRef(program_id),
+        );
 
         let mut sections = Sections::new(EndianVec::new(LittleEndian));
-
-        // Write DWARF data which should only include `child2`, `child3` and `child4`
         dwarf.write(&mut sections).unwrap();
 
-        let read_debug_info = read::DebugInfo::new(sections.debug_info.slice(), LittleEndian);
-        let read_debug_abbrev = read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian);
-        let read_debug_str = read::DebugStr::new(sections.debug_str.slice(), LittleEndian);
-        let read_unit = read_debug_info.units().next().unwrap().unwrap();
-        let abbrevs = read_unit.abbreviations(&read_debug_abbrev).unwrap();
-        let mut entries = read_unit.entries(&abbrevs);
-        // root
-        entries.next_dfs().unwrap().unwrap();
-        // child2
-        let (_, read_child2) = entries.next_dfs().unwrap().unwrap();
-        check_name(read_child2, &read_debug_str, "child2");
-        // child3
-        let (_, read_child3) = entries.next_dfs().unwrap().unwrap();
-        check_name(read_child3, &read_debug_str, "child3");
-        // child4
-        let (_, read_child4) = entries.next_dfs().unwrap().unwrap();
-        check_name(read_child4, &read_debug_str, "child4");
-        // There should be no more entries
-        assert!(entries.next_dfs().unwrap().is_none());
+        let read_dwarf = read::Dwarf {
+            debug_abbrev: read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian),
+            debug_info: read::DebugInfo::new(sections.debug_info.slice(), LittleEndian),
+            debug_line: read::DebugLine::new(sections.debug_line.slice(), LittleEndian),
+            ..Default::default()
+        };
+
+        // Check the reference was resolved to the second line program.
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let offset = match entry.attr_value(constants::DW_AT_stmt_list).unwrap() {
+            Some(read::AttributeValue::DebugLineRef(offset)) => offset,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        assert_ne!(
+            Some(offset),
+            read_unit
+                .line_program
+                .as_ref()
+                .map(|program| program.header().offset())
+        );
+        let program = read_dwarf
+            .debug_line
+            .program(offset, 8, None, None)
+            .unwrap();
+        let mut rows = program.rows();
+        let (_, row) = rows.next_row().unwrap().unwrap();
+        assert_eq!(row.address(), 0x2000);
+
+        // Only `Dwarf::from` can convert references to other line programs.
+        let mut line_strings = LineStringTable::default();
+        let mut strings = StringTable::default();
+        assert_eq!(
+            UnitTable::from(&read_dwarf, &mut line_strings, &mut strings, &|address| {
+                Some(Address::Constant(address))
+            })
+            .err(),
+            Some(ConvertError::InvalidLineRef)
+        );
+
+        let convert_dwarf =
+            Dwarf::from(&read_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        assert_eq!(convert_dwarf.line_programs.len(), 1);
+        let convert_unit = convert_dwarf.units.get(convert_dwarf.units.id(0));
+        let convert_root = convert_unit.get(convert_unit.root());
+        let convert_child = convert_unit.get(*convert_root.children().next().unwrap());
+        assert_eq!(
+            convert_child.get(constants::DW_AT_stmt_list),
+            Some(&AttributeValue::DebugLineRef(LineProgramId::new(0)))
+        );
+    }
+
+    #[test]
+    fn test_convert_lists() {
+        let write_sections = |dwarf: &mut Dwarf| {
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+            sections
+        };
+        for version in [2, 4, 5] {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version,
+                address_size: 8,
+            };
+            let mut dwarf = Dwarf::new();
+            let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(unit_id);
+            let range_list_id = unit.ranges.add(RangeList(vec![
+                Range::StartEnd {
+                    begin: Address::Constant(0x1000),
+                    end: Address::Constant(0x1010),
+                },
+                Range::StartLength {
+                    begin: Address::Constant(0x2000),
+                    length: 0x20,
+                },
+                Range::BaseAddress {
+                    address: Address::Constant(0x1000),
+                },
+                Range::OffsetPair {
+                    begin: 0x100,
+                    end: 0x110,
+                },
+            ]));
+            let mut expression = Expression::new();
+            expression.op_addr(Address::Constant(0x3000));
+            let loc_list_id = unit.locations.add(LocationList(vec![
+                Location::StartEnd {
+                    begin: Address::Constant(0x1000),
+                    end: Address::Constant(0x1008),
+                    data: expression.clone(),
+                },
+                Location::BaseAddress {
+                    address: Address::Constant(0x1000),
+                },
+                Location::OffsetPair {
+                    begin: 0x8,
+                    end: 0x10,
+                    data: expression,
+                },
+            ]));
+            let root = unit.root();
+            let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+            unit.get_mut(subprogram).set(
+                constants::DW_AT_ranges,
+                AttributeValue::RangeListRef(range_list_id),
+            );
+            let variable = unit.add(subprogram, constants::DW_TAG_variable);
+            unit.get_mut(variable).set(
+                constants::DW_AT_location,
+                AttributeValue::LocationListRef(loc_list_id),
+            );
+            let sections = write_sections(&mut dwarf);
+
+            // Convert with address translation, and write the result again.
+            let from_dwarf = sections.read_dwarf();
+            let mut convert_dwarf = Dwarf::from(&from_dwarf, &|address| {
+                Some(Address::Constant(address + 0x10000))
+            })
+            .unwrap();
+            let convert_sections = write_sections(&mut convert_dwarf);
+
+            let read_dwarf = convert_sections.read_dwarf();
+            let read_header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            let mut entries = read_unit.entries();
+            entries.next_dfs().unwrap();
+
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            assert_eq!(entry.tag(), constants::DW_TAG_subprogram);
+            let mut ranges = read_dwarf.die_ranges(&read_unit, entry).unwrap();
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(read::Range {
+                    begin: 0x11000,
+                    end: 0x11010
+                })
+            );
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(read::Range {
+                    begin: 0x12000,
+                    end: 0x12020
+                })
+            );
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(read::Range {
+                    begin: 0x11100,
+                    end: 0x11110
+                })
+            );
+            assert_eq!(ranges.next().unwrap(), None);
+
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            assert_eq!(entry.tag(), constants::DW_TAG_variable);
+            let value = entry
+                .attr_value(constants::DW_AT_location)
+                .unwrap()
+                .unwrap();
+            let mut locations = read_dwarf
+                .attr_locations(&read_unit, value)
+                .unwrap()
+                .unwrap();
+            let location = locations.next().unwrap().unwrap();
+            assert_eq!(
+                location.range,
+                read::Range {
+                    begin: 0x11000,
+                    end: 0x11008
+                }
+            );
+            let mut ops = location.data.operations(encoding);
+            assert_eq!(
+                ops.next().unwrap(),
+                Some(read::Operation::Address { address: 0x13000 })
+            );
+            let location = locations.next().unwrap().unwrap();
+            assert_eq!(
+                location.range,
+                read::Range {
+                    begin: 0x11008,
+                    end: 0x11010
+                }
+            );
+            assert_eq!(locations.next().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_convert_verbatim() {
+        let write_sections = |dwarf: &mut Dwarf| {
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+            sections
+        };
+        for version in [2, 4, 5] {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version,
+                address_size: 8,
+            };
+            let mut dwarf = Dwarf::new();
+            // Ensure that string offsets change during conversion.
+            dwarf.strings.add("unused");
+
+            // A unit that is modified.
+            let unit_id0 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit0 = dwarf.units.get_mut(unit_id0);
+            let root0 = unit0.root();
+            let variable0 = unit0.add(root0, constants::DW_TAG_variable);
+
+            // A unit that is copied.
+            let line_string = |dwarf: &mut Dwarf, val: &[u8]| {
+                if version >= 5 {
+                    LineString::LineStringRef(dwarf.line_strings.add(val))
+                } else {
+                    LineString::String(val.to_vec())
+                }
+            };
+            let comp_dir = line_string(&mut dwarf, b"/dir");
+            let comp_file = line_string(&mut dwarf, b"main.c");
+            let file_name = line_string(&mut dwarf, b"a.c");
+            let mut line_program =
+                LineProgram::new(encoding, LineEncoding::default(), comp_dir, comp_file, None);
+            let dir = line_program.default_directory();
+            let file = line_program.add_file(file_name, dir, None);
+            line_program.begin_sequence(Some(Address::Constant(0x1000)));
+            line_program.row().file = file;
+            line_program.row().line = 5;
+            line_program.generate_row();
+            line_program.end_sequence(0x10);
+            let name = dwarf.strings.add("unit1");
+            let unit_id1 = dwarf.units.add(Unit::new(encoding, line_program));
+            let unit1 = dwarf.units.get_mut(unit_id1);
+            let root1 = unit1.root();
+            unit1
+                .get_mut(root1)
+                .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+            let range_list_id = unit1.ranges.add(RangeList(vec![Range::StartEnd {
+                begin: Address::Constant(0x1000),
+                end: Address::Constant(0x1010),
+            }]));
+            let mut expression = Expression::new();
+            expression.op_breg(crate::Register(6), 8);
+            let loc_list_id = unit1.locations.add(LocationList(vec![Location::StartEnd {
+                begin: Address::Constant(0x1000),
+                end: Address::Constant(0x1008),
+                data: expression,
+            }]));
+            let subprogram = unit1.add(root1, constants::DW_TAG_subprogram);
+            let entry = unit1.get_mut(subprogram);
+            entry.set(
+                constants::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(file)),
+            );
+            entry.set(
+                constants::DW_AT_ranges,
+                AttributeValue::RangeListRef(range_list_id),
+            );
+            let variable1 = unit1.add(subprogram, constants::DW_TAG_variable);
+            unit1.get_mut(variable1).set(
+                constants::DW_AT_location,
+                AttributeValue::LocationListRef(loc_list_id),
+            );
+
+            // A unit that must be converted because it refers to another unit.
+            let unit_id2 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit2 = dwarf.units.get_mut(unit_id2);
+            let root2 = unit2.root();
+            let variable2 = unit2.add(root2, constants::DW_TAG_variable);
+            unit2.get_mut(variable2).set(
+                constants::DW_AT_specification,
+                AttributeValue::DebugInfoRef(Reference::Entry(unit_id0, variable0)),
+            );
+
+            // A unit that must be converted because it refers to macro information.
+            let unit_id3 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit3 = dwarf.units.get_mut(unit_id3);
+            let root3 = unit3.root();
+            if version >= 5 {
+                unit3.get_mut(root3).set(
+                    constants::DW_AT_macros,
+                    AttributeValue::DebugMacroRef(DebugMacroOffset(0)),
+                );
+            } else {
+                unit3.get_mut(root3).set(
+                    constants::DW_AT_macro_info,
+                    AttributeValue::DebugMacinfoRef(DebugMacinfoOffset(0)),
+                );
+            }
+
+            let sections = write_sections(&mut dwarf);
+            let from_dwarf = sections.read_dwarf();
+            let first_unit = from_dwarf.units().next().unwrap().unwrap().offset();
+            let mut convert_dwarf = Dwarf::from_modified(
+                &from_dwarf,
+                &|address| Some(Address::Constant(address)),
+                &|header| header.offset() == first_unit,
+            )
+            .unwrap();
+            let verbatim: Vec<_> = (0..4)
+                .map(|i| {
+                    convert_dwarf
+                        .units
+                        .get(convert_dwarf.units.id(i))
+                        .is_verbatim()
+                })
+                .collect();
+            assert_eq!(verbatim, [false, true, false, false]);
+            let convert_sections = write_sections(&mut convert_dwarf);
+            assert_ne!(
+                sections.debug_str.slice(),
+                convert_sections.debug_str.slice()
+            );
+
+            let read_dwarf = convert_sections.read_dwarf();
+            let mut read_units = read_dwarf.units();
+            read_units.next().unwrap().unwrap();
+            let read_header = read_units.next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            assert_eq!(read_unit.name.unwrap().slice(), b"unit1");
+
+            let program = read_unit.line_program.clone().unwrap();
+            let header = program.header().clone();
+            let mut rows = program.rows();
+            let (_, row) = rows.next_row().unwrap().unwrap();
+            assert_eq!(row.address(), 0x1000);
+            assert_eq!(row.line().map(|line| line.get()), Some(5));
+            let file_name = |index| {
+                let file = header.file(index).unwrap();
+                let name = read_dwarf
+                    .attr_string(&read_unit, file.path_name())
+                    .unwrap();
+                name.slice()
+            };
+            assert_eq!(file_name(row.file_index()), b"a.c");
+
+            let mut entries = read_unit.entries();
+            entries.next_dfs().unwrap();
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            assert_eq!(entry.tag(), constants::DW_TAG_subprogram);
+            match entry.attr_value(constants::DW_AT_decl_file).unwrap() {
+                Some(read::AttributeValue::FileIndex(index)) => {
+                    assert_eq!(file_name(index), b"a.c")
+                }
+                otherwise => panic!("unexpected {:?}", otherwise),
+            }
+            let mut ranges = read_dwarf.die_ranges(&read_unit, entry).unwrap();
+            assert_eq!(
+                ranges.next().unwrap(),
+                Some(read::Range {
+                    begin: 0x1000,
+                    end: 0x1010
+                })
+            );
+            assert_eq!(ranges.next().unwrap(), None);
+
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            let value = entry
+                .attr_value(constants::DW_AT_location)
+                .unwrap()
+                .unwrap();
+            let mut locations = read_dwarf
+                .attr_locations(&read_unit, value)
+                .unwrap()
+                .unwrap();
+            let location = locations.next().unwrap().unwrap();
+            assert_eq!(
+                location.range,
+                read::Range {
+                    begin: 0x1000,
+                    end: 0x1008
+                }
+            );
+            let mut ops = location.data.operations(encoding);
+            assert_eq!(
+                ops.next().unwrap(),
+                Some(read::Operation::RegisterOffset {
+                    register: crate::Register(6),
+                    offset: 8,
+                    base_type: read::UnitOffset(0),
+                })
+            );
+            assert_eq!(locations.next().unwrap(), None);
+
+            // The reference in the third unit is still valid.
+            let read_header = read_units.next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            let mut entries = read_unit.entries();
+            entries.next_dfs().unwrap();
+            let (_, entry) = entries.next_dfs().unwrap().unwrap();
+            let offset = match entry.attr_value(constants::DW_AT_specification).unwrap() {
+                Some(read::AttributeValue::DebugInfoRef(offset)) => offset,
+                otherwise => panic!("unexpected {:?}", otherwise),
+            };
+            let read_header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            let offset = offset.to_unit_offset(&read_unit.header).unwrap();
+            assert_eq!(
+                read_unit.entry(offset).unwrap().tag(),
+                constants::DW_TAG_variable
+            );
+        }
+    }
+
+    #[test]
+    fn test_preserve_encoding() {
+        #[rustfmt::skip]
+        let debug_abbrev = [
+            // Code 2: variable, no children.
+            2, 0x34, 0,
+            0x03, 0x08, // DW_AT_name, DW_FORM_string
+            0x49, 0x15, // DW_AT_type, DW_FORM_ref_udata
+            0x1c, 0x0a, // DW_AT_const_value, DW_FORM_block1
+            0x3b, 0x21, 3, // DW_AT_decl_line, DW_FORM_implicit_const
+            0, 0,
+            // Code 3: base type, no children.
+            3, 0x24, 0,
+            0x03, 0x08, // DW_AT_name, DW_FORM_string
+            0x3e, 0x0b, // DW_AT_encoding, DW_FORM_data1
+            0x0b, 0x0b, // DW_AT_byte_size, DW_FORM_data1
+            0, 0,
+            // Code 5: compile unit, children.
+            5, 0x11, 1,
+            0x25, 0x08, // DW_AT_producer, DW_FORM_string
+            0x13, 0x0b, // DW_AT_language, DW_FORM_data1
+            0, 0,
+            // Code 9: subprogram, children.
+            9, 0x2e, 1,
+            0x03, 0x08, // DW_AT_name, DW_FORM_string
+            0x01, 0x13, // DW_AT_sibling, DW_FORM_ref4
+            0x3b, 0x05, // DW_AT_decl_line, DW_FORM_data2
+            0x49, 0x11, // DW_AT_type, DW_FORM_ref1
+            0x3f, 0x19, // DW_AT_external, DW_FORM_flag_present
+            0, 0,
+            0,
+        ];
+        #[rustfmt::skip]
+        let debug_info = [
+            // Unit header: length, version 5, DW_UT_compile, address size, abbrev offset.
+            38, 0, 0, 0, 5, 0, 1, 8, 0, 0, 0, 0,
+            // 0x0c: compile unit.
+            5, b'p', 0, 0x1c,
+            // 0x10: subprogram, with a sibling and a type that follow it.
+            9, b'f', 0, 0x22, 0, 0, 0, 7, 0, 0x22,
+            // 0x1a: variable.
+            2, b'x', 0, 0x22, 2, 1, 2,
+            0,
+            // 0x22: base type.
+            3, b'i', b'n', b't', 0, 5, 4,
+            0,
+        ];
+
+        let from_dwarf = read::Dwarf::load(|id| -> read::Result<_> {
+            let data: &[u8] = match id {
+                SectionId::DebugAbbrev => &debug_abbrev,
+                SectionId::DebugInfo => &debug_info,
+                _ => &[],
+            };
+            Ok(read::EndianSlice::new(data, LittleEndian))
+        })
+        .unwrap();
+
+        for preserve_encoding in [false, true] {
+            let mut dwarf =
+                Dwarf::from(&from_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+            let unit_id = dwarf.units.id(0);
+            dwarf.units.get_mut(unit_id).preserve_encoding = preserve_encoding;
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+            let identical = sections.debug_abbrev.slice() == &debug_abbrev[..]
+                && sections.debug_info.slice() == &debug_info[..];
+            assert_eq!(identical, preserve_encoding);
+        }
+
+        // Forms that can't encode a modified value are replaced.
+        let mut dwarf =
+            Dwarf::from(&from_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        let unit_id = dwarf.units.id(0);
+        let unit = dwarf.units.get_mut(unit_id);
+        unit.preserve_encoding = true;
+        let root = unit.root();
+        let subprogram = unit.get(root).children().copied().next().unwrap();
+        unit.get_mut(subprogram)
+            .set(constants::DW_AT_decl_line, AttributeValue::Udata(0x1_0000));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let read_dwarf = sections.read_dwarf();
+        let read_unit = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_unit).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.code(), 9);
+        let attr = entry.attr(constants::DW_AT_decl_line).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_udata);
+        assert_eq!(attr.udata_value(), Some(0x1_0000));
+        let attr = entry.attr(constants::DW_AT_sibling).unwrap().unwrap();
+        assert_eq!(
+            attr.value(),
+            read::AttributeValue::UnitRef(read::UnitOffset(0x23))
+        );
+
+        // Forms that aren't supported by an older version are replaced.
+        let mut dwarf =
+            Dwarf::from(&from_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        let unit_id = dwarf.units.id(0);
+        let unit = dwarf.units.get_mut(unit_id);
+        unit.preserve_encoding = true;
+        unit.set_version(3, &dwarf.line_strings, &mut dwarf.strings)
+            .unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let read_dwarf = sections.read_dwarf();
+        let read_unit = read_dwarf.units().next().unwrap().unwrap();
+        assert_eq!(read_unit.version(), 3);
+        let read_unit = read_dwarf.unit(read_unit).unwrap();
+        let mut entries = read_unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            let mut attrs = entry.attrs();
+            while let Some(attr) = attrs.next().unwrap() {
+                assert_ne!(attr.form(), constants::DW_FORM_flag_present);
+                assert_ne!(attr.form(), constants::DW_FORM_implicit_const);
+            }
+        }
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap();
+        let (_, subprogram) = entries.next_dfs().unwrap().unwrap();
+        let attr = subprogram.attr(constants::DW_AT_external).unwrap().unwrap();
+        assert_eq!(attr.value(), read::AttributeValue::Flag(true));
+    }
+
+    #[test]
+    fn test_convert_unit() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id0 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit_id1 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let name = dwarf.strings.add("int");
+        let unit1 = dwarf.units.get_mut(unit_id1);
+        let root1 = unit1.root();
+        let base_type = unit1.add(root1, constants::DW_TAG_base_type);
+        unit1
+            .get_mut(base_type)
+            .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        let variable1 = unit1.add(root1, constants::DW_TAG_variable);
+        unit1
+            .get_mut(variable1)
+            .set(constants::DW_AT_type, AttributeValue::UnitRef(base_type));
+        let variable2 = unit1.add(root1, constants::DW_TAG_variable);
+        unit1.get_mut(variable2).set(
+            constants::DW_AT_type,
+            AttributeValue::DebugInfoRef(Reference::Entry(unit_id1, base_type)),
+        );
+        let unit0 = dwarf.units.get_mut(unit_id0);
+        let root0 = unit0.root();
+        let variable0 = unit0.add(root0, constants::DW_TAG_variable);
+        unit0.get_mut(variable0).set(
+            constants::DW_AT_type,
+            AttributeValue::DebugInfoRef(Reference::Entry(unit_id1, base_type)),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let read_dwarf = sections.read_dwarf();
+        let mut read_units = read_dwarf.units();
+        let read_header0 = read_units.next().unwrap().unwrap();
+        let read_header1 = read_units.next().unwrap().unwrap();
+
+        let mut line_strings = LineStringTable::default();
+        let mut strings = StringTable::default();
+        let convert_address = &|address| Some(Address::Constant(address));
+        assert_eq!(
+            Unit::from(
+                read_header0,
+                &read_dwarf,
+                &mut line_strings,
+                &mut strings,
+                convert_address,
+            )
+            .err(),
+            Some(ConvertError::InvalidDebugInfoRef)
+        );
+
+        let unit = Unit::from(
+            read_header1,
+            &read_dwarf,
+            &mut line_strings,
+            &mut strings,
+            convert_address,
+        )
+        .unwrap();
+        let children: Vec<_> = unit.get(unit.root()).children().copied().collect();
+        assert_eq!(children.len(), 3);
+        let base_type = unit.get(children[0]);
+        assert_eq!(base_type.tag(), constants::DW_TAG_base_type);
+        assert!(matches!(
+            base_type.get(constants::DW_AT_name),
+            Some(AttributeValue::StringRef(id)) if strings.get(*id) == b"int"
+        ));
+        for variable in &children[1..] {
+            assert_eq!(
+                unit.get(*variable).get(constants::DW_AT_type),
+                Some(&AttributeValue::UnitRef(children[0]))
+            );
+        }
+
+        // The unit can be written on its own.
+        let mut dwarf = DwarfUnit::new(encoding);
+        dwarf.unit = unit;
+        dwarf.strings = strings;
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+    }
+
+    #[test]
+    fn test_set_version() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"/dir".to_vec()),
+            LineString::String(b"file.c".to_vec()),
+            None,
+        );
+        let dir = program.default_directory();
+        let file = program.add_file(LineString::String(b"file.c".to_vec()), dir, None);
+        program.begin_sequence(Some(Address::Constant(0x1000)));
+        program.row().file = file;
+        program.generate_row();
+        program.end_sequence(0x10);
+
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let range_list_id = unit.ranges.add(RangeList(vec![Range::StartLength {
+            begin: Address::Constant(0x1000),
+            length: 0x10,
+        }]));
+        let root = unit.root();
+        let producer = dwarf.strings.add("producer");
+        unit.get_mut(root).set(
+            constants::DW_AT_producer,
+            AttributeValue::StringRef(producer),
+        );
+        let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+        let subprogram_entry = unit.get_mut(subprogram);
+        subprogram_entry.set(
+            constants::DW_AT_ranges,
+            AttributeValue::RangeListRef(range_list_id),
+        );
+        subprogram_entry.set(
+            constants::DW_AT_GNU_all_call_sites,
+            AttributeValue::Flag(true),
+        );
+        let call_site = unit.add(subprogram, constants::DW_TAG_GNU_call_site);
+        unit.get_mut(call_site).set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x1008)),
+        );
+        unit.get_mut(call_site).set(
+            constants::DW_AT_abstract_origin,
+            AttributeValue::UnitRef(subprogram),
+        );
+
+        assert_eq!(dwarf.set_version(6), Err(Error::UnsupportedVersion(6)));
+        dwarf.set_version(5).unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        assert!(sections.debug_ranges.slice().is_empty());
+        assert!(!sections.debug_rnglists.slice().is_empty());
+        assert!(!sections.debug_str_offsets.slice().is_empty());
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        assert_eq!(read_header.version(), 5);
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        assert_eq!(
+            read_unit.line_program.as_ref().unwrap().header().version(),
+            5
+        );
+        let mut entries = read_unit.entries();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let producer = entry.attr(constants::DW_AT_producer).unwrap().unwrap();
+        assert_eq!(producer.form(), constants::DW_FORM_strx);
+        assert_eq!(
+            read_dwarf
+                .attr_string(&read_unit, producer.value())
+                .unwrap()
+                .slice(),
+            b"producer"
+        );
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(
+            entry.attr_value(constants::DW_AT_call_all_calls).unwrap(),
+            Some(read::AttributeValue::Flag(true))
+        );
+        let mut ranges = read_dwarf.die_ranges(&read_unit, entry).unwrap();
+        assert_eq!(
+            ranges.next().unwrap(),
+            Some(read::Range {
+                begin: 0x1000,
+                end: 0x1010
+            })
+        );
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), constants::DW_TAG_call_site);
+        assert_eq!(
+            entry.attr_value(constants::DW_AT_call_return_pc).unwrap(),
+            Some(read::AttributeValue::Addr(0x1008))
+        );
+        assert!(entry
+            .attr_value(constants::DW_AT_call_origin)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_set_version_macinfo() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id1 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit_id2 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id2);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_macro_info,
+            AttributeValue::DebugMacinfoRef(DebugMacinfoOffset(0)),
+        );
+
+        // No units are changed if any unit can't be changed.
+        assert_eq!(dwarf.set_version(5), Err(Error::UnsupportedMacinfo));
+        assert_eq!(dwarf.units.get(unit_id1).version(), 4);
+        assert_eq!(dwarf.units.get(unit_id2).version(), 4);
+        assert!(dwarf.units.get(unit_id1).form_selector.is_none());
+        dwarf.set_version(3).unwrap();
+
+        let unit = dwarf.units.get_mut(unit_id2);
+        let root = unit.root();
+        unit.get_mut(root).delete(constants::DW_AT_macro_info);
+        dwarf.set_version(5).unwrap();
+        assert_eq!(dwarf.units.get(unit_id2).version(), 5);
+    }
+
+    #[test]
+    fn test_convert_macinfo() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let mut line_program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"comp_dir".to_vec()),
+            LineString::String(b"comp_name".to_vec()),
+            None,
+        );
+        let dir = line_program.default_directory();
+        let file = line_program.add_file(LineString::String(b"a.h".to_vec()), dir, None);
+        line_program.begin_sequence(Some(Address::Constant(0x1000)));
+        line_program.row().file = file;
+        line_program.generate_row();
+        line_program.end_sequence(0x10);
+        let unit_id = dwarf.units.add(Unit::new(encoding, line_program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_macro_info,
+            AttributeValue::DebugMacinfoRef(DebugMacinfoOffset(0)),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let mut read_dwarf = sections.read_dwarf();
+        let convert_address = &|address| Some(Address::Constant(address));
+
+        let macinfo = [
+            &[constants::DW_MACINFO_start_file.0, 0, 1][..],
+            &[constants::DW_MACINFO_define.0, 1],
+            b"A 1\0",
+            &[constants::DW_MACINFO_undef.0, 2],
+            b"A\0",
+            &[constants::DW_MACINFO_end_file.0, 0],
+        ]
+        .concat();
+        read_dwarf.debug_macinfo = read::DebugMacinfo::new(&macinfo, LittleEndian);
+        let mut convert_dwarf = Dwarf::from(&read_dwarf, convert_address).unwrap();
+        let unit = convert_dwarf.units.get(convert_dwarf.units.id(0));
+        let root = unit.get(unit.root());
+        assert_eq!(root.get(constants::DW_AT_macro_info), None);
+        let entries = unit.macros.get(unit.macros.root()).entries();
+        assert_eq!(entries.len(), 4);
+        match entries[0] {
+            Macro::StartFile { line: 0, file } => {
+                let (name, _) = unit.line_program.get_file(file);
+                assert_eq!(*name, LineString::String(b"a.h".to_vec()));
+            }
+            ref otherwise => panic!("unexpected {:?}", otherwise),
+        }
+        assert_eq!(
+            entries[1..],
+            [
+                Macro::Define {
+                    line: 1,
+                    text: MacroString::String(b"A 1".to_vec()),
+                },
+                Macro::Undef {
+                    line: 2,
+                    text: MacroString::String(b"A".to_vec()),
+                },
+                Macro::EndFile,
+            ]
+        );
+        // The macro information no longer prevents changing to version 5.
+        convert_dwarf.set_version(5).unwrap();
+
+        let macinfo = [&[constants::DW_MACINFO_vendor_ext.0, 1][..], b"x\0", &[0]].concat();
+        read_dwarf.debug_macinfo = read::DebugMacinfo::new(&macinfo, LittleEndian);
+        assert_eq!(
+            Dwarf::from(&read_dwarf, convert_address).err(),
+            Some(ConvertError::UnsupportedMacinfo)
+        );
+    }
+
+    #[test]
+    fn test_set_version_downgrade() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let dir = LineString::LineStringRef(dwarf.line_strings.add("/dir"));
+        let file = LineString::LineStringRef(dwarf.line_strings.add("file.c"));
+        let mut program =
+            LineProgram::new(encoding, LineEncoding::default(), dir, file.clone(), None);
+        let dir = program.default_directory();
+        let file = program.add_file(file, dir, None);
+        program.begin_sequence(Some(Address::Constant(0x1000)));
+        program.row().file = file;
+        program.generate_row();
+        program.end_sequence(0x10);
+
+        let unit_id = dwarf.units.add(Unit::new(encoding, program));
+        let unit = dwarf.units.get_mut(unit_id);
+        let range_list_id = unit.ranges.add(RangeList(vec![Range::StartLength {
+            begin: Address::Constant(0x1000),
+            length: 0x10,
+        }]));
+        let root = unit.root();
+        let name = dwarf.line_strings.add("file.c");
+        unit.get_mut(root)
+            .set(constants::DW_AT_name, AttributeValue::LineStringRef(name));
+        let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+        unit.get_mut(subprogram).set(
+            constants::DW_AT_ranges,
+            AttributeValue::RangeListRef(range_list_id),
+        );
+        let call_site = unit.add(subprogram, constants::DW_TAG_call_site);
+        unit.get_mut(call_site).set(
+            constants::DW_AT_call_return_pc,
+            AttributeValue::Address(Address::Constant(0x1008)),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        // Convert the DWARF 5 sections to version 4.
+        let read_dwarf = sections.read_dwarf();
+        let mut dwarf =
+            Dwarf::from(&read_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        dwarf.set_version(4).unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        assert!(sections.debug_line_str.slice().is_empty());
+        assert!(sections.debug_rnglists.slice().is_empty());
+        assert!(!sections.debug_ranges.slice().is_empty());
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        assert_eq!(read_header.version(), 4);
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let read_program = read_unit.line_program.as_ref().unwrap().header();
+        assert_eq!(read_program.version(), 4);
+        let read_file = read_program.file(1).unwrap();
+        assert_eq!(
+            read_dwarf
+                .attr_string(&read_unit, read_file.path_name())
+                .unwrap()
+                .slice(),
+            b"file.c"
+        );
+        let mut entries = read_unit.entries();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let name = entry.attr_value(constants::DW_AT_name).unwrap().unwrap();
+        assert_eq!(
+            read_dwarf.attr_string(&read_unit, name).unwrap().slice(),
+            b"file.c"
+        );
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let mut ranges = read_dwarf.die_ranges(&read_unit, entry).unwrap();
+        assert_eq!(
+            ranges.next().unwrap(),
+            Some(read::Range {
+                begin: 0x1000,
+                end: 0x1010
+            })
+        );
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), constants::DW_TAG_GNU_call_site);
+        assert_eq!(
+            entry.attr_value(constants::DW_AT_low_pc).unwrap(),
+            Some(read::AttributeValue::Addr(0x1008))
+        );
+    }
+
+    #[test]
+    fn test_delete_entries() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id1 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit_id2 = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+
+        let unit1 = dwarf.units.get_mut(unit_id1);
+        let root1 = unit1.root();
+        unit1.get_mut(root1).set(
+            constants::DW_AT_producer,
+            AttributeValue::String(b"producer".to_vec()),
+        );
+        let subprogram = unit1.add(root1, constants::DW_TAG_subprogram);
+        let block = unit1.add(subprogram, constants::DW_TAG_lexical_block);
+        let variable1 = unit1.add(block, constants::DW_TAG_variable);
+        let variable2 = unit1.add(subprogram, constants::DW_TAG_variable);
+        let mut expression = Expression::new();
+        expression.op_implicit_pointer(Reference::Entry(unit_id1, variable1), 0);
+        unit1.get_mut(variable2).set(
+            constants::DW_AT_location,
+            AttributeValue::Exprloc(expression),
+        );
+        unit1.get_mut(variable2).set(
+            constants::DW_AT_abstract_origin,
+            AttributeValue::UnitRef(variable1),
+        );
+        unit1.get_mut(variable2).set(
+            constants::DW_AT_name,
+            AttributeValue::String(b"variable2".to_vec()),
+        );
+
+        let unit2 = dwarf.units.get_mut(unit_id2);
+        let root2 = unit2.root();
+        let variable3 = unit2.add(root2, constants::DW_TAG_variable);
+        unit2.get_mut(variable3).set(
+            constants::DW_AT_specification,
+            AttributeValue::DebugInfoRef(Reference::Entry(unit_id1, variable1)),
+        );
+        unit2.get_mut(variable3).set(
+            constants::DW_AT_abstract_origin,
+            AttributeValue::DebugInfoRef(Reference::Entry(unit_id1, variable2)),
+        );
+
+        dwarf
+            .units
+            .delete_entries(|_, entry| entry.tag() == constants::DW_TAG_lexical_block);
+        dwarf
+            .units
+            .get_mut(unit_id1)
+            .delete_attributes(|_, attr| attr.name() == constants::DW_AT_producer);
+
+        let unit1 = dwarf.units.get(unit_id1);
+        assert_eq!(unit1.get(root1).attrs().count(), 0);
+        assert_eq!(
+            unit1
+                .get(subprogram)
+                .children()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![variable2]
+        );
+        let attrs = unit1
+            .get(variable2)
+            .attrs()
+            .map(Attribute::name)
+            .collect::<Vec<_>>();
+        assert_eq!(attrs, vec![constants::DW_AT_name]);
+        let unit2 = dwarf.units.get(unit_id2);
+        let attrs = unit2
+            .get(variable3)
+            .attrs()
+            .map(Attribute::name)
+            .collect::<Vec<_>>();
+        assert_eq!(attrs, vec![constants::DW_AT_abstract_origin]);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        // Deleting from a single unit keeps references from other units.
+        let unit2 = dwarf.units.get_mut(unit_id2);
+        let variable4 = unit2.add(root2, constants::DW_TAG_variable);
+        unit2
+            .get_mut(variable4)
+            .set(constants::DW_AT_type, AttributeValue::UnitRef(variable3));
+        dwarf.units.get_mut(unit_id1).delete_entries(|_| true);
+        let unit1 = dwarf.units.get(unit_id1);
+        assert_eq!(unit1.get(root1).children().count(), 0);
+        let unit2 = dwarf.units.get_mut(unit_id2);
+        assert!(unit2
+            .get(variable3)
+            .get(constants::DW_AT_abstract_origin)
+            .is_some());
+        unit2.delete_entries(|entry| entry.id() == variable3);
+        assert_eq!(unit2.get(variable4).attrs().count(), 0);
+    }
+
+    #[test]
+    fn test_delete_entries_location_list() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+        let procedure = unit.add(root, constants::DW_TAG_dwarf_procedure);
+        let location_list = |operation: &dyn Fn(&mut Expression)| {
+            let mut expression = Expression::new();
+            operation(&mut expression);
+            LocationList(vec![Location::StartLength {
+                begin: Address::Constant(0x1000),
+                length: 0x10,
+                data: expression,
+            }])
+        };
+        let dead_list = unit
+            .locations
+            .add(location_list(&|expression| expression.op_call(procedure)));
+        let live_list = unit.locations.add(location_list(&|expression| {
+            expression.op_reg(crate::Register(0))
+        }));
+        let variable1 = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(variable1).set(
+            constants::DW_AT_location,
+            AttributeValue::LocationListRef(dead_list),
+        );
+        let variable2 = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(variable2).set(
+            constants::DW_AT_location,
+            AttributeValue::LocationListRef(live_list),
+        );
+
+        unit.delete_entries(|entry| entry.id() == procedure);
+        assert!(unit.get(variable1).get(constants::DW_AT_location).is_none());
+        let id = match unit.get(variable2).get(constants::DW_AT_location) {
+            Some(AttributeValue::LocationListRef(id)) => *id,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        assert_eq!(
+            unit.locations.get(id),
+            &location_list(&|expression| expression.op_reg(crate::Register(0)))
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+    }
+
+    #[test]
+    fn test_discard_addresses() {
+        fn build() -> (Dwarf, [UnitEntryId; 7]) {
+            let encoding = Encoding {
+                format: Format::Dwarf32,
+                version: 4,
+                address_size: 8,
+            };
+            let mut program = LineProgram::new(
+                encoding,
+                LineEncoding::default(),
+                LineString::String(b"dir".to_vec()),
+                LineString::String(b"file".to_vec()),
+                None,
+            );
+            for &address in &[0x1000, 0x2000] {
+                program.begin_sequence(Some(Address::Constant(address)));
+                program.generate_row();
+                program.end_sequence(0x10);
+            }
+
+            let mut dwarf = Dwarf::new();
+            let unit_id = dwarf.units.add(Unit::new(encoding, program));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            let range_list_id = unit.ranges.add(RangeList(vec![
+                Range::StartLength {
+                    begin: Address::Constant(0x1000),
+                    length: 0x10,
+                },
+                Range::StartLength {
+                    begin: Address::Constant(0x2000),
+                    length: 0x10,
+                },
+            ]));
+            unit.get_mut(root).set(
+                constants::DW_AT_ranges,
+                AttributeValue::RangeListRef(range_list_id),
+            );
+
+            let int = unit.add(root, constants::DW_TAG_base_type);
+            let long = unit.add(root, constants::DW_TAG_base_type);
+            let pointer = unit.add(root, constants::DW_TAG_pointer_type);
+            unit.get_mut(pointer)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(long));
+
+            let mut subprograms = Vec::new();
+            for &(address, ty) in &[(0x1000, int), (0x2000, pointer)] {
+                let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+                let entry = unit.get_mut(subprogram);
+                entry.set(
+                    constants::DW_AT_low_pc,
+                    AttributeValue::Address(Address::Constant(address)),
+                );
+                entry.set(constants::DW_AT_high_pc, AttributeValue::Udata(0x10));
+                let variable = unit.add(subprogram, constants::DW_TAG_variable);
+                unit.get_mut(variable)
+                    .set(constants::DW_AT_type, AttributeValue::UnitRef(ty));
+                subprograms.push(subprogram);
+            }
+
+            let variable = unit.add(root, constants::DW_TAG_variable);
+            let mut expression = Expression::new();
+            expression.op_addr(Address::Constant(0x2100));
+            unit.get_mut(variable).set(
+                constants::DW_AT_location,
+                AttributeValue::Exprloc(expression),
+            );
+            (
+                dwarf,
+                [
+                    root,
+                    int,
+                    long,
+                    pointer,
+                    subprograms[0],
+                    subprograms[1],
+                    variable,
+                ],
+            )
+        }
+
+        fn sequences(dwarf: &mut Dwarf) -> Vec<u64> {
+            let mut sections = Sections::new(EndianVec::new(LittleEndian));
+            dwarf.write(&mut sections).unwrap();
+            let read_dwarf = sections.read_dwarf();
+            let read_header = read_dwarf.units().next().unwrap().unwrap();
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            let program = read_unit.line_program.unwrap();
+            let (_, sequences) = program.sequences().unwrap();
+            sequences.iter().map(|sequence| sequence.start).collect()
+        }
+
+        let live = |address| match address {
+            Address::Constant(address) => address < 0x2000,
+            Address::Symbol { .. } => true,
+        };
+
+        let (mut dwarf, [root, int, _, _, subprogram1, _, variable]) = build();
+        dwarf.discard_addresses(live, TombstonePolicy::Delete);
+        let unit = dwarf.units.get(dwarf.units.id(0));
+        assert_eq!(
+            unit.get(root).children().copied().collect::<Vec<_>>(),
+            vec![int, subprogram1, variable]
+        );
+        assert_eq!(unit.get(variable).attrs().count(), 0);
+        match unit.get(root).get(constants::DW_AT_ranges) {
+            Some(AttributeValue::RangeListRef(id)) => {
+                assert_eq!(
+                    unit.ranges.get(*id),
+                    &RangeList(vec![Range::StartLength {
+                        begin: Address::Constant(0x1000),
+                        length: 0x10,
+                    }])
+                );
+            }
+            otherwise => panic!("unexpected {:?}", otherwise),
+        }
+        assert_eq!(sequences(&mut dwarf), vec![0x1000]);
+
+        let (mut dwarf, [root, int, long, pointer, subprogram1, subprogram2, variable]) = build();
+        dwarf.discard_addresses(live, TombstonePolicy::Address(0));
+        let unit = dwarf.units.get(dwarf.units.id(0));
+        assert_eq!(
+            unit.get(root).children().copied().collect::<Vec<_>>(),
+            vec![int, long, pointer, subprogram1, subprogram2, variable]
+        );
+        assert_eq!(
+            unit.get(subprogram2).get(constants::DW_AT_low_pc),
+            Some(&AttributeValue::Address(Address::Constant(0)))
+        );
+        let mut expression = Expression::new();
+        expression.op_addr(Address::Constant(0));
+        assert_eq!(
+            unit.get(variable).get(constants::DW_AT_location),
+            Some(&AttributeValue::Exprloc(expression))
+        );
+        assert_eq!(sequences(&mut dwarf), vec![0x1000, 0]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        // Add a unit containing `ns::S`, and a variable that refers to it.
+        fn add_unit(
+            units: &mut UnitTable,
+            encoding: Encoding,
+            decl_line: u64,
+            byte_size: u64,
+        ) -> (UnitId, UnitEntryId, UnitEntryId) {
+            let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = units.get_mut(unit_id);
+            let root = unit.root();
+            let int = unit.add(root, constants::DW_TAG_base_type);
+            unit.get_mut(int).set(
+                constants::DW_AT_name,
+                AttributeValue::String(b"int".to_vec()),
+            );
+            let namespace = unit.add(root, constants::DW_TAG_namespace);
+            unit.get_mut(namespace).set(
+                constants::DW_AT_name,
+                AttributeValue::String(b"ns".to_vec()),
+            );
+            let structure = unit.add(namespace, constants::DW_TAG_structure_type);
+            let entry = unit.get_mut(structure);
+            entry.set(constants::DW_AT_name, AttributeValue::String(b"S".to_vec()));
+            entry.set(constants::DW_AT_byte_size, AttributeValue::Udata(byte_size));
+            entry.set(constants::DW_AT_decl_line, AttributeValue::Udata(decl_line));
+            let member = unit.add(structure, constants::DW_TAG_member);
+            unit.get_mut(member)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(int));
+            let variable = unit.add(root, constants::DW_TAG_variable);
+            unit.get_mut(variable)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(structure));
+            (unit_id, structure, variable)
+        }
+
+        let mut units1 = UnitTable::default();
+        let (unit_id1, structure1, _) = add_unit(&mut units1, encoding, 1, 4);
+
+        let mut units2 = UnitTable::default();
+        let (unit_id2, structure2, variable2) = add_unit(&mut units2, encoding, 2, 4);
+        let (_, structure3, variable3) = add_unit(&mut units2, encoding, 3, 8);
+        let unit_id4 = units2.add(Unit::new(encoding, LineProgram::none()));
+        let unit4 = units2.get_mut(unit_id4);
+        let root4 = unit4.root();
+        let variable4 = unit4.add(root4, constants::DW_TAG_variable);
+        unit4.get_mut(variable4).set(
+            constants::DW_AT_type,
+            AttributeValue::DebugInfoRef(Reference::Entry(unit_id2, structure2)),
+        );
+
+        let mut dwarf = Dwarf::new();
+        dwarf.units = units1;
+        let ids = dwarf.units.merge(units2);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[0], dwarf.units.id(1));
+
+        // The definition in the second unit is a duplicate.
+        let unit2 = dwarf.units.get(ids[0]);
+        assert_eq!(unit2.get(unit2.root()).children().count(), 3);
+        let namespace2 = *unit2.get(unit2.root()).children().nth(1).unwrap();
+        assert_eq!(unit2.get(namespace2).children().count(), 0);
+        assert_eq!(
+            unit2.get(variable2).get(constants::DW_AT_type),
+            Some(&AttributeValue::DebugInfoRef(Reference::Entry(
+                unit_id1, structure1
+            )))
+        );
+
+        // The definition in the third unit has a different size.
+        let unit3 = dwarf.units.get(ids[1]);
+        assert_eq!(
+            unit3.get(variable3).get(constants::DW_AT_type),
+            Some(&AttributeValue::UnitRef(structure3))
+        );
+
+        // References to other merged units are updated.
+        let unit4 = dwarf.units.get(ids[2]);
+        assert_eq!(
+            unit4.get(variable4).get(constants::DW_AT_type),
+            Some(&AttributeValue::DebugInfoRef(Reference::Entry(
+                unit_id1, structure1
+            )))
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+    }
+
+    #[test]
+    fn test_create_partial_units() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        // Add a unit containing `int`, `S`, `S *`, and a variable that refers
+        // to the pointer. Only the first two units contain `S`.
+        let add_unit = |dwarf: &mut Dwarf, structure: bool| {
+            let dir = LineString::String(b"/dir".to_vec());
+            let file = LineString::String(b"file.c".to_vec());
+            let mut program = LineProgram::new(encoding, LineEncoding::default(), dir, file, None);
+            let include = program.add_directory(LineString::String(b"/include".to_vec()));
+            let header = program.add_file(LineString::String(b"s.h".to_vec()), include, None);
+            let unit_id = dwarf.units.add(Unit::new(encoding, program));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            unit.get_mut(root).set(
+                constants::DW_AT_language,
+                AttributeValue::Language(constants::DW_LANG_C11),
+            );
+            let int = unit.add(root, constants::DW_TAG_base_type);
+            let entry = unit.get_mut(int);
+            entry.set(
+                constants::DW_AT_name,
+                AttributeValue::String(b"int".to_vec()),
+            );
+            entry.set(constants::DW_AT_byte_size, AttributeValue::Udata(4));
+            // A base type that is referenced by an expression must not be moved.
+            let long = unit.add(root, constants::DW_TAG_base_type);
+            let entry = unit.get_mut(long);
+            entry.set(
+                constants::DW_AT_name,
+                AttributeValue::String(b"long".to_vec()),
+            );
+            entry.set(constants::DW_AT_byte_size, AttributeValue::Udata(8));
+            let variable = unit.add(root, constants::DW_TAG_variable);
+            let mut expression = Expression::new();
+            expression.op_convert(Some(long));
+            unit.get_mut(variable).set(
+                constants::DW_AT_location,
+                AttributeValue::Exprloc(expression),
+            );
+            if !structure {
+                return (unit_id, None);
+            }
+            let structure = unit.add(root, constants::DW_TAG_structure_type);
+            let entry = unit.get_mut(structure);
+            entry.set(constants::DW_AT_name, AttributeValue::String(b"S".to_vec()));
+            entry.set(
+                constants::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(header)),
+            );
+            let member = unit.add(structure, constants::DW_TAG_member);
+            unit.get_mut(member)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(int));
+            let pointer = unit.add(root, constants::DW_TAG_pointer_type);
+            unit.get_mut(pointer)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(structure));
+            unit.get_mut(variable)
+                .set(constants::DW_AT_type, AttributeValue::UnitRef(pointer));
+            (unit_id, Some(variable))
+        };
+        let (unit_id1, variable1) = add_unit(&mut dwarf, true);
+        let (unit_id2, variable2) = add_unit(&mut dwarf, true);
+        let (unit_id3, _) = add_unit(&mut dwarf, false);
+
+        let partial_ids = dwarf.units.create_partial_units();
+        assert_eq!(partial_ids.len(), 2);
+
+        // `int` is shared by all units.
+        let partial1 = dwarf.units.get(partial_ids[0]);
+        let partial_root1 = partial1.root();
+        assert_eq!(
+            partial1.get(partial_root1).tag(),
+            constants::DW_TAG_partial_unit
+        );
+        assert_eq!(
+            partial1.get(partial_root1).get(constants::DW_AT_language),
+            Some(&AttributeValue::Language(constants::DW_LANG_C11))
+        );
+        let partial_children1: Vec<_> = partial1.get(partial_root1).children().copied().collect();
+        assert_eq!(partial_children1.len(), 1);
+        let int = partial_children1[0];
+        assert_eq!(partial1.get(int).tag(), constants::DW_TAG_base_type);
+
+        // `S` and `S *` are shared by the first two units.
+        let partial2 = dwarf.units.get(partial_ids[1]);
+        let partial_root2 = partial2.root();
+        let partial_children2: Vec<_> = partial2.get(partial_root2).children().copied().collect();
+        assert_eq!(partial_children2.len(), 2);
+        let structure = partial_children2[0];
+        let pointer = partial_children2[1];
+        assert_eq!(
+            partial2.get(structure).tag(),
+            constants::DW_TAG_structure_type
+        );
+        let member = *partial2.get(structure).children().next().unwrap();
+        assert_eq!(
+            partial2.get(member).get(constants::DW_AT_type),
+            Some(&AttributeValue::DebugInfoRef(Reference::Entry(
+                partial_ids[0],
+                int
+            )))
+        );
+        match partial2.get(structure).get(constants::DW_AT_decl_file) {
+            Some(AttributeValue::FileIndex(Some(file))) => {
+                let (path, directory) = partial2.line_program.get_file(*file);
+                assert_eq!(path, &LineString::String(b"s.h".to_vec()));
+                assert_eq!(
+                    partial2.line_program.get_directory(directory),
+                    &LineString::String(b"/include".to_vec())
+                );
+            }
+            otherwise => panic!("unexpected {:?}", otherwise),
+        }
+        assert_eq!(
+            partial2.get(pointer).get(constants::DW_AT_type),
+            Some(&AttributeValue::UnitRef(structure))
+        );
+
+        for (unit_id, variable, imports) in [
+            (unit_id1, variable1, &partial_ids[..]),
+            (unit_id2, variable2, &partial_ids[..]),
+            (unit_id3, None, &partial_ids[..1]),
+        ] {
+            let unit = dwarf.units.get(unit_id);
+            let children: Vec<_> = unit.get(unit.root()).children().copied().collect();
+            // The imports, `long`, and the variable.
+            assert_eq!(children.len(), imports.len() + 2);
+            for (child, partial_id) in children.iter().zip(imports) {
+                let import = unit.get(*child);
+                assert_eq!(import.tag(), constants::DW_TAG_imported_unit);
+                let partial_root = dwarf.units.get(*partial_id).root();
+                assert_eq!(
+                    import.get(constants::DW_AT_import),
+                    Some(&AttributeValue::DebugInfoRef(Reference::Entry(
+                        *partial_id,
+                        partial_root
+                    )))
+                );
+            }
+            let long = unit.get(children[imports.len()]);
+            assert_eq!(long.tag(), constants::DW_TAG_base_type);
+            if let Some(variable) = variable {
+                assert_eq!(
+                    unit.get(variable).get(constants::DW_AT_type),
+                    Some(&AttributeValue::DebugInfoRef(Reference::Entry(
+                        partial_ids[1],
+                        pointer
+                    )))
+                );
+            }
+        }
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_debug_info = read::DebugInfo::new(sections.debug_info.slice(), LittleEndian);
+        let mut read_units = read_debug_info.units();
+        let mut unit_types = Vec::new();
+        while let Some(header) = read_units.next().unwrap() {
+            unit_types.push(header.type_());
+        }
+        assert_eq!(
+            unit_types,
+            [
+                read::UnitType::Compilation,
+                read::UnitType::Compilation,
+                read::UnitType::Compilation,
+                read::UnitType::Partial,
+                read::UnitType::Partial,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_partial_units_relative_file() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        };
+        // Add a unit containing `S`, declared in `include/s.h`.
+        let add_unit = |dwarf: &mut Dwarf, comp_dir: &[u8], include: &[u8]| {
+            let dir = LineString::String(comp_dir.to_vec());
+            let file = LineString::String(b"file.c".to_vec());
+            let mut program = LineProgram::new(encoding, LineEncoding::default(), dir, file, None);
+            let include = program.add_directory(LineString::String(include.to_vec()));
+            let header = program.add_file(LineString::String(b"s.h".to_vec()), include, None);
+            let unit_id = dwarf.units.add(Unit::new(encoding, program));
+            let unit = dwarf.units.get_mut(unit_id);
+            let root = unit.root();
+            let structure = unit.add(root, constants::DW_TAG_structure_type);
+            let entry = unit.get_mut(structure);
+            entry.set(constants::DW_AT_name, AttributeValue::String(b"S".to_vec()));
+            entry.set(
+                constants::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(header)),
+            );
+        };
+
+        // Relative directories are only the same if the compilation
+        // directories are the same.
+        for (comp_dir1, comp_dir2, include, shared) in [
+            (&b"/a"[..], &b"/a"[..], &b"include"[..], true),
+            (b"/a", b"/b", b"include", false),
+            (b"/a", b"/b", b"/include", true),
+            (b"C:\\a", b"C:\\b", b"D:\\include", true),
+        ] {
+            let mut dwarf = Dwarf::new();
+            add_unit(&mut dwarf, comp_dir1, include);
+            add_unit(&mut dwarf, comp_dir2, include);
+            let partial_ids = dwarf.units.create_partial_units();
+            assert_eq!(partial_ids.len(), usize::from(shared));
+        }
+    }
+
+    #[test]
+    fn test_implicit_const() {
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        });
+        let root = dwarf.unit.root();
+        for &val in &[-5, -5, 7] {
+            let id = dwarf.unit.add(root, constants::DW_TAG_variable);
+            dwarf.unit.get_mut(id).set(
+                constants::DW_AT_decl_line,
+                AttributeValue::ImplicitConst(val),
+            );
+        }
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let mut read_entries = Vec::new();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            let attr = entry.attr(constants::DW_AT_decl_line).unwrap().unwrap();
+            let abbrev = read_unit.abbreviations.get(entry.code()).unwrap();
+            let spec = abbrev.attributes()[0];
+            assert_eq!(spec.form(), constants::DW_FORM_implicit_const);
+            assert_eq!(spec.implicit_const_value(), attr.value().sdata_value());
+            read_entries.push((entry.code(), attr.value()));
+        }
+        assert_eq!(read_entries.len(), 3);
+        assert_eq!(read_entries[0], read_entries[1]);
+        assert_eq!(read_entries[1].1, read::AttributeValue::Sdata(-5));
+        assert_ne!(read_entries[1].0, read_entries[2].0);
+        assert_eq!(read_entries[2].1, read::AttributeValue::Udata(7));
+
+        // Changing to version 4 uses `DW_FORM_sdata` instead.
+        dwarf
+            .unit
+            .set_version(4, &dwarf.line_strings, &mut dwarf.strings)
+            .unwrap();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let mut read_values = Vec::new();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            let attr = entry.attr(constants::DW_AT_decl_line).unwrap().unwrap();
+            assert_eq!(attr.form(), constants::DW_FORM_sdata);
+            read_values.push(attr.value().sdata_value());
+        }
+        assert_eq!(read_values, [Some(-5), Some(-5), Some(7)]);
+    }
+
+    #[test]
+    fn test_select_string_forms() {
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        });
+        let root = dwarf.unit.root();
+        let producer = dwarf.strings.add("producer");
+        let entry = dwarf.unit.get_mut(root);
+        entry.set(
+            constants::DW_AT_name,
+            AttributeValue::String(b"main.c".to_vec()),
+        );
+        entry.set(
+            constants::DW_AT_producer,
+            AttributeValue::StringRef(producer),
+        );
+        dwarf
+            .unit
+            .select_string_forms(&mut dwarf.strings, |name, _| name == constants::DW_AT_name);
+        let entry = dwarf.unit.get(root);
+        let name = match entry.get(constants::DW_AT_name) {
+            Some(AttributeValue::StringRef(id)) => *id,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        assert_eq!(dwarf.strings.get(name), b"main.c");
+        assert_eq!(
+            entry.get(constants::DW_AT_producer),
+            Some(&AttributeValue::String(b"producer".to_vec()))
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        let (_, read_root) = entries.next_dfs().unwrap().unwrap();
+        let name = read_root.attr(constants::DW_AT_name).unwrap().unwrap();
+        assert_eq!(name.form(), constants::DW_FORM_strp);
+        let producer = read_root.attr(constants::DW_AT_producer).unwrap().unwrap();
+        assert_eq!(producer.form(), constants::DW_FORM_string);
+    }
+
+    #[test]
+    fn test_form_selector() {
+        fn select(
+            name: constants::DwAt,
+            _value: &AttributeValue,
+            _default: constants::DwForm,
+        ) -> Option<constants::DwForm> {
+            match name {
+                constants::DW_AT_byte_size => Some(constants::DW_FORM_data4),
+                constants::DW_AT_decl_line => Some(constants::DW_FORM_udata),
+                constants::DW_AT_type => Some(constants::DW_FORM_ref_udata),
+                constants::DW_AT_specification => Some(constants::DW_FORM_ref1),
+                _ => None,
+            }
+        }
+
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        });
+        dwarf.unit.form_selector = Some(select);
+        let root = dwarf.unit.root();
+        let decl = dwarf.unit.add(root, constants::DW_TAG_variable);
+        let var = dwarf.unit.add(root, constants::DW_TAG_variable);
+        // Make the forward reference to the type need more than one byte.
+        dwarf.unit.get_mut(var).set(
+            constants::DW_AT_name,
+            AttributeValue::String(vec![b'a'; 200]),
+        );
+        dwarf
+            .unit
+            .get_mut(var)
+            .set(constants::DW_AT_decl_line, AttributeValue::Data1(200));
+        dwarf.unit.get_mut(var).set(
+            constants::DW_AT_specification,
+            AttributeValue::UnitRef(decl),
+        );
+        let ty = dwarf.unit.add(root, constants::DW_TAG_structure_type);
+        dwarf
+            .unit
+            .get_mut(ty)
+            .set(constants::DW_AT_byte_size, AttributeValue::Udata(4));
+        dwarf
+            .unit
+            .get_mut(var)
+            .set(constants::DW_AT_type, AttributeValue::UnitRef(ty));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let (_, read_decl) = entries.next_dfs().unwrap().unwrap();
+        let decl_offset = read_decl.offset();
+        let (_, read_var) = entries.next_dfs().unwrap().unwrap();
+        let mut var_attrs = Vec::new();
+        let mut attrs = read_var.attrs();
+        while let Some(attr) = attrs.next().unwrap() {
+            var_attrs.push(attr);
+        }
+        let (_, read_ty) = entries.next_dfs().unwrap().unwrap();
+        let ty_offset = read_ty.offset();
+        assert!(ty_offset.0 > 0x80);
+
+        let forms = var_attrs
+            .iter()
+            .map(|attr| (attr.name(), attr.form()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            forms,
+            [
+                (constants::DW_AT_name, constants::DW_FORM_string),
+                (constants::DW_AT_decl_line, constants::DW_FORM_udata),
+                (constants::DW_AT_specification, constants::DW_FORM_ref1),
+                (constants::DW_AT_type, constants::DW_FORM_ref_udata),
+            ]
+        );
+        assert_eq!(var_attrs[1].raw_value(), read::AttributeValue::Udata(200));
+        assert_eq!(
+            var_attrs[2].raw_value(),
+            read::AttributeValue::UnitRef(decl_offset)
+        );
+        assert_eq!(
+            var_attrs[3].raw_value(),
+            read::AttributeValue::UnitRef(ty_offset)
+        );
+        let attr = read_ty.attr(constants::DW_AT_byte_size).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_data4);
+        assert_eq!(attr.raw_value(), read::AttributeValue::Data4(4));
+
+        dwarf.unit.form_selector = Some(|_, value, _| match *value {
+            AttributeValue::Udata(_) => Some(constants::DW_FORM_data2),
+            _ => None,
+        });
+        dwarf
+            .unit
+            .get_mut(ty)
+            .set(constants::DW_AT_byte_size, AttributeValue::Udata(0x1_0000));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        assert_eq!(dwarf.write(&mut sections), Err(Error::ValueTooLarge));
+
+        dwarf.unit.form_selector = Some(|name, _, _| {
+            if name == constants::DW_AT_name {
+                Some(constants::DW_FORM_data4)
+            } else {
+                None
+            }
+        });
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        assert_eq!(
+            dwarf.write(&mut sections),
+            Err(Error::InvalidAttributeValue)
+        );
+    }
+
+    #[test]
+    fn test_form_selector_index_forms() {
+        fn select(
+            _name: constants::DwAt,
+            value: &AttributeValue,
+            _default: constants::DwForm,
+        ) -> Option<constants::DwForm> {
+            match *value {
+                AttributeValue::StringRef(_) => Some(constants::DW_FORM_strx1),
+                AttributeValue::Address(_) => Some(constants::DW_FORM_addrx),
+                AttributeValue::RangeListRef(_) => Some(constants::DW_FORM_rnglistx),
+                AttributeValue::LocationListRef(_) => Some(constants::DW_FORM_loclistx),
+                _ => None,
+            }
+        }
+
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        });
+        dwarf.unit.form_selector = Some(select);
+        let root = dwarf.unit.root();
+        let name = dwarf.strings.add("root");
+        dwarf
+            .unit
+            .get_mut(root)
+            .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        dwarf.unit.get_mut(root).set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0x1000)),
+        );
+        // Add an unused list first so that the index is nonzero.
+        dwarf.unit.ranges.add(RangeList(vec![Range::StartLength {
+            begin: Address::Constant(0x3000),
+            length: 0x10,
+        }]));
+        let range_list = dwarf.unit.ranges.add(RangeList(vec![Range::StartLength {
+            begin: Address::Constant(0x1000),
+            length: 0x100,
+        }]));
+        dwarf.unit.get_mut(root).set(
+            constants::DW_AT_ranges,
+            AttributeValue::RangeListRef(range_list),
+        );
+        let var = dwarf.unit.add(root, constants::DW_TAG_variable);
+        let var_name = dwarf.strings.add("var");
+        dwarf
+            .unit
+            .get_mut(var)
+            .set(constants::DW_AT_name, AttributeValue::StringRef(var_name));
+        let mut expression = Expression::new();
+        expression.op(constants::DW_OP_reg0);
+        let loc_list = dwarf
+            .unit
+            .locations
+            .add(LocationList(vec![Location::StartLength {
+                begin: Address::Constant(0x1010),
+                length: 0x20,
+                data: expression,
+            }]));
+        dwarf.unit.get_mut(var).set(
+            constants::DW_AT_location,
+            AttributeValue::LocationListRef(loc_list),
+        );
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        assert_ne!(read_unit.str_offsets_base, DebugStrOffsetsBase(0));
+        assert_ne!(read_unit.addr_base, DebugAddrBase(0));
+        assert_ne!(read_unit.rnglists_base, DebugRngListsBase(0));
+        assert_ne!(read_unit.loclists_base, DebugLocListsBase(0));
+
+        let mut entries = read_unit.entries();
+        let (_, read_root) = entries.next_dfs().unwrap().unwrap();
+        let attr = read_root.attr(constants::DW_AT_name).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_strx1);
+        assert_eq!(
+            attr.raw_value(),
+            read::AttributeValue::DebugStrOffsetsIndex(DebugStrOffsetsIndex(0))
+        );
+        assert_eq!(
+            read_dwarf
+                .attr_string(&read_unit, attr.value())
+                .unwrap()
+                .slice(),
+            b"root"
+        );
+        let attr = read_root.attr(constants::DW_AT_low_pc).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_addrx);
+        assert_eq!(
+            attr.raw_value(),
+            read::AttributeValue::DebugAddrIndex(DebugAddrIndex(0))
+        );
+        assert_eq!(
+            read_dwarf.attr_address(&read_unit, attr.value()).unwrap(),
+            Some(0x1000)
+        );
+        let attr = read_root.attr(constants::DW_AT_ranges).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_rnglistx);
+        assert_eq!(
+            attr.raw_value(),
+            read::AttributeValue::DebugRngListsIndex(DebugRngListsIndex(1))
+        );
+        let mut ranges = read_dwarf
+            .attr_ranges(&read_unit, attr.value())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ranges.next().unwrap(),
+            Some(read::Range {
+                begin: 0x1000,
+                end: 0x1100,
+            })
+        );
+        assert_eq!(ranges.next().unwrap(), None);
+
+        let (_, read_var) = entries.next_dfs().unwrap().unwrap();
+        let attr = read_var.attr(constants::DW_AT_name).unwrap().unwrap();
+        assert_eq!(
+            attr.raw_value(),
+            read::AttributeValue::DebugStrOffsetsIndex(DebugStrOffsetsIndex(1))
+        );
+        assert_eq!(
+            read_dwarf
+                .attr_string(&read_unit, attr.value())
+                .unwrap()
+                .slice(),
+            b"var"
+        );
+        let attr = read_var.attr(constants::DW_AT_location).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_loclistx);
+        assert_eq!(
+            attr.raw_value(),
+            read::AttributeValue::DebugLocListsIndex(DebugLocListsIndex(0))
+        );
+        let mut locations = read_dwarf
+            .attr_locations(&read_unit, attr.value())
+            .unwrap()
+            .unwrap();
+        let location = locations.next().unwrap().unwrap();
+        assert_eq!(
+            location.range,
+            read::Range {
+                begin: 0x1010,
+                end: 0x1030,
+            }
+        );
+        assert_eq!(location.data.0.slice(), &[constants::DW_OP_reg0.0][..]);
+        assert_eq!(locations.next().unwrap(), None);
+
+        // Index forms require version 5.
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        });
+        dwarf.unit.form_selector = Some(select);
+        let root = dwarf.unit.root();
+        let name = dwarf.strings.add("root");
+        dwarf
+            .unit
+            .get_mut(root)
+            .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        assert_eq!(dwarf.write(&mut sections), Err(Error::NeedVersion(5)));
+    }
+
+    #[test]
+    fn test_data16() {
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        });
+        let data = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+            0x0f, 0x10,
+        ];
+        let root = dwarf.unit.root();
+        let id = dwarf.unit.add(root, constants::DW_TAG_variable);
+        dwarf
+            .unit
+            .get_mut(id)
+            .set(constants::DW_AT_const_value, AttributeValue::Data16(data));
+        let mut sections = Sections::new(EndianVec::new(BigEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(
+            entry.attr_value(constants::DW_AT_const_value).unwrap(),
+            Some(read::AttributeValue::Data16(data))
+        );
+
+        let convert_dwarf =
+            Dwarf::from(&read_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        let convert_unit = convert_dwarf.units.get(convert_dwarf.units.id(0));
+        let convert_root = convert_unit.get(convert_unit.root());
+        let convert_entry = convert_unit.get(*convert_root.children().next().unwrap());
+        assert_eq!(
+            convert_entry.get(constants::DW_AT_const_value),
+            Some(&AttributeValue::Data16(data))
+        );
+
+        // Changing to version 4 uses a block for `DW_AT_const_value`.
+        dwarf
+            .unit
+            .set_version(4, &dwarf.line_strings, &mut dwarf.strings)
+            .unwrap();
+        let mut sections = Sections::new(EndianVec::new(BigEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let attr = entry.attr(constants::DW_AT_const_value).unwrap().unwrap();
+        assert_eq!(attr.form(), constants::DW_FORM_block);
+        assert_eq!(
+            attr.value(),
+            read::AttributeValue::Block(read::EndianSlice::new(&data, BigEndian))
+        );
+
+        // Other attributes can't be changed to version 4.
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 5,
+            address_size: 8,
+        });
+        let root = dwarf.unit.root();
+        let id = dwarf.unit.add(root, constants::DW_TAG_variable);
+        dwarf
+            .unit
+            .get_mut(id)
+            .set(constants::DW_AT_byte_size, AttributeValue::Data16(data));
+        assert_eq!(
+            dwarf
+                .unit
+                .set_version(4, &dwarf.line_strings, &mut dwarf.strings),
+            Err(Error::NeedVersion(5))
+        );
+        assert_eq!(dwarf.unit.version(), 5);
+    }
+
+    #[test]
+    fn test_unknown_form() {
+        let mut dwarf = DwarfUnit::new(Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        });
+        let unknown_form = constants::DwForm(0x1f80);
+        let root = dwarf.unit.root();
+        let id = dwarf.unit.add(root, constants::DW_TAG_variable);
+        let entry = dwarf.unit.get_mut(id);
+        entry.set(
+            constants::DW_AT_name,
+            AttributeValue::Unknown(unknown_form, vec![1, 2, 3]),
+        );
+        entry.set(constants::DW_AT_decl_line, AttributeValue::Udata(10));
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut read_dwarf = sections.read_dwarf();
+        let read_header = read_dwarf.units().next().unwrap().unwrap();
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(
+            entry.attr(constants::DW_AT_name),
+            Err(read::Error::UnknownForm)
+        );
+
+        read_dwarf
+            .debug_abbrev
+            .set_unknown_form_size(|form| match form {
+                constants::DwForm(0x1f80) => Some(3),
+                _ => None,
+            });
+        let read_unit = read_dwarf.unit(read_header).unwrap();
+        let mut entries = read_unit.entries();
+        entries.next_dfs().unwrap().unwrap();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        let attr = entry.attr(constants::DW_AT_name).unwrap().unwrap();
+        assert_eq!(attr.form(), unknown_form);
+        assert_eq!(
+            attr.value(),
+            read::AttributeValue::Unknown(read::EndianSlice::new(&[1, 2, 3], LittleEndian))
+        );
+        assert_eq!(
+            entry.attr_value(constants::DW_AT_decl_line).unwrap(),
+            Some(read::AttributeValue::Udata(10))
+        );
+
+        let convert_dwarf =
+            Dwarf::from(&read_dwarf, &|address| Some(Address::Constant(address))).unwrap();
+        let convert_unit = convert_dwarf.units.get(convert_dwarf.units.id(0));
+        let convert_root = convert_unit.get(convert_unit.root());
+        let convert_entry = convert_unit.get(*convert_root.children().next().unwrap());
+        assert_eq!(
+            convert_entry.get(constants::DW_AT_name),
+            Some(&AttributeValue::Unknown(unknown_form, vec![1, 2, 3]))
+        );
+    }
+
+    #[test]
+    fn test_share_abbreviations() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = UnitTable::default();
+        for name in &["a", "b", "c"] {
+            let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = units.get_mut(unit_id);
+            let root = unit.root();
+            unit.get_mut(root).set(
+                constants::DW_AT_name,
+                AttributeValue::String(name.as_bytes().to_vec()),
+            );
+            let child = unit.add(root, constants::DW_TAG_base_type);
+            unit.get_mut(child)
+                .set(constants::DW_AT_byte_size, AttributeValue::Data1(4));
+        }
+        // A unit with a different abbreviation table.
+        let unit_id = units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = units.get_mut(unit_id);
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_language,
+            AttributeValue::Language(constants::DW_LANG_C),
+        );
+
+        let debug_line_str_offsets = DebugLineStrOffsets::none();
+        let debug_str_offsets = DebugStrOffsets::none();
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        units
+            .write(&mut sections, &debug_line_str_offsets, &debug_str_offsets)
+            .unwrap();
+        let unshared_len = sections.debug_abbrev.len();
+
+        units.set_share_abbreviations(true);
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        units
+            .write(&mut sections, &debug_line_str_offsets, &debug_str_offsets)
+            .unwrap();
+        assert!(sections.debug_abbrev.len() < unshared_len);
+
+        let read_dwarf = sections.read_dwarf();
+        let mut abbrev_offsets = Vec::new();
+        let mut read_units = read_dwarf.units();
+        while let Some(read_header) = read_units.next().unwrap() {
+            abbrev_offsets.push(read_header.debug_abbrev_offset());
+            let read_unit = read_dwarf.unit(read_header).unwrap();
+            let mut entries = read_unit.entries();
+            let (_, root) = entries.next_dfs().unwrap().unwrap();
+            if abbrev_offsets.len() <= 3 {
+                assert!(root.attr(constants::DW_AT_name).unwrap().is_some());
+                let (_, child) = entries.next_dfs().unwrap().unwrap();
+                assert_eq!(child.tag(), constants::DW_TAG_base_type);
+                assert_eq!(
+                    child.attr_value(constants::DW_AT_byte_size).unwrap(),
+                    Some(read::AttributeValue::Udata(4))
+                );
+            } else {
+                assert_eq!(
+                    root.attr_value(constants::DW_AT_language).unwrap(),
+                    Some(read::AttributeValue::Language(constants::DW_LANG_C))
+                );
+            }
+        }
+        assert_eq!(abbrev_offsets.len(), 4);
+        assert_eq!(abbrev_offsets[0], abbrev_offsets[1]);
+        assert_eq!(abbrev_offsets[0], abbrev_offsets[2]);
+        assert_ne!(abbrev_offsets[0], abbrev_offsets[3]);
+    }
+
+    #[test]
+    fn test_delete_child() {
+        fn set_name(unit: &mut Unit, id: UnitEntryId, name: &str) {
+            let entry = unit.get_mut(id);
+            entry.set(constants::DW_AT_name, AttributeValue::String(name.into()));
+        }
+        fn check_name<R: read::Reader>(
+            entry: &read::DebuggingInformationEntry<'_, '_, R>,
+            debug_str: &read::DebugStr<R>,
+            name: &str,
+        ) {
+            let name_attr = entry.attr(constants::DW_AT_name).unwrap().unwrap();
+            let entry_name = name_attr.string_value(debug_str).unwrap();
+            let entry_name_str = entry_name.to_string().unwrap();
+            assert_eq!(entry_name_str, name);
+        }
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let root = dwarf.unit.root();
+
+        // Add and delete entries in the root unit
+        let child1 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
+        set_name(&mut dwarf.unit, child1, "child1");
+        let grandchild1 = dwarf.unit.add(child1, constants::DW_TAG_variable);
+        set_name(&mut dwarf.unit, grandchild1, "grandchild1");
+        let child2 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
+        set_name(&mut dwarf.unit, child2, "child2");
+        // This deletes both `child1` and its child `grandchild1`
+        dwarf.unit.get_mut(root).delete_child(child1);
+        let child3 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
+        set_name(&mut dwarf.unit, child3, "child3");
+        let child4 = dwarf.unit.add(root, constants::DW_TAG_subprogram);
+        set_name(&mut dwarf.unit, child4, "child4");
+        let grandchild4 = dwarf.unit.add(child4, constants::DW_TAG_variable);
+        set_name(&mut dwarf.unit, grandchild4, "grandchild4");
+        dwarf.unit.get_mut(child4).delete_child(grandchild4);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+
+        // Write DWARF data which should only include `child2`, `child3` and `child4`
+        dwarf.write(&mut sections).unwrap();
+
+        let read_debug_info = read::DebugInfo::new(sections.debug_info.slice(), LittleEndian);
+        let read_debug_abbrev = read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian);
+        let read_debug_str = read::DebugStr::new(sections.debug_str.slice(), LittleEndian);
+        let read_unit = read_debug_info.units().next().unwrap().unwrap();
+        let abbrevs = read_unit.abbreviations(&read_debug_abbrev).unwrap();
+        let mut entries = read_unit.entries(&abbrevs);
+        // root
+        entries.next_dfs().unwrap().unwrap();
+        // child2
+        let (_, read_child2) = entries.next_dfs().unwrap().unwrap();
+        check_name(read_child2, &read_debug_str, "child2");
+        // child3
+        let (_, read_child3) = entries.next_dfs().unwrap().unwrap();
+        check_name(read_child3, &read_debug_str, "child3");
+        // child4
+        let (_, read_child4) = entries.next_dfs().unwrap().unwrap();
+        check_name(read_child4, &read_debug_str, "child4");
+        // There should be no more entries
+        assert!(entries.next_dfs().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_parent() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut unit = Unit::new(encoding, LineProgram::none());
+        let root = unit.root();
+        let child1 = unit.add(root, constants::DW_TAG_subprogram);
+        let grandchild1 = unit.add(child1, constants::DW_TAG_variable);
+        let grandchild2 = unit.add(child1, constants::DW_TAG_variable);
+        let child2 = unit.add(root, constants::DW_TAG_subprogram);
+
+        unit.set_parent(grandchild1, child2);
+        assert_eq!(unit.get(grandchild1).parent(), Some(child2));
+        assert_eq!(
+            unit.get(child1).children().copied().collect::<Vec<_>>(),
+            [grandchild2]
+        );
+        assert_eq!(
+            unit.get(child2).children().copied().collect::<Vec<_>>(),
+            [grandchild1]
+        );
+
+        // Move a subtree, and reattach a deleted child.
+        unit.set_parent(child1, child2);
+        unit.get_mut(child2).delete_child(grandchild1);
+        unit.set_parent(grandchild1, root);
+        assert_eq!(
+            unit.get(root).children().copied().collect::<Vec<_>>(),
+            [child2, grandchild1]
+        );
+        assert_eq!(
+            unit.get(child2).children().copied().collect::<Vec<_>>(),
+            [child1]
+        );
+        assert_eq!(unit.get(child1).parent(), Some(child2));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot move an entry into itself")]
+    fn test_set_parent_cycle() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut unit = Unit::new(encoding, LineProgram::none());
+        let root = unit.root();
+        let child = unit.add(root, constants::DW_TAG_subprogram);
+        let grandchild = unit.add(child, constants::DW_TAG_lexical_block);
+        unit.set_parent(child, grandchild);
+    }
+
+    #[test]
+    fn test_exprloc_references() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut units = UnitTable::default();
+        let unit_id1 = units.add(Unit::new(encoding, LineProgram::none()));
+        let unit_id2 = units.add(Unit::new(encoding, LineProgram::none()));
+
+        let unit2 = units.get_mut(unit_id2);
+        let root2 = unit2.root();
+        let target2 = unit2.add(root2, constants::DW_TAG_variable);
+
+        let unit1 = units.get_mut(unit_id1);
+        let root1 = unit1.root();
+        let target1 = unit1.add(root1, constants::DW_TAG_dwarf_procedure);
+        let variable = unit1.add(root1, constants::DW_TAG_variable);
+        let mut expression = Expression::new();
+        expression.op_call(target1);
+        expression.op_implicit_pointer(Reference::Entry(unit_id2, target2), 4);
+        unit1.get_mut(variable).set(
+            constants::DW_AT_location,
+            AttributeValue::Exprloc(expression),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        let offsets = units
+            .write(
+                &mut sections,
+                &DebugLineStrOffsets::none(),
+                &DebugStrOffsets::none(),
+            )
+            .unwrap();
+
+        let read_debug_info = read::DebugInfo::new(sections.debug_info.slice(), LittleEndian);
+        let read_debug_abbrev = read::DebugAbbrev::new(sections.debug_abbrev.slice(), LittleEndian);
+        let read_unit = read_debug_info.units().next().unwrap().unwrap();
+        let abbrevs = read_unit.abbreviations(&read_debug_abbrev).unwrap();
+        let unit_offset = offsets.unit(unit_id1);
+        let variable_offset = offsets.entry(unit_id1, variable);
+        let read_entry = read_unit
+            .entry(
+                &abbrevs,
+                variable_offset.to_unit_offset(&read_unit).unwrap(),
+            )
+            .unwrap();
+        let read_expression = match read_entry.attr_value(constants::DW_AT_location).unwrap() {
+            Some(read::AttributeValue::Exprloc(expression)) => expression,
+            otherwise => panic!("unexpected {:?}", otherwise),
+        };
+        let mut read_operations = read_expression.operations(encoding);
+        assert_eq!(
+            read_operations.next().unwrap(),
+            Some(read::Operation::Call {
+                offset: read::DieReference::UnitRef(read::UnitOffset(
+                    offsets.entry(unit_id1, target1).0 - unit_offset.0
+                )),
+            })
+        );
+        assert_eq!(
+            read_operations.next().unwrap(),
+            Some(read::Operation::ImplicitPointer {
+                value: offsets.entry(unit_id2, target2),
+                byte_offset: 4,
+            })
+        );
+        assert_eq!(read_operations.next().unwrap(), None);
+    }
+
+    #[test]
+    fn test_allocated_bytes() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        assert_eq!(dwarf.allocated_bytes(), 0);
+
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let empty = dwarf.units.allocated_bytes();
+        assert!(empty >= mem::size_of::<Unit>());
+        assert_eq!(dwarf.allocated_bytes(), empty);
+
+        let unit = dwarf.units.get_mut(unit_id);
+        let unit_empty = unit.allocated_bytes();
+        let root = unit.root();
+        unit.get_mut(root).set(
+            constants::DW_AT_name,
+            AttributeValue::String(vec![b'a'; 1000]),
+        );
+        let mut expression = Expression::new();
+        expression.op_constu(1);
+        let list = unit
+            .locations
+            .add(LocationList(vec![Location::DefaultLocation {
+                data: expression,
+            }]));
+        let child = unit.add(root, constants::DW_TAG_variable);
+        unit.get_mut(child).set(
+            constants::DW_AT_location,
+            AttributeValue::LocationListRef(list),
+        );
+        assert!(unit.allocated_bytes() >= unit_empty + 1000);
+        assert_eq!(
+            dwarf.units.allocated_bytes() - empty,
+            dwarf.units.get(unit_id).allocated_bytes() - unit_empty
+        );
+
+        let units = dwarf.units.allocated_bytes();
+        dwarf.strings.add("string");
+        assert!(dwarf.allocated_bytes() >= units + 6);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_write_parallel() {
+        use crate::write::{RelocateWriter, Relocation};
+
+        #[derive(Clone)]
+        struct Section {
+            writer: EndianVec<LittleEndian>,
+            relocations: Vec<Relocation>,
+        }
+
+        impl RelocateWriter for Section {
+            type Writer = EndianVec<LittleEndian>;
+
+            fn writer(&self) -> &Self::Writer {
+                &self.writer
+            }
+
+            fn writer_mut(&mut self) -> &mut Self::Writer {
+                &mut self.writer
+            }
+
+            fn relocate(&mut self, relocation: Relocation) {
+                self.relocations.push(relocation);
+            }
+        }
+
+        fn dwarf() -> Dwarf {
+            let mut dwarf = Dwarf::new();
+            let mut entries = Vec::new();
+            for (index, version) in [4, 5, 5, 2].iter().enumerate() {
+                let encoding = Encoding {
+                    format: Format::Dwarf32,
+                    version: *version,
+                    address_size: 8,
+                };
+                let dir = LineString::new("dir", encoding, &mut dwarf.line_strings);
+                let file = LineString::new(
+                    format!("file{}.c", index),
+                    encoding,
+                    &mut dwarf.line_strings,
+                );
+                let line_program =
+                    LineProgram::new(encoding, LineEncoding::default(), dir, file, None);
+                let unit_id = dwarf.units.add(Unit::new(encoding, line_program));
+                let name = dwarf.strings.add(format!("unit{}", index));
+                let unit = dwarf.units.get_mut(unit_id);
+                let root = unit.root();
+                unit.get_mut(root)
+                    .set(constants::DW_AT_name, AttributeValue::StringRef(name));
+                unit.get_mut(root).set(
+                    constants::DW_AT_low_pc,
+                    AttributeValue::Address(Address::Symbol {
+                        symbol: index,
+                        addend: 0,
+                    }),
+                );
+                let range_list = unit.ranges.add(RangeList(vec![Range::StartLength {
+                    begin: Address::Symbol {
+                        symbol: index,
+                        addend: 0x10,
+                    },
+                    length: 0x20,
+                }]));
+                let subprogram = unit.add(root, constants::DW_TAG_subprogram);
+                unit.get_mut(subprogram).set(
+                    constants::DW_AT_ranges,
+                    AttributeValue::RangeListRef(range_list),
+                );
+                let directory = unit.line_program.default_directory();
+                let file = LineString::new("a.c", encoding, &mut dwarf.line_strings);
+                let file = unit.line_program.add_file(file, directory, None);
+                unit.get_mut(subprogram).set(
+                    constants::DW_AT_decl_file,
+                    AttributeValue::FileIndex(Some(file)),
+                );
+                let variable = unit.add(subprogram, constants::DW_TAG_variable);
+                entries.push((unit_id, variable));
+                if index % 2 == 0 {
+                    // Add enough entries to make the units differ in size.
+                    for _ in 0..index * 3 {
+                        let child = unit.add(root, constants::DW_TAG_base_type);
+                        unit.get_mut(child)
+                            .set(constants::DW_AT_byte_size, AttributeValue::Data1(4));
+                    }
+                }
+            }
+            // References between units, both in entries and in location lists.
+            for i in 0..entries.len() {
+                let (unit_id, variable) = entries[i];
+                let (other_unit_id, other_variable) = entries[(i + 1) % entries.len()];
+                let mut expression = Expression::new();
+                expression.op_call_ref(Reference::Entry(other_unit_id, other_variable));
+                let unit = dwarf.units.get_mut(unit_id);
+                let location_list = unit.locations.add(LocationList(vec![Location::StartEnd {
+                    begin: Address::Constant(0x1000),
+                    end: Address::Constant(0x1010),
+                    data: expression,
+                }]));
+                let entry = unit.get_mut(variable);
+                entry.set(
+                    constants::DW_AT_location,
+                    AttributeValue::LocationListRef(location_list),
+                );
+                entry.set(
+                    constants::DW_AT_specification,
+                    AttributeValue::DebugInfoRef(Reference::Entry(other_unit_id, other_variable)),
+                );
+            }
+            dwarf
+        }
+
+        fn write(share: bool, parallel: bool) -> Sections<Section> {
+            let mut dwarf = dwarf();
+            dwarf.units.set_share_abbreviations(share);
+            let mut sections = Sections::new(Section {
+                writer: EndianVec::new(LittleEndian),
+                relocations: Vec::new(),
+            });
+            if parallel {
+                dwarf.write_parallel(&mut sections).unwrap();
+            } else {
+                dwarf.write(&mut sections).unwrap();
+            }
+            sections
+        }
+
+        for share in [false, true] {
+            let sequential = write(share, false);
+            let parallel = write(share, true);
+            sequential
+                .for_each(|id, section| {
+                    let other = parallel.get(id).unwrap();
+                    assert_eq!(section.writer.slice(), other.writer.slice(), "{:?}", id);
+                    let mut relocations = section.relocations.clone();
+                    relocations.sort_by_key(|r| r.offset);
+                    let mut other_relocations = other.relocations.clone();
+                    other_relocations.sort_by_key(|r| r.offset);
+                    assert_eq!(relocations, other_relocations, "{:?}", id);
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+            assert!(!sequential.debug_info.writer.slice().is_empty());
+        }
     }
 }
@@ -209,6 +209,11 @@ pub trait Writer {
                 self.write_u32(write_val)
             }
             8 => self.write_u64(val),
+            3 | 5 | 6 | 7 => {
+                let mut bytes = [0; 8];
+                let bytes = write_uint(self.endian(), &mut bytes, val, size)?;
+                self.write(bytes)
+            }
             otherwise => Err(Error::UnsupportedWordSize(otherwise)),
         }
     }
@@ -273,6 +278,11 @@ pub trait Writer {
                 self.write_u32_at(offset, write_val)
             }
             8 => self.write_u64_at(offset, val),
+            3 | 5 | 6 | 7 => {
+                let mut bytes = [0; 8];
+                let bytes = write_uint(self.endian(), &mut bytes, val, size)?;
+                self.write_at(offset, bytes)
+            }
             otherwise => Err(Error::UnsupportedWordSize(otherwise)),
         }
     }
@@ -324,6 +334,18 @@ pub trait Writer {
 #[derive(Debug, Clone, Copy)]
 pub struct InitialLengthOffset(usize);
 
+/// Encode `val` as an unsigned integer of `size` bytes into `bytes`.
+///
+/// Returns an error if the value is too large for the size.
+fn write_uint<E: Endianity>(endian: E, bytes: &mut [u8; 8], val: u64, size: u8) -> Result<&[u8]> {
+    if val >> (8 * u32::from(size)) != 0 {
+        return Err(Error::ValueTooLarge);
+    }
+    let bytes = &mut bytes[..usize::from(size)];
+    endian.write_uint(bytes, val);
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,7 +440,7 @@ mod tests {
         assert_eq!(w.write_udata(0x100, 1), Err(Error::ValueTooLarge));
         assert_eq!(w.write_udata(0x1_0000, 2), Err(Error::ValueTooLarge));
         assert_eq!(w.write_udata(0x1_0000_0000, 4), Err(Error::ValueTooLarge));
-        assert_eq!(w.write_udata(0x00, 3), Err(Error::UnsupportedWordSize(3)));
+        assert_eq!(w.write_udata(0x00, 9), Err(Error::UnsupportedWordSize(9)));
         w.write_udata_at(14, 0x11, 1).unwrap();
         w.write_udata_at(12, 0x2233, 2).unwrap();
         w.write_udata_at(8, 0x4455_6677, 4).unwrap();
@@ -437,10 +459,30 @@ mod tests {
             Err(Error::ValueTooLarge)
         );
         assert_eq!(
-            w.write_udata_at(0, 0x00, 3),
-            Err(Error::UnsupportedWordSize(3))
+            w.write_udata_at(0, 0x00, 9),
+            Err(Error::UnsupportedWordSize(9))
+        );
+
+        let mut w = write::EndianVec::new(LittleEndian);
+        w.write_udata(0x0011_2233, 3).unwrap();
+        w.write_udata(0x4455_6677_8899, 6).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(w.slice(), &[
+            0x33, 0x22, 0x11,
+            0x99, 0x88, 0x77, 0x66, 0x55, 0x44,
+        ]);
+        assert_eq!(w.write_udata(0x100_0000, 3), Err(Error::ValueTooLarge));
+        w.write_udata_at(0, 0x00aa_bbcc, 3).unwrap();
+        assert_eq!(&w.slice()[..3], &[0xcc, 0xbb, 0xaa]);
+        assert_eq!(
+            w.write_udata_at(3, 0x1_0000_0000_0000, 6),
+            Err(Error::ValueTooLarge)
         );
 
+        let mut w = write::EndianVec::new(BigEndian);
+        w.write_udata(0x0011_2233, 3).unwrap();
+        assert_eq!(w.slice(), &[0x11, 0x22, 0x33]);
+
         let mut w = write::EndianVec::new(LittleEndian);
         w.write_uleb128(0).unwrap();
         assert_eq!(w.slice(), &[0]);
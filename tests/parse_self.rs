@@ -3,8 +3,8 @@
 use gimli::{
     AttributeValue, DebugAbbrev, DebugAddr, DebugAddrBase, DebugAranges, DebugInfo, DebugLine,
     DebugLoc, DebugLocLists, DebugPubNames, DebugPubTypes, DebugRanges, DebugRngLists, DebugStr,
-    Encoding, EndianSlice, Expression, LittleEndian, LocationLists, Operation, RangeLists,
-    RangeListsOffset, Reader,
+    Encoding, EndianSlice, Expression, LittleEndian, LocationLists, Operation, PubNameMap,
+    RangeLists, RangeListsOffset, Reader,
 };
 use std::collections::hash_map::HashMap;
 use std::env;
@@ -340,6 +340,36 @@ fn test_parse_self_debug_pubnames() {
     }
 }
 
+#[test]
+fn test_parse_self_debug_pubnames_sets() {
+    let debug_info = read_section("debug_info");
+    let debug_info = DebugInfo::new(&debug_info, LittleEndian);
+
+    let debug_pubnames = read_section("debug_pubnames");
+    let debug_pubnames = DebugPubNames::new(&debug_pubnames, LittleEndian);
+
+    let mut count = 0;
+    let mut sets = debug_pubnames.sets();
+    while let Some(set) = sets.next().expect("Should parse pubnames set OK") {
+        let unit_offset = set.unit_header_offset();
+        let unit = debug_info
+            .header_from_offset(unit_offset)
+            .expect("Should parse unit header OK");
+        assert_eq!(set.unit_length(), unit.length_including_self());
+        let mut entries = set.entries();
+        while let Some(entry) = entries.next().expect("Should parse pubname OK") {
+            assert_eq!(entry.unit_header_offset(), unit_offset);
+            count += 1;
+        }
+    }
+
+    let mut pubnames = debug_pubnames.items();
+    while pubnames.next().expect("Should parse pubname OK").is_some() {
+        count -= 1;
+    }
+    assert_eq!(count, 0);
+}
+
 #[test]
 fn test_parse_self_debug_pubtypes() {
     let debug_info = read_section("debug_info");
@@ -431,3 +461,32 @@ fn test_parse_self_eh_frame_hdr() {
         .parse(&bases, address_size)
         .expect("we can parse the `.eh_frame_hdr` section OK");
 }
+
+#[test]
+fn test_parse_self_pub_name_map() {
+    let debug_pubnames = read_section("debug_pubnames");
+    let debug_pubnames = DebugPubNames::new(&debug_pubnames, LittleEndian);
+
+    let debug_pubtypes = read_section("debug_pubtypes");
+    let debug_pubtypes = DebugPubTypes::new(&debug_pubtypes, LittleEndian);
+
+    let mut map = PubNameMap::new();
+    map.add_pubnames(&debug_pubnames)
+        .expect("Should parse pubnames OK");
+    map.add_pubtypes(&debug_pubtypes)
+        .expect("Should parse pubtypes OK");
+    assert!(!map.is_empty());
+    assert_eq!(map.iter().count(), map.len());
+
+    let mut pubnames = debug_pubnames.items();
+    while let Some(entry) = pubnames.next().expect("Should parse pubname OK") {
+        let found = map.find(entry.name().slice());
+        assert!(found.contains(&(entry.unit_header_offset(), entry.die_offset())));
+    }
+    let mut pubtypes = debug_pubtypes.items();
+    while let Some(entry) = pubtypes.next().expect("Should parse pubtype OK") {
+        let found = map.find(entry.name().slice());
+        assert!(found.contains(&(entry.unit_header_offset(), entry.die_offset())));
+    }
+    assert!(map.find(b"not a name").is_empty());
+}